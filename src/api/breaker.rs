@@ -0,0 +1,173 @@
+// Circuit Breaker
+//
+// Tracks consecutive failures per base URL. Once an endpoint has failed
+// `threshold` times in a row within `window`, calls to it are refused for
+// `cooldown` instead of each one waiting out its own timeout and retries.
+// After the cooldown a single trial call is let through while the rest
+// are still refused; success closes the breaker, failure reopens it.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use super::error::{ApiError, ApiResult};
+
+/// Failure counts for one base URL
+#[derive(Debug, Clone, Copy)]
+struct BreakerState {
+    failures: u32,
+    first_failure: Instant,
+    open_until: Option<Instant>,
+    /// A half-open trial call is in flight
+    trial: bool,
+}
+
+/// Consecutive-failure circuit breaker keyed by base URL
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    window: Duration,
+    cooldown: Duration,
+    states: Mutex<HashMap<String, BreakerState>>,
+}
+
+impl Default for CircuitBreaker {
+    /// 5 consecutive failures within a minute open the breaker for 30s
+    fn default() -> Self {
+        Self::new(5, Duration::from_secs(60), Duration::from_secs(30))
+    }
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, window: Duration, cooldown: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            window,
+            cooldown,
+            states: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// `Err(ApiError::Unavailable)` while the breaker for `base_url` is open
+    pub fn check(&self, base_url: &str) -> ApiResult<()> {
+        self.check_at(base_url, Instant::now())
+    }
+
+    pub fn record_success(&self, base_url: &str) {
+        self.lock().remove(base_url);
+    }
+
+    pub fn record_failure(&self, base_url: &str) {
+        self.record_failure_at(base_url, Instant::now())
+    }
+
+    fn check_at(&self, base_url: &str, now: Instant) -> ApiResult<()> {
+        let mut states = self.lock();
+        let Some(state) = states.get_mut(base_url) else { return Ok(()) };
+        match state.open_until {
+            Some(until) if now < until => Err(ApiError::Unavailable {
+                base_url: base_url.to_string(),
+                retry_in: until - now,
+            }),
+            Some(_) => {
+                // Half-open: let this call through as the trial and keep
+                // refusing others until it reports back or another cooldown
+                // passes
+                state.trial = true;
+                state.open_until = Some(now + self.cooldown);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn record_failure_at(&self, base_url: &str, now: Instant) {
+        let mut states = self.lock();
+        let state = states.entry(base_url.to_string()).or_insert(BreakerState {
+            failures: 0,
+            first_failure: now,
+            open_until: None,
+            trial: false,
+        });
+
+        // A half-open trial call failing reopens straight away; otherwise
+        // only failures within the window count as consecutive
+        let half_open = std::mem::take(&mut state.trial);
+        if !half_open && now.duration_since(state.first_failure) > self.window {
+            state.failures = 0;
+            state.first_failure = now;
+        }
+        state.failures += 1;
+
+        if half_open || state.failures >= self.threshold {
+            if state.open_until.is_none() {
+                log::warn!(
+                    "{} failed {} times in a row, pausing calls for {:?}",
+                    base_url,
+                    state.failures,
+                    self.cooldown
+                );
+            }
+            state.open_until = Some(now + self.cooldown);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, BreakerState>> {
+        // The map holds plain counters, so a poisoned lock is still usable
+        self.states.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+static SHARED_BREAKER: OnceLock<Arc<CircuitBreaker>> = OnceLock::new();
+
+/// The process-wide breaker used by every `ApiClient` unless the builder is
+/// given another, so clients rebuilt per call still share failure counts
+pub fn shared_circuit_breaker() -> Arc<CircuitBreaker> {
+    SHARED_BREAKER.get_or_init(|| Arc::new(CircuitBreaker::default())).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://api.example.com";
+
+    #[test]
+    fn test_breaker_trips_and_resets() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60), Duration::from_secs(30));
+        let start = Instant::now();
+
+        breaker.record_failure_at(URL, start);
+        breaker.record_failure_at(URL, start + Duration::from_secs(1));
+        assert!(breaker.check_at(URL, start + Duration::from_secs(2)).is_ok());
+
+        breaker.record_failure_at(URL, start + Duration::from_secs(2));
+        let err = breaker.check_at(URL, start + Duration::from_secs(12)).unwrap_err();
+        assert!(matches!(err, ApiError::Unavailable { retry_in, .. } if retry_in == Duration::from_secs(20)));
+        assert!(breaker.check_at("https://other.example.com", start).is_ok());
+
+        // Half-open after the cooldown: a single trial goes through, and
+        // its failure reopens the breaker
+        let after = start + Duration::from_secs(33);
+        assert!(breaker.check_at(URL, after).is_ok());
+        assert!(breaker.check_at(URL, after).is_err());
+        breaker.record_failure_at(URL, after);
+        assert!(breaker.check_at(URL, after + Duration::from_secs(1)).is_err());
+
+        breaker.record_success(URL);
+        assert!(breaker.check_at(URL, after + Duration::from_secs(1)).is_ok());
+    }
+
+    #[test]
+    fn test_failures_outside_window_are_not_consecutive() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(10), Duration::from_secs(30));
+        let start = Instant::now();
+
+        breaker.record_failure_at(URL, start);
+        breaker.record_failure_at(URL, start + Duration::from_secs(20));
+        assert!(breaker.check_at(URL, start + Duration::from_secs(21)).is_ok());
+
+        breaker.record_failure_at(URL, start + Duration::from_secs(25));
+        assert!(breaker.check_at(URL, start + Duration::from_secs(26)).is_err());
+    }
+}
@@ -4,13 +4,15 @@
 /// cloud and bare metal provider APIs with proper error handling, retries,
 /// rate limiting, and logging.
 
-use reqwest::{Client, Method, Response, StatusCode};
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode};
 use serde::de::DeserializeOwned;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::sleep;
 
+use super::breaker::CircuitBreaker;
 use super::error::{ApiError, ApiResult};
 
 /// Authentication method for API clients
@@ -24,11 +26,60 @@ pub enum AuthMethod {
     None,
 }
 
+/// Request body encoding
+#[derive(Debug, Clone, Copy)]
+pub enum RequestBody<'a> {
+    /// JSON-encoded body (`application/json`), the default for provider APIs
+    Json(&'a Value),
+    /// Form-encoded body (`application/x-www-form-urlencoded`), as used by
+    /// query-style APIs such as AWS EC2
+    Form(&'a [(&'a str, &'a str)]),
+}
+
+/// Connection pool settings for the underlying `reqwest::Client`
+///
+/// Provider APIs are called in bursts (pricing refreshes, bulk lifecycle
+/// commands), so a few warm connections per host save a TLS handshake on
+/// most requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolConfig {
+    /// Idle connections kept open per host
+    pub max_idle_per_host: usize,
+    /// How long an idle connection is kept before it is closed
+    pub idle_timeout: Duration,
+    /// TCP keep-alive interval for open connections (`None` disables it)
+    pub tcp_keepalive: Option<Duration>,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_idle_per_host: 8,
+            idle_timeout: Duration::from_secs(90),
+            tcp_keepalive: Some(Duration::from_secs(60)),
+        }
+    }
+}
+
+impl PoolConfig {
+    /// Build a `reqwest::Client` with these pool settings. Request timeouts
+    /// are set per request by `ApiClient`, so the client itself has none.
+    pub fn build_client(&self) -> ApiResult<Client> {
+        Client::builder()
+            .pool_max_idle_per_host(self.max_idle_per_host)
+            .pool_idle_timeout(self.idle_timeout)
+            .tcp_keepalive(self.tcp_keepalive)
+            .build()
+            .map_err(|e| ApiError::RequestBuild(e.to_string()))
+    }
+}
+
 /// Base API client with common functionality for provider APIs
 ///
 /// Features:
 /// - Automatic retries with exponential backoff
 /// - Rate limit handling
+/// - Circuit breaking per base URL
 /// - Request/response logging
 /// - Standardized error handling
 pub struct ApiClient {
@@ -39,6 +90,7 @@ pub struct ApiClient {
     max_retries: u32,
     rate_limit_delay: Duration,
     default_headers: HashMap<String, String>,
+    breaker: Arc<CircuitBreaker>,
 }
 
 impl ApiClient {
@@ -47,6 +99,22 @@ impl ApiClient {
         ApiClientBuilder::new(base_url)
     }
 
+    /// The base URL requests are made against
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// The timeout applied to each request
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// This client with a different request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
     /// Build full URL from endpoint
     fn build_url(&self, endpoint: &str) -> String {
         let base = self.base_url.trim_end_matches('/');
@@ -123,25 +191,23 @@ impl ApiClient {
         F: FnMut() -> Fut,
         Fut: std::future::Future<Output = ApiResult<T>>,
     {
-        const MAX_RATE_LIMIT_RETRIES: u32 = 3;
-
-        for attempt in 0..MAX_RATE_LIMIT_RETRIES {
+        for attempt in 0..=self.max_retries {
             match func().await {
                 Ok(result) => return Ok(result),
                 Err(e) if e.is_rate_limit() => {
-                    if attempt < MAX_RATE_LIMIT_RETRIES - 1 {
-                        let wait_time = self.rate_limit_delay * 2_u32.pow(attempt);
+                    if attempt < self.max_retries {
+                        let wait_time = self.rate_limit_delay.saturating_mul(2_u32.saturating_pow(attempt));
                         log::warn!(
                             "Rate limited, waiting {:?} before retry {}/{}",
                             wait_time,
                             attempt + 1,
-                            MAX_RATE_LIMIT_RETRIES
+                            self.max_retries
                         );
                         sleep(wait_time).await;
                     } else {
                         log::error!(
                             "Rate limit exceeded after {} retries",
-                            MAX_RATE_LIMIT_RETRIES
+                            self.max_retries
                         );
                         return Err(e);
                     }
@@ -170,45 +236,89 @@ impl ApiClient {
         params: Option<&HashMap<String, String>>,
         headers: Option<HashMap<String, String>>,
     ) -> ApiResult<T> {
+        self.request_with_body(method, endpoint, data.map(RequestBody::Json), params, headers)
+            .await
+    }
+
+    /// Make HTTP request to API with an explicitly encoded body
+    pub async fn request_with_body<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Option<RequestBody<'_>>,
+        params: Option<&HashMap<String, String>>,
+        headers: Option<HashMap<String, String>>,
+    ) -> ApiResult<T> {
+        log::debug!("{} {}", method, self.build_url(endpoint));
+        match body {
+            Some(RequestBody::Json(d)) => log::debug!("Request data: {}", d),
+            Some(RequestBody::Form(f)) => log::debug!("Request form: {:?}", f),
+            None => {}
+        }
+
+        let make_request = || async {
+            let request_builder =
+                self.build_request(method.clone(), endpoint, body, params, headers.clone());
+            let response = request_builder.send().await?;
+            let result: T = self.handle_response(response).await?;
+            log::debug!("Response received successfully");
+            Ok(result)
+        };
+
+        self.breaker.check(&self.base_url)?;
+        let result = self.execute_with_rate_limiting(make_request).await;
+        match &result {
+            Err(e) if e.is_outage() => self.breaker.record_failure(&self.base_url),
+            _ => self.breaker.record_success(&self.base_url),
+        }
+        result
+    }
+
+    /// Assemble a request with auth, default and extra headers, query
+    /// parameters and body
+    fn build_request(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Option<RequestBody<'_>>,
+        params: Option<&HashMap<String, String>>,
+        headers: Option<HashMap<String, String>>,
+    ) -> RequestBuilder {
         let url = self.build_url(endpoint);
 
         // Merge headers
         let mut request_headers = self.default_headers.clone();
         self.add_auth_headers(&mut request_headers);
+        if let Some(RequestBody::Form(_)) = body {
+            request_headers.insert(
+                "Content-Type".to_string(),
+                "application/x-www-form-urlencoded".to_string(),
+            );
+        }
         if let Some(h) = headers {
             request_headers.extend(h);
         }
 
-        log::debug!("{} {}", method, url);
-        if let Some(d) = data {
-            log::debug!("Request data: {}", d);
-        }
+        let mut request_builder = self.client.request(method, &url).timeout(self.timeout);
 
-        let make_request = || async {
-            let mut request_builder = self.client.request(method.clone(), &url).timeout(self.timeout);
-
-            // Add headers
-            for (key, value) in &request_headers {
-                request_builder = request_builder.header(key, value);
-            }
-
-            // Add query parameters
-            if let Some(p) = params {
-                request_builder = request_builder.query(p);
-            }
+        // Add headers
+        for (key, value) in &request_headers {
+            request_builder = request_builder.header(key, value);
+        }
 
-            // Add body data
-            if let Some(d) = data {
-                request_builder = request_builder.json(d);
-            }
+        // Add query parameters
+        if let Some(p) = params {
+            request_builder = request_builder.query(p);
+        }
 
-            let response = request_builder.send().await?;
-            let result: T = self.handle_response(response).await?;
-            log::debug!("Response received successfully");
-            Ok(result)
-        };
+        // Add body data
+        match body {
+            Some(RequestBody::Json(d)) => request_builder = request_builder.json(d),
+            Some(RequestBody::Form(f)) => request_builder = request_builder.form(f),
+            None => {}
+        }
 
-        self.execute_with_rate_limiting(make_request).await
+        request_builder
     }
 
     /// Make GET request
@@ -229,6 +339,16 @@ impl ApiClient {
         self.request(Method::POST, endpoint, data, None, None).await
     }
 
+    /// Make POST request with a form-encoded body
+    pub async fn post_form<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        form: &[(&str, &str)],
+    ) -> ApiResult<T> {
+        self.request_with_body(Method::POST, endpoint, Some(RequestBody::Form(form)), None, None)
+            .await
+    }
+
     /// Make PUT request
     pub async fn put<T: DeserializeOwned>(
         &self,
@@ -261,6 +381,9 @@ pub struct ApiClientBuilder {
     max_retries: u32,
     rate_limit_delay: Duration,
     headers: HashMap<String, String>,
+    pool: PoolConfig,
+    http_client: Option<Client>,
+    breaker: Option<Arc<CircuitBreaker>>,
 }
 
 impl ApiClientBuilder {
@@ -277,6 +400,9 @@ impl ApiClientBuilder {
             max_retries: 3,
             rate_limit_delay: Duration::from_secs(1),
             headers,
+            pool: PoolConfig::default(),
+            http_client: None,
+            breaker: None,
         }
     }
 
@@ -307,7 +433,7 @@ impl ApiClientBuilder {
         self
     }
 
-    /// Set maximum number of retries
+    /// Set how many times a rate-limited request is retried
     pub fn max_retries(mut self, max_retries: u32) -> Self {
         self.max_retries = max_retries;
         self
@@ -325,12 +451,51 @@ impl ApiClientBuilder {
         self
     }
 
+    /// Set all connection pool settings at once
+    pub fn pool(mut self, pool: PoolConfig) -> Self {
+        self.pool = pool;
+        self
+    }
+
+    /// Set the number of idle connections kept per host
+    pub fn pool_max_idle_per_host(mut self, max_idle: usize) -> Self {
+        self.pool.max_idle_per_host = max_idle;
+        self
+    }
+
+    /// Set how long idle connections are kept
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool.idle_timeout = timeout;
+        self
+    }
+
+    /// Set the TCP keep-alive interval (`None` disables keep-alive probes)
+    pub fn tcp_keepalive(mut self, interval: Option<Duration>) -> Self {
+        self.pool.tcp_keepalive = interval;
+        self
+    }
+
+    /// Reuse an existing `reqwest::Client` and its connection pool, e.g.
+    /// [`shared_http_client`](super::shared_http_client). Pool settings on
+    /// this builder are then ignored; the request timeout still applies.
+    pub fn http_client(mut self, client: Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Track failures in `breaker` instead of the process-wide
+    /// [`shared_circuit_breaker`](super::shared_circuit_breaker)
+    pub fn circuit_breaker(mut self, breaker: Arc<CircuitBreaker>) -> Self {
+        self.breaker = Some(breaker);
+        self
+    }
+
     /// Build the API client
     pub fn build(self) -> ApiResult<ApiClient> {
-        let client = Client::builder()
-            .timeout(self.timeout)
-            .build()
-            .map_err(|e| ApiError::RequestBuild(e.to_string()))?;
+        let client = match self.http_client {
+            Some(client) => client,
+            None => self.pool.build_client()?,
+        };
 
         Ok(ApiClient {
             client,
@@ -340,6 +505,7 @@ impl ApiClientBuilder {
             max_retries: self.max_retries,
             rate_limit_delay: self.rate_limit_delay,
             default_headers: self.headers,
+            breaker: self.breaker.unwrap_or_else(super::shared_circuit_breaker),
         })
     }
 }
@@ -376,4 +542,108 @@ mod tests {
         assert_eq!(client.base_url, "https://api.example.com");
         assert_eq!(client.max_retries, 5);
     }
+
+    #[test]
+    fn test_form_request_encoding() {
+        let client = ApiClient::builder("https://ec2.example.com")
+            .bearer_auth("test-token")
+            .build()
+            .unwrap();
+
+        let form = [("Action", "DescribeInstances"), ("Filter.1.Name", "tag:env & team")];
+        let request = client
+            .build_request(Method::POST, "/", Some(RequestBody::Form(&form)), None, None)
+            .build()
+            .unwrap();
+
+        let content_types: Vec<_> = request.headers().get_all("content-type").iter().collect();
+        assert_eq!(content_types, ["application/x-www-form-urlencoded"]);
+        assert_eq!(request.headers()["authorization"], "Bearer test-token");
+        assert_eq!(
+            request.body().and_then(|b| b.as_bytes()).unwrap(),
+            b"Action=DescribeInstances&Filter.1.Name=tag%3Aenv+%26+team"
+        );
+    }
+
+    #[test]
+    fn test_json_request_encoding_is_default() {
+        let client = ApiClient::builder("https://api.example.com").build().unwrap();
+        let data = serde_json::json!({"name": "node"});
+        let request = client
+            .build_request(Method::POST, "/servers", Some(RequestBody::Json(&data)), None, None)
+            .build()
+            .unwrap();
+
+        assert_eq!(request.headers()["content-type"], "application/json");
+        assert_eq!(
+            request.body().and_then(|b| b.as_bytes()).unwrap(),
+            br#"{"name":"node"}"#
+        );
+    }
+
+    #[test]
+    fn test_pool_settings() {
+        let builder = ApiClient::builder("https://api.example.com")
+            .pool_max_idle_per_host(2)
+            .pool_idle_timeout(Duration::from_secs(5))
+            .tcp_keepalive(None);
+        assert_eq!(
+            builder.pool,
+            PoolConfig { max_idle_per_host: 2, idle_timeout: Duration::from_secs(5), tcp_keepalive: None }
+        );
+        assert!(builder.build().is_ok());
+
+        let shared = PoolConfig::default().build_client().unwrap();
+        let client = ApiClient::builder("https://api.example.com")
+            .http_client(shared)
+            .timeout(Duration::from_secs(5))
+            .build()
+            .unwrap();
+        let request = client.build_request(Method::GET, "/", None, None, None).build().unwrap();
+        assert_eq!(request.timeout(), Some(&Duration::from_secs(5)));
+    }
+
+    #[tokio::test]
+    async fn test_breaker_short_circuits_dead_endpoint() {
+        // A port nothing listens on, so every request fails to connect
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let breaker = Arc::new(CircuitBreaker::new(2, Duration::from_secs(60), Duration::from_secs(60)));
+        let client = ApiClient::builder(&base)
+            .circuit_breaker(breaker.clone())
+            .build()
+            .unwrap();
+
+        for _ in 0..2 {
+            let err = client.get::<Value>("/servers", None).await.unwrap_err();
+            assert!(err.is_outage(), "{}", err);
+        }
+        let err = client.get::<Value>("/servers", None).await.unwrap_err();
+        assert!(matches!(err, ApiError::Unavailable { .. }), "{}", err);
+        assert!(err.to_string().contains("temporarily unavailable"));
+
+        breaker.record_success(&base);
+        let err = client.get::<Value>("/servers", None).await.unwrap_err();
+        assert!(err.is_outage(), "{}", err);
+    }
+    #[tokio::test]
+    async fn test_rate_limit_retries_follow_max_retries() {
+        let client = ApiClient::builder("https://api.example.com")
+            .max_retries(2)
+            .rate_limit_delay(Duration::ZERO)
+            .build()
+            .unwrap();
+
+        let calls = std::cell::Cell::new(0);
+        let result: ApiResult<()> = client
+            .execute_with_rate_limiting(|| {
+                calls.set(calls.get() + 1);
+                async { Err(ApiError::rate_limit("slow down", Some(429))) }
+            })
+            .await;
+        assert!(result.unwrap_err().is_rate_limit());
+        assert_eq!(calls.get(), 3);
+    }
 }
@@ -5,6 +5,10 @@
 
 use super::client::ApiClient;
 use super::error::ApiResult;
+use super::Provider;
+
+/// Default API endpoint, overridable with `CAPSULE_DIGITALOCEAN_BASE_URL`
+pub const DEFAULT_BASE_URL: &str = "https://api.digitalocean.com/v2";
 
 /// DigitalOcean API client
 pub struct DigitalOceanClient {
@@ -26,13 +30,29 @@ impl DigitalOceanClient {
     /// let client = DigitalOceanClient::new("your-api-token").unwrap();
     /// ```
     pub fn new(api_key: impl Into<String>) -> ApiResult<Self> {
-        let client = ApiClient::builder("https://api.digitalocean.com/v2")
+        Self::with_base_url(api_key, Provider::DigitalOcean.resolve_base_url(DEFAULT_BASE_URL))
+    }
+
+    /// Create a client against a non-default endpoint (a regional API or a
+    /// mock server in tests)
+    pub fn with_base_url(
+        api_key: impl Into<String>,
+        base_url: impl Into<String>,
+    ) -> ApiResult<Self> {
+        let client = ApiClient::builder(base_url)
             .bearer_auth(api_key)
+            .http_client(super::shared_http_client()?)
             .build()?;
 
         Ok(Self { client })
     }
 
+    /// This client with a different request timeout (default 30s)
+    pub fn with_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.client = self.client.with_timeout(timeout);
+        self
+    }
+
     /// Get reference to underlying API client
     pub fn client(&self) -> &ApiClient {
         &self.client
@@ -48,4 +68,31 @@ mod tests {
         let client = DigitalOceanClient::new("test-token");
         assert!(client.is_ok());
     }
+
+    #[test]
+    fn test_base_url_override() {
+        let client = DigitalOceanClient::with_base_url("t", "https://do.example.test/v2").unwrap();
+        assert_eq!(client.client().base_url(), "https://do.example.test/v2");
+
+        let env_var = Provider::DigitalOcean.base_url_env_var();
+        assert_eq!(env_var, "CAPSULE_DIGITALOCEAN_BASE_URL");
+        assert_eq!(DigitalOceanClient::new("t").unwrap().client().base_url(), DEFAULT_BASE_URL);
+    }
+
+    #[tokio::test]
+    async fn test_client_against_mock_server() {
+        let (base, request) = crate::test_support::serve_once(
+            "200 OK",
+            r#"{"sizes":[{"slug":"s-1vcpu-1gb","price_hourly":0.00893,"price_monthly":6.0}]}"#,
+        )
+        .await;
+
+        let client = DigitalOceanClient::with_base_url("test-token", format!("{}/v2", base)).unwrap();
+        let body: serde_json::Value = client.client().get("/sizes", None).await.unwrap();
+        assert_eq!(body["sizes"][0]["slug"], "s-1vcpu-1gb");
+
+        let request = request.await.unwrap().to_lowercase();
+        assert!(request.starts_with("get /v2/sizes http/1.1"));
+        assert!(request.contains("authorization: bearer test-token"));
+    }
 }
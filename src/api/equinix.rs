@@ -5,6 +5,10 @@
 
 use super::client::ApiClient;
 use super::error::ApiResult;
+use super::Provider;
+
+/// Default API endpoint, overridable with `CAPSULE_EQUINIX_BASE_URL`
+pub const DEFAULT_BASE_URL: &str = "https://api.equinix.com/metal/v1";
 
 /// Equinix Metal API client
 pub struct EquinixMetalClient {
@@ -26,13 +30,29 @@ impl EquinixMetalClient {
     /// let client = EquinixMetalClient::new("your-api-token").unwrap();
     /// ```
     pub fn new(api_key: impl Into<String>) -> ApiResult<Self> {
-        let client = ApiClient::builder("https://api.equinix.com/metal/v1")
+        Self::with_base_url(api_key, Provider::Equinix.resolve_base_url(DEFAULT_BASE_URL))
+    }
+
+    /// Create a client against a non-default endpoint (a regional API or a
+    /// mock server in tests)
+    pub fn with_base_url(
+        api_key: impl Into<String>,
+        base_url: impl Into<String>,
+    ) -> ApiResult<Self> {
+        let client = ApiClient::builder(base_url)
             .api_key_auth("X-Auth-Token", api_key)
+            .http_client(super::shared_http_client()?)
             .build()?;
 
         Ok(Self { client })
     }
 
+    /// This client with a different request timeout (default 30s)
+    pub fn with_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.client = self.client.with_timeout(timeout);
+        self
+    }
+
     /// Get reference to underlying API client
     pub fn client(&self) -> &ApiClient {
         &self.client
@@ -59,6 +59,13 @@ pub enum ApiError {
     /// Network error
     #[error("Network error: {0}")]
     Network(String),
+
+    /// Calls refused by the circuit breaker after repeated failures
+    #[error("Provider temporarily unavailable: {base_url} kept failing, retrying after {}s", .retry_in.as_secs().max(1))]
+    Unavailable {
+        base_url: String,
+        retry_in: std::time::Duration,
+    },
 }
 
 /// Result type alias for API operations
@@ -106,6 +113,16 @@ impl ApiError {
         matches!(self, ApiError::RateLimit { .. })
     }
 
+    /// Whether this failure suggests the endpoint itself is down (no
+    /// connection, timeouts, 5xx), as counted by the circuit breaker
+    pub fn is_outage(&self) -> bool {
+        match self {
+            ApiError::Connection(_) | ApiError::Timeout(_) | ApiError::Network(_) => true,
+            ApiError::General { status_code, .. } => *status_code >= 500,
+            _ => false,
+        }
+    }
+
     /// Get status code if available
     pub fn status_code(&self) -> Option<u16> {
         match self {
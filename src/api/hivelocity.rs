@@ -5,6 +5,10 @@
 
 use super::client::ApiClient;
 use super::error::ApiResult;
+use super::Provider;
+
+/// Default API endpoint, overridable with `CAPSULE_HIVELOCITY_BASE_URL`
+pub const DEFAULT_BASE_URL: &str = "https://core.hivelocity.net/api/v2";
 
 /// Hivelocity API client
 pub struct HivelocityClient {
@@ -26,13 +30,29 @@ impl HivelocityClient {
     /// let client = HivelocityClient::new("your-api-key").unwrap();
     /// ```
     pub fn new(api_key: impl Into<String>) -> ApiResult<Self> {
-        let client = ApiClient::builder("https://core.hivelocity.net/api/v2")
+        Self::with_base_url(api_key, Provider::Hivelocity.resolve_base_url(DEFAULT_BASE_URL))
+    }
+
+    /// Create a client against a non-default endpoint (a regional API or a
+    /// mock server in tests)
+    pub fn with_base_url(
+        api_key: impl Into<String>,
+        base_url: impl Into<String>,
+    ) -> ApiResult<Self> {
+        let client = ApiClient::builder(base_url)
             .api_key_auth("X-API-Key", api_key)
+            .http_client(super::shared_http_client()?)
             .build()?;
 
         Ok(Self { client })
     }
 
+    /// This client with a different request timeout (default 30s)
+    pub fn with_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.client = self.client.with_timeout(timeout);
+        self
+    }
+
     /// Get reference to underlying API client
     pub fn client(&self) -> &ApiClient {
         &self.client
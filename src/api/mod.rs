@@ -10,15 +10,25 @@
 ///
 /// - `error`: Error types for API operations
 /// - `client`: Base API client with retry logic and rate limiting
+/// - `breaker`: Circuit breaker shared by every client
 /// - Provider-specific clients: `digitalocean`, `hivelocity`, `vultr`, `linode`, `scaleway`, `equinix`, `aws`
 ///
 /// # Features
 ///
 /// - **Automatic Retries**: Exponential backoff for failed requests (max 3 retries)
 /// - **Rate Limit Handling**: Automatic detection and waiting for 429 responses
-/// - **Connection Pooling**: Efficient HTTP connection reuse
+/// - **Circuit Breaker**: after 5 consecutive connection failures, timeouts
+///   or 5xx responses within a minute, calls to that base URL fail fast with
+///   `ApiError::Unavailable` for 30s ([`shared_circuit_breaker`])
+/// - **Connection Pooling**: every provider client shares one `reqwest::Client`
+///   ([`shared_http_client`]), so connections survive clients being rebuilt.
+///   Tune a private pool with `ApiClientBuilder::pool_max_idle_per_host`,
+///   `pool_idle_timeout` and `tcp_keepalive` (defaults: 8 idle connections
+///   per host, 90s idle timeout, 60s keep-alive)
 /// - **Standardized Error Handling**: Consistent error types across all providers
 /// - **Request Logging**: Debug logging for all API operations
+/// - **Configurable Endpoints**: `with_base_url` on each client, or the
+///   `CAPSULE_<PROVIDER>_BASE_URL` environment variable
 ///
 /// # Example
 ///
@@ -42,6 +52,7 @@
 
 pub mod error;
 pub mod client;
+pub mod breaker;
 
 // Provider-specific clients
 pub mod aws;
@@ -54,7 +65,26 @@ pub mod vultr;
 
 // Re-export commonly used types
 pub use error::{ApiError, ApiResult};
-pub use client::{ApiClient, AuthMethod};
+pub use client::{ApiClient, AuthMethod, PoolConfig, RequestBody};
+pub use breaker::{shared_circuit_breaker, CircuitBreaker};
+
+use std::sync::OnceLock;
+
+static SHARED_HTTP_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+/// The process-wide HTTP client used by the provider clients, built once with
+/// the default [`PoolConfig`]. Cloning it shares its connection pool.
+///
+/// Pooled connections are driven by the runtime that opened them, so callers
+/// outside an async context should make their requests on
+/// `providers::pricing::api_runtime` rather than a runtime of their own.
+pub fn shared_http_client() -> ApiResult<reqwest::Client> {
+    if let Some(client) = SHARED_HTTP_CLIENT.get() {
+        return Ok(client.clone());
+    }
+    let client = PoolConfig::default().build_client()?;
+    Ok(SHARED_HTTP_CLIENT.get_or_init(|| client).clone())
+}
 
 // Re-export provider clients
 pub use aws::AwsClient;
@@ -92,6 +122,7 @@ impl Provider {
     }
 
     /// Parse provider from string
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "aws" => Some(Provider::Aws),
@@ -105,6 +136,19 @@ impl Provider {
         }
     }
 
+    /// Environment variable overriding this provider's API base URL
+    pub fn base_url_env_var(&self) -> String {
+        format!("CAPSULE_{}_BASE_URL", self.as_str().to_uppercase())
+    }
+
+    /// The base URL from `CAPSULE_<PROVIDER>_BASE_URL`, or `default`
+    pub fn resolve_base_url(&self, default: &str) -> String {
+        std::env::var(self.base_url_env_var())
+            .ok()
+            .filter(|url| !url.trim().is_empty())
+            .unwrap_or_else(|| default.to_string())
+    }
+
     /// Get all supported providers
     pub fn all() -> Vec<Provider> {
         vec![
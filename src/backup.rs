@@ -0,0 +1,348 @@
+// `capsule backup` / `capsule restore`: all of ~/.capsule in one tar.gz
+//
+// The archive holds the directory tree as it is on disk, plus `backup.json`
+// (when it was made and whether provider secrets were kept) and a
+// `checksums.json` manifest covering every file. Restore checks the
+// manifest before anything in ~/.capsule is touched.
+
+use anyhow::{Context, Result};
+use colored::*;
+use dialoguer::Confirm;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+use capsule::exit::CliError;
+use capsule::providers::ProviderConfig;
+
+use crate::server::checksum::{ChecksumManifest, MANIFEST_FILE};
+
+/// Describes the backup itself; covered by the checksum manifest
+const BACKUP_INFO_FILE: &str = "backup.json";
+
+const PROVIDERS_FILE: &str = "providers.yml";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub capsule_version: String,
+    pub created_at: String,
+    /// Whether `providers.yml` kept its API keys
+    pub include_secrets: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RestoreOptions {
+    /// Overwrite existing files without asking
+    pub force: bool,
+    /// Only show what would be restored
+    pub dry_run: bool,
+}
+
+/// A private (0700) temporary directory, removed on drop
+fn staging(purpose: &str) -> Result<TempDir> {
+    tempfile::Builder::new()
+        .prefix(&format!("capsule-{}-", purpose))
+        .tempdir()
+        .context("Failed to create temporary directory")
+}
+
+/// Pack `capsule_dir` into the tar.gz `output`. Without `include_secrets`,
+/// API keys and other secret settings are left out of `providers.yml`.
+/// Returns the number of files backed up.
+pub fn create_backup(capsule_dir: &Path, output: &Path, include_secrets: bool) -> Result<usize> {
+    if !capsule_dir.is_dir() {
+        return Err(CliError::not_found(format!("Nothing to back up: {} does not exist", capsule_dir.display())).into());
+    }
+    let output = std::path::absolute(output)?;
+
+    let staging = staging("backup")?;
+    copy_tree(capsule_dir, staging.path())?;
+
+    let providers = staging.path().join(PROVIDERS_FILE);
+    if !include_secrets && providers.exists() {
+        let configs = load_provider_configs(&providers)?;
+        let stripped: HashMap<String, ProviderConfig> =
+            configs.iter().map(|(name, config)| (name.clone(), config.without_secrets())).collect();
+        fs::write(&providers, serde_yaml::to_string(&stripped)?)?;
+    }
+
+    let info = BackupInfo {
+        capsule_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        include_secrets,
+    };
+    fs::write(staging.path().join(BACKUP_INFO_FILE), serde_json::to_string_pretty(&info)?)?;
+
+    let manifest = ChecksumManifest::generate(staging.path())?;
+    manifest.save(&staging.path().join(MANIFEST_FILE))?;
+
+    // The archive may hold API keys: make it owner-only before tar writes
+    // a byte, rather than fixing the mode afterwards
+    let archive = private_file(&output)
+        .with_context(|| format!("Failed to create backup archive {}", output.display()))?;
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg("-")
+        .arg("-C")
+        .arg(staging.path())
+        .arg(".")
+        .stdout(archive)
+        .status()
+        .context("Failed to execute tar")?;
+    if !status.success() {
+        anyhow::bail!("Failed to write backup archive {}", output.display());
+    }
+
+    Ok(manifest.files.len() - 1)
+}
+
+/// Unpack a backup made by `create_backup` into `capsule_dir` after
+/// checking every file against its manifest. Returns the restored files.
+pub fn restore_backup(archive: &Path, capsule_dir: &Path, options: RestoreOptions) -> Result<Vec<String>> {
+    if !archive.is_file() {
+        return Err(CliError::not_found(format!("Backup {} not found", archive.display())).into());
+    }
+
+    let staging = staging("restore")?;
+    let status = Command::new("tar")
+        .arg("-xzf")
+        .arg(archive)
+        .arg("-C")
+        .arg(staging.path())
+        .status()
+        .context("Failed to execute tar")?;
+    if !status.success() {
+        return Err(CliError::usage(format!("{} is not a readable tar.gz archive", archive.display())).into());
+    }
+
+    let manifest_path = staging.path().join(MANIFEST_FILE);
+    if !manifest_path.exists() || !staging.path().join(BACKUP_INFO_FILE).exists() {
+        return Err(CliError::usage(format!("{} is not a capsule backup", archive.display())).into());
+    }
+    let manifest = ChecksumManifest::load(&manifest_path)?;
+    verify_backup(&manifest, staging.path())?;
+
+    let info: BackupInfo = serde_json::from_str(&fs::read_to_string(staging.path().join(BACKUP_INFO_FILE))?)
+        .context("Failed to parse backup.json")?;
+
+    let files: Vec<String> = manifest
+        .files
+        .keys()
+        .filter(|f| f.as_str() != BACKUP_INFO_FILE)
+        .cloned()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let existing: Vec<&String> = files.iter().filter(|f| capsule_dir.join(f).exists()).collect();
+
+    println!("{} Backup from {} (capsule {})", "▸".cyan().bold(), info.created_at.cyan(), info.capsule_version);
+    println!("  {} files, {} already present in {}", files.len(), existing.len(), capsule_dir.display());
+    if !info.include_secrets {
+        println!("  {} Made without secrets; existing provider keys are kept", "ℹ".cyan());
+    }
+
+    if options.dry_run {
+        for file in &files {
+            let marker = if capsule_dir.join(file).exists() { "overwrite".yellow() } else { "new".green() };
+            println!("    {} {}", marker, file);
+        }
+        return Ok(files);
+    }
+
+    if !existing.is_empty() && !options.force {
+        let confirmed = std::io::stdin().is_terminal()
+            && Confirm::new()
+                .with_prompt(format!("Overwrite {} existing file(s) in {}?", existing.len(), capsule_dir.display()))
+                .default(false)
+                .interact()?;
+        if !confirmed {
+            return Err(CliError::usage(format!(
+                "Restore would overwrite {} existing file(s); pass --force to replace them",
+                existing.len()
+            ))
+            .into());
+        }
+    }
+
+    let current_providers = capsule_dir.join(PROVIDERS_FILE);
+    let staged_providers = staging.path().join(PROVIDERS_FILE);
+    if !info.include_secrets && current_providers.exists() && staged_providers.exists() {
+        let current = load_provider_configs(&current_providers)?;
+        let mut restored = load_provider_configs(&staged_providers)?;
+        for (name, config) in restored.iter_mut() {
+            if let Some(current) = current.get(name) {
+                config.keep_secrets_from(current);
+            }
+        }
+        fs::write(&staged_providers, serde_yaml::to_string(&restored)?)?;
+    }
+
+    for file in &files {
+        let target = capsule_dir.join(file);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(staging.path().join(file), &target)
+            .with_context(|| format!("Failed to restore {}", target.display()))?;
+    }
+
+    Ok(files)
+}
+
+/// Every manifest entry present and intact, and nothing extra in the archive
+fn verify_backup(manifest: &ChecksumManifest, dir: &Path) -> Result<()> {
+    if let Some(bad) = manifest.files.keys().find(|f| Path::new(f).is_absolute() || f.split('/').any(|c| c == "..")) {
+        anyhow::bail!("Backup manifest lists a path outside the backup: {}", bad);
+    }
+
+    let report = manifest.validate(dir, false)?;
+    if !report.is_valid() {
+        let files: Vec<&str> = report.errors.iter().map(|e| e.file.as_str()).collect();
+        anyhow::bail!("Backup is corrupt; checksum failed for: {}", files.join(", "));
+    }
+
+    let mut unlisted = Vec::new();
+    list_files(dir, dir, &mut unlisted)?;
+    unlisted.retain(|f| f != MANIFEST_FILE && !manifest.files.contains_key(f));
+    if !unlisted.is_empty() {
+        anyhow::bail!("Backup contains files missing from its manifest: {}", unlisted.join(", "));
+    }
+    Ok(())
+}
+
+/// Create or truncate `path`, readable and writable by the owner only
+fn private_file(path: &Path) -> Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        let file = options.open(path)?;
+        // An existing file keeps its old mode through open
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        Ok(file)
+    }
+    #[cfg(not(unix))]
+    {
+        Ok(options.open(path)?)
+    }
+}
+
+fn load_provider_configs(path: &Path) -> Result<HashMap<String, ProviderConfig>> {
+    let content = fs::read_to_string(path)?;
+    serde_yaml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+fn copy_tree(from: &Path, to: &Path) -> Result<()> {
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let path = entry.path();
+        let target = to.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            fs::create_dir_all(&target)?;
+            copy_tree(&path, &target)?;
+        } else if file_type.is_file() {
+            fs::copy(&path, &target).with_context(|| format!("Failed to read {}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+fn list_files(dir: &Path, base: &Path, files: &mut Vec<String>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            list_files(&path, base, files)?;
+        } else {
+            files.push(path.strip_prefix(base).unwrap().to_string_lossy().to_string());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    fn capsule_dir(root: &Path) -> PathBuf {
+        let dir = root.join(".capsule");
+        fs::create_dir_all(dir.join("monitoring_data")).unwrap();
+        fs::write(dir.join("inventory.json"), "{\"xnodes\":{}}").unwrap();
+        fs::write(dir.join("monitoring_data/health_history.json"), "{}").unwrap();
+        fs::write(dir.join(PROVIDERS_FILE), "vultr:\n  api_key: vultr-secret-123456\n  region: ewr\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_backup_restore_roundtrip_without_secrets() {
+        let root = tempdir().unwrap();
+        let source = capsule_dir(root.path());
+        let archive = root.path().join("capsule.tar.gz");
+
+        // Even over a world-readable file the archive ends up owner-only
+        fs::write(&archive, "old").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&archive, fs::Permissions::from_mode(0o644)).unwrap();
+        }
+        assert_eq!(create_backup(&source, &archive, false).unwrap(), 3);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&archive).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+
+        // A fresh machine gets the files but no keys
+        let fresh = root.path().join("fresh");
+        let restored = restore_backup(&archive, &fresh, RestoreOptions::default()).unwrap();
+        assert_eq!(restored, [
+            "inventory.json",
+            "monitoring_data/health_history.json",
+            PROVIDERS_FILE,
+        ]);
+        let providers = fs::read_to_string(fresh.join(PROVIDERS_FILE)).unwrap();
+        assert!(!providers.contains("vultr-secret"));
+        assert!(providers.contains("region: ewr"));
+
+        // Restoring over existing state needs --force and keeps its keys
+        fs::write(source.join("inventory.json"), "changed").unwrap();
+        let err = restore_backup(&archive, &source, RestoreOptions::default()).unwrap_err();
+        assert_eq!(capsule::exit::exit_code_for(&err), capsule::exit::USAGE);
+        restore_backup(&archive, &source, RestoreOptions { force: true, dry_run: false }).unwrap();
+        assert_eq!(fs::read_to_string(source.join("inventory.json")).unwrap(), "{\"xnodes\":{}}");
+        assert!(fs::read_to_string(source.join(PROVIDERS_FILE)).unwrap().contains("vultr-secret-123456"));
+    }
+
+    #[test]
+    fn test_restore_rejects_tampered_backup() {
+        let root = tempdir().unwrap();
+        let source = capsule_dir(root.path());
+        let archive = root.path().join("capsule.tar.gz");
+        create_backup(&source, &archive, true).unwrap();
+
+        let unpacked = root.path().join("unpacked");
+        fs::create_dir_all(&unpacked).unwrap();
+        Command::new("tar").arg("-xzf").arg(&archive).arg("-C").arg(&unpacked).status().unwrap();
+        assert!(fs::read_to_string(unpacked.join(PROVIDERS_FILE)).unwrap().contains("vultr-secret-123456"));
+        fs::write(unpacked.join("inventory.json"), "tampered").unwrap();
+        let tampered = root.path().join("tampered.tar.gz");
+        Command::new("tar").arg("-czf").arg(&tampered).arg("-C").arg(&unpacked).arg(".").status().unwrap();
+
+        let target = root.path().join("target");
+        let err = restore_backup(&tampered, &target, RestoreOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("checksum failed for: inventory.json"));
+        assert!(!target.exists());
+
+        let err = restore_backup(&root.path().join("missing.tar.gz"), &target, RestoreOptions::default()).unwrap_err();
+        assert_eq!(capsule::exit::exit_code_for(&err), capsule::exit::NOT_FOUND);
+    }
+}
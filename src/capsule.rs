@@ -0,0 +1,151 @@
+// Library entry point tying config, providers, inventory and monitoring
+// to one base directory
+//
+// The CLI builds each of these from `~/.capsule` on demand. Embedders can
+// do the same through `Capsule`, or point it at another directory:
+//
+//     let mut capsule = Capsule::with_base_dir("/srv/capsule");
+//     let running = capsule.inventory()?.list_by_status("running").len();
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::config::{self, Config, ConfigResult};
+use crate::inventory::XNodeInventory;
+use crate::monitoring::MonitoringSystem;
+use crate::providers::ProviderManager;
+
+/// Capsule state rooted at one base directory. Managers are constructed on
+/// first use and kept for the lifetime of the value.
+pub struct Capsule {
+    base_dir: PathBuf,
+    inventory: Option<XNodeInventory>,
+    providers: Option<ProviderManager>,
+    monitoring: Option<MonitoringSystem>,
+}
+
+impl Default for Capsule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Capsule {
+    /// Capsule state in `~/.capsule`, as used by the CLI
+    pub fn new() -> Self {
+        Self::with_base_dir(config::get_capsule_dir())
+    }
+
+    /// Capsule state in `base_dir` instead of `~/.capsule`
+    pub fn with_base_dir(base_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            base_dir: base_dir.into(),
+            inventory: None,
+            providers: None,
+            monitoring: None,
+        }
+    }
+
+    /// Use `manager` instead of building one from `providers.yml`, e.g. a
+    /// manager over `ProviderManager::with_providers` in tests
+    pub fn with_provider_manager(mut self, manager: ProviderManager) -> Self {
+        self.providers = Some(manager);
+        self
+    }
+
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
+
+    /// The xNode inventory (`inventory.json`)
+    pub fn inventory(&mut self) -> Result<&mut XNodeInventory> {
+        if self.inventory.is_none() {
+            let inventory = XNodeInventory::new(Some(self.base_dir.join("inventory.json")))?;
+            self.inventory = Some(inventory);
+        }
+        Ok(self.inventory.as_mut().expect("inventory was just loaded"))
+    }
+
+    /// The provider manager (`providers.yml`)
+    pub fn providers(&mut self) -> Result<&mut ProviderManager> {
+        if self.providers.is_none() {
+            let manager = ProviderManager::new(Some(self.base_dir.join("providers.yml")))?;
+            self.providers = Some(manager);
+        }
+        Ok(self.providers.as_mut().expect("providers were just loaded"))
+    }
+
+    /// Providers and inventory together, for calls such as
+    /// `openmesh::deploy_and_record` that need both
+    pub fn providers_and_inventory(&mut self) -> Result<(&mut ProviderManager, &mut XNodeInventory)> {
+        self.providers()?;
+        self.inventory()?;
+        match (self.providers.as_mut(), self.inventory.as_mut()) {
+            (Some(providers), Some(inventory)) => Ok((providers, inventory)),
+            _ => unreachable!("providers and inventory were just loaded"),
+        }
+    }
+
+    /// The monitoring system (`monitoring.yml`, history in `monitoring_data/`)
+    pub async fn monitoring(&mut self) -> Result<&mut MonitoringSystem> {
+        if self.monitoring.is_none() {
+            let system = MonitoringSystem::with_data_dir(
+                self.base_dir.join("monitoring.yml"),
+                self.base_dir.join("monitoring_data"),
+            )
+            .await?;
+            self.monitoring = Some(system);
+        }
+        Ok(self.monitoring.as_mut().expect("monitoring was just loaded"))
+    }
+
+    /// A built-in profile, or a user profile from `configs/`
+    pub fn load_profile(&self, name: &str) -> ConfigResult<Config> {
+        config::load_profile_from(&self.base_dir, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xnode::XNode;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_state_lives_under_base_dir() {
+        let dir = TempDir::new().unwrap();
+        let mut capsule = Capsule::with_base_dir(dir.path());
+
+        let xnode = XNode::new("x-1".into(), "web".into(), "running".into(), "10.0.0.1".into());
+        capsule
+            .inventory()
+            .unwrap()
+            .add_xnode(&xnode, "fake".into(), "small".into(), 0.1, Vec::new())
+            .unwrap();
+        assert!(dir.path().join("inventory.json").exists());
+
+        let mut reopened = Capsule::with_base_dir(dir.path());
+        assert!(reopened.inventory().unwrap().get_xnode("x-1").is_some());
+    }
+
+    #[test]
+    fn test_load_profile_reads_base_dir() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join("configs")).unwrap();
+        std::fs::write(dir.path().join("configs/team.yml"), "presets: [web]\n").unwrap();
+
+        let capsule = Capsule::with_base_dir(dir.path());
+        assert_eq!(capsule.load_profile("team").unwrap().presets, ["web"]);
+        assert_eq!(capsule.load_profile("missing").unwrap().presets, Config::default().presets);
+    }
+
+    #[tokio::test]
+    async fn test_monitoring_history_under_base_dir() {
+        let dir = TempDir::new().unwrap();
+        let mut capsule = Capsule::with_base_dir(dir.path());
+
+        capsule.monitoring().await.unwrap();
+        assert!(dir.path().join("monitoring_data").is_dir());
+    }
+}
@@ -1,9 +1,90 @@
 // Core configuration types and utilities for Capsule
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
-use anyhow::{Context, Result};
+use anyhow::Result;
+use thiserror::Error;
+
+use crate::exit::{self, CliError};
+
+/// Why a profile or preset operation failed
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Profile not found: {0}")]
+    NotFound(String),
+
+    #[error("Cannot modify built-in profile '{0}'. Create a new profile or switch to a user profile.")]
+    BuiltinReadOnly(String),
+
+    #[error("Cannot delete the active profile '{0}'. Switch to another profile first.")]
+    ActiveProfile(String),
+
+    #[error("Stack '{preset}' is a dependency of {}; it would still be installed. Use --force to remove it anyway.", .dependents.join(", "))]
+    RequiredBy {
+        preset: String,
+        dependents: Vec<String>,
+    },
+
+    #[error("Profile inheritance loops: {}", .0.join(" -> "))]
+    ExtendsCycle(Vec<String>),
+
+    #[error("Failed to parse {}: {source}", .path.display())]
+    Parse {
+        path: PathBuf,
+        source: serde_yaml::Error,
+    },
+
+    #[error("Failed to access {}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+impl ConfigError {
+    /// Exit code for this error; see `crate::exit`
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::NotFound(_) => exit::NOT_FOUND,
+            Self::BuiltinReadOnly(_) | Self::ActiveProfile(_) | Self::RequiredBy { .. } | Self::ExtendsCycle(_) => {
+                exit::USAGE
+            }
+            Self::Parse { .. } | Self::Io { .. } => exit::FAILURE,
+        }
+    }
+}
+
+pub type ConfigResult<T> = std::result::Result<T, ConfigError>;
+
+fn io_error(path: &Path) -> impl FnOnce(std::io::Error) -> ConfigError + '_ {
+    move |source| ConfigError::Io { path: path.to_path_buf(), source }
+}
+
+fn parse_error(path: &Path) -> impl FnOnce(serde_yaml::Error) -> ConfigError + '_ {
+    move |source| ConfigError::Parse { path: path.to_path_buf(), source }
+}
+
+/// Path of a user profile's YAML file
+fn profile_path(name: &str) -> PathBuf {
+    get_capsule_dir().join("configs").join(format!("{}.yml", name))
+}
+
+/// The named profile, or the active one
+fn profile_name_or_active(name: Option<&str>) -> ConfigResult<String> {
+    match name {
+        Some(n) => Ok(n.to_string()),
+        None => get_active_config_name(),
+    }
+}
+
+/// Refuse to modify built-in profiles
+fn ensure_writable(name: &str) -> ConfigResult<()> {
+    if is_builtin_profile(name) {
+        return Err(ConfigError::BuiltinReadOnly(name.to_string()));
+    }
+    Ok(())
+}
 
 /// Capsule configuration profile
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +97,9 @@ pub struct Config {
     pub custom_packages: Vec<String>,
     #[serde(default)]
     pub editor: Option<String>,
+    /// Profile (built-in or user) whose settings this one builds on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
 }
 
 impl Default for Config {
@@ -25,10 +109,93 @@ impl Default for Config {
             presets: vec!["base".to_string()],
             custom_packages: vec![],
             editor: Some("vim".to_string()),
+            extends: None,
         }
     }
 }
 
+impl Config {
+    /// This profile layered over `parent`: presets and packages are the
+    /// union (parent's first), description and editor are ours if set
+    pub fn merged_over(self, parent: Config) -> Config {
+        let union = |mut base: Vec<String>, extra: Vec<String>| {
+            for item in extra {
+                if !base.contains(&item) {
+                    base.push(item);
+                }
+            }
+            base
+        };
+        Config {
+            description: self.description.or(parent.description),
+            presets: union(parent.presets, self.presets),
+            custom_packages: union(parent.custom_packages, self.custom_packages),
+            editor: self.editor.or(parent.editor),
+            extends: self.extends,
+        }
+    }
+}
+
+/// Output formats for `capsule show`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ShowFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl std::str::FromStr for ShowFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            other => Err(format!("unknown format '{}' (expected text or json)", other)),
+        }
+    }
+}
+
+/// Where a profile's configuration comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ProfileSource {
+    /// Compiled into capsule and read-only
+    Builtin,
+    /// A YAML file under ~/.capsule/configs; until it exists the
+    /// default configuration is used
+    File { path: PathBuf, exists: bool },
+}
+
+impl ProfileSource {
+    pub fn of(name: &str) -> Self {
+        if is_builtin_profile(name) {
+            return Self::Builtin;
+        }
+        let path = profile_path(name);
+        let exists = path.is_file();
+        Self::File { path, exists }
+    }
+}
+
+/// A resolved profile as `capsule show --format json` prints it
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileView {
+    pub profile: String,
+    pub source: ProfileSource,
+    #[serde(flatten)]
+    pub config: Config,
+}
+
+impl ProfileView {
+    /// The active profile with its configuration and source
+    pub fn active() -> ConfigResult<Self> {
+        let (profile, config) = load_active_config()?;
+        let source = ProfileSource::of(&profile);
+        Ok(Self { profile, source, config })
+    }
+}
+
 /// Preset/Stack definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Preset {
@@ -42,6 +209,9 @@ pub struct Preset {
     pub dependencies: Vec<String>,
     #[serde(default)]
     pub optional_dependencies: Vec<OptionalDependency>,
+    /// Packages that cannot be installed alongside this stack's packages
+    #[serde(default)]
+    pub conflicts: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -80,62 +250,97 @@ pub fn get_presets_dir() -> PathBuf {
         .join("capsule_package/presets")
 }
 
-/// Load configuration from file
-pub fn load_config(profile_name: Option<&str>) -> Result<Config> {
-    let config_dir = get_capsule_dir().join("configs");
-    let config_file = if let Some(name) = profile_name {
-        config_dir.join(format!("{}.yml", name))
-    } else {
-        config_dir.join("default.yml")
-    };
+/// Load configuration from file, with any `extends` chain applied
+pub fn load_config(profile_name: Option<&str>) -> ConfigResult<Config> {
+    let name = profile_name.unwrap_or("default");
+    let config = read_config(&profile_path(name))?;
+    resolve_extends(&get_capsule_dir(), name, config)
+}
+
+/// Load a profile stored under `capsule_dir` instead of `~/.capsule`,
+/// resolving built-in profiles and `extends`
+pub fn load_profile_from(capsule_dir: &Path, name: &str) -> ConfigResult<Config> {
+    let config = read_profile_from(capsule_dir, name)?;
+    resolve_extends(capsule_dir, name, config)
+}
 
+/// A profile as written, without following `extends`
+fn read_profile_from(capsule_dir: &Path, name: &str) -> ConfigResult<Config> {
+    match get_builtin_profile(name) {
+        Some(config) => Ok(config),
+        None => read_config(&capsule_dir.join("configs").join(format!("{}.yml", name))),
+    }
+}
+
+/// Merge `config` (the profile `name`) over each profile it extends in turn.
+/// Parents must exist; a profile reached twice is a cycle.
+fn resolve_extends(capsule_dir: &Path, name: &str, config: Config) -> ConfigResult<Config> {
+    let mut chain = vec![name.to_string()];
+    let mut layers = vec![config];
+    while let Some(parent) = layers.last().and_then(|c| c.extends.clone()) {
+        if chain.contains(&parent) {
+            chain.push(parent);
+            return Err(ConfigError::ExtendsCycle(chain));
+        }
+        let path = capsule_dir.join("configs").join(format!("{}.yml", parent));
+        if !is_builtin_profile(&parent) && !path.exists() {
+            return Err(ConfigError::NotFound(parent));
+        }
+        layers.push(read_profile_from(capsule_dir, &parent)?);
+        chain.push(parent);
+    }
+
+    let mut layers = layers.into_iter().rev();
+    let root = layers.next().expect("chain starts with the profile itself");
+    Ok(layers.fold(root, |parent, child| child.merged_over(parent)))
+}
+
+/// Read a profile file, treating a missing file as the default configuration
+fn read_config(config_file: &Path) -> ConfigResult<Config> {
     if !config_file.exists() {
         return Ok(Config::default());
     }
 
-    let contents = std::fs::read_to_string(&config_file)
-        .context(format!("Failed to read config file: {:?}", config_file))?;
-    let config: Config = serde_yaml::from_str(&contents)
-        .context("Failed to parse config YAML")?;
-    Ok(config)
+    let contents = std::fs::read_to_string(config_file).map_err(io_error(config_file))?;
+    serde_yaml::from_str(&contents).map_err(parse_error(config_file))
+}
+
+/// Load a profile by name, resolving built-in profiles
+pub fn load_profile(name: &str) -> ConfigResult<Config> {
+    load_profile_from(&get_capsule_dir(), name)
+}
+
+/// Load the active profile, returning its name alongside the configuration
+pub fn load_active_config() -> ConfigResult<(String, Config)> {
+    let name = get_active_config_name()?;
+    let config = load_profile(&name)?;
+    Ok((name, config))
 }
 
 /// Save configuration to file
-pub fn save_config(config: &Config, profile_name: Option<&str>) -> Result<()> {
+pub fn save_config(config: &Config, profile_name: Option<&str>) -> ConfigResult<()> {
+    let config_file = profile_path(profile_name.unwrap_or("default"));
     let config_dir = get_capsule_dir().join("configs");
-    std::fs::create_dir_all(&config_dir)
-        .context("Failed to create config directory")?;
-
-    let config_file = if let Some(name) = profile_name {
-        config_dir.join(format!("{}.yml", name))
-    } else {
-        config_dir.join("default.yml")
-    };
+    std::fs::create_dir_all(&config_dir).map_err(io_error(&config_dir))?;
 
-    let contents = serde_yaml::to_string(config)
-        .context("Failed to serialize config")?;
-    std::fs::write(config_file, contents)
-        .context("Failed to write config file")?;
-    Ok(())
+    let contents = serde_yaml::to_string(config).map_err(parse_error(&config_file))?;
+    std::fs::write(&config_file, contents).map_err(io_error(&config_file))
 }
 
 /// Load a preset by name
-pub fn load_preset(name: &str) -> Result<Option<Preset>> {
+pub fn load_preset(name: &str) -> ConfigResult<Option<Preset>> {
     let preset_file = get_presets_dir().join(format!("{}.yml", name));
 
     if !preset_file.exists() {
         return Ok(None);
     }
 
-    let contents = std::fs::read_to_string(&preset_file)
-        .context(format!("Failed to read preset file: {:?}", preset_file))?;
-    let preset: Preset = serde_yaml::from_str(&contents)
-        .context("Failed to parse preset YAML")?;
-    Ok(Some(preset))
+    let contents = std::fs::read_to_string(&preset_file).map_err(io_error(&preset_file))?;
+    serde_yaml::from_str(&contents).map(Some).map_err(parse_error(&preset_file))
 }
 
 /// Resolve preset dependencies recursively
-pub fn resolve_dependencies(preset_name: &str) -> Result<Vec<String>> {
+pub fn resolve_dependencies(preset_name: &str) -> ConfigResult<Vec<String>> {
     let mut resolved = Vec::new();
     let mut visiting = std::collections::HashSet::new();
 
@@ -148,7 +353,7 @@ fn resolve_dependencies_inner(
     preset_name: &str,
     resolved: &mut Vec<String>,
     visiting: &mut std::collections::HashSet<String>,
-) -> Result<()> {
+) -> ConfigResult<()> {
     if visiting.contains(preset_name) || resolved.contains(&preset_name.to_string()) {
         return Ok(());
     }
@@ -170,10 +375,33 @@ fn resolve_dependencies_inner(
     Ok(())
 }
 
+/// Presets in `enabled`, other than `preset` itself, that depend on
+/// `preset` directly or transitively
+pub fn dependents_of(preset: &str, enabled: &[String]) -> ConfigResult<Vec<String>> {
+    dependents_with(preset, enabled, resolve_dependencies)
+}
+
+fn dependents_with(
+    preset: &str,
+    enabled: &[String],
+    resolve: impl Fn(&str) -> ConfigResult<Vec<String>>,
+) -> ConfigResult<Vec<String>> {
+    let mut dependents = Vec::new();
+    for other in enabled.iter().filter(|p| *p != preset) {
+        if resolve(other)?.iter().any(|dep| dep == preset) {
+            dependents.push(other.clone());
+        }
+    }
+    Ok(dependents)
+}
+
+/// Packages grouped by the preset (or "base"/"custom") that contributed them
+pub type PackagesByPreset = BTreeMap<String, Vec<String>>;
+
 /// Collect all packages from config
-pub fn collect_packages(config: &Config) -> Result<(Vec<String>, HashMap<String, Vec<String>>)> {
+pub fn collect_packages(config: &Config) -> ConfigResult<(Vec<String>, PackagesByPreset)> {
     let mut all_packages = Vec::new();
-    let mut packages_by_preset = HashMap::new();
+    let mut packages_by_preset = BTreeMap::new();
 
     // Base packages
     let base_packages = vec![
@@ -224,8 +452,66 @@ pub fn collect_packages(config: &Config) -> Result<(Vec<String>, HashMap<String,
     Ok((unique_packages, packages_by_preset))
 }
 
+/// A package in the effective set that a stack declares it conflicts with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackageConflict {
+    /// Stack whose `conflicts` list names the package
+    pub preset: String,
+    pub package: String,
+    /// Stacks (or "base"/"custom") that brought the package in
+    pub introduced_by: Vec<String>,
+}
+
+impl std::fmt::Display for PackageConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Stack '{}' conflicts with '{}' (from {})",
+            self.preset,
+            self.package,
+            self.introduced_by.join(", ")
+        )
+    }
+}
+
+/// Conflicts between the packages a profile would install
+pub fn find_conflicts(config: &Config) -> ConfigResult<Vec<PackageConflict>> {
+    let (_, packages_by_preset) = collect_packages(config)?;
+    let mut declared = BTreeMap::new();
+    for stack in packages_by_preset.keys() {
+        if let Some(preset) = load_preset(stack)? {
+            if !preset.conflicts.is_empty() {
+                declared.insert(stack.clone(), preset.conflicts);
+            }
+        }
+    }
+    Ok(conflicts_in(&packages_by_preset, &declared))
+}
+
+/// Match each stack's declared conflicts against what the other stacks contribute
+fn conflicts_in(packages_by_preset: &PackagesByPreset, declared: &BTreeMap<String, Vec<String>>) -> Vec<PackageConflict> {
+    let mut conflicts = Vec::new();
+    for (preset, packages) in declared.iter().filter(|(preset, _)| packages_by_preset.contains_key(*preset)) {
+        for package in packages {
+            let introduced_by: Vec<String> = packages_by_preset
+                .iter()
+                .filter(|(stack, pkgs)| *stack != preset && pkgs.contains(package))
+                .map(|(stack, _)| stack.clone())
+                .collect();
+            if !introduced_by.is_empty() {
+                conflicts.push(PackageConflict {
+                    preset: preset.clone(),
+                    package: package.clone(),
+                    introduced_by,
+                });
+            }
+        }
+    }
+    conflicts
+}
+
 /// List all available presets
-pub fn list_presets() -> Result<Vec<String>> {
+pub fn list_presets() -> ConfigResult<Vec<String>> {
     let presets_dir = get_presets_dir();
     let mut presets = Vec::new();
 
@@ -233,8 +519,8 @@ pub fn list_presets() -> Result<Vec<String>> {
         return Ok(presets);
     }
 
-    for entry in std::fs::read_dir(presets_dir)? {
-        let entry = entry?;
+    for entry in std::fs::read_dir(&presets_dir).map_err(io_error(&presets_dir))? {
+        let entry = entry.map_err(io_error(&presets_dir))?;
         let path = entry.path();
         if path.extension().and_then(|s| s.to_str()) == Some("yml") {
             if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
@@ -248,41 +534,45 @@ pub fn list_presets() -> Result<Vec<String>> {
 }
 
 /// Get the active configuration name from ~/.capsule/active.txt
-pub fn get_active_config_name() -> Result<String> {
-    let active_file = get_capsule_dir().join("active.txt");
+pub fn get_active_config_name() -> ConfigResult<String> {
+    let capsule_dir = get_capsule_dir();
+    let active_file = capsule_dir.join("active.txt");
 
     if !active_file.exists() {
-        std::fs::create_dir_all(get_capsule_dir())?;
-        std::fs::write(&active_file, "default")?;
+        std::fs::create_dir_all(&capsule_dir).map_err(io_error(&capsule_dir))?;
+        std::fs::write(&active_file, "default").map_err(io_error(&active_file))?;
         return Ok("default".to_string());
     }
 
-    Ok(std::fs::read_to_string(&active_file)?.trim().to_string())
+    let name = std::fs::read_to_string(&active_file).map_err(io_error(&active_file))?;
+    Ok(name.trim().to_string())
 }
 
-/// Set the active configuration name
-pub fn set_active_config_name(name: &str) -> Result<()> {
+/// Set the active configuration name; it must be a built-in or saved
+/// user profile, so the active profile never silently falls back to defaults
+pub fn set_active_config_name(name: &str) -> ConfigResult<()> {
+    if !is_builtin_profile(name) && !profile_path(name).is_file() {
+        return Err(ConfigError::NotFound(name.to_string()));
+    }
+
     let capsule_dir = get_capsule_dir();
-    std::fs::create_dir_all(&capsule_dir)?;
-    std::fs::write(capsule_dir.join("active.txt"), name)?;
-    Ok(())
+    std::fs::create_dir_all(&capsule_dir).map_err(io_error(&capsule_dir))?;
+    let active_file = capsule_dir.join("active.txt");
+    std::fs::write(&active_file, name).map_err(io_error(&active_file))
 }
 
 /// Get the path to a config file
-pub fn get_config_file(name: Option<&str>) -> Result<PathBuf> {
-    let name = match name {
-        Some(n) => n.to_string(),
-        None => get_active_config_name()?,
-    };
+pub fn get_config_file(name: Option<&str>) -> ConfigResult<PathBuf> {
+    let name = profile_name_or_active(name)?;
 
     let configs_dir = get_capsule_dir().join("configs");
-    std::fs::create_dir_all(&configs_dir)?;
+    std::fs::create_dir_all(&configs_dir).map_err(io_error(&configs_dir))?;
 
-    Ok(configs_dir.join(format!("{}.yml", name)))
+    Ok(profile_path(&name))
 }
 
 /// List all user configuration files
-pub fn list_all_configs() -> Result<Vec<String>> {
+pub fn list_all_configs() -> ConfigResult<Vec<String>> {
     let configs_dir = get_capsule_dir().join("configs");
 
     if !configs_dir.exists() {
@@ -290,8 +580,8 @@ pub fn list_all_configs() -> Result<Vec<String>> {
     }
 
     let mut names = Vec::new();
-    for entry in std::fs::read_dir(configs_dir)? {
-        let entry = entry?;
+    for entry in std::fs::read_dir(&configs_dir).map_err(io_error(&configs_dir))? {
+        let entry = entry.map_err(io_error(&configs_dir))?;
         let path = entry.path();
 
         if path.extension().and_then(|s| s.to_str()) == Some("yml") {
@@ -323,6 +613,7 @@ pub fn builtin_profiles() -> HashMap<String, Config> {
             ],
             custom_packages: vec!["tmux".to_string(), "htop".to_string(), "jq".to_string()],
             editor: Some("vim".to_string()),
+            extends: None,
         },
     );
 
@@ -338,6 +629,7 @@ pub fn builtin_profiles() -> HashMap<String, Config> {
             ],
             custom_packages: vec!["fail2ban".to_string()],
             editor: Some("vim".to_string()),
+            extends: None,
         },
     );
 
@@ -353,6 +645,7 @@ pub fn builtin_profiles() -> HashMap<String, Config> {
             ],
             custom_packages: vec!["htop".to_string(), "nvtop".to_string()],
             editor: Some("vim".to_string()),
+            extends: None,
         },
     );
 
@@ -369,6 +662,7 @@ pub fn builtin_profiles() -> HashMap<String, Config> {
             ],
             custom_packages: vec!["htop".to_string(), "nvtop".to_string()],
             editor: Some("vim".to_string()),
+            extends: None,
         },
     );
 
@@ -384,6 +678,7 @@ pub fn builtin_profiles() -> HashMap<String, Config> {
             ],
             custom_packages: vec!["tmux".to_string()],
             editor: Some("vim".to_string()),
+            extends: None,
         },
     );
 
@@ -394,6 +689,7 @@ pub fn builtin_profiles() -> HashMap<String, Config> {
             presets: vec!["base".to_string()],
             custom_packages: vec!["tmux".to_string(), "htop".to_string()],
             editor: Some("vim".to_string()),
+            extends: None,
         },
     );
 
@@ -418,7 +714,7 @@ pub fn get_builtin_profile(name: &str) -> Option<Config> {
 }
 
 /// Ensure config file exists, creating default if needed
-pub fn ensure_config(name: Option<&str>) -> Result<PathBuf> {
+pub fn ensure_config(name: Option<&str>) -> ConfigResult<PathBuf> {
     let config_file = get_config_file(name)?;
 
     if !config_file.exists() {
@@ -429,126 +725,274 @@ pub fn ensure_config(name: Option<&str>) -> Result<PathBuf> {
     Ok(config_file)
 }
 
-/// Copy a profile from src to dst
-pub fn copy_profile(src: &str, dst: &str) -> Result<()> {
-    let src_config = load_config(Some(src))?;
-    save_config(&src_config, Some(dst))?;
-    Ok(())
+/// Editor for `capsule config edit`: the profile's `editor`, then
+/// `$EDITOR`, then vim
+pub fn resolve_editor(config: &Config) -> String {
+    config
+        .editor
+        .clone()
+        .filter(|e| !e.trim().is_empty())
+        .or_else(|| std::env::var("EDITOR").ok().filter(|e| !e.trim().is_empty()))
+        .unwrap_or_else(|| "vim".to_string())
 }
 
-/// Delete a user profile
-pub fn delete_profile(name: &str) -> Result<()> {
-    if is_builtin_profile(name) {
-        anyhow::bail!("Cannot delete built-in profile: {}", name);
+/// Parse an edited profile, rejecting YAML errors, unknown presets and
+/// blank package names
+pub fn parse_profile(contents: &str) -> Result<Config> {
+    let config: Config = serde_yaml::from_str(contents)
+        .map_err(|e| CliError::usage(format!("Invalid profile YAML: {}", e)))?;
+
+    let mut problems = Vec::new();
+    // Preset files are only available where capsule was installed with them
+    if get_presets_dir().is_dir() {
+        for preset in config.presets.iter().filter(|p| p.as_str() != "base") {
+            if load_preset(preset)?.is_none() {
+                problems.push(format!("unknown preset '{}'", preset));
+            }
+        }
+    }
+    if config.custom_packages.iter().any(|p| p.trim().is_empty()) {
+        problems.push("custom_packages contains an empty name".to_string());
+    }
+    if let Some(parent) = &config.extends {
+        if !is_builtin_profile(parent) && !profile_path(parent).exists() {
+            problems.push(format!("extends unknown profile '{}'", parent));
+        }
     }
 
+    if !problems.is_empty() {
+        return Err(CliError::usage(format!("Invalid profile: {}", problems.join(", "))).into());
+    }
+    Ok(config)
+}
+
+/// Copy a profile (built-in or user) from src to dst
+pub fn copy_profile(src: &str, dst: &str) -> ConfigResult<()> {
+    ensure_writable(dst)?;
+    let src_config = match get_builtin_profile(src) {
+        Some(config) => config,
+        None if profile_path(src).exists() => read_config(&profile_path(src))?,
+        None => return Err(ConfigError::NotFound(src.to_string())),
+    };
+    save_config(&src_config, Some(dst))
+}
+
+/// Delete a user profile
+pub fn delete_profile(name: &str) -> ConfigResult<()> {
+    ensure_writable(name)?;
+
     let config_file = get_config_file(Some(name))?;
 
     if !config_file.exists() {
-        anyhow::bail!("Profile not found: {}", name);
+        return Err(ConfigError::NotFound(name.to_string()));
     }
 
     // Don't allow deleting the active profile
-    let active = get_active_config_name()?;
-    if active == name {
-        anyhow::bail!("Cannot delete the active profile. Switch to another profile first.");
+    if get_active_config_name()? == name {
+        return Err(ConfigError::ActiveProfile(name.to_string()));
     }
 
-    std::fs::remove_file(&config_file)?;
-    Ok(())
+    std::fs::remove_file(&config_file).map_err(io_error(&config_file))
 }
 
-/// Add a preset (stack) to the configuration
-pub fn add_preset(preset: &str, name: Option<&str>) -> Result<()> {
-    let config_name = match name {
-        Some(n) => n.to_string(),
-        None => get_active_config_name()?,
-    };
-
-    if is_builtin_profile(&config_name) {
-        anyhow::bail!(
-            "Cannot modify built-in profile '{}'. Create a new profile or switch to a user profile.",
-            config_name
-        );
-    }
-
-    let mut config = load_config(Some(&config_name))?;
+/// Load a user profile, apply `change` and save it back
+fn modify_profile(name: Option<&str>, change: impl FnOnce(&mut Config)) -> ConfigResult<()> {
+    let config_name = profile_name_or_active(name)?;
+    ensure_writable(&config_name)?;
 
-    if !config.presets.contains(&preset.to_string()) {
-        config.presets.push(preset.to_string());
-        save_config(&config, Some(&config_name))?;
-    }
-
-    Ok(())
+    // Edit the profile as written so inherited settings aren't copied in
+    let mut config = read_config(&profile_path(&config_name))?;
+    change(&mut config);
+    save_config(&config, Some(&config_name))
 }
 
-/// Remove a preset (stack) from the configuration
-pub fn remove_preset(preset: &str, name: Option<&str>) -> Result<()> {
-    let config_name = match name {
-        Some(n) => n.to_string(),
-        None => get_active_config_name()?,
-    };
+/// Add a preset (stack) to the configuration
+pub fn add_preset(preset: &str, name: Option<&str>) -> ConfigResult<()> {
+    modify_profile(name, |config| {
+        if !config.presets.iter().any(|p| p == preset) {
+            config.presets.push(preset.to_string());
+        }
+    })
+}
 
-    if is_builtin_profile(&config_name) {
-        anyhow::bail!(
-            "Cannot modify built-in profile '{}'. Create a new profile or switch to a user profile.",
-            config_name
-        );
+/// Remove a preset (stack) from the configuration. Refused while other
+/// enabled presets depend on it, since they would pull it back in, unless
+/// `force`; returns those dependents.
+pub fn remove_preset(preset: &str, name: Option<&str>, force: bool) -> ConfigResult<Vec<String>> {
+    let config_name = profile_name_or_active(name)?;
+    ensure_writable(&config_name)?;
+
+    let mut config = read_config(&profile_path(&config_name))?;
+    let dependents = dependents_of(preset, &config.presets)?;
+    if !dependents.is_empty() && !force {
+        return Err(ConfigError::RequiredBy { preset: preset.to_string(), dependents });
     }
 
-    let mut config = load_config(Some(&config_name))?;
     config.presets.retain(|p| p != preset);
     save_config(&config, Some(&config_name))?;
-
-    Ok(())
+    Ok(dependents)
 }
 
 /// Add custom packages to the configuration
-pub fn add_packages(packages: &[String], name: Option<&str>) -> Result<()> {
-    let config_name = match name {
-        Some(n) => n.to_string(),
-        None => get_active_config_name()?,
-    };
+pub fn add_packages(packages: &[String], name: Option<&str>) -> ConfigResult<()> {
+    modify_profile(name, |config| {
+        for package in packages {
+            if !config.custom_packages.contains(package) {
+                config.custom_packages.push(package.clone());
+            }
+        }
+    })
+}
 
-    if is_builtin_profile(&config_name) {
-        anyhow::bail!(
-            "Cannot modify built-in profile '{}'. Create a new profile or switch to a user profile.",
-            config_name
-        );
+/// Remove custom packages from the configuration
+pub fn remove_packages(packages: &[String], name: Option<&str>) -> ConfigResult<()> {
+    modify_profile(name, |config| config.custom_packages.retain(|p| !packages.contains(p)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_profiles_are_read_only() {
+        let builtin = list_builtin_profiles().into_iter().next().unwrap();
+
+        let err = add_preset("web", Some(&builtin)).unwrap_err();
+        assert!(matches!(&err, ConfigError::BuiltinReadOnly(name) if *name == builtin));
+        assert!(matches!(delete_profile(&builtin), Err(ConfigError::BuiltinReadOnly(_))));
+        assert!(matches!(copy_profile("anything", &builtin), Err(ConfigError::BuiltinReadOnly(_))));
+
+        let err = anyhow::Error::new(err).context("Failed to add stack");
+        assert_eq!(exit::exit_code_for(&err), exit::USAGE);
     }
 
-    let mut config = load_config(Some(&config_name))?;
+    #[test]
+    fn test_profile_view_json() {
+        let builtin = list_builtin_profiles().into_iter().next().unwrap();
+        let view = ProfileView {
+            profile: builtin.clone(),
+            source: ProfileSource::of(&builtin),
+            config: get_builtin_profile(&builtin).unwrap(),
+        };
+        let json = serde_json::to_value(&view).unwrap();
+
+        assert_eq!(json["profile"], builtin.as_str());
+        assert_eq!(json["source"]["kind"], "builtin");
+        assert!(json["presets"].as_array().is_some_and(|p| !p.is_empty()));
+        assert!(json.get("custom_packages").is_some());
+
+        let source = serde_json::to_value(ProfileSource::File { path: "/tmp/x.yml".into(), exists: false }).unwrap();
+        assert_eq!(source, serde_json::json!({ "kind": "file", "path": "/tmp/x.yml", "exists": false }));
+        assert_eq!("JSON".parse::<ShowFormat>(), Ok(ShowFormat::Json));
+    }
 
-    for package in packages {
-        if !config.custom_packages.contains(package) {
-            config.custom_packages.push(package.clone());
+    fn write_profiles(profiles: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("configs")).unwrap();
+        for (name, yaml) in profiles {
+            std::fs::write(dir.path().join("configs").join(format!("{}.yml", name)), yaml).unwrap();
         }
+        dir
     }
 
-    save_config(&config, Some(&config_name))?;
-    Ok(())
-}
+    #[test]
+    fn test_extends_merges_parent_chain() {
+        let dir = write_profiles(&[
+            ("team", "extends: minimal\npresets: [base, docker]\ncustom_packages: [jq]\neditor: nano\n"),
+            ("backend", "extends: team\npresets: [python, docker]\ncustom_packages: [htop]\n"),
+        ]);
+
+        let config = load_profile_from(dir.path(), "backend").unwrap();
+        assert_eq!(config.presets, ["base", "docker", "python"]);
+        assert_eq!(config.custom_packages, ["tmux", "htop", "jq"]);
+        assert_eq!(config.editor.as_deref(), Some("nano"));
+        assert_eq!(config.description.as_deref(), Some("Minimal setup with essential tools only"));
+        assert_eq!(config.extends.as_deref(), Some("team"));
+
+        // Without extends a profile stands alone
+        let team: Config = serde_yaml::from_str("presets: [web]\n").unwrap();
+        assert!(team.extends.is_none());
+        assert!(!serde_yaml::to_string(&team).unwrap().contains("extends"));
+    }
 
-/// Remove custom packages from the configuration
-pub fn remove_packages(packages: &[String], name: Option<&str>) -> Result<()> {
-    let config_name = match name {
-        Some(n) => n.to_string(),
-        None => get_active_config_name()?,
-    };
+    #[test]
+    fn test_extends_rejects_cycles_and_missing_parents() {
+        let dir = write_profiles(&[
+            ("a", "extends: b\n"),
+            ("b", "extends: c\n"),
+            ("c", "extends: a\n"),
+            ("orphan", "extends: nowhere\n"),
+        ]);
+
+        let err = load_profile_from(dir.path(), "a").unwrap_err();
+        assert!(matches!(&err, ConfigError::ExtendsCycle(chain) if chain == &["a", "b", "c", "a"]));
+        assert_eq!(err.exit_code(), exit::USAGE);
+        assert!(matches!(load_profile_from(dir.path(), "orphan"), Err(ConfigError::NotFound(name)) if name == "nowhere"));
+    }
 
-    if is_builtin_profile(&config_name) {
-        anyhow::bail!(
-            "Cannot modify built-in profile '{}'. Create a new profile or switch to a user profile.",
-            config_name
+    #[test]
+    fn test_conflicts_in_reports_introducing_stacks() {
+        let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let by_preset = PackagesByPreset::from([
+            ("mariadb".to_string(), strings(&["mariadb"])),
+            ("legacy".to_string(), strings(&["mysql", "postfix"])),
+            ("custom".to_string(), strings(&["mysql"])),
+        ]);
+        let declared = BTreeMap::from([
+            ("mariadb".to_string(), strings(&["mysql", "mariadb"])),
+            ("absent".to_string(), strings(&["postfix"])),
+        ]);
+
+        assert_eq!(
+            conflicts_in(&by_preset, &declared),
+            vec![PackageConflict {
+                preset: "mariadb".to_string(),
+                package: "mysql".to_string(),
+                introduced_by: strings(&["custom", "legacy"]),
+            }]
         );
+
+        let preset: Preset = serde_yaml::from_str("name: web\ndescription: Web\n").unwrap();
+        assert!(preset.conflicts.is_empty());
     }
 
-    let mut config = load_config(Some(&config_name))?;
+    #[test]
+    fn test_set_active_rejects_unknown_profile() {
+        let name = format!("no-such-profile-{}", std::process::id());
+        let err = set_active_config_name(&name).unwrap_err();
+        assert!(matches!(&err, ConfigError::NotFound(n) if *n == name));
+        assert_eq!(err.exit_code(), exit::NOT_FOUND);
+    }
 
-    for package in packages {
-        config.custom_packages.retain(|p| p != package);
+    #[test]
+    fn test_dependents_of_follows_transitive_dependencies() {
+        let deps: HashMap<&str, Vec<&str>> = HashMap::from([
+            ("web", vec!["nodejs", "web"]),
+            ("fullstack", vec!["python", "nodejs", "web", "fullstack"]),
+            ("python", vec!["python"]),
+        ]);
+        let resolve = |name: &str| -> ConfigResult<Vec<String>> {
+            Ok(deps.get(name).map(|d| d.iter().map(|s| s.to_string()).collect()).unwrap_or_default())
+        };
+        let enabled: Vec<String> = ["nodejs", "web", "fullstack", "python"].iter().map(|s| s.to_string()).collect();
+
+        assert_eq!(dependents_with("nodejs", &enabled, resolve).unwrap(), ["web", "fullstack"]);
+        assert_eq!(dependents_with("web", &enabled, resolve).unwrap(), ["fullstack"]);
+        assert!(dependents_with("fullstack", &enabled, resolve).unwrap().is_empty());
+
+        let err = ConfigError::RequiredBy { preset: "nodejs".into(), dependents: vec!["web".into(), "fullstack".into()] };
+        assert_eq!(err.exit_code(), exit::USAGE);
+        assert!(err.to_string().contains("dependency of web, fullstack"));
     }
 
-    save_config(&config, Some(&config_name))?;
-    Ok(())
+    #[test]
+    fn test_config_error_exit_codes() {
+        assert_eq!(ConfigError::NotFound("dev".into()).exit_code(), exit::NOT_FOUND);
+        assert_eq!(ConfigError::ActiveProfile("dev".into()).exit_code(), exit::USAGE);
+
+        let source = serde_yaml::from_str::<Config>("presets: [").unwrap_err();
+        let err = ConfigError::Parse { path: PathBuf::from("dev.yml"), source };
+        assert_eq!(err.exit_code(), exit::FAILURE);
+        assert!(err.to_string().starts_with("Failed to parse dev.yml"));
+    }
 }
@@ -1,6 +1,88 @@
-use chrono::{DateTime, Utc};
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Days of daily cost samples kept in the cost log
+const COST_LOG_RETENTION_DAYS: i64 = 90;
+
+/// Assumed monthly egress for an xNode without an `egress_tb_monthly`
+/// metadata value
+pub const DEFAULT_EGRESS_TB_MONTHLY: f64 = 0.5;
+
+/// One running xNode's expected transfer and what its provider charges
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeTransfer {
+    pub provider: String,
+    /// Expected outbound transfer per month
+    pub egress_tb: f64,
+    /// Transfer included in the node's plan
+    pub included_tb: f64,
+    /// Price per TB beyond `included_tb`
+    pub overage_per_tb: f64,
+}
+
+impl NodeTransfer {
+    pub fn overage_tb(&self) -> f64 {
+        (self.egress_tb - self.included_tb).max(0.0)
+    }
+
+    pub fn monthly_cost(&self) -> f64 {
+        self.overage_tb() * self.overage_per_tb
+    }
+}
+
+/// Estimated monthly data transfer cost, kept apart from compute cost
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BandwidthEstimate {
+    pub egress_tb: f64,
+    pub included_tb: f64,
+    pub overage_tb: f64,
+    /// Estimated overage charges per month
+    pub monthly: f64,
+    pub by_provider: BTreeMap<String, f64>,
+    /// Running xNodes left out because their plan's allowance is unknown
+    #[serde(default)]
+    pub unpriced: usize,
+}
+
+impl BandwidthEstimate {
+    pub fn from_nodes(nodes: &[NodeTransfer], unpriced: usize) -> Self {
+        let mut estimate = Self { unpriced, ..Self::default() };
+        for node in nodes {
+            estimate.egress_tb += node.egress_tb;
+            estimate.included_tb += node.included_tb;
+            estimate.overage_tb += node.overage_tb();
+            estimate.monthly += node.monthly_cost();
+            *estimate.by_provider.entry(node.provider.clone()).or_insert(0.0) += node.monthly_cost();
+        }
+        estimate
+    }
+
+    fn summary_rows(&self) -> [(&'static str, String); 4] {
+        [
+            ("Expected egress", format!("{:.2} TB", self.egress_tb)),
+            ("Included in plans", format!("{:.2} TB", self.included_tb)),
+            ("Overage", format!("{:.2} TB", self.overage_tb)),
+            ("Overage cost (est.)", format!("${:.2}/month", self.monthly)),
+        ]
+    }
+
+    fn note(&self) -> String {
+        let mut note = format!(
+            "Estimate from each xNode's egress_tb_monthly metadata (default {} TB) and provider overage rates.",
+            DEFAULT_EGRESS_TB_MONTHLY
+        );
+        if self.unpriced > 0 {
+            note.push_str(&format!(" {} xNode(s) with unknown plans are not included.", self.unpriced));
+        }
+        note
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CostReport {
@@ -8,17 +90,20 @@ pub struct CostReport {
     pub total_daily: f64,
     pub total_monthly: f64,
     pub projected_annual: f64,
-    pub by_provider: HashMap<String, f64>,
-    pub by_region: HashMap<String, f64>,
+    pub by_provider: BTreeMap<String, f64>,
+    pub by_region: BTreeMap<String, f64>,
     pub active_count: usize,
     pub total_count: usize,
+    /// Estimated transfer overage, not included in the compute totals above
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bandwidth: Option<BandwidthEstimate>,
 }
 
 impl CostReport {
     pub fn new(
         total_hourly: f64,
-        by_provider: HashMap<String, f64>,
-        by_region: HashMap<String, f64>,
+        by_provider: BTreeMap<String, f64>,
+        by_region: BTreeMap<String, f64>,
         active_count: usize,
         total_count: usize,
     ) -> Self {
@@ -31,9 +116,15 @@ impl CostReport {
             by_region,
             active_count,
             total_count,
+            bandwidth: None,
         }
     }
 
+    pub fn with_bandwidth(mut self, bandwidth: BandwidthEstimate) -> Self {
+        self.bandwidth = Some(bandwidth);
+        self
+    }
+
     pub fn generate_report(&self) -> String {
         let mut lines = vec![
             "============================================================".to_string(),
@@ -60,9 +151,7 @@ impl CostReport {
         if self.by_provider.is_empty() {
             lines.push("  No data available".to_string());
         } else {
-            let mut providers: Vec<_> = self.by_provider.iter().collect();
-            providers.sort_by(|a, b| b.1.partial_cmp(a.1).unwrap());
-            for (provider, cost) in providers {
+            for (provider, cost) in breakdown(&self.by_provider) {
                 lines.push(format!("  {:<20} ${:.2}/hour", provider, cost));
             }
         }
@@ -74,17 +163,270 @@ impl CostReport {
         if self.by_region.is_empty() {
             lines.push("  No data available".to_string());
         } else {
-            let mut regions: Vec<_> = self.by_region.iter().collect();
-            regions.sort_by(|a, b| b.1.partial_cmp(a.1).unwrap());
-            for (region, cost) in regions {
+            for (region, cost) in breakdown(&self.by_region) {
                 lines.push(format!("  {:<20} ${:.2}/hour", region, cost));
             }
         }
 
+        if let Some(bandwidth) = &self.bandwidth {
+            lines.push(String::new());
+            lines.push("ESTIMATED BANDWIDTH (MONTHLY, NOT IN TOTALS ABOVE)".to_string());
+            lines.push("------------------------------------------------------------".to_string());
+            for (label, value) in bandwidth.summary_rows() {
+                lines.push(format!("{:<22}{}", format!("{}:", label), value));
+            }
+            for (provider, cost) in breakdown(&bandwidth.by_provider) {
+                lines.push(format!("  {:<20} ${:.2}/month", provider, cost));
+            }
+            lines.push(format!(
+                "Compute + bandwidth (est.): ${:.2}/month",
+                self.total_monthly + bandwidth.monthly
+            ));
+            lines.push(bandwidth.note());
+        }
+
         lines.push("============================================================".to_string());
 
         lines.join("\n")
     }
+
+    /// Markdown version of [`generate_report`](Self::generate_report)
+    pub fn generate_markdown(&self) -> String {
+        let mut lines = vec![
+            "# xNode Inventory Cost Report".to_string(),
+            String::new(),
+            format!("Generated: {}", Utc::now().format("%Y-%m-%d %H:%M:%S UTC")),
+            String::new(),
+            "## Summary".to_string(),
+            String::new(),
+            "| | |".to_string(),
+            "|---|---:|".to_string(),
+            format!("| Active xNodes | {} |", self.active_count),
+            format!("| Total xNodes | {} |", self.total_count),
+            format!("| Hourly | ${:.2} |", self.total_hourly),
+            format!("| Daily | ${:.2} |", self.total_daily),
+            format!("| Monthly | ${:.2} |", self.total_monthly),
+            format!("| Annual (proj.) | ${:.2} |", self.projected_annual),
+        ];
+
+        for (title, column, costs) in [
+            ("By Provider", "Provider", &self.by_provider),
+            ("By Region", "Region", &self.by_region),
+        ] {
+            lines.push(String::new());
+            lines.push(format!("## {}", title));
+            lines.push(String::new());
+            if costs.is_empty() {
+                lines.push("No data available".to_string());
+                continue;
+            }
+            lines.push(format!("| {} | Hourly |", column));
+            lines.push("|---|---:|".to_string());
+            for (name, cost) in breakdown(costs) {
+                lines.push(format!("| {} | ${:.2} |", name.replace('|', "\\|"), cost));
+            }
+        }
+
+        if let Some(bandwidth) = &self.bandwidth {
+            lines.push(String::new());
+            lines.push("## Estimated Bandwidth (monthly)".to_string());
+            lines.push(String::new());
+            lines.push("Not included in the compute totals above.".to_string());
+            lines.push(String::new());
+            lines.push("| | |".to_string());
+            lines.push("|---|---:|".to_string());
+            for (label, value) in bandwidth.summary_rows() {
+                lines.push(format!("| {} | {} |", label, value));
+            }
+            lines.push(format!(
+                "| Compute + bandwidth (est.) | ${:.2}/month |",
+                self.total_monthly + bandwidth.monthly
+            ));
+            lines.push(String::new());
+            lines.push(format!("_{}_", bandwidth.note()));
+        }
+
+        lines.join("\n") + "\n"
+    }
+
+    /// Standalone HTML version of [`generate_report`](Self::generate_report)
+    pub fn generate_html(&self) -> String {
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>xNode Inventory Cost Report</title>\n\
+             <style>\n\
+             body { font-family: sans-serif; margin: 2em; }\n\
+             table { border-collapse: collapse; margin-bottom: 1.5em; }\n\
+             th, td { border: 1px solid #ccc; padding: 4px 12px; }\n\
+             td.cost { text-align: right; }\n\
+             </style>\n</head>\n<body>\n",
+        );
+        html.push_str("<h1>xNode Inventory Cost Report</h1>\n");
+        html.push_str(&format!(
+            "<p>Generated: {}</p>\n",
+            Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
+        ));
+
+        html.push_str("<h2>Summary</h2>\n<table>\n");
+        for (label, value) in [
+            ("Active xNodes", self.active_count.to_string()),
+            ("Total xNodes", self.total_count.to_string()),
+            ("Hourly", format!("${:.2}", self.total_hourly)),
+            ("Daily", format!("${:.2}", self.total_daily)),
+            ("Monthly", format!("${:.2}", self.total_monthly)),
+            ("Annual (proj.)", format!("${:.2}", self.projected_annual)),
+        ] {
+            html.push_str(&format!("<tr><th>{}</th><td class=\"cost\">{}</td></tr>\n", label, value));
+        }
+        html.push_str("</table>\n");
+
+        for (title, column, costs) in [
+            ("By Provider", "Provider", &self.by_provider),
+            ("By Region", "Region", &self.by_region),
+        ] {
+            html.push_str(&format!("<h2>{}</h2>\n", title));
+            if costs.is_empty() {
+                html.push_str("<p>No data available</p>\n");
+                continue;
+            }
+            html.push_str(&format!("<table>\n<tr><th>{}</th><th>Hourly</th></tr>\n", column));
+            for (name, cost) in breakdown(costs) {
+                html.push_str(&format!(
+                    "<tr><td>{}</td><td class=\"cost\">${:.2}</td></tr>\n",
+                    escape_html(name),
+                    cost
+                ));
+            }
+            html.push_str("</table>\n");
+        }
+
+        if let Some(bandwidth) = &self.bandwidth {
+            html.push_str("<h2>Estimated Bandwidth (monthly)</h2>\n");
+            html.push_str("<p>Not included in the compute totals above.</p>\n<table>\n");
+            let total = format!("${:.2}/month", self.total_monthly + bandwidth.monthly);
+            for (label, value) in bandwidth.summary_rows().into_iter().chain([("Compute + bandwidth (est.)", total)]) {
+                html.push_str(&format!("<tr><th>{}</th><td class=\"cost\">{}</td></tr>\n", label, value));
+            }
+            html.push_str(&format!("</table>\n<p><em>{}</em></p>\n", escape_html(&bandwidth.note())));
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    /// Render the report in `format`
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Text => self.generate_report(),
+            ReportFormat::Markdown => self.generate_markdown(),
+            ReportFormat::Html => self.generate_html(),
+        }
+    }
+}
+
+/// Output format for `cost-report`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportFormat {
+    #[default]
+    Text,
+    Markdown,
+    Html,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" | "txt" => Ok(Self::Text),
+            "md" | "markdown" => Ok(Self::Markdown),
+            "html" | "htm" => Ok(Self::Html),
+            other => Err(format!("unknown report format '{}' (expected text, md or html)", other)),
+        }
+    }
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Text => "text",
+            Self::Markdown => "md",
+            Self::Html => "html",
+        };
+        f.write_str(name)
+    }
+}
+
+/// What `cost-report --group-by` sums running costs over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostGrouping {
+    Provider,
+    Region,
+    Tag,
+}
+
+impl FromStr for CostGrouping {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "provider" => Ok(Self::Provider),
+            "region" => Ok(Self::Region),
+            "tag" => Ok(Self::Tag),
+            other => Err(format!("unknown grouping '{}' (expected provider, region or tag)", other)),
+        }
+    }
+}
+
+impl fmt::Display for CostGrouping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Provider => "provider",
+            Self::Region => "region",
+            Self::Tag => "tag",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Running cost of the xNodes in one group
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupCost {
+    pub group: String,
+    pub nodes: usize,
+    pub hourly: f64,
+    pub monthly: f64,
+}
+
+impl GroupCost {
+    /// Rank `(group, (nodes, hourly))` totals, most expensive first (ties by name)
+    pub fn ranked(totals: BTreeMap<String, (usize, f64)>) -> Vec<Self> {
+        let mut groups: Vec<Self> = totals
+            .into_iter()
+            .map(|(group, (nodes, hourly))| Self {
+                group,
+                nodes,
+                hourly,
+                monthly: hourly * 24.0 * 30.0,
+            })
+            .collect();
+        groups.sort_by(|a, b| b.hourly.partial_cmp(&a.hourly).unwrap().then_with(|| a.group.cmp(&b.group)));
+        groups
+    }
+}
+
+/// Cost breakdown entries, most expensive first (ties by name)
+fn breakdown(costs: &BTreeMap<String, f64>) -> Vec<(&String, &f64)> {
+    let mut entries: Vec<_> = costs.iter().collect();
+    entries.sort_by(|a, b| b.1.partial_cmp(a.1).unwrap());
+    entries
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -100,6 +442,9 @@ pub struct DeploymentRecord {
     pub name: Option<String>,
     #[serde(default)]
     pub tags: Vec<String>,
+    /// Why the xNode was torn down, e.g. "cost cleanup" or "migrated"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub termination_reason: Option<String>,
 }
 
 impl DeploymentRecord {
@@ -123,6 +468,7 @@ impl DeploymentRecord {
             region,
             name,
             tags,
+            termination_reason: None,
         }
     }
 
@@ -137,6 +483,174 @@ impl DeploymentRecord {
     }
 }
 
+/// What one xNode has cost so far and will cost at its current hourly rate
+#[derive(Debug, Clone, Serialize)]
+pub struct CostForecast {
+    pub xnode_id: String,
+    pub cost_hourly: f64,
+    pub deployed_at: DateTime<Utc>,
+    pub uptime_hours: f64,
+    /// Spent since deployment
+    pub accrued: f64,
+    pub daily: f64,
+    pub weekly: f64,
+    pub monthly: f64,
+    pub annual: f64,
+    /// Spend within the current calendar month once it ends, if the
+    /// xNode keeps running
+    pub month_end: f64,
+}
+
+impl CostForecast {
+    /// Project an active deployment at `cost_hourly`; `now` picks the
+    /// calendar month for `month_end`
+    pub fn new(record: &DeploymentRecord, cost_hourly: f64, now: DateTime<Utc>) -> Self {
+        let uptime_hours = record.calculate_uptime().max(0.0);
+        let (month_start, next_month) = month_bounds(now);
+        let billed_from = record.deployed_at.max(month_start);
+        let month_hours = (next_month - billed_from).num_seconds().max(0) as f64 / 3600.0;
+
+        Self {
+            xnode_id: record.xnode_id.clone(),
+            cost_hourly,
+            deployed_at: record.deployed_at,
+            uptime_hours,
+            accrued: uptime_hours * cost_hourly,
+            daily: cost_hourly * 24.0,
+            weekly: cost_hourly * 24.0 * 7.0,
+            monthly: cost_hourly * 24.0 * 30.0,
+            annual: cost_hourly * 24.0 * 365.0,
+            month_end: month_hours * cost_hourly,
+        }
+    }
+}
+
+/// Start of `now`'s calendar month and of the next one, in UTC
+fn month_bounds(now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+    use chrono::Datelike;
+
+    let first = NaiveDate::from_ymd_opt(now.year(), now.month(), 1).expect("valid month");
+    let next = first.checked_add_months(chrono::Months::new(1)).expect("valid month");
+    let midnight = |d: NaiveDate| d.and_time(chrono::NaiveTime::MIN).and_utc();
+    (midnight(first), midnight(next))
+}
+
+/// Total monthly run-rate at the end of one day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostSample {
+    pub date: NaiveDate,
+    pub monthly: f64,
+}
+
+/// Daily time series of total run-rate, used as a baseline for spotting jumps
+#[derive(Debug, Clone)]
+pub struct CostLog {
+    path: PathBuf,
+    samples: Vec<CostSample>,
+}
+
+impl CostLog {
+    pub fn load(path: &Path) -> Result<Self> {
+        let samples = if path.exists() {
+            let contents = fs::read_to_string(path).context("Failed to read cost log")?;
+            serde_json::from_str(&contents).context("Failed to parse cost log")?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            samples,
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.samples)?;
+        fs::write(&self.path, json).context("Failed to write cost log")?;
+        Ok(())
+    }
+
+    pub fn samples(&self) -> &[CostSample] {
+        &self.samples
+    }
+
+    /// Record the run-rate for `date`, replacing any earlier sample that day
+    pub fn record(&mut self, date: NaiveDate, monthly: f64) {
+        self.samples.retain(|s| s.date != date);
+        self.samples.push(CostSample { date, monthly });
+        self.samples.sort_by_key(|s| s.date);
+
+        let cutoff = date - chrono::Duration::days(COST_LOG_RETENTION_DAYS);
+        self.samples.retain(|s| s.date > cutoff);
+    }
+
+    /// Average run-rate over the `days` days before `today`. A day without a
+    /// sample carries the last known value forward, since the log is only
+    /// written when the inventory changes.
+    pub fn baseline(&self, today: NaiveDate, days: u32) -> Option<f64> {
+        let values: Vec<f64> = (1..=days as i64)
+            .filter_map(|offset| {
+                let day = today - chrono::Duration::days(offset);
+                self.samples
+                    .iter()
+                    .rev()
+                    .find(|s| s.date <= day)
+                    .map(|s| s.monthly)
+            })
+            .collect();
+
+        if values.is_empty() {
+            return None;
+        }
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+
+    /// Compare `current_monthly` against the baseline, flagging increases of
+    /// at least `threshold_percent`
+    pub fn detect_anomaly(
+        &self,
+        current_monthly: f64,
+        today: NaiveDate,
+        window_days: u32,
+        threshold_percent: f64,
+    ) -> Option<CostAnomaly> {
+        let baseline = self.baseline(today, window_days)?;
+        if baseline <= 0.0 {
+            return None;
+        }
+
+        let increase_percent = (current_monthly - baseline) / baseline * 100.0;
+        if increase_percent < threshold_percent {
+            return None;
+        }
+
+        Some(CostAnomaly {
+            current_monthly,
+            baseline_monthly: baseline,
+            window_days,
+            increase_percent,
+        })
+    }
+}
+
+/// Spend that jumped beyond the configured threshold
+#[derive(Debug, Clone, Serialize)]
+pub struct CostAnomaly {
+    pub current_monthly: f64,
+    pub baseline_monthly: f64,
+    pub window_days: u32,
+    pub increase_percent: f64,
+}
+
+impl CostAnomaly {
+    pub fn describe(&self) -> String {
+        format!(
+            "spend up {:.0}% vs. {}-day average (${:.2}/month vs. ${:.2}/month)",
+            self.increase_percent, self.window_days, self.current_monthly, self.baseline_monthly
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -145,8 +659,8 @@ mod tests {
     fn test_cost_report_calculations() {
         let report = CostReport::new(
             10.0,
-            HashMap::new(),
-            HashMap::new(),
+            BTreeMap::new(),
+            BTreeMap::new(),
             5,
             10,
         );
@@ -157,6 +671,69 @@ mod tests {
         assert_eq!(report.projected_annual, 87600.0);
     }
 
+    #[test]
+    fn test_bandwidth_estimate() {
+        let node = |provider: &str, egress_tb: f64, included_tb: f64, overage_per_tb: f64| NodeTransfer {
+            provider: provider.to_string(),
+            egress_tb,
+            included_tb,
+            overage_per_tb,
+        };
+        let estimate = BandwidthEstimate::from_nodes(
+            &[node("aws", 2.0, 0.0, 90.0), node("vultr", 3.0, 2.0, 10.0), node("vultr", 0.5, 1.0, 10.0)],
+            1,
+        );
+
+        assert!((estimate.overage_tb - 3.0).abs() < 1e-9);
+        assert!((estimate.monthly - 190.0).abs() < 1e-9);
+        assert_eq!(estimate.by_provider["vultr"], 10.0);
+
+        let report = CostReport::new(1.0, BTreeMap::new(), BTreeMap::new(), 3, 4).with_bandwidth(estimate);
+        assert_eq!(report.total_monthly, 720.0);
+        let text = report.render(ReportFormat::Text);
+        assert!(text.contains("Overage cost (est.):  $190.00/month"));
+        assert!(text.contains("Compute + bandwidth (est.): $910.00/month"));
+        assert!(text.contains("1 xNode(s) with unknown plans are not included"));
+        assert!(report.render(ReportFormat::Markdown).contains("| Overage | 3.00 TB |"));
+        assert!(!CostReport::new(1.0, BTreeMap::new(), BTreeMap::new(), 0, 0)
+            .render(ReportFormat::Html)
+            .contains("Bandwidth"));
+    }
+
+    #[test]
+    fn test_deployment_record_loads_without_termination_reason() {
+        let json = r#"{"xnode_id":"a","provider":"vultr","template":"small",
+            "deployed_at":"2024-01-01T00:00:00Z","terminated_at":null,
+            "total_cost":0.0,"uptime_hours":0.0,"region":null,"name":null}"#;
+        let record: DeploymentRecord = serde_json::from_str(json).unwrap();
+        assert!(record.termination_reason.is_none());
+        assert!(!serde_json::to_string(&record).unwrap().contains("termination_reason"));
+    }
+
+    #[test]
+    fn test_report_formats_share_breakdown() {
+        let report = CostReport::new(
+            0.05,
+            BTreeMap::from([("vultr".to_string(), 0.01), ("linode".to_string(), 0.04)]),
+            BTreeMap::from([("<ams>".to_string(), 0.05)]),
+            2,
+            3,
+        );
+
+        let markdown = report.render(ReportFormat::Markdown);
+        assert!(markdown.contains("| Monthly | $36.00 |"));
+        let linode = markdown.find("| linode | $0.04 |").unwrap();
+        assert!(linode < markdown.find("| vultr | $0.01 |").unwrap());
+
+        let html = report.render(ReportFormat::Html);
+        assert!(html.contains("<tr><td>linode</td><td class=\"cost\">$0.04</td></tr>"));
+        assert!(html.contains("&lt;ams&gt;"));
+        assert!(html.find(">linode<").unwrap() < html.find(">vultr<").unwrap());
+
+        assert_eq!("MD".parse::<ReportFormat>().unwrap(), ReportFormat::Markdown);
+        assert!("pdf".parse::<ReportFormat>().is_err());
+    }
+
     #[test]
     fn test_deployment_record_uptime() {
         let now = Utc::now();
@@ -175,4 +752,76 @@ mod tests {
         let uptime = record.calculate_uptime();
         assert!((uptime - 5.0).abs() < 0.1);
     }
+
+    #[test]
+    fn test_cost_forecast() {
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let now = at("2024-02-10T00:00:00Z");
+
+        let record = DeploymentRecord::new(
+            "x-1".to_string(), "vultr".to_string(), "small".to_string(),
+            at("2024-02-05T00:00:00Z"), None, None, vec![],
+        );
+        let forecast = CostForecast::new(&record, 0.5, now);
+        assert_eq!(forecast.daily, 12.0);
+        assert_eq!(forecast.weekly, 84.0);
+        assert_eq!(forecast.monthly, 360.0);
+        assert_eq!(forecast.annual, 4380.0);
+        // Feb 5 to Mar 1 in a leap year: 25 days
+        assert!((forecast.month_end - 25.0 * 12.0).abs() < 1e-9);
+        assert!((forecast.accrued - forecast.uptime_hours * 0.5).abs() < 1e-9);
+
+        // Deployed before this month: billed from the 1st
+        let old = DeploymentRecord { deployed_at: at("2023-12-20T00:00:00Z"), ..record };
+        assert!((CostForecast::new(&old, 0.5, now).month_end - 29.0 * 12.0).abs() < 1e-9);
+
+        let (start, next) = month_bounds(at("2024-12-31T23:00:00Z"));
+        assert_eq!((start, next), (at("2024-12-01T00:00:00Z"), at("2025-01-01T00:00:00Z")));
+    }
+
+    #[test]
+    fn test_cost_log_baseline_carries_forward() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cost_log.json");
+        let day = |d| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+
+        let mut log = CostLog::load(&path).unwrap();
+        log.record(day(1), 100.0);
+        log.record(day(4), 300.0);
+        log.record(day(4), 200.0);
+        log.save().unwrap();
+
+        let log = CostLog::load(&path).unwrap();
+        assert_eq!(log.samples().len(), 2);
+
+        // Days 1-3 at 100, days 4-7 at 200
+        let baseline = log.baseline(day(8), 7).unwrap();
+        assert!((baseline - 1100.0 / 7.0).abs() < 1e-9);
+        assert!(log.baseline(day(1), 7).is_none());
+    }
+
+    #[test]
+    fn test_detect_anomaly() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = CostLog::load(&dir.path().join("cost_log.json")).unwrap();
+        let today = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        log.record(today - chrono::Duration::days(8), 100.0);
+
+        let anomaly = log.detect_anomaly(140.0, today, 7, 25.0).unwrap();
+        assert!((anomaly.increase_percent - 40.0).abs() < 1e-9);
+        assert!(anomaly.describe().starts_with("spend up 40% vs. 7-day average"));
+
+        assert!(log.detect_anomaly(120.0, today, 7, 25.0).is_none());
+        assert!(log.detect_anomaly(50.0, today, 7, 25.0).is_none());
+    }
+
+    #[test]
+    fn test_cost_log_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = CostLog::load(&dir.path().join("cost_log.json")).unwrap();
+        let today = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        log.record(today - chrono::Duration::days(120), 1.0);
+        log.record(today, 2.0);
+        assert_eq!(log.samples().len(), 1);
+    }
 }
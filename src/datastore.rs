@@ -1,14 +1,173 @@
-use anyhow::Result;
-use flate2::write::{GzEncoder, GzDecoder};
+use anyhow::{Context, Result};
+use flate2::write::{GzEncoder, GzDecoder, ZlibDecoder, ZlibEncoder};
 use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sled::transaction::{ConflictableTransactionError, TransactionError};
 use sled::Db;
-use std::io::{Write, Read};
-use std::path::PathBuf;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::exit::CliError;
 
 const COMPRESSION_THRESHOLD: usize = 1024; // Compress values larger than 1KB
 
+/// Leading byte of every stored value, recording how it is encoded
+const TAG_RAW: u8 = 0x00;
+const TAG_GZIP: u8 = 0x1f;
+const TAG_ZLIB: u8 = 0x02;
+
+/// sled tree holding store settings, kept apart from user keys
+const META_TREE: &str = "__capsule_meta";
+const CONFIG_KEY: &str = "config";
+
+/// How long to wait for another capsule process to release the database
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Handle to the embedded key-value store.
+///
+/// sled makes a single handle safe to share between threads (clone it), and
+/// holds an exclusive file lock so only one process has the database open.
+/// Opening waits for that lock, so concurrent `capsule data` commands run
+/// one after another instead of failing.
+#[derive(Clone)]
 pub struct DataStore {
     db: Db,
+    config: DataStoreConfig,
+}
+
+/// Compression used for values written from now on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionAlgorithm {
+    #[default]
+    Gzip,
+    Zlib,
+}
+
+impl CompressionAlgorithm {
+    fn tag(&self) -> u8 {
+        match self {
+            Self::Gzip => TAG_GZIP,
+            Self::Zlib => TAG_ZLIB,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            TAG_GZIP => Some(Self::Gzip),
+            TAG_ZLIB => Some(Self::Zlib),
+            _ => None,
+        }
+    }
+
+    fn compress(&self, data: &[u8], level: u32) -> Result<Vec<u8>> {
+        let level = Compression::new(level);
+        Ok(match self {
+            Self::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), level);
+                encoder.write_all(data)?;
+                encoder.finish()?
+            }
+            Self::Zlib => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), level);
+                encoder.write_all(data)?;
+                encoder.finish()?
+            }
+        })
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(match self {
+            Self::Gzip => {
+                let mut decoder = GzDecoder::new(Vec::new());
+                decoder.write_all(data)?;
+                decoder.finish()?
+            }
+            Self::Zlib => {
+                let mut decoder = ZlibDecoder::new(Vec::new());
+                decoder.write_all(data)?;
+                decoder.finish()?
+            }
+        })
+    }
+}
+
+impl FromStr for CompressionAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "gzip" => Ok(Self::Gzip),
+            "zlib" => Ok(Self::Zlib),
+            other => Err(format!("unknown algorithm '{}' (expected gzip or zlib)", other)),
+        }
+    }
+}
+
+impl fmt::Display for CompressionAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Gzip => "gzip",
+            Self::Zlib => "zlib",
+        })
+    }
+}
+
+/// Per-store settings, saved inside the database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataStoreConfig {
+    /// Values larger than this many bytes are compressed
+    pub compression_threshold: usize,
+    pub algorithm: CompressionAlgorithm,
+    /// Compression level, 0 (none) to 9 (smallest)
+    pub level: u32,
+}
+
+impl Default for DataStoreConfig {
+    fn default() -> Self {
+        Self {
+            compression_threshold: COMPRESSION_THRESHOLD,
+            algorithm: CompressionAlgorithm::default(),
+            level: Compression::default().level(),
+        }
+    }
+}
+
+/// Totals reported by `capsule data stats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CompressionStats {
+    pub values: usize,
+    pub compressed_values: usize,
+    /// Sum of value sizes as stored, excluding encoding tags
+    pub stored_bytes: usize,
+    /// Sum of value sizes as returned by `get`
+    pub original_bytes: usize,
+}
+
+/// One key's share of the store, for `capsule data stats --per-key`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KeyUsage {
+    pub key: String,
+    /// Value size as stored, excluding the encoding tag
+    pub stored_bytes: usize,
+    pub compressed: bool,
+    /// Fraction of all stored value bytes
+    pub share: f64,
+}
+
+impl CompressionStats {
+    /// Stored size as a fraction of the original (lower is better)
+    pub fn ratio(&self) -> f64 {
+        if self.original_bytes == 0 {
+            1.0
+        } else {
+            self.stored_bytes as f64 / self.original_bytes as f64
+        }
+    }
 }
 
 impl DataStore {
@@ -16,10 +175,31 @@ impl DataStore {
         let data_dir = Self::get_data_dir()?;
         std::fs::create_dir_all(&data_dir)?;
 
-        let db_path = data_dir.join("capsule.db");
-        let db = sled::open(&db_path)?;
+        Self::open(&data_dir.join("capsule.db"))
+    }
 
-        Ok(Self { db })
+    /// Open the store at `path`, waiting while another process holds it
+    pub fn open(path: &Path) -> Result<Self> {
+        let started = Instant::now();
+        loop {
+            match sled::open(path) {
+                Ok(db) => {
+                    let config = Self::load_config(&db)?;
+                    return Ok(Self { db, config });
+                }
+                Err(e) if is_lock_error(&e) && started.elapsed() < LOCK_TIMEOUT => {
+                    std::thread::sleep(LOCK_RETRY_INTERVAL);
+                }
+                Err(e) if is_lock_error(&e) => {
+                    anyhow::bail!(
+                        "Datastore {} is still in use by another capsule process after {}s",
+                        path.display(),
+                        LOCK_TIMEOUT.as_secs()
+                    );
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
     }
 
     fn get_data_dir() -> Result<PathBuf> {
@@ -28,59 +208,65 @@ impl DataStore {
         Ok(home.join(".capsule").join("data"))
     }
 
+    /// Compression settings in effect for new writes
+    pub fn config(&self) -> &DataStoreConfig {
+        &self.config
+    }
+
+    /// Change compression settings. Existing values keep the encoding they
+    /// were written with; `get` reads every encoding.
+    pub fn set_config(&mut self, config: DataStoreConfig) -> Result<()> {
+        if config.level > 9 {
+            return Err(CliError::usage(format!("Compression level must be 0-9, got {}", config.level)).into());
+        }
+
+        let meta = self.db.open_tree(META_TREE)?;
+        meta.insert(CONFIG_KEY, serde_json::to_vec(&config)?)?;
+        meta.flush()?;
+        self.config = config;
+        Ok(())
+    }
+
+    fn load_config(db: &Db) -> Result<DataStoreConfig> {
+        let meta = db.open_tree(META_TREE)?;
+        match meta.get(CONFIG_KEY)? {
+            Some(bytes) => Ok(serde_json::from_slice(&bytes)
+                .context("Failed to parse datastore config")?),
+            None => Ok(DataStoreConfig::default()),
+        }
+    }
+
     /// Store a key-value pair
     pub fn set(&self, key: &str, value: &[u8]) -> Result<()> {
-        let stored_value = if value.len() > COMPRESSION_THRESHOLD {
-            // Compress large values
-            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
-            encoder.write_all(value)?;
-            let compressed = encoder.finish()?;
-
-            // Prepend magic byte to indicate compression
-            let mut result = vec![0x1f]; // Magic byte for compressed data
-            result.extend_from_slice(&compressed);
-            result
-        } else {
-            // Small values stored as-is with different magic byte
-            let mut result = vec![0x00]; // Magic byte for uncompressed data
-            result.extend_from_slice(value);
-            result
-        };
-
+        let stored_value = self.encode(value)?;
         self.db.insert(key.as_bytes(), stored_value)?;
         self.db.flush()?;
         Ok(())
     }
 
-    /// Get a value by key
-    pub fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
-        if let Some(stored_value) = self.db.get(key.as_bytes())? {
-            let data = stored_value.to_vec();
-
-            if data.is_empty() {
-                return Ok(Some(Vec::new()));
+    /// Tag a value with its encoding, compressing it when it is over the
+    /// threshold and compression actually saves space
+    fn encode(&self, value: &[u8]) -> Result<Vec<u8>> {
+        if value.len() > self.config.compression_threshold {
+            let algorithm = self.config.algorithm;
+            let compressed = algorithm.compress(value, self.config.level)?;
+            if compressed.len() < value.len() {
+                let mut result = vec![algorithm.tag()];
+                result.extend_from_slice(&compressed);
+                return Ok(result);
             }
+        }
 
-            // Check magic byte
-            match data[0] {
-                0x1f => {
-                    // Compressed data
-                    let mut decoder = GzDecoder::new(Vec::new());
-                    decoder.write_all(&data[1..])?;
-                    let decompressed = decoder.finish()?;
-                    Ok(Some(decompressed))
-                }
-                0x00 => {
-                    // Uncompressed data
-                    Ok(Some(data[1..].to_vec()))
-                }
-                _ => {
-                    // Unknown format, return as-is (backwards compatibility)
-                    Ok(Some(data))
-                }
-            }
-        } else {
-            Ok(None)
+        let mut result = vec![TAG_RAW];
+        result.extend_from_slice(value);
+        Ok(result)
+    }
+
+    /// Get a value by key
+    pub fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match self.db.get(key.as_bytes())? {
+            Some(stored_value) => Ok(Some(decode(&stored_value)?)),
+            None => Ok(None),
         }
     }
 
@@ -91,6 +277,52 @@ impl DataStore {
         Ok(removed.is_some())
     }
 
+    /// Move a value to a new key in one transaction. The stored bytes are
+    /// moved as-is, so the value keeps its encoding.
+    pub fn rename(&self, old: &str, new: &str, overwrite: bool) -> Result<()> {
+        self.transfer(old, new, overwrite, true)
+    }
+
+    /// Duplicate a value under a new key, keeping its encoding
+    pub fn copy(&self, src: &str, dst: &str, overwrite: bool) -> Result<()> {
+        self.transfer(src, dst, overwrite, false)
+    }
+
+    fn transfer(&self, src: &str, dst: &str, overwrite: bool, remove_src: bool) -> Result<()> {
+        if src == dst {
+            return Err(CliError::usage("Source and destination keys are the same").into());
+        }
+
+        let result = self.db.transaction(|tx| {
+            let value = tx
+                .get(src.as_bytes())?
+                .ok_or(ConflictableTransactionError::Abort(CliError::not_found(format!(
+                    "Key '{}' not found",
+                    src
+                ))))?;
+            if !overwrite && tx.get(dst.as_bytes())?.is_some() {
+                return Err(ConflictableTransactionError::Abort(CliError::usage(format!(
+                    "Key '{}' already exists; use --overwrite to replace it",
+                    dst
+                ))));
+            }
+
+            tx.insert(dst.as_bytes(), value)?;
+            if remove_src {
+                tx.remove(src.as_bytes())?;
+            }
+            Ok(())
+        });
+
+        match result {
+            Ok(()) => {}
+            Err(TransactionError::Abort(e)) => return Err(e.into()),
+            Err(TransactionError::Storage(e)) => return Err(e.into()),
+        }
+        self.db.flush()?;
+        Ok(())
+    }
+
     /// List all keys
     pub fn list_keys(&self) -> Result<Vec<String>> {
         let mut keys = Vec::new();
@@ -110,7 +342,7 @@ impl DataStore {
         for item in self.db.iter() {
             let (key, value) = item?;
             if let Ok(key_str) = String::from_utf8(key.to_vec()) {
-                let compressed = !value.is_empty() && value[0] == 0x1f;
+                let compressed = value.first().and_then(|t| CompressionAlgorithm::from_tag(*t)).is_some();
                 let size = value.len() - 1; // Subtract magic byte
                 items.push((key_str, size, compressed));
             }
@@ -119,6 +351,23 @@ impl DataStore {
         Ok(items)
     }
 
+    /// Stored size of every key, largest first
+    pub fn key_usage(&self) -> Result<Vec<KeyUsage>> {
+        let items = self.list_all()?;
+        let total: usize = items.iter().map(|(_, size, _)| size).sum();
+        let mut usage: Vec<KeyUsage> = items
+            .into_iter()
+            .map(|(key, stored_bytes, compressed)| KeyUsage {
+                key,
+                stored_bytes,
+                compressed,
+                share: if total == 0 { 0.0 } else { stored_bytes as f64 / total as f64 },
+            })
+            .collect();
+        usage.sort_by(|a, b| b.stored_bytes.cmp(&a.stored_bytes).then_with(|| a.key.cmp(&b.key)));
+        Ok(usage)
+    }
+
     /// Store a file
     pub fn set_file(&self, key: &str, file_path: &std::path::Path) -> Result<()> {
         let data = std::fs::read(file_path)?;
@@ -143,6 +392,22 @@ impl DataStore {
         Ok((count, size_on_disk as usize))
     }
 
+    /// Stored versus original size across all values
+    pub fn compression_stats(&self) -> Result<CompressionStats> {
+        let mut stats = CompressionStats::default();
+        for item in self.db.iter() {
+            let (_, value) = item?;
+            let original = decode(&value)?.len();
+            stats.values += 1;
+            stats.original_bytes += original;
+            stats.stored_bytes += value.len().saturating_sub(1);
+            if value.first().and_then(|t| CompressionAlgorithm::from_tag(*t)).is_some() {
+                stats.compressed_values += 1;
+            }
+        }
+        Ok(stats)
+    }
+
     /// Clear all data
     pub fn clear(&self) -> Result<usize> {
         let count = self.db.len();
@@ -152,13 +417,19 @@ impl DataStore {
     }
 
     /// Export database to a directory
-    pub fn export(&self, output_dir: &std::path::Path) -> Result<usize> {
+    ///
+    /// With `key_pattern`, only keys matching that glob are exported (see
+    /// [`crate::glob`]).
+    pub fn export(&self, output_dir: &std::path::Path, key_pattern: Option<&str>) -> Result<usize> {
         std::fs::create_dir_all(output_dir)?;
         let mut count = 0;
 
         for item in self.db.iter() {
             let (key, _) = item?;
             if let Ok(key_str) = String::from_utf8(key.to_vec()) {
+                if key_pattern.is_some_and(|p| !crate::glob::matches(p, &key_str)) {
+                    continue;
+                }
                 if let Some(data) = self.get(&key_str)? {
                     let safe_filename = key_str.replace(['/', '\\', ':'], "_");
                     let output_path = output_dir.join(safe_filename);
@@ -171,3 +442,170 @@ impl DataStore {
         Ok(count)
     }
 }
+
+/// Decode a stored value according to its tag
+fn decode(data: &[u8]) -> Result<Vec<u8>> {
+    match data.split_first() {
+        None => Ok(Vec::new()),
+        Some((&TAG_RAW, rest)) => Ok(rest.to_vec()),
+        // Untagged values from older versions may start with a tag byte by
+        // chance; if they don't decompress, they are returned as-is
+        Some((tag, rest)) => match CompressionAlgorithm::from_tag(*tag) {
+            Some(algorithm) => Ok(algorithm.decompress(rest).unwrap_or_else(|_| data.to_vec())),
+            // Unknown format, return as-is (backwards compatibility)
+            None => Ok(data.to_vec()),
+        },
+    }
+}
+
+/// sled reports a held database lock as a generic I/O error
+fn is_lock_error(err: &sled::Error) -> bool {
+    matches!(err, sled::Error::Io(e) if e.to_string().contains("could not acquire lock"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_concurrent_sets_from_threads() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DataStore::open(&dir.path().join("capsule.db")).unwrap();
+
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let store = store.clone();
+                std::thread::spawn(move || {
+                    for i in 0..50 {
+                        let value = format!("{}-{}", t, i).repeat(if i % 5 == 0 { 200 } else { 1 });
+                        store.set(&format!("thread-{}/key-{}", t, i), value.as_bytes()).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(store.list_keys().unwrap().len(), 400);
+        assert_eq!(store.get("thread-3/key-7").unwrap().unwrap(), b"3-7");
+        assert_eq!(store.get("thread-6/key-10").unwrap().unwrap(), "6-10".repeat(200).as_bytes());
+    }
+
+    #[test]
+    fn test_compression_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capsule.db");
+        let mut store = DataStore::open(&path).unwrap();
+        let text = "capsule ".repeat(100);
+
+        store.set("gzip", text.as_bytes()).unwrap();
+        store.set_config(DataStoreConfig {
+            compression_threshold: 16,
+            algorithm: CompressionAlgorithm::Zlib,
+            level: 9,
+        })
+        .unwrap();
+        store.set("zlib", text.as_bytes()).unwrap();
+        store.set("tiny", b"short value").unwrap();
+        // Random-looking data that doesn't shrink is stored raw
+        let noise: Vec<u8> = (0..64u32).map(|i| (i.wrapping_mul(2654435761) >> 24) as u8).collect();
+        store.set("noise", &noise).unwrap();
+
+        let stored = |key: &str| store.db.get(key).unwrap().unwrap()[0];
+        assert_eq!(stored("gzip"), TAG_RAW, "800 bytes is under the default threshold");
+        assert_eq!(stored("zlib"), TAG_ZLIB);
+        assert_eq!(stored("tiny"), TAG_RAW);
+        assert_eq!(stored("noise"), TAG_RAW);
+        assert_eq!(store.get("zlib").unwrap().unwrap(), text.as_bytes());
+        assert_eq!(store.get("noise").unwrap().unwrap(), noise);
+
+        let stats = store.compression_stats().unwrap();
+        assert_eq!(stats.values, 4);
+        assert_eq!(stats.compressed_values, 1);
+        assert!(stats.ratio() < 1.0);
+
+        // Settings persist and values written under any setting stay readable
+        drop(store);
+        let mut store = DataStore::open(&path).unwrap();
+        assert_eq!(store.config().algorithm, CompressionAlgorithm::Zlib);
+        assert!(store.set_config(DataStoreConfig { level: 12, ..DataStoreConfig::default() }).is_err());
+        store.set_config(DataStoreConfig::default()).unwrap();
+        assert_eq!(store.get("zlib").unwrap().unwrap(), text.as_bytes());
+        assert_eq!(store.list_keys().unwrap().len(), 4);
+
+        // Untagged legacy values that happen to start with a tag byte
+        for legacy in [&[TAG_ZLIB, b'h', b'i'][..], &[TAG_GZIP, 0x00, 0x01][..]] {
+            store.db.insert("legacy", legacy).unwrap();
+            assert_eq!(store.get("legacy").unwrap().unwrap(), legacy);
+        }
+    }
+
+    #[test]
+    fn test_key_usage_largest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DataStore::open(&dir.path().join("capsule.db")).unwrap();
+        store.set("small", b"abc").unwrap();
+        store.set("big", &[7u8; 900]).unwrap();
+        store.set("also-small", b"xyz").unwrap();
+        store.set("squeezed", "hello ".repeat(1000).as_bytes()).unwrap();
+
+        let usage = store.key_usage().unwrap();
+        let keys: Vec<&str> = usage.iter().map(|u| u.key.as_str()).collect();
+        assert_eq!(keys[0], "big");
+        assert_eq!(&keys[2..], ["also-small", "small"]);
+        assert!(usage[1].compressed && !usage[0].compressed);
+        assert!((usage.iter().map(|u| u.share).sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rename_and_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DataStore::open(&dir.path().join("capsule.db")).unwrap();
+        let big = "blob ".repeat(500);
+        store.set("big", big.as_bytes()).unwrap();
+        store.set("other", b"x").unwrap();
+
+        store.copy("big", "big-copy", false).unwrap();
+        store.rename("big", "moved", false).unwrap();
+        assert!(store.get("big").unwrap().is_none());
+        assert_eq!(store.get("moved").unwrap().unwrap(), big.as_bytes());
+        assert_eq!(store.db.get("big-copy").unwrap(), store.db.get("moved").unwrap());
+        assert_eq!(store.db.get("moved").unwrap().unwrap()[0], TAG_GZIP);
+
+        let exit_code = |r: Result<()>| crate::exit::exit_code_for(&r.unwrap_err());
+        assert_eq!(exit_code(store.rename("moved", "other", false)), crate::exit::USAGE);
+        assert_eq!(exit_code(store.copy("missing", "new", false)), crate::exit::NOT_FOUND);
+        assert_eq!(store.get("other").unwrap().unwrap(), b"x");
+
+        store.rename("moved", "other", true).unwrap();
+        assert_eq!(store.get("other").unwrap().unwrap(), big.as_bytes());
+        assert_eq!(store.list_keys().unwrap(), ["big-copy", "other"]);
+    }
+
+    #[test]
+    fn test_open_waits_for_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("capsule.db");
+        let first = DataStore::open(&path).unwrap();
+
+        let (opened_tx, opened_rx) = mpsc::channel();
+        let waiter = {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                let store = DataStore::open(&path).unwrap();
+                opened_tx.send(()).unwrap();
+                store.get("held").unwrap()
+            })
+        };
+
+        // sled's lock is per file handle, so a second open blocks even
+        // within one process until the first handle is dropped
+        assert!(opened_rx.recv_timeout(Duration::from_millis(200)).is_err());
+        first.set("held", b"yes").unwrap();
+        drop(first);
+
+        assert_eq!(waiter.join().unwrap().unwrap(), b"yes");
+    }
+}
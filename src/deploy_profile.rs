@@ -0,0 +1,184 @@
+// Saved deploy settings ("deploy profiles")
+//
+// `capsule openmesh deploy-profile save <name>` stores provider, template,
+// region, sizing, tags and extras in `~/.capsule/deploy-profiles.yml`;
+// `xnode deploy --profile <name>` starts from them, with explicit flags
+// taking precedence.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Deploy flags that can be saved in a profile
+#[derive(clap::Args, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeploySettings {
+    /// Provider name (e.g., hivelocity, digitalocean)
+    #[arg(short, long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+
+    /// Template ID
+    #[arg(short, long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+
+    /// Region
+    #[arg(short, long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+
+    /// Maximum hourly budget
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<f64>,
+
+    /// Minimum CPU cores
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_cpu: Option<u32>,
+
+    /// Minimum memory (GB)
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_memory: Option<u32>,
+
+    /// Tag to record on the xNode (repeatable)
+    #[arg(long = "tag", value_name = "TAG")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
+    /// Provider-specific setting, e.g. subnet_id=subnet-0abc (repeatable)
+    #[arg(long = "extra", value_name = "KEY=VALUE")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra: Vec<String>,
+}
+
+impl DeploySettings {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// These settings with gaps filled from `defaults`. Tags given here
+    /// replace the saved ones; extras are merged, keys given here winning.
+    pub fn or(self, defaults: &DeploySettings) -> DeploySettings {
+        let tags = if self.tags.is_empty() { defaults.tags.clone() } else { self.tags };
+        // parse_extra keeps the last value for a key, so ours go last
+        let extra = defaults.extra.iter().cloned().chain(self.extra).collect();
+
+        DeploySettings {
+            provider: self.provider.or_else(|| defaults.provider.clone()),
+            template: self.template.or_else(|| defaults.template.clone()),
+            region: self.region.or_else(|| defaults.region.clone()),
+            budget: self.budget.or(defaults.budget),
+            min_cpu: self.min_cpu.or(defaults.min_cpu),
+            min_memory: self.min_memory.or(defaults.min_memory),
+            tags,
+            extra,
+        }
+    }
+}
+
+/// Deploy profiles by name, persisted in `deploy-profiles.yml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeployProfiles {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, DeploySettings>,
+}
+
+impl DeployProfiles {
+    pub fn default_path() -> PathBuf {
+        crate::config::get_capsule_dir().join("deploy-profiles.yml")
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut profiles: Self = if path.exists() {
+            let contents = fs::read_to_string(path).context("Failed to read deploy profiles")?;
+            serde_yaml::from_str(&contents)
+                .with_context(|| format!("Failed to parse {}", path.display()))?
+        } else {
+            Self::default()
+        };
+        profiles.path = path.to_path_buf();
+        Ok(profiles)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let yaml = serde_yaml::to_string(self)?;
+        fs::write(&self.path, yaml).context("Failed to write deploy profiles")?;
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&DeploySettings> {
+        self.profiles.get(name)
+    }
+
+    /// Store `settings` under `name`, returning whether it replaced a profile
+    pub fn insert(&mut self, name: &str, settings: DeploySettings) -> bool {
+        self.profiles.insert(name.to_string(), settings).is_some()
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<DeploySettings> {
+        self.profiles.remove(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn web() -> DeploySettings {
+        DeploySettings {
+            provider: Some("hivelocity".into()),
+            template: Some("small".into()),
+            region: Some("tpa1".into()),
+            tags: vec!["team:web".into()],
+            extra: vec!["os=ubuntu".into(), "vlan=10".into()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_explicit_flags_override_profile() {
+        let explicit = DeploySettings {
+            region: Some("lax1".into()),
+            budget: Some(0.5),
+            extra: vec!["vlan=20".into()],
+            ..Default::default()
+        };
+
+        let merged = explicit.or(&web());
+        assert_eq!(merged.provider.as_deref(), Some("hivelocity"));
+        assert_eq!(merged.template.as_deref(), Some("small"));
+        assert_eq!(merged.region.as_deref(), Some("lax1"));
+        assert_eq!(merged.budget, Some(0.5));
+        assert_eq!(merged.tags, ["team:web"]);
+        assert_eq!(merged.extra, ["os=ubuntu", "vlan=10", "vlan=20"]);
+
+        let retagged = DeploySettings { tags: vec!["team:api".into()], ..Default::default() }.or(&web());
+        assert_eq!(retagged.tags, ["team:api"]);
+    }
+
+    #[test]
+    fn test_profiles_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("deploy-profiles.yml");
+
+        let mut profiles = DeployProfiles::load(&path).unwrap();
+        assert!(!profiles.insert("web", web()));
+        assert!(profiles.insert("web", web()));
+        profiles.save().unwrap();
+
+        let mut reloaded = DeployProfiles::load(&path).unwrap();
+        assert_eq!(reloaded.get("web"), Some(&web()));
+        assert!(reloaded.remove("web").is_some());
+        assert!(reloaded.get("web").is_none());
+    }
+}
@@ -0,0 +1,106 @@
+// Process exit codes for the capsule CLI
+//
+// Scripts and CI jobs rely on these values, so treat them as a stable
+// interface:
+//
+//   0  success (including informational results such as "no keys stored")
+//   1  generic failure (I/O, network, provider or parse errors)
+//   2  usage error (invalid arguments, refused operation, missing --confirm)
+//   3  not found (unknown profile, key, snapshot, ...)
+// 130  interrupted by SIGINT/SIGTERM before finishing
+
+use thiserror::Error;
+
+/// Command completed successfully
+pub const SUCCESS: i32 = 0;
+
+/// Generic failure
+pub const FAILURE: i32 = 1;
+
+/// The command was invoked incorrectly or the operation was refused
+pub const USAGE: i32 = 2;
+
+/// The requested resource does not exist
+pub const NOT_FOUND: i32 = 3;
+
+/// Stopped early by SIGINT/SIGTERM (128 + SIGINT, as shells report it)
+pub const INTERRUPTED: i32 = 130;
+
+/// Errors that map to a specific, non-generic exit code
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error("{0}")]
+    Usage(String),
+
+    #[error("{0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Interrupted(String),
+}
+
+impl CliError {
+    pub fn usage(message: impl Into<String>) -> Self {
+        Self::Usage(message.into())
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound(message.into())
+    }
+
+    pub fn interrupted(message: impl Into<String>) -> Self {
+        Self::Interrupted(message.into())
+    }
+
+    /// Exit code for this error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Usage(_) => USAGE,
+            Self::NotFound(_) => NOT_FOUND,
+            Self::Interrupted(_) => INTERRUPTED,
+        }
+    }
+}
+
+/// Map an error to the process exit code it should produce
+pub fn exit_code_for(err: &anyhow::Error) -> i32 {
+    err.chain()
+        .find_map(|cause| {
+            if cause.is::<crate::providers::DeployConfigError>() {
+                return Some(USAGE);
+            }
+            if let Some(e) = cause.downcast_ref::<crate::config::ConfigError>() {
+                return Some(e.exit_code());
+            }
+            cause.downcast_ref::<CliError>().map(CliError::exit_code)
+        })
+        .unwrap_or(FAILURE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_exit_code_for_cli_errors() {
+        let err = anyhow::Error::new(CliError::not_found("Key 'x' not found"));
+        assert_eq!(exit_code_for(&err), NOT_FOUND);
+
+        let err = anyhow::Error::new(CliError::usage("Use --confirm"));
+        assert_eq!(exit_code_for(&err), USAGE);
+
+        let err = anyhow::Error::new(CliError::interrupted("Interrupted after 1 of 3"));
+        assert_eq!(exit_code_for(&err), INTERRUPTED);
+    }
+
+    #[test]
+    fn test_exit_code_for_wrapped_and_generic_errors() {
+        let err: anyhow::Result<()> = Err(CliError::not_found("missing").into());
+        let err = err.context("Failed to load profile").unwrap_err();
+        assert_eq!(exit_code_for(&err), NOT_FOUND);
+
+        let err = anyhow::anyhow!("SCP transfer failed");
+        assert_eq!(exit_code_for(&err), FAILURE);
+    }
+}
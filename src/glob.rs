@@ -0,0 +1,123 @@
+// Minimal glob matching for paths and keys
+//
+// Supports `*` (any run of characters except `/`), `**` (any run of
+// characters including `/`), `?` (a single character other than `/`) and
+// `[abc]` / `[a-z]` character classes. A backslash makes the next
+// character literal, so `\*` matches a `*`.
+
+/// Check whether `text` matches the glob `pattern`
+pub fn matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    match_from(&pattern, &text)
+}
+
+/// Check whether `text` matches any of `patterns`
+pub fn matches_any<S: AsRef<str>>(patterns: &[S], text: &str) -> bool {
+    patterns.iter().any(|p| matches(p.as_ref(), text))
+}
+
+fn match_from(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') => {
+            if pattern.get(1) == Some(&'*') {
+                // `**/` also matches zero directories
+                let rest = &pattern[2..];
+                if rest.first() == Some(&'/') && match_from(&rest[1..], text) {
+                    return true;
+                }
+                (0..=text.len()).any(|i| match_from(rest, &text[i..]))
+            } else {
+                let rest = &pattern[1..];
+                for i in 0..=text.len() {
+                    if match_from(rest, &text[i..]) {
+                        return true;
+                    }
+                    if text.get(i) == Some(&'/') {
+                        break;
+                    }
+                }
+                false
+            }
+        }
+        Some('?') => {
+            matches!(text.first(), Some(c) if *c != '/') && match_from(&pattern[1..], &text[1..])
+        }
+        Some('[') => {
+            let Some(end) = pattern.iter().skip(1).position(|c| *c == ']').map(|p| p + 1) else {
+                return text.first() == Some(&'[') && match_from(&pattern[1..], &text[1..]);
+            };
+            let Some(c) = text.first() else { return false };
+            class_matches(&pattern[1..end], *c) && match_from(&pattern[end + 1..], &text[1..])
+        }
+        Some('\\') if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && match_from(&pattern[2..], &text[1..])
+        }
+        Some(p) => text.first() == Some(p) && match_from(&pattern[1..], &text[1..]),
+    }
+}
+
+fn class_matches(class: &[char], c: char) -> bool {
+    let (negate, class) = match class.first() {
+        Some('!') | Some('^') => (true, &class[1..]),
+        _ => (false, class),
+    };
+
+    let mut i = 0;
+    let mut found = false;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == '-' {
+            if class[i] <= c && c <= class[i + 2] {
+                found = true;
+            }
+            i += 3;
+        } else {
+            if class[i] == c {
+                found = true;
+            }
+            i += 1;
+        }
+    }
+
+    found != negate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_literal_and_wildcards() {
+        assert!(matches("/etc/shadow", "/etc/shadow"));
+        assert!(!matches("/etc/shadow", "/etc/shadow-"));
+        assert!(matches("/etc/shadow*", "/etc/shadow-"));
+        assert!(matches("/etc/ssh/ssh_host_*_key", "/etc/ssh/ssh_host_ed25519_key"));
+        assert!(!matches("/etc/ssh/ssh_host_*_key", "/etc/ssh/ssh_host_ed25519_key.pub"));
+        assert!(matches("config.?ml", "config.yml"));
+    }
+
+    #[test]
+    fn test_star_does_not_cross_directories() {
+        assert!(!matches("/etc/*", "/etc/ssl/private/key.pem"));
+        assert!(matches("/etc/ssl/private/**", "/etc/ssl/private/nested/key.pem"));
+        assert!(matches("**/*.key", "/etc/nginx/tls/site.key"));
+        assert!(matches("**/*.key", "site.key"));
+    }
+
+    #[test]
+    fn test_character_classes() {
+        assert!(matches("node-[0-9]", "node-7"));
+        assert!(!matches("node-[!0-9]", "node-7"));
+        assert!(matches("[abc]*", "backup"));
+        assert!(matches_any(&["x*", "b*"], "backup"));
+    }
+
+    #[test]
+    fn test_backslash_escapes() {
+        assert!(matches("deploy/\\*", "deploy/*"));
+        assert!(!matches("deploy/\\*", "deploy/web"));
+        assert!(matches("node\\[1\\]", "node[1]"));
+        assert!(matches("trailing\\", "trailing\\"));
+    }
+}
@@ -0,0 +1,270 @@
+// `capsule init`: guided first-time setup
+//
+// Asks for a profile name, the stacks to enable, an editor and optionally a
+// provider API key, then writes the profile, stores the key in
+// `providers.yml` and makes the profile active. `--non-interactive` takes
+// the same answers from flags for scripted installs.
+
+use anyhow::Result;
+use colored::*;
+use dialoguer::{Confirm, Input, MultiSelect, Password, Select};
+use std::io::IsTerminal;
+
+use capsule::config::{self, Config, ConfigError};
+use capsule::exit::CliError;
+use capsule::providers::ProviderManager;
+use capsule::ui::*;
+
+/// Answers given on the command line; in interactive mode they are the defaults
+#[derive(Debug, Default)]
+pub struct InitOptions {
+    pub profile: Option<String>,
+    pub stacks: Vec<String>,
+    pub editor: Option<String>,
+    pub provider: Option<String>,
+    pub api_key: Option<String>,
+    /// Take every answer from the options instead of prompting
+    pub non_interactive: bool,
+    /// Replace an existing profile of the same name
+    pub force: bool,
+}
+
+/// What `init` will write
+#[derive(Debug)]
+struct InitPlan {
+    profile: String,
+    config: Config,
+    /// Provider and API key to store in providers.yml
+    credentials: Option<(String, String)>,
+}
+
+pub fn handle_init_command(options: InitOptions) -> Result<()> {
+    let available = config::list_presets()?;
+    let mut manager = ProviderManager::new(None)?;
+    let providers = manager.list_providers();
+
+    let plan = if options.non_interactive {
+        plan_from_options(options, &available, &providers)?
+    } else {
+        if !std::io::stdin().is_terminal() {
+            return Err(CliError::usage("capsule init needs a terminal; use --non-interactive with flags instead").into());
+        }
+        prompt_plan(options, &available, &providers)?
+    };
+
+    config::save_config(&plan.config, Some(&plan.profile))?;
+    config::set_active_config_name(&plan.profile)?;
+    if let Some((provider, api_key)) = plan.credentials {
+        manager.configure_provider(provider, api_key)?;
+    }
+
+    println!();
+    success(&format!("Created profile '{}' and made it active", plan.profile));
+    println!(
+        "  {} Stacks: {}",
+        "▸".cyan(),
+        plan.config.presets.join(", ").green()
+    );
+    tip(&format!("Install packages with: {}", "capsule setup".cyan().bold()));
+    Ok(())
+}
+
+/// Build the plan from flags alone, rejecting anything a prompt would not allow
+fn plan_from_options(options: InitOptions, available: &[String], providers: &[String]) -> Result<InitPlan> {
+    let profile = options.profile.unwrap_or_else(|| "default".to_string());
+    check_profile_name(&profile, options.force)?;
+    let presets = check_stacks(&options.stacks, available)?;
+
+    let credentials = match (options.provider, options.api_key) {
+        (None, None) => None,
+        (Some(provider), Some(api_key)) if !api_key.trim().is_empty() => {
+            if !providers.contains(&provider) {
+                return Err(CliError::usage(format!(
+                    "Unknown provider '{}' (available: {})",
+                    provider,
+                    providers.join(", ")
+                ))
+                .into());
+            }
+            Some((provider, api_key))
+        }
+        _ => return Err(CliError::usage("--provider and --api-key must be given together").into()),
+    };
+
+    Ok(InitPlan {
+        profile,
+        config: new_profile(presets, options.editor),
+        credentials,
+    })
+}
+
+fn prompt_plan(options: InitOptions, available: &[String], providers: &[String]) -> Result<InitPlan> {
+    header("🌱 Welcome to Capsule");
+    println!("  Answer a few questions to create your first profile.\n");
+
+    let profile = loop {
+        let name: String = Input::new()
+            .with_prompt("Profile name")
+            .default(options.profile.clone().unwrap_or_else(|| "default".to_string()))
+            .interact_text()?;
+        let exists = config::list_all_configs()?.contains(&name);
+        if exists && !options.force {
+            let replace = Confirm::new()
+                .with_prompt(format!("Profile '{}' already exists. Replace it?", name))
+                .default(false)
+                .interact()?;
+            if !replace {
+                continue;
+            }
+        }
+        match check_profile_name(&name, true) {
+            Ok(()) => break name,
+            Err(e) => warning(&e.to_string()),
+        }
+    };
+
+    let stacks = if available.is_empty() {
+        println!("{} No stacks are installed; starting with the base stack", "ℹ".cyan());
+        Vec::new()
+    } else {
+        let defaults: Vec<bool> = available.iter().map(|s| options.stacks.contains(s)).collect();
+        let picked = MultiSelect::new()
+            .with_prompt("Stacks to enable (space to toggle, enter to confirm)")
+            .items(available)
+            .defaults(&defaults)
+            .interact()?;
+        picked.into_iter().map(|i| available[i].clone()).collect()
+    };
+
+    let editor: String = Input::new()
+        .with_prompt("Editor")
+        .default(options.editor.clone().unwrap_or_else(|| config::resolve_editor(&Config::default())))
+        .interact_text()?;
+
+    let mut credentials = None;
+    if !providers.is_empty()
+        && Confirm::new()
+            .with_prompt("Configure a provider API key now?")
+            .default(options.provider.is_some())
+            .interact()?
+    {
+        let default = options
+            .provider
+            .as_ref()
+            .and_then(|p| providers.iter().position(|name| name == p))
+            .unwrap_or(0);
+        let index = Select::new()
+            .with_prompt("Provider")
+            .items(providers)
+            .default(default)
+            .interact()?;
+        let api_key = match options.api_key {
+            Some(key) => key,
+            None => Password::new()
+                .with_prompt(format!("API key for {}", providers[index]))
+                .interact()?,
+        };
+        credentials = Some((providers[index].clone(), api_key));
+    }
+
+    Ok(InitPlan {
+        profile,
+        config: new_profile(stacks, Some(editor)),
+        credentials,
+    })
+}
+
+/// Built-in profiles are read-only; existing user profiles need `force`
+fn check_profile_name(name: &str, force: bool) -> Result<()> {
+    if name.trim().is_empty() || name.contains(['/', '\\']) {
+        return Err(CliError::usage(format!("Invalid profile name '{}'", name)).into());
+    }
+    if config::is_builtin_profile(name) {
+        return Err(ConfigError::BuiltinReadOnly(name.to_string()).into());
+    }
+    if !force && config::list_all_configs()?.iter().any(|p| p == name) {
+        return Err(CliError::usage(format!("Profile '{}' already exists; use --force to replace it", name)).into());
+    }
+    Ok(())
+}
+
+/// Stacks must be installed presets; `base` is always allowed
+fn check_stacks(stacks: &[String], available: &[String]) -> Result<Vec<String>> {
+    let unknown: Vec<&str> = stacks
+        .iter()
+        .filter(|s| s.as_str() != "base" && !available.contains(s))
+        .map(String::as_str)
+        .collect();
+    if !unknown.is_empty() {
+        return Err(CliError::usage(format!("Unknown stack(s): {}", unknown.join(", "))).into());
+    }
+    Ok(stacks.to_vec())
+}
+
+/// A profile with the base stack plus `stacks`
+fn new_profile(stacks: Vec<String>, editor: Option<String>) -> Config {
+    let mut presets = vec!["base".to_string()];
+    for stack in stacks {
+        if !presets.contains(&stack) {
+            presets.push(stack);
+        }
+    }
+    Config {
+        description: Some("Created by capsule init".to_string()),
+        presets,
+        custom_packages: Vec::new(),
+        editor: editor.filter(|e| !e.trim().is_empty()),
+        extends: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_plan_from_options() {
+        let options = InitOptions {
+            profile: Some("work".to_string()),
+            stacks: strings(&["rust", "base"]),
+            editor: Some("nano".to_string()),
+            provider: Some("vultr".to_string()),
+            api_key: Some("key".to_string()),
+            non_interactive: true,
+            force: true,
+        };
+        let plan = plan_from_options(options, &strings(&["python", "rust"]), &strings(&["vultr"])).unwrap();
+
+        assert_eq!(plan.profile, "work");
+        assert_eq!(plan.config.presets, strings(&["base", "rust"]));
+        assert_eq!(plan.config.editor.as_deref(), Some("nano"));
+        assert_eq!(plan.credentials, Some(("vultr".to_string(), "key".to_string())));
+    }
+
+    #[test]
+    fn test_plan_from_options_rejects_bad_input() {
+        let available = strings(&["rust"]);
+        let providers = strings(&["vultr"]);
+        let plan = |options: InitOptions| {
+            plan_from_options(InitOptions { force: true, ..options }, &available, &providers)
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert!(plan(InitOptions { stacks: strings(&["go"]), ..Default::default() }).contains("Unknown stack(s): go"));
+        assert!(plan(InitOptions { provider: Some("vultr".to_string()), ..Default::default() })
+            .contains("must be given together"));
+        assert!(plan(InitOptions {
+            provider: Some("nope".to_string()),
+            api_key: Some("key".to_string()),
+            ..Default::default()
+        })
+        .contains("Unknown provider 'nope'"));
+        assert!(plan(InitOptions { profile: Some("a/b".to_string()), ..Default::default() })
+            .contains("Invalid profile name"));
+    }
+}
@@ -1,13 +1,223 @@
-use crate::cost::{CostReport, DeploymentRecord};
-use crate::xnode::XNode;
+use crate::cost::{CostGrouping, CostLog, CostReport, DeploymentRecord, GroupCost};
+use crate::exit::CliError;
+use crate::xnode::{ConnectionSettings, XNode, META_EGRESS_TB_MONTHLY, META_SNAPSHOTS};
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::fs;
+use std::net::IpAddr;
+use std::str::FromStr;
 use std::path::PathBuf;
 
-const VERSION: &str = "1.0";
+const VERSION: &str = "1.1";
+
+/// Version assumed for inventory files written before versions were checked
+const FIRST_VERSION: &str = "1.0";
+
+/// One step in upgrading an inventory file: the version it applies to, the
+/// version it produces, and the edit to the raw JSON
+type Migration = (&'static str, &'static str, fn(&mut serde_json::Value) -> Result<()>);
+
+/// Upgrades applied in order by `load` until the file is at `VERSION`
+const MIGRATIONS: &[Migration] = &[
+    // 1.1 only started checking versions; the layout is unchanged
+    ("1.0", "1.1", |_| Ok(())),
+];
+
+/// Upgrade raw inventory JSON to `VERSION`, returning the version it was
+/// at, or `None` if it was already current
+fn migrate_inventory(data: &mut serde_json::Value) -> Result<Option<String>> {
+    let original = data
+        .get("version")
+        .and_then(serde_json::Value::as_str)
+        .unwrap_or(FIRST_VERSION)
+        .to_string();
+
+    let mut version = original.clone();
+    while version != VERSION {
+        let Some((_, to, step)) = MIGRATIONS.iter().find(|(from, _, _)| *from == version) else {
+            anyhow::bail!(
+                "Inventory file is version {}, which this capsule (inventory version {}) cannot read; upgrade capsule",
+                version,
+                VERSION
+            );
+        };
+        step(data).with_context(|| format!("Failed to migrate inventory from version {} to {}", version, to))?;
+        version = to.to_string();
+        data["version"] = serde_json::Value::from(version.clone());
+    }
+
+    Ok((version != original).then_some(original))
+}
+
+/// Daily cost log kept next to the inventory file
+const COST_LOG_FILE: &str = "cost_log.json";
+
+/// Cost group for running xNodes without tags
+pub const UNTAGGED: &str = "untagged";
+
+/// File formats for `xnode export` and `xnode import`. CSV is for
+/// spreadsheets and drops metadata; JSON and YAML round-trip every field.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+    Yaml,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            "yaml" | "yml" => Ok(Self::Yaml),
+            other => Err(format!("unknown format '{}' (expected csv, json or yaml)", other)),
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A point in time given on the command line: a duration back from now
+/// (`7d`, `24h`), a day (`2024-05-01`) or an RFC 3339 timestamp
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeBound {
+    Ago(chrono::Duration),
+    Day(chrono::NaiveDate),
+    At(DateTime<Utc>),
+}
+
+impl TimeBound {
+    /// Earliest instant covered; a day starts at midnight UTC
+    pub fn start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Self::Ago(ago) => now - *ago,
+            Self::Day(day) => day.and_time(chrono::NaiveTime::MIN).and_utc(),
+            Self::At(at) => *at,
+        }
+    }
+
+    /// First instant after the bound, so a day includes all of itself
+    pub fn end(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Self::Day(_) => self.start(now) + chrono::Duration::days(1),
+            _ => self.start(now),
+        }
+    }
+}
+
+impl FromStr for TimeBound {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(at) = DateTime::parse_from_rfc3339(s) {
+            return Ok(Self::At(at.with_timezone(&Utc)));
+        }
+        if let Ok(day) = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            return Ok(Self::Day(day));
+        }
+        crate::monitoring::history::parse_window(s)
+            .map(Self::Ago)
+            .map_err(|_| format!("invalid time '{}' (e.g. 7d, 24h, 2024-05-01 or 2024-05-01T12:00:00Z)", s))
+    }
+}
+
+/// Time bounds applied by `get_deployment_history`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HistoryWindow {
+    pub since: Option<TimeBound>,
+    pub until: Option<TimeBound>,
+    /// Match on `terminated_at` instead of `deployed_at`; active
+    /// deployments never match
+    pub by_termination: bool,
+}
+
+impl HistoryWindow {
+    fn contains(&self, record: &DeploymentRecord, now: DateTime<Utc>) -> bool {
+        let time = if self.by_termination {
+            match record.terminated_at {
+                Some(t) => t,
+                None => return false,
+            }
+        } else {
+            record.deployed_at
+        };
+        self.since.is_none_or(|since| time >= since.start(now))
+            && self.until.is_none_or(|until| time < until.end(now))
+    }
+}
+
+/// Lossless inventory export written by `export` in JSON or YAML
+#[derive(Debug, Serialize, Deserialize)]
+struct InventoryExport {
+    version: String,
+    exported_at: DateTime<Utc>,
+    xnodes: Vec<XNodeEntry>,
+}
+
+const CSV_HEADER: &str = "id,name,provider,status,ip_address,region,deployed_at,cost_hourly,tags";
+
+fn csv_row(xnode: &XNodeEntry) -> String {
+    let fields = [
+        xnode.id.clone(),
+        xnode.name.clone(),
+        xnode.provider.clone(),
+        xnode.status.clone(),
+        xnode.ip_address.clone(),
+        xnode.region.clone().unwrap_or_default(),
+        xnode.deployed_at.to_rfc3339(),
+        format!("{:.2}", xnode.cost_hourly),
+        xnode.tags.join(","),
+    ];
+    fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(",")
+}
+
+/// Quote a CSV field if it contains a comma, quote or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Split one CSV line into fields, honouring quoted fields
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct XNodeEntry {
@@ -25,6 +235,70 @@ pub struct XNodeEntry {
     pub tags: Vec<String>,
     #[serde(default)]
     pub metadata: HashMap<String, serde_json::Value>,
+    /// When `sync` or a health check last confirmed the xNode exists
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<DateTime<Utc>>,
+}
+
+/// Days without confirmation after which `list` and `show` flag an xNode
+/// as possibly stale
+pub const DEFAULT_STALE_AFTER_DAYS: u32 = 7;
+
+/// Short relative age such as "3d ago", "5h ago" or "just now"
+pub fn format_age(since: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let age = now - since;
+    if age.num_days() > 0 {
+        format!("{}d ago", age.num_days())
+    } else if age.num_hours() > 0 {
+        format!("{}h ago", age.num_hours())
+    } else if age.num_minutes() > 0 {
+        format!("{}m ago", age.num_minutes())
+    } else {
+        "just now".to_string()
+    }
+}
+
+impl XNodeEntry {
+    /// Whether nothing has confirmed this xNode within `days` of `now`.
+    /// Entries never seen count from when they were deployed.
+    pub fn is_stale(&self, days: u32, now: DateTime<Utc>) -> bool {
+        now - self.last_seen.unwrap_or(self.deployed_at) > chrono::Duration::days(days as i64)
+    }
+
+    /// "3d ago", or "never" for entries no sync or health check has confirmed
+    pub fn last_seen_label(&self, now: DateTime<Utc>) -> String {
+        self.last_seen.map(|seen| format_age(seen, now)).unwrap_or_else(|| "never".to_string())
+    }
+
+    /// Expected monthly egress in TB from metadata, or the default estimate
+    pub fn egress_tb_monthly(&self) -> f64 {
+        self.metadata
+            .get(META_EGRESS_TB_MONTHLY)
+            .and_then(|v| v.as_f64().or_else(|| v.as_str()?.trim().parse().ok()))
+            .filter(|tb| tb.is_finite() && *tb >= 0.0)
+            .unwrap_or(crate::cost::DEFAULT_EGRESS_TB_MONTHLY)
+    }
+
+    /// Connection settings derived from this entry's metadata
+    pub fn connection_settings(&self) -> ConnectionSettings {
+        ConnectionSettings::from_metadata(&self.metadata)
+    }
+
+    /// Provider snapshots recorded for this xNode
+    pub fn snapshots(&self) -> Vec<SnapshotRecord> {
+        self.metadata
+            .get(META_SNAPSHOTS)
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// A provider-side snapshot of an xNode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotRecord {
+    pub id: String,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,16 +322,20 @@ impl Default for InventoryMetadata {
 struct InventoryData {
     version: String,
     last_updated: DateTime<Utc>,
-    xnodes: HashMap<String, XNodeEntry>,
+    xnodes: BTreeMap<String, XNodeEntry>,
     history: Vec<DeploymentRecord>,
     metadata: InventoryMetadata,
 }
 
 pub struct XNodeInventory {
     inventory_file: PathBuf,
-    xnodes: HashMap<String, XNodeEntry>,
+    xnodes: BTreeMap<String, XNodeEntry>,
     history: Vec<DeploymentRecord>,
     metadata: InventoryMetadata,
+    /// Open `transaction`s; saves are deferred while any is open
+    transaction_depth: usize,
+    /// A save was deferred by an open transaction
+    save_pending: Cell<bool>,
 }
 
 impl XNodeInventory {
@@ -69,9 +347,11 @@ impl XNodeInventory {
 
         let mut inventory = Self {
             inventory_file,
-            xnodes: HashMap::new(),
+            xnodes: BTreeMap::new(),
             history: Vec::new(),
             metadata: InventoryMetadata::default(),
+            transaction_depth: 0,
+            save_pending: Cell::new(false),
         };
 
         inventory.ensure_directory()?;
@@ -105,17 +385,36 @@ impl XNodeInventory {
         let contents = fs::read_to_string(&self.inventory_file)
             .context("Failed to read inventory file")?;
 
-        let data: InventoryData = serde_json::from_str(&contents)
+        let mut raw: serde_json::Value = serde_json::from_str(&contents)
+            .context("Failed to parse inventory JSON")?;
+        let migrated_from = migrate_inventory(&mut raw)?;
+        let data: InventoryData = serde_json::from_value(raw)
             .context("Failed to parse inventory JSON")?;
 
         self.xnodes = data.xnodes;
         self.history = data.history;
         self.metadata = data.metadata;
 
+        // Write the upgrade back, keeping the original file until it is
+        // overwritten by a later migration
+        if let Some(version) = migrated_from {
+            let original = self.inventory_file.with_extension(format!("json.v{}.backup", version));
+            fs::copy(&self.inventory_file, &original)
+                .context("Failed to back up inventory before migrating it")?;
+            self.save()?;
+        }
+
         Ok(())
     }
 
+    /// Write the inventory, replacing the file atomically. Inside a
+    /// `transaction` this only notes that a save is due.
     pub fn save(&self) -> Result<()> {
+        if self.transaction_depth > 0 {
+            self.save_pending.set(true);
+            return Ok(());
+        }
+
         self.backup_inventory()?;
 
         let data = InventoryData {
@@ -129,12 +428,58 @@ impl XNodeInventory {
         let json = serde_json::to_string_pretty(&data)
             .context("Failed to serialize inventory")?;
 
-        fs::write(&self.inventory_file, json)
+        let staging = self.inventory_file.with_extension("json.tmp");
+        fs::write(&staging, json)
             .context("Failed to write inventory file")?;
+        fs::rename(&staging, &self.inventory_file)
+            .context("Failed to replace inventory file")?;
+
+        // Keep the daily run-rate series current for anomaly detection. The
+        // inventory is already written, so a failure here must not fail it.
+        if let Err(e) = self.record_run_rate() {
+            log::warn!("Failed to update cost log: {:#}", e);
+        }
 
         Ok(())
     }
 
+    fn record_run_rate(&self) -> Result<()> {
+        let mut cost_log = self.cost_log()?;
+        cost_log.record(Utc::now().date_naive(), self.get_total_cost()["monthly"]);
+        cost_log.save()
+    }
+
+    /// Apply `f` as one update: saves inside it are deferred and the
+    /// inventory is written once if it returns `Ok`. If it fails, every
+    /// change it made is rolled back and nothing is written. Transactions
+    /// nest; only the outermost one saves.
+    pub fn transaction<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let snapshot = (self.xnodes.clone(), self.history.clone(), self.metadata.clone());
+
+        self.transaction_depth += 1;
+        let result = f(self);
+        self.transaction_depth -= 1;
+
+        let result = result.and_then(|value| {
+            if self.transaction_depth == 0 && self.save_pending.take() {
+                self.save()?;
+            }
+            Ok(value)
+        });
+        if result.is_err() {
+            (self.xnodes, self.history, self.metadata) = snapshot;
+            if self.transaction_depth == 0 {
+                self.save_pending.set(false);
+            }
+        }
+        result
+    }
+
+    /// Daily run-rate history for this inventory
+    pub fn cost_log(&self) -> Result<CostLog> {
+        CostLog::load(&self.inventory_file.with_file_name(COST_LOG_FILE))
+    }
+
     pub fn add_xnode(
         &mut self,
         xnode: &XNode,
@@ -150,49 +495,64 @@ impl XNodeInventory {
         let entry = XNodeEntry {
             id: xnode.id.clone(),
             name: xnode.name.clone(),
-            provider: provider.clone(),
-            template: template.clone(),
+            provider,
+            template,
             status: xnode.status.clone(),
             ip_address: xnode.ip_address.clone(),
             ssh_port: xnode.ssh_port,
             region: xnode.region.clone(),
             deployed_at: xnode.created_at,
             cost_hourly,
-            tags: tags.clone(),
+            tags,
             metadata: xnode.metadata.clone(),
+            last_seen: None,
         };
 
-        self.xnodes.insert(xnode.id.clone(), entry);
+        self.insert_entry(entry);
+        self.save()?;
+        Ok(())
+    }
 
+    /// Add an entry with its deployment record, without saving
+    fn insert_entry(&mut self, entry: XNodeEntry) {
         let record = DeploymentRecord::new(
-            xnode.id.clone(),
-            provider,
-            template,
-            xnode.created_at,
-            xnode.region.clone(),
-            Some(xnode.name.clone()),
-            tags,
+            entry.id.clone(),
+            entry.provider.clone(),
+            entry.template.clone(),
+            entry.deployed_at,
+            entry.region.clone(),
+            Some(entry.name.clone()),
+            entry.tags.clone(),
         );
         self.history.push(record);
 
         self.metadata.total_deployed += 1;
-        if xnode.status == "running" {
+        if entry.status == "running" {
             self.metadata.total_running += 1;
         }
 
-        self.save()?;
-        Ok(())
+        self.xnodes.insert(entry.id.clone(), entry);
     }
 
-    pub fn remove_xnode(&mut self, xnode_id: &str) -> Result<()> {
+    /// Drop an xNode, closing its deployment record with `reason`
+    pub fn remove_xnode(&mut self, xnode_id: &str, reason: Option<&str>) -> Result<()> {
         let entry = self.xnodes.get(xnode_id)
             .ok_or_else(|| anyhow::anyhow!("XNode {} not found in inventory", xnode_id))?
             .clone();
 
+        self.close_entry(&entry, reason);
+        self.save()?;
+        Ok(())
+    }
+
+    /// Close the entry's active deployment record at its current rate and
+    /// drop it from the inventory, without saving
+    fn close_entry(&mut self, entry: &XNodeEntry, reason: Option<&str>) {
         // Update history record
         for record in &mut self.history {
-            if record.xnode_id == xnode_id && record.is_active() {
+            if record.xnode_id == entry.id && record.is_active() {
                 record.terminated_at = Some(Utc::now());
+                record.termination_reason = reason.map(String::from);
                 record.uptime_hours = record.calculate_uptime();
                 record.total_cost = record.uptime_hours * entry.cost_hourly;
                 self.metadata.total_lifetime_cost += record.total_cost;
@@ -205,9 +565,45 @@ impl XNodeInventory {
             self.metadata.total_running = self.metadata.total_running.saturating_sub(1);
         }
 
-        self.xnodes.remove(xnode_id);
+        self.xnodes.remove(&entry.id);
+    }
+
+    /// Re-home an xNode after a manual migration, keeping its name, tags
+    /// and metadata. The current deployment record is closed at the old
+    /// rate and a new one opened, so cost history stays correct. With
+    /// `new_id` the old entry is replaced by one under the new ID;
+    /// otherwise the entry keeps its ID.
+    pub fn move_xnode(&mut self, xnode_id: &str, target: XNodeMove) -> Result<XNodeEntry> {
+        let old = self.xnodes.get(xnode_id)
+            .ok_or_else(|| CliError::not_found(format!("XNode {} not found in inventory", xnode_id)))?
+            .clone();
+
+        let new_id = target.new_id.unwrap_or_else(|| old.id.clone());
+        if new_id != old.id && self.xnodes.contains_key(&new_id) {
+            return Err(CliError::usage(format!("XNode with ID {} already exists in inventory", new_id)).into());
+        }
+
+        let reason = if new_id == old.id {
+            format!("moved to {}/{}", target.provider, target.template)
+        } else {
+            format!("succeeded by {} on {}", new_id, target.provider)
+        };
+        self.close_entry(&old, Some(&reason));
+
+        let entry = XNodeEntry {
+            id: new_id,
+            provider: target.provider,
+            template: target.template,
+            region: target.region,
+            cost_hourly: target.cost_hourly,
+            ip_address: target.ip_address.unwrap_or_else(|| old.ip_address.clone()),
+            deployed_at: Utc::now(),
+            last_seen: None,
+            ..old
+        };
+        self.insert_entry(entry.clone());
         self.save()?;
-        Ok(())
+        Ok(entry)
     }
 
     pub fn get_xnode(&self, xnode_id: &str) -> Option<&XNodeEntry> {
@@ -234,6 +630,10 @@ impl XNodeInventory {
             }
         }
 
+        if let Some(template) = updates.template {
+            entry.template = template;
+        }
+
         if let Some(ip_address) = updates.ip_address {
             entry.ip_address = ip_address;
         }
@@ -250,6 +650,35 @@ impl XNodeInventory {
         Ok(())
     }
 
+    /// Record that `xnode_ids` were confirmed alive at `at`, saving once.
+    /// IDs not in the inventory are ignored.
+    pub fn mark_seen(&mut self, xnode_ids: &[String], at: DateTime<Utc>) -> Result<()> {
+        let mut changed = false;
+        for id in xnode_ids {
+            if let Some(entry) = self.xnodes.get_mut(id) {
+                entry.last_seen = Some(at);
+                changed = true;
+            }
+        }
+        if changed {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Append a provider snapshot to the xNode's metadata
+    pub fn record_snapshot(&mut self, xnode_id: &str, snapshot: SnapshotRecord) -> Result<()> {
+        let entry = self.xnodes.get_mut(xnode_id)
+            .ok_or_else(|| anyhow::anyhow!("XNode {} not found in inventory", xnode_id))?;
+
+        let mut snapshots = entry.snapshots();
+        snapshots.push(snapshot);
+        entry.metadata.insert(META_SNAPSHOTS.to_string(), serde_json::to_value(snapshots)?);
+
+        self.save()?;
+        Ok(())
+    }
+
     pub fn list_all(&self) -> Vec<&XNodeEntry> {
         self.xnodes.values().collect()
     }
@@ -284,6 +713,37 @@ impl XNodeInventory {
             .collect()
     }
 
+    /// xNodes matching every given selector (all `tags`, `provider`, `status`),
+    /// sorted by ID; no selectors selects nothing
+    pub fn select(
+        &self,
+        tags: &[String],
+        provider: Option<&str>,
+        status: Option<&str>,
+    ) -> Vec<&XNodeEntry> {
+        let mut selections: Vec<Vec<&XNodeEntry>> = Vec::new();
+        if !tags.is_empty() {
+            selections.push(self.list_by_tags(tags, true));
+        }
+        if let Some(provider) = provider {
+            selections.push(self.list_by_provider(provider));
+        }
+        if let Some(status) = status {
+            selections.push(self.list_by_status(status));
+        }
+
+        let Some((first, rest)) = selections.split_first() else {
+            return Vec::new();
+        };
+        let mut selected: Vec<&XNodeEntry> = first
+            .iter()
+            .filter(|entry| rest.iter().all(|s| s.iter().any(|e| e.id == entry.id)))
+            .copied()
+            .collect();
+        selected.sort_by(|a, b| a.id.cmp(&b.id));
+        selected
+    }
+
     pub fn search(&self, query: &str) -> Vec<&XNodeEntry> {
         let query_lower = query.to_lowercase();
         self.xnodes
@@ -295,6 +755,29 @@ impl XNodeInventory {
             .collect()
     }
 
+    /// The xNode with IP address `ip`; see `find_all_by_ip` when an address
+    /// may have been reused
+    pub fn find_by_ip(&self, ip: &str) -> Option<&XNodeEntry> {
+        self.find_all_by_ip(ip).into_iter().next()
+    }
+
+    /// Every xNode with IP address `ip`. Addresses are compared as parsed
+    /// IPs where possible, so `::1` matches `0:0:0:0:0:0:0:1`.
+    pub fn find_all_by_ip(&self, ip: &str) -> Vec<&XNodeEntry> {
+        let wanted = ip.trim();
+        let parsed = wanted.parse::<IpAddr>().ok();
+        self.xnodes
+            .values()
+            .filter(|xnode| {
+                let address = xnode.ip_address.trim();
+                match (parsed, address.parse::<IpAddr>()) {
+                    (Some(wanted), Ok(address)) => wanted == address,
+                    _ => !address.is_empty() && address == wanted,
+                }
+            })
+            .collect()
+    }
+
     pub fn get_total_cost(&self) -> HashMap<String, f64> {
         let total_hourly: f64 = self.xnodes
             .values()
@@ -311,8 +794,8 @@ impl XNodeInventory {
     }
 
     pub fn get_cost_report(&self) -> CostReport {
-        let mut by_provider: HashMap<String, f64> = HashMap::new();
-        let mut by_region: HashMap<String, f64> = HashMap::new();
+        let mut by_provider: BTreeMap<String, f64> = BTreeMap::new();
+        let mut by_region: BTreeMap<String, f64> = BTreeMap::new();
         let mut active_count = 0;
 
         for xnode in self.xnodes.values() {
@@ -342,10 +825,35 @@ impl XNodeInventory {
         )
     }
 
+    /// Running costs summed per provider, region or tag. A node with several
+    /// tags counts towards each; nodes without tags land in "untagged".
+    pub fn cost_by(&self, grouping: CostGrouping) -> Vec<GroupCost> {
+        let mut totals: BTreeMap<String, (usize, f64)> = BTreeMap::new();
+
+        for xnode in self.xnodes.values().filter(|x| x.status == "running") {
+            let mut groups: Vec<String> = match grouping {
+                CostGrouping::Provider => vec![xnode.provider.clone()],
+                CostGrouping::Region => vec![xnode.region.clone().unwrap_or_else(|| "unknown".to_string())],
+                CostGrouping::Tag if xnode.tags.is_empty() => vec![UNTAGGED.to_string()],
+                CostGrouping::Tag => xnode.tags.clone(),
+            };
+            groups.sort_unstable();
+            groups.dedup();
+
+            for group in groups {
+                let total = totals.entry(group).or_insert((0, 0.0));
+                total.0 += 1;
+                total.1 += xnode.cost_hourly;
+            }
+        }
+
+        GroupCost::ranked(totals)
+    }
+
     pub fn get_statistics(&self) -> InventoryStatistics {
-        let mut status_distribution: HashMap<String, usize> = HashMap::new();
-        let mut provider_distribution: HashMap<String, usize> = HashMap::new();
-        let mut region_distribution: HashMap<String, usize> = HashMap::new();
+        let mut status_distribution: BTreeMap<String, usize> = BTreeMap::new();
+        let mut provider_distribution: BTreeMap<String, usize> = BTreeMap::new();
+        let mut region_distribution: BTreeMap<String, usize> = BTreeMap::new();
 
         for xnode in self.xnodes.values() {
             *status_distribution.entry(xnode.status.clone()).or_insert(0) += 1;
@@ -396,7 +904,9 @@ impl XNodeInventory {
                 }
             })
             .collect();
-        longest_running.sort_by(|a, b| b.uptime_hours.partial_cmp(&a.uptime_hours).unwrap());
+        longest_running.sort_by(|a, b| {
+            b.uptime_hours.partial_cmp(&a.uptime_hours).unwrap().then_with(|| a.id.cmp(&b.id))
+        });
         longest_running.truncate(5);
 
         InventoryStatistics {
@@ -414,6 +924,154 @@ impl XNodeInventory {
         }
     }
 
+    /// Internal inconsistencies, each with a suggested fix. `known_template`
+    /// says whether any provider still offers a template ID.
+    pub fn validate(&self, known_template: impl Fn(&str) -> bool) -> Vec<InventoryIssue> {
+        let mut issues = Vec::new();
+        let issue = |kind, xnode_id: &str, message: String, fix: String| InventoryIssue {
+            kind,
+            xnode_id: xnode_id.to_string(),
+            message,
+            fix,
+        };
+
+        let mut by_ip: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for xnode in self.xnodes.values() {
+            if xnode.status == "running" && xnode.ip_address.is_empty() {
+                issues.push(issue(
+                    IssueKind::RunningWithoutIp,
+                    &xnode.id,
+                    "running but has no IP address".to_string(),
+                    "capsule openmesh xnode sync".to_string(),
+                ));
+            }
+            if xnode.cost_hourly <= 0.0 {
+                issues.push(issue(
+                    IssueKind::ZeroCost,
+                    &xnode.id,
+                    "hourly cost is $0, so cost reports undercount it".to_string(),
+                    format!("capsule openmesh xnode move {} --cost <HOURLY>", xnode.id),
+                ));
+            }
+            if !known_template(&xnode.template) {
+                issues.push(issue(
+                    IssueKind::UnknownTemplate,
+                    &xnode.id,
+                    format!("template '{}' is not offered by any provider", xnode.template),
+                    format!("capsule openmesh xnode move {} --template <ID>", xnode.id),
+                ));
+            }
+            if !xnode.ip_address.is_empty() {
+                by_ip.entry(&xnode.ip_address).or_default().push(&xnode.id);
+            }
+        }
+
+        for (ip, ids) in by_ip.into_iter().filter(|(_, ids)| ids.len() > 1) {
+            for id in &ids {
+                let others: Vec<&str> = ids.iter().filter(|other| *other != id).copied().collect();
+                issues.push(issue(
+                    IssueKind::DuplicateIp,
+                    id,
+                    format!("IP {} is also recorded for {}", ip, others.join(", ")),
+                    "capsule openmesh xnode sync, then prune whichever no longer exists".to_string(),
+                ));
+            }
+        }
+
+        for record in self.history.iter().filter(|r| r.is_active()) {
+            if !self.xnodes.contains_key(&record.xnode_id) {
+                issues.push(issue(
+                    IssueKind::OrphanedHistory,
+                    &record.xnode_id,
+                    format!("deployment record from {} is still open but the xNode is gone", record.deployed_at.format("%Y-%m-%d")),
+                    format!("capsule openmesh xnode history --xnode-id {} (then adopt or close it)", record.xnode_id),
+                ));
+            }
+        }
+
+        issues
+    }
+
+    /// Distinct tags across all xNodes with how many nodes carry each,
+    /// most used first
+    pub fn tag_summary(&self) -> TagSummary {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        let mut untagged = 0;
+
+        for xnode in self.xnodes.values() {
+            if xnode.tags.is_empty() {
+                untagged += 1;
+            }
+            let mut seen: Vec<&str> = xnode.tags.iter().map(String::as_str).collect();
+            seen.sort_unstable();
+            seen.dedup();
+            for tag in seen {
+                *counts.entry(tag).or_insert(0) += 1;
+            }
+        }
+
+        let mut tags: Vec<TagCount> = counts
+            .into_iter()
+            .map(|(tag, count)| TagCount { tag: tag.to_string(), count })
+            .collect();
+        tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+
+        TagSummary { tags, untagged }
+    }
+
+    /// Export every entry to `filename` in `format`
+    pub fn export(&self, filename: &str, format: ExportFormat) -> Result<()> {
+        if format == ExportFormat::Csv {
+            return self.export_csv(filename);
+        }
+
+        let xnodes: Vec<XNodeEntry> = self.xnodes.values().cloned().collect();
+        let export = InventoryExport {
+            version: VERSION.to_string(),
+            exported_at: Utc::now(),
+            xnodes,
+        };
+
+        let contents = match format {
+            ExportFormat::Json => serde_json::to_string_pretty(&export)?,
+            _ => serde_yaml::to_string(&export)?,
+        };
+        fs::write(filename, contents)
+            .with_context(|| format!("Failed to write {}", filename))?;
+        Ok(())
+    }
+
+    /// Import entries from a file written by `export`, skipping IDs already
+    /// in the inventory. Returns how many entries were added.
+    pub fn import(&mut self, filename: &str, format: ExportFormat) -> Result<usize> {
+        if format == ExportFormat::Csv {
+            return self.import_csv(filename);
+        }
+
+        let contents = fs::read_to_string(filename)
+            .with_context(|| format!("Failed to read {}", filename))?;
+        let export: InventoryExport = match format {
+            ExportFormat::Json => serde_json::from_str(&contents)
+                .context("Failed to parse JSON inventory export")?,
+            _ => serde_yaml::from_str(&contents)
+                .context("Failed to parse YAML inventory export")?,
+        };
+
+        let mut imported = 0;
+        for entry in export.xnodes {
+            if self.xnodes.contains_key(&entry.id) {
+                continue;
+            }
+            self.insert_entry(entry);
+            imported += 1;
+        }
+
+        if imported > 0 {
+            self.save()?;
+        }
+        Ok(imported)
+    }
+
     pub fn export_csv(&self, filename: &str) -> Result<()> {
         use std::io::Write;
 
@@ -421,34 +1079,72 @@ impl XNodeInventory {
             .context("Failed to create CSV file")?;
         let mut writer = std::io::BufWriter::new(file);
 
-        // Write header
-        writeln!(
-            writer,
-            "id,name,provider,status,ip_address,region,deployed_at,cost_hourly,tags"
-        )?;
-
-        // Write rows
+        writeln!(writer, "{}", CSV_HEADER)?;
         for xnode in self.xnodes.values() {
-            let region = xnode.region.as_deref().unwrap_or("");
-            let tags = xnode.tags.join(",");
-            writeln!(
-                writer,
-                "{},{},{},{},{},{},{},{:.2},{}",
-                xnode.id,
-                xnode.name,
-                xnode.provider,
-                xnode.status,
-                xnode.ip_address,
-                region,
-                xnode.deployed_at.to_rfc3339(),
-                xnode.cost_hourly,
-                tags
-            )?;
+            writeln!(writer, "{}", csv_row(xnode))?;
         }
 
         Ok(())
     }
 
+    /// Append entries to a CSV written by `export_csv`, skipping rows whose
+    /// id and deployed_at are already in the file. A missing or empty file
+    /// gets the header first. Returns how many rows were appended.
+    pub fn append_csv(&self, filename: &str) -> Result<usize> {
+        use std::io::Write;
+
+        let existing = match fs::read_to_string(filename) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", filename)),
+        };
+
+        let mut lines = existing.lines();
+        let mut seen = std::collections::HashSet::new();
+        let write_header = match lines.next() {
+            None => true,
+            Some(header) if header.trim_end() == CSV_HEADER => {
+                for line in lines {
+                    let fields = split_csv_line(line);
+                    if fields.len() > 6 {
+                        seen.insert((fields[0].clone(), fields[6].clone()));
+                    }
+                }
+                false
+            }
+            Some(header) => anyhow::bail!(
+                "{} is not an inventory CSV export (header is '{}', expected '{}')",
+                filename,
+                header,
+                CSV_HEADER
+            ),
+        };
+
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(filename)
+            .with_context(|| format!("Failed to open {}", filename))?;
+        let mut writer = std::io::BufWriter::new(file);
+
+        if write_header {
+            writeln!(writer, "{}", CSV_HEADER)?;
+        } else if !existing.ends_with('\n') {
+            writeln!(writer)?;
+        }
+
+        let mut appended = 0;
+        for xnode in self.xnodes.values() {
+            if seen.contains(&(xnode.id.clone(), xnode.deployed_at.to_rfc3339())) {
+                continue;
+            }
+            writeln!(writer, "{}", csv_row(xnode))?;
+            appended += 1;
+        }
+
+        Ok(appended)
+    }
+
     pub fn import_csv(&mut self, filename: &str) -> Result<usize> {
         use std::io::BufRead;
 
@@ -456,73 +1152,80 @@ impl XNodeInventory {
             .context("Failed to open CSV file")?;
         let reader = std::io::BufReader::new(file);
 
-        let mut imported = 0;
         let mut lines = reader.lines();
 
         // Skip header
         lines.next();
 
-        for line in lines {
-            let line = line?;
-            let parts: Vec<&str> = line.split(',').collect();
+        // One save for the whole file; a read error part way adds nothing
+        self.transaction(|inventory| {
+            let mut imported = 0;
+            for line in lines {
+                let line = line?;
+                let parts = split_csv_line(&line);
 
-            if parts.len() < 8 {
-                continue;
-            }
+                if parts.len() < 8 {
+                    continue;
+                }
 
-            let xnode_id = parts[0].to_string();
+                let xnode_id = parts[0].clone();
 
-            // Skip if already exists
-            if self.xnodes.contains_key(&xnode_id) {
-                continue;
-            }
-
-            let deployed_at = DateTime::parse_from_rfc3339(parts[6])
-                .unwrap_or_else(|_| Utc::now().into())
-                .with_timezone(&Utc);
+                // Skip if already exists
+                if inventory.xnodes.contains_key(&xnode_id) {
+                    continue;
+                }
 
-            let tags = if parts.len() > 8 {
-                parts[8].split(',').map(|s| s.trim().to_string()).collect()
-            } else {
-                Vec::new()
-            };
+                let deployed_at = DateTime::parse_from_rfc3339(&parts[6])
+                    .unwrap_or_else(|_| Utc::now().into())
+                    .with_timezone(&Utc);
 
-            let xnode = XNode {
-                id: xnode_id,
-                name: parts[1].to_string(),
-                status: parts[3].to_string(),
-                ip_address: parts[4].to_string(),
-                ssh_port: 22,
-                tunnel_port: None,
-                created_at: deployed_at,
-                region: if parts[5].is_empty() {
-                    None
+                let tags = if parts.len() > 8 {
+                    parts[8]
+                        .split(',')
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty())
+                        .collect()
                 } else {
-                    Some(parts[5].to_string())
-                },
-                metadata: HashMap::new(),
-            };
-
-            let cost_hourly = parts[7].parse::<f64>().unwrap_or(0.0);
-
-            self.add_xnode(
-                &xnode,
-                parts[2].to_string(),
-                "imported".to_string(),
-                cost_hourly,
-                tags,
-            )?;
-
-            imported += 1;
-        }
-
-        Ok(imported)
+                    Vec::new()
+                };
+
+                let xnode = XNode {
+                    id: xnode_id,
+                    name: parts[1].to_string(),
+                    status: parts[3].to_string(),
+                    ip_address: parts[4].to_string(),
+                    ssh_port: 22,
+                    tunnel_port: None,
+                    created_at: deployed_at,
+                    region: if parts[5].is_empty() {
+                        None
+                    } else {
+                        Some(parts[5].to_string())
+                    },
+                    metadata: HashMap::new(),
+                };
+
+                let cost_hourly = parts[7].parse::<f64>().unwrap_or(0.0);
+
+                inventory.add_xnode(
+                    &xnode,
+                    parts[2].to_string(),
+                    "imported".to_string(),
+                    cost_hourly,
+                    tags,
+                )?;
+
+                imported += 1;
+            }
+            Ok(imported)
+        })
     }
 
     pub fn get_deployment_history(
         &self,
         xnode_id: Option<&str>,
         provider: Option<&str>,
+        window: &HistoryWindow,
         limit: Option<usize>,
     ) -> Vec<&DeploymentRecord> {
         let mut records: Vec<_> = self.history.iter().collect();
@@ -536,8 +1239,11 @@ impl XNodeInventory {
             records.retain(|r| r.provider == prov);
         }
 
+        let now = Utc::now();
+        records.retain(|r| window.contains(r, now));
+
         // Sort by deployment time (newest first)
-        records.sort_by(|a, b| b.deployed_at.cmp(&a.deployed_at));
+        records.sort_by_key(|r| std::cmp::Reverse(r.deployed_at));
 
         // Apply limit
         if let Some(limit) = limit {
@@ -569,20 +1275,34 @@ impl XNodeInventory {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct XNodeUpdate {
     pub status: Option<String>,
+    pub template: Option<String>,
     pub ip_address: Option<String>,
     pub region: Option<String>,
     pub cost_hourly: Option<f64>,
 }
 
+/// Where `XNodeInventory::move_xnode` puts an xNode
+#[derive(Debug, Clone, PartialEq)]
+pub struct XNodeMove {
+    pub provider: String,
+    pub template: String,
+    pub region: Option<String>,
+    pub cost_hourly: f64,
+    /// New address; the old one is kept if unset
+    pub ip_address: Option<String>,
+    /// Record a successor under this ID instead of editing in place
+    pub new_id: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InventoryStatistics {
     pub total_xnodes: usize,
-    pub status_distribution: HashMap<String, usize>,
-    pub provider_distribution: HashMap<String, usize>,
-    pub region_distribution: HashMap<String, usize>,
+    pub status_distribution: BTreeMap<String, usize>,
+    pub provider_distribution: BTreeMap<String, usize>,
+    pub region_distribution: BTreeMap<String, usize>,
     pub total_deployments: usize,
     pub active_deployments: usize,
     pub terminated_deployments: usize,
@@ -592,6 +1312,51 @@ pub struct InventoryStatistics {
     pub longest_running: Vec<LongestRunningXNode>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// What `XNodeInventory::validate` found wrong
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    RunningWithoutIp,
+    ZeroCost,
+    UnknownTemplate,
+    DuplicateIp,
+    OrphanedHistory,
+}
+
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::RunningWithoutIp => "running without IP",
+            Self::ZeroCost => "zero cost",
+            Self::UnknownTemplate => "unknown template",
+            Self::DuplicateIp => "duplicate IP",
+            Self::OrphanedHistory => "orphaned history",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InventoryIssue {
+    pub kind: IssueKind,
+    pub xnode_id: String,
+    pub message: String,
+    /// Command or step that should resolve it
+    pub fix: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagSummary {
+    pub tags: Vec<TagCount>,
+    pub untagged: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MostExpensiveXNode {
     pub id: String,
@@ -612,6 +1377,192 @@ mod tests {
     use super::*;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_structured_export_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut inventory =
+            XNodeInventory::new(Some(temp_dir.path().join("inventory.json"))).unwrap();
+
+        let mut xnode = XNode::new(
+            "node-1".to_string(),
+            "Web, primary".to_string(),
+            "running".to_string(),
+            "10.0.0.1".to_string(),
+        );
+        xnode.ssh_port = 2222;
+        xnode.metadata.insert("owner".to_string(), serde_json::json!("ops"));
+        inventory
+            .add_xnode(&xnode, "vultr".to_string(), "vc2-1c-1gb".to_string(), 0.01, vec!["web".to_string(), "prod".to_string()])
+            .unwrap();
+
+        for format in [ExportFormat::Json, ExportFormat::Yaml] {
+            let file = temp_dir.path().join(format!("export.{}", format));
+            let file = file.to_str().unwrap();
+            inventory.export(file, format).unwrap();
+
+            let mut restored =
+                XNodeInventory::new(Some(temp_dir.path().join(format!("restored-{}.json", format)))).unwrap();
+            assert_eq!(restored.import(file, format).unwrap(), 1);
+            assert_eq!(restored.import(file, format).unwrap(), 0);
+
+            let entry = restored.get_xnode("node-1").unwrap();
+            assert_eq!(entry.name, "Web, primary");
+            assert_eq!(entry.template, "vc2-1c-1gb");
+            assert_eq!(entry.ssh_port, 2222);
+            assert_eq!(entry.tags, ["web", "prod"]);
+            assert_eq!(entry.metadata["owner"], "ops");
+            assert_eq!(restored.metadata.total_running, 1);
+        }
+
+        assert_eq!("YML".parse::<ExportFormat>().unwrap(), ExportFormat::Yaml);
+        assert!("xml".parse::<ExportFormat>().is_err());
+    }
+
+    #[test]
+    fn test_validate_reports_inconsistencies() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut inventory =
+            XNodeInventory::new(Some(temp_dir.path().join("inventory.json"))).unwrap();
+
+        let nodes = [
+            ("ok", "10.0.0.1", "small", 0.01),
+            ("no-ip", "", "small", 0.01),
+            ("free", "10.0.0.2", "small", 0.0),
+            ("retired", "10.0.0.3", "gone-plan", 0.01),
+            ("dup", "10.0.0.1", "small", 0.01),
+            ("removed", "10.0.0.9", "small", 0.01),
+        ];
+        for (id, ip, template, cost) in nodes {
+            let xnode = XNode::new(id.into(), id.into(), "running".into(), ip.into());
+            inventory.add_xnode(&xnode, "vultr".into(), template.into(), cost, Vec::new()).unwrap();
+        }
+        // Drop the entry but leave its deployment record open
+        inventory.xnodes.remove("removed");
+
+        let issues = inventory.validate(|template| template == "small");
+        let found: Vec<(IssueKind, &str)> = issues.iter().map(|i| (i.kind, i.xnode_id.as_str())).collect();
+        assert_eq!(
+            found,
+            [
+                (IssueKind::ZeroCost, "free"),
+                (IssueKind::RunningWithoutIp, "no-ip"),
+                (IssueKind::UnknownTemplate, "retired"),
+                (IssueKind::DuplicateIp, "dup"),
+                (IssueKind::DuplicateIp, "ok"),
+                (IssueKind::OrphanedHistory, "removed"),
+            ]
+        );
+        assert!(issues[3].message.contains("also recorded for ok"));
+        assert!(inventory.validate(|_| true).iter().all(|i| i.kind != IssueKind::UnknownTemplate));
+    }
+
+    #[test]
+    fn test_last_seen_and_staleness() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("inventory.json");
+        let mut inventory = XNodeInventory::new(Some(path.clone())).unwrap();
+
+        let mut xnode = XNode::new("node-1".into(), "web".into(), "running".into(), "10.0.0.1".into());
+        let now = Utc::now();
+        xnode.created_at = now - chrono::Duration::days(10);
+        inventory.add_xnode(&xnode, "vultr".into(), "small".into(), 0.01, Vec::new()).unwrap();
+
+        let entry = inventory.get_xnode("node-1").unwrap();
+        assert!(entry.last_seen.is_none());
+        assert_eq!(entry.last_seen_label(now), "never");
+        assert!(entry.is_stale(7, now));
+        assert!(!entry.is_stale(14, now));
+
+        inventory.mark_seen(&["node-1".to_string(), "gone".to_string()], now - chrono::Duration::days(3)).unwrap();
+        let reloaded = XNodeInventory::new(Some(path)).unwrap();
+        let entry = reloaded.get_xnode("node-1").unwrap();
+        assert_eq!(entry.last_seen_label(now), "3d ago");
+        assert!(!entry.is_stale(7, now));
+        assert!(entry.is_stale(2, now));
+    }
+
+    #[test]
+    fn test_csv_append_skips_exported_rows() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut inventory =
+            XNodeInventory::new(Some(temp_dir.path().join("inventory.json"))).unwrap();
+        let file = temp_dir.path().join("audit.csv");
+        let file = file.to_str().unwrap();
+
+        let web = XNode::new("node-1".into(), "Web, primary".into(), "running".into(), "10.0.0.1".into());
+        inventory
+            .add_xnode(&web, "vultr".into(), "small".into(), 0.01, vec!["web".into(), "prod".into()])
+            .unwrap();
+        assert_eq!(inventory.append_csv(file).unwrap(), 1);
+        assert_eq!(inventory.append_csv(file).unwrap(), 0);
+
+        let db = XNode::new("node-2".into(), "db".into(), "running".into(), "10.0.0.2".into());
+        inventory.add_xnode(&db, "vultr".into(), "small".into(), 0.02, Vec::new()).unwrap();
+        assert_eq!(inventory.append_csv(file).unwrap(), 1);
+
+        let contents = fs::read_to_string(file).unwrap();
+        assert_eq!(contents.lines().filter(|l| *l == CSV_HEADER).count(), 1);
+        assert_eq!(contents.lines().count(), 3);
+
+        let mut restored =
+            XNodeInventory::new(Some(temp_dir.path().join("restored.json"))).unwrap();
+        assert_eq!(restored.import_csv(file).unwrap(), 2);
+        let entry = restored.get_xnode("node-1").unwrap();
+        assert_eq!(entry.name, "Web, primary");
+        assert_eq!(entry.tags, ["web", "prod"]);
+
+        let other = temp_dir.path().join("other.csv");
+        fs::write(&other, "host,ip\n").unwrap();
+        assert!(inventory.append_csv(other.to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_statistics_ordering_is_stable() {
+        let temp_dir = TempDir::new().unwrap();
+        let nodes = [
+            ("node-c", "vultr", "stopped", "ewr"),
+            ("node-a", "linode", "running", "us-east"),
+            ("node-d", "digitalocean", "running", "nyc1"),
+            ("node-b", "vultr", "running", "ams"),
+        ];
+
+        let build = |name: &str, order: &[usize]| {
+            let mut inventory = XNodeInventory::new(Some(temp_dir.path().join(name))).unwrap();
+            for &i in order {
+                let (id, provider, status, region) = nodes[i];
+                let mut xnode = XNode::new(id.to_string(), id.to_string(), status.to_string(), "10.0.0.1".to_string());
+                xnode.region = Some(region.to_string());
+                inventory
+                    .add_xnode(&xnode, provider.to_string(), "small".to_string(), 0.01, vec![])
+                    .unwrap();
+            }
+            inventory
+        };
+        let forward = build("forward.json", &[0, 1, 2, 3]);
+        let backward = build("backward.json", &[3, 2, 1, 0]);
+
+        // Uptimes depend on deployment time, so only the rest is compared
+        let stats = |inventory: &XNodeInventory| {
+            let mut stats = inventory.get_statistics();
+            stats.longest_running.clear();
+            serde_json::to_string(&stats).unwrap()
+        };
+        assert_eq!(stats(&forward), stats(&backward));
+        assert_eq!(
+            forward.get_statistics().provider_distribution.keys().collect::<Vec<_>>(),
+            ["digitalocean", "linode", "vultr"]
+        );
+
+        let report = |inventory: &XNodeInventory| {
+            let report = inventory.get_cost_report().generate_report();
+            report.lines().filter(|l| !l.starts_with("Generated:")).collect::<Vec<_>>().join("\n")
+        };
+        assert_eq!(report(&forward), report(&backward));
+
+        let ids: Vec<_> = backward.list_all().iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["node-a", "node-b", "node-c", "node-d"]);
+    }
+
     #[test]
     fn test_inventory_creation() {
         let temp_dir = TempDir::new().unwrap();
@@ -644,8 +1595,340 @@ mod tests {
         assert_eq!(inventory.metadata.total_deployed, 1);
         assert_eq!(inventory.metadata.total_running, 1);
 
-        inventory.remove_xnode("test-1").unwrap();
+        inventory.remove_xnode("test-1", Some("migrated")).unwrap();
         assert_eq!(inventory.xnodes.len(), 0);
         assert_eq!(inventory.metadata.total_running, 0);
+        assert_eq!(inventory.history[0].termination_reason.as_deref(), Some("migrated"));
+    }
+
+    #[test]
+    fn test_record_snapshot() {
+        let temp_dir = TempDir::new().unwrap();
+        let inventory_file = temp_dir.path().join("inventory.json");
+
+        let mut inventory = XNodeInventory::new(Some(inventory_file.clone())).unwrap();
+        let xnode = XNode::new(
+            "test-1".to_string(),
+            "Test Node".to_string(),
+            "running".to_string(),
+            "192.168.1.1".to_string(),
+        );
+        inventory
+            .add_xnode(&xnode, "linode".to_string(), "default".to_string(), 1.5, vec![])
+            .unwrap();
+
+        for (id, name) in [("snap-1", "before-upgrade"), ("snap-2", "after-upgrade")] {
+            let record = SnapshotRecord {
+                id: id.to_string(),
+                name: name.to_string(),
+                created_at: Utc::now(),
+            };
+            inventory.record_snapshot("test-1", record).unwrap();
+        }
+        assert!(inventory.record_snapshot("missing", SnapshotRecord {
+            id: "x".to_string(),
+            name: "x".to_string(),
+            created_at: Utc::now(),
+        }).is_err());
+
+        let reloaded = XNodeInventory::new(Some(inventory_file)).unwrap();
+        let snapshots = reloaded.get_xnode("test-1").unwrap().snapshots();
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(snapshots[0].name, "before-upgrade");
+        assert_eq!(snapshots[1].id, "snap-2");
+    }
+
+    #[test]
+    fn test_select_intersects_selectors() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut inventory = XNodeInventory::new(Some(temp_dir.path().join("inventory.json"))).unwrap();
+
+        for (id, provider, status, tags) in [
+            ("a", "vultr", "running", vec!["staging"]),
+            ("b", "vultr", "stopped", vec!["staging", "web"]),
+            ("c", "linode", "running", vec!["staging"]),
+            ("d", "vultr", "running", vec!["prod"]),
+        ] {
+            let xnode = XNode::new(id.to_string(), id.to_string(), status.to_string(), String::new());
+            let tags = tags.into_iter().map(String::from).collect();
+            inventory
+                .add_xnode(&xnode, provider.to_string(), "small".to_string(), 0.1, tags)
+                .unwrap();
+        }
+
+        let ids = |entries: Vec<&XNodeEntry>| entries.iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+        let staging = vec!["staging".to_string()];
+
+        assert_eq!(ids(inventory.select(&staging, None, None)), ["a", "b", "c"]);
+        assert_eq!(ids(inventory.select(&staging, Some("vultr"), None)), ["a", "b"]);
+        assert_eq!(ids(inventory.select(&staging, Some("vultr"), Some("running"))), ["a"]);
+        assert_eq!(ids(inventory.select(&[], None, Some("running"))), ["a", "c", "d"]);
+        assert!(inventory.select(&[], None, None).is_empty());
+    }
+
+    #[test]
+    fn test_tag_summary_counts_and_orders_by_frequency() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut inventory = XNodeInventory::new(Some(temp_dir.path().join("inventory.json"))).unwrap();
+        assert!(inventory.tag_summary().tags.is_empty());
+
+        for (id, tags) in [
+            ("a", vec!["web", "staging"]),
+            ("b", vec!["staging", "staging"]),
+            ("c", vec!["db", "prod"]),
+            ("d", vec![]),
+        ] {
+            let xnode = XNode::new(id.to_string(), id.to_string(), "running".to_string(), String::new());
+            let tags = tags.into_iter().map(String::from).collect();
+            inventory
+                .add_xnode(&xnode, "vultr".to_string(), "small".to_string(), 0.1, tags)
+                .unwrap();
+        }
+
+        let summary = inventory.tag_summary();
+        let tags: Vec<(&str, usize)> = summary.tags.iter().map(|t| (t.tag.as_str(), t.count)).collect();
+        assert_eq!(tags, [("staging", 2), ("db", 1), ("prod", 1), ("web", 1)]);
+        assert_eq!(summary.untagged, 1);
+    }
+
+    #[test]
+    fn test_deployment_history_time_window() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut inventory = XNodeInventory::new(Some(temp_dir.path().join("inventory.json"))).unwrap();
+        let now = Utc::now();
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        for (id, deployed, terminated) in [
+            ("old", at("2024-04-30T23:00:00Z"), Some(at("2024-05-02T10:00:00Z"))),
+            ("may", at("2024-05-01T08:00:00Z"), None),
+            ("recent", now - chrono::Duration::hours(2), None),
+        ] {
+            let mut record = DeploymentRecord::new(
+                id.to_string(), "vultr".to_string(), "small".to_string(), deployed, None, None, Vec::new(),
+            );
+            record.terminated_at = terminated;
+            inventory.history.push(record);
+        }
+
+        let ids = |window: HistoryWindow| -> Vec<String> {
+            inventory
+                .get_deployment_history(None, None, &window, None)
+                .iter()
+                .map(|r| r.xnode_id.clone())
+                .collect()
+        };
+        let day: TimeBound = "2024-05-01".parse().unwrap();
+        assert_eq!(ids(HistoryWindow { since: Some("24h".parse().unwrap()), ..Default::default() }), ["recent"]);
+        assert_eq!(ids(HistoryWindow { since: Some(day), until: Some(day), by_termination: false }), ["may"]);
+        assert_eq!(ids(HistoryWindow { until: Some(day), ..Default::default() }), ["may", "old"]);
+        assert_eq!(
+            ids(HistoryWindow { since: Some("2024-05-02T00:00:00Z".parse().unwrap()), by_termination: true, ..Default::default() }),
+            ["old"]
+        );
+
+        assert_eq!("7d".parse::<TimeBound>().unwrap().start(now), now - chrono::Duration::days(7));
+        assert_eq!(day.end(now), at("2024-05-02T00:00:00Z"));
+        assert!("last week".parse::<TimeBound>().unwrap_err().contains("invalid time 'last week'"));
+    }
+
+    #[test]
+    fn test_move_xnode_in_place_and_as_successor() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut inventory = XNodeInventory::new(Some(temp_dir.path().join("inventory.json"))).unwrap();
+        let xnode = XNode::new("v-1".to_string(), "web".to_string(), "running".to_string(), "10.0.0.1".to_string());
+        inventory
+            .add_xnode(&xnode, "vultr".to_string(), "small".to_string(), 0.1, vec!["web".to_string()])
+            .unwrap();
+
+        let target = XNodeMove {
+            provider: "digitalocean".to_string(),
+            template: "s-1vcpu".to_string(),
+            region: Some("nyc3".to_string()),
+            cost_hourly: 0.2,
+            ip_address: None,
+            new_id: None,
+        };
+        let moved = inventory.move_xnode("v-1", target.clone()).unwrap();
+        assert_eq!((moved.id.as_str(), moved.provider.as_str()), ("v-1", "digitalocean"));
+        assert_eq!(moved.tags, ["web"]);
+        assert_eq!(moved.ip_address, "10.0.0.1");
+
+        let history = inventory.get_deployment_history(Some("v-1"), None, &HistoryWindow::default(), None);
+        assert_eq!(history.len(), 2);
+        let closed = history.iter().find(|r| !r.is_active()).unwrap();
+        assert_eq!(closed.provider, "vultr");
+        assert_eq!(closed.termination_reason.as_deref(), Some("moved to digitalocean/s-1vcpu"));
+
+        let successor = XNodeMove { new_id: Some("do-9".to_string()), ip_address: Some("10.0.0.9".to_string()), ..target };
+        let moved = inventory.move_xnode("v-1", successor.clone()).unwrap();
+        assert_eq!((moved.id.as_str(), moved.name.as_str()), ("do-9", "web"));
+        assert!(inventory.get_xnode("v-1").is_none());
+        assert_eq!(inventory.get_statistics().total_xnodes, 1);
+        assert!(inventory.get_deployment_history(Some("v-1"), None, &HistoryWindow::default(), None).iter().all(|r| !r.is_active()));
+
+        let err = inventory.move_xnode("v-1", successor).unwrap_err();
+        assert_eq!(crate::exit::exit_code_for(&err), crate::exit::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_find_by_ip_returns_every_match() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut inventory = XNodeInventory::new(Some(temp_dir.path().join("inventory.json"))).unwrap();
+
+        for (id, ip) in [("a", "10.0.0.1"), ("b", "10.0.0.2"), ("c", "10.0.0.1"), ("d", "::1"), ("e", "")] {
+            let xnode = XNode::new(id.to_string(), id.to_string(), "running".to_string(), ip.to_string());
+            inventory
+                .add_xnode(&xnode, "vultr".to_string(), "small".to_string(), 0.1, Vec::new())
+                .unwrap();
+        }
+
+        assert_eq!(inventory.find_by_ip("10.0.0.2").unwrap().id, "b");
+        let shared: Vec<&str> = inventory.find_all_by_ip(" 10.0.0.1").iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(shared, ["a", "c"]);
+        assert_eq!(inventory.find_by_ip("0:0:0:0:0:0:0:1").unwrap().id, "d");
+        assert!(inventory.find_by_ip("10.0.0.3").is_none());
+        assert!(inventory.find_by_ip("").is_none());
+    }
+
+    #[test]
+    fn test_cost_by_tag_counts_each_tag_and_untagged() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut inventory = XNodeInventory::new(Some(temp_dir.path().join("inventory.json"))).unwrap();
+
+        for (id, status, cost, tags) in [
+            ("a", "running", 0.10, vec!["web", "staging"]),
+            ("b", "running", 0.30, vec!["web"]),
+            ("c", "running", 0.05, vec![]),
+            ("d", "stopped", 9.00, vec!["web"]),
+        ] {
+            let xnode = XNode::new(id.to_string(), id.to_string(), status.to_string(), String::new());
+            let tags = tags.into_iter().map(String::from).collect();
+            inventory
+                .add_xnode(&xnode, "vultr".to_string(), "small".to_string(), cost, tags)
+                .unwrap();
+        }
+
+        let groups = inventory.cost_by(CostGrouping::Tag);
+        let summary: Vec<(&str, usize)> = groups.iter().map(|g| (g.group.as_str(), g.nodes)).collect();
+        assert_eq!(summary, [("web", 2), ("staging", 1), (UNTAGGED, 1)]);
+        assert!((groups[0].hourly - 0.40).abs() < 1e-9);
+        assert!((groups[0].monthly - 0.40 * 24.0 * 30.0).abs() < 1e-9);
+
+        let providers = inventory.cost_by(CostGrouping::Provider);
+        assert_eq!(providers.len(), 1);
+        assert_eq!(providers[0].nodes, 3);
+    }
+
+    #[test]
+    fn test_cost_log_failure_does_not_fail_save() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut inventory =
+            XNodeInventory::new(Some(temp_dir.path().join("inventory.json"))).unwrap();
+        // A directory where the cost log belongs makes every cost log write fail
+        fs::create_dir(temp_dir.path().join(COST_LOG_FILE)).unwrap();
+
+        let xnode = XNode::new("node-1".to_string(), "web".to_string(), "running".to_string(), "10.0.0.1".to_string());
+        inventory
+            .add_xnode(&xnode, "vultr".to_string(), "vc2-1c-1gb".to_string(), 0.01, vec![])
+            .unwrap();
+
+        let reloaded = XNodeInventory::new(Some(temp_dir.path().join("inventory.json"))).unwrap();
+        assert!(reloaded.get_xnode("node-1").is_some());
+    }
+
+    #[test]
+    fn test_transaction_saves_once_or_rolls_back() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("inventory.json");
+        let mut inventory = XNodeInventory::new(Some(file.clone())).unwrap();
+        let node = |id: &str| XNode::new(id.to_string(), id.to_string(), "running".to_string(), "10.0.0.1".to_string());
+        inventory.add_xnode(&node("a"), "vultr".to_string(), "small".to_string(), 0.1, vec![]).unwrap();
+
+        // Nothing reaches the file until the transaction returns
+        inventory
+            .transaction(|inv| {
+                inv.add_xnode(&node("b"), "vultr".to_string(), "small".to_string(), 0.1, vec![])?;
+                inv.transaction(|inv| inv.update_xnode("a", XNodeUpdate { status: Some("stopped".to_string()), ..Default::default() }))?;
+                let on_disk = XNodeInventory::new(Some(file.clone()))?;
+                assert!(on_disk.get_xnode("b").is_none());
+                assert_eq!(on_disk.get_xnode("a").unwrap().status, "running");
+                Ok(())
+            })
+            .unwrap();
+        let on_disk = XNodeInventory::new(Some(file.clone())).unwrap();
+        assert!(on_disk.get_xnode("b").is_some());
+        assert_eq!(on_disk.get_xnode("a").unwrap().status, "stopped");
+        assert_eq!(on_disk.metadata.total_running, 1);
+
+        // A failure part way undoes the earlier changes in memory and on disk
+        let before = fs::read_to_string(&file).unwrap();
+        let err = inventory
+            .transaction(|inv| {
+                inv.remove_xnode("b", None)?;
+                inv.add_xnode(&node("c"), "vultr".to_string(), "small".to_string(), 0.1, vec![])?;
+                inv.add_xnode(&node("a"), "vultr".to_string(), "small".to_string(), 0.1, vec![])
+            })
+            .unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+        assert!(inventory.get_xnode("b").is_some());
+        assert!(inventory.get_xnode("c").is_none());
+        assert_eq!(inventory.metadata.total_running, 1);
+        assert_eq!(fs::read_to_string(&file).unwrap(), before);
+
+        // Saves work normally again afterwards
+        inventory.remove_xnode("b", None).unwrap();
+        assert!(XNodeInventory::new(Some(file)).unwrap().get_xnode("b").is_none());
+    }
+
+    #[test]
+    fn test_import_csv_saves_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let csv = temp_dir.path().join("nodes.csv");
+        let rows: Vec<String> = (0..50)
+            .map(|i| format!("n{i},node {i},vultr,running,10.0.0.{i},ewr,2026-01-01T00:00:00Z,0.1,web"))
+            .collect();
+        fs::write(&csv, format!("{}\n{}\n", CSV_HEADER, rows.join("\n"))).unwrap();
+
+        let file = temp_dir.path().join("inventory.json");
+        let mut inventory = XNodeInventory::new(Some(file.clone())).unwrap();
+        assert_eq!(inventory.import_csv(csv.to_str().unwrap()).unwrap(), 50);
+
+        // A single save leaves no backup of an earlier partial write
+        assert!(!file.with_extension("json.backup").exists());
+        assert!(!file.with_extension("json.tmp").exists());
+        let reloaded = XNodeInventory::new(Some(file)).unwrap();
+        assert_eq!(reloaded.list_all().len(), 50);
+        assert_eq!(reloaded.get_xnode("n7").unwrap().tags, ["web"]);
+    }
+
+    #[test]
+    fn test_load_migrates_older_inventory() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("inventory.json");
+        let original = serde_json::json!({
+            "version": "1.0",
+            "last_updated": "2026-01-01T00:00:00Z",
+            "xnodes": {},
+            "history": [],
+            "metadata": {"total_deployed": 2, "total_running": 0, "total_lifetime_cost": 1.5}
+        })
+        .to_string();
+        fs::write(&file, &original).unwrap();
+
+        let inventory = XNodeInventory::new(Some(file.clone())).unwrap();
+        assert_eq!(inventory.metadata.total_deployed, 2);
+        let upgraded: serde_json::Value = serde_json::from_str(&fs::read_to_string(&file).unwrap()).unwrap();
+        assert_eq!(upgraded["version"], VERSION);
+        assert_eq!(fs::read_to_string(file.with_extension("json.v1.0.backup")).unwrap(), original);
+
+        // Files without a version are the oldest layout
+        let mut unversioned = serde_json::json!({"xnodes": {}});
+        assert_eq!(migrate_inventory(&mut unversioned).unwrap().as_deref(), Some("1.0"));
+        assert_eq!(unversioned["version"], VERSION);
+
+        // Current files are left alone; newer ones are refused, not misread
+        assert_eq!(migrate_inventory(&mut upgraded.clone()).unwrap(), None);
+        fs::write(&file, r#"{"version": "9.0"}"#).unwrap();
+        let err = XNodeInventory::new(Some(file)).err().unwrap();
+        assert!(err.to_string().contains("version 9.0"));
     }
 }
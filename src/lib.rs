@@ -1,5 +1,7 @@
 // Core modules for the CLI framework
+pub mod capsule;
 pub mod config;
+pub mod deploy_profile;
 pub mod openmesh;
 pub mod providers;
 pub mod ui;
@@ -13,7 +15,8 @@ pub mod inventory;
 pub mod cost;
 pub mod openmesh_cli;
 
-// API clients and HTTP integration
+// API clients and HTTP integration; its file headers are `///` blocks
+#[allow(clippy::empty_line_after_doc_comments)]
 pub mod api;
 
 // Nix integration modules
@@ -23,7 +26,23 @@ pub mod nixos;
 // Embedded key-value datastore
 pub mod datastore;
 
+// Process exit codes
+pub mod exit;
+
+// Release checks
+pub mod update;
+
+// Glob matching helpers
+pub mod glob;
+
+// Graceful shutdown on SIGINT/SIGTERM
+pub mod shutdown;
+
+#[cfg(test)]
+mod test_support;
+
 // Re-export for convenience
+pub use capsule::Capsule;
 pub use config::*;
 pub use openmesh::*;
 pub use providers::*;
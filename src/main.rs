@@ -1,27 +1,84 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use colored::*;
+use dialoguer::Confirm;
+use std::io::IsTerminal;
 
 use capsule::config::*;
 use capsule::openmesh::{handle_openmesh_command, handle_xnode_command, OpenMeshCommands, XnodeCommands};
 use capsule::ui::*;
-use capsule::datastore::DataStore;
-
+use capsule::datastore::{CompressionAlgorithm, DataStore, DataStoreConfig};
+use capsule::exit::{self, CliError};
+use capsule::monitoring::history::HistoryMetric;
+
+mod backup;
+mod init;
+mod nixos_cli;
+mod self_update;
+mod send;
 mod server;
 
 #[derive(Parser)]
 #[command(name = "capsule")]
 #[command(version = "0.1.0")]
 #[command(about = "🌱 Capsule - User-friendly server configuration tool", long_about = None)]
+#[command(after_help = "Exit codes:\n  0  Success\n  1  General failure\n  2  Usage error or refused operation\n  3  Requested resource not found\n  130  Interrupted before finishing")]
 struct Cli {
+    /// Skip banners, headers and tips; print only results and errors
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Print tables at full width even if they overflow the terminal
+    #[arg(long, global = true, conflicts_with = "compact")]
+    wide: bool,
+
+    /// Print table rows as blocks of `Column: value` lines
+    #[arg(long, global = true)]
+    compact: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
+    /// 🧭 Create a profile step by step (stacks, editor, provider key)
+    Init {
+        /// Profile name (default: default)
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Stack to enable (repeatable)
+        #[arg(long = "stack", value_name = "STACK")]
+        stacks: Vec<String>,
+
+        /// Editor for `capsule config edit`
+        #[arg(long)]
+        editor: Option<String>,
+
+        /// Provider to store an API key for
+        #[arg(long, requires = "api_key")]
+        provider: Option<String>,
+
+        /// API key for --provider
+        #[arg(long, requires = "provider")]
+        api_key: Option<String>,
+
+        /// Take all answers from flags instead of prompting
+        #[arg(long)]
+        non_interactive: bool,
+
+        /// Replace an existing profile with the same name
+        #[arg(long)]
+        force: bool,
+    },
+
     /// Show current configuration
-    Show,
+    Show {
+        /// Output format: text or json
+        #[arg(long, default_value = "text")]
+        format: ShowFormat,
+    },
 
     /// List available technology stacks
     Stacks,
@@ -36,6 +93,10 @@ enum Commands {
     Remove {
         /// Stack name to remove
         stack: String,
+
+        /// Remove it even if another enabled stack depends on it
+        #[arg(long)]
+        force: bool,
     },
 
     /// List all profiles
@@ -47,6 +108,12 @@ enum Commands {
         command: ProfileCommands,
     },
 
+    /// Edit configuration files
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+
     /// Package management commands
     Pkg {
         #[command(subcommand)]
@@ -65,18 +132,54 @@ enum Commands {
         command: XnodeCommands,
     },
 
+    /// 📈 xNode health, metrics and alerts
+    Monitor {
+        #[command(subcommand)]
+        command: MonitorCommands,
+    },
+
     /// 💾 Embedded key-value datastore
     Data {
         #[command(subcommand)]
         command: DataCommands,
     },
 
+    /// ❄️ NixOS configuration generation and management
+    Nixos {
+        #[command(subcommand)]
+        command: nixos_cli::NixOSCommands,
+    },
+
     /// 📸 Server snapshot and restore
     Server {
         #[command(subcommand)]
         command: ServerCommands,
     },
 
+    /// 💼 Back up all capsule state (~/.capsule) to a tar.gz archive
+    Backup {
+        /// Archive to write
+        file: std::path::PathBuf,
+
+        /// Keep API keys and other secrets in providers.yml
+        #[arg(long)]
+        include_secrets: bool,
+    },
+
+    /// ♻️  Restore capsule state from a backup archive
+    Restore {
+        /// Archive written by `capsule backup`
+        file: std::path::PathBuf,
+
+        /// Overwrite existing files without asking
+        #[arg(long)]
+        force: bool,
+
+        /// Only show what would be restored
+        #[arg(long)]
+        dry_run: bool,
+    },
+
     /// 📤 Send capsule binary to remote server
     Send {
         /// Remote server (user@host or host)
@@ -85,6 +188,24 @@ enum Commands {
         /// Remote installation path
         #[arg(short, long, default_value = "/usr/local/bin/capsule")]
         path: String,
+
+        /// Retry the transfer on network failures
+        #[arg(long, default_value_t = 3)]
+        retries: u32,
+    },
+
+    /// Show version information
+    Version {
+        /// Check GitHub for a newer release
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Update capsule to the latest release
+    Update {
+        /// Only report what would be downloaded
+        #[arg(long)]
+        dry_run: bool,
     },
 }
 
@@ -95,26 +216,58 @@ enum ServerCommands {
         /// Output directory for snapshot
         #[arg(default_value = "./capsule-snapshot")]
         output: std::path::PathBuf,
+
+        /// Also include the active profile's packages in packages.nix
+        #[arg(long)]
+        include_packages_from_profile: bool,
+
+        /// Skip /etc files matching this glob (repeatable)
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+
+        /// Don't apply the built-in excludes (shadow files, private keys)
+        #[arg(long)]
+        no_default_excludes: bool,
+
+        /// Write only a passphrase-protected <output>/<name>.tar.enc archive
+        #[arg(long)]
+        encrypt: bool,
+
+        /// Read the encryption passphrase from a file instead of prompting
+        #[arg(long, requires = "encrypt")]
+        key_file: Option<std::path::PathBuf>,
     },
 
     /// Restore server from snapshot
     Unpack {
-        /// Snapshot directory to restore from
+        /// Snapshot directory (or encrypted .tar.enc archive) to restore from
         snapshot: std::path::PathBuf,
 
         /// Dry run - show what would be done
         #[arg(long)]
         dry_run: bool,
+
+        /// Restore only this component: packages, users, etc or services (repeatable; default all)
+        #[arg(long, value_name = "COMPONENT")]
+        only: Vec<server::RestoreStep>,
+
+        /// Passphrase file for encrypted snapshots
+        #[arg(long)]
+        key_file: Option<std::path::PathBuf>,
     },
 
     /// Validate snapshot integrity with checksums
     Validate {
-        /// Snapshot directory to validate
+        /// Snapshot directory (or encrypted .tar.enc archive) to validate
         snapshot: std::path::PathBuf,
 
         /// Verbose output showing all file checks
         #[arg(short, long)]
         verbose: bool,
+
+        /// Passphrase file for encrypted snapshots
+        #[arg(long)]
+        key_file: Option<std::path::PathBuf>,
     },
 }
 
@@ -130,11 +283,14 @@ enum DataCommands {
     Set {
         /// Key to store
         key: String,
-        /// Value to store (or use --file)
+        /// Value to store (or use --file / --stdin)
         value: Option<String>,
         /// Store contents of a file
-        #[arg(short, long)]
+        #[arg(short, long, conflicts_with = "value")]
         file: Option<std::path::PathBuf>,
+        /// Store everything read from standard input
+        #[arg(long, conflicts_with_all = ["value", "file"])]
+        stdin: bool,
     },
 
     /// Delete a key
@@ -143,6 +299,28 @@ enum DataCommands {
         key: String,
     },
 
+    /// Rename a key
+    Rename {
+        /// Current key
+        old: String,
+        /// New key
+        new: String,
+        /// Replace the new key if it already exists
+        #[arg(long)]
+        overwrite: bool,
+    },
+
+    /// Copy a value to another key
+    Copy {
+        /// Key to copy
+        src: String,
+        /// Destination key
+        dst: String,
+        /// Replace the destination if it already exists
+        #[arg(long)]
+        overwrite: bool,
+    },
+
     /// List all keys
     Keys,
 
@@ -167,12 +345,45 @@ enum DataCommands {
     },
 
     /// Show database statistics
-    Stats,
+    Stats {
+        /// List keys by stored size, largest first
+        #[arg(long)]
+        per_key: bool,
 
-    /// Export all data to directory
+        /// Only the N largest keys (implies --per-key)
+        #[arg(long, value_name = "N")]
+        top: Option<usize>,
+
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show or change compression settings
+    Config {
+        /// Compress values larger than this many bytes
+        #[arg(long)]
+        threshold: Option<usize>,
+
+        /// Compression algorithm: gzip or zlib
+        #[arg(long)]
+        algorithm: Option<CompressionAlgorithm>,
+
+        /// Compression level, 0-9
+        #[arg(long)]
+        level: Option<u32>,
+    },
+
+    /// Export data to directory, one file per key
     Export {
         /// Output directory
         output: std::path::PathBuf,
+
+        /// Only export keys matching this glob. `*` and `?` stop at `/`,
+        /// `**` crosses it, `[a-z]` matches a class and `\` escapes the
+        /// next character. Quote the pattern so the shell leaves it alone.
+        #[arg(long, value_name = "GLOB")]
+        key: Option<String>,
     },
 
     /// Clear all data (WARNING: destructive!)
@@ -212,6 +423,151 @@ enum ProfileCommands {
     },
 }
 
+#[derive(Subcommand)]
+enum MonitorCommands {
+    /// Show monitoring dashboard
+    Status,
+
+    /// Show the dashboard, or write it as a standalone HTML page
+    Dashboard {
+        /// Write an HTML page to this file instead of printing
+        #[arg(long, value_name = "FILE")]
+        html: Option<std::path::PathBuf>,
+    },
+
+    /// Check health of an xNode
+    Health {
+        /// xNode ID
+        xnode_id: String,
+
+        /// Check again even if a recent result is cached
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Collect resource metrics
+    Metrics {
+        /// xNode ID
+        xnode_id: String,
+
+        /// Collect again even if a recent sample is cached
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Show recorded health and metrics for an xNode
+    History {
+        /// xNode ID
+        xnode_id: String,
+
+        /// Only show one metric: cpu, memory or disk
+        #[arg(long)]
+        metric: Option<HistoryMetric>,
+
+        /// Only show samples from this far back, e.g. 30m, 1h, 2d
+        #[arg(long)]
+        last: Option<String>,
+
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// List active alerts
+    Alerts,
+
+    /// Acknowledge an alert
+    Ack {
+        /// Alert ID
+        alert_id: String,
+    },
+
+    /// Resolve an alert
+    Resolve {
+        /// Alert ID
+        alert_id: String,
+    },
+
+    /// Stop delivering alerts for an xNode or tag for a while (they are still recorded)
+    Silence {
+        /// xNode ID
+        #[arg(required_unless_present = "tag", conflicts_with = "tag")]
+        xnode_id: Option<String>,
+
+        /// Silence every xNode with this tag instead
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// How long to silence for, e.g. 30m, 2h, 1d
+        #[arg(long, default_value = "1h")]
+        duration: String,
+
+        /// Why, shown in `monitor silences`
+        #[arg(long)]
+        reason: Option<String>,
+    },
+
+    /// List active silences
+    Silences,
+
+    /// Remove silences
+    Unsilence {
+        /// Silence ID or xNode ID
+        #[arg(required_unless_present_any = ["tag", "all"])]
+        id: Option<String>,
+
+        /// Remove silences for this tag
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// Remove every silence
+        #[arg(long, conflicts_with_all = ["id", "tag"])]
+        all: bool,
+    },
+
+    /// Show monitoring configuration
+    Config,
+
+    /// Serve dashboard, health and Prometheus metrics over HTTP while
+    /// checking running xNodes in the background
+    #[cfg(feature = "server")]
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        listen: std::net::SocketAddr,
+    },
+
+    /// Live dashboard (auto-refresh)
+    Watch,
+
+    /// Check every running xNode once and exit; fails if any is unhealthy
+    #[command(name = "check-all")]
+    CheckAll {
+        /// Collect resource metrics as well
+        #[arg(long)]
+        metrics: bool,
+
+        /// Print results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Open a profile in your editor and validate it on save
+    Edit {
+        /// Profile name (defaults to the active profile)
+        profile: Option<String>,
+    },
+
+    /// Check that a profile's stacks can be installed together
+    Validate {
+        /// Profile name (defaults to the active profile)
+        profile: Option<String>,
+    },
+}
+
 #[derive(Subcommand)]
 enum PkgCommands {
     /// Add custom packages
@@ -227,17 +583,42 @@ enum PkgCommands {
     },
 }
 
-fn main() -> Result<()> {
+fn main() {
     let cli = Cli::parse();
+    set_quiet(cli.quiet);
+    set_table_layout(match (cli.wide, cli.compact) {
+        (true, _) => TableLayout::Wide,
+        (_, true) => TableLayout::Compact,
+        _ => TableLayout::Auto,
+    });
+
+    if let Err(e) = run(cli) {
+        error(&format!("{:#}", e));
+        std::process::exit(exit::exit_code_for(&e));
+    }
+}
 
+fn run(cli: Cli) -> Result<()> {
     match cli.command {
         None => show_overview()?,
-        Some(Commands::Show) => show_config()?,
+        Some(Commands::Init { profile, stacks, editor, provider, api_key, non_interactive, force }) => {
+            init::handle_init_command(init::InitOptions {
+                profile,
+                stacks,
+                editor,
+                provider,
+                api_key,
+                non_interactive,
+                force,
+            })?
+        }
+        Some(Commands::Show { format }) => show_config(format)?,
         Some(Commands::Stacks) => list_stacks()?,
         Some(Commands::Add { stack }) => add_stack(&stack)?,
-        Some(Commands::Remove { stack }) => remove_stack(&stack)?,
+        Some(Commands::Remove { stack, force }) => remove_stack(&stack, force)?,
         Some(Commands::Profiles) => list_profiles()?,
         Some(Commands::Profile { command }) => handle_profile_command(command)?,
+        Some(Commands::Config { command }) => handle_config_command(command)?,
         Some(Commands::Pkg { command }) => handle_pkg_command(command)?,
         Some(Commands::Openmesh { command }) => {
             if let Some(cmd) = command {
@@ -251,9 +632,19 @@ fn main() -> Result<()> {
             // Alias for openmesh xnode
             handle_xnode_command(command)?;
         }
+        Some(Commands::Monitor { command }) => handle_monitor_command(command)?,
         Some(Commands::Data { command }) => handle_data_command(command)?,
+        Some(Commands::Nixos { command }) => nixos_cli::handle_nixos_command(command)?,
         Some(Commands::Server { command }) => handle_server_command(command)?,
-        Some(Commands::Send { server, path }) => handle_send_command(&server, &path)?,
+        Some(Commands::Backup { file, include_secrets }) => handle_backup_command(&file, include_secrets)?,
+        Some(Commands::Restore { file, force, dry_run }) => {
+            handle_restore_command(&file, backup::RestoreOptions { force, dry_run })?
+        }
+        Some(Commands::Send { server, path, retries }) => {
+            send::handle_send_command(&server, &path, retries)?
+        }
+        Some(Commands::Version { check }) => handle_version_command(check)?,
+        Some(Commands::Update { dry_run }) => self_update::handle_update_command(dry_run)?,
     }
 
     Ok(())
@@ -270,6 +661,12 @@ fn show_overview() -> Result<()> {
     );
 
     section_header("🚀 Quick Start");
+    println!(
+        "    {} {} {}",
+        "▸".green().bold(),
+        "capsule init".cyan().bold(),
+        "            Guided first-time setup".white()
+    );
     println!(
         "    {} {} {}",
         "▸".green().bold(),
@@ -377,7 +774,7 @@ fn show_overview() -> Result<()> {
         "    {} {} {}",
         "•".magenta().bold(),
         "backup".cyan().bold(),
-        "             Backup package list".white()
+        "             Back up ~/.capsule".white()
     );
     println!(
         "    {} {} {}",
@@ -402,20 +799,18 @@ fn show_overview() -> Result<()> {
     );
 
     divider();
-    println!();
-    println!(
-        "  {} Run {} for detailed command list",
-        "💡 Tip:".cyan(),
-        "capsule --help".cyan().bold()
-    );
-    println!();
+    tip(&format!("Run {} for detailed command list", "capsule --help".cyan().bold()));
 
     Ok(())
 }
 
-fn show_config() -> Result<()> {
-    let active_name = get_active_config_name()?;
-    let config = load_config(None)?;
+fn show_config(format: ShowFormat) -> Result<()> {
+    let view = ProfileView::active()?;
+    if format == ShowFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&view)?);
+        return Ok(());
+    }
+    let ProfileView { profile: active_name, source, config } = view;
 
     header("⚙  CONFIGURATION");
 
@@ -449,16 +844,19 @@ fn show_config() -> Result<()> {
     section_header("Settings");
     let editor_value = config.editor.as_deref().unwrap_or("vim");
     info_line("Editor", &editor_value.cyan().to_string());
+    if let Some(parent) = &config.extends {
+        info_line("Extends", &parent.cyan().to_string());
+    }
 
     // Show config source
-    if is_builtin_profile(&active_name) {
-        info_line(
+    match source {
+        ProfileSource::Builtin => info_line(
             "Source",
             &"Built-in profile (read-only)".yellow().to_string(),
-        );
-    } else {
-        let config_path = get_config_file(None)?;
-        info_line("Config File", &config_path.display().to_string().white());
+        ),
+        ProfileSource::File { path, .. } => {
+            info_line("Config File", &path.display().to_string().white())
+        }
     }
     println!();
 
@@ -494,13 +892,7 @@ fn list_stacks() -> Result<()> {
     println!("  {} {:14} {}", "○".cyan(), "cuda", "NVIDIA CUDA support".white());
 
     divider();
-    println!();
-    println!(
-        "  {} Use {} to add a stack",
-        "💡 Tip:".cyan(),
-        "capsule add <stack>".cyan().bold()
-    );
-    println!();
+    tip(&format!("Use {} to add a stack", "capsule add <stack>".cyan().bold()));
 
     Ok(())
 }
@@ -508,36 +900,27 @@ fn list_stacks() -> Result<()> {
 fn add_stack(stack: &str) -> Result<()> {
     let active_name = get_active_config_name()?;
 
-    if is_builtin_profile(&active_name) {
-        error(&format!(
-            "Cannot modify built-in profile '{}'. Create a new profile first.",
-            active_name
-        ));
-        return Ok(());
-    }
-
     add_preset(stack, None)?;
     success(&format!("Added stack '{}' to profile '{}'", stack, active_name));
 
     Ok(())
 }
 
-fn remove_stack(stack: &str) -> Result<()> {
+fn remove_stack(stack: &str, force: bool) -> Result<()> {
     let active_name = get_active_config_name()?;
 
-    if is_builtin_profile(&active_name) {
-        error(&format!(
-            "Cannot modify built-in profile '{}'. Create a new profile first.",
-            active_name
-        ));
-        return Ok(());
-    }
-
-    remove_preset(stack, None)?;
+    let dependents = remove_preset(stack, None, force)?;
     success(&format!(
         "Removed stack '{}' from profile '{}'",
         stack, active_name
     ));
+    if !dependents.is_empty() {
+        warning(&format!(
+            "'{}' will still be installed as a dependency of {}",
+            stack,
+            dependents.join(", ")
+        ));
+    }
 
     Ok(())
 }
@@ -572,12 +955,7 @@ fn list_profiles() -> Result<()> {
         "▸".cyan(),
         active.green().bold()
     );
-    println!(
-        "  {} Use {} to switch profiles",
-        "💡 Tip:".cyan(),
-        "capsule profile use <name>".cyan().bold()
-    );
-    println!();
+    tip(&format!("Use {} to switch profiles", "capsule profile use <name>".cyan().bold()));
 
     Ok(())
 }
@@ -589,15 +967,6 @@ fn handle_profile_command(command: ProfileCommands) -> Result<()> {
             success(&format!("Created new profile '{}'", name));
         }
         ProfileCommands::Use { name } => {
-            // Check if profile exists (either built-in or user)
-            if !is_builtin_profile(&name) {
-                let user_configs = list_all_configs()?;
-                if !user_configs.contains(&name) {
-                    error(&format!("Profile '{}' not found", name));
-                    return Ok(());
-                }
-            }
-
             set_active_config_name(&name)?;
             success(&format!("Switched to profile '{}'", name));
         }
@@ -614,15 +983,168 @@ fn handle_profile_command(command: ProfileCommands) -> Result<()> {
     Ok(())
 }
 
+fn handle_monitor_command(command: MonitorCommands) -> Result<()> {
+    use capsule::monitoring::{commands, silences::SilenceTarget, MonitoringSystem};
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+
+    runtime.block_on(async {
+        let mut system = MonitoringSystem::new(None).await?;
+        if let Err(e) = commands::load_xnode_tags(&mut system) {
+            log::warn!("Tag silences unavailable: {:#}", e);
+        }
+
+        match command {
+            MonitorCommands::Status => commands::show_dashboard(&system).await,
+            MonitorCommands::Dashboard { html: Some(path) } => {
+                commands::export_dashboard_html(&system, &path).await
+            }
+            MonitorCommands::Dashboard { html: None } => commands::show_dashboard(&system).await,
+            MonitorCommands::Health { xnode_id, force } => {
+                system.set_force_refresh(force);
+                commands::show_health_check(&mut system, &xnode_id).await
+            }
+            MonitorCommands::Metrics { xnode_id, force } => {
+                system.set_force_refresh(force);
+                commands::show_metrics(&mut system, &xnode_id).await
+            }
+            MonitorCommands::History { xnode_id, metric, last, json } => {
+                commands::show_history(&system, &xnode_id, metric, last.as_deref(), json).await
+            }
+            MonitorCommands::Alerts => commands::list_alerts(&system).await,
+            MonitorCommands::Ack { alert_id } => {
+                commands::acknowledge_alert(&mut system, &alert_id).await
+            }
+            MonitorCommands::Resolve { alert_id } => {
+                commands::resolve_alert(&mut system, &alert_id).await
+            }
+            MonitorCommands::Silence { xnode_id, tag, duration, reason } => {
+                let target = match (xnode_id, tag) {
+                    (_, Some(tag)) => SilenceTarget::Tag(tag),
+                    (Some(id), None) => SilenceTarget::Xnode(id),
+                    (None, None) => unreachable!("clap requires an xNode ID or --tag"),
+                };
+                commands::silence(&mut system, target, &duration, reason).await
+            }
+            MonitorCommands::Silences => commands::list_silences(&system).await,
+            MonitorCommands::Unsilence { id, tag, all } => {
+                commands::unsilence(&mut system, id.as_deref(), tag.as_deref(), all).await
+            }
+            MonitorCommands::Config => commands::show_config(&system).await,
+            MonitorCommands::Watch => commands::watch_dashboard(&mut system).await,
+            MonitorCommands::CheckAll { metrics, json } => {
+                commands::check_all(&mut system, metrics, json).await
+            }
+            #[cfg(feature = "server")]
+            MonitorCommands::Serve { listen } => capsule::monitoring::serve::serve(system, listen).await,
+        }
+    })
+}
+
+fn handle_config_command(command: ConfigCommands) -> Result<()> {
+    match command {
+        ConfigCommands::Edit { profile } => edit_profile(profile)?,
+        ConfigCommands::Validate { profile } => validate_profile(profile)?,
+    }
+
+    Ok(())
+}
+
+fn edit_profile(profile: Option<String>) -> Result<()> {
+    let mut name = match profile {
+        Some(name) => name,
+        None => get_active_config_name()?,
+    };
+
+    // Built-in profiles are read-only, so edit a user copy instead
+    if let Some(builtin) = get_builtin_profile(&name) {
+        let copy = format!("{}-custom", name);
+        if !get_config_file(Some(&copy))?.exists() {
+            save_config(&builtin, Some(&copy))?;
+            println!("{} '{}' is built-in; editing a copy as '{}'", "▸".cyan(), name, copy.green());
+        } else {
+            println!("{} '{}' is built-in; editing your copy '{}'", "▸".cyan(), name, copy.green());
+        }
+        name = copy;
+    }
+
+    let path = get_config_file(Some(&name))?;
+    if !path.exists() {
+        return Err(CliError::not_found(format!("Profile '{}' not found", name)).into());
+    }
+    let original = std::fs::read_to_string(&path)?;
+    let editor = resolve_editor(&load_config(Some(&name))?);
+
+    loop {
+        let mut words = editor.split_whitespace();
+        let program = words.next().unwrap_or("vim");
+        let status = std::process::Command::new(program)
+            .args(words)
+            .arg(&path)
+            .status()
+            .map_err(|e| anyhow::anyhow!("Failed to run editor '{}': {}", editor, e))?;
+        if !status.success() {
+            std::fs::write(&path, &original)?;
+            anyhow::bail!("Editor '{}' exited with {}; profile left unchanged", editor, status);
+        }
+
+        let err = match parse_profile(&std::fs::read_to_string(&path)?) {
+            Ok(_) => break,
+            Err(err) => err,
+        };
+        warning(&err.to_string());
+
+        let reopen = std::io::stdin().is_terminal()
+            && Confirm::new()
+                .with_prompt("Re-open the editor to fix it?")
+                .default(true)
+                .interact()?;
+        if !reopen {
+            std::fs::write(&path, &original)?;
+            return Err(CliError::usage(format!(
+                "{}; profile '{}' left unchanged",
+                err, name
+            ))
+            .into());
+        }
+    }
+
+    success(&format!("Saved profile '{}'", name));
+    for conflict in find_conflicts(&load_config(Some(&name))?)? {
+        warning(&conflict.to_string());
+    }
+    Ok(())
+}
+
+fn validate_profile(profile: Option<String>) -> Result<()> {
+    let name = match profile {
+        Some(name) => name,
+        None => get_active_config_name()?,
+    };
+    let conflicts = find_conflicts(&load_config(Some(&name))?)?;
+    if conflicts.is_empty() {
+        success(&format!("Profile '{}' has no package conflicts", name));
+        return Ok(());
+    }
+
+    for conflict in &conflicts {
+        println!("  {} {}", "✗".red(), conflict);
+    }
+    Err(CliError::usage(format!(
+        "Profile '{}' has {} package conflict(s); remove one of the conflicting stacks or packages",
+        name,
+        conflicts.len()
+    ))
+    .into())
+}
+
 fn handle_pkg_command(command: PkgCommands) -> Result<()> {
     let active_name = get_active_config_name()?;
 
     if is_builtin_profile(&active_name) {
-        error(&format!(
-            "Cannot modify built-in profile '{}'. Create a new profile first.",
-            active_name
-        ));
-        return Ok(());
+        return Err(ConfigError::BuiltinReadOnly(active_name).into());
     }
 
     match command {
@@ -648,7 +1170,7 @@ fn handle_pkg_command(command: PkgCommands) -> Result<()> {
 }
 
 fn handle_data_command(command: DataCommands) -> Result<()> {
-    let ds = DataStore::new()?;
+    let mut ds = DataStore::new()?;
 
     match command {
         DataCommands::Get { key } => {
@@ -665,12 +1187,16 @@ fn handle_data_command(command: DataCommands) -> Result<()> {
                     }
                 }
             } else {
-                error(&format!("Key '{}' not found", key));
+                return Err(CliError::not_found(format!("Key '{}' not found", key)).into());
             }
         }
 
-        DataCommands::Set { key, value, file } => {
-            if let Some(file_path) = file {
+        DataCommands::Set { key, value, file, stdin } => {
+            if stdin {
+                let value = read_stdin_value()?;
+                ds.set(&key, &value)?;
+                success(&format!("Stored key '{}' ({} bytes)", key, value.len()));
+            } else if let Some(file_path) = file {
                 ds.set_file(&key, &file_path)?;
                 let metadata = std::fs::metadata(&file_path)?;
                 success(&format!("Stored file '{}' ({} bytes) as key '{}'", 
@@ -679,7 +1205,7 @@ fn handle_data_command(command: DataCommands) -> Result<()> {
                 ds.set(&key, val.as_bytes())?;
                 success(&format!("Stored key '{}' ({} bytes)", key, val.len()));
             } else {
-                error("Must provide either value or --file");
+                return Err(CliError::usage("Must provide either value, --file or --stdin").into());
             }
         }
 
@@ -687,10 +1213,20 @@ fn handle_data_command(command: DataCommands) -> Result<()> {
             if ds.delete(&key)? {
                 success(&format!("Deleted key '{}'", key));
             } else {
-                error(&format!("Key '{}' not found", key));
+                return Err(CliError::not_found(format!("Key '{}' not found", key)).into());
             }
         }
 
+        DataCommands::Rename { old, new, overwrite } => {
+            ds.rename(&old, &new, overwrite)?;
+            success(&format!("Renamed key '{}' to '{}'", old, new));
+        }
+
+        DataCommands::Copy { src, dst, overwrite } => {
+            ds.copy(&src, &dst, overwrite)?;
+            success(&format!("Copied key '{}' to '{}'", src, dst));
+        }
+
         DataCommands::Keys => {
             let keys = ds.list_keys()?;
             if keys.is_empty() {
@@ -760,7 +1296,7 @@ fn handle_data_command(command: DataCommands) -> Result<()> {
             if ds.get_file(&key, &output)? {
                 success(&format!("Exported key '{}' to '{}'", key, output.display()));
             } else {
-                error(&format!("Key '{}' not found", key));
+                return Err(CliError::not_found(format!("Key '{}' not found", key)).into());
             }
         }
 
@@ -771,8 +1307,28 @@ fn handle_data_command(command: DataCommands) -> Result<()> {
                 file.display(), metadata.len(), key));
         }
 
-        DataCommands::Stats => {
+        DataCommands::Stats { per_key, top, json } => {
             let (count, disk_size) = ds.stats()?;
+            let compression = ds.compression_stats()?;
+            let largest = if per_key || top.is_some() {
+                let mut usage = ds.key_usage()?;
+                usage.truncate(top.unwrap_or(usize::MAX));
+                Some(usage)
+            } else {
+                None
+            };
+
+            if json {
+                let stats = serde_json::json!({
+                    "keys": count,
+                    "disk_bytes": disk_size,
+                    "compression": compression,
+                    "largest_keys": largest,
+                });
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+                return Ok(());
+            }
+
             header("💾 DATASTORE STATISTICS");
             
             println!("  {} {}", "Total keys:".white().bold(), count.to_string().cyan());
@@ -786,23 +1342,63 @@ fn handle_data_command(command: DataCommands) -> Result<()> {
                 }
             );
             
+            println!("  {} {} of {} values, {:.0}% of original size",
+                "Compression:".white().bold(),
+                compression.compressed_values.to_string().cyan(),
+                compression.values,
+                compression.ratio() * 100.0
+            );
+
             let data_dir = home::home_dir()
                 .ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?
                 .join(".capsule").join("data");
             println!("  {} {}", "Location:".white().bold(), data_dir.display().to_string().cyan());
             println!();
+
+            if let Some(largest) = largest {
+                print_key_usage(&largest);
+            }
+        }
+
+        DataCommands::Config { threshold, algorithm, level } => {
+            if threshold.is_some() || algorithm.is_some() || level.is_some() {
+                let current = ds.config().clone();
+                ds.set_config(DataStoreConfig {
+                    compression_threshold: threshold.unwrap_or(current.compression_threshold),
+                    algorithm: algorithm.unwrap_or(current.algorithm),
+                    level: level.unwrap_or(current.level),
+                })?;
+                success("Updated datastore settings (existing values are unchanged)");
+            }
+
+            let config = ds.config();
+            header("💾 DATASTORE SETTINGS");
+            info_line("Compress above", &format!("{} bytes", config.compression_threshold).cyan().to_string());
+            info_line("Algorithm", &config.algorithm.to_string().cyan().to_string());
+            info_line("Level", &config.level.to_string().cyan().to_string());
+            println!();
         }
 
-        DataCommands::Export { output } => {
+        DataCommands::Export { output, key } => {
             std::fs::create_dir_all(&output)?;
-            let count = ds.export(&output)?;
-            success(&format!("Exported {} keys to '{}'", count, output.display()));
+            let count = ds.export(&output, key.as_deref())?;
+            match key {
+                Some(pattern) if count == 0 => {
+                    warning(&format!("No keys match '{}'", pattern));
+                }
+                Some(pattern) => success(&format!(
+                    "Exported {} keys matching '{}' to '{}'",
+                    count,
+                    pattern,
+                    output.display()
+                )),
+                None => success(&format!("Exported {} keys to '{}'", count, output.display())),
+            }
         }
 
         DataCommands::Clear { confirm } => {
             if !confirm {
-                error("This will delete ALL data. Use --confirm to proceed.");
-                return Ok(());
+                return Err(CliError::usage("This will delete ALL data. Use --confirm to proceed.").into());
             }
             
             let count = ds.clear()?;
@@ -813,122 +1409,143 @@ fn handle_data_command(command: DataCommands) -> Result<()> {
     Ok(())
 }
 
+/// Read piped input for `data set --stdin`, refusing a terminal or an
+/// empty pipe (store an empty value explicitly with `data set <key> ""`)
+/// Table of keys by stored size for `data stats --per-key`
+fn print_key_usage(usage: &[capsule::datastore::KeyUsage]) {
+    use prettytable::{Table, Row, Cell, format};
+
+    if usage.is_empty() {
+        println!("{}", "No data stored".yellow());
+        return;
+    }
+
+    section_header("Largest keys");
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(Row::new(vec![
+        Cell::new("Key").style_spec("Fb"),
+        Cell::new("Stored").style_spec("Fb"),
+        Cell::new("Share").style_spec("Fb"),
+        Cell::new("Compressed").style_spec("Fb"),
+    ]));
+    for key in usage {
+        let size = if key.stored_bytes < 1024 {
+            format!("{} B", key.stored_bytes)
+        } else if key.stored_bytes < 1024 * 1024 {
+            format!("{:.1} KB", key.stored_bytes as f64 / 1024.0)
+        } else {
+            format!("{:.1} MB", key.stored_bytes as f64 / (1024.0 * 1024.0))
+        };
+        table.add_row(Row::new(vec![
+            Cell::new(&key.key).style_spec("Fc"),
+            Cell::new(&size).style_spec("Fg"),
+            Cell::new(&format!("{:.1}%", key.share * 100.0)),
+            Cell::new(if key.compressed { "✓" } else { "-" }),
+        ]));
+    }
+    table.printstd();
+    println!();
+}
+
+fn read_stdin_value() -> Result<Vec<u8>> {
+    use std::io::Read;
+
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Err(CliError::usage("--stdin expects piped input, e.g. `cat file | capsule data set key --stdin`").into());
+    }
+
+    let mut value = Vec::new();
+    stdin.read_to_end(&mut value)?;
+    if value.is_empty() {
+        return Err(CliError::usage("Standard input was empty; use `capsule data set <key> \"\"` to store an empty value").into());
+    }
+    Ok(value)
+}
+
 fn handle_server_command(command: ServerCommands) -> Result<()> {
     match command {
-        ServerCommands::Pack { output } => {
-            server::pack(&output)?;
+        ServerCommands::Pack {
+            output,
+            include_packages_from_profile,
+            exclude,
+            no_default_excludes,
+            encrypt,
+            key_file,
+        } => {
+            let options = server::PackOptions {
+                include_profile: include_packages_from_profile,
+                excludes: exclude,
+                no_default_excludes,
+                encrypt,
+                key_file,
+            };
+            server::pack(&output, &options)?;
         }
-        ServerCommands::Unpack { snapshot, dry_run } => {
-            server::unpack(&snapshot, dry_run)?;
+        ServerCommands::Unpack { snapshot, dry_run, only, key_file } => {
+            server::unpack(&snapshot, dry_run, &only, key_file)?;
         }
-        ServerCommands::Validate { snapshot, verbose } => {
-            server::validate(&snapshot, verbose)?;
+        ServerCommands::Validate { snapshot, verbose, key_file } => {
+            server::validate(&snapshot, verbose, key_file)?;
         }
     }
 
     Ok(())
 }
 
-fn handle_send_command(server: &str, remote_path: &str) -> Result<()> {
-    use anyhow::Context;
-    use std::process::Command;
-
-    println!("{}", "📤 Sending capsule binary to remote server...".cyan().bold());
-    println!();
-
-    // Get the current binary path
-    let binary_path = std::env::current_exe()
-        .context("Failed to locate capsule binary")?;
-
-    println!("{} Binary location: {}",
-        "▸".green().bold(),
-        binary_path.display().to_string().cyan());
-
-    // Check binary size
-    let metadata = std::fs::metadata(&binary_path)
-        .context("Failed to read binary metadata")?;
-    let size_mb = metadata.len() as f64 / (1024.0 * 1024.0);
-
-    println!("{} Binary size: {:.2} MB",
-        "▸".green().bold(),
-        size_mb.to_string().cyan());
-    println!();
-
-    // Use SCP to transfer the binary
-    println!("{} Transferring to {}...",
-        "▸".green().bold(),
-        server.cyan());
-
-    let temp_path = format!("/tmp/capsule-{}", std::process::id());
-
-    let scp_status = Command::new("scp")
-        .arg(&binary_path)
-        .arg(format!("{}:{}", server, temp_path))
-        .status()
-        .context("Failed to execute scp")?;
-
-    if !scp_status.success() {
-        anyhow::bail!("SCP transfer failed");
+fn handle_backup_command(file: &std::path::Path, include_secrets: bool) -> Result<()> {
+    let count = backup::create_backup(&get_capsule_dir(), file, include_secrets)?;
+    success(&format!("Backed up {} files to {}", count, file.display()));
+    if include_secrets {
+        warning("The archive contains provider API keys; store it somewhere private");
+    } else {
+        println!("  Provider keys were left out; pass --include-secrets to keep them");
     }
+    Ok(())
+}
 
-    println!("{} Transfer complete", "  ✓".green());
-    println!();
-
-    // Install to remote path
-    println!("{} Installing to {}...",
-        "▸".green().bold(),
-        remote_path.cyan());
+fn handle_restore_command(file: &std::path::Path, options: backup::RestoreOptions) -> Result<()> {
+    let restored = backup::restore_backup(file, &get_capsule_dir(), options)?;
+    if !options.dry_run {
+        success(&format!("Restored {} files into {}", restored.len(), get_capsule_dir().display()));
+    }
+    Ok(())
+}
 
-    let install_cmd = format!(
-        "sudo mv {} {} && sudo chmod +x {}",
-        temp_path, remote_path, remote_path
-    );
+fn handle_version_command(check: bool) -> Result<()> {
+    use capsule::update::{check_for_update, describe_check_error, CURRENT_VERSION, GITHUB_API_URL};
 
-    let ssh_status = Command::new("ssh")
-        .arg(server)
-        .arg(&install_cmd)
-        .status()
-        .context("Failed to execute ssh")?;
+    println!("capsule {}", CURRENT_VERSION);
 
-    if !ssh_status.success() {
-        anyhow::bail!("Remote installation failed");
+    if !check {
+        return Ok(());
     }
 
-    println!("{} Installation complete", "  ✓".green());
-    println!();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
 
-    // Verify installation
-    println!("{} Verifying installation...", "▸".green().bold());
+    let result = runtime
+        .block_on(check_for_update(GITHUB_API_URL))
+        .map_err(|e| anyhow::anyhow!(describe_check_error(&e)))?;
 
-    let verify_cmd = format!("{} --version", remote_path);
-    let verify_status = Command::new("ssh")
-        .arg(server)
-        .arg(&verify_cmd)
-        .status()
-        .context("Failed to verify installation")?;
+    println!();
+    info_line("Installed", &result.current);
+    info_line("Latest", result.latest.version());
+    info_line("Release", &result.latest.html_url);
+    println!();
 
-    if !verify_status.success() {
-        println!("{} {} (binary installed but may not be in PATH)",
-            "  !".yellow(),
-            "Warning: verification failed".yellow());
+    if result.update_available {
+        warning(&format!(
+            "A newer version is available: {} → {}",
+            result.current,
+            result.latest.version()
+        ));
     } else {
-        println!("{} Capsule is ready on remote server", "  ✓".green());
+        success("You are running the latest version");
     }
     println!();
 
-    println!("{} Capsule successfully deployed to {}",
-        "✅".green(),
-        server.green().bold());
-    println!();
-    println!("{} Connect: {} {}",
-        "💡 Tip:".yellow(),
-        "ssh".cyan().bold(),
-        server.cyan());
-    println!("{} Run: {} {}",
-        "💡 Tip:".yellow(),
-        "ssh".cyan().bold(),
-        format!("{} 'capsule --help'", server).cyan());
-    println!();
-
     Ok(())
 }
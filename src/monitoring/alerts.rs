@@ -30,6 +30,8 @@ pub enum AlertType {
     SshUnreachable,
     HttpError,
     CostThreshold,
+    /// Usage far from the node's own baseline
+    MetricAnomaly,
 }
 
 impl std::fmt::Display for AlertType {
@@ -42,10 +44,34 @@ impl std::fmt::Display for AlertType {
             AlertType::SshUnreachable => write!(f, "ssh_unreachable"),
             AlertType::HttpError => write!(f, "http_error"),
             AlertType::CostThreshold => write!(f, "cost_threshold"),
+            AlertType::MetricAnomaly => write!(f, "metric_anomaly"),
         }
     }
 }
 
+/// A monthly spend level that raises a `CostThreshold` alert of `severity`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CostTier {
+    /// Total monthly cost of running xNodes, in USD
+    pub monthly: f64,
+    pub severity: AlertSeverity,
+}
+
+/// Index into `tiers` (sorted by `monthly`) of the tier to alert at for a
+/// monthly `total`, given the tier alerted at now. A higher tier takes over
+/// as soon as it is exceeded; the current one holds until the total falls
+/// more than `hysteresis` (a fraction of the tier) below it.
+pub fn cost_tier_level(
+    tiers: &[CostTier],
+    total: f64,
+    current: Option<usize>,
+    hysteresis: f64,
+) -> Option<usize> {
+    let exceeded = tiers.iter().rposition(|tier| total >= tier.monthly);
+    let held = tiers.iter().rposition(|tier| total >= tier.monthly * (1.0 - hysteresis));
+    exceeded.max(held.zip(current).map(|(held, current)| held.min(current)))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Alert {
     pub id: String,
@@ -58,6 +84,9 @@ pub struct Alert {
     pub acknowledged: bool,
     #[serde(default)]
     pub resolved: bool,
+    /// Raised while a silence covered the xNode, so it was not delivered
+    #[serde(default)]
+    pub silenced: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<serde_json::Value>,
 }
@@ -85,6 +114,7 @@ impl Alert {
             timestamp,
             acknowledged: false,
             resolved: false,
+            silenced: false,
             metadata: None,
         }
     }
@@ -283,27 +313,30 @@ impl AlertStore {
     }
 
     pub fn get_active_alerts(&self) -> Vec<&Alert> {
-        self.active_alerts
-            .values()
-            .filter(|a| !a.resolved)
-            .collect()
+        oldest_first(self.active_alerts.values().filter(|a| !a.resolved))
     }
 
     pub fn get_alerts_for_xnode(&self, xnode_id: &str) -> Vec<&Alert> {
-        self.active_alerts
-            .values()
-            .filter(|a| a.xnode_id == xnode_id && !a.resolved)
-            .collect()
+        oldest_first(
+            self.active_alerts
+                .values()
+                .filter(|a| a.xnode_id == xnode_id && !a.resolved),
+        )
     }
 
     pub fn has_similar_alert(&self, xnode_id: &str, alert_type: AlertType) -> bool {
+        self.similar_alert(xnode_id, alert_type).is_some()
+    }
+
+    /// The unresolved alert of this type for this xNode, if any
+    pub fn similar_alert(&self, xnode_id: &str, alert_type: AlertType) -> Option<&Alert> {
         self.active_alerts
             .values()
-            .any(|a| a.xnode_id == xnode_id && a.alert_type == alert_type && !a.resolved)
+            .find(|a| a.xnode_id == xnode_id && a.alert_type == alert_type && !a.resolved)
     }
 
     pub fn get_all_alerts(&self) -> Vec<&Alert> {
-        self.active_alerts.values().collect()
+        oldest_first(self.active_alerts.values())
     }
 
     pub fn load_from_map(&mut self, alerts: HashMap<String, Alert>) {
@@ -315,6 +348,13 @@ impl AlertStore {
     }
 }
 
+/// Order alerts by when they fired, then by ID, so listings are stable
+fn oldest_first<'a>(alerts: impl Iterator<Item = &'a Alert>) -> Vec<&'a Alert> {
+    let mut alerts: Vec<&Alert> = alerts.collect();
+    alerts.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then_with(|| a.id.cmp(&b.id)));
+    alerts
+}
+
 impl Default for AlertStore {
     fn default() -> Self {
         Self::new()
@@ -383,4 +423,25 @@ mod tests {
         assert!(!store.has_similar_alert("test-node", AlertType::HighMemory));
         assert!(!store.has_similar_alert("other-node", AlertType::HighCpu));
     }
+
+    #[test]
+    fn test_cost_tier_level_with_hysteresis() {
+        let tiers = [
+            CostTier { monthly: 500.0, severity: AlertSeverity::Warning },
+            CostTier { monthly: 1000.0, severity: AlertSeverity::Critical },
+        ];
+
+        assert_eq!(cost_tier_level(&tiers, 400.0, None, 0.1), None);
+        assert_eq!(cost_tier_level(&tiers, 600.0, None, 0.1), Some(0));
+        assert_eq!(cost_tier_level(&tiers, 1200.0, Some(0), 0.1), Some(1));
+
+        // Dipping just under a tier keeps it; falling past the margin steps down
+        assert_eq!(cost_tier_level(&tiers, 950.0, Some(1), 0.1), Some(1));
+        assert_eq!(cost_tier_level(&tiers, 850.0, Some(1), 0.1), Some(0));
+        assert_eq!(cost_tier_level(&tiers, 460.0, Some(0), 0.1), Some(0));
+        assert_eq!(cost_tier_level(&tiers, 440.0, Some(0), 0.1), None);
+
+        // Within the margin of a tier not yet alerted is not enough
+        assert_eq!(cost_tier_level(&tiers, 950.0, Some(0), 0.1), Some(0));
+    }
 }
@@ -1,9 +1,15 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use colored::Colorize;
-use prettytable::{Cell, Row, Table};
+use prettytable::{format, Cell, Row, Table};
 use std::time::Duration;
 
-use super::{MonitoringSystem, alerts::{AlertSeverity, Alert}, health::HealthStatus};
+use super::history::{parse_window, sparkline, HistoryMetric, NodeHistory};
+use super::{FleetSweep, MonitoringSystem, alerts::{AlertSeverity, Alert}, health::{HealthCheck, HealthStatus}, metrics::MIN_BASELINE_SAMPLES};
+use super::metrics::{sort_top, MetricsTarget, ResourceMetrics, TopSort};
+use super::silences::SilenceTarget;
+use crate::exit::CliError;
+use crate::shutdown::shutdown_signal;
+use crate::inventory::{XNodeEntry, XNodeInventory};
 
 pub async fn show_dashboard(system: &MonitoringSystem) -> Result<()> {
     let data = system.get_dashboard_data();
@@ -89,21 +95,41 @@ pub async fn show_dashboard(system: &MonitoringSystem) -> Result<()> {
     Ok(())
 }
 
+/// Write the dashboard to `path` as a standalone HTML page
+pub async fn export_dashboard_html(system: &MonitoringSystem, path: &std::path::Path) -> Result<()> {
+    let html = system.get_dashboard_data().generate_html();
+    tokio::fs::write(path, html)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!("{} Dashboard written to {}", "✓".green().bold(), path.display());
+    Ok(())
+}
+
 pub async fn show_health_check(system: &mut MonitoringSystem, xnode_id: &str) -> Result<()> {
     println!("\n{} {}", "Checking health for xNode:".white().bold(), xnode_id.cyan());
 
-    // For demonstration, using placeholder values
-    // In real implementation, this would fetch from xnode database
+    let xnode = inventory_entry(xnode_id)?;
+    let settings = xnode.connection_settings();
+
     let health_check = system
         .check_health(
             xnode_id.to_string(),
-            Some("192.168.1.100"),
-            false,
+            ip_address(&xnode),
+            settings.has_webserver,
+            &settings.tcp_ports,
         )
         .await;
 
+    // Any passing check means the node is still there
+    if !system.served_from_cache()
+        && matches!(health_check.status, HealthStatus::Healthy | HealthStatus::Degraded)
+    {
+        XNodeInventory::new(None)?.mark_seen(&[xnode_id.to_string()], chrono::Utc::now())?;
+    }
+
     println!("\n{}", "HEALTH CHECK RESULTS".white().bold());
     println!("{}", "=".repeat(60));
+    print_cache_note(system, &health_check.timestamp);
 
     let status_str = match health_check.status {
         HealthStatus::Healthy => "HEALTHY".green().bold(),
@@ -144,31 +170,37 @@ pub async fn show_health_check(system: &mut MonitoringSystem, xnode_id: &str) ->
 pub async fn show_metrics(system: &mut MonitoringSystem, xnode_id: &str) -> Result<()> {
     println!("\n{} {}", "Collecting metrics for xNode:".white().bold(), xnode_id.cyan());
 
-    // For demonstration, using placeholder values
+    let xnode = inventory_entry(xnode_id)?;
+    let settings = xnode.connection_settings();
+
     let metrics = system
         .collect_metrics(
             xnode_id.to_string(),
-            Some("192.168.1.100"),
-            None,
+            ip_address(&xnode),
+            Some(settings.ssh_user()),
+            settings.ssh_key_path.as_deref(),
         )
         .await;
 
     if let Some(metrics) = metrics {
+        // Color the bars by the same thresholds that raise alerts
+        let config = system.get_config();
         println!("\n{}", "RESOURCE METRICS".white().bold());
         println!("{}", "=".repeat(60));
+        print_cache_note(system, &metrics.timestamp);
 
         println!("  Timestamp: {}", format_timestamp(&metrics.timestamp));
         println!("\n{}", "CPU".white().bold());
         println!("  Usage: {:.1}%", metrics.cpu_percent);
-        print_usage_bar(metrics.cpu_percent, 75.0, 90.0);
+        print_usage_bar(metrics.cpu_percent, config.cpu_warning_threshold, config.cpu_critical_threshold);
 
         println!("\n{}", "MEMORY".white().bold());
         println!("  Usage: {:.1}%", metrics.memory_percent);
-        print_usage_bar(metrics.memory_percent, 80.0, 95.0);
+        print_usage_bar(metrics.memory_percent, config.memory_warning_threshold, config.memory_critical_threshold);
 
         println!("\n{}", "DISK".white().bold());
         println!("  Usage: {:.1}%", metrics.disk_percent);
-        print_usage_bar(metrics.disk_percent, 85.0, 95.0);
+        print_usage_bar(metrics.disk_percent, config.disk_warning_threshold, config.disk_critical_threshold);
 
         println!("\n{}", "LOAD AVERAGE".white().bold());
         println!(
@@ -176,6 +208,20 @@ pub async fn show_metrics(system: &mut MonitoringSystem, xnode_id: &str) -> Resu
             metrics.load_average.0, metrics.load_average.1, metrics.load_average.2
         );
 
+        if config.metrics_baseline_sigma.is_some() {
+            println!("\n{}", "BASELINE".white().bold());
+            let deviations = system.baseline_deviations(&metrics);
+            let prior = system.metrics_history(xnode_id).len().saturating_sub(1);
+            if prior < MIN_BASELINE_SAMPLES {
+                println!("  Building baseline ({} of {} samples)", prior, MIN_BASELINE_SAMPLES);
+            } else if deviations.is_empty() {
+                println!("  Within this node's usual range");
+            }
+            for deviation in &deviations {
+                println!("  {} {}", "⚠".yellow(), deviation);
+            }
+        }
+
         system.save_history().await?;
     } else {
         println!("{}", "  Failed to collect metrics".red());
@@ -185,6 +231,19 @@ pub async fn show_metrics(system: &mut MonitoringSystem, xnode_id: &str) -> Resu
     Ok(())
 }
 
+/// Say so when a result came from the freshness cache rather than a new check
+fn print_cache_note(system: &MonitoringSystem, timestamp: &str) {
+    if !system.served_from_cache() {
+        return;
+    }
+    let age = super::result_age(timestamp).map_or(0, |age| age.as_secs());
+    println!(
+        "  {} from {}s ago (pass --force to check again)",
+        "Cached result".yellow(),
+        age
+    );
+}
+
 pub async fn list_alerts(system: &MonitoringSystem) -> Result<()> {
     println!("\n{}", "ACTIVE ALERTS".cyan().bold());
     println!("{}", "=".repeat(60));
@@ -257,6 +316,106 @@ pub async fn resolve_alert(system: &mut MonitoringSystem, alert_id: &str) -> Res
     Ok(())
 }
 
+/// Give the system each inventory xNode's tags so tag silences apply
+pub fn load_xnode_tags(system: &mut MonitoringSystem) -> Result<()> {
+    let inventory = XNodeInventory::new(None)?;
+    let tags = inventory
+        .list_all()
+        .into_iter()
+        .map(|entry| (entry.id.clone(), entry.tags.clone()))
+        .collect();
+    system.set_xnode_tags(tags);
+    Ok(())
+}
+
+pub async fn silence(
+    system: &mut MonitoringSystem,
+    target: SilenceTarget,
+    duration: &str,
+    reason: Option<String>,
+) -> Result<()> {
+    let duration = parse_window(duration)?;
+    if duration <= chrono::Duration::zero() {
+        return Err(CliError::usage("Silence duration must be positive").into());
+    }
+
+    let silence = system.silence(target, duration, reason);
+    system.save_history().await?;
+    println!(
+        "{} Silenced {} until {} ({})",
+        "✓".green().bold(),
+        silence.target.to_string().cyan(),
+        silence.until.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+        silence.id.white().italic()
+    );
+    println!("  Alerts are still recorded; undo with: {}", format!("capsule monitor unsilence {}", silence.id).cyan());
+    Ok(())
+}
+
+pub async fn list_silences(system: &MonitoringSystem) -> Result<()> {
+    println!("\n{}", "ACTIVE SILENCES".cyan().bold());
+    println!("{}", "=".repeat(60));
+
+    let silences = system.active_silences();
+    if silences.is_empty() {
+        println!("{}", "  No active silences".green());
+        println!();
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(Row::new(vec![
+        Cell::new("ID").style_spec("Fb"),
+        Cell::new("Target").style_spec("Fb"),
+        Cell::new("Until").style_spec("Fb"),
+        Cell::new("Remaining").style_spec("Fb"),
+        Cell::new("Reason").style_spec("Fb"),
+    ]));
+    let now = chrono::Utc::now();
+    for silence in silences {
+        let remaining = (silence.until - now).num_minutes().max(1);
+        table.add_row(Row::new(vec![
+            Cell::new(&silence.id),
+            Cell::new(&silence.target.to_string()).style_spec("Fc"),
+            Cell::new(&silence.until.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string()),
+            Cell::new(&format!("{}h {:02}m", remaining / 60, remaining % 60)),
+            Cell::new(silence.reason.as_deref().unwrap_or("-")),
+        ]));
+    }
+    table.printstd();
+    println!();
+    Ok(())
+}
+
+/// Remove silences by silence ID or xNode ID, by tag, or all of them
+pub async fn unsilence(
+    system: &mut MonitoringSystem,
+    id: Option<&str>,
+    tag: Option<&str>,
+    all: bool,
+) -> Result<()> {
+    let removed = if all {
+        system.unsilence(|_| true)
+    } else {
+        system.unsilence(|s| match (&s.target, id, tag) {
+            (_, Some(id), _) if s.id == id => true,
+            (SilenceTarget::Xnode(xnode), Some(id), _) => xnode == id,
+            (SilenceTarget::Tag(t), _, Some(tag)) => t == tag,
+            _ => false,
+        })
+    };
+
+    if removed == 0 && !all {
+        let what = id.map(str::to_string).or_else(|| tag.map(|t| format!("tag {}", t))).unwrap_or_default();
+        return Err(CliError::not_found(format!("No silence found for {}", what)).into());
+    }
+
+    system.save_history().await?;
+    println!("{} Removed {} silence(s)", "✓".green().bold(), removed);
+    Ok(())
+}
+
 pub async fn show_config(system: &MonitoringSystem) -> Result<()> {
     let config = system.get_config();
 
@@ -280,12 +439,24 @@ pub async fn show_config(system: &MonitoringSystem) -> Result<()> {
     println!("  Disk Warning: {:.0}%", config.disk_warning_threshold);
     println!("  Disk Critical: {:.0}%", config.disk_critical_threshold);
 
+    if !config.cost_tiers.is_empty() {
+        println!("\n{}", "COST TIERS".white().bold());
+        for tier in config.sorted_cost_tiers() {
+            println!("  ${:.2}/month: {}", tier.monthly, tier.severity);
+        }
+        println!("  Hysteresis: {:.0}%", config.cost_tier_hysteresis * 100.0);
+    }
+
     println!("\n{}", "ALERT DELIVERY".white().bold());
     println!("  Console: {}", config.alert_delivery.console_alerts);
     println!("  Email: {}", config.alert_delivery.email_alerts);
     println!("  Webhook: {}", config.alert_delivery.webhook_alerts);
     println!("  Slack: {}", config.alert_delivery.slack_alerts);
 
+    println!("\n{}", "RETENTION".white().bold());
+    println!("  Health Checks: {} per xNode", config.health_history_points);
+    println!("  Metrics Samples: {} per xNode", config.metrics_history_points);
+
     println!("\n{}", "AUTO-REMEDIATION".white().bold());
     println!("  Auto Restart on Failure: {}", config.auto_restart_on_failure);
     println!("  Auto Scale on High Load: {}", config.auto_scale_on_high_load);
@@ -298,6 +469,8 @@ pub async fn watch_dashboard(system: &mut MonitoringSystem) -> Result<()> {
     println!("{}", "Starting live dashboard (Press Ctrl+C to exit)...".cyan());
 
     let mut interval = tokio::time::interval(Duration::from_secs(5));
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
 
     loop {
         // Clear screen (ANSI escape code)
@@ -307,12 +480,371 @@ pub async fn watch_dashboard(system: &mut MonitoringSystem) -> Result<()> {
 
         println!("{}", "Refreshing in 5 seconds...".white().italic());
 
-        interval.tick().await;
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = &mut shutdown => break,
+        }
+    }
+
+    // Persist anything collected while watching before exiting
+    system.save_history().await?;
+    println!("\n{}", "Dashboard stopped; history saved.".cyan());
+    Ok(())
+}
+
+/// One xNode's result from `monitor check-all`
+#[derive(Debug, serde::Serialize)]
+pub struct NodeCheck {
+    pub health: HealthCheck,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<ResourceMetrics>,
+    pub active_alerts: usize,
+}
+
+/// Check every running xNode once, concurrently, and print a summary.
+/// Results and alerts are saved like any other check; fails if any node is
+/// unhealthy so cron jobs and scripts can act on the exit code.
+pub async fn check_all(system: &mut MonitoringSystem, with_metrics: bool, json: bool) -> Result<()> {
+    let inventory = XNodeInventory::new(None)?;
+    let nodes: Vec<XNodeEntry> = inventory.list_by_status("running").into_iter().cloned().collect();
+    if nodes.is_empty() {
+        if json {
+            println!("[]");
+        } else {
+            println!("\n{} No running xNodes to check.\n", "ℹ".cyan());
+        }
+        return Ok(());
+    }
+
+    let sweep = system.prober().sweep(&nodes, with_metrics).await;
+    system.apply_sweep(&sweep, inventory.get_total_cost()["monthly"]).await?;
+
+    let FleetSweep { checks, metrics } = sweep;
+    let metrics = if with_metrics { metrics } else { vec![None; nodes.len()] };
+    let results: Vec<NodeCheck> = checks
+        .into_iter()
+        .zip(metrics)
+        .map(|(health, metrics)| {
+            let active_alerts = system.get_xnode_status(&health.xnode_id).active_alerts.len();
+            NodeCheck { health, metrics, active_alerts }
+        })
+        .collect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    } else {
+        print_check_all(system, &nodes, &results, with_metrics);
+    }
+
+    let unhealthy: Vec<&str> = results
+        .iter()
+        .filter(|r| r.health.status == HealthStatus::Unhealthy)
+        .map(|r| r.health.xnode_id.as_str())
+        .collect();
+    if !unhealthy.is_empty() {
+        anyhow::bail!("{} of {} xNode(s) unhealthy: {}", unhealthy.len(), results.len(), unhealthy.join(", "));
+    }
+    Ok(())
+}
+
+fn print_check_all(system: &MonitoringSystem, nodes: &[XNodeEntry], results: &[NodeCheck], with_metrics: bool) {
+    let config = system.get_config();
+
+    let mut titles = vec!["xNode ID", "Name", "Status", "Ping", "SSH", "HTTP"];
+    if with_metrics {
+        titles.extend(["CPU", "Memory", "Disk"]);
+    }
+    titles.push("Alerts");
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(Row::new(titles.iter().map(|title| Cell::new(title)).collect()));
+
+    for result in results {
+        let check = &result.health;
+        let status = match check.status {
+            HealthStatus::Healthy => "HEALTHY".green(),
+            HealthStatus::Degraded => "DEGRADED".yellow(),
+            HealthStatus::Unhealthy => "UNHEALTHY".red(),
+            HealthStatus::Unknown => "UNKNOWN".white(),
+        };
+        let entry = nodes.iter().find(|n| n.id == check.xnode_id);
+        let mut cells = vec![
+            Cell::new(&check.xnode_id),
+            Cell::new(entry.map_or("-", |n| n.name.as_str())),
+            Cell::new(&status.to_string()),
+            Cell::new(&check_status_to_str(check.checks.get("ping").copied())),
+            Cell::new(&check_status_to_str(check.checks.get("ssh").copied())),
+            Cell::new(&check_status_to_str(check.checks.get("http").copied())),
+        ];
+        if with_metrics {
+            match &result.metrics {
+                Some(m) => cells.extend([
+                    usage_cell(m.cpu_percent, config.cpu_warning_threshold, config.cpu_critical_threshold),
+                    usage_cell(m.memory_percent, config.memory_warning_threshold, config.memory_critical_threshold),
+                    usage_cell(m.disk_percent, config.disk_warning_threshold, config.disk_critical_threshold),
+                ]),
+                None => cells.extend((0..3).map(|_| Cell::new("-"))),
+            }
+        }
+        cells.push(match result.active_alerts {
+            0 => Cell::new("0"),
+            n => Cell::new(&n.to_string().red().to_string()),
+        });
+        table.add_row(Row::new(cells));
+    }
+
+    println!("\n{}", "FLEET HEALTH".cyan().bold());
+    table.printstd();
+
+    let count = |status: HealthStatus| results.iter().filter(|r| r.health.status == status).count();
+    println!(
+        "\n{} healthy, {} degraded, {} unhealthy, {} unknown\n",
+        count(HealthStatus::Healthy).to_string().green(),
+        count(HealthStatus::Degraded).to_string().yellow(),
+        count(HealthStatus::Unhealthy).to_string().red(),
+        count(HealthStatus::Unknown),
+    );
+}
+
+/// Settings for `xnode top`
+pub struct TopOptions {
+    pub sort: TopSort,
+    pub interval: Duration,
+    /// Only nodes on this provider
+    pub provider: Option<String>,
+    /// Print one table and exit instead of refreshing
+    pub once: bool,
+}
+
+/// Fleet-wide live view: collect metrics from every running xNode at once
+/// and redraw a table sorted by `options.sort` until Ctrl-C
+pub async fn watch_top(system: &mut MonitoringSystem, options: &TopOptions) -> Result<()> {
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+
+    loop {
+        // Reload each round so nodes deployed or destroyed meanwhile show up
+        let inventory = XNodeInventory::new(None)?;
+        let nodes: Vec<XNodeEntry> = inventory
+            .select(&[], options.provider.as_deref(), Some("running"))
+            .into_iter()
+            .cloned()
+            .collect();
+        if nodes.is_empty() {
+            println!("\n{} No running xNodes to watch.\n", "ℹ".cyan());
+            break;
+        }
+
+        let targets: Vec<MetricsTarget> = nodes.iter().map(MetricsTarget::for_xnode).collect();
+        let mut rows = tokio::select! {
+            rows = system.collect_metrics_concurrently(&targets) => rows,
+            _ = &mut shutdown => break,
+        };
+        sort_top(&mut rows, options.sort);
+
+        if !options.once {
+            // Clear screen (ANSI escape code)
+            print!("\x1B[2J\x1B[1;1H");
+        }
+        print_top(system, &nodes, &rows, options.sort);
+        if options.once {
+            break;
+        }
+        println!(
+            "{}",
+            format!("Refreshing every {}s (Press Ctrl+C to exit)...", options.interval.as_secs()).white().italic()
+        );
+
+        tokio::select! {
+            _ = tokio::time::sleep(options.interval) => {}
+            _ = &mut shutdown => break,
+        }
+    }
+
+    // Keep the samples collected while watching
+    system.save_history().await?;
+    Ok(())
+}
+
+fn print_top(
+    system: &MonitoringSystem,
+    nodes: &[XNodeEntry],
+    rows: &[(String, Option<ResourceMetrics>)],
+    sort: TopSort,
+) {
+    let config = system.get_config();
+    let reachable = rows.iter().filter(|(_, metrics)| metrics.is_some()).count();
+
+    println!(
+        "\n{} {}",
+        "XNODE TOP".cyan().bold(),
+        format!("{} of {} running xNodes reporting, sorted by {}", reachable, rows.len(), sort).dimmed()
+    );
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(Row::new(
+        ["xNode ID", "Name", "Provider", "CPU", "Memory", "Disk", "Load (1/5/15)"]
+            .iter()
+            .map(|title| Cell::new(title))
+            .collect(),
+    ));
+
+    for (xnode_id, metrics) in rows {
+        let entry = nodes.iter().find(|n| &n.id == xnode_id);
+        let mut cells = vec![
+            Cell::new(xnode_id),
+            Cell::new(entry.map_or("-", |n| n.name.as_str())),
+            Cell::new(entry.map_or("-", |n| n.provider.as_str())),
+        ];
+        match metrics {
+            Some(m) => cells.extend([
+                usage_cell(m.cpu_percent, config.cpu_warning_threshold, config.cpu_critical_threshold),
+                usage_cell(m.memory_percent, config.memory_warning_threshold, config.memory_critical_threshold),
+                usage_cell(m.disk_percent, config.disk_warning_threshold, config.disk_critical_threshold),
+                Cell::new(&format!("{:.2} {:.2} {:.2}", m.load_average.0, m.load_average.1, m.load_average.2)),
+            ]),
+            None => {
+                cells.push(Cell::new(&"unreachable".red().to_string()));
+                cells.extend((0..3).map(|_| Cell::new("-")));
+            }
+        }
+        table.add_row(Row::new(cells));
+    }
+
+    table.printstd();
+    println!();
+}
+
+/// A percentage colored by the same thresholds that raise alerts
+fn usage_cell(usage: f64, warning_threshold: f64, critical_threshold: f64) -> Cell {
+    let text = format!("{:.1}%", usage);
+    let text = if usage >= critical_threshold {
+        text.red()
+    } else if usage >= warning_threshold {
+        text.yellow()
+    } else {
+        text.green()
+    };
+    Cell::new(&text.to_string())
+}
+
+/// Number of rows printed in the `monitor history` table
+const HISTORY_TABLE_ROWS: usize = 20;
+
+pub async fn show_history(
+    system: &MonitoringSystem,
+    xnode_id: &str,
+    metric: Option<HistoryMetric>,
+    last: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    let window = last.map(parse_window).transpose()?;
+    let history = NodeHistory::collect(system, xnode_id, metric, window);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&history)?);
+        return Ok(());
+    }
+
+    if history.is_empty() {
+        println!(
+            "\n{} No monitoring history recorded for {}{}.",
+            "ℹ".cyan(),
+            xnode_id.cyan(),
+            last.map(|l| format!(" in the last {}", l)).unwrap_or_default()
+        );
+        println!(
+            "  Collect some with {} or {}\n",
+            format!("capsule monitor health {}", xnode_id).cyan().bold(),
+            format!("capsule monitor metrics {}", xnode_id).cyan().bold()
+        );
+        return Ok(());
+    }
+
+    println!("\n{} {}", "HISTORY".white().bold(), xnode_id.cyan());
+    println!("{}", "=".repeat(60));
+
+    if !history.metrics.is_empty() {
+        println!("\n{}", "RESOURCE METRICS".white().bold());
+        for metric in &history.metrics_shown {
+            let values = history.series(*metric);
+            let avg = values.iter().sum::<f64>() / values.len() as f64;
+            let max = values.iter().cloned().fold(f64::MIN, f64::max);
+            println!(
+                "  {:<7} {}  avg {:.1}% | max {:.1}%",
+                metric.to_string(),
+                sparkline(&values).cyan(),
+                avg,
+                max
+            );
+        }
+
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+        let mut titles = vec![Cell::new("Timestamp")];
+        titles.extend(history.metrics_shown.iter().map(|m| Cell::new(&m.to_string())));
+        table.set_titles(Row::new(titles));
+
+        let skip = history.metrics.len().saturating_sub(HISTORY_TABLE_ROWS);
+        for point in &history.metrics[skip..] {
+            let mut cells = vec![Cell::new(&format_timestamp(&point.timestamp))];
+            cells.extend(history.metrics_shown.iter().map(|m| {
+                let value = point.values.get(&m.to_string()).copied().unwrap_or_default();
+                Cell::new(&format!("{:.1}%", value))
+            }));
+            table.add_row(Row::new(cells));
+        }
+        println!();
+        table.printstd();
+        if skip > 0 {
+            println!(
+                "  {}",
+                format!("Showing the last {} of {} samples; use --json for all", HISTORY_TABLE_ROWS, history.metrics.len()).dimmed()
+            );
+        }
+    }
+
+    if !history.health.is_empty() {
+        println!("\n{}", "HEALTH TIMELINE".white().bold());
+        for span in &history.health {
+            let status = match span.status {
+                HealthStatus::Healthy => "HEALTHY".green(),
+                HealthStatus::Degraded => "DEGRADED".yellow(),
+                HealthStatus::Unhealthy => "UNHEALTHY".red(),
+                HealthStatus::Unknown => "UNKNOWN".white(),
+            };
+            println!(
+                "  {} → {}  {:<9} ({} check{})",
+                format_timestamp(&span.since),
+                format_timestamp(&span.until),
+                status,
+                span.checks,
+                if span.checks == 1 { "" } else { "s" }
+            );
+        }
     }
+
+    println!();
+    Ok(())
 }
 
 // Helper functions
 
+/// Look up an xNode in the inventory
+fn inventory_entry(xnode_id: &str) -> Result<XNodeEntry> {
+    let inventory = XNodeInventory::new(None)?;
+    inventory
+        .get_xnode(xnode_id)
+        .cloned()
+        .ok_or_else(|| CliError::not_found(format!("xNode '{}' not found in inventory", xnode_id)).into())
+}
+
+fn ip_address(xnode: &XNodeEntry) -> Option<&str> {
+    Some(xnode.ip_address.as_str()).filter(|ip| !ip.is_empty())
+}
+
 fn check_status_to_str(status: Option<bool>) -> String {
     match status {
         Some(true) => "OK".green().to_string(),
@@ -341,35 +873,84 @@ fn print_alert(alert: &Alert) {
     } else {
         "".white()
     };
+    let silenced_badge = if alert.silenced {
+        " [SILENCED]".white().dimmed()
+    } else {
+        "".white()
+    };
 
     println!(
-        "  {} {} {}{}",
+        "  {} {} {}{}{}",
         severity_badge,
         alert.xnode_id.cyan(),
         alert.message.white(),
-        ack_badge
+        ack_badge,
+        silenced_badge
     );
     println!("    ID: {} | {}", alert.id.white().italic(), format_timestamp(&alert.timestamp));
 }
 
+/// Width of the usage bars in `show_metrics`
+const BAR_WIDTH: usize = 40;
+
+/// Marker drawn at the warning and critical thresholds in the empty part
+/// of a usage bar
+const THRESHOLD_MARKER: char = '|';
+
+/// Bar cells for `usage`: `=` up to the usage, threshold markers beyond it
+fn bar_cells(usage: f64, warning_threshold: f64, critical_threshold: f64) -> Vec<char> {
+    let position = |percent: f64| ((percent.clamp(0.0, 100.0) / 100.0) * BAR_WIDTH as f64) as usize;
+    let filled = position(usage);
+
+    let mut cells: Vec<char> = (0..BAR_WIDTH)
+        .map(|i| if i < filled { '=' } else { ' ' })
+        .collect();
+    for threshold in [warning_threshold, critical_threshold] {
+        let at = position(threshold).min(BAR_WIDTH - 1);
+        if at >= filled {
+            cells[at] = THRESHOLD_MARKER;
+        }
+    }
+    cells
+}
+
 fn print_usage_bar(usage: f64, warning_threshold: f64, critical_threshold: f64) {
-    let bar_width = 40;
-    let filled = ((usage / 100.0) * bar_width as f64) as usize;
-    let empty = bar_width - filled;
+    let cells = bar_cells(usage, warning_threshold, critical_threshold);
+    let filled: String = cells.iter().take_while(|c| **c == '=').collect();
+    let rest: String = cells[filled.len()..].iter().collect();
 
-    let bar_color = if usage >= critical_threshold {
-        "red"
+    let filled = if usage >= critical_threshold {
+        filled.red()
     } else if usage >= warning_threshold {
-        "yellow"
+        filled.yellow()
     } else {
-        "green"
+        filled.green()
     };
 
-    let bar = match bar_color {
-        "red" => format!("{}{}", "=".repeat(filled).red(), " ".repeat(empty)),
-        "yellow" => format!("{}{}", "=".repeat(filled).yellow(), " ".repeat(empty)),
-        _ => format!("{}{}", "=".repeat(filled).green(), " ".repeat(empty)),
-    };
+    println!(
+        "  [{}{}] {}",
+        filled,
+        rest.dimmed(),
+        format!("warn {:.0}% | crit {:.0}%", warning_threshold, critical_threshold).dimmed()
+    );
+}
 
-    println!("  [{}]", bar);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bar_cells_mark_thresholds() {
+        let cells: String = bar_cells(25.0, 75.0, 90.0).into_iter().collect();
+        assert_eq!(cells.len(), BAR_WIDTH);
+        assert!(cells.starts_with(&"=".repeat(10)));
+        assert_eq!(cells.find(THRESHOLD_MARKER), Some(30));
+        assert_eq!(cells.rfind(THRESHOLD_MARKER), Some(36));
+
+        // Markers are hidden once usage passes them, and usage over 100% is clamped
+        let cells: String = bar_cells(80.0, 75.0, 90.0).into_iter().collect();
+        assert_eq!(cells.matches(THRESHOLD_MARKER).count(), 1);
+        let cells: String = bar_cells(120.0, 75.0, 100.0).into_iter().collect();
+        assert_eq!(cells, "=".repeat(BAR_WIDTH));
+    }
 }
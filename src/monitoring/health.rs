@@ -3,6 +3,8 @@ use std::collections::HashMap;
 use std::time::{Duration, Instant};
 use tokio::process::Command;
 
+use crate::inventory::XNodeEntry;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum HealthStatus {
@@ -46,12 +48,36 @@ impl HealthCheck {
     }
 }
 
+#[derive(Clone)]
 pub struct HealthChecker {
     pub ping_timeout: Duration,
     pub ssh_timeout: Duration,
     pub http_timeout: Duration,
 }
 
+/// What to check on one xNode
+#[derive(Debug, Clone, Default)]
+pub struct HealthTarget {
+    pub xnode_id: String,
+    pub ip_address: Option<String>,
+    pub has_webserver: bool,
+    /// Extra TCP ports to probe besides the configured ones
+    pub tcp_ports: Vec<u16>,
+}
+
+impl HealthTarget {
+    /// Target an inventory entry with its connection settings
+    pub fn for_xnode(xnode: &XNodeEntry) -> Self {
+        let settings = xnode.connection_settings();
+        Self {
+            xnode_id: xnode.id.clone(),
+            ip_address: Some(xnode.ip_address.clone()).filter(|ip| !ip.is_empty()),
+            has_webserver: settings.has_webserver,
+            tcp_ports: settings.tcp_ports,
+        }
+    }
+}
+
 impl Default for HealthChecker {
     fn default() -> Self {
         Self {
@@ -76,6 +102,7 @@ impl HealthChecker {
         xnode_id: String,
         ip_address: Option<&str>,
         has_webserver: bool,
+        tcp_ports: &[u16],
     ) -> HealthCheck {
         let mut health_check = HealthCheck::new(xnode_id);
 
@@ -96,6 +123,11 @@ impl HealthChecker {
             self.check_http(&mut health_check, ip).await;
         }
 
+        // Probe any extra ports, recorded as `tcp:<port>`
+        for &port in tcp_ports {
+            self.check_tcp_port(&mut health_check, ip, port).await;
+        }
+
         // Determine overall status
         health_check.status = self.determine_status(&health_check.checks);
 
@@ -167,6 +199,33 @@ impl HealthChecker {
         }
     }
 
+    async fn check_tcp_port(&self, health_check: &mut HealthCheck, ip: &str, port: u16) {
+        let name = tcp_check_name(port);
+        let start = Instant::now();
+
+        let result = tokio::time::timeout(
+            self.ssh_timeout,
+            tokio::net::TcpStream::connect((ip, port)),
+        ).await;
+
+        let elapsed = start.elapsed().as_millis() as f64;
+        health_check.response_times.insert(name.clone(), elapsed);
+
+        match result {
+            Ok(Ok(_)) => {
+                health_check.checks.insert(name, true);
+            }
+            Ok(Err(e)) => {
+                health_check.checks.insert(name, false);
+                health_check.error_messages.push(format!("TCP port {} unreachable: {}", port, e));
+            }
+            Err(_) => {
+                health_check.checks.insert(name, false);
+                health_check.error_messages.push(format!("TCP port {} check timeout", port));
+            }
+        }
+    }
+
     async fn check_http(&self, health_check: &mut HealthCheck, ip: &str) {
         let start = Instant::now();
         let url = format!("http://{}", ip);
@@ -216,6 +275,11 @@ impl HealthChecker {
     }
 }
 
+/// Name of the check for an extra TCP port, e.g. `tcp:5432`
+pub fn tcp_check_name(port: u16) -> String {
+    format!("tcp:{}", port)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -252,4 +316,25 @@ mod tests {
         checks.clear();
         assert_eq!(checker.determine_status(&checks), HealthStatus::Unknown);
     }
+
+    #[tokio::test]
+    async fn test_tcp_port_checks() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open = listener.local_addr().unwrap().port();
+        let closed = {
+            let l = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            l.local_addr().unwrap().port()
+        };
+
+        let checker = HealthChecker::new(1, 1, 1);
+        let mut health_check = HealthCheck::new("node".to_string());
+        checker.check_tcp_port(&mut health_check, "127.0.0.1", open).await;
+        checker.check_tcp_port(&mut health_check, "127.0.0.1", closed).await;
+
+        assert!(health_check.checks[&tcp_check_name(open)]);
+        assert!(!health_check.checks[&tcp_check_name(closed)]);
+        assert!(health_check.response_times.contains_key(&format!("tcp:{}", open)));
+        assert_eq!(checker.determine_status(&health_check.checks), HealthStatus::Degraded);
+        assert!(health_check.error_messages[0].contains(&format!("TCP port {} unreachable", closed)));
+    }
 }
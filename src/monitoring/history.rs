@@ -0,0 +1,249 @@
+// Time series views over recorded health and metrics history
+//
+// `MonitoringSystem` keeps a bounded number of samples per xNode (24 hours
+// by default, see `health_history_points`/`metrics_history_points`). These
+// helpers select a window of that history for `capsule monitor history`,
+// either as a table with sparklines or as JSON for plotting tools.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use super::health::{HealthCheck, HealthStatus};
+use super::metrics::ResourceMetrics;
+use super::MonitoringSystem;
+use crate::exit::CliError;
+
+/// Sparkline glyphs from lowest to highest
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// A resource metric that can be charted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HistoryMetric {
+    Cpu,
+    Memory,
+    Disk,
+}
+
+impl HistoryMetric {
+    pub const ALL: [HistoryMetric; 3] = [Self::Cpu, Self::Memory, Self::Disk];
+
+    pub fn value(&self, metrics: &ResourceMetrics) -> f64 {
+        match self {
+            Self::Cpu => metrics.cpu_percent,
+            Self::Memory => metrics.memory_percent,
+            Self::Disk => metrics.disk_percent,
+        }
+    }
+}
+
+impl FromStr for HistoryMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "cpu" => Ok(Self::Cpu),
+            "memory" | "mem" => Ok(Self::Memory),
+            "disk" => Ok(Self::Disk),
+            other => Err(format!("unknown metric '{}' (expected cpu, memory or disk)", other)),
+        }
+    }
+}
+
+impl fmt::Display for HistoryMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Cpu => "cpu",
+            Self::Memory => "memory",
+            Self::Disk => "disk",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Parse a window like `90s`, `15m`, `1h` or `2d`
+pub fn parse_window(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (amount, unit) = text.split_at(split);
+    let invalid = || CliError::usage(format!("Invalid time window '{}' (e.g. 30m, 1h, 2d)", text));
+
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    let window = match unit {
+        "s" => Duration::seconds(amount),
+        "m" => Duration::minutes(amount),
+        "h" | "" => Duration::hours(amount),
+        "d" => Duration::days(amount),
+        _ => return Err(invalid().into()),
+    };
+    Ok(window)
+}
+
+/// Render percentages (0-100) as a sparkline
+pub fn sparkline(values: &[f64]) -> String {
+    values
+        .iter()
+        .map(|v| {
+            let level = (v.clamp(0.0, 100.0) / 100.0 * (SPARK_LEVELS.len() - 1) as f64).round();
+            SPARK_LEVELS[level as usize]
+        })
+        .collect()
+}
+
+/// One metrics sample, restricted to the requested metrics
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricPoint {
+    pub timestamp: String,
+    #[serde(flatten)]
+    pub values: BTreeMap<String, f64>,
+}
+
+/// A run of consecutive health checks with the same status
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthSpan {
+    pub since: String,
+    pub until: String,
+    pub status: HealthStatus,
+    pub checks: usize,
+}
+
+/// Recorded history for one xNode within a window
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeHistory {
+    pub xnode_id: String,
+    #[serde(skip)]
+    pub metrics_shown: Vec<HistoryMetric>,
+    pub metrics: Vec<MetricPoint>,
+    pub health: Vec<HealthSpan>,
+}
+
+impl NodeHistory {
+    /// Collect history for `xnode_id`, keeping samples newer than `window`
+    /// and only `metric` if one is given
+    pub fn collect(
+        system: &MonitoringSystem,
+        xnode_id: &str,
+        metric: Option<HistoryMetric>,
+        window: Option<Duration>,
+    ) -> Self {
+        let cutoff = window.map(|w| Utc::now() - w);
+        let metrics_shown = match metric {
+            Some(metric) => vec![metric],
+            None => HistoryMetric::ALL.to_vec(),
+        };
+
+        let metrics = system
+            .metrics_history(xnode_id)
+            .iter()
+            .filter(|m| within(&m.timestamp, cutoff))
+            .map(|m| MetricPoint {
+                timestamp: m.timestamp.clone(),
+                values: metrics_shown
+                    .iter()
+                    .map(|metric| (metric.to_string(), metric.value(m)))
+                    .collect(),
+            })
+            .collect();
+
+        let checks: Vec<&HealthCheck> = system
+            .health_history(xnode_id)
+            .iter()
+            .filter(|c| within(&c.timestamp, cutoff))
+            .collect();
+
+        Self {
+            xnode_id: xnode_id.to_string(),
+            metrics_shown,
+            metrics,
+            health: health_spans(&checks),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.metrics.is_empty() && self.health.is_empty()
+    }
+
+    /// Values of one metric in time order
+    pub fn series(&self, metric: HistoryMetric) -> Vec<f64> {
+        let key = metric.to_string();
+        self.metrics.iter().filter_map(|p| p.values.get(&key).copied()).collect()
+    }
+}
+
+/// Is `timestamp` at or after `cutoff`? Unparseable timestamps are kept.
+fn within(timestamp: &str, cutoff: Option<DateTime<Utc>>) -> bool {
+    match (cutoff, DateTime::parse_from_rfc3339(timestamp)) {
+        (Some(cutoff), Ok(at)) => at.with_timezone(&Utc) >= cutoff,
+        _ => true,
+    }
+}
+
+/// Collapse consecutive checks with the same status into spans
+fn health_spans(checks: &[&HealthCheck]) -> Vec<HealthSpan> {
+    let mut spans: Vec<HealthSpan> = Vec::new();
+    for check in checks {
+        match spans.last_mut() {
+            Some(span) if span.status == check.status => {
+                span.until = check.timestamp.clone();
+                span.checks += 1;
+            }
+            _ => spans.push(HealthSpan {
+                since: check.timestamp.clone(),
+                until: check.timestamp.clone(),
+                status: check.status,
+                checks: 1,
+            }),
+        }
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(status: HealthStatus, minutes_ago: i64) -> HealthCheck {
+        let mut check = HealthCheck::new("node-1".to_string());
+        check.status = status;
+        check.timestamp = (Utc::now() - Duration::minutes(minutes_ago)).to_rfc3339();
+        check
+    }
+
+    #[test]
+    fn test_parse_window() {
+        assert_eq!(parse_window("90s").unwrap(), Duration::seconds(90));
+        assert_eq!(parse_window("15m").unwrap(), Duration::minutes(15));
+        assert_eq!(parse_window("1h").unwrap(), Duration::hours(1));
+        assert_eq!(parse_window("2d").unwrap(), Duration::days(2));
+        assert!(parse_window("h").is_err());
+        assert!(parse_window("5w").is_err());
+    }
+
+    #[test]
+    fn test_sparkline_scales_percentages() {
+        assert_eq!(sparkline(&[0.0, 50.0, 100.0, 150.0]), "▁▅██");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn test_health_spans_and_window() {
+        let checks = [
+            check(HealthStatus::Healthy, 120),
+            check(HealthStatus::Healthy, 50),
+            check(HealthStatus::Degraded, 40),
+            check(HealthStatus::Healthy, 30),
+        ];
+        let recent: Vec<&HealthCheck> = checks
+            .iter()
+            .filter(|c| within(&c.timestamp, Some(Utc::now() - Duration::hours(1))))
+            .collect();
+
+        let spans = health_spans(&recent);
+        assert_eq!(spans.len(), 3);
+        assert_eq!(spans[0].checks, 1);
+        assert_eq!(spans[1].status, HealthStatus::Degraded);
+    }
+}
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use tokio::process::Command;
 
+use crate::inventory::XNodeEntry;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResourceMetrics {
     pub xnode_id: String,
@@ -28,6 +30,214 @@ impl ResourceMetrics {
     }
 }
 
+/// Exponential moving average of the thresholded metrics for one xNode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MetricsEma {
+    pub cpu_percent: f64,
+    pub memory_percent: f64,
+    pub disk_percent: f64,
+}
+
+impl MetricsEma {
+    /// Start the average at `metrics`
+    pub fn new(metrics: &ResourceMetrics) -> Self {
+        Self {
+            cpu_percent: metrics.cpu_percent,
+            memory_percent: metrics.memory_percent,
+            disk_percent: metrics.disk_percent,
+        }
+    }
+
+    /// Fold in a new sample; `alpha` is the weight of the new sample
+    pub fn update(&mut self, alpha: f64, metrics: &ResourceMetrics) {
+        let blend = |average: f64, sample: f64| alpha * sample + (1.0 - alpha) * average;
+        self.cpu_percent = blend(self.cpu_percent, metrics.cpu_percent);
+        self.memory_percent = blend(self.memory_percent, metrics.memory_percent);
+        self.disk_percent = blend(self.disk_percent, metrics.disk_percent);
+    }
+
+    /// The average over `samples`, oldest first
+    pub fn from_samples(alpha: f64, samples: &[ResourceMetrics]) -> Option<Self> {
+        let (first, rest) = samples.split_first()?;
+        let mut ema = Self::new(first);
+        for sample in rest {
+            ema.update(alpha, sample);
+        }
+        Some(ema)
+    }
+}
+
+/// Prior samples needed before a node's baseline is trusted
+pub const MIN_BASELINE_SAMPLES: usize = 12;
+
+/// Smallest spread assumed for a baseline, in percentage points, so a node
+/// that has sat perfectly flat isn't flagged for a 0.1% wobble
+const MIN_BASELINE_STDDEV: f64 = 1.0;
+
+/// Mean and standard deviation of one metric over a node's history
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MetricStats {
+    pub mean: f64,
+    pub stddev: f64,
+}
+
+impl MetricStats {
+    fn of(values: impl Iterator<Item = f64> + Clone) -> Self {
+        let count = values.clone().count() as f64;
+        let mean = values.clone().sum::<f64>() / count;
+        let variance = values.map(|v| (v - mean).powi(2)).sum::<f64>() / count;
+        Self { mean, stddev: variance.sqrt() }
+    }
+}
+
+/// A node's usual CPU, memory and disk usage
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MetricsBaseline {
+    pub cpu_percent: MetricStats,
+    pub memory_percent: MetricStats,
+    pub disk_percent: MetricStats,
+}
+
+impl MetricsBaseline {
+    /// The baseline over `samples`, or `None` with fewer than
+    /// `MIN_BASELINE_SAMPLES`
+    pub fn from_samples(samples: &[ResourceMetrics]) -> Option<Self> {
+        if samples.len() < MIN_BASELINE_SAMPLES {
+            return None;
+        }
+        Some(Self {
+            cpu_percent: MetricStats::of(samples.iter().map(|m| m.cpu_percent)),
+            memory_percent: MetricStats::of(samples.iter().map(|m| m.memory_percent)),
+            disk_percent: MetricStats::of(samples.iter().map(|m| m.disk_percent)),
+        })
+    }
+
+    /// Metrics in `metrics` more than `sigmas` standard deviations from
+    /// their mean, in either direction
+    pub fn deviations(&self, metrics: &ResourceMetrics, sigmas: f64) -> Vec<MetricDeviation> {
+        [
+            ("cpu", metrics.cpu_percent, self.cpu_percent),
+            ("memory", metrics.memory_percent, self.memory_percent),
+            ("disk", metrics.disk_percent, self.disk_percent),
+        ]
+        .into_iter()
+        .map(|(metric, value, usual)| MetricDeviation { metric, value, usual })
+        .filter(|deviation| deviation.sigmas().abs() > sigmas)
+        .collect()
+    }
+}
+
+/// A metric well outside its node's usual range
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricDeviation {
+    pub metric: &'static str,
+    pub value: f64,
+    pub usual: MetricStats,
+}
+
+impl MetricDeviation {
+    /// Signed distance from the mean in standard deviations
+    pub fn sigmas(&self) -> f64 {
+        (self.value - self.usual.mean) / self.usual.stddev.max(MIN_BASELINE_STDDEV)
+    }
+}
+
+impl std::fmt::Display for MetricDeviation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {:.1}% (usually {:.1}% ± {:.1}, {:+.1}σ)",
+            self.metric,
+            self.value,
+            self.usual.mean,
+            self.usual.stddev,
+            self.sigmas()
+        )
+    }
+}
+
+/// Where to collect one xNode's metrics from
+#[derive(Debug, Clone, Default)]
+pub struct MetricsTarget {
+    pub xnode_id: String,
+    pub ip_address: Option<String>,
+    pub ssh_user: Option<String>,
+    pub ssh_key_path: Option<String>,
+}
+
+impl MetricsTarget {
+    /// Target an inventory entry with its connection settings
+    pub fn for_xnode(xnode: &XNodeEntry) -> Self {
+        let settings = xnode.connection_settings();
+        Self {
+            xnode_id: xnode.id.clone(),
+            ip_address: Some(xnode.ip_address.clone()).filter(|ip| !ip.is_empty()),
+            ssh_user: Some(settings.ssh_user().to_string()),
+            ssh_key_path: settings.ssh_key_path,
+        }
+    }
+}
+
+/// Column `xnode top` orders nodes by, busiest first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TopSort {
+    #[default]
+    Cpu,
+    Memory,
+    Disk,
+    Load,
+}
+
+impl TopSort {
+    pub fn value(&self, metrics: &ResourceMetrics) -> f64 {
+        match self {
+            Self::Cpu => metrics.cpu_percent,
+            Self::Memory => metrics.memory_percent,
+            Self::Disk => metrics.disk_percent,
+            Self::Load => metrics.load_average.0,
+        }
+    }
+}
+
+impl std::str::FromStr for TopSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "cpu" => Ok(Self::Cpu),
+            "memory" | "mem" => Ok(Self::Memory),
+            "disk" => Ok(Self::Disk),
+            "load" => Ok(Self::Load),
+            other => Err(format!("unknown column '{}' (expected cpu, memory, disk or load)", other)),
+        }
+    }
+}
+
+impl std::fmt::Display for TopSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Cpu => "cpu",
+            Self::Memory => "memory",
+            Self::Disk => "disk",
+            Self::Load => "load",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Order per-node samples by `sort`, busiest first, then by xNode ID;
+/// nodes whose metrics could not be collected go last
+pub fn sort_top(rows: &mut [(String, Option<ResourceMetrics>)], sort: TopSort) {
+    rows.sort_by(|(a_id, a), (b_id, b)| {
+        let a = a.as_ref().map(|m| sort.value(m));
+        let b = b.as_ref().map(|m| sort.value(m));
+        b.partial_cmp(&a)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a_id.cmp(b_id))
+    });
+}
+
+#[derive(Clone)]
 pub struct MetricsCollector {
     pub ssh_timeout: std::time::Duration,
 }
@@ -51,31 +261,32 @@ impl MetricsCollector {
         &self,
         xnode_id: String,
         ip_address: Option<&str>,
+        ssh_user: Option<&str>,
         ssh_key_path: Option<&str>,
     ) -> Option<ResourceMetrics> {
         let ip = ip_address?;
-        let ssh_key = ssh_key_path.unwrap_or("~/.ssh/id_rsa");
-
-        // Build SSH command to collect all metrics in one call
-        let cmd = format!(
-            "top -bn1 | grep 'Cpu(s)' | awk '{{print $2}}' && \
-             free | grep Mem | awk '{{print ($3/$2) * 100}}' && \
-             df -h / | tail -1 | awk '{{print $5}}' && \
-             uptime"
-        );
-
-        let ssh_cmd = format!(
-            "ssh -o StrictHostKeyChecking=no -o ConnectTimeout=5 -i {} root@{} '{}'",
-            ssh_key, ip, cmd
-        );
-
-        let result = tokio::time::timeout(
-            self.ssh_timeout,
-            Command::new("sh")
-                .arg("-c")
-                .arg(&ssh_cmd)
-                .output()
-        ).await;
+        let ssh_user = ssh_user.unwrap_or("root");
+
+        // Collect all metrics in one call
+        let cmd = "top -bn1 | grep 'Cpu(s)' | awk '{print $2}' && \
+             free | grep Mem | awk '{print ($3/$2) * 100}' && \
+             df -h / | tail -1 | awk '{print $5}' && \
+             uptime";
+
+        // User, key and address come from inventory metadata, so they are
+        // passed as separate arguments and never through a local shell
+        let mut ssh = Command::new("ssh");
+        ssh.args(["-o", "StrictHostKeyChecking=no", "-o", "ConnectTimeout=5"]);
+        if let Some(key) = ssh_key_path {
+            let key = match (key.strip_prefix("~/"), dirs::home_dir()) {
+                (Some(rest), Some(home)) => home.join(rest).display().to_string(),
+                _ => key.to_string(),
+            };
+            ssh.arg("-i").arg(key);
+        }
+        ssh.arg("--").arg(format!("{}@{}", ssh_user, ip)).arg(cmd);
+
+        let result = tokio::time::timeout(self.ssh_timeout, ssh.output()).await;
 
         match result {
             Ok(Ok(output)) if output.status.success() => {
@@ -166,6 +377,21 @@ mod tests {
         assert_eq!(result, None);
     }
 
+    #[tokio::test]
+    async fn test_connection_settings_never_reach_a_shell() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("pwned");
+        let user = format!("root$(touch {})", marker.display());
+        let key = format!("k; touch {}", marker.display());
+
+        let collector = MetricsCollector::new(1);
+        let metrics = collector
+            .collect_metrics("x-1".to_string(), Some("192.0.2.1"), Some(&user), Some(&key))
+            .await;
+        assert!(metrics.is_none());
+        assert!(!marker.exists());
+    }
+
     #[test]
     fn test_parse_metrics_output() {
         let collector = MetricsCollector::default();
@@ -181,4 +407,48 @@ mod tests {
         assert_eq!(metrics.disk_percent, 85.0);
         assert_eq!(metrics.load_average, (0.52, 0.58, 0.59));
     }
+
+    #[test]
+    fn test_baseline_flags_relative_jumps() {
+        let sample = |cpu: f64| ResourceMetrics { cpu_percent: cpu, memory_percent: 50.0, disk_percent: 30.0, ..ResourceMetrics::new("n".to_string()) };
+        let history: Vec<_> = (0..MIN_BASELINE_SAMPLES).map(|i| sample(4.0 + (i % 3) as f64)).collect();
+
+        assert!(MetricsBaseline::from_samples(&history[1..]).is_none());
+        let baseline = MetricsBaseline::from_samples(&history).unwrap();
+        assert!((baseline.cpu_percent.mean - 5.0).abs() < 1e-9);
+
+        // 40% is below any fixed threshold but far outside this node's range
+        let deviations = baseline.deviations(&sample(40.0), 3.0);
+        assert_eq!(deviations.len(), 1);
+        assert_eq!(deviations[0].metric, "cpu");
+        assert!(deviations[0].sigmas() > 30.0);
+
+        // Flat memory and disk only flag once past the minimum spread
+        assert!(baseline.deviations(&sample(6.0), 3.0).is_empty());
+        let drop = ResourceMetrics { disk_percent: 26.0, ..sample(5.0) };
+        assert_eq!(baseline.deviations(&drop, 3.0)[0].metric, "disk");
+    }
+
+    #[test]
+    fn test_sort_top_busiest_first() {
+        let sample = |id: &str, cpu: f64, load: f64| {
+            let metrics = ResourceMetrics { cpu_percent: cpu, load_average: (load, 0.0, 0.0), ..ResourceMetrics::new(id.to_string()) };
+            (id.to_string(), Some(metrics))
+        };
+        let mut rows = vec![
+            ("down".to_string(), None),
+            sample("web-2", 40.0, 0.5),
+            sample("db", 90.0, 0.2),
+            sample("web-1", 40.0, 3.0),
+        ];
+
+        sort_top(&mut rows, TopSort::Cpu);
+        let ids: Vec<_> = rows.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, ["db", "web-1", "web-2", "down"]);
+
+        sort_top(&mut rows, "load".parse().unwrap());
+        let ids: Vec<_> = rows.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, ["web-1", "web-2", "db", "down"]);
+        assert!("swap".parse::<TopSort>().is_err());
+    }
 }
@@ -2,19 +2,50 @@ pub mod health;
 pub mod metrics;
 pub mod alerts;
 pub mod commands;
+pub mod history;
+pub mod silences;
+#[cfg(feature = "server")]
+pub mod serve;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::fs;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 
-use health::{HealthCheck, HealthChecker, HealthStatus};
-use metrics::{MetricsCollector, ResourceMetrics};
-use alerts::{Alert, AlertManager, AlertSeverity, AlertStore, AlertType, AlertDeliveryConfig};
+use health::{HealthCheck, HealthChecker, HealthStatus, HealthTarget};
+use metrics::{MetricDeviation, MetricsBaseline, MetricsCollector, MetricsEma, MetricsTarget, ResourceMetrics};
+use crate::cost::CostAnomaly;
+use crate::exit::CliError;
+use crate::inventory::{XNodeEntry, XNodeInventory};
+use alerts::{cost_tier_level, Alert, AlertManager, AlertSeverity, AlertStore, AlertType, AlertDeliveryConfig, CostTier};
+use silences::{Silence, SilenceStore, SilenceTarget};
 
-const MAX_HEALTH_HISTORY: usize = 288;  // 24 hours at 5 min intervals
-const MAX_METRICS_HISTORY: usize = 1440; // 24 hours at 1 min intervals
+const DEFAULT_HEALTH_HISTORY_POINTS: usize = 288;  // 24 hours at 5 min intervals
+const DEFAULT_METRICS_HISTORY_POINTS: usize = 1440; // 24 hours at 1 min intervals
+const DEFAULT_CACHE_FRESHNESS_SECONDS: u64 = 30;
+const DEFAULT_COST_TIER_HYSTERESIS: f64 = 0.05;
+
+/// Most SSH metric collections `collect_metrics_concurrently` runs at once
+const MAX_CONCURRENT_COLLECTIONS: usize = 16;
+
+/// Smallest retention accepted for either history
+pub const MIN_HISTORY_POINTS: usize = 12;
+
+const HEALTH_HISTORY_FILE: &str = "health_history.json";
+const METRICS_HISTORY_FILE: &str = "metrics_history.json";
+const ACTIVE_ALERTS_FILE: &str = "active_alerts.json";
+const SILENCES_FILE: &str = "silences.json";
+
+/// `xnode_id` used for alerts about the inventory as a whole
+pub const COST_ALERT_SOURCE: &str = "inventory";
+
+/// Metadata key holding the tier a cost-tier alert was raised for
+const COST_TIER_KEY: &str = "tier_monthly";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitoringConfig {
@@ -34,6 +65,38 @@ pub struct MonitoringConfig {
     pub disk_warning_threshold: f64,
     pub disk_critical_threshold: f64,
 
+    /// TCP ports probed on every xNode in health checks, in addition to
+    /// any listed in the node's `tcp_ports` metadata
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tcp_ports: Vec<u16>,
+
+    /// Compare an exponential moving average of CPU, memory and disk with
+    /// the thresholds instead of the latest sample. The value (0 < alpha <= 1)
+    /// is the weight of each new sample; lower values need longer sustained
+    /// load to alert. Unset compares raw samples.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics_smoothing_alpha: Option<f64>,
+
+    /// Flag CPU, memory or disk usage more than this many standard
+    /// deviations from the node's own mean over its metrics history, even
+    /// below the thresholds. Unset turns baseline tracking off.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics_baseline_sigma: Option<f64>,
+
+    /// Also raise info alerts for baseline deviations
+    #[serde(default)]
+    pub metrics_baseline_alerts: bool,
+
+    /// Monthly spend levels for the inventory, each raising a
+    /// `cost_threshold` alert of its severity once exceeded
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cost_tiers: Vec<CostTier>,
+
+    /// How far (as a fraction of the tier) spend must fall below an alerted
+    /// tier before it stops applying and can fire again
+    #[serde(default = "default_cost_tier_hysteresis")]
+    pub cost_tier_hysteresis: f64,
+
     // Alert delivery
     #[serde(flatten)]
     pub alert_delivery: AlertDeliveryConfig,
@@ -41,6 +104,97 @@ pub struct MonitoringConfig {
     // Auto-remediation
     pub auto_restart_on_failure: bool,
     pub auto_scale_on_high_load: bool,
+
+    // History retention, in samples kept per xNode
+    #[serde(default = "default_health_history_points")]
+    pub health_history_points: usize,
+    #[serde(default = "default_metrics_history_points")]
+    pub metrics_history_points: usize,
+
+    /// Reuse a node's last health check or metrics sample if it is at most
+    /// this old instead of reconnecting; 0 always checks
+    #[serde(default = "default_cache_freshness_seconds")]
+    pub cache_freshness_seconds: u64,
+}
+
+fn default_cache_freshness_seconds() -> u64 {
+    DEFAULT_CACHE_FRESHNESS_SECONDS
+}
+
+fn default_cost_tier_hysteresis() -> f64 {
+    DEFAULT_COST_TIER_HYSTERESIS
+}
+
+fn default_health_history_points() -> usize {
+    DEFAULT_HEALTH_HISTORY_POINTS
+}
+
+fn default_metrics_history_points() -> usize {
+    DEFAULT_METRICS_HISTORY_POINTS
+}
+
+impl MonitoringConfig {
+    /// Reject settings that would make the monitor misbehave
+    pub fn validate(&self) -> Result<()> {
+        for (name, points) in [
+            ("health_history_points", self.health_history_points),
+            ("metrics_history_points", self.metrics_history_points),
+        ] {
+            if points < MIN_HISTORY_POINTS {
+                return Err(CliError::usage(format!(
+                    "{} must be at least {} (got {})",
+                    name, MIN_HISTORY_POINTS, points
+                ))
+                .into());
+            }
+        }
+        if let Some(alpha) = self.metrics_smoothing_alpha {
+            if !(alpha > 0.0 && alpha <= 1.0) {
+                return Err(CliError::usage(format!(
+                    "metrics_smoothing_alpha must be greater than 0 and at most 1 (got {})",
+                    alpha
+                ))
+                .into());
+            }
+        }
+        for tier in &self.cost_tiers {
+            if !(tier.monthly > 0.0 && tier.monthly.is_finite()) {
+                return Err(CliError::usage(format!(
+                    "cost_tiers amounts must be greater than 0 (got {})",
+                    tier.monthly
+                ))
+                .into());
+            }
+        }
+        let tiers = self.sorted_cost_tiers();
+        if let Some(pair) = tiers.windows(2).find(|pair| pair[0].monthly == pair[1].monthly) {
+            return Err(CliError::usage(format!("cost_tiers lists ${:.2} more than once", pair[0].monthly)).into());
+        }
+        if !(0.0..1.0).contains(&self.cost_tier_hysteresis) {
+            return Err(CliError::usage(format!(
+                "cost_tier_hysteresis must be at least 0 and below 1 (got {})",
+                self.cost_tier_hysteresis
+            ))
+            .into());
+        }
+        if let Some(sigma) = self.metrics_baseline_sigma {
+            if !(sigma > 0.0 && sigma.is_finite()) {
+                return Err(CliError::usage(format!(
+                    "metrics_baseline_sigma must be greater than 0 (got {})",
+                    sigma
+                ))
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// `cost_tiers` from the cheapest up
+    pub fn sorted_cost_tiers(&self) -> Vec<CostTier> {
+        let mut tiers = self.cost_tiers.clone();
+        tiers.sort_by(|a, b| a.monthly.total_cmp(&b.monthly));
+        tiers
+    }
 }
 
 impl Default for MonitoringConfig {
@@ -57,13 +211,140 @@ impl Default for MonitoringConfig {
             memory_critical_threshold: 95.0,
             disk_warning_threshold: 85.0,
             disk_critical_threshold: 95.0,
+            tcp_ports: Vec::new(),
+            metrics_smoothing_alpha: None,
+            metrics_baseline_sigma: None,
+            metrics_baseline_alerts: false,
+            cost_tiers: Vec::new(),
+            cost_tier_hysteresis: DEFAULT_COST_TIER_HYSTERESIS,
             alert_delivery: AlertDeliveryConfig::default(),
             auto_restart_on_failure: false,
             auto_scale_on_high_load: false,
+            health_history_points: DEFAULT_HEALTH_HISTORY_POINTS,
+            metrics_history_points: DEFAULT_METRICS_HISTORY_POINTS,
+            cache_freshness_seconds: DEFAULT_CACHE_FRESHNESS_SECONDS,
         }
     }
 }
 
+/// The network side of a `MonitoringSystem`, cloned out of it so a fleet
+/// can be probed without holding the system (or its lock) meanwhile
+#[derive(Clone)]
+pub struct FleetProber {
+    health_checker: HealthChecker,
+    metrics_collector: MetricsCollector,
+    /// Configured ports probed on every node
+    tcp_ports: Vec<u16>,
+}
+
+/// Unrecorded results of one pass over a set of xNodes, in node order
+#[derive(Debug, Default)]
+pub struct FleetSweep {
+    pub checks: Vec<HealthCheck>,
+    /// Empty unless metrics were requested; otherwise one per node
+    pub metrics: Vec<Option<ResourceMetrics>>,
+}
+
+impl FleetProber {
+    /// Check the health of `nodes`, and collect their metrics with
+    /// `with_metrics`; record the result with `MonitoringSystem::apply_sweep`
+    pub async fn sweep(&self, nodes: &[XNodeEntry], with_metrics: bool) -> FleetSweep {
+        let targets: Vec<HealthTarget> = nodes.iter().map(HealthTarget::for_xnode).collect();
+        let checks = self.check_health(&targets).await;
+        let metrics = if with_metrics {
+            let targets: Vec<MetricsTarget> = nodes.iter().map(MetricsTarget::for_xnode).collect();
+            self.collect_metrics(&targets).await
+        } else {
+            Vec::new()
+        };
+        FleetSweep { checks, metrics }
+    }
+
+    /// Check every one of `targets` at once; results follow their order
+    pub async fn check_health(&self, targets: &[HealthTarget]) -> Vec<HealthCheck> {
+        let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_COLLECTIONS));
+        let mut tasks = JoinSet::new();
+        for (i, target) in targets.iter().cloned().enumerate() {
+            let checker = self.health_checker.clone();
+            let tcp_ports = self.tcp_ports(&target.tcp_ports);
+            let permits = permits.clone();
+            tasks.spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                let check = checker
+                    .check_health(target.xnode_id, target.ip_address.as_deref(), target.has_webserver, &tcp_ports)
+                    .await;
+                (i, check)
+            });
+        }
+
+        let mut checked = vec![None; targets.len()];
+        while let Some(joined) = tasks.join_next().await {
+            if let Ok((i, check)) = joined {
+                checked[i] = Some(check);
+            }
+        }
+
+        targets
+            .iter()
+            .zip(checked)
+            .map(|(target, check)| {
+                // A check that panicked tells us nothing about the node
+                check.unwrap_or_else(|| {
+                    let mut check = HealthCheck::new(target.xnode_id.clone());
+                    check.error_messages.push("Health check did not complete".to_string());
+                    check
+                })
+            })
+            .collect()
+    }
+
+    /// Collect metrics from all `targets` at once; results follow their
+    /// order, with `None` for nodes that could not be reached
+    pub async fn collect_metrics(&self, targets: &[MetricsTarget]) -> Vec<Option<ResourceMetrics>> {
+        let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_COLLECTIONS));
+        let mut tasks = JoinSet::new();
+        for (i, target) in targets.iter().cloned().enumerate() {
+            let collector = self.metrics_collector.clone();
+            let permits = permits.clone();
+            tasks.spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                let metrics = collector
+                    .collect_metrics(
+                        target.xnode_id,
+                        target.ip_address.as_deref(),
+                        target.ssh_user.as_deref(),
+                        target.ssh_key_path.as_deref(),
+                    )
+                    .await;
+                (i, metrics)
+            });
+        }
+
+        let mut collected = vec![None; targets.len()];
+        while let Some(joined) = tasks.join_next().await {
+            if let Ok((i, metrics)) = joined {
+                collected[i] = metrics;
+            }
+        }
+        collected
+    }
+
+    fn tcp_ports(&self, node_ports: &[u16]) -> Vec<u16> {
+        merge_ports(&self.tcp_ports, node_ports)
+    }
+}
+
+/// `configured` followed by any of `node_ports` not already in it
+fn merge_ports(configured: &[u16], node_ports: &[u16]) -> Vec<u16> {
+    let mut ports = configured.to_vec();
+    for port in node_ports {
+        if !ports.contains(port) {
+            ports.push(*port);
+        }
+    }
+    ports
+}
+
 pub struct MonitoringSystem {
     config_path: PathBuf,
     data_dir: PathBuf,
@@ -73,23 +354,29 @@ pub struct MonitoringSystem {
     alert_manager: AlertManager,
     health_history: HashMap<String, Vec<HealthCheck>>,
     metrics_history: HashMap<String, Vec<ResourceMetrics>>,
+    /// Smoothed metrics per xNode when `metrics_smoothing_alpha` is set
+    metrics_ema: HashMap<String, MetricsEma>,
     alert_store: AlertStore,
+    silences: SilenceStore,
+    /// Inventory tags per xNode, for silences that target a tag
+    xnode_tags: HashMap<String, Vec<String>>,
+    /// Ignore `cache_freshness_seconds` and always check
+    force_refresh: bool,
+    /// Whether the last `check_health`/`collect_metrics` reused a result
+    served_from_cache: bool,
 }
 
 impl MonitoringSystem {
     pub async fn new(config_path: Option<PathBuf>) -> Result<Self> {
-        let config_path = config_path.unwrap_or_else(|| {
-            dirs::home_dir()
-                .unwrap()
-                .join(".capsule")
-                .join("monitoring.yml")
-        });
+        let capsule_dir = dirs::home_dir().unwrap().join(".capsule");
+        let config_path = config_path.unwrap_or_else(|| capsule_dir.join("monitoring.yml"));
 
-        let data_dir = dirs::home_dir()
-            .unwrap()
-            .join(".capsule")
-            .join("monitoring_data");
+        Self::with_data_dir(config_path, capsule_dir.join("monitoring_data")).await
+    }
 
+    /// Like `new`, but keeping history in `data_dir` instead of
+    /// `~/.capsule/monitoring_data`
+    pub async fn with_data_dir(config_path: PathBuf, data_dir: PathBuf) -> Result<Self> {
         fs::create_dir_all(&data_dir).await?;
 
         let config = Self::load_config(&config_path).await?;
@@ -110,7 +397,12 @@ impl MonitoringSystem {
             alert_manager,
             health_history: HashMap::new(),
             metrics_history: HashMap::new(),
+            metrics_ema: HashMap::new(),
             alert_store: AlertStore::new(),
+            silences: SilenceStore::new(),
+            xnode_tags: HashMap::new(),
+            force_refresh: false,
+            served_from_cache: false,
         };
 
         system.load_history().await?;
@@ -122,6 +414,9 @@ impl MonitoringSystem {
         if path.exists() {
             let content = fs::read_to_string(path).await?;
             let config: MonitoringConfig = serde_yaml::from_str(&content)?;
+            config
+                .validate()
+                .with_context(|| format!("Invalid monitoring config {}", path.display()))?;
             Ok(config)
         } else {
             Ok(MonitoringConfig::default())
@@ -139,121 +434,294 @@ impl MonitoringSystem {
 
     async fn load_history(&mut self) -> Result<()> {
         // Load health history
-        let health_file = self.data_dir.join("health_history.json");
+        let health_file = self.data_dir.join(HEALTH_HISTORY_FILE);
         if health_file.exists() {
             let content = fs::read_to_string(&health_file).await?;
             let data: HashMap<String, Vec<HealthCheck>> = serde_json::from_str(&content)?;
-            for (xnode_id, mut checks) in data {
-                if checks.len() > MAX_HEALTH_HISTORY {
-                    checks = checks.into_iter().rev().take(MAX_HEALTH_HISTORY).rev().collect();
-                }
+            for (xnode_id, checks) in data {
+                let checks = keep_last(&checks, self.config.health_history_points);
                 self.health_history.insert(xnode_id, checks);
             }
         }
 
         // Load metrics history
-        let metrics_file = self.data_dir.join("metrics_history.json");
+        let metrics_file = self.data_dir.join(METRICS_HISTORY_FILE);
         if metrics_file.exists() {
             let content = fs::read_to_string(&metrics_file).await?;
             let data: HashMap<String, Vec<ResourceMetrics>> = serde_json::from_str(&content)?;
-            for (xnode_id, mut metrics) in data {
-                if metrics.len() > MAX_METRICS_HISTORY {
-                    metrics = metrics.into_iter().rev().take(MAX_METRICS_HISTORY).rev().collect();
-                }
+            for (xnode_id, metrics) in data {
+                let metrics = keep_last(&metrics, self.config.metrics_history_points);
                 self.metrics_history.insert(xnode_id, metrics);
             }
         }
 
         // Load active alerts
-        let alerts_file = self.data_dir.join("active_alerts.json");
+        let alerts_file = self.data_dir.join(ACTIVE_ALERTS_FILE);
         if alerts_file.exists() {
             let content = fs::read_to_string(&alerts_file).await?;
             let data: HashMap<String, Alert> = serde_json::from_str(&content)?;
             self.alert_store.load_from_map(data);
         }
 
+        let silences_file = self.data_dir.join(SILENCES_FILE);
+        if silences_file.exists() {
+            let content = fs::read_to_string(&silences_file).await?;
+            let data: Vec<Silence> = serde_json::from_str(&content)?;
+            self.silences.load(data);
+        }
+
         Ok(())
     }
 
+    /// Persist health, metrics, alerts and unexpired silences. Each file is
+    /// staged next to its target first, so a failed write (e.g. disk full)
+    /// leaves all four as they were; see `HistorySaveError` for what was
+    /// saved otherwise.
     pub async fn save_history(&self) -> Result<()> {
-        // Save health history (last 24 hours)
+        // Trim to the configured retention
         let health_data: HashMap<String, Vec<HealthCheck>> = self
             .health_history
             .iter()
-            .map(|(k, v)| {
-                let limited: Vec<HealthCheck> = v.iter().rev().take(MAX_HEALTH_HISTORY).rev().cloned().collect();
-                (k.clone(), limited)
-            })
+            .map(|(k, v)| (k.clone(), keep_last(v, self.config.health_history_points)))
             .collect();
-        let content = serde_json::to_string_pretty(&health_data)?;
-        fs::write(self.data_dir.join("health_history.json"), content).await?;
-
-        // Save metrics history (last 24 hours)
         let metrics_data: HashMap<String, Vec<ResourceMetrics>> = self
             .metrics_history
             .iter()
-            .map(|(k, v)| {
-                let limited: Vec<ResourceMetrics> = v.iter().rev().take(MAX_METRICS_HISTORY).rev().cloned().collect();
-                (k.clone(), limited)
-            })
+            .map(|(k, v)| (k.clone(), keep_last(v, self.config.metrics_history_points)))
             .collect();
-        let content = serde_json::to_string_pretty(&metrics_data)?;
-        fs::write(self.data_dir.join("metrics_history.json"), content).await?;
-
-        // Save active alerts
-        let content = serde_json::to_string_pretty(self.alert_store.as_map())?;
-        fs::write(self.data_dir.join("active_alerts.json"), content).await?;
 
+        let files = [
+            (HEALTH_HISTORY_FILE, serde_json::to_string_pretty(&health_data)?),
+            (METRICS_HISTORY_FILE, serde_json::to_string_pretty(&metrics_data)?),
+            (ACTIVE_ALERTS_FILE, serde_json::to_string_pretty(self.alert_store.as_map())?),
+            (SILENCES_FILE, serde_json::to_string_pretty(&self.silences.active(chrono::Utc::now()))?),
+        ];
+        write_files_atomically(&self.data_dir, &files).await?;
         Ok(())
     }
 
+    /// Always check nodes, even if a result within the freshness window exists
+    pub fn set_force_refresh(&mut self, force: bool) {
+        self.force_refresh = force;
+    }
+
+    /// Whether the last `check_health` or `collect_metrics` call returned a
+    /// cached result instead of contacting the node
+    pub fn served_from_cache(&self) -> bool {
+        self.served_from_cache
+    }
+
+    /// Configured ports followed by the node's own, without duplicates
+    fn tcp_ports(&self, node_ports: &[u16]) -> Vec<u16> {
+        merge_ports(&self.config.tcp_ports, node_ports)
+    }
+
+    /// The newest of `history` if it is recent enough to reuse
+    fn fresh<'a, T>(&self, history: Option<&'a Vec<T>>, timestamp: impl Fn(&T) -> &str) -> Option<&'a T> {
+        if self.force_refresh || self.config.cache_freshness_seconds == 0 {
+            return None;
+        }
+        let latest = history?.last()?;
+        let age = result_age(timestamp(latest))?;
+        (age <= Duration::from_secs(self.config.cache_freshness_seconds)).then_some(latest)
+    }
+
+    /// Check an xNode's health, reusing a check within the freshness window.
+    /// `tcp_ports` are probed along with the configured `tcp_ports`.
     pub async fn check_health(
         &mut self,
         xnode_id: String,
         ip_address: Option<&str>,
         has_webserver: bool,
+        tcp_ports: &[u16],
     ) -> HealthCheck {
+        let cached = self.fresh(self.health_history.get(&xnode_id), |c| &c.timestamp).cloned();
+        self.served_from_cache = cached.is_some();
+        if let Some(cached) = cached {
+            return cached;
+        }
+
         let health_check = self
             .health_checker
-            .check_health(xnode_id.clone(), ip_address, has_webserver)
+            .check_health(xnode_id.clone(), ip_address, has_webserver, &self.tcp_ports(tcp_ports))
             .await;
 
-        // Store in history
-        self.health_history
-            .entry(xnode_id.clone())
-            .or_insert_with(Vec::new)
-            .push(health_check.clone());
+        self.record_health(health_check.clone()).await;
+        health_check
+    }
 
-        // Check for alerts
-        self.check_health_alerts(&health_check).await;
+    /// The probing half of this system, to check xNodes without holding it
+    pub fn prober(&self) -> FleetProber {
+        FleetProber {
+            health_checker: self.health_checker.clone(),
+            metrics_collector: self.metrics_collector.clone(),
+            tcp_ports: self.config.tcp_ports.clone(),
+        }
+    }
 
-        health_check
+    /// Check every one of `targets` at once, bypassing the freshness cache,
+    /// and record each result and its alerts. Results follow the order of
+    /// `targets`.
+    pub async fn check_health_concurrently(&mut self, targets: &[HealthTarget]) -> Vec<HealthCheck> {
+        let checks = self.prober().check_health(targets).await;
+        self.served_from_cache = false;
+        for check in &checks {
+            self.record_health(check.clone()).await;
+        }
+        checks
     }
 
     pub async fn collect_metrics(
         &mut self,
         xnode_id: String,
         ip_address: Option<&str>,
+        ssh_user: Option<&str>,
         ssh_key_path: Option<&str>,
     ) -> Option<ResourceMetrics> {
+        let cached = self.fresh(self.metrics_history.get(&xnode_id), |m| &m.timestamp).cloned();
+        self.served_from_cache = cached.is_some();
+        if cached.is_some() {
+            return cached;
+        }
+
         let metrics = self
             .metrics_collector
-            .collect_metrics(xnode_id.clone(), ip_address, ssh_key_path)
+            .collect_metrics(xnode_id, ip_address, ssh_user, ssh_key_path)
             .await?;
+        self.record_metrics(metrics.clone()).await;
+        Some(metrics)
+    }
+
+    /// Collect fresh metrics from all `targets` at once, bypassing the
+    /// freshness cache, and record each sample. Results follow the order of
+    /// `targets`; nodes that could not be reached come back as `None`.
+    pub async fn collect_metrics_concurrently(
+        &mut self,
+        targets: &[MetricsTarget],
+    ) -> Vec<(String, Option<ResourceMetrics>)> {
+        let collected = self.prober().collect_metrics(targets).await;
+        self.served_from_cache = false;
+        for metrics in collected.iter().flatten() {
+            self.record_metrics(metrics.clone()).await;
+        }
+        targets.iter().map(|t| t.xnode_id.clone()).zip(collected).collect()
+    }
+
+    /// Record a sweep from `prober()`: keep each result and raise its
+    /// alerts, mark the nodes that answered as seen in the inventory,
+    /// re-check cost tiers against `monthly_cost` and save history
+    pub async fn apply_sweep(&mut self, sweep: &FleetSweep, monthly_cost: f64) -> Result<()> {
+        self.served_from_cache = false;
+        for check in &sweep.checks {
+            self.record_health(check.clone()).await;
+        }
+        for metrics in sweep.metrics.iter().flatten() {
+            self.record_metrics(metrics.clone()).await;
+        }
+
+        // Any passing check means the node is still there
+        let seen: Vec<String> = sweep
+            .checks
+            .iter()
+            .filter(|c| matches!(c.status, HealthStatus::Healthy | HealthStatus::Degraded))
+            .map(|c| c.xnode_id.clone())
+            .collect();
+        if !seen.is_empty() {
+            XNodeInventory::new(None)?.mark_seen(&seen, chrono::Utc::now())?;
+        }
+
+        self.check_cost_tiers(monthly_cost).await;
+        self.save_history().await
+    }
+
+    /// Add a check to the xNode's history and raise any alerts it calls for
+    async fn record_health(&mut self, check: HealthCheck) {
+        self.health_history
+            .entry(check.xnode_id.clone())
+            .or_default()
+            .push(check.clone());
+        self.check_health_alerts(&check).await;
+    }
+
+    /// Add a sample to the xNode's history and raise any threshold alerts
+    pub async fn record_metrics(&mut self, metrics: ResourceMetrics) {
+        self.update_ema(&metrics);
+        let deviations = self.baseline_deviations(&metrics);
 
         // Store in history
         self.metrics_history
-            .entry(xnode_id.clone())
-            .or_insert_with(Vec::new)
+            .entry(metrics.xnode_id.clone())
+            .or_default()
             .push(metrics.clone());
 
         // Check for alerts
         self.check_metrics_alerts(&metrics).await;
+        if self.config.metrics_baseline_alerts && !deviations.is_empty() {
+            let summary: Vec<String> = deviations.iter().map(ToString::to_string).collect();
+            self.create_alert(
+                metrics.xnode_id.clone(),
+                AlertType::MetricAnomaly,
+                AlertSeverity::Info,
+                format!("Unusual usage: {}", summary.join(", ")),
+                serde_json::to_value(&deviations).ok(),
+            ).await;
+        }
+    }
 
-        Some(metrics)
+    /// Metrics in `metrics` far from the node's baseline over the samples
+    /// before it; empty unless `metrics_baseline_sigma` is set
+    pub fn baseline_deviations(&self, metrics: &ResourceMetrics) -> Vec<MetricDeviation> {
+        let Some(sigmas) = self.config.metrics_baseline_sigma else {
+            return Vec::new();
+        };
+        let history = self.metrics_history(&metrics.xnode_id);
+        let prior = match history.split_last() {
+            Some((last, rest)) if last.timestamp == metrics.timestamp => rest,
+            _ => history,
+        };
+        MetricsBaseline::from_samples(prior).map_or_else(Vec::new, |baseline| baseline.deviations(metrics, sigmas))
+    }
+
+    /// Fold `metrics` into the node's moving average, seeding it from the
+    /// stored history the first time so one-shot runs smooth too
+    fn update_ema(&mut self, metrics: &ResourceMetrics) {
+        let Some(alpha) = self.config.metrics_smoothing_alpha else {
+            return;
+        };
+        let history = self.metrics_history.get(&metrics.xnode_id).map_or(&[][..], Vec::as_slice);
+        match self.metrics_ema.get_mut(&metrics.xnode_id) {
+            Some(ema) => ema.update(alpha, metrics),
+            None => {
+                let mut ema = MetricsEma::from_samples(alpha, history)
+                    .unwrap_or_else(|| MetricsEma::new(metrics));
+                if !history.is_empty() {
+                    ema.update(alpha, metrics);
+                }
+                self.metrics_ema.insert(metrics.xnode_id.clone(), ema);
+            }
+        }
+    }
+
+    /// Smoothed metrics for an xNode, if smoothing is enabled and it has samples
+    pub fn smoothed_metrics(&self, xnode_id: &str) -> Option<&MetricsEma> {
+        self.metrics_ema.get(xnode_id)
+    }
+
+    /// Resolve the xNode's open alert of `alert_type` once its condition
+    /// has cleared
+    fn resolve_recovered(&mut self, xnode_id: &str, alert_type: AlertType) {
+        if let Some(alert) = self.alert_store.similar_alert(xnode_id, alert_type) {
+            let id = alert.id.clone();
+            self.alert_store.resolve_alert(&id);
+        }
     }
 
     async fn check_health_alerts(&mut self, health_check: &HealthCheck) {
+        if health_check.status == HealthStatus::Healthy {
+            self.resolve_recovered(&health_check.xnode_id, AlertType::SshUnreachable);
+            self.resolve_recovered(&health_check.xnode_id, AlertType::ServiceDown);
+        }
+
         if health_check.status == HealthStatus::Unhealthy {
             if !health_check.checks.get("ssh").copied().unwrap_or(true) {
                 self.create_alert(
@@ -278,61 +746,83 @@ impl MonitoringSystem {
     }
 
     async fn check_metrics_alerts(&mut self, metrics: &ResourceMetrics) {
+        let compared = self
+            .smoothed_metrics(&metrics.xnode_id)
+            .copied()
+            .unwrap_or_else(|| MetricsEma::new(metrics));
+        let smoothed = self.config.metrics_smoothing_alpha.is_some();
+        let usage = |value: f64, latest: f64| {
+            if smoothed {
+                format!("{:.1}% (smoothed, latest {:.1}%)", value, latest)
+            } else {
+                format!("{:.1}%", value)
+            }
+        };
+        let cpu = usage(compared.cpu_percent, metrics.cpu_percent);
+        let memory = usage(compared.memory_percent, metrics.memory_percent);
+        let disk = usage(compared.disk_percent, metrics.disk_percent);
+
         // CPU alerts
-        if metrics.cpu_percent >= self.config.cpu_critical_threshold {
+        if compared.cpu_percent >= self.config.cpu_critical_threshold {
             self.create_alert(
                 metrics.xnode_id.clone(),
                 AlertType::HighCpu,
                 AlertSeverity::Critical,
-                format!("Critical CPU usage: {:.1}%", metrics.cpu_percent),
+                format!("Critical CPU usage: {}", cpu),
                 Some(serde_json::to_value(metrics).unwrap()),
             ).await;
-        } else if metrics.cpu_percent >= self.config.cpu_warning_threshold {
+        } else if compared.cpu_percent >= self.config.cpu_warning_threshold {
             self.create_alert(
                 metrics.xnode_id.clone(),
                 AlertType::HighCpu,
                 AlertSeverity::Warning,
-                format!("High CPU usage: {:.1}%", metrics.cpu_percent),
+                format!("High CPU usage: {}", cpu),
                 Some(serde_json::to_value(metrics).unwrap()),
             ).await;
+        } else {
+            self.resolve_recovered(&metrics.xnode_id, AlertType::HighCpu);
         }
 
         // Memory alerts
-        if metrics.memory_percent >= self.config.memory_critical_threshold {
+        if compared.memory_percent >= self.config.memory_critical_threshold {
             self.create_alert(
                 metrics.xnode_id.clone(),
                 AlertType::HighMemory,
                 AlertSeverity::Critical,
-                format!("Critical memory usage: {:.1}%", metrics.memory_percent),
+                format!("Critical memory usage: {}", memory),
                 Some(serde_json::to_value(metrics).unwrap()),
             ).await;
-        } else if metrics.memory_percent >= self.config.memory_warning_threshold {
+        } else if compared.memory_percent >= self.config.memory_warning_threshold {
             self.create_alert(
                 metrics.xnode_id.clone(),
                 AlertType::HighMemory,
                 AlertSeverity::Warning,
-                format!("High memory usage: {:.1}%", metrics.memory_percent),
+                format!("High memory usage: {}", memory),
                 Some(serde_json::to_value(metrics).unwrap()),
             ).await;
+        } else {
+            self.resolve_recovered(&metrics.xnode_id, AlertType::HighMemory);
         }
 
         // Disk alerts
-        if metrics.disk_percent >= self.config.disk_critical_threshold {
+        if compared.disk_percent >= self.config.disk_critical_threshold {
             self.create_alert(
                 metrics.xnode_id.clone(),
                 AlertType::LowDisk,
                 AlertSeverity::Critical,
-                format!("Critical disk usage: {:.1}%", metrics.disk_percent),
+                format!("Critical disk usage: {}", disk),
                 Some(serde_json::to_value(metrics).unwrap()),
             ).await;
-        } else if metrics.disk_percent >= self.config.disk_warning_threshold {
+        } else if compared.disk_percent >= self.config.disk_warning_threshold {
             self.create_alert(
                 metrics.xnode_id.clone(),
                 AlertType::LowDisk,
                 AlertSeverity::Warning,
-                format!("High disk usage: {:.1}%", metrics.disk_percent),
+                format!("High disk usage: {}", disk),
                 Some(serde_json::to_value(metrics).unwrap()),
             ).await;
+        } else {
+            self.resolve_recovered(&metrics.xnode_id, AlertType::LowDisk);
         }
     }
 
@@ -344,39 +834,148 @@ impl MonitoringSystem {
         message: String,
         metadata: Option<serde_json::Value>,
     ) {
-        // Check if similar alert already exists (prevent spam)
-        if self.alert_store.has_similar_alert(&xnode_id, alert_type) {
-            return;
+        let silenced = self.is_silenced(&xnode_id);
+
+        // Check if similar alert already exists (prevent spam). One raised
+        // during a silence that has since ended is replaced, so the
+        // condition is still delivered.
+        if let Some(existing) = self.alert_store.similar_alert(&xnode_id, alert_type) {
+            if silenced || !existing.silenced {
+                return;
+            }
+            let id = existing.id.clone();
+            self.alert_store.resolve_alert(&id);
         }
 
-        let mut alert = Alert::new(xnode_id.clone(), alert_type, severity, message);
+        let mut alert = Alert::new(xnode_id, alert_type, severity, message);
         if let Some(metadata) = metadata {
             alert = alert.with_metadata(metadata);
         }
+        self.raise_alert(alert).await;
+    }
+
+    /// Deliver `alert` unless a silence covers its xNode, store it and run
+    /// any auto-remediation
+    async fn raise_alert(&mut self, mut alert: Alert) {
+        let silenced = self.is_silenced(&alert.xnode_id);
+        alert.silenced = silenced;
 
         // Deliver alert
-        if let Err(e) = self.alert_manager.deliver_alert(&alert).await {
-            eprintln!("Failed to deliver alert: {}", e);
+        if !silenced {
+            if let Err(e) = self.alert_manager.deliver_alert(&alert).await {
+                eprintln!("Failed to deliver alert: {}", e);
+            }
+        }
+
+        // Auto-remediation
+        if !silenced && self.config.auto_restart_on_failure && alert.alert_type == AlertType::ServiceDown {
+            eprintln!("Auto-remediation triggered for {}", alert.xnode_id);
+            // Would trigger restart here
         }
 
         // Store alert
         self.alert_store.add_alert(alert);
+    }
 
-        // Auto-remediation
-        if self.config.auto_restart_on_failure && alert_type == AlertType::ServiceDown {
-            eprintln!("Auto-remediation triggered for {}", xnode_id);
-            // Would trigger restart here
+    /// Raise an inventory-wide alert for a jump in spend
+    pub async fn raise_cost_alert(&mut self, anomaly: &CostAnomaly) {
+        self.create_alert(
+            COST_ALERT_SOURCE.to_string(),
+            AlertType::CostThreshold,
+            AlertSeverity::Warning,
+            format!("Cost anomaly: {}", anomaly.describe()),
+            serde_json::to_value(anomaly).ok(),
+        ).await;
+    }
+
+    /// Move the inventory's cost-tier alert to the tier that applies to a
+    /// monthly `total` and return that tier. Stepping up raises a new alert;
+    /// stepping down (past `cost_tier_hysteresis`) resolves it, recording the
+    /// lower tier still exceeded without delivering it again.
+    pub async fn check_cost_tiers(&mut self, total: f64) -> Option<CostTier> {
+        let tiers = self.config.sorted_cost_tiers();
+        let active = self
+            .alert_store
+            .get_alerts_for_xnode(COST_ALERT_SOURCE)
+            .into_iter()
+            .filter(|a| a.alert_type == AlertType::CostThreshold)
+            .find_map(|a| Some((a.id.clone(), a.metadata.as_ref()?.get(COST_TIER_KEY)?.as_f64()?)));
+        let current = active
+            .as_ref()
+            .and_then(|(_, monthly)| tiers.iter().position(|t| t.monthly == *monthly));
+
+        let level = cost_tier_level(&tiers, total, current, self.config.cost_tier_hysteresis);
+        if level == current && (current.is_some() || active.is_none()) {
+            return level.map(|i| tiers[i]);
+        }
+
+        if let Some((id, _)) = &active {
+            self.alert_store.resolve_alert(id);
+        }
+        let tier = tiers[level?];
+        let mut alert = Alert::new(
+            COST_ALERT_SOURCE.to_string(),
+            AlertType::CostThreshold,
+            tier.severity,
+            format!("Monthly cost ${:.2} is over the ${:.2} tier", total, tier.monthly),
+        )
+        .with_metadata(serde_json::json!({ COST_TIER_KEY: tier.monthly, "monthly_total": total }));
+        // Distinct from a cost anomaly alert raised in the same second
+        alert.id = format!("{}_tier_{}", alert.id, tier.monthly);
+
+        if level > current {
+            self.raise_alert(alert).await;
+        } else {
+            self.alert_store.add_alert(alert);
         }
+        Some(tier)
     }
 
     pub fn acknowledge_alert(&mut self, alert_id: &str) -> bool {
         self.alert_store.acknowledge_alert(alert_id)
     }
 
+    /// Tell the system which tags each xNode has, for tag silences
+    pub fn set_xnode_tags(&mut self, tags: HashMap<String, Vec<String>>) {
+        self.xnode_tags = tags;
+    }
+
+    fn is_silenced(&self, xnode_id: &str) -> bool {
+        let tags = self.xnode_tags.get(xnode_id).map_or(&[][..], Vec::as_slice);
+        self.silences.silencing(xnode_id, tags, chrono::Utc::now()).is_some()
+    }
+
+    /// Stop delivering alerts for `target` for `duration`
+    pub fn silence(&mut self, target: SilenceTarget, duration: chrono::Duration, reason: Option<String>) -> Silence {
+        let now = chrono::Utc::now();
+        self.silences.prune(now);
+        self.silences.add(target, duration, reason, now).clone()
+    }
+
+    /// Unexpired silences, ending soonest first
+    pub fn active_silences(&self) -> Vec<&Silence> {
+        self.silences.active(chrono::Utc::now())
+    }
+
+    /// Remove silences for which `select` holds, returning how many were removed
+    pub fn unsilence(&mut self, select: impl Fn(&Silence) -> bool) -> usize {
+        self.silences.remove_where(select)
+    }
+
     pub fn resolve_alert(&mut self, alert_id: &str) -> bool {
         self.alert_store.resolve_alert(alert_id)
     }
 
+    /// Recorded health checks for an xNode, oldest first
+    pub fn health_history(&self, xnode_id: &str) -> &[HealthCheck] {
+        self.health_history.get(xnode_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Recorded resource metrics for an xNode, oldest first
+    pub fn metrics_history(&self, xnode_id: &str) -> &[ResourceMetrics] {
+        self.metrics_history.get(xnode_id).map_or(&[], Vec::as_slice)
+    }
+
     pub fn get_xnode_status(&self, xnode_id: &str) -> XNodeStatus {
         let recent_health: Vec<_> = self
             .health_history
@@ -408,7 +1007,7 @@ impl MonitoringSystem {
             .keys()
             .chain(self.metrics_history.keys())
             .cloned()
-            .collect::<std::collections::HashSet<_>>()
+            .collect::<std::collections::BTreeSet<_>>()
             .into_iter()
             .collect();
 
@@ -433,7 +1032,7 @@ impl MonitoringSystem {
             .filter(|a| a.severity == AlertSeverity::Warning)
             .count();
 
-        let recent_checks: HashMap<String, HealthCheck> = all_xnodes
+        let recent_checks: BTreeMap<String, HealthCheck> = all_xnodes
             .iter()
             .filter_map(|xid| {
                 self.health_history
@@ -454,6 +1053,56 @@ impl MonitoringSystem {
         }
     }
 
+    /// Latest health, resource usage and active alert counts in the
+    /// Prometheus text exposition format
+    pub fn prometheus_metrics(&self) -> String {
+        use std::fmt::Write;
+
+        let xnodes: std::collections::BTreeSet<&String> =
+            self.health_history.keys().chain(self.metrics_history.keys()).collect();
+        let mut out = String::new();
+
+        out.push_str("# HELP capsule_xnode_healthy Whether the latest health check passed\n");
+        out.push_str("# TYPE capsule_xnode_healthy gauge\n");
+        for xnode_id in &xnodes {
+            if let Some(check) = self.health_history.get(*xnode_id).and_then(|h| h.last()) {
+                let _ = writeln!(
+                    out,
+                    "capsule_xnode_healthy{{xnode=\"{}\",status=\"{}\"}} {}",
+                    prometheus_label(xnode_id),
+                    check.status,
+                    u8::from(check.status == HealthStatus::Healthy)
+                );
+            }
+        }
+
+        let gauges: [(&str, &str, MetricField); 4] = [
+            ("capsule_xnode_cpu_percent", "CPU usage", |m| m.cpu_percent),
+            ("capsule_xnode_memory_percent", "Memory usage", |m| m.memory_percent),
+            ("capsule_xnode_disk_percent", "Disk usage", |m| m.disk_percent),
+            ("capsule_xnode_load1", "One-minute load average", |m| m.load_average.0),
+        ];
+        for (name, help, value) in gauges {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} gauge", name);
+            for xnode_id in &xnodes {
+                if let Some(metrics) = self.metrics_history.get(*xnode_id).and_then(|m| m.last()) {
+                    let _ = writeln!(out, "{}{{xnode=\"{}\"}} {}", name, prometheus_label(xnode_id), value(metrics));
+                }
+            }
+        }
+
+        let active_alerts = self.alert_store.get_active_alerts();
+        out.push_str("# HELP capsule_active_alerts Active alerts by severity\n");
+        out.push_str("# TYPE capsule_active_alerts gauge\n");
+        for severity in [AlertSeverity::Info, AlertSeverity::Warning, AlertSeverity::Critical] {
+            let count = active_alerts.iter().filter(|a| a.severity == severity).count();
+            let _ = writeln!(out, "capsule_active_alerts{{severity=\"{}\"}} {}", severity, count);
+        }
+
+        out
+    }
+
     pub fn get_config(&self) -> &MonitoringConfig {
         &self.config
     }
@@ -463,6 +1112,26 @@ impl MonitoringSystem {
     }
 }
 
+/// How long ago an RFC 3339 timestamp was; `None` if it doesn't parse or
+/// lies in the future
+pub fn result_age(timestamp: &str) -> Option<Duration> {
+    let then = chrono::DateTime::parse_from_rfc3339(timestamp).ok()?;
+    (chrono::Utc::now() - then.with_timezone(&chrono::Utc)).to_std().ok()
+}
+
+/// Reads one value out of a metrics sample
+type MetricField = fn(&ResourceMetrics) -> f64;
+
+/// Escape a Prometheus label value
+fn prometheus_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// The newest `limit` entries of a time-ordered history
+fn keep_last<T: Clone>(history: &[T], limit: usize) -> Vec<T> {
+    history[history.len().saturating_sub(limit)..].to_vec()
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct XNodeStatus {
     pub xnode_id: String,
@@ -481,5 +1150,507 @@ pub struct DashboardData {
     pub critical_alerts: usize,
     pub warning_alerts: usize,
     pub active_alerts: Vec<Alert>,
-    pub recent_checks: HashMap<String, HealthCheck>,
+    pub recent_checks: BTreeMap<String, HealthCheck>,
+}
+
+impl DashboardData {
+    /// Standalone HTML page with the overview, each node's latest health
+    /// check and active alerts, colour-coded by status and severity
+    pub fn generate_html(&self) -> String {
+        use crate::cost::escape_html;
+
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Capsule Monitoring Dashboard</title>\n\
+             <style>\n\
+             body { font-family: sans-serif; margin: 2em; }\n\
+             table { border-collapse: collapse; margin-bottom: 1.5em; }\n\
+             th, td { border: 1px solid #ccc; padding: 4px 12px; text-align: left; }\n\
+             td.count { text-align: right; }\n\
+             .healthy { color: #1a7f37; }\n\
+             .degraded, .warning { color: #b35900; }\n\
+             .unhealthy, .critical { color: #cf222e; font-weight: bold; }\n\
+             .unknown, .info { color: #57606a; }\n\
+             </style>\n</head>\n<body>\n",
+        );
+        html.push_str("<h1>Capsule Monitoring Dashboard</h1>\n");
+        html.push_str(&format!(
+            "<p>Generated: {}</p>\n",
+            chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
+        ));
+
+        html.push_str("<h2>Overview</h2>\n<table>\n");
+        for (label, value, class) in [
+            ("Total xNodes", self.total_xnodes, ""),
+            ("Healthy", self.healthy_xnodes, "healthy"),
+            ("Unhealthy", self.unhealthy_xnodes, "unhealthy"),
+            ("Critical alerts", self.critical_alerts, "critical"),
+            ("Warning alerts", self.warning_alerts, "warning"),
+        ] {
+            html.push_str(&format!(
+                "<tr><th>{}</th><td class=\"count {}\">{}</td></tr>\n",
+                label, class, value
+            ));
+        }
+        html.push_str("</table>\n");
+
+        html.push_str("<h2>xNode Health</h2>\n");
+        if self.recent_checks.is_empty() {
+            html.push_str("<p>No health checks recorded</p>\n");
+        } else {
+            html.push_str("<table>\n<tr><th>xNode</th><th>Status</th><th>Checks</th><th>Checked</th></tr>\n");
+            for (xnode_id, check) in &self.recent_checks {
+                let mut checks: Vec<_> = check.checks.iter().collect();
+                checks.sort();
+                let checks = checks
+                    .into_iter()
+                    .map(|(name, passed)| {
+                        let class = if *passed { "healthy" } else { "unhealthy" };
+                        format!("<span class=\"{}\">{}</span>", class, escape_html(name))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                html.push_str(&format!(
+                    "<tr><td>{}</td><td class=\"{}\">{}</td><td>{}</td><td>{}</td></tr>\n",
+                    escape_html(xnode_id),
+                    check.status,
+                    check.status,
+                    checks,
+                    escape_html(&check.timestamp)
+                ));
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("<h2>Active Alerts</h2>\n");
+        if self.active_alerts.is_empty() {
+            html.push_str("<p>No active alerts</p>\n");
+        } else {
+            html.push_str("<table>\n<tr><th>Severity</th><th>xNode</th><th>Message</th><th>Raised</th></tr>\n");
+            for alert in &self.active_alerts {
+                html.push_str(&format!(
+                    "<tr><td class=\"{}\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                    alert.severity,
+                    alert.severity,
+                    escape_html(&alert.xnode_id),
+                    escape_html(&alert.message),
+                    escape_html(&alert.timestamp)
+                ));
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+/// Which history files `save_history` managed to persist before failing
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "Failed to save {failed} ({source}); saved: {}; not saved: {}",
+    list_or_none(.saved),
+    list_or_none(.unsaved)
+)]
+pub struct HistorySaveError {
+    pub saved: Vec<String>,
+    pub failed: String,
+    pub unsaved: Vec<String>,
+    pub source: std::io::Error,
+}
+
+fn list_or_none(files: &[String]) -> String {
+    if files.is_empty() {
+        "none".to_string()
+    } else {
+        files.join(", ")
+    }
+}
+
+/// Write every `(file name, content)` pair into `dir`. All contents are
+/// written to `.tmp` files before any target is replaced, so only a failed
+/// rename can leave the set partly updated.
+async fn write_files_atomically(
+    dir: &Path,
+    files: &[(&str, String)],
+) -> std::result::Result<(), HistorySaveError> {
+    let names: Vec<String> = files.iter().map(|(name, _)| name.to_string()).collect();
+    let staged = |name: &str| dir.join(format!("{}.tmp", name));
+
+    for (i, (name, content)) in files.iter().enumerate() {
+        if let Err(source) = fs::write(staged(name), content).await {
+            // Including the one that failed, which may be partly written
+            for (written, _) in &files[..=i] {
+                let _ = fs::remove_file(staged(written)).await;
+            }
+            return Err(HistorySaveError {
+                saved: Vec::new(),
+                failed: name.to_string(),
+                unsaved: names,
+                source,
+            });
+        }
+    }
+
+    for (i, (name, _)) in files.iter().enumerate() {
+        if let Err(source) = fs::rename(staged(name), dir.join(name)).await {
+            for (pending, _) in &files[i..] {
+                let _ = fs::remove_file(staged(pending)).await;
+            }
+            return Err(HistorySaveError {
+                saved: names[..i].to_vec(),
+                failed: name.to_string(),
+                unsaved: names[i..].to_vec(),
+                source,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_retention_config() {
+        let config: MonitoringConfig = serde_yaml::from_str(
+            &serde_yaml::to_string(&MonitoringConfig::default())
+                .unwrap()
+                .lines()
+                .filter(|l| !l.contains("history_points"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+        .unwrap();
+        assert_eq!(config.health_history_points, DEFAULT_HEALTH_HISTORY_POINTS);
+        assert_eq!(config.metrics_history_points, DEFAULT_METRICS_HISTORY_POINTS);
+        assert!(config.validate().is_ok());
+
+        let config = MonitoringConfig { health_history_points: 2, ..config };
+        let err = config.validate().unwrap_err();
+        assert_eq!(crate::exit::exit_code_for(&err), crate::exit::USAGE);
+        assert!(err.to_string().contains("health_history_points"));
+
+        assert_eq!(keep_last(&[1, 2, 3, 4], 2), [3, 4]);
+        assert_eq!(keep_last(&[1, 2], 5), [1, 2]);
+    }
+
+    #[test]
+    fn test_dashboard_html() {
+        let mut check = HealthCheck::new("web-<1>".to_string());
+        check.status = HealthStatus::Degraded;
+        check.checks.insert("ping".to_string(), true);
+        check.checks.insert("tcp:5432".to_string(), false);
+        let alert = Alert::new(
+            "web-<1>".to_string(),
+            AlertType::HighCpu,
+            AlertSeverity::Critical,
+            "High CPU usage: 97.0%".to_string(),
+        );
+
+        let data = DashboardData {
+            total_xnodes: 1,
+            healthy_xnodes: 0,
+            unhealthy_xnodes: 1,
+            critical_alerts: 1,
+            warning_alerts: 0,
+            active_alerts: vec![alert],
+            recent_checks: BTreeMap::from([("web-<1>".to_string(), check)]),
+        };
+        let html = data.generate_html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("Generated: "));
+        assert!(html.contains("<td>web-&lt;1&gt;</td><td class=\"degraded\">degraded</td>"));
+        assert!(html.contains("<span class=\"unhealthy\">tcp:5432</span>"));
+        assert!(html.contains("<td class=\"critical\">critical</td>"));
+        assert!(!html.contains("web-<1>"));
+
+        let empty = DashboardData { active_alerts: Vec::new(), recent_checks: BTreeMap::new(), ..data };
+        assert!(empty.generate_html().contains("No active alerts"));
+    }
+
+    #[tokio::test]
+    async fn test_prometheus_metrics() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut system = MonitoringSystem::with_data_dir(dir.path().join("monitoring.yml"), dir.path().join("data"))
+            .await
+            .unwrap();
+        let mut check = HealthCheck::new("web-\"1\"".to_string());
+        check.status = HealthStatus::Healthy;
+        system.health_history.entry(check.xnode_id.clone()).or_default().push(check);
+        system
+            .record_metrics(ResourceMetrics { cpu_percent: 97.0, ..ResourceMetrics::new("db-1".to_string()) })
+            .await;
+
+        let text = system.prometheus_metrics();
+        assert!(text.contains("# TYPE capsule_xnode_healthy gauge"));
+        assert!(text.contains("capsule_xnode_healthy{xnode=\"web-\\\"1\\\"\",status=\"healthy\"} 1"));
+        assert!(text.contains("capsule_xnode_cpu_percent{xnode=\"db-1\"} 97"));
+        assert!(!text.contains("capsule_xnode_cpu_percent{xnode=\"web"));
+        assert!(text.contains("capsule_active_alerts{severity=\"critical\"} 1"));
+        assert!(text.contains("capsule_active_alerts{severity=\"info\"} 0"));
+    }
+
+    #[tokio::test]
+    async fn test_recent_results_are_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut system = MonitoringSystem::with_data_dir(dir.path().join("monitoring.yml"), dir.path().join("data"))
+            .await
+            .unwrap();
+        let sample = ResourceMetrics { cpu_percent: 12.0, ..ResourceMetrics::new("x-1".to_string()) };
+        system.record_metrics(sample).await;
+
+        // No IP means a real collection would fail, so a result is the cache
+        let cached = system.collect_metrics("x-1".to_string(), None, None, None).await.unwrap();
+        assert_eq!(cached.cpu_percent, 12.0);
+        assert!(system.served_from_cache());
+
+        system.set_force_refresh(true);
+        assert!(system.collect_metrics("x-1".to_string(), None, None, None).await.is_none());
+        assert!(!system.served_from_cache());
+        system.set_force_refresh(false);
+
+        system.get_config_mut().cache_freshness_seconds = 0;
+        assert!(system.collect_metrics("x-1".to_string(), None, None, None).await.is_none());
+
+        let stale = (chrono::Utc::now() - chrono::Duration::minutes(5)).to_rfc3339();
+        assert!(result_age(&stale).unwrap() >= Duration::from_secs(299));
+        assert!(result_age("not a time").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_smoothing_ignores_momentary_spikes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut system = MonitoringSystem::with_data_dir(dir.path().join("monitoring.yml"), dir.path().join("data"))
+            .await
+            .unwrap();
+        system.get_config_mut().metrics_smoothing_alpha = Some(0.3);
+        let sample = |cpu: f64| ResourceMetrics { cpu_percent: cpu, ..ResourceMetrics::new("x-1".to_string()) };
+
+        system.record_metrics(sample(20.0)).await;
+        system.record_metrics(sample(99.0)).await;
+        let ema = system.smoothed_metrics("x-1").unwrap();
+        assert!((ema.cpu_percent - 43.7).abs() < 1e-9);
+        assert!(system.get_xnode_status("x-1").active_alerts.is_empty());
+
+        // Sustained load pushes the average over the warning threshold
+        for _ in 0..3 {
+            system.record_metrics(sample(99.0)).await;
+        }
+        let alerts = system.get_xnode_status("x-1").active_alerts;
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].severity, AlertSeverity::Warning);
+        assert_eq!(alerts[0].message, "High CPU usage: 80.0% (smoothed, latest 99.0%)");
+        assert_eq!(system.metrics_history("x-1").len(), 5);
+
+        let config = MonitoringConfig { metrics_smoothing_alpha: Some(1.5), ..MonitoringConfig::default() };
+        assert!(config.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_baseline_deviations_raise_info_alerts() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut system = MonitoringSystem::with_data_dir(dir.path().join("monitoring.yml"), dir.path().join("data"))
+            .await
+            .unwrap();
+        system.get_config_mut().alert_delivery.console_alerts = false;
+        let sample = |cpu: f64| ResourceMetrics { cpu_percent: cpu, ..ResourceMetrics::new("x-1".to_string()) };
+        for _ in 0..metrics::MIN_BASELINE_SAMPLES {
+            system.record_metrics(sample(5.0)).await;
+        }
+
+        // Off by default
+        assert!(system.baseline_deviations(&sample(40.0)).is_empty());
+
+        system.get_config_mut().metrics_baseline_sigma = Some(3.0);
+        assert_eq!(system.baseline_deviations(&sample(40.0))[0].metric, "cpu");
+        system.record_metrics(sample(40.0)).await;
+        assert!(system.get_xnode_status("x-1").active_alerts.is_empty());
+
+        system.get_config_mut().metrics_baseline_alerts = true;
+        system.record_metrics(sample(45.0)).await;
+        let alerts = system.get_xnode_status("x-1").active_alerts;
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].alert_type, AlertType::MetricAnomaly);
+        assert_eq!(alerts[0].severity, AlertSeverity::Info);
+        assert!(alerts[0].message.starts_with("Unusual usage: cpu 45.0%"));
+
+        let config = MonitoringConfig { metrics_baseline_sigma: Some(0.0), ..MonitoringConfig::default() };
+        assert!(config.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_silenced_alerts_are_recorded_not_delivered() {
+        let dir = tempfile::tempdir().unwrap();
+        let open = || MonitoringSystem::with_data_dir(dir.path().join("monitoring.yml"), dir.path().join("data"));
+        let mut system = open().await.unwrap();
+        system.get_config_mut().alert_delivery.console_alerts = false;
+        system.set_xnode_tags(HashMap::from([("x-2".to_string(), vec!["web".to_string()])]));
+        let busy = |id: &str| ResourceMetrics { cpu_percent: 99.0, ..ResourceMetrics::new(id.to_string()) };
+
+        system.silence(SilenceTarget::Xnode("x-1".to_string()), chrono::Duration::hours(2), None);
+        system.silence(SilenceTarget::Tag("web".to_string()), chrono::Duration::hours(2), Some("deploy".to_string()));
+        system.record_metrics(busy("x-1")).await;
+        system.record_metrics(busy("x-2")).await;
+        system.record_metrics(busy("x-3")).await;
+        let silenced = |system: &MonitoringSystem, id: &str| {
+            system.get_xnode_status(id).active_alerts.iter().map(|a| a.silenced).collect::<Vec<_>>()
+        };
+        assert_eq!(silenced(&system, "x-1"), vec![true]);
+        assert_eq!(silenced(&system, "x-2"), vec![true]);
+        assert_eq!(silenced(&system, "x-3"), vec![false]);
+
+        // Once the silence is lifted the condition is raised again for delivery
+        assert_eq!(system.unsilence(|s| s.target == SilenceTarget::Xnode("x-1".to_string())), 1);
+        system.record_metrics(busy("x-1")).await;
+        assert_eq!(silenced(&system, "x-1"), vec![false]);
+
+        system.save_history().await.unwrap();
+        let reloaded = open().await.unwrap();
+        let silences = reloaded.active_silences();
+        assert_eq!(silences.len(), 1);
+        assert_eq!(silences[0].reason.as_deref(), Some("deploy"));
+    }
+
+    #[tokio::test]
+    async fn test_write_files_atomically_reports_what_was_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            ("a.json", "new-a".to_string()),
+            ("b.json", "new-b".to_string()),
+            ("c.json", "new-c".to_string()),
+        ];
+        std::fs::write(dir.path().join("a.json"), "old-a").unwrap();
+
+        // A staging failure leaves every target untouched
+        std::fs::create_dir(dir.path().join("b.json.tmp")).unwrap();
+        let err = write_files_atomically(dir.path(), &files).await.unwrap_err();
+        assert_eq!(err.failed, "b.json");
+        assert!(err.saved.is_empty());
+        assert_eq!(std::fs::read_to_string(dir.path().join("a.json")).unwrap(), "old-a");
+        assert!(!dir.path().join("a.json.tmp").exists());
+        assert!(err.to_string().contains("saved: none; not saved: a.json, b.json, c.json"));
+        std::fs::remove_dir(dir.path().join("b.json.tmp")).unwrap();
+
+        // A failed rename is reported with the files already replaced
+        std::fs::create_dir_all(dir.path().join("b.json/occupied")).unwrap();
+        let err = write_files_atomically(dir.path(), &files).await.unwrap_err();
+        assert_eq!(err.saved, ["a.json"]);
+        assert_eq!(err.unsaved, ["b.json", "c.json"]);
+        assert!(!dir.path().join("c.json.tmp").exists());
+        std::fs::remove_dir_all(dir.path().join("b.json")).unwrap();
+
+        write_files_atomically(dir.path(), &files).await.unwrap();
+        assert_eq!(std::fs::read_to_string(dir.path().join("c.json")).unwrap(), "new-c");
+    }
+
+    #[tokio::test]
+    async fn test_collect_metrics_concurrently_keeps_target_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut system = MonitoringSystem::with_data_dir(dir.path().join("monitoring.yml"), dir.path().join("data"))
+            .await
+            .unwrap();
+        let targets: Vec<MetricsTarget> = ["web-2", "db", "web-1"]
+            .iter()
+            .map(|id| MetricsTarget { xnode_id: id.to_string(), ..MetricsTarget::default() })
+            .collect();
+
+        // Nodes without an IP can't be reached and record nothing
+        let results = system.collect_metrics_concurrently(&targets).await;
+        let ids: Vec<_> = results.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, ["web-2", "db", "web-1"]);
+        assert!(results.iter().all(|(_, metrics)| metrics.is_none()));
+        assert!(system.metrics_history("db").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_check_health_concurrently_records_every_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut system = MonitoringSystem::with_data_dir(dir.path().join("monitoring.yml"), dir.path().join("data"))
+            .await
+            .unwrap();
+        let targets: Vec<HealthTarget> = ["web-2", "db", "web-1"]
+            .iter()
+            .map(|id| HealthTarget { xnode_id: id.to_string(), ..HealthTarget::default() })
+            .collect();
+
+        // Without an IP nothing can be probed, but the attempt is recorded
+        let checks = system.check_health_concurrently(&targets).await;
+        let ids: Vec<_> = checks.iter().map(|c| c.xnode_id.as_str()).collect();
+        assert_eq!(ids, ["web-2", "db", "web-1"]);
+        assert!(checks.iter().all(|c| c.status == HealthStatus::Unknown));
+        assert_eq!(system.health_history("db").len(), 1);
+        assert!(!system.served_from_cache());
+    }
+
+    #[tokio::test]
+    async fn test_alerts_resolve_when_condition_clears() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut system = MonitoringSystem::with_data_dir(dir.path().join("monitoring.yml"), dir.path().join("data"))
+            .await
+            .unwrap();
+        system.get_config_mut().alert_delivery.console_alerts = false;
+        let active = |system: &MonitoringSystem| {
+            let mut types: Vec<AlertType> = system.get_xnode_status("x-1").active_alerts.iter().map(|a| a.alert_type).collect();
+            types.sort_by_key(|t| format!("{:?}", t));
+            types
+        };
+
+        system.record_metrics(ResourceMetrics { cpu_percent: 99.0, disk_percent: 95.0, ..ResourceMetrics::new("x-1".to_string()) }).await;
+        assert_eq!(active(&system), [AlertType::HighCpu, AlertType::LowDisk]);
+        system.record_metrics(ResourceMetrics { disk_percent: 95.0, ..ResourceMetrics::new("x-1".to_string()) }).await;
+        assert_eq!(active(&system), [AlertType::LowDisk]);
+
+        let mut down = HealthCheck::new("x-1".to_string());
+        down.status = HealthStatus::Unhealthy;
+        down.checks = HashMap::from([("ping".to_string(), false), ("ssh".to_string(), false)]);
+        system.check_health_alerts(&down).await;
+        assert_eq!(active(&system), [AlertType::LowDisk, AlertType::ServiceDown, AlertType::SshUnreachable]);
+
+        let up = HealthCheck { status: HealthStatus::Healthy, ..HealthCheck::new("x-1".to_string()) };
+        system.check_health_alerts(&up).await;
+        assert_eq!(active(&system), [AlertType::LowDisk]);
+    }
+
+    #[tokio::test]
+    async fn test_cost_tiers_escalate_once_and_step_down() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut system = MonitoringSystem::with_data_dir(dir.path().join("monitoring.yml"), dir.path().join("data"))
+            .await
+            .unwrap();
+        system.get_config_mut().cost_tiers = vec![
+            CostTier { monthly: 1000.0, severity: AlertSeverity::Critical },
+            CostTier { monthly: 500.0, severity: AlertSeverity::Warning },
+        ];
+        system.get_config_mut().cost_tier_hysteresis = 0.1;
+        let tier_alerts = |system: &MonitoringSystem| -> Vec<AlertSeverity> {
+            system.alert_store.get_alerts_for_xnode(COST_ALERT_SOURCE).iter().map(|a| a.severity).collect()
+        };
+
+        assert!(system.check_cost_tiers(400.0).await.is_none());
+        assert!(tier_alerts(&system).is_empty());
+
+        assert_eq!(system.check_cost_tiers(600.0).await.unwrap().monthly, 500.0);
+        let first = system.alert_store.get_alerts_for_xnode(COST_ALERT_SOURCE)[0].id.clone();
+        system.check_cost_tiers(700.0).await;
+        assert_eq!(system.alert_store.get_alerts_for_xnode(COST_ALERT_SOURCE)[0].id, first);
+
+        system.check_cost_tiers(1200.0).await;
+        assert_eq!(tier_alerts(&system), [AlertSeverity::Critical]);
+        system.check_cost_tiers(950.0).await;
+        assert_eq!(tier_alerts(&system), [AlertSeverity::Critical]);
+        system.check_cost_tiers(850.0).await;
+        assert_eq!(tier_alerts(&system), [AlertSeverity::Warning]);
+        system.check_cost_tiers(300.0).await;
+        assert!(tier_alerts(&system).is_empty());
+
+        // Duplicate tiers and out-of-range hysteresis are rejected
+        let mut config = system.get_config().clone();
+        config.cost_tier_hysteresis = 1.0;
+        assert!(config.validate().is_err());
+        config.cost_tier_hysteresis = 0.05;
+        config.cost_tiers.push(CostTier { monthly: 500.0, severity: AlertSeverity::Info });
+        assert!(config.validate().unwrap_err().to_string().contains("more than once"));
+    }
 }
@@ -0,0 +1,177 @@
+// HTTP Status Server
+//
+// `capsule monitor serve` keeps checking every running xNode in the
+// background and answers read-only requests about the results:
+//
+// - `GET /dashboard`   the dashboard overview as JSON
+// - `GET /health/<id>` the latest health check for one xNode as JSON
+// - `GET /metrics`     health, resource usage and alert counts for Prometheus
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{header, Body, Method, Request, Response, Server, StatusCode};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use super::MonitoringSystem;
+use crate::inventory::{XNodeEntry, XNodeInventory};
+use crate::shutdown::shutdown_signal;
+
+/// Serve status on `listen` until SIGINT/SIGTERM, checking all running
+/// xNodes every `check_interval_seconds`
+pub async fn serve(system: MonitoringSystem, listen: SocketAddr) -> Result<()> {
+    let interval = Duration::from_secs(system.get_config().check_interval_seconds.max(1));
+    let system = Arc::new(Mutex::new(system));
+
+    let make_service = {
+        let system = system.clone();
+        make_service_fn(move |_| {
+            let system = system.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let system = system.clone();
+                    async move { Ok::<_, Infallible>(handle(&system, request).await) }
+                }))
+            }
+        })
+    };
+    let server = Server::try_bind(&listen)
+        .with_context(|| format!("Failed to listen on {}", listen))?
+        .serve(make_service);
+
+    println!(
+        "{} Serving monitoring status on http://{} (Press Ctrl+C to stop)",
+        "✓".green().bold(),
+        server.local_addr()
+    );
+    println!("  Checking running xNodes every {}s", interval.as_secs());
+
+    tokio::select! {
+        result = server => result.context("HTTP server failed")?,
+        _ = check_loop(&system, interval) => {}
+        _ = shutdown_signal() => {}
+    }
+
+    system.lock().await.save_history().await?;
+    println!("\n{}", "Status server stopped; history saved.".cyan());
+    Ok(())
+}
+
+/// Answer one request from the current monitoring state
+pub async fn handle(system: &Mutex<MonitoringSystem>, request: Request<Body>) -> Response<Body> {
+    if request.method() != Method::GET {
+        return text(StatusCode::METHOD_NOT_ALLOWED, "Only GET is supported\n");
+    }
+
+    let path = request.uri().path().trim_end_matches('/');
+    let system = system.lock().await;
+
+    match path {
+        "/dashboard" => json(StatusCode::OK, &system.get_dashboard_data()),
+        "/metrics" => {
+            let mut response = text(StatusCode::OK, system.prometheus_metrics());
+            response.headers_mut().insert(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("text/plain; version=0.0.4"),
+            );
+            response
+        }
+        _ => match path.strip_prefix("/health/").filter(|id| !id.is_empty() && !id.contains('/')) {
+            Some(xnode_id) => match system.get_xnode_status(xnode_id).current_health {
+                Some(check) => json(StatusCode::OK, &check),
+                None => json(
+                    StatusCode::NOT_FOUND,
+                    &serde_json::json!({ "error": format!("No health checks recorded for '{}'", xnode_id) }),
+                ),
+            },
+            None => text(StatusCode::NOT_FOUND, "Not found; try /dashboard, /health/<id> or /metrics\n"),
+        },
+    }
+}
+
+/// Check every running xNode, then wait `interval`; never returns
+async fn check_loop(system: &Mutex<MonitoringSystem>, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        if let Err(e) = check_all(system).await {
+            log::warn!("Background health checks failed: {:#}", e);
+        }
+    }
+}
+
+async fn check_all(system: &Mutex<MonitoringSystem>) -> Result<()> {
+    let inventory = XNodeInventory::new(None)?;
+    let nodes: Vec<XNodeEntry> = inventory.list_by_status("running").into_iter().cloned().collect();
+
+    // Probe without the lock so requests are answered meanwhile
+    let prober = system.lock().await.prober();
+    let sweep = prober.sweep(&nodes, true).await;
+    system.lock().await.apply_sweep(&sweep, inventory.get_total_cost()["monthly"]).await
+}
+
+fn json(status: StatusCode, value: &impl serde::Serialize) -> Response<Body> {
+    let body = serde_json::to_string_pretty(value).unwrap_or_else(|_| "{}".to_string());
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, header::HeaderValue::from_static("application/json"));
+    response
+}
+
+fn text(status: StatusCode, body: impl Into<Body>) -> Response<Body> {
+    let mut response = Response::new(body.into());
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, header::HeaderValue::from_static("text/plain"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitoring::metrics::ResourceMetrics;
+
+    async fn get(system: &Mutex<MonitoringSystem>, path: &str) -> (StatusCode, String) {
+        let request = Request::get(path).body(Body::empty()).unwrap();
+        let response = handle(system, request).await;
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_routes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut system = MonitoringSystem::with_data_dir(dir.path().join("monitoring.yml"), dir.path().join("data"))
+            .await
+            .unwrap();
+        system
+            .record_metrics(ResourceMetrics { cpu_percent: 42.5, ..ResourceMetrics::new("x-1".to_string()) })
+            .await;
+        let system = Mutex::new(system);
+
+        let (status, body) = get(&system, "/dashboard").await;
+        assert_eq!(status, StatusCode::OK);
+        let dashboard: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(dashboard["total_xnodes"], 1);
+
+        let (status, body) = get(&system, "/metrics").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("capsule_xnode_cpu_percent{xnode=\"x-1\"} 42.5"));
+
+        let (status, body) = get(&system, "/health/x-1").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body.contains("No health checks recorded for 'x-1'"));
+
+        assert_eq!(get(&system, "/nope").await.0, StatusCode::NOT_FOUND);
+        let post = Request::post("/dashboard").body(Body::empty()).unwrap();
+        assert_eq!(handle(&system, post).await.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+}
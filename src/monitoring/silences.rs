@@ -0,0 +1,135 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// What a silence applies to: one xNode, or every xNode with a tag
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SilenceTarget {
+    Xnode(String),
+    Tag(String),
+}
+
+impl std::fmt::Display for SilenceTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SilenceTarget::Xnode(id) => write!(f, "xnode {}", id),
+            SilenceTarget::Tag(tag) => write!(f, "tag {}", tag),
+        }
+    }
+}
+
+/// Alerts for matching xNodes are recorded but not delivered until `until`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Silence {
+    pub id: String,
+    pub target: SilenceTarget,
+    pub created_at: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl Silence {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        now < self.until
+    }
+
+    pub fn matches(&self, xnode_id: &str, tags: &[String]) -> bool {
+        match &self.target {
+            SilenceTarget::Xnode(id) => id == xnode_id,
+            SilenceTarget::Tag(tag) => tags.contains(tag),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SilenceStore {
+    silences: Vec<Silence>,
+}
+
+impl SilenceStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Silence `target` for `duration` from `now`
+    pub fn add(&mut self, target: SilenceTarget, duration: Duration, reason: Option<String>, now: DateTime<Utc>) -> &Silence {
+        let next = self
+            .silences
+            .iter()
+            .filter_map(|s| s.id.strip_prefix("silence-")?.parse::<u64>().ok())
+            .max()
+            .unwrap_or(0)
+            + 1;
+        self.silences.push(Silence {
+            id: format!("silence-{}", next),
+            target,
+            created_at: now,
+            until: now + duration,
+            reason,
+        });
+        self.silences.last().unwrap()
+    }
+
+    /// Unexpired silences, ending soonest first
+    pub fn active(&self, now: DateTime<Utc>) -> Vec<&Silence> {
+        let mut active: Vec<&Silence> = self.silences.iter().filter(|s| s.is_active(now)).collect();
+        active.sort_by(|a, b| a.until.cmp(&b.until).then_with(|| a.id.cmp(&b.id)));
+        active
+    }
+
+    /// The active silence covering an xNode with these tags, if any
+    pub fn silencing(&self, xnode_id: &str, tags: &[String], now: DateTime<Utc>) -> Option<&Silence> {
+        self.silences
+            .iter()
+            .find(|s| s.is_active(now) && s.matches(xnode_id, tags))
+    }
+
+    /// Remove silences for which `select` holds, returning how many were removed
+    pub fn remove_where(&mut self, select: impl Fn(&Silence) -> bool) -> usize {
+        let before = self.silences.len();
+        self.silences.retain(|s| !select(s));
+        before - self.silences.len()
+    }
+
+    /// Drop expired silences so the saved file doesn't grow forever
+    pub fn prune(&mut self, now: DateTime<Utc>) {
+        self.silences.retain(|s| s.is_active(now));
+    }
+
+    pub fn load(&mut self, silences: Vec<Silence>) {
+        self.silences = silences;
+    }
+
+    pub fn as_slice(&self) -> &[Silence] {
+        &self.silences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_silence_matching_and_expiry() {
+        let now = Utc::now();
+        let mut store = SilenceStore::new();
+        store.add(SilenceTarget::Xnode("x-1".to_string()), Duration::hours(2), None, now);
+        let id = store.add(SilenceTarget::Tag("web".to_string()), Duration::minutes(30), None, now).id.clone();
+        assert_eq!(id, "silence-2");
+
+        let web = vec!["web".to_string()];
+        assert!(store.silencing("x-1", &[], now).is_some());
+        assert!(store.silencing("x-2", &web, now).is_some());
+        assert!(store.silencing("x-2", &[], now).is_none());
+
+        let later = now + Duration::hours(1);
+        assert!(store.silencing("x-2", &web, later).is_none());
+        assert_eq!(store.active(later).len(), 1);
+
+        store.prune(later);
+        assert_eq!(store.as_slice().len(), 1);
+        assert_eq!(store.remove_where(|s| s.target == SilenceTarget::Xnode("x-1".to_string())), 1);
+        assert!(store.active(now).is_empty());
+    }
+}
@@ -3,12 +3,13 @@
 use crate::config::{collect_packages, load_preset, Config};
 use crate::ui::{error, info_line, success};
 use anyhow::{Context, Result};
+use std::io::{BufRead, BufReader};
 use std::path::Path;
 use std::process::{Command, Stdio};
 
 /// Generate Nix configuration from profile
 pub fn generate_nix_config(config: &Config) -> Result<String> {
-    let (unique_packages, packages_by_preset) = collect_packages(config)?;
+    let (_, packages_by_preset) = collect_packages(config)?;
 
     let mut lines = Vec::new();
 
@@ -172,12 +173,8 @@ pub fn check_nixos_available() -> bool {
         .unwrap_or(false)
 }
 
-/// Run nixos-rebuild command
-pub fn run_nixos_rebuild(
-    action: &str,
-    config_dir: Option<&Path>,
-    flake: bool,
-) -> Result<i32> {
+/// Build a `sudo nixos-rebuild <action>` command for `config_dir`
+fn nixos_rebuild_command(action: &str, config_dir: Option<&Path>, flake: bool) -> Command {
     let mut cmd = Command::new("sudo");
     cmd.arg("nixos-rebuild");
     cmd.arg(action);
@@ -193,6 +190,17 @@ pub fn run_nixos_rebuild(
         }
     }
 
+    cmd
+}
+
+/// Run nixos-rebuild command
+pub fn run_nixos_rebuild(
+    action: &str,
+    config_dir: Option<&Path>,
+    flake: bool,
+) -> Result<i32> {
+    let mut cmd = nixos_rebuild_command(action, config_dir, flake);
+
     println!("\nRunning: {:?}\n", cmd);
 
     let status = cmd
@@ -204,6 +212,104 @@ pub fn run_nixos_rebuild(
     Ok(status.code().unwrap_or(1))
 }
 
+/// Unit changes `nixos-rebuild dry-activate` reports it would make
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ActivationPlan {
+    pub start: Vec<String>,
+    pub stop: Vec<String>,
+    pub restart: Vec<String>,
+    pub reload: Vec<String>,
+    /// Changed units that would be left running as they are
+    pub unchanged: Vec<String>,
+}
+
+impl ActivationPlan {
+    /// Collect the `would <verb> the following units: a, b` lines
+    pub fn parse(output: &str) -> Self {
+        let mut plan = Self::default();
+        for line in output.lines() {
+            let Some((action, units)) = line.trim().split_once(" the following ") else {
+                continue;
+            };
+            let Some((_, units)) = units.split_once(':') else {
+                continue;
+            };
+            let target = match action {
+                "would start" => &mut plan.start,
+                "would stop" => &mut plan.stop,
+                "would restart" => &mut plan.restart,
+                "would reload" => &mut plan.reload,
+                "would NOT stop" => &mut plan.unchanged,
+                _ => continue,
+            };
+            target.extend(
+                units
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|u| !u.is_empty())
+                    .map(str::to_string),
+            );
+        }
+        plan
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start.is_empty()
+            && self.stop.is_empty()
+            && self.restart.is_empty()
+            && self.reload.is_empty()
+            && self.unchanged.is_empty()
+    }
+}
+
+/// Run `nixos-rebuild dry-activate`, echoing its output, and parse the
+/// units it would change. Returns the exit code with the plan.
+pub fn dry_activate(config_dir: Option<&Path>, flake: bool) -> Result<(i32, ActivationPlan)> {
+    capture_activation_plan(nixos_rebuild_command("dry-activate", config_dir, flake))
+}
+
+/// Run a `nixos-rebuild dry-activate` command (local or over ssh), echoing
+/// its output, and parse the units it would change
+pub fn capture_activation_plan(mut cmd: Command) -> Result<(i32, ActivationPlan)> {
+    println!("\nRunning: {:?}\n", cmd);
+
+    // The activation script reports on stderr
+    let mut child = cmd
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to execute nixos-rebuild")?;
+
+    let mut output = String::new();
+    if let Some(stderr) = child.stderr.take() {
+        for line in BufReader::new(stderr).lines() {
+            let line = line?;
+            eprintln!("{}", line);
+            output.push_str(&line);
+            output.push('\n');
+        }
+    }
+    let status = child.wait().context("Failed to wait for nixos-rebuild")?;
+
+    Ok((status.code().unwrap_or(1), ActivationPlan::parse(&output)))
+}
+
+/// Build an ssh command running `nixos-rebuild <action>` on `target` against
+/// a configuration already copied to `remote_dir`. Flakes are built for the
+/// remote machine's own hostname.
+pub fn remote_nixos_rebuild_command(target: &str, action: &str, remote_dir: &str, flake: bool) -> Command {
+    let config = if flake {
+        format!("--flake {}#\"$(hostname)\"", remote_dir)
+    } else {
+        format!("-I nixos-config={}/configuration.nix", remote_dir)
+    };
+
+    let mut cmd = Command::new("ssh");
+    cmd.arg(target)
+        .arg(format!("sudo nixos-rebuild {} {}", action, config));
+    cmd
+}
+
 /// List NixOS generations
 pub fn list_generations() -> Result<Vec<String>> {
     let output = Command::new("nixos-rebuild")
@@ -232,4 +338,41 @@ mod tests {
         assert!(nix_config.contains("environment.systemPackages"));
         assert!(nix_config.contains("git"));
     }
+
+    #[test]
+    fn test_parse_activation_plan() {
+        let output = "\
+building the system configuration...
+would stop the following units: old-app.service
+would NOT stop the following changed units: getty@tty1.service
+would activate the configuration...
+would restart systemd
+would restart the following units: sshd.service, nginx.service
+would start the following units: postgresql.service
+would reload the following units: dbus.service
+";
+        let plan = ActivationPlan::parse(output);
+        assert_eq!(plan.stop, ["old-app.service"]);
+        assert_eq!(plan.unchanged, ["getty@tty1.service"]);
+        assert_eq!(plan.restart, ["sshd.service", "nginx.service"]);
+        assert_eq!(plan.start, ["postgresql.service"]);
+        assert_eq!(plan.reload, ["dbus.service"]);
+
+        assert!(ActivationPlan::parse("building the system configuration...\n").is_empty());
+    }
+
+    #[test]
+    fn test_remote_nixos_rebuild_command() {
+        let cmd = remote_nixos_rebuild_command("root@host", "switch", "/tmp/capsule-nixos-1", false);
+        let args: Vec<_> = cmd.get_args().collect();
+        assert_eq!(cmd.get_program(), "ssh");
+        assert_eq!(
+            args,
+            ["root@host", "sudo nixos-rebuild switch -I nixos-config=/tmp/capsule-nixos-1/configuration.nix"]
+        );
+
+        let cmd = remote_nixos_rebuild_command("host", "dry-activate", "/tmp/c", true);
+        let args: Vec<_> = cmd.get_args().collect();
+        assert_eq!(args[1], "sudo nixos-rebuild dry-activate --flake /tmp/c#\"$(hostname)\"");
+    }
 }
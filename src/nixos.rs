@@ -2,7 +2,7 @@
 
 use crate::config::{collect_packages, load_preset, Config};
 use anyhow::{Context, Result};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
@@ -20,9 +20,30 @@ impl ServiceMapping {
     }
 }
 
+impl Default for ServiceMapping {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A user account carried over from an existing machine
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedUser {
+    pub name: String,
+    pub uid: u32,
+    pub home: String,
+    /// Nix package providing the login shell, e.g. `zsh`
+    pub shell: String,
+    pub groups: Vec<String>,
+}
+
 /// NixOS Configuration Generator
 pub struct NixOSConfigGenerator {
+    #[allow(dead_code)]
     capsule_dir: PathBuf,
+    users: Vec<ImportedUser>,
+    tcp_ports: Vec<u16>,
+    udp_ports: Vec<u16>,
 }
 
 impl NixOSConfigGenerator {
@@ -34,12 +55,30 @@ impl NixOSConfigGenerator {
                 .join(".capsule")
         });
 
-        Self { capsule_dir }
+        Self {
+            capsule_dir,
+            users: Vec::new(),
+            tcp_ports: Vec::new(),
+            udp_ports: Vec::new(),
+        }
+    }
+
+    /// Declare these accounts in configuration.nix alongside the primary user
+    pub fn with_users(mut self, users: Vec<ImportedUser>) -> Self {
+        self.users = users;
+        self
+    }
+
+    /// Open these ports in the firewall in addition to SSH
+    pub fn with_open_ports(mut self, tcp: Vec<u16>, udp: Vec<u16>) -> Self {
+        self.tcp_ports = tcp;
+        self.udp_ports = udp;
+        self
     }
 
     /// Detect and map services from presets
-    pub fn detect_services(&self, config: &Config) -> HashMap<String, String> {
-        let mut services = HashMap::new();
+    pub fn detect_services(&self, config: &Config) -> BTreeMap<String, String> {
+        let mut services = BTreeMap::new();
         let presets = &config.presets;
 
         // Service mapping
@@ -50,7 +89,6 @@ impl NixOSConfigGenerator {
             ]),
             ("webserver", vec![
                 ("services.nginx.enable", "true"),
-                ("networking.firewall.allowedTCPPorts", "[ 80 443 ]"),
             ]),
             ("database", vec![
                 ("services.postgresql.enable", "true"),
@@ -127,6 +165,10 @@ impl NixOSConfigGenerator {
         lines.push(format!("  users.users.{} = {{", username));
         lines.push("    isNormalUser = true;".to_string());
         lines.push("    description = \"Primary User\";".to_string());
+        if let Some(user) = self.users.iter().find(|u| u.name == username) {
+            lines.push(format!("    uid = {};", user.uid));
+            lines.push(format!("    shell = pkgs.{};", user.shell));
+        }
         lines.push("    extraGroups = [ \"wheel\" \"networkmanager\" \"docker\" ];".to_string());
         lines.push("    openssh.authorizedKeys.keys = [".to_string());
         lines.push("      # Add your SSH public keys here".to_string());
@@ -134,6 +176,25 @@ impl NixOSConfigGenerator {
         lines.push("  };".to_string());
         lines.push("".to_string());
 
+        for user in self.users.iter().filter(|u| u.name != username && u.name != "root") {
+            lines.push(format!("  users.users.{} = {{", user.name));
+            lines.push("    isNormalUser = true;".to_string());
+            lines.push(format!("    uid = {};", user.uid));
+            lines.push(format!("    home = \"{}\";", user.home));
+            lines.push(format!("    shell = pkgs.{};", user.shell));
+            let groups: Vec<String> = user
+                .groups
+                .iter()
+                .filter(|g| **g != user.name)
+                .map(|g| format!("\"{}\"", g))
+                .collect();
+            if !groups.is_empty() {
+                lines.push(format!("    extraGroups = [ {} ];", groups.join(" ")));
+            }
+            lines.push("  };".to_string());
+            lines.push("".to_string());
+        }
+
         // Services configuration
         if !services.is_empty() {
             lines.push("  # Services".to_string());
@@ -162,8 +223,17 @@ impl NixOSConfigGenerator {
         lines.push("  # Firewall".to_string());
         lines.push("  networking.firewall = {".to_string());
         lines.push("    enable = true;".to_string());
-        lines.push("    allowedTCPPorts = [ 22 ];".to_string());
-        lines.push("    # allowedUDPPorts = [ ... ];".to_string());
+        let mut tcp_ports: BTreeSet<u16> = std::iter::once(22).chain(self.tcp_ports.iter().copied()).collect();
+        if profile.presets.iter().any(|p| p == "webserver") {
+            tcp_ports.extend([80, 443]);
+        }
+        lines.push(format!("    allowedTCPPorts = [ {} ];", join_ports(&tcp_ports)));
+        if self.udp_ports.is_empty() {
+            lines.push("    # allowedUDPPorts = [ ... ];".to_string());
+        } else {
+            let udp_ports: BTreeSet<u16> = self.udp_ports.iter().copied().collect();
+            lines.push(format!("    allowedUDPPorts = [ {} ];", join_ports(&udp_ports)));
+        }
         lines.push("  };".to_string());
         lines.push("".to_string());
 
@@ -344,7 +414,7 @@ impl NixOSConfigGenerator {
     /// Generate flake.nix
     pub fn generate_flake_nix(
         &self,
-        profile: &Config,
+        _profile: &Config,
         hostname: &str,
         username: &str,
     ) -> Result<String> {
@@ -436,10 +506,10 @@ impl NixOSConfigGenerator {
         output_dir: &Path,
         hostname: &str,
         username: &str,
-    ) -> Result<HashMap<String, PathBuf>> {
+    ) -> Result<BTreeMap<String, PathBuf>> {
         std::fs::create_dir_all(output_dir)?;
 
-        let mut generated_files = HashMap::new();
+        let mut generated_files = BTreeMap::new();
 
         // Generate configuration.nix
         let config_nix = self.generate_configuration_nix(profile, hostname, username)?;
@@ -521,6 +591,10 @@ sudo nixos-rebuild build-vm
     }
 }
 
+fn join_ports(ports: &BTreeSet<u16>) -> String {
+    ports.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(" ")
+}
+
 /// Validate NixOS configuration file
 pub fn validate_config(config_path: &Path) -> Result<(bool, Vec<String>)> {
     let mut errors = Vec::new();
@@ -583,4 +657,44 @@ mod tests {
         let services = generator.detect_services(&config);
         assert!(services.contains_key("virtualisation.docker.enable"));
     }
+
+    #[test]
+    fn test_imported_users_and_ports() {
+        let user = |name: &str, uid| ImportedUser {
+            name: name.to_string(),
+            uid,
+            home: format!("/home/{}", name),
+            shell: "zsh".to_string(),
+            groups: vec![name.to_string(), "sudo".to_string()],
+        };
+        let generator = NixOSConfigGenerator::new(None)
+            .with_users(vec![user("alice", 1000), user("bob", 1001)])
+            .with_open_ports(vec![443, 80, 22], vec![51820]);
+
+        let config = generator
+            .generate_configuration_nix(&Config::default(), "web-1", "alice")
+            .unwrap();
+        assert!(config.contains("  users.users.alice = {\n    isNormalUser = true;\n    description = \"Primary User\";\n    uid = 1000;"));
+        assert!(config.contains("  users.users.bob = {"));
+        assert!(config.contains("    home = \"/home/bob\";"));
+        assert!(config.contains("    extraGroups = [ \"sudo\" ];"));
+        assert_eq!(config.matches("users.users.alice").count(), 1);
+        assert!(config.contains("allowedTCPPorts = [ 22 80 443 ];"));
+        assert!(config.contains("allowedUDPPorts = [ 51820 ];"));
+
+        let plain = NixOSConfigGenerator::new(None)
+            .generate_configuration_nix(&Config::default(), "web-1", "alice")
+            .unwrap();
+        assert!(plain.contains("allowedTCPPorts = [ 22 ];"));
+        assert!(plain.contains("# allowedUDPPorts = [ ... ];"));
+
+        // The webserver preset opens HTTP(S) in the single firewall block
+        let mut webserver = Config::default();
+        webserver.presets.push("webserver".to_string());
+        let config = NixOSConfigGenerator::new(None)
+            .generate_configuration_nix(&webserver, "web-1", "alice")
+            .unwrap();
+        assert_eq!(config.matches("allowedTCPPorts").count(), 1);
+        assert!(config.contains("allowedTCPPorts = [ 22 80 443 ];"));
+    }
 }
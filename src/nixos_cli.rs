@@ -1,49 +1,38 @@
-// This file contains the additions needed for main.rs for Nix/NixOS support
+// `capsule nixos` subcommands
+
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use colored::*;
+use dialoguer::Confirm;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use capsule::config::{find_conflicts, load_config};
+use capsule::exit::CliError;
+use capsule::nix::{
+    capture_activation_plan, dry_activate, list_generations, remote_nixos_rebuild_command,
+    run_nixos_rebuild, ActivationPlan,
+};
+use capsule::nixos::{test_in_vm, validate_config, NixOSConfigGenerator};
+use capsule::ui::*;
+
+use crate::send;
+use crate::server;
+
+/// Retries for copying the configuration to a remote host
+const UPLOAD_RETRIES: u32 = 3;
 
-use capsule::nix::*;
-use capsule::nixos::*;
-use std::path::PathBuf;
-
-// Add these variants to Commands enum:
-/*
-    /// Install packages using Nix
-    Setup {
-        /// Dry run mode (check what would be installed)
-        #[arg(long)]
-        check: bool,
-        /// Verbose output level
-        #[arg(short, long, action = clap::ArgAction::Count)]
-        verbose: u8,
-    },
-
-    /// Dry run - check what would be changed
-    Check {
-        /// Verbose output level
-        #[arg(short, long, action = clap::ArgAction::Count)]
-        verbose: u8,
-    },
-
-    /// Preview generated Nix configuration
-    Preview,
-
-    /// NixOS configuration generation and management
-    Nixos {
-        #[command(subcommand)]
-        command: NixOSCommands,
-    },
-*/
-
-// Add this new enum:
 #[derive(Subcommand)]
-enum NixOSCommands {
+pub enum NixOSCommands {
     /// Generate NixOS configuration files from profile
     Generate {
         /// Output directory (default: ~/.capsule/nixos)
         #[arg(short, long)]
         output: Option<PathBuf>,
-        /// System hostname
-        #[arg(long, default_value = "nixos")]
-        hostname: String,
+        /// System hostname (default: the snapshot's hostname, else "nixos")
+        #[arg(long)]
+        hostname: Option<String>,
         /// Primary user account name
         #[arg(long)]
         username: Option<String>,
@@ -59,6 +48,13 @@ enum NixOSCommands {
         /// Generate all configuration files
         #[arg(long)]
         all: bool,
+        /// Build the configuration from a `server pack` snapshot instead of
+        /// the active profile
+        #[arg(long, value_name = "SNAPSHOT")]
+        from_snapshot: Option<PathBuf>,
+        /// Passphrase file for an encrypted snapshot
+        #[arg(long, requires = "from_snapshot")]
+        key_file: Option<PathBuf>,
     },
 
     /// Validate NixOS configuration syntax
@@ -83,6 +79,13 @@ enum NixOSCommands {
         /// Use flake configuration
         #[arg(long)]
         flake: bool,
+        /// Show what would change with `nixos-rebuild dry-activate`, then
+        /// ask before switching
+        #[arg(long)]
+        dry_run: bool,
+        /// Copy the configuration to this host (user@host) and rebuild there
+        #[arg(long, value_name = "USER@HOST")]
+        target: Option<String>,
     },
 
     /// Rollback to previous NixOS generation
@@ -92,83 +95,7 @@ enum NixOSCommands {
     ListGenerations,
 }
 
-// Add these match arms to the main match statement:
-/*
-        Some(Commands::Setup { check, verbose }) => cmd_setup(check, verbose)?,
-        Some(Commands::Check { verbose }) => cmd_check(verbose)?,
-        Some(Commands::Preview) => cmd_preview()?,
-        Some(Commands::Nixos { command }) => handle_nixos_command(command)?,
-*/
-
-// Handler functions:
-
-fn cmd_setup(check: bool, verbose: u8) -> Result<()> {
-    if check {
-        banner("🔍 DRY RUN MODE");
-        println!("  Checking what would be installed...\n");
-    } else {
-        banner("🚀 SETTING UP SERVER");
-    }
-
-    if verbose > 0 {
-        println!("  Verbose level: {}\n", verbose);
-    }
-
-    let config = load_config(None)?;
-    
-    // Check if Nix is installed
-    if !check_nix_installed() {
-        error("nix-env not found. Please install Nix: https://nixos.org/download.html");
-        return Ok(());
-    }
-
-    run_nix_env(&config, check, verbose)?;
-    Ok(())
-}
-
-fn cmd_check(verbose: u8) -> Result<()> {
-    banner("🔍 CONFIGURATION CHECK");
-    if verbose > 0 {
-        println!("  Verbose level: {}\n", verbose);
-    }
-
-    let config = load_config(None)?;
-    
-    if !check_nix_installed() {
-        error("nix-env not found. Please install Nix: https://nixos.org/download.html");
-        return Ok(());
-    }
-
-    run_nix_env(&config, true, verbose)?;
-    Ok(())
-}
-
-fn cmd_preview() -> Result<()> {
-    let config = load_config(None)?;
-    
-    header("📋 NIX CONFIGURATION PREVIEW");
-
-    let (packages, _) = collect_packages(&config)?;
-    
-    section_header("Summary");
-    info_line("Stacks", &config.presets.len().to_string());
-    info_line("Total Packages", &packages.len().to_string());
-
-    section_header("Generated Nix Configuration");
-    let nix_config = generate_nix_config(&config)?;
-    println!("{}", nix_config.bright_black());
-
-    divider();
-    println!();
-    println!("  {} Run {} to preview installation without applying",
-             "💡 Tip:".cyan(),
-             "capsule check".cyan().bold());
-    println!();
-
-    Ok(())
-}
-
-fn handle_nixos_command(command: NixOSCommands) -> Result<()> {
+pub fn handle_nixos_command(command: NixOSCommands) -> Result<()> {
     match command {
         NixOSCommands::Generate {
             output,
@@ -178,9 +105,40 @@ fn handle_nixos_command(command: NixOSCommands) -> Result<()> {
             flake,
             hardware,
             all,
+            from_snapshot,
+            key_file,
         } => {
-            let config = load_config(None)?;
-            
+            let (config, generator, hostname, username) = match from_snapshot {
+                Some(path) => {
+                    let snapshot = server::load_snapshot(&path, key_file)?;
+                    let users = server::nixos_users(&snapshot);
+                    let username = username.or_else(|| users.first().map(|u| u.name.clone()));
+                    let generator = NixOSConfigGenerator::new(None)
+                        .with_users(users)
+                        .with_open_ports(snapshot.firewall.tcp_ports.clone(), snapshot.firewall.udp_ports.clone());
+                    (
+                        server::nixos_profile(&snapshot),
+                        generator,
+                        hostname.unwrap_or_else(|| snapshot.hostname.clone()),
+                        username,
+                    )
+                }
+                None => (
+                    load_config(None)?,
+                    NixOSConfigGenerator::new(None),
+                    hostname.unwrap_or_else(|| "nixos".to_string()),
+                    username,
+                ),
+            };
+
+            if let Some(conflict) = find_conflicts(&config)?.first() {
+                return Err(CliError::usage(format!(
+                    "{}; run 'capsule config validate' for details",
+                    conflict
+                ))
+                .into());
+            }
+
             let output_dir = output.unwrap_or_else(|| {
                 dirs::home_dir()
                     .expect("Could not find home directory")
@@ -191,8 +149,6 @@ fn handle_nixos_command(command: NixOSCommands) -> Result<()> {
                 std::env::var("USER").unwrap_or_else(|_| "user".to_string())
             });
 
-            let generator = NixOSConfigGenerator::new(None);
-            
             header("🔧 NIXOS CONFIGURATION GENERATOR");
 
             let generate_all = !home_manager && !flake && !hardware || all;
@@ -328,7 +284,11 @@ fn handle_nixos_command(command: NixOSCommands) -> Result<()> {
             Ok(())
         }
 
-        NixOSCommands::Apply { config_dir, flake } => {
+        NixOSCommands::Apply { config_dir, flake, dry_run, target } => {
+            if let Some(target) = target {
+                return apply_remote(&target, config_dir, flake, dry_run);
+            }
+
             let config_dir = config_dir.map(|p| p.to_path_buf());
 
             header("🚀 APPLYING NIXOS CONFIGURATION");
@@ -339,6 +299,10 @@ fn handle_nixos_command(command: NixOSCommands) -> Result<()> {
             info_line("Mode", if flake { "Flake" } else { "Traditional" });
             println!();
 
+            if dry_run && !plan_activation(config_dir.as_deref(), flake)? {
+                return Ok(());
+            }
+
             warning("This will modify your system configuration!");
             println!();
 
@@ -394,3 +358,147 @@ fn handle_nixos_command(command: NixOSCommands) -> Result<()> {
         }
     }
 }
+
+/// Dry-activate the configuration and show the unit changes. Returns whether
+/// the user wants to go ahead with the switch.
+fn plan_activation(config_dir: Option<&Path>, flake: bool) -> Result<bool> {
+    section_header("Planning changes (dry-activate)");
+    let (code, plan) = dry_activate(config_dir, flake)?;
+    review_plan(code, &plan)
+}
+
+/// Show a dry-activate result and ask whether to switch
+fn review_plan(code: i32, plan: &ActivationPlan) -> Result<bool> {
+    if code != 0 {
+        return Err(anyhow::anyhow!(
+            "nixos-rebuild dry-activate failed with exit code {}; nothing was applied",
+            code
+        ));
+    }
+
+    section_header("Planned changes");
+    if plan.is_empty() {
+        println!("  No units would be started, stopped or restarted");
+    }
+    for (marker, label, units) in [
+        ("+".green(), "start", &plan.start),
+        ("-".red(), "stop", &plan.stop),
+        ("~".yellow(), "restart", &plan.restart),
+        ("~".yellow(), "reload", &plan.reload),
+        ("=".bright_black(), "keep running", &plan.unchanged),
+    ] {
+        for unit in units {
+            println!("  {} {:<13} {}", marker, label, unit);
+        }
+    }
+    println!();
+
+    if !std::io::stdin().is_terminal() {
+        println!("  {} Re-run without {} to switch", "💡".cyan(), "--dry-run".cyan());
+        println!();
+        return Ok(false);
+    }
+
+    Ok(Confirm::new()
+        .with_prompt("Switch to this configuration now?")
+        .default(false)
+        .interact()?)
+}
+
+/// Copy a generated configuration to `target` and run `nixos-rebuild` there
+fn apply_remote(target: &str, config_dir: Option<PathBuf>, flake: bool, dry_run: bool) -> Result<()> {
+    let config_dir = config_dir.unwrap_or_else(|| {
+        dirs::home_dir()
+            .expect("Could not find home directory")
+            .join(".capsule/nixos")
+    });
+    let entry = if flake { "flake.nix" } else { "configuration.nix" };
+    if !config_dir.join(entry).exists() {
+        return Err(CliError::not_found(format!(
+            "{} not found in {} (run `capsule nixos generate` first)",
+            entry,
+            config_dir.display()
+        ))
+        .into());
+    }
+
+    header("🚀 APPLYING NIXOS CONFIGURATION");
+    info_line("Configuration", &config_dir.display().to_string());
+    info_line("Target", target);
+    info_line("Mode", if flake { "Flake" } else { "Traditional" });
+    println!();
+
+    section_header("Copying configuration");
+    let remote_dir = make_remote_temp_dir(target)?;
+    send::run_with_retries("Configuration upload", UPLOAD_RETRIES, || {
+        let mut cmd = Command::new("scp");
+        cmd.arg("-r")
+            .arg("-q")
+            .arg(config_dir.join("."))
+            .arg(format!("{}:{}/", target, remote_dir));
+        cmd
+    })?;
+    success(&format!("Copied to {}:{}", target, remote_dir));
+
+    let result = rebuild_remote(target, &remote_dir, flake, dry_run);
+
+    // Best effort: a leftover copy in /tmp is harmless
+    let _ = Command::new("ssh")
+        .arg(target)
+        .arg(format!("rm -rf {}", remote_dir))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+
+    if result? {
+        success(&format!("NixOS configuration applied to {}", target));
+        println!();
+    }
+    Ok(())
+}
+
+/// Create a private, unpredictable directory on `target` with `mktemp -d`
+fn make_remote_temp_dir(target: &str) -> Result<String> {
+    let output = Command::new("ssh")
+        .arg(target)
+        .arg("mktemp -d -t capsule-nixos.XXXXXXXXXX")
+        .stderr(Stdio::inherit())
+        .output()
+        .context("Failed to execute ssh")?;
+    let dir = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || dir.is_empty() {
+        anyhow::bail!("Could not create a temporary directory on {}", target);
+    }
+    Ok(dir)
+}
+
+/// Run the rebuild on `target`; returns false when the user stopped after
+/// the dry run
+fn rebuild_remote(target: &str, remote_dir: &str, flake: bool, dry_run: bool) -> Result<bool> {
+    if dry_run {
+        section_header("Planning changes (dry-activate)");
+        let (code, plan) = capture_activation_plan(remote_nixos_rebuild_command(
+            target,
+            "dry-activate",
+            remote_dir,
+            flake,
+        ))?;
+        if !review_plan(code, &plan)? {
+            return Ok(false);
+        }
+    }
+
+    warning(&format!("This will modify the system configuration of {}!", target));
+    let mut cmd = remote_nixos_rebuild_command(target, "switch", remote_dir, flake);
+    println!("\nRunning: {:?}\n", cmd);
+    let status = cmd.status().context("Failed to execute ssh")?;
+    if !status.success() {
+        anyhow::bail!(
+            "nixos-rebuild switch failed on {} (exit code {})",
+            target,
+            status.code().unwrap_or(-1)
+        );
+    }
+    Ok(true)
+}
+
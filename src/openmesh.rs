@@ -1,37 +1,65 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use chrono::Utc;
 use colored::*;
 use dialoguer::{Select, Input, Confirm};
 use prettytable::{Table, Row, Cell, format};
 use std::collections::HashMap;
-
-use crate::providers::{ProviderManager, DeployConfig};
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::deploy_profile::{DeployProfiles, DeploySettings};
+use crate::cost::{CostGrouping, DeploymentRecord, ReportFormat};
+use crate::exit::CliError;
+use crate::inventory::{ExportFormat, DEFAULT_STALE_AFTER_DAYS, HistoryWindow, SnapshotRecord, TimeBound, XNodeEntry, XNodeInventory, XNodeMove, XNodeUpdate};
+use crate::monitoring::health::HealthCheck;
+use crate::monitoring::commands::{watch_top, TopOptions};
+use crate::monitoring::metrics::{ResourceMetrics, TopSort};
+use crate::monitoring::{alerts::Alert, MonitoringSystem, XNodeStatus};
+use crate::providers::filter::TemplateFilter;
+use crate::providers::pricing::PRICING_STALE_DAYS;
+use crate::shutdown::Interrupt;
+use crate::providers::{console_url, group_by_spec, parse_arch, region_label, wait_for_running, ProviderManager, ProviderOperation, ProviderTemplate, DeployConfig, Region, TemplateEfficiency};
+use crate::ui::{is_quiet, FitTable};
+use crate::xnode::XNode;
+
+/// How often `deploy --follow` polls the provider
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Default `deploy --expensive-above`, in USD per hour
+const DEFAULT_EXPENSIVE_HOURLY: f64 = 1.0;
 
 pub fn handle_openmesh_command(command: OpenMeshCommands) -> Result<()> {
     match command {
         OpenMeshCommands::Overview => show_openmesh_overview()?,
-        OpenMeshCommands::Providers => list_providers()?,
+        OpenMeshCommands::Providers { command: None } => list_providers()?,
+        OpenMeshCommands::Providers { command: Some(ProvidersCommands::RefreshPricing) } => refresh_pricing()?,
         OpenMeshCommands::Xnode { command } => handle_xnode_command(command)?,
         OpenMeshCommands::Provider { command } => handle_provider_command(command)?,
+        OpenMeshCommands::DeployProfile { command } => handle_deploy_profile_command(command)?,
     }
     Ok(())
 }
 
 fn show_openmesh_overview() -> Result<()> {
-    println!();
-    println!("{}", "╔═══════════════════════════════════════════════════════════════════════╗".cyan());
-    println!("{}", "║                                                                       ║".cyan());
-    println!("{}              {}  OPENMESH XNODE DEPLOYMENT PLATFORM  {}              {}", "║".cyan(), "🌐".cyan(), "🌐".cyan(), "║".cyan());
-    println!("{}", "║                                                                       ║".cyan());
-    println!("{}", "║   Deploy and manage infrastructure across 8 cloud providers with     ║".cyan());
-    println!("{}", "║   a unified, beautiful CLI interface.                                ║".cyan());
-    println!("{}", "║                                                                       ║".cyan());
-    println!("{}", "╚═══════════════════════════════════════════════════════════════════════╝".cyan());
-    println!();
+    if !is_quiet() {
+        println!();
+        println!("{}", "╔═══════════════════════════════════════════════════════════════════════╗".cyan());
+        println!("{}", "║                                                                       ║".cyan());
+        println!("{}              {}  OPENMESH XNODE DEPLOYMENT PLATFORM  {}              {}", "║".cyan(), "🌐".cyan(), "🌐".cyan(), "║".cyan());
+        println!("{}", "║                                                                       ║".cyan());
+        println!("{}", "║   Deploy and manage infrastructure across 8 cloud providers with     ║".cyan());
+        println!("{}", "║   a unified, beautiful CLI interface.                                ║".cyan());
+        println!("{}", "║                                                                       ║".cyan());
+        println!("{}", "╚═══════════════════════════════════════════════════════════════════════╝".cyan());
+        println!();
+    }
 
     println!("{}", "  📋 QUICK START".green().bold());
     println!();
     println!("    {}  {}  {}", "1.".cyan().bold(), "capsule openmesh providers".white().bold(), "→ Browse 8 cloud providers".white());
-    println!("    {}  {}  {}", "2.".cyan().bold(), "capsule openmesh xnode templates".white().bold(), "→ View 36 instance templates".white());
+    println!("    {}  {}  {}", "2.".cyan().bold(), "capsule openmesh xnode templates".white().bold(), "→ View 38 instance templates".white());
     println!("    {}  {}  {}", "3.".cyan().bold(), "capsule openmesh xnode deploy".white().bold(), "→ Launch your xNode".white());
     println!();
 
@@ -82,6 +110,10 @@ fn show_openmesh_overview() -> Result<()> {
     table.printstd();
     println!();
 
+    if is_quiet() {
+        return Ok(());
+    }
+
     println!("{}", "  💡 PRO TIPS".yellow().bold());
     println!();
     println!("    {}  Use {} for smart instance selection", "•".yellow(), "capsule openmesh xnode deploy --budget 0.5".cyan());
@@ -101,43 +133,71 @@ fn show_openmesh_overview() -> Result<()> {
 pub fn handle_xnode_command(command: XnodeCommands) -> Result<()> {
     match command {
         XnodeCommands::Providers => list_providers()?,
-        XnodeCommands::Templates { gpu } => list_templates(gpu)?,
-        XnodeCommands::Deploy {
-            provider,
-            template,
-            name,
-            region,
-            budget,
-            min_cpu,
-            min_memory,
-        } => deploy_instance(provider, template, name, region, budget, min_cpu, min_memory)?,
-        XnodeCommands::List { status, provider } => {
-            println!("{} xNodes list (filtered by status: {:?}, provider: {:?})", "→".cyan(), status, provider);
-            println!("{}", "This feature is not yet implemented.".yellow());
+        XnodeCommands::Templates { gpu, arch, filter, all } => {
+            list_templates(gpu, arch.as_deref(), filter.as_ref(), all)?
+        }
+        XnodeCommands::DescribeTemplate { template_id, provider, json } => {
+            describe_template(&template_id, provider.as_deref(), json)?
+        }
+        XnodeCommands::Deploy { profile, settings, name, follow, timeout, yes, expensive_above } => {
+            let settings = match profile {
+                Some(profile) => with_deploy_profile(&profile, settings)?,
+                None => settings,
+            };
+            let follow = follow.then(|| Duration::from_secs(timeout));
+            let gate = (!yes).then_some(expensive_above);
+            deploy_instance(settings, name, follow, gate)?
+        },
+        XnodeCommands::Show { xnode_id, json, stale_after } => show_xnode(&xnode_id, json, stale_after)?,
+        XnodeCommands::Logs { xnode_id, lines, unit, follow } => {
+            show_logs(&xnode_id, lines, unit.as_deref(), follow)?
+        },
+        XnodeCommands::Open { xnode_id, print } => open_console(&xnode_id, print)?,
+        XnodeCommands::Top { sort, interval, provider, once } => {
+            let options = TopOptions { sort, interval: Duration::from_secs(interval), provider, once };
+            top_xnodes(&options)?
+        },
+        XnodeCommands::Find { ip, name, json } => {
+            crate::openmesh_cli::find_xnodes(ip.as_deref(), name.as_deref(), json)?
+        },
+        XnodeCommands::Start { selector } => run_bulk(&selector, BulkAction::Start)?,
+        XnodeCommands::Stop { selector } => run_bulk(&selector, BulkAction::Stop)?,
+        XnodeCommands::Restart { selector } => run_bulk(&selector, BulkAction::Restart)?,
+        XnodeCommands::Destroy { selector, reason } => run_bulk(&selector, BulkAction::Destroy { reason })?,
+        XnodeCommands::Sync { providers, parallel } => sync_xnodes(providers, parallel)?,
+        XnodeCommands::Prune(options) => prune_xnodes(options)?,
+        XnodeCommands::Validate { json } => validate_inventory(json)?,
+        XnodeCommands::Adopt { provider, instance_id, cost } => adopt_xnode(&provider, &instance_id, cost)?,
+        XnodeCommands::Resize { xnode_id, template } => resize_xnode(&xnode_id, &template)?,
+        XnodeCommands::Move(options) => move_xnode(options)?,
+        XnodeCommands::Snapshot { xnode_id, name } => snapshot_xnode(&xnode_id, name)?,
+        XnodeCommands::List { status, provider, stale_after } => {
+            crate::openmesh_cli::list_xnodes(status, provider, stale_after)?
         },
         XnodeCommands::Inventory { provider, status } => {
             println!("{} Inventory feature (filtered by provider: {:?}, status: {:?})", "→".cyan(), provider, status);
             println!("{}", "This feature is not yet implemented.".yellow());
         },
-        XnodeCommands::CostReport => {
-            println!("{} Cost report", "→".cyan());
-            println!("{}", "This feature is not yet implemented.".yellow());
+        XnodeCommands::CostReport { group_by: Some(grouping), json, .. } => {
+            crate::openmesh_cli::show_grouped_costs(grouping, json)?
         },
-        XnodeCommands::Stats => {
-            println!("{} Inventory statistics", "→".cyan());
-            println!("{}", "This feature is not yet implemented.".yellow());
+        XnodeCommands::CostReport { anomaly_threshold, window, alert, format, output, .. } => {
+            crate::openmesh_cli::show_cost_report(anomaly_threshold, window, alert, format, output.as_deref())?
         },
-        XnodeCommands::Export { filename } => {
-            println!("{} Export to {}", "→".cyan(), filename);
-            println!("{}", "This feature is not yet implemented.".yellow());
+        XnodeCommands::CostForecast { xnode_id, json } => {
+            crate::openmesh_cli::show_cost_forecast(&xnode_id, json)?
         },
-        XnodeCommands::Import { filename } => {
-            println!("{} Import from {}", "→".cyan(), filename);
-            println!("{}", "This feature is not yet implemented.".yellow());
+        XnodeCommands::Stats { json, output } => crate::openmesh_cli::show_statistics(json, output.as_deref())?,
+        XnodeCommands::Tags { json } => crate::openmesh_cli::show_tags(json)?,
+        XnodeCommands::Export { filename, format, append } => {
+            crate::openmesh_cli::export_inventory(&filename, format, append)?
         },
-        XnodeCommands::History { xnode_id, provider, limit } => {
-            println!("{} Deployment history (xnode_id: {:?}, provider: {:?}, limit: {:?})", "→".cyan(), xnode_id, provider, limit);
-            println!("{}", "This feature is not yet implemented.".yellow());
+        XnodeCommands::Import { filename, format } => {
+            crate::openmesh_cli::import_inventory(&filename, format)?
+        },
+        XnodeCommands::History { xnode_id, provider, since, until, terminated, limit, json, output } => {
+            let window = HistoryWindow { since, until, by_termination: terminated };
+            crate::openmesh_cli::show_deployment_history(xnode_id, provider, window, limit, json, output.as_deref())?
         },
         XnodeCommands::Cleanup { days } => {
             println!("{} Cleanup deployment history older than {} days", "→".cyan(), days);
@@ -154,7 +214,10 @@ pub enum OpenMeshCommands {
     Overview,
 
     /// 🍒 List all available cloud providers
-    Providers,
+    Providers {
+        #[command(subcommand)]
+        command: Option<ProvidersCommands>,
+    },
 
     /// 🌐 xNode deployment and management
     #[command(after_help = "\n\
@@ -168,6 +231,7 @@ pub enum OpenMeshCommands {
   Quick Start:\n\
     • capsule openmesh xnode providers    → List all cloud providers\n\
     • capsule openmesh xnode templates    → Browse instance templates\n\
+    • capsule openmesh xnode describe-template <id> → Template details\n\
     • capsule openmesh xnode deploy       → Deploy a new xNode\n\
     • capsule openmesh xnode list         → View all deployed xNodes\n\
 \n\
@@ -188,6 +252,12 @@ pub enum OpenMeshCommands {
         #[command(subcommand)]
         command: ProviderSubcommands,
     },
+
+    /// 📌 Saved deploy settings for `xnode deploy --profile`
+    DeployProfile {
+        #[command(subcommand)]
+        command: DeployProfileCommands,
+    },
 }
 
 #[derive(clap::Subcommand)]
@@ -203,19 +273,32 @@ pub enum OpenMeshCommands {
   🚀 Deployment:\n\
     deploy          Launch new xNode instances\n\
                     Example: --provider hivelocity --template small\n\
+                    Saved flags: --profile <name> (see openmesh deploy-profile)\n\
+                    Asks before spending; --yes skips the cost prompt\n\
+    sync            Reconcile inventory with providers (--parallel N)\n\
+    prune           Drop xNodes providers no longer report\n\
+    move            Record a manual migration (--new-id for a successor)\n\
 \n\
   📊 Management:\n\
     list (ls)       View all deployed xNodes\n\
+    show            Everything about one xNode\n\
+    logs            Recent journald output over SSH (--follow)\n\
+    open            Open the xNode in its provider's web console\n\
+    top             Live resource usage across running xNodes (--sort)\n\
+    find            Look up xNodes by --ip or --name\n\
     inventory       Detailed xNode inventory\n\
-    stats           Show deployment statistics\n\
+    stats           Show deployment statistics (--output to save)\n\
+    tags            List tags in use with node counts\n\
+    validate        Check the inventory for inconsistencies\n\
 \n\
   💰 Cost Analysis:\n\
-    cost-report     Generate cost breakdown\n\
-    export          Export data to CSV\n\
-    import          Import inventory from CSV\n\
+    cost-report     Generate cost breakdown (--group-by tag, --output)\n\
+    cost-forecast   Accrued and projected spend for one xNode\n\
+    export          Export inventory to CSV, JSON or YAML\n\
+    import          Import inventory from CSV, JSON or YAML\n\
 \n\
   🔍 History:\n\
-    history         View deployment history\n\
+    history         View deployment history (--json, --output)\n\
     cleanup         Remove old history records\n\
 \n\
   💡 Tips:\n\
@@ -233,37 +316,228 @@ pub enum XnodeCommands {
         /// Show only GPU templates
         #[arg(long)]
         gpu: bool,
+
+        /// Only templates for this CPU architecture (x86_64 or arm64)
+        #[arg(long, value_parser = parse_arch)]
+        arch: Option<String>,
+
+        /// Only templates matching an expression, e.g. 'cpu>=8 && memory>=16 && price<0.5'.
+        /// Fields: cpu, memory_gb, storage_gb, price_hourly, price_monthly; `gpu` or `!gpu`
+        #[arg(long, visible_alias = "template-filter", value_name = "EXPR")]
+        filter: Option<TemplateFilter>,
+
+        /// List every template instead of only the cheapest offer per spec
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Show everything about one instance template
+    DescribeTemplate {
+        /// Template ID
+        template_id: String,
+
+        /// Provider offering the template (only needed if several do)
+        #[arg(long)]
+        provider: Option<String>,
+
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
     },
 
     /// Deploy a new xNode instance
     Deploy {
-        /// Provider name (e.g., hivelocity, digitalocean)
-        #[arg(short, long)]
-        provider: Option<String>,
+        /// Start from a saved deploy profile; other flags override it
+        #[arg(long)]
+        profile: Option<String>,
 
-        /// Template ID
-        #[arg(short, long)]
-        template: Option<String>,
+        #[command(flatten)]
+        settings: DeploySettings,
 
         /// Instance name
         #[arg(short, long)]
         name: Option<String>,
 
-        /// Region
+        /// Wait for the instance to come up, printing each state change
+        #[arg(long)]
+        follow: bool,
+
+        /// Seconds to wait with --follow before giving up
+        #[arg(long, default_value_t = 600, requires = "follow")]
+        timeout: u64,
+
+        /// Deploy without confirming the cost
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Hourly price above which the template ID must be typed to confirm
+        #[arg(long, value_name = "USD", default_value_t = DEFAULT_EXPENSIVE_HOURLY)]
+        expensive_above: f64,
+    },
+
+    /// Find xNodes by exact IP address or by name
+    Find {
+        /// IP address to look up
+        #[arg(long, required_unless_present = "name", conflicts_with = "name")]
+        ip: Option<String>,
+
+        /// Name or ID substring to look up
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show full details for one xNode
+    Show {
+        /// xNode ID
+        xnode_id: String,
+
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Flag the xNode if nothing has confirmed it in this many days
+        #[arg(long, value_name = "DAYS", default_value_t = DEFAULT_STALE_AFTER_DAYS)]
+        stale_after: u32,
+    },
+
+    /// Print recent journald output from an xNode over SSH
+    Logs {
+        /// xNode ID
+        xnode_id: String,
+
+        /// Number of journal lines to show
+        #[arg(short = 'n', long, default_value_t = 100)]
+        lines: usize,
+
+        /// Only this systemd unit, e.g. nginx.service
+        #[arg(short, long)]
+        unit: Option<String>,
+
+        /// Keep streaming new entries (journalctl -f)
         #[arg(short, long)]
-        region: Option<String>,
+        follow: bool,
+    },
+
+    /// Open an xNode's page in its provider's web console
+    Open {
+        /// xNode ID
+        xnode_id: String,
+
+        /// Print the URL instead of launching a browser
+        #[arg(long)]
+        print: bool,
+    },
+
+    /// Live CPU, memory, disk and load for every running xNode
+    Top {
+        /// Column to sort by, busiest first: cpu, memory, disk or load
+        #[arg(short, long, default_value = "cpu")]
+        sort: TopSort,
+
+        /// Seconds between refreshes
+        #[arg(short = 'n', long, default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+
+        /// Only xNodes on this provider
+        #[arg(long)]
+        provider: Option<String>,
+
+        /// Print the table once and exit
+        #[arg(long)]
+        once: bool,
+    },
+
+    /// Start stopped xNodes
+    Start {
+        #[command(flatten)]
+        selector: XnodeSelector,
+    },
+
+    /// Stop running xNodes without destroying them
+    Stop {
+        #[command(flatten)]
+        selector: XnodeSelector,
+    },
+
+    /// Stop and start xNodes
+    Restart {
+        #[command(flatten)]
+        selector: XnodeSelector,
+    },
+
+    /// Delete xNodes at the provider and remove them from inventory
+    Destroy {
+        #[command(flatten)]
+        selector: XnodeSelector,
 
-        /// Maximum hourly budget
+        /// Why the xNodes are being torn down (e.g. "cost cleanup", "migrated"),
+        /// kept in the deployment history
         #[arg(long)]
-        budget: Option<f64>,
+        reason: Option<String>,
+    },
+
+    /// Reconcile the inventory with what providers report
+    Sync {
+        /// Only query this provider (repeatable; default: providers with
+        /// credentials or inventory entries)
+        #[arg(long = "provider", value_name = "NAME")]
+        providers: Vec<String>,
+
+        /// Query up to this many providers at once
+        #[arg(long, default_value = "4")]
+        parallel: usize,
+    },
 
-        /// Minimum CPU cores
+    /// Drop xNodes their provider no longer reports (or, with --status,
+    /// every xNode in that status) from the inventory
+    Prune(PruneOptions),
+
+    /// Check the inventory for inconsistencies such as running xNodes
+    /// without an IP, duplicate IPs or unknown templates; exits 1 if any
+    Validate {
+        /// Print issues as JSON
         #[arg(long)]
-        min_cpu: Option<u32>,
+        json: bool,
+    },
+
+    /// Add an instance created outside capsule to the inventory
+    Adopt {
+        /// Provider name
+        provider: String,
 
-        /// Minimum memory (GB)
+        /// Instance ID at the provider
+        instance_id: String,
+
+        /// Hourly cost, when it can't be inferred from a known template
         #[arg(long)]
-        min_memory: Option<u32>,
+        cost: Option<f64>,
+    },
+
+    /// Resize an xNode to a different template
+    Resize {
+        /// xNode ID
+        xnode_id: String,
+
+        /// New template ID
+        #[arg(short, long)]
+        template: String,
+    },
+
+    /// Record that an xNode was migrated to another provider, template or region
+    Move(MoveOptions),
+
+    /// Create a provider-side snapshot (image) of an xNode
+    Snapshot {
+        /// xNode ID
+        xnode_id: String,
+
+        /// Snapshot label (defaults to a timestamp)
+        #[arg(short, long)]
+        name: Option<String>,
     },
 
     /// List all deployed xNodes
@@ -276,6 +550,10 @@ pub enum XnodeCommands {
         /// Filter by provider
         #[arg(long)]
         provider: Option<String>,
+
+        /// Flag xNodes no sync or health check has confirmed in this many days
+        #[arg(long, value_name = "DAYS", default_value_t = DEFAULT_STALE_AFTER_DAYS)]
+        stale_after: u32,
     },
 
     /// View detailed xNode inventory
@@ -290,23 +568,90 @@ pub enum XnodeCommands {
     },
 
     /// Generate cost analysis report
-    #[command(name = "cost-report")]
-    CostReport,
+    #[command(name = "cost-report", visible_alias = "costs")]
+    CostReport {
+        /// Flag spend increases of at least this percentage over the baseline
+        #[arg(long, default_value = "25")]
+        anomaly_threshold: f64,
+
+        /// Days of cost history averaged for the baseline
+        #[arg(long, default_value = "7")]
+        window: u32,
+
+        /// Raise a cost_threshold monitoring alert when an anomaly is found
+        /// or spend crosses one of the monitoring `cost_tiers`
+        #[arg(long)]
+        alert: bool,
+
+        /// Report format: text, md or html
+        #[arg(long, default_value = "text")]
+        format: ReportFormat,
+
+        /// Write the report to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<std::path::PathBuf>,
+
+        /// Rank running costs by provider, region or tag instead
+        #[arg(long, value_name = "GROUP", conflicts_with_all = ["format", "output", "alert"])]
+        group_by: Option<CostGrouping>,
+
+        /// Print the --group-by ranking as JSON
+        #[arg(long, requires = "group_by")]
+        json: bool,
+    },
+
+    /// Project one xNode's spend at its current hourly rate
+    #[command(name = "cost-forecast")]
+    CostForecast {
+        /// xNode ID
+        xnode_id: String,
+
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
     /// Show inventory statistics
-    Stats,
+    Stats {
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Write the statistics to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<std::path::PathBuf>,
+    },
+
+    /// List tags in use and how many xNodes carry each
+    Tags {
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
-    /// Export inventory to CSV
+    /// Export inventory to CSV, JSON or YAML
     Export {
         /// Output filename
         #[arg(default_value = "inventory.csv")]
         filename: String,
+
+        /// Output format: csv, json or yaml (json and yaml keep every field)
+        #[arg(long, default_value = "csv")]
+        format: ExportFormat,
+
+        /// Append rows not already in an existing CSV instead of overwriting it
+        #[arg(long)]
+        append: bool,
     },
 
-    /// Import inventory from CSV
+    /// Import inventory from CSV, JSON or YAML
     Import {
         /// Input filename
         filename: String,
+
+        /// Input format: csv, json or yaml
+        #[arg(long, default_value = "csv")]
+        format: ExportFormat,
     },
 
     /// Show deployment history
@@ -319,9 +664,29 @@ pub enum XnodeCommands {
         #[arg(long)]
         provider: Option<String>,
 
+        /// Only deployments from this time on: 7d, 24h, 2024-05-01 or RFC 3339
+        #[arg(long)]
+        since: Option<TimeBound>,
+
+        /// Only deployments up to this time; a date includes the whole day
+        #[arg(long)]
+        until: Option<TimeBound>,
+
+        /// Apply --since/--until to termination time instead of deployment time
+        #[arg(long)]
+        terminated: bool,
+
         /// Limit number of records
         #[arg(long)]
         limit: Option<usize>,
+
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Write the history table (or JSON) to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<std::path::PathBuf>,
     },
 
     /// Cleanup old deployment history
@@ -332,6 +697,122 @@ pub enum XnodeCommands {
     },
 }
 
+/// Where an xNode was migrated to. Only the inventory changes; the move
+/// itself is done by hand beforehand.
+#[derive(clap::Args, Clone, Debug, Default)]
+pub struct MoveOptions {
+    /// xNode ID
+    pub xnode_id: String,
+
+    /// Provider now hosting the node
+    #[arg(short, long)]
+    pub provider: Option<String>,
+
+    /// Template on the new provider (required when the provider changes)
+    #[arg(short, long)]
+    pub template: Option<String>,
+
+    /// Region on the new provider
+    #[arg(short, long)]
+    pub region: Option<String>,
+
+    /// Hourly cost (default: the template's price)
+    #[arg(long)]
+    pub cost: Option<f64>,
+
+    /// New IP address
+    #[arg(long)]
+    pub ip: Option<String>,
+
+    /// Record the move as a new xNode with this ID, closing the old entry;
+    /// without it the entry is edited in place
+    #[arg(long, value_name = "ID")]
+    pub new_id: Option<String>,
+}
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct PruneOptions {
+    /// Prune xNodes in this status (e.g. terminated) without asking providers
+    #[arg(long, conflicts_with_all = ["providers", "parallel"])]
+    pub status: Option<String>,
+
+    /// Only check this provider (repeatable; default: providers with
+    /// credentials or inventory entries)
+    #[arg(long = "provider", value_name = "NAME")]
+    pub providers: Vec<String>,
+
+    /// Query up to this many providers at once
+    #[arg(long, default_value = "4")]
+    pub parallel: usize,
+
+    /// Set status to terminated instead of removing the entries
+    #[arg(long)]
+    pub mark: bool,
+
+    /// List what would be pruned without changing the inventory
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Skip the confirmation prompt
+    #[arg(long, short)]
+    pub yes: bool,
+}
+
+/// Which xNodes a lifecycle command acts on: one ID, or every node matching
+/// all of the given selectors
+#[derive(clap::Args, Clone, Debug)]
+pub struct XnodeSelector {
+    /// xNode ID
+    #[arg(required_unless_present_any = ["tags", "provider", "status"], conflicts_with_all = ["tags", "provider", "status"])]
+    pub xnode_id: Option<String>,
+
+    /// Select xNodes carrying this tag (repeatable; all must match)
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+
+    /// Select xNodes from this provider
+    #[arg(long)]
+    pub provider: Option<String>,
+
+    /// Select xNodes with this status
+    #[arg(long)]
+    pub status: Option<String>,
+
+    /// Skip the confirmation prompt (required for bulk destroy)
+    #[arg(short, long)]
+    pub yes: bool,
+}
+
+#[derive(clap::Subcommand)]
+pub enum DeployProfileCommands {
+    /// Save deploy flags under a name for `xnode deploy --profile`
+    Save {
+        /// Profile name
+        name: String,
+
+        #[command(flatten)]
+        settings: DeploySettings,
+    },
+
+    /// List saved deploy profiles
+    #[command(visible_alias = "ls")]
+    List,
+
+    /// Delete a saved deploy profile
+    #[command(visible_alias = "rm")]
+    Delete {
+        /// Profile name
+        name: String,
+    },
+}
+
+#[derive(clap::Subcommand)]
+pub enum ProvidersCommands {
+    /// Fetch current template prices from provider APIs and cache them
+    #[command(name = "refresh-pricing")]
+    RefreshPricing,
+}
+
 #[derive(clap::Subcommand)]
 pub enum ProviderSubcommands {
     /// Configure provider credentials
@@ -342,31 +823,86 @@ pub enum ProviderSubcommands {
         #[arg(short, long)]
         api_key: String,
     },
+
+    /// Delete a provider's stored API key
+    #[command(visible_alias = "rm")]
+    Remove {
+        /// Provider name
+        name: String,
+
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// List providers and whether an API key is configured for each
+    #[command(visible_alias = "status")]
+    List {
+        /// Only providers with an API key
+        #[arg(long)]
+        configured: bool,
+
+        /// Ask each configured provider whether it accepts the key
+        #[arg(long)]
+        verify: bool,
+
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// List the regions a provider offers, with their locations
+    Regions {
+        /// Provider name
+        name: String,
+
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// List the OS images a provider can deploy
+    #[command(visible_alias = "image-list")]
+    Images {
+        /// Provider name
+        name: String,
+
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Print the stored provider settings with API keys and secrets masked
+    #[command(name = "show-config")]
+    ShowConfig {
+        /// Print as JSON instead of YAML
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 fn list_providers() -> Result<()> {
     // ASCII art header
-    println!();
-    println!("{}", "╔═══════════════════════════════════════════════════════════════╗".cyan());
-    println!("{}", "║           🌐  OPENMESH CLOUD PROVIDERS  🌐                   ║".cyan().bold());
-    println!("{}", "╚═══════════════════════════════════════════════════════════════╝".cyan());
-    println!();
+    if !is_quiet() {
+        println!();
+        println!("{}", "╔═══════════════════════════════════════════════════════════════╗".cyan());
+        println!("{}", "║           🌐  OPENMESH CLOUD PROVIDERS  🌐                   ║".cyan().bold());
+        println!("{}", "╚═══════════════════════════════════════════════════════════════╝".cyan());
+        println!();
+    }
 
     let manager = ProviderManager::new(None)?;
     let providers = manager.list_providers();
 
-    let mut table = Table::new();
-    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
-
-    table.add_row(Row::new(vec![
-        Cell::new("Provider").style_spec("Fb"),
-        Cell::new("Type").style_spec("Fb"),
-        Cell::new("CPU Range").style_spec("Fb"),
-        Cell::new("Memory").style_spec("Fb"),
-        Cell::new("Regions").style_spec("Fb"),
-        Cell::new("Price/hr").style_spec("Fb"),
-        Cell::new("GPU").style_spec("Fb"),
-    ]));
+    let mut table = FitTable::new(&[
+        ("Provider", 0),
+        ("Type", 2),
+        ("CPU Range", 1),
+        ("Memory", 1),
+        ("Regions", 2),
+        ("Price/hr", 0),
+        ("GPU", 1),
+    ]);
 
     for provider_name in &providers {
         if let Some(provider) = manager.get_provider(provider_name) {
@@ -410,7 +946,7 @@ fn list_providers() -> Result<()> {
                 provider_name.clone()
             };
 
-            table.add_row(Row::new(vec![
+            table.add_row(vec![
                 Cell::new(&display_name).style_spec("Fc"),
                 Cell::new(instance_type),
                 Cell::new(&format!("{}-{} cores", min_cpu, max_cpu)),
@@ -418,12 +954,21 @@ fn list_providers() -> Result<()> {
                 Cell::new(&regions.len().to_string()),
                 Cell::new(&format!("${:.3}-${:.2}", min_price, max_price)).style_spec("Fg"),
                 Cell::new(&if has_gpu { format!("{} ✓", gpu_count) } else { "-".to_string() }),
-            ]));
+            ]);
         }
     }
 
     table.printstd();
 
+    println!();
+    println!("{}", "REGIONS".cyan().bold());
+    for provider_name in &providers {
+        if let Some(provider) = manager.get_provider(provider_name) {
+            let regions: Vec<String> = provider.regions().iter().map(|r| region_label(provider, r)).collect();
+            println!("  {:<14} {}", provider_name, regions.join(", "));
+        }
+    }
+
     println!();
     println!("{}", "─────────────────────────────────────────────────────────────────".cyan());
     println!("{} {} providers • {} total templates • {} with GPU",
@@ -432,10 +977,36 @@ fn list_providers() -> Result<()> {
         manager.get_all_templates().len(),
         manager.get_gpu_templates().len()
     );
-    println!("{} Use {} to view detailed templates", "💡".cyan(), "capsule openmesh xnode templates".cyan().bold());
-    println!("{} Configure credentials: {}", "🔧".cyan(), "capsule openmesh provider configure <name> --api-key <key>".cyan().bold());
+    if !is_quiet() {
+        println!("{} Use {} to view detailed templates", "💡".cyan(), "capsule openmesh xnode templates".cyan().bold());
+        println!("{} Configure credentials: {}", "🔧".cyan(), "capsule openmesh provider configure <name> --api-key <key>".cyan().bold());
+    }
+    println!();
+
+    Ok(())
+}
+
+fn refresh_pricing() -> Result<()> {
+    let mut manager = ProviderManager::new(None)?;
+
     println!();
+    println!("{} Refreshing template pricing...", "▸".green().bold());
+    let results = manager.refresh_pricing()?;
+
+    let mut refreshed = 0;
+    for (provider, result) in &results {
+        match result {
+            Ok(changed) => {
+                refreshed += 1;
+                println!("  {} {:<14} {} price(s) updated", "✓".green(), provider, changed);
+            }
+            Err(e) => println!("  {} {:<14} {:#}", "⚠".yellow(), provider, e),
+        }
+    }
 
+    println!();
+    println!("{} Refreshed {} of {} providers with live pricing", "▸".green().bold(), refreshed, results.len());
+    println!();
     Ok(())
 }
 
@@ -446,81 +1017,1494 @@ fn handle_provider_command(command: ProviderSubcommands) -> Result<()> {
             manager.configure_provider(name.clone(), api_key)?;
             println!("{} Configured provider: {}", "✓".green(), name.cyan());
         }
+        ProviderSubcommands::Remove { name, yes } => remove_provider_credentials(&name, yes)?,
+        ProviderSubcommands::List { configured, verify, json } => {
+            list_provider_credentials(configured, verify, json)?
+        }
+        ProviderSubcommands::Regions { name, json } => list_provider_regions(&name, json)?,
+        ProviderSubcommands::Images { name, json } => list_provider_images(&name, json)?,
+        ProviderSubcommands::ShowConfig { json } => show_provider_config(json)?,
     }
     Ok(())
 }
 
-fn list_templates(gpu_only: bool) -> Result<()> {
-    let manager = ProviderManager::new(None)?;
-    let templates = if gpu_only {
-        manager.get_gpu_templates()
-    } else {
-        manager.get_all_templates()
-    };
+fn remove_provider_credentials(name: &str, yes: bool) -> Result<()> {
+    let mut manager = ProviderManager::new(None)?;
+    if manager.get_provider(name).is_none() {
+        return Err(CliError::not_found(format!("Unknown provider: {}", name)).into());
+    }
 
-    // ASCII art header
-    println!();
-    if gpu_only {
-        println!("{}", "╔═══════════════════════════════════════════════════════════════╗".cyan());
-        println!("{}", "║              🎮  GPU INSTANCE TEMPLATES  🎮                   ║".cyan().bold());
-        println!("{}", "╚═══════════════════════════════════════════════════════════════╝".cyan());
+    if !yes {
+        let prompt = format!("Remove stored credentials for {}?", name);
+        if !Confirm::new().with_prompt(prompt).default(false).interact()? {
+            println!("{}", "Aborted".yellow());
+            return Ok(());
+        }
+    }
+
+    if manager.remove_provider_credentials(name)? {
+        println!("{} Removed credentials for {}", "✓".green(), name.cyan());
     } else {
-        println!("{}", "╔═══════════════════════════════════════════════════════════════╗".cyan());
-        println!("{}", "║             📦  XNODE INSTANCE TEMPLATES  📦                  ║".cyan().bold());
-        println!("{}", "╚═══════════════════════════════════════════════════════════════╝".cyan());
+        println!("{} No credentials stored for {}", "ℹ".cyan(), name.cyan());
+    }
+    Ok(())
+}
+
+fn list_provider_regions(provider_name: &str, json: bool) -> Result<()> {
+    let manager = ProviderManager::new(None)?;
+    let regions = manager.regions(provider_name)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&regions)?);
+        return Ok(());
     }
-    println!();
 
     let mut table = Table::new();
     table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
-
-    table.add_row(Row::new(vec![
-        Cell::new("Provider").style_spec("Fb"),
-        Cell::new("Template").style_spec("Fb"),
-        Cell::new("CPU").style_spec("Fb"),
-        Cell::new("Memory").style_spec("Fb"),
-        Cell::new("Storage").style_spec("Fb"),
-        Cell::new("GPU").style_spec("Fb"),
-        Cell::new("Price/hr").style_spec("Fb"),
-        Cell::new("Price/mo").style_spec("Fb"),
+    table.set_titles(Row::new(vec![
+        Cell::new("Region").style_spec("Fb"),
+        Cell::new("Location").style_spec("Fb"),
     ]));
-
-    for template in &templates {
+    for region in &regions {
         table.add_row(Row::new(vec![
-            Cell::new(&template.provider).style_spec("Fc"),
-            Cell::new(&template.name),
-            Cell::new(&format!("{} cores", template.cpu)),
-            Cell::new(&format!("{} GB", template.memory_gb)),
-            Cell::new(&format!("{} GB", template.storage_gb)),
-            Cell::new(&template.gpu.as_deref().unwrap_or("-")),
-            Cell::new(&format!("${:.3}", template.price_hourly)).style_spec("Fg"),
-            Cell::new(&format!("${:.2}", template.price_monthly)).style_spec("Fy"),
+            Cell::new(&region.slug).style_spec("Fc"),
+            Cell::new(region.name.as_deref().unwrap_or("-")),
         ]));
     }
-
+    table.printstd();
+    println!("\n{} {} region(s) for {}", "ℹ".cyan(), regions.len(), provider_name.cyan());
+    Ok(())
+}
+
+fn list_provider_images(provider_name: &str, json: bool) -> Result<()> {
+    let manager = ProviderManager::new(None)?;
+    let images = manager.images(provider_name)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&images)?);
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(Row::new(vec![
+        Cell::new("Image").style_spec("Fb"),
+        Cell::new("Name").style_spec("Fb"),
+        Cell::new("Arch").style_spec("Fb"),
+    ]));
+    for image in &images {
+        table.add_row(Row::new(vec![
+            Cell::new(&image.id).style_spec("Fc"),
+            Cell::new(&image.name),
+            Cell::new(&image.arch),
+        ]));
+    }
+    table.printstd();
+    println!("\n{} {} image(s) for {}", "ℹ".cyan(), images.len(), provider_name.cyan());
+    Ok(())
+}
+
+fn show_provider_config(json: bool) -> Result<()> {
+    let config = ProviderManager::new(None)?.redacted_config();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&config)?);
+    } else if config.is_empty() {
+        println!("{} No provider settings stored", "ℹ".cyan());
+    } else {
+        print!("{}", serde_yaml::to_string(&config)?);
+    }
+    Ok(())
+}
+
+fn list_provider_credentials(configured_only: bool, verify: bool, json: bool) -> Result<()> {
+    let manager = ProviderManager::new(None)?;
+    let mut statuses = manager.credential_status(verify);
+    if configured_only {
+        statuses.retain(|s| s.configured);
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&statuses)?);
+        return Ok(());
+    }
+
+    if statuses.is_empty() {
+        println!("{} No provider has an API key configured", "ℹ".cyan());
+        println!("  Configure one with: {}", "capsule openmesh provider configure <name> --api-key <key>".cyan());
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    let mut titles = vec![Cell::new("Provider").style_spec("Fb"), Cell::new("API key").style_spec("Fb")];
+    if verify {
+        titles.push(Cell::new("Verified").style_spec("Fb"));
+    }
+    table.set_titles(Row::new(titles));
+
+    for status in &statuses {
+        let key = if status.configured { Cell::new("✓ configured").style_spec("Fg") } else { Cell::new("⚠ not set").style_spec("Fy") };
+        let mut row = vec![Cell::new(&status.provider).style_spec("Fc"), key];
+        if verify {
+            row.push(match (status.valid, &status.error) {
+                (Some(true), _) => Cell::new("✓ accepted").style_spec("Fg"),
+                (Some(false), _) => Cell::new("✗ rejected").style_spec("Fr"),
+                (None, Some(error)) => Cell::new(&format!("? {}", error)).style_spec("Fy"),
+                (None, None) => Cell::new("-"),
+            });
+        }
+        table.add_row(Row::new(row));
+    }
+    table.printstd();
+
+    let missing = statuses.iter().filter(|s| !s.configured).count();
+    if missing > 0 {
+        println!();
+        println!(
+            "{} {} provider(s) have no key; deploys to them fall back to mock mode. Set one with {}",
+            "💡".cyan(),
+            missing,
+            "capsule openmesh provider configure <name> --api-key <key>".cyan()
+        );
+    }
+    Ok(())
+}
+
+fn list_templates(gpu_only: bool, arch: Option<&str>, filter: Option<&TemplateFilter>, all: bool) -> Result<()> {
+    let manager = ProviderManager::new(None)?;
+    let mut templates = if gpu_only {
+        manager.get_gpu_templates()
+    } else {
+        manager.get_all_templates()
+    };
+    if let Some(arch) = arch {
+        templates.retain(|t| t.arch == arch);
+    }
+    if let Some(filter) = filter {
+        templates.retain(|t| filter.matches(t));
+    }
+
+    // ASCII art header
+    if !is_quiet() {
+        println!();
+        if gpu_only {
+            println!("{}", "╔═══════════════════════════════════════════════════════════════╗".cyan());
+            println!("{}", "║              🎮  GPU INSTANCE TEMPLATES  🎮                   ║".cyan().bold());
+            println!("{}", "╚═══════════════════════════════════════════════════════════════╝".cyan());
+        } else {
+            println!("{}", "╔═══════════════════════════════════════════════════════════════╗".cyan());
+            println!("{}", "║             📦  XNODE INSTANCE TEMPLATES  📦                  ║".cyan().bold());
+            println!("{}", "╚═══════════════════════════════════════════════════════════════╝".cyan());
+        }
+        println!();
+    }
+
+    let mut table = FitTable::new(&[
+        ("Provider", 0),
+        ("Template", 0),
+        ("CPU", 1),
+        ("Memory", 1),
+        ("Storage", 2),
+        ("GPU", 2),
+        ("Arch", 3),
+        ("Price/hr", 0),
+        ("Price/mo", 2),
+        ("Regions", 3),
+        (if all { "" } else { "Also at" }, 4),
+    ]);
+
+    let total = templates.len();
+    let rows: Vec<(ProviderTemplate, Vec<String>)> = if all {
+        templates.into_iter().map(|t| (t, Vec::new())).collect()
+    } else {
+        group_by_spec(templates)
+            .into_iter()
+            .map(|group| {
+                let others = group
+                    .alternatives()
+                    .iter()
+                    .map(|t| format!("{}/{} ${:.3}", t.provider, t.id, t.price_hourly))
+                    .collect();
+                (group.cheapest().clone(), others)
+            })
+            .collect()
+    };
+
+    for (template, others) in &rows {
+        let provider = manager.get_provider(&template.provider);
+        let mut locations: Vec<String> = template
+            .regions
+            .iter()
+            .map(|r| provider.and_then(|p| p.region_display_name(r)).unwrap_or_else(|| r.to_string()))
+            .collect();
+        locations.dedup();
+
+        table.add_row(vec![
+            Cell::new(&template.provider).style_spec("Fc"),
+            Cell::new(&template.name),
+            Cell::new(&format!("{} cores", template.cpu)),
+            Cell::new(&format!("{} GB", template.memory_gb)),
+            Cell::new(&format!("{} GB", template.storage_gb)),
+            Cell::new(template.gpu.as_deref().unwrap_or("-")),
+            Cell::new(&template.arch),
+            Cell::new(&format!("${:.3}", template.price_hourly)).style_spec("Fg"),
+            Cell::new(&format!("${:.2}", template.price_monthly)).style_spec("Fy"),
+            Cell::new(&locations.join(", ")),
+            Cell::new(&others.join(", ")),
+        ]);
+    }
+
     table.printstd();
 
     println!();
     println!("{}", "─────────────────────────────────────────────────────────────────".cyan());
-    println!("{} {} templates available", "▸".green().bold(), templates.len());
-    println!("{} Deploy with: {}", "🚀".cyan(), "capsule openmesh xnode deploy --provider <name> --template <id>".cyan().bold());
-    if !gpu_only {
+    if all || rows.len() == total {
+        println!("{} {} templates available", "▸".green().bold(), total);
+    } else {
+        println!("{} {} templates available, {} distinct specs (cheapest shown; {} lists every template)",
+            "▸".green().bold(),
+            total,
+            rows.len(),
+            "--all".cyan()
+        );
+    }
+    if let Some(refreshed_at) = manager.pricing_refreshed_at()? {
+        let age = chrono::Utc::now().signed_duration_since(refreshed_at).num_days();
+        if age > PRICING_STALE_DAYS {
+            println!("{} Prices were last refreshed {} days ago: {}",
+                "⚠".yellow(),
+                age,
+                "capsule openmesh providers refresh-pricing".cyan().bold()
+            );
+        }
+    }
+    if !is_quiet() {
+        println!("{} Deploy with: {}", "🚀".cyan(), "capsule openmesh xnode deploy --provider <name> --template <id>".cyan().bold());
+    }
+    if !gpu_only && !is_quiet() {
         println!("{} GPU only: {}", "💡".cyan(), "capsule openmesh xnode templates --gpu".cyan().bold());
     }
+    if !is_quiet() {
+        println!("{} Details: {}", "🔎".cyan(), "capsule openmesh xnode describe-template <id>".cyan().bold());
+    }
     println!();
 
     Ok(())
 }
 
+/// One catalog template with its regions and unit costs, for
+/// `xnode describe-template`
+#[derive(Debug, serde::Serialize)]
+struct TemplateDetails {
+    #[serde(flatten)]
+    template: ProviderTemplate,
+    price_annual: f64,
+    region_details: Vec<Region>,
+    efficiency: TemplateEfficiency,
+}
+
+fn describe_template(template_id: &str, provider: Option<&str>, json: bool) -> Result<()> {
+    let manager = ProviderManager::new(None)?;
+    let template = manager.find_template(template_id, provider)?;
+    let regions = manager.regions(&template.provider)?;
+    let details = TemplateDetails {
+        price_annual: template.price_annual(),
+        region_details: template
+            .regions
+            .iter()
+            .map(|slug| {
+                regions
+                    .iter()
+                    .find(|r| &r.slug == slug)
+                    .cloned()
+                    .unwrap_or_else(|| Region { slug: slug.clone(), name: None })
+            })
+            .collect(),
+        efficiency: template.efficiency(),
+        template,
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&details)?);
+        return Ok(());
+    }
+
+    let template = &details.template;
+    println!();
+    println!("{} {}", "📦".cyan(), template.name.cyan().bold());
+    println!("{}", "─────────────────────────────────────────────────────────────────".cyan());
+    println!("  {} {}", "ID:".white().bold(), template.id);
+    println!("  {} {}", "Provider:".white().bold(), template.provider);
+    println!("  {} {} cores", "CPU:".white().bold(), template.cpu);
+    println!("  {} {} GB", "Memory:".white().bold(), template.memory_gb);
+    println!("  {} {} GB", "Storage:".white().bold(), template.storage_gb);
+    println!("  {} {} TB", "Bandwidth:".white().bold(), template.bandwidth_tb);
+    println!("  {} {}", "GPU:".white().bold(), template.gpu.as_deref().unwrap_or("-"));
+    println!("  {} {}", "Arch:".white().bold(), template.arch);
+    println!(
+        "  {} {}",
+        "Features:".white().bold(),
+        if template.features.is_empty() { "-".to_string() } else { template.features.join(", ") }
+    );
+
+    println!();
+    println!("{}", "  Regions".white().bold());
+    if details.region_details.is_empty() {
+        println!("    {}", "none listed".white().dimmed());
+    }
+    if let Some(provider) = manager.get_provider(&template.provider) {
+        for region in &details.region_details {
+            println!("    {}", region_label(provider, &region.slug));
+        }
+    }
+
+    println!();
+    println!("{}", "  Price".white().bold());
+    println!("    Hourly: {}", format!("${:.3}", template.price_hourly).green());
+    println!("    Monthly: {}", format!("${:.2}", template.price_monthly).yellow());
+    println!("    Annual: ${:.2}", details.price_annual);
+
+    let unit = |cost: Option<f64>, per: &str| match cost {
+        Some(cost) => format!("${:.2} per {} per month", cost, per),
+        None => "-".to_string(),
+    };
+    let efficiency = &details.efficiency;
+    println!();
+    println!("{}", "  Cost Efficiency".white().bold());
+    println!("    CPU: {}", unit(efficiency.per_cpu_monthly, "core"));
+    println!("    Memory: {}", unit(efficiency.per_gb_memory_monthly, "GB"));
+    println!("    Storage: {}", unit(efficiency.per_gb_storage_monthly, "GB"));
+    println!("    Bandwidth: {}", unit(efficiency.per_tb_bandwidth_monthly, "TB"));
+
+    if !is_quiet() {
+        println!();
+        println!(
+            "{} Deploy with: {}",
+            "🚀".cyan(),
+            format!("capsule openmesh xnode deploy --provider {} --template {}", template.provider, template.id).cyan().bold()
+        );
+    }
+    println!();
+
+    Ok(())
+}
+
+/// Everything known about a single xNode, aggregated for `xnode show`
+#[derive(Debug, serde::Serialize)]
+struct XNodeDetails {
+    #[serde(flatten)]
+    entry: XNodeEntry,
+    deployment: Option<DeploymentRecord>,
+    accrued_cost: f64,
+    projected_monthly_cost: f64,
+    /// Not confirmed by sync or a health check within the stale window
+    stale: bool,
+    health: Option<HealthCheck>,
+    metrics: Option<ResourceMetrics>,
+    active_alerts: Vec<Alert>,
+}
+
+impl XNodeDetails {
+    fn new(
+        entry: XNodeEntry,
+        deployment: Option<DeploymentRecord>,
+        status: Option<XNodeStatus>,
+        stale_after_days: u32,
+    ) -> Self {
+        // Active records only get their uptime filled in on termination
+        let deployment = deployment.map(|mut record| {
+            if record.is_active() {
+                record.uptime_hours = record.calculate_uptime();
+                record.total_cost = record.uptime_hours * entry.cost_hourly;
+            }
+            record
+        });
+        let accrued_cost = deployment.as_ref().map(|r| r.total_cost).unwrap_or(0.0);
+        let projected_monthly_cost = entry.cost_hourly * 24.0 * 30.0;
+        let stale = entry.is_stale(stale_after_days, Utc::now());
+        let (health, metrics, active_alerts) = match status {
+            Some(status) => (status.current_health, status.current_metrics, status.active_alerts),
+            None => (None, None, Vec::new()),
+        };
+
+        Self {
+            entry,
+            deployment,
+            accrued_cost,
+            projected_monthly_cost,
+            stale,
+            health,
+            metrics,
+            active_alerts,
+        }
+    }
+}
+
+fn show_xnode(xnode_id: &str, json: bool, stale_after_days: u32) -> Result<()> {
+    let inventory = XNodeInventory::new(None)?;
+    let entry = inventory_entry(&inventory, xnode_id)?;
+    let deployment = inventory
+        .get_deployment_history(Some(xnode_id), None, &HistoryWindow::default(), Some(1))
+        .first()
+        .map(|r| (*r).clone());
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let status = runtime
+        .block_on(MonitoringSystem::new(None))
+        .ok()
+        .map(|system| system.get_xnode_status(xnode_id));
+
+    let details = XNodeDetails::new(entry, deployment, status, stale_after_days);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&details)?);
+        return Ok(());
+    }
+
+    let entry = &details.entry;
+    println!();
+    println!("{} {}", "🌐".cyan(), entry.name.cyan().bold());
+    println!("{}", "─────────────────────────────────────────────────────────────────".cyan());
+    println!("  {} {}", "ID:".white().bold(), entry.id);
+    println!("  {} {}", "Status:".white().bold(), entry.status);
+    println!("  {} {}", "Provider:".white().bold(), entry.provider);
+    println!("  {} {}", "Template:".white().bold(), entry.template);
+    println!("  {} {}", "Region:".white().bold(), entry.region.as_deref().unwrap_or("-"));
+    println!("  {} {}", "IP Address:".white().bold(), if entry.ip_address.is_empty() { "-" } else { &entry.ip_address });
+    println!("  {} {}", "SSH Port:".white().bold(), entry.ssh_port);
+    println!("  {} {}", "Deployed:".white().bold(), entry.deployed_at.format("%Y-%m-%d %H:%M:%S UTC"));
+    let last_seen = entry.last_seen_label(Utc::now());
+    if details.stale {
+        println!(
+            "  {} {} {}",
+            "Last Seen:".white().bold(),
+            last_seen.yellow(),
+            format!("(possibly stale; run `capsule openmesh xnode sync` or `monitor health {}`)", entry.id).yellow()
+        );
+    } else {
+        println!("  {} {}", "Last Seen:".white().bold(), last_seen);
+    }
+    println!("  {} {}", "Tags:".white().bold(), if entry.tags.is_empty() { "-".to_string() } else { entry.tags.join(", ") });
+
+    if !entry.metadata.is_empty() {
+        println!();
+        println!("{}", "  Metadata".white().bold());
+        let mut keys: Vec<_> = entry.metadata.keys().collect();
+        keys.sort();
+        for key in keys {
+            println!("    {}: {}", key, entry.metadata[key]);
+        }
+    }
+
+    println!();
+    println!("{}", "  Cost".white().bold());
+    println!("    Hourly: ${:.3}", entry.cost_hourly);
+    println!("    Projected monthly: ${:.2}", details.projected_monthly_cost);
+    if let Some(record) = &details.deployment {
+        println!("    Uptime: {:.1} hours", record.uptime_hours);
+        println!("    Accrued: ${:.2}", details.accrued_cost);
+        if let Some(terminated) = record.terminated_at {
+            println!("    Terminated: {}", terminated.format("%Y-%m-%d %H:%M:%S UTC"));
+        }
+    }
+
+    println!();
+    println!("{}", "  Monitoring".white().bold());
+    match &details.health {
+        Some(health) => println!("    Health: {} (checked {})", health.status, health.timestamp),
+        None => println!("    Health: {}", "no data".white().dimmed()),
+    }
+    if let Some(metrics) = &details.metrics {
+        println!("    CPU: {:.1}%  Memory: {:.1}%  Disk: {:.1}%",
+            metrics.cpu_percent,
+            metrics.memory_percent,
+            metrics.disk_percent
+        );
+    }
+    if !details.active_alerts.is_empty() {
+        println!("    Active alerts: {}", details.active_alerts.len().to_string().red());
+    }
+    println!();
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum BulkAction {
+    Start,
+    Stop,
+    Restart,
+    /// Recording why in the deployment history, if a reason was given
+    Destroy { reason: Option<String> },
+}
+
+impl BulkAction {
+    fn verb(&self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::Stop => "stop",
+            Self::Restart => "restart",
+            Self::Destroy { .. } => "destroy",
+        }
+    }
+
+    fn is_destroy(&self) -> bool {
+        matches!(self, Self::Destroy { .. })
+    }
+}
+
+/// Exit status of the remote command when journalctl is not installed
+const NO_JOURNALCTL: i32 = 127;
+
+/// Shell command run on the node by `xnode logs`
+fn journal_command(lines: usize, unit: Option<&str>, follow: bool) -> Result<String> {
+    let mut command = format!("journalctl --no-pager -n {}", lines);
+    if let Some(unit) = unit {
+        let valid = !unit.is_empty()
+            && unit.chars().all(|c| c.is_ascii_alphanumeric() || "@._:-\\".contains(c));
+        if !valid {
+            return Err(CliError::usage(format!("Invalid unit name '{}'", unit)).into());
+        }
+        command.push_str(&format!(" -u {}", unit));
+    }
+    if follow {
+        command.push_str(" -f");
+    }
+    Ok(format!(
+        "command -v journalctl >/dev/null 2>&1 || exit {}; {}",
+        NO_JOURNALCTL, command
+    ))
+}
+
+/// Stream journald output from an xNode, using its inventory address and
+/// SSH settings
+fn show_logs(xnode_id: &str, lines: usize, unit: Option<&str>, follow: bool) -> Result<()> {
+    let inventory = XNodeInventory::new(None)?;
+    let entry = inventory_entry(&inventory, xnode_id)?;
+    if entry.ip_address.is_empty() {
+        anyhow::bail!("xNode {} has no IP address yet; try `capsule openmesh xnode sync`", xnode_id);
+    }
+
+    let remote = journal_command(lines, unit, follow)?;
+    let status = entry
+        .connection_settings()
+        .ssh_command(&entry.ip_address, entry.ssh_port)
+        .arg(remote)
+        .status()
+        .context("Failed to run ssh")?;
+
+    match status.code() {
+        Some(0) | None => Ok(()),
+        Some(255) => anyhow::bail!(
+            "Could not reach {} at {}:{} over SSH",
+            xnode_id,
+            entry.ip_address,
+            entry.ssh_port
+        ),
+        Some(NO_JOURNALCTL) => anyhow::bail!("{} has no journald (journalctl not found)", xnode_id),
+        Some(code) => anyhow::bail!("journalctl on {} exited with status {}", xnode_id, code),
+    }
+}
+
+fn open_console(xnode_id: &str, print: bool) -> Result<()> {
+    let inventory = XNodeInventory::new(None)?;
+    let entry = inventory_entry(&inventory, xnode_id)?;
+    let manager = ProviderManager::new(None)?;
+    let template = manager.console_url_template(&entry.provider).ok_or_else(|| {
+        CliError::usage(format!(
+            "No console URL known for provider {}; set `console_url` for it in providers.yml",
+            entry.provider
+        ))
+    })?;
+    let url = console_url(&template, &entry.id, entry.region.as_deref())?;
+
+    if print {
+        println!("{}", url);
+        return Ok(());
+    }
+
+    println!("{} Opening {} in the {} console...", "▸".green().bold(), entry.name.cyan(), entry.provider);
+    if let Err(e) = open::that(&url) {
+        println!("{} Could not launch a browser ({}); open it manually:", "⚠".yellow(), e);
+        println!("  {}", url);
+    }
+    Ok(())
+}
+
+fn top_xnodes(options: &TopOptions) -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    runtime.block_on(async {
+        let mut system = MonitoringSystem::new(None).await?;
+        if let Err(e) = crate::monitoring::commands::load_xnode_tags(&mut system) {
+            log::warn!("Tag silences unavailable: {:#}", e);
+        }
+        watch_top(&mut system, options).await
+    })
+}
+
+fn inventory_entry(inventory: &XNodeInventory, xnode_id: &str) -> Result<XNodeEntry> {
+    inventory
+        .get_xnode(xnode_id)
+        .cloned()
+        .ok_or_else(|| CliError::not_found(format!("xNode '{}' not found in inventory", xnode_id)).into())
+}
+
+fn run_bulk(selector: &XnodeSelector, action: BulkAction) -> Result<()> {
+    let mut inventory = XNodeInventory::new(None)?;
+    let manager = ProviderManager::new(None)?;
+
+    let targets: Vec<XNodeEntry> = match &selector.xnode_id {
+        Some(id) => vec![inventory_entry(&inventory, id)?],
+        None => inventory
+            .select(&selector.tags, selector.provider.as_deref(), selector.status.as_deref())
+            .into_iter()
+            .cloned()
+            .collect(),
+    };
+    let bulk = selector.xnode_id.is_none();
+
+    if targets.is_empty() {
+        return Err(CliError::not_found("No xNodes match the given selectors").into());
+    }
+
+    if bulk {
+        println!();
+        println!("{} {} {} xNode(s):", "→".cyan(), "Will".white(), action.verb());
+        for entry in &targets {
+            println!("    {} {} ({}, {})", "•".cyan(), entry.id, entry.provider, entry.status);
+        }
+        println!();
+    }
+
+    if action.is_destroy() && bulk && !selector.yes {
+        return Err(CliError::usage("Bulk destroy requires --yes").into());
+    }
+
+    let needs_confirmation = bulk || action.is_destroy();
+    if needs_confirmation && !selector.yes {
+        let prompt = format!("{} {} xNode(s)?", capitalize(action.verb()), targets.len());
+        if !Confirm::new().with_prompt(prompt).default(false).interact()? {
+            println!("{}", "Aborted".yellow());
+            return Ok(());
+        }
+    }
+
+    // Stop between xNodes on Ctrl-C so each inventory update completes
+    let interrupt = Interrupt::install()?;
+    let mut failures = Vec::new();
+    for (done, entry) in targets.iter().enumerate() {
+        if interrupt.is_raised() {
+            return Err(CliError::interrupted(format!(
+                "Interrupted after {} of {} xNodes ({} failed); inventory is up to date",
+                done,
+                targets.len(),
+                failures.len()
+            ))
+            .into());
+        }
+        if let Err(e) = apply_action(&manager, &mut inventory, entry, &action) {
+            println!("{} {}: {:#}", "✗".red().bold(), entry.id, e);
+            failures.push((entry.id.clone(), e));
+        }
+    }
+
+    if !bulk {
+        return match failures.pop() {
+            Some((_, e)) => Err(e),
+            None => Ok(()),
+        };
+    }
+
+    println!();
+    println!("{} {} succeeded, {} failed",
+        "▸".green().bold(),
+        targets.len() - failures.len(),
+        failures.len()
+    );
+    if !failures.is_empty() {
+        anyhow::bail!("Failed to {} {} of {} xNodes", action.verb(), failures.len(), targets.len());
+    }
+    Ok(())
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn apply_action(
+    manager: &ProviderManager,
+    inventory: &mut XNodeInventory,
+    entry: &XNodeEntry,
+    action: &BulkAction,
+) -> Result<()> {
+    if let BulkAction::Destroy { reason } = action {
+        println!("{} Destroying {} ({})...", "▸".green().bold(), entry.name.cyan(), entry.provider);
+        destroy_xnode(manager, inventory, &entry.id, reason.as_deref())?;
+
+        println!("{} {} destroyed", "✓".green().bold(), entry.name.cyan());
+        return Ok(());
+    }
+
+    // Check capabilities before calling out so bare-metal providers get a
+    // clear answer instead of an API error
+    let operation = match action {
+        BulkAction::Start => ProviderOperation::Start,
+        _ => ProviderOperation::Stop,
+    };
+    let provider = manager.provider_supporting(&entry.provider, operation)?;
+
+    let (verb, status) = match action {
+        BulkAction::Start => ("Starting", "running"),
+        BulkAction::Restart => ("Restarting", "running"),
+        _ => ("Stopping", "stopped"),
+    };
+    println!("{} {} {} ({})...", "▸".green().bold(), verb, entry.name.cyan(), entry.provider);
+
+    let changed = match action {
+        BulkAction::Start => provider.start_instance(&entry.id)?,
+        BulkAction::Restart => provider.stop_instance(&entry.id)? && provider.start_instance(&entry.id)?,
+        _ => provider.stop_instance(&entry.id)?,
+    };
+    if !changed {
+        anyhow::bail!("Provider {} did not {} {}", entry.provider, action.verb(), entry.id);
+    }
+
+    inventory.update_xnode(&entry.id, XNodeUpdate {
+        status: Some(status.to_string()),
+        ..Default::default()
+    })?;
+
+    println!("{} {} is now {}", "✓".green().bold(), entry.name.cyan(), status);
+    Ok(())
+}
+
+fn adopt_xnode(provider: &str, instance_id: &str, cost: Option<f64>) -> Result<()> {
+    let mut inventory = XNodeInventory::new(None)?;
+    let manager = ProviderManager::new(None)?;
+
+    println!("{} Looking up {} on {}...", "▸".green().bold(), instance_id.cyan(), provider);
+    let entry = adopt_instance(&manager, &mut inventory, provider, instance_id, cost)?;
+
+    println!("{} Adopted {} ({})", "✓".green().bold(), entry.name.cyan(), entry.id);
+    println!("  {} {} • {} • {}",
+        "Instance:".white().bold(),
+        entry.template,
+        entry.region.as_deref().unwrap_or("unknown region"),
+        entry.status.yellow()
+    );
+    println!("  {} ${:.3}/hr (${:.2}/mo)",
+        "Cost:".white().bold(),
+        entry.cost_hourly,
+        entry.cost_hourly * 730.0
+    );
+    Ok(())
+}
+
+/// `requested`, or by default every provider with credentials or inventory entries
+fn providers_to_query(manager: &ProviderManager, inventory: &XNodeInventory, requested: Vec<String>) -> Vec<String> {
+    if !requested.is_empty() {
+        return requested;
+    }
+    manager
+        .list_providers()
+        .into_iter()
+        .filter(|name| manager.has_credentials(name) || !inventory.list_by_provider(name).is_empty())
+        .collect()
+}
+
+fn sync_xnodes(providers: Vec<String>, parallel: usize) -> Result<()> {
+    let mut inventory = XNodeInventory::new(None)?;
+    let manager = ProviderManager::new(None)?;
+
+    let providers = providers_to_query(&manager, &inventory, providers);
+    if providers.is_empty() {
+        println!("{} No providers with credentials or inventory entries to sync", "ℹ".cyan());
+        return Ok(());
+    }
+
+    println!("{} Querying {} provider(s), up to {} at a time...", "▸".green().bold(), providers.len(), parallel);
+    let report = sync_inventory(&manager, &mut inventory, &providers, parallel)?;
+
+    for (id, change) in &report.updated {
+        println!("  {} {}: {}", "↻".cyan(), id, change);
+    }
+    for id in &report.missing {
+        println!("  {} {}: not reported by its provider", "⚠".yellow(), id);
+    }
+    for instance in &report.untracked {
+        println!("  {} {} on {} is not in the inventory (capsule openmesh xnode adopt {} {})",
+            "+".cyan(), instance.id, instance.provider, instance.provider, instance.id);
+    }
+    for (provider, e) in &report.errors {
+        println!("  {} {}: {:#}", "✗".red().bold(), provider, e);
+    }
+    for provider in &report.unlisted {
+        println!("  {} {}: listing instances is not supported yet; not checked", "⚠".yellow(), provider);
+    }
+
+    println!();
+    println!("{} {} of {} providers synced • {} updated • {} missing • {} untracked",
+        "▸".green().bold(),
+        report.synced.len(),
+        providers.len(),
+        report.updated.len(),
+        report.missing.len(),
+        report.untracked.len()
+    );
+    if !report.errors.is_empty() {
+        anyhow::bail!("Failed to query {} of {} providers", report.errors.len(), providers.len());
+    }
+    Ok(())
+}
+
+fn prune_xnodes(options: PruneOptions) -> Result<()> {
+    let mut inventory = XNodeInventory::new(None)?;
+
+    let (targets, reason) = match &options.status {
+        Some(status) => {
+            let targets: Vec<XNodeEntry> = inventory.list_by_status(status).into_iter().cloned().collect();
+            (targets, format!("pruned: status {}", status))
+        }
+        None => {
+            let manager = ProviderManager::new(None)?;
+            let providers = providers_to_query(&manager, &inventory, options.providers.clone());
+            println!("{} Checking {} provider(s) for xNodes that no longer exist...", "▸".green().bold(), providers.len());
+
+            let report = find_ghosts(&manager, &inventory, &providers, options.parallel)?;
+            for (provider, e) in &report.errors {
+                println!("  {} {}: {:#} (its xNodes are kept)", "⚠".yellow(), provider, e);
+            }
+            for provider in &report.unchecked {
+                println!("  {} {}: listing instances is not supported yet; not checked (its xNodes are kept)", "⚠".yellow(), provider);
+            }
+            (report.ghosts, "pruned: not reported by provider".to_string())
+        }
+    };
+
+    if targets.is_empty() {
+        println!("{} Nothing to prune", "✓".green().bold());
+        return Ok(());
+    }
+
+    let verb = if options.mark { "Mark as terminated" } else { "Remove" };
+    println!();
+    println!("{} {} {} xNode(s):", "→".cyan(), verb, targets.len());
+    for entry in &targets {
+        println!("    {} {} ({}, {}, {})", "•".cyan(), entry.id, entry.name, entry.provider, entry.status);
+    }
+    println!();
+
+    if options.dry_run {
+        println!("{} Dry run: inventory left unchanged", "ℹ".cyan());
+        return Ok(());
+    }
+    if !options.yes {
+        let prompt = format!("{} {} xNode(s)?", verb, targets.len());
+        if !Confirm::new().with_prompt(prompt).default(false).interact()? {
+            println!("{}", "Aborted".yellow());
+            return Ok(());
+        }
+    }
+
+    for entry in &targets {
+        if options.mark {
+            inventory.update_xnode(&entry.id, XNodeUpdate {
+                status: Some("terminated".to_string()),
+                ..Default::default()
+            })?;
+        } else {
+            inventory.remove_xnode(&entry.id, Some(&reason))?;
+        }
+    }
+    println!("{} Pruned {} xNode(s)", "✓".green().bold(), targets.len());
+    Ok(())
+}
+
+fn validate_inventory(json: bool) -> Result<()> {
+    let inventory = XNodeInventory::new(None)?;
+    let manager = ProviderManager::new(None)?;
+    let templates: std::collections::HashSet<String> =
+        manager.get_all_templates().into_iter().map(|t| t.id).collect();
+
+    let issues = inventory.validate(|template| templates.contains(template));
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&issues)?);
+    } else if issues.is_empty() {
+        println!("{} Inventory is consistent ({} xNodes checked)", "✓".green().bold(), inventory.list_all().len());
+    } else {
+        for issue in &issues {
+            println!("  {} {} [{}]: {}", "✗".red().bold(), issue.xnode_id, issue.kind, issue.message);
+            println!("      {} {}", "fix:".white().dimmed(), issue.fix.cyan());
+        }
+        println!();
+    }
+
+    if !issues.is_empty() {
+        anyhow::bail!("Found {} inventory issue(s)", issues.len());
+    }
+    Ok(())
+}
+
+fn resize_xnode(xnode_id: &str, template_id: &str) -> Result<()> {
+    let mut inventory = XNodeInventory::new(None)?;
+    let entry = inventory_entry(&inventory, xnode_id)?;
+
+    let manager = ProviderManager::new(None)?;
+    let provider = manager.provider_supporting(&entry.provider, ProviderOperation::Resize)?;
+
+    let template = provider.get_template(template_id).ok_or_else(|| {
+        CliError::not_found(format!("Template '{}' not found for provider {}", template_id, entry.provider))
+    })?;
+    if template.id == entry.template {
+        return Err(CliError::usage(format!("{} already uses template {}", xnode_id, template_id)).into());
+    }
+
+    println!("{} Resizing {} from {} to {}...",
+        "▸".green().bold(),
+        entry.name.cyan(),
+        entry.template,
+        template.id.cyan()
+    );
+
+    let instance = provider.resize(xnode_id, template_id)?;
+
+    inventory.update_xnode(xnode_id, XNodeUpdate {
+        template: Some(instance.template.clone()),
+        cost_hourly: Some(instance.cost_hourly),
+        ..Default::default()
+    })?;
+
+    println!("{} {} resized", "✓".green().bold(), entry.name.cyan());
+    println!("  {} ${:.3}/hr → ${:.3}/hr",
+        "Cost:".white().bold(),
+        entry.cost_hourly,
+        instance.cost_hourly
+    );
+    Ok(())
+}
+
+/// Resolve `options` against the catalog into a move for `entry`. Unset
+/// fields keep the entry's values while the provider stays the same.
+pub fn plan_move(manager: &ProviderManager, entry: &XNodeEntry, options: &MoveOptions) -> Result<XNodeMove> {
+    let provider_name = options.provider.clone().unwrap_or_else(|| entry.provider.clone());
+    let provider = manager
+        .get_provider(&provider_name)
+        .ok_or_else(|| CliError::not_found(format!("Provider {} not found", provider_name)))?;
+    let same_provider = provider_name == entry.provider;
+
+    let template = match (&options.template, same_provider) {
+        (Some(template), _) => template.clone(),
+        (None, true) => entry.template.clone(),
+        (None, false) => {
+            return Err(CliError::usage(format!(
+                "--template is required when moving {} from {} to {}",
+                entry.id, entry.provider, provider_name
+            ))
+            .into())
+        }
+    };
+
+    let catalog_price = provider.get_template(&template).map(|t| t.price_hourly);
+    let cost_hourly = match (options.cost, catalog_price) {
+        (Some(cost), _) => cost,
+        (None, _) if same_provider && template == entry.template => entry.cost_hourly,
+        (None, Some(price)) => price,
+        (None, None) => {
+            return Err(CliError::not_found(format!(
+                "Template '{}' not found for provider {}; pass --cost to record it anyway",
+                template, provider_name
+            ))
+            .into())
+        }
+    };
+
+    let region = match &options.region {
+        Some(region) => Some(manager.resolve_region(&provider_name, region)?),
+        None => same_provider.then(|| entry.region.clone()).flatten(),
+    };
+
+    let target = XNodeMove {
+        provider: provider_name,
+        template,
+        region,
+        cost_hourly,
+        ip_address: options.ip.clone(),
+        new_id: options.new_id.clone(),
+    };
+
+    let unchanged = target.provider == entry.provider
+        && target.template == entry.template
+        && target.region == entry.region
+        && target.cost_hourly == entry.cost_hourly
+        && target.ip_address.as_ref().is_none_or(|ip| *ip == entry.ip_address)
+        && target.new_id.is_none();
+    if unchanged {
+        return Err(CliError::usage(format!("Nothing to move; {} already matches", entry.id)).into());
+    }
+    Ok(target)
+}
+
+fn move_xnode(options: MoveOptions) -> Result<()> {
+    let mut inventory = XNodeInventory::new(None)?;
+    let entry = inventory_entry(&inventory, &options.xnode_id)?;
+    let manager = ProviderManager::new(None)?;
+
+    let target = plan_move(&manager, &entry, &options)?;
+    let moved = inventory.move_xnode(&entry.id, target)?;
+
+    let from = format!("{}/{}", entry.provider, entry.template);
+    let to = format!("{}/{}", moved.provider, moved.template);
+    if moved.id == entry.id {
+        println!("{} Moved {} from {} to {}", "✓".green().bold(), entry.name.cyan(), from, to.cyan());
+    } else {
+        println!(
+            "{} {} ({}) on {} succeeded by {} on {}",
+            "✓".green().bold(),
+            entry.name.cyan(),
+            entry.id,
+            from,
+            moved.id.cyan(),
+            to.cyan()
+        );
+    }
+    println!("  {} ${:.3}/hr → ${:.3}/hr",
+        "Cost:".white().bold(),
+        entry.cost_hourly,
+        moved.cost_hourly
+    );
+    println!("  {} previous deployment closed; see {}",
+        "History:".white().bold(),
+        format!("capsule openmesh xnode history --xnode-id {}", entry.id).cyan()
+    );
+    Ok(())
+}
+
+fn snapshot_xnode(xnode_id: &str, name: Option<String>) -> Result<()> {
+    let mut inventory = XNodeInventory::new(None)?;
+    let entry = inventory_entry(&inventory, xnode_id)?;
+
+    let manager = ProviderManager::new(None)?;
+    let provider = manager.provider_supporting(&entry.provider, ProviderOperation::Snapshot)?;
+
+    let created_at = chrono::Utc::now();
+    let name = name.unwrap_or_else(|| format!("{}-{}", entry.name, created_at.format("%Y%m%d-%H%M%S")));
+
+    println!("{} Snapshotting {} as {}...", "▸".green().bold(), entry.name.cyan(), name.cyan());
+    let snapshot_id = provider.snapshot_instance(xnode_id, &name)?;
+
+    inventory.record_snapshot(xnode_id, SnapshotRecord {
+        id: snapshot_id.clone(),
+        name,
+        created_at,
+    })?;
+
+    println!("{} Snapshot created: {}", "✓".green().bold(), snapshot_id.cyan());
+    println!("  {} {} snapshot(s) recorded for {}",
+        "Inventory:".white().bold(),
+        entry.snapshots().len() + 1,
+        xnode_id
+    );
+    Ok(())
+}
+
+/// Deploy through `manager` and record the new instance in `inventory`,
+/// keeping provider metadata for monitoring. `tags` are recorded after the
+/// provider's `default_tags`.
+pub fn deploy_and_record(
+    manager: &ProviderManager,
+    inventory: &mut XNodeInventory,
+    provider: &str,
+    template_id: &str,
+    config: &DeployConfig,
+    tags: &[String],
+) -> Result<crate::providers::Instance> {
+    let instance = manager.deploy_to_provider(provider, template_id, config)?;
+    inventory.add_xnode(
+        &XNode::from_instance(&instance),
+        instance.provider.clone(),
+        instance.template.clone(),
+        instance.cost_hourly,
+        manager.deploy_tags(provider, tags),
+    )?;
+    Ok(instance)
+}
+
+/// Record an existing provider instance in `inventory`. The hourly cost is
+/// `cost` if given, else the price of the instance's template, else what
+/// the provider reported.
+pub fn adopt_instance(
+    manager: &ProviderManager,
+    inventory: &mut XNodeInventory,
+    provider_name: &str,
+    instance_id: &str,
+    cost: Option<f64>,
+) -> Result<XNodeEntry> {
+    if inventory.get_xnode(instance_id).is_some() {
+        return Err(CliError::usage(format!("{} is already in the inventory", instance_id)).into());
+    }
+
+    let provider = manager.get_provider(provider_name)
+        .ok_or_else(|| CliError::not_found(format!("Provider {} not found", provider_name)))?;
+    let instance = provider.get_instance(instance_id)?;
+
+    let cost_hourly = cost
+        .or_else(|| provider.get_template(&instance.template).map(|t| t.price_hourly))
+        .or(Some(instance.cost_hourly).filter(|c| *c > 0.0))
+        .ok_or_else(|| CliError::usage(format!(
+            "Can't infer the cost of {} (template '{}' is unknown); pass --cost",
+            instance_id, instance.template
+        )))?;
+
+    inventory.add_xnode(
+        &XNode::from_instance(&instance),
+        provider_name.to_string(),
+        instance.template.clone(),
+        cost_hourly,
+        Vec::new(),
+    )?;
+    inventory_entry(inventory, &instance.id)
+}
+
+/// Delete an xNode at its provider, then drop it from `inventory`, noting
+/// `reason` on its deployment record
+pub fn destroy_xnode(
+    manager: &ProviderManager,
+    inventory: &mut XNodeInventory,
+    xnode_id: &str,
+    reason: Option<&str>,
+) -> Result<()> {
+    let entry = inventory_entry(inventory, xnode_id)?;
+    let provider = manager.get_provider(&entry.provider)
+        .ok_or_else(|| CliError::not_found(format!("Provider {} not found", entry.provider)))?;
+
+    if !provider.delete_instance(&entry.id)? {
+        anyhow::bail!("Provider {} did not delete {}", entry.provider, entry.id);
+    }
+    inventory.remove_xnode(&entry.id, reason)?;
+    Ok(())
+}
+
+/// Providers that couldn't be queried, with why
+pub type ProviderErrors = Vec<(String, anyhow::Error)>;
+
+/// Outcome of reconciling the inventory with provider instance lists
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    /// Providers whose instances were listed
+    pub synced: Vec<String>,
+    /// xNodes whose status or address changed, with what changed
+    pub updated: Vec<(String, String)>,
+    /// xNodes in the inventory that their provider no longer reports
+    pub missing: Vec<String>,
+    /// Provider instances that aren't in the inventory
+    pub untracked: Vec<crate::providers::Instance>,
+    /// Providers that couldn't be listed
+    pub errors: ProviderErrors,
+    /// Providers whose listing isn't implemented yet; their xNodes are left
+    /// alone
+    pub unlisted: Vec<String>,
+}
+
+/// Outcome of looking for inventory entries their provider no longer has
+#[derive(Debug, Default)]
+pub struct GhostReport {
+    /// Entries their provider no longer lists
+    pub ghosts: Vec<XNodeEntry>,
+    /// Providers that couldn't be listed
+    pub errors: ProviderErrors,
+    /// Providers whose listing isn't implemented yet, so weren't checked
+    pub unchecked: Vec<String>,
+}
+
+/// Split `providers` into those whose `list_instances` reports real
+/// instances and those where it is still a stub. Unknown providers count as
+/// listable so that listing them reports the lookup failure.
+fn split_by_listing(manager: &ProviderManager, providers: &[String]) -> (Vec<String>, Vec<String>) {
+    providers.iter().cloned().partition(|name| match manager.get_provider(name) {
+        Some(provider) => provider.capabilities().live_listing,
+        None => true,
+    })
+}
+
+/// Run `list_instances` on each of `providers`, at most `parallel` at once.
+/// Results come back in the order of `providers`. Providers without
+/// credentials fail rather than report an empty (and misleading) list.
+pub fn list_instances_parallel(
+    manager: &ProviderManager,
+    providers: &[String],
+    parallel: usize,
+) -> Vec<(String, Result<Vec<crate::providers::Instance>>)> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(providers.len()));
+
+    std::thread::scope(|scope| {
+        for _ in 0..parallel.clamp(1, providers.len().max(1)) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(name) = providers.get(i) else { break };
+                let listed = match manager.get_provider(name) {
+                    Some(provider) if !provider.is_configured() => Err(CliError::usage(format!(
+                        "No API key configured for {}; run `capsule openmesh provider configure {} --api-key <key>`",
+                        name, name
+                    ))
+                    .into()),
+                    Some(provider) => provider.list_instances(),
+                    None => Err(CliError::not_found(format!("Provider {} not found", name)).into()),
+                };
+                results.lock().unwrap().push((i, name.clone(), listed));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(i, _, _)| *i);
+    results.into_iter().map(|(_, name, listed)| (name, listed)).collect()
+}
+
+/// List instances on `providers` (`parallel` at a time) and bring the
+/// inventory's status and IP addresses up to date, marking every node a
+/// provider still reports as seen. Nodes of providers that fail to list, or
+/// can't be listed yet, are left alone; both are returned in the report.
+pub fn sync_inventory(
+    manager: &ProviderManager,
+    inventory: &mut XNodeInventory,
+    providers: &[String],
+    parallel: usize,
+) -> Result<SyncReport> {
+    if parallel == 0 {
+        return Err(CliError::usage("--parallel must be at least 1").into());
+    }
+
+    let (listable, unlisted) = split_by_listing(manager, providers);
+    let listed = list_instances_parallel(manager, &listable, parallel);
+
+    // Apply every provider's changes with one save; a failure part way
+    // leaves the inventory as it was
+    inventory.transaction(|inventory| {
+        let mut report = SyncReport { unlisted: unlisted.clone(), ..Default::default() };
+        for (provider, listed) in listed {
+            let instances = match listed {
+                Ok(instances) => instances,
+                Err(e) => {
+                    report.errors.push((provider, e));
+                    continue;
+                }
+            };
+
+            let known: Vec<XNodeEntry> = inventory.list_by_provider(&provider).into_iter().cloned().collect();
+            let mut seen = Vec::new();
+            for entry in &known {
+                let Some(instance) = instances.iter().find(|i| i.id == entry.id) else {
+                    report.missing.push(entry.id.clone());
+                    continue;
+                };
+                seen.push(entry.id.clone());
+
+                let mut update = XNodeUpdate::default();
+                let mut changes = Vec::new();
+                if instance.status != entry.status {
+                    changes.push(format!("status {} → {}", entry.status, instance.status));
+                    update.status = Some(instance.status.clone());
+                }
+                if !instance.ip_address.is_empty() && instance.ip_address != entry.ip_address {
+                    changes.push(format!("IP {} → {}", entry.ip_address, instance.ip_address));
+                    update.ip_address = Some(instance.ip_address.clone());
+                }
+                if !changes.is_empty() {
+                    inventory.update_xnode(&entry.id, update)?;
+                    report.updated.push((entry.id.clone(), changes.join(", ")));
+                }
+            }
+            inventory.mark_seen(&seen, Utc::now())?;
+
+            report.untracked.extend(
+                instances
+                    .into_iter()
+                    .filter(|i| inventory.get_xnode(&i.id).is_none()),
+            );
+            report.synced.push(provider);
+        }
+        Ok(report)
+    })
+}
+
+/// Inventory entries of `providers` that the provider no longer lists.
+/// Providers that fail to list are returned with their error, and those
+/// that can't be listed yet as unchecked; none of their entries count as
+/// ghosts.
+pub fn find_ghosts(
+    manager: &ProviderManager,
+    inventory: &XNodeInventory,
+    providers: &[String],
+    parallel: usize,
+) -> Result<GhostReport> {
+    if parallel == 0 {
+        return Err(CliError::usage("--parallel must be at least 1").into());
+    }
+
+    let (listable, unchecked) = split_by_listing(manager, providers);
+    let mut report = GhostReport { unchecked, ..Default::default() };
+    for (provider, listed) in list_instances_parallel(manager, &listable, parallel) {
+        match listed {
+            Ok(instances) => report.ghosts.extend(
+                inventory
+                    .list_by_provider(&provider)
+                    .into_iter()
+                    .filter(|entry| !instances.iter().any(|i| i.id == entry.id))
+                    .cloned(),
+            ),
+            Err(e) => report.errors.push((provider, e)),
+        }
+    }
+    Ok(report)
+}
+
+/// Parse `KEY=VALUE` pairs for `DeployConfig::extra`; values that parse as
+/// JSON (numbers, booleans, arrays) keep their type
+fn parse_extra(pairs: &[String]) -> Result<HashMap<String, serde_json::Value>> {
+    pairs
+        .iter()
+        .map(|pair| {
+            let (key, value) = pair
+                .split_once('=')
+                .filter(|(k, _)| !k.is_empty())
+                .ok_or_else(|| CliError::usage(format!("Invalid --extra '{}', expected KEY=VALUE", pair)))?;
+            let value = serde_json::from_str(value)
+                .unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+            Ok((key.to_string(), value))
+        })
+        .collect()
+}
+
+/// `settings` with gaps filled from the named deploy profile
+fn with_deploy_profile(profile: &str, settings: DeploySettings) -> Result<DeploySettings> {
+    let profiles = DeployProfiles::load(&DeployProfiles::default_path())?;
+    let saved = profiles.get(profile).ok_or_else(|| {
+        CliError::not_found(format!(
+            "Deploy profile '{}' not found. See: capsule openmesh deploy-profile list",
+            profile
+        ))
+    })?;
+    println!("{} Using deploy profile {}", "→".cyan(), profile.cyan());
+    Ok(settings.or(saved))
+}
+
+fn handle_deploy_profile_command(command: DeployProfileCommands) -> Result<()> {
+    let mut profiles = DeployProfiles::load(&DeployProfiles::default_path())?;
+
+    match command {
+        DeployProfileCommands::Save { name, settings } => {
+            if settings.is_empty() {
+                return Err(CliError::usage("Nothing to save; pass deploy flags such as --provider and --template").into());
+            }
+            // Catch malformed extras now rather than at deploy time
+            parse_extra(&settings.extra)?;
+            let replaced = profiles.insert(&name, settings);
+            profiles.save()?;
+            let verb = if replaced { "Updated" } else { "Saved" };
+            println!("{} {} deploy profile {}", "✓".green(), verb, name.cyan());
+            println!("  Deploy with: {}", format!("capsule openmesh xnode deploy --profile {}", name).cyan());
+        }
+        DeployProfileCommands::List => {
+            if profiles.profiles.is_empty() {
+                println!("{} No deploy profiles saved", "ℹ".cyan());
+                println!("  Save one with: {}", "capsule openmesh deploy-profile save <name> --provider <p> --template <t>".cyan());
+                return Ok(());
+            }
+
+            let mut table = Table::new();
+            table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+            table.set_titles(Row::new(
+                ["Profile", "Provider", "Template", "Region", "Sizing", "Tags", "Extra"]
+                    .iter()
+                    .map(|title| Cell::new(title).style_spec("bFc"))
+                    .collect(),
+            ));
+            let or_dash = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+            for (name, settings) in &profiles.profiles {
+                let sizing: Vec<String> = [
+                    settings.budget.map(|b| format!("≤${:.3}/hr", b)),
+                    settings.min_cpu.map(|c| format!("≥{} CPU", c)),
+                    settings.min_memory.map(|m| format!("≥{} GB", m)),
+                ]
+                .into_iter()
+                .flatten()
+                .collect();
+                table.add_row(Row::new(vec![
+                    Cell::new(name).style_spec("Fc"),
+                    Cell::new(&or_dash(&settings.provider)),
+                    Cell::new(&or_dash(&settings.template)),
+                    Cell::new(&or_dash(&settings.region)),
+                    Cell::new(&if sizing.is_empty() { "-".to_string() } else { sizing.join(", ") }),
+                    Cell::new(&settings.tags.join(", ")),
+                    Cell::new(&settings.extra.join(" ")),
+                ]));
+            }
+            table.printstd();
+        }
+        DeployProfileCommands::Delete { name } => {
+            if profiles.remove(&name).is_none() {
+                return Err(CliError::not_found(format!("Deploy profile '{}' not found", name)).into());
+            }
+            profiles.save()?;
+            println!("{} Deleted deploy profile {}", "✓".green(), name.cyan());
+        }
+    }
+    Ok(())
+}
+
+/// Ask before deploying `template`; above `expensive_above` per hour the
+/// template ID must be typed back. Refuses outright without a terminal.
+fn confirm_deploy_cost(template: &ProviderTemplate, expensive_above: f64) -> Result<bool> {
+    if !std::io::stdin().is_terminal() {
+        return Err(CliError::usage("Deploying costs money; pass --yes to deploy without a terminal to confirm").into());
+    }
+
+    let prompt = format!(
+        "Deploy {} at ${:.3}/hr (${:.2}/mo, ${:.2}/yr)?",
+        template.id,
+        template.price_hourly,
+        template.price_monthly,
+        template.price_annual()
+    );
+    if !Confirm::new().with_prompt(prompt).default(false).interact()? {
+        return Ok(false);
+    }
+
+    if template.price_hourly > expensive_above {
+        println!(
+            "{} {} costs more than ${:.2}/hr",
+            "⚠".yellow().bold(),
+            template.id.cyan(),
+            expensive_above
+        );
+        let typed: String = Input::new()
+            .with_prompt(format!("Type {} to confirm", template.id))
+            .allow_empty(true)
+            .interact_text()?;
+        return Ok(typed.trim() == template.id);
+    }
+    Ok(true)
+}
+
+/// `cost_gate` is the `--expensive-above` threshold, or `None` with `--yes`
 fn deploy_instance(
-    provider: Option<String>,
-    template: Option<String>,
+    settings: DeploySettings,
     name: Option<String>,
-    region: Option<String>,
-    budget: Option<f64>,
-    min_cpu: Option<u32>,
-    min_memory: Option<u32>,
+    follow: Option<Duration>,
+    cost_gate: Option<f64>,
 ) -> Result<()> {
+    let extra = parse_extra(&settings.extra)?;
+    let DeploySettings { provider, template, region, budget, min_cpu, min_memory, tags, .. } = settings;
     let mut manager = ProviderManager::new(None)?;
 
     // Interactive provider selection if not specified
@@ -577,6 +2561,17 @@ fn deploy_instance(
         selected
     };
 
+    if follow.is_some() {
+        let live = manager.get_provider(&selected_provider).is_some_and(|p| p.capabilities().live_deploy);
+        if !live {
+            return Err(CliError::usage(format!(
+                "--follow is not supported for {}: its deploys can't be polled yet",
+                selected_provider
+            ))
+            .into());
+        }
+    }
+
     // Smart template selection
     let (selected_template, template_obj) = if let Some(t) = template {
         let provider_obj = manager.get_provider(&selected_provider)
@@ -615,7 +2610,7 @@ fn deploy_instance(
 
     // Get default region for provider
     let selected_region = if let Some(r) = region {
-        r
+        manager.resolve_region(&selected_provider, &r)?
     } else {
         let provider_obj = manager.get_provider(&selected_provider)
             .ok_or_else(|| anyhow::anyhow!("Provider not found"))?;
@@ -627,34 +2622,59 @@ fn deploy_instance(
         region: selected_region,
         os: Some("ubuntu-20.04".to_string()),
         ssh_keys: None,
-        extra: HashMap::new(),
+        extra,
     };
 
     // ASCII art header
-    println!();
-    println!("{}", "╔═══════════════════════════════════════════════════════════════╗".cyan());
-    println!("{}", "║              🚀  DEPLOYING XNODE INSTANCE  🚀                 ║".cyan().bold());
-    println!("{}", "╚═══════════════════════════════════════════════════════════════╝".cyan());
-    println!();
+    if !is_quiet() {
+        println!();
+        println!("{}", "╔═══════════════════════════════════════════════════════════════╗".cyan());
+        println!("{}", "║              🚀  DEPLOYING XNODE INSTANCE  🚀                 ║".cyan().bold());
+        println!("{}", "╚═══════════════════════════════════════════════════════════════╝".cyan());
+        println!();
+    }
     println!("  {} {}", "Provider:".white().bold(), selected_provider.cyan());
     println!("  {} {}", "Template:".white().bold(), template_obj.name.cyan());
     println!("  {} {}", "Name:".white().bold(), config.name.cyan());
-    println!("  {} {}", "Region:".white().bold(), config.region.cyan());
+    let region_shown = match manager.get_provider(&selected_provider) {
+        Some(provider_obj) => region_label(provider_obj, &config.region),
+        None => config.region.clone(),
+    };
+    println!("  {} {}", "Region:".white().bold(), region_shown.cyan());
+    let tags = manager.deploy_tags(&selected_provider, &tags);
+    if !tags.is_empty() {
+        println!("  {} {}", "Tags:".white().bold(), tags.join(", ").cyan());
+    }
     println!("  {} {} cores • {} GB RAM • {} GB storage",
         "Specs:".white().bold(),
         template_obj.cpu,
         template_obj.memory_gb,
         template_obj.storage_gb
     );
-    println!("  {} ${:.3}/hr • ${:.2}/month",
+    println!("  {} ${:.3}/hr • ${:.2}/month • ${:.2}/year",
         "Cost:".white().bold(),
         template_obj.price_hourly,
-        template_obj.price_monthly
+        template_obj.price_monthly,
+        template_obj.price_annual()
     );
     println!();
+    if let Some(expensive_above) = cost_gate {
+        if !confirm_deploy_cost(&template_obj, expensive_above)? {
+            println!("{}", "Aborted".yellow());
+            return Ok(());
+        }
+    }
     println!("{} Provisioning instance...", "▸".green().bold());
 
-    let instance = manager.deploy_to_provider(&selected_provider, &selected_template, &config)?;
+    let mut inventory = XNodeInventory::new(None)?;
+    let instance = deploy_and_record(
+        &manager,
+        &mut inventory,
+        &selected_provider,
+        &selected_template,
+        &config,
+        &tags,
+    )?;
 
     println!();
     println!("{}", "─────────────────────────────────────────────────────────────────".green());
@@ -667,9 +2687,132 @@ fn deploy_instance(
         instance.cost_hourly,
         instance.cost_hourly * 730.0
     );
-    println!();
-    println!("{} Use {} to view all instances", "💡".cyan(), "capsule xnode list".cyan().bold());
+    if !is_quiet() {
+        println!();
+        println!("{} Use {} to view all instances", "💡".cyan(), "capsule xnode list".cyan().bold());
+        println!();
+    }
+
+    if let Some(timeout) = follow {
+        follow_deployment(&manager, &mut inventory, &instance, timeout)?;
+    }
+
+    Ok(())
+}
+
+/// Poll a fresh deployment until it is running, recording the final state
+fn follow_deployment(
+    manager: &ProviderManager,
+    inventory: &mut XNodeInventory,
+    instance: &crate::providers::Instance,
+    timeout: Duration,
+) -> Result<()> {
+    let provider = manager.get_provider(&instance.provider)
+        .ok_or_else(|| CliError::not_found(format!("Provider {} not found", instance.provider)))?;
+
+    println!("{} Following {} (timeout {}s)...", "▸".green().bold(), instance.id.cyan(), timeout.as_secs());
+    let running = wait_for_running(provider, &instance.id, timeout, FOLLOW_POLL_INTERVAL, |status, elapsed| {
+        println!("  {:>5}s  {}", elapsed.as_secs(), status.yellow());
+    })?;
+
+    match running {
+        Some(running) => {
+            inventory.update_xnode(&instance.id, XNodeUpdate {
+                status: Some(running.status.clone()),
+                ip_address: Some(running.ip_address.clone()).filter(|ip| !ip.is_empty()),
+                ..Default::default()
+            })?;
+            println!("{} {} is running at {}", "✓".green().bold(), running.id.cyan(), running.ip_address.cyan());
+        }
+        None => {
+            println!(
+                "{} {} is still provisioning after {}s; it stays in inventory as {}. Check it later with {}",
+                "⚠".yellow().bold(),
+                instance.id.cyan(),
+                timeout.as_secs(),
+                instance.status.yellow(),
+                format!("capsule openmesh xnode show {}", instance.id).cyan().bold()
+            );
+        }
+    }
     println!();
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xnode::XNode;
+
+    #[test]
+    fn test_journal_command() {
+        assert_eq!(
+            journal_command(50, Some("nginx.service"), true).unwrap(),
+            "command -v journalctl >/dev/null 2>&1 || exit 127; journalctl --no-pager -n 50 -u nginx.service -f"
+        );
+        assert!(journal_command(100, None, false).unwrap().ends_with("journalctl --no-pager -n 100"));
+        assert!(journal_command(100, Some("nginx; rm -rf /"), false).is_err());
+    }
+
+    #[test]
+    fn test_xnode_details_costs() {
+        let xnode = XNode::new(
+            "node-1".to_string(),
+            "Node".to_string(),
+            "running".to_string(),
+            "10.0.0.1".to_string(),
+        );
+        let entry = XNodeEntry {
+            id: xnode.id.clone(),
+            name: xnode.name.clone(),
+            provider: "vultr".to_string(),
+            template: "small".to_string(),
+            status: xnode.status.clone(),
+            ip_address: xnode.ip_address.clone(),
+            ssh_port: 22,
+            region: None,
+            deployed_at: xnode.created_at,
+            cost_hourly: 0.5,
+            tags: vec!["web".to_string()],
+            metadata: HashMap::new(),
+            last_seen: None,
+        };
+        let record = DeploymentRecord::new(
+            "node-1".to_string(),
+            "vultr".to_string(),
+            "small".to_string(),
+            chrono::Utc::now() - chrono::Duration::hours(10),
+            None,
+            None,
+            Vec::new(),
+        );
+
+        let details = XNodeDetails::new(entry, Some(record), None, DEFAULT_STALE_AFTER_DAYS);
+        assert_eq!(details.projected_monthly_cost, 360.0);
+        assert!((details.accrued_cost - 5.0).abs() < 0.01);
+        assert!(details.health.is_none());
+        assert!(!details.stale);
+
+        let json = serde_json::to_value(&details).unwrap();
+        assert_eq!(json["id"], "node-1");
+        assert_eq!(json["tags"][0], "web");
+        assert!(json["deployment"]["uptime_hours"].as_f64().unwrap() > 9.9);
+    }
+
+    #[test]
+    fn test_parse_extra() {
+        let extra = parse_extra(&[
+            "subnet_id=subnet-0abc".to_string(),
+            "count=3".to_string(),
+            "note=a=b".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(extra["subnet_id"], "subnet-0abc");
+        assert_eq!(extra["count"], 3);
+        assert_eq!(extra["note"], "a=b");
+
+        assert!(parse_extra(&["novalue".to_string()]).is_err());
+        assert!(parse_extra(&["=x".to_string()]).is_err());
+    }
+}
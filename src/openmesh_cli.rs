@@ -1,11 +1,18 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use colored::Colorize;
 use prettytable::{Table, Row, Cell, format};
+use std::path::Path;
 
-use crate::inventory::XNodeInventory;
-use crate::ui::{header, success};
+use chrono::Utc;
 
-pub fn list_inventory(provider: Option<String>, status: Option<String>) -> Result<()> {
+use crate::cost::{BandwidthEstimate, CostForecast, CostGrouping, DeploymentRecord, NodeTransfer, ReportFormat};
+use crate::exit::CliError;
+use crate::inventory::{ExportFormat, HistoryWindow, InventoryStatistics, XNodeEntry, XNodeInventory, DEFAULT_STALE_AFTER_DAYS};
+use crate::monitoring::MonitoringSystem;
+use crate::providers::ProviderManager;
+use crate::ui::{header, success, warning};
+
+pub fn list_inventory(provider: Option<String>, status: Option<String>, stale_after_days: u32) -> Result<()> {
     let inventory = XNodeInventory::new(None)?;
 
     let entries = if let Some(prov) = provider {
@@ -21,6 +28,26 @@ pub fn list_inventory(provider: Option<String>, status: Option<String>) -> Resul
         return Ok(());
     }
 
+    header("XNODE INVENTORY");
+    entries_table(&entries, stale_after_days).printstd();
+    println!("\nTotal xNodes: {}", entries.len());
+
+    let now = Utc::now();
+    let stale = entries.iter().filter(|e| e.is_stale(stale_after_days, now)).count();
+    if stale > 0 {
+        warning(&format!(
+            "{} xNode(s) not confirmed in {} days (marked *); run `capsule openmesh xnode sync` to refresh",
+            stale, stale_after_days
+        ));
+    }
+
+    Ok(())
+}
+
+/// One row per entry: ID, name, provider, status, IP, region, cost and when
+/// it was last seen, with entries older than `stale_after_days` marked `*`
+fn entries_table(entries: &[&XNodeEntry], stale_after_days: u32) -> Table {
+    let now = Utc::now();
     let mut table = Table::new();
     table.set_format(*format::consts::FORMAT_BOX_CHARS);
 
@@ -32,9 +59,10 @@ pub fn list_inventory(provider: Option<String>, status: Option<String>) -> Resul
         Cell::new("IP Address").style_spec("Fc"),
         Cell::new("Region").style_spec("Fc"),
         Cell::new("Cost/Hour").style_spec("Fc"),
+        Cell::new("Last Seen").style_spec("Fc"),
     ]));
 
-    for entry in &entries {
+    for entry in entries {
         let status_colored = match entry.status.as_str() {
             "running" => entry.status.green().to_string(),
             "stopped" => entry.status.yellow().to_string(),
@@ -51,93 +79,369 @@ pub fn list_inventory(provider: Option<String>, status: Option<String>) -> Resul
             Cell::new(&entry.ip_address),
             Cell::new(entry.region.as_deref().unwrap_or("-")),
             Cell::new(&format!("${:.2}", entry.cost_hourly)),
+            if entry.is_stale(stale_after_days, now) {
+                Cell::new(&format!("{} *", entry.last_seen_label(now))).style_spec("Fy")
+            } else {
+                Cell::new(&entry.last_seen_label(now))
+            },
         ]));
     }
+    table
+}
 
-    header("XNODE INVENTORY");
+/// Look up xNodes by exact IP address or by name, printing every match
+pub fn find_xnodes(ip: Option<&str>, name: Option<&str>, json: bool) -> Result<()> {
+    let inventory = XNodeInventory::new(None)?;
+
+    let (entries, query) = match (ip, name) {
+        (Some(ip), _) => (inventory.find_all_by_ip(ip), format!("IP {}", ip)),
+        (None, Some(name)) => (inventory.search(name), format!("name '{}'", name)),
+        (None, None) => return Err(CliError::usage("Specify --ip or --name").into()),
+    };
+
+    if entries.is_empty() {
+        return Err(CliError::not_found(format!("No xNode matches {}", query)).into());
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    } else {
+        entries_table(&entries, DEFAULT_STALE_AFTER_DAYS).printstd();
+    }
+
+    if ip.is_some() && entries.len() > 1 {
+        let ids: Vec<&str> = entries.iter().map(|e| e.id.as_str()).collect();
+        warning(&format!(
+            "{} xNodes share {} ({}); the inventory may hold stale entries",
+            entries.len(),
+            query,
+            ids.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Spend so far and projected spend for one xNode at its current rate
+pub fn show_cost_forecast(xnode_id: &str, json: bool) -> Result<()> {
+    let inventory = XNodeInventory::new(None)?;
+    let entry = inventory
+        .get_xnode(xnode_id)
+        .ok_or_else(|| CliError::not_found(format!("xNode '{}' not found in inventory", xnode_id)))?;
+
+    // Nodes added without a deployment record are costed from the entry
+    let record = inventory
+        .get_deployment_history(Some(xnode_id), None, &HistoryWindow::default(), None)
+        .into_iter()
+        .find(|r| r.is_active())
+        .cloned()
+        .unwrap_or_else(|| {
+            DeploymentRecord::new(
+                entry.id.clone(),
+                entry.provider.clone(),
+                entry.template.clone(),
+                entry.deployed_at,
+                entry.region.clone(),
+                Some(entry.name.clone()),
+                entry.tags.clone(),
+            )
+        });
+    let forecast = CostForecast::new(&record, entry.cost_hourly, Utc::now());
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&forecast)?);
+        return Ok(());
+    }
+
+    header(&format!("COST FORECAST: {}", entry.name));
+    println!("  {} {} ({}/{})", "xNode:".white().bold(), entry.id.cyan(), entry.provider, entry.template);
+    println!("  {} ${:.4}/hr", "Rate:".white().bold(), forecast.cost_hourly);
+    println!(
+        "  {} {:.1} hours since {}",
+        "Uptime:".white().bold(),
+        forecast.uptime_hours,
+        forecast.deployed_at.format("%Y-%m-%d %H:%M UTC")
+    );
+    println!("  {} {}", "Accrued:".white().bold(), format!("${:.2}", forecast.accrued).green());
+    println!();
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    table.set_titles(Row::new(vec![
+        Cell::new("Period").style_spec("Fc"),
+        Cell::new("Projected").style_spec("Fc"),
+    ]));
+    for (period, cost) in [
+        ("Daily", forecast.daily),
+        ("Weekly", forecast.weekly),
+        ("Monthly (30 days)", forecast.monthly),
+        ("This month, by its end", forecast.month_end),
+        ("Annual", forecast.annual),
+    ] {
+        table.add_row(Row::new(vec![Cell::new(period), Cell::new(&format!("${:.2}", cost))]));
+    }
     table.printstd();
-    println!("\nTotal xNodes: {}", entries.len());
 
+    if entry.status != "running" {
+        println!();
+        warning(&format!(
+            "{} is {}; the projection assumes it is billed at ${:.4}/hr throughout",
+            entry.id, entry.status, forecast.cost_hourly
+        ));
+    }
     Ok(())
 }
 
-pub fn show_cost_report() -> Result<()> {
+pub fn show_cost_report(
+    anomaly_threshold: f64,
+    window_days: u32,
+    alert: bool,
+    format: ReportFormat,
+    output: Option<&Path>,
+) -> Result<()> {
     let inventory = XNodeInventory::new(None)?;
-    let report = inventory.get_cost_report();
+    let report = inventory
+        .get_cost_report()
+        .with_bandwidth(estimate_bandwidth(&inventory, &ProviderManager::new(None)?));
+
+    match output {
+        Some(path) => write_output(path, &report.render(format), &format!("{} cost report", format))?,
+        None => println!("\n{}", report.render(format)),
+    }
+
+    let anomaly = inventory.cost_log()?.detect_anomaly(
+        report.total_monthly,
+        Utc::now().date_naive(),
+        window_days,
+        anomaly_threshold,
+    );
+
+    if let Some(anomaly) = &anomaly {
+        println!();
+        warning(&format!("Cost anomaly: {}", anomaly.describe()));
+    }
 
-    println!("\n{}", report.generate_report());
+    if alert {
+        let monthly = inventory.get_total_cost()["monthly"];
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let tier = runtime.block_on(async {
+            let mut system = MonitoringSystem::new(None).await?;
+            if let Some(anomaly) = &anomaly {
+                system.raise_cost_alert(anomaly).await;
+            }
+            let tier = system.check_cost_tiers(monthly).await;
+            system.save_history().await.map(|_| tier)
+        })?;
+        if let Some(tier) = tier {
+            println!();
+            warning(&format!("Monthly cost ${:.2} is over the ${:.2} {} tier", monthly, tier.monthly, tier.severity));
+        }
+    }
 
     Ok(())
 }
 
-pub fn list_xnodes(status: Option<String>, provider: Option<String>) -> Result<()> {
-    list_inventory(provider, status)
+/// Transfer overage for running xNodes, from each node's expected egress and
+/// its template's included bandwidth
+fn estimate_bandwidth(inventory: &XNodeInventory, manager: &ProviderManager) -> BandwidthEstimate {
+    let mut nodes = Vec::new();
+    let mut unpriced = 0;
+
+    for xnode in inventory.list_by_status("running") {
+        let included_tb = manager
+            .get_provider(&xnode.provider)
+            .and_then(|p| p.get_template(&xnode.template))
+            .map(|t| t.bandwidth_tb);
+        match (included_tb, manager.bandwidth_overage_per_tb(&xnode.provider)) {
+            (Some(included_tb), Some(overage_per_tb)) => nodes.push(NodeTransfer {
+                provider: xnode.provider.clone(),
+                egress_tb: xnode.egress_tb_monthly(),
+                included_tb,
+                overage_per_tb,
+            }),
+            _ => unpriced += 1,
+        }
+    }
+
+    BandwidthEstimate::from_nodes(&nodes, unpriced)
 }
 
-pub fn show_statistics() -> Result<()> {
+/// Ranked running costs per provider, region or tag
+pub fn show_grouped_costs(grouping: CostGrouping, json: bool) -> Result<()> {
     let inventory = XNodeInventory::new(None)?;
-    let stats = inventory.get_statistics();
+    let groups = inventory.cost_by(grouping);
 
-    header("INVENTORY STATISTICS");
+    if json {
+        println!("{}", serde_json::to_string_pretty(&groups)?);
+        return Ok(());
+    }
 
-    println!("\n{}", "SUMMARY".cyan().bold());
-    println!("  Total xNodes: {}", stats.total_xnodes);
-    println!("  Active Deployments: {}", stats.active_deployments);
-    println!("  Terminated Deployments: {}", stats.terminated_deployments);
-    println!("  Lifetime Cost: ${:.2}", stats.lifetime_cost);
-    println!("  Average Uptime: {:.1} hours", stats.average_uptime_hours);
+    if groups.is_empty() {
+        println!("No running xNodes to cost");
+        return Ok(());
+    }
 
-    if !stats.status_distribution.is_empty() {
-        println!("\n{}", "STATUS DISTRIBUTION".cyan().bold());
-        for (status, count) in &stats.status_distribution {
-            println!("  {}: {}", status, count);
-        }
+    let title = match grouping {
+        CostGrouping::Provider => "Provider",
+        CostGrouping::Region => "Region",
+        CostGrouping::Tag => "Tag",
+    };
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    table.set_titles(Row::new(vec![
+        Cell::new(title).style_spec("Fc"),
+        Cell::new("xNodes").style_spec("Fc"),
+        Cell::new("Cost/Hour").style_spec("Fc"),
+        Cell::new("Cost/Month").style_spec("Fc"),
+    ]));
+    for group in &groups {
+        table.add_row(Row::new(vec![
+            Cell::new(&group.group),
+            Cell::new(&group.nodes.to_string()),
+            Cell::new(&format!("${:.2}", group.hourly)),
+            Cell::new(&format!("${:.2}", group.monthly)),
+        ]));
     }
 
-    if !stats.provider_distribution.is_empty() {
-        println!("\n{}", "PROVIDER DISTRIBUTION".cyan().bold());
-        for (provider, count) in &stats.provider_distribution {
-            println!("  {}: {}", provider, count);
+    header(&format!("COSTS BY {}", grouping.to_string().to_uppercase()));
+    table.printstd();
+    if grouping == CostGrouping::Tag {
+        println!("\nxNodes with several tags count towards each, so groups can sum to more than the total");
+    }
+    Ok(())
+}
+
+pub fn list_xnodes(status: Option<String>, provider: Option<String>, stale_after_days: u32) -> Result<()> {
+    list_inventory(provider, status, stale_after_days)
+}
+
+/// Write report content to `path`, confirming with a single line
+fn write_output(path: &Path, content: &str, what: &str) -> Result<()> {
+    let content = if content.ends_with('\n') { content.to_string() } else { format!("{}\n", content) };
+    std::fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+    success(&format!("Wrote {} to {}", what, path.display()));
+    Ok(())
+}
+
+/// Inventory statistics; `output` receives the plain text (or JSON) instead of stdout
+pub fn show_statistics(json: bool, output: Option<&Path>) -> Result<()> {
+    let inventory = XNodeInventory::new(None)?;
+    let stats = inventory.get_statistics();
+
+    let content = if json {
+        serde_json::to_string_pretty(&stats)?
+    } else {
+        statistics_text(&stats, output.is_none())
+    };
+    match output {
+        Some(path) => write_output(path, &content, "inventory statistics"),
+        None if json => {
+            println!("{}", content);
+            Ok(())
+        }
+        None => {
+            header("INVENTORY STATISTICS");
+            println!("{}", content);
+            Ok(())
         }
     }
+}
 
-    if !stats.region_distribution.is_empty() {
-        println!("\n{}", "REGION DISTRIBUTION".cyan().bold());
-        for (region, count) in &stats.region_distribution {
-            println!("  {}: {}", region, count);
+fn statistics_text(stats: &InventoryStatistics, color: bool) -> String {
+    let title = |text: &str| if color { text.cyan().bold().to_string() } else { text.to_string() };
+    let mut lines = vec![
+        title("SUMMARY"),
+        format!("  Total xNodes: {}", stats.total_xnodes),
+        format!("  Active Deployments: {}", stats.active_deployments),
+        format!("  Terminated Deployments: {}", stats.terminated_deployments),
+        format!("  Lifetime Cost: ${:.2}", stats.lifetime_cost),
+        format!("  Average Uptime: {:.1} hours", stats.average_uptime_hours),
+    ];
+
+    for (section, counts) in [
+        ("STATUS DISTRIBUTION", &stats.status_distribution),
+        ("PROVIDER DISTRIBUTION", &stats.provider_distribution),
+        ("REGION DISTRIBUTION", &stats.region_distribution),
+    ] {
+        if !counts.is_empty() {
+            lines.push(String::new());
+            lines.push(title(section));
+            lines.extend(counts.iter().map(|(name, count)| format!("  {}: {}", name, count)));
         }
     }
 
     if !stats.most_expensive.is_empty() {
-        println!("\n{}", "MOST EXPENSIVE XNODES".cyan().bold());
+        lines.push(String::new());
+        lines.push(title("MOST EXPENSIVE XNODES"));
         for xnode in &stats.most_expensive {
-            println!("  {} ({}): ${:.2}/hour", xnode.name, xnode.id, xnode.cost_hourly);
+            lines.push(format!("  {} ({}): ${:.2}/hour", xnode.name, xnode.id, xnode.cost_hourly));
         }
     }
 
     if !stats.longest_running.is_empty() {
-        println!("\n{}", "LONGEST RUNNING XNODES".cyan().bold());
+        lines.push(String::new());
+        lines.push(title("LONGEST RUNNING XNODES"));
         for xnode in &stats.longest_running {
-            println!(
+            lines.push(format!(
                 "  {} ({}): {:.1} hours ({:.1} days)",
                 xnode.name, xnode.id, xnode.uptime_hours, xnode.uptime_days
-            );
+            ));
         }
     }
 
+    lines.join("\n")
+}
+
+pub fn show_tags(json: bool) -> Result<()> {
+    let inventory = XNodeInventory::new(None)?;
+    let summary = inventory.tag_summary();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+        return Ok(());
+    }
+
+    if summary.tags.is_empty() {
+        println!("No tags in use ({} untagged xNode(s))", summary.untagged);
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    table.set_titles(Row::new(vec![
+        Cell::new("Tag").style_spec("Fc"),
+        Cell::new("xNodes").style_spec("Fc"),
+    ]));
+    for tag in &summary.tags {
+        table.add_row(Row::new(vec![Cell::new(&tag.tag), Cell::new(&tag.count.to_string())]));
+    }
+    table.printstd();
+
+    if summary.untagged > 0 {
+        println!("\n  {} xNode(s) have no tags", summary.untagged);
+    }
     Ok(())
 }
 
-pub fn export_inventory(filename: &str) -> Result<()> {
+pub fn export_inventory(filename: &str, format: ExportFormat, append: bool) -> Result<()> {
     let inventory = XNodeInventory::new(None)?;
-    inventory.export_csv(filename)?;
-    success(&format!("Exported inventory to {}", filename));
+    if append {
+        if format != ExportFormat::Csv {
+            return Err(CliError::usage("--append is only supported for CSV exports").into());
+        }
+        let count = inventory.append_csv(filename)?;
+        success(&format!("Appended {} new rows to {}", count, filename));
+        return Ok(());
+    }
+    inventory.export(filename, format)?;
+    success(&format!("Exported inventory to {} ({})", filename, format));
     Ok(())
 }
 
-pub fn import_inventory(filename: &str) -> Result<()> {
+pub fn import_inventory(filename: &str, format: ExportFormat) -> Result<()> {
     let mut inventory = XNodeInventory::new(None)?;
-    let count = inventory.import_csv(filename)?;
+    let count = inventory.import(filename, format)?;
     success(&format!("Imported {} xNodes from {}", count, filename));
     Ok(())
 }
@@ -145,20 +449,55 @@ pub fn import_inventory(filename: &str) -> Result<()> {
 pub fn show_deployment_history(
     xnode_id: Option<String>,
     provider: Option<String>,
+    window: HistoryWindow,
     limit: Option<usize>,
+    json: bool,
+    output: Option<&Path>,
 ) -> Result<()> {
+    if let (Some(since), Some(until)) = (window.since, window.until) {
+        let now = Utc::now();
+        if since.start(now) >= until.end(now) {
+            return Err(CliError::usage("--since must be earlier than --until").into());
+        }
+    }
+
     let inventory = XNodeInventory::new(None)?;
     let records = inventory.get_deployment_history(
         xnode_id.as_deref(),
         provider.as_deref(),
+        &window,
         limit,
     );
 
-    if records.is_empty() {
+    if json {
+        let content = serde_json::to_string_pretty(&records)?;
+        return match output {
+            Some(path) => write_output(path, &content, "deployment history"),
+            None => {
+                println!("{}", content);
+                Ok(())
+            }
+        };
+    }
+
+    if records.is_empty() && output.is_none() {
         println!("No deployment history found");
         return Ok(());
     }
 
+    let table = history_table(&records, output.is_none());
+    if let Some(path) = output {
+        return write_output(path, &table.to_string(), "deployment history");
+    }
+
+    header("DEPLOYMENT HISTORY");
+    table.printstd();
+    println!("\nTotal records: {}", records.len());
+
+    Ok(())
+}
+
+fn history_table(records: &[&DeploymentRecord], color: bool) -> Table {
     let mut table = Table::new();
     table.set_format(*format::consts::FORMAT_BOX_CHARS);
 
@@ -170,13 +509,15 @@ pub fn show_deployment_history(
         Cell::new("Uptime (hrs)").style_spec("Fc"),
         Cell::new("Total Cost").style_spec("Fc"),
         Cell::new("Status").style_spec("Fc"),
+        Cell::new("Reason").style_spec("Fc"),
     ]));
 
-    for record in &records {
-        let status = if record.is_active() {
-            "Active".green().to_string()
-        } else {
-            "Terminated".yellow().to_string()
+    for record in records {
+        let status = match (record.is_active(), color) {
+            (true, true) => "Active".green().to_string(),
+            (false, true) => "Terminated".yellow().to_string(),
+            (true, false) => "Active".to_string(),
+            (false, false) => "Terminated".to_string(),
         };
 
         let uptime = if record.uptime_hours > 0.0 {
@@ -193,14 +534,11 @@ pub fn show_deployment_history(
             Cell::new(&uptime),
             Cell::new(&format!("${:.2}", record.total_cost)),
             Cell::new(&status),
+            Cell::new(record.termination_reason.as_deref().unwrap_or("-")),
         ]));
     }
 
-    header("DEPLOYMENT HISTORY");
-    table.printstd();
-    println!("\nTotal records: {}", records.len());
-
-    Ok(())
+    table
 }
 
 pub fn cleanup_history(days: u64) -> Result<()> {
@@ -212,3 +550,32 @@ pub fn cleanup_history(days: u64) -> Result<()> {
     ));
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_statistics_text_without_color_is_plain() {
+        let stats = InventoryStatistics {
+            total_xnodes: 2,
+            status_distribution: BTreeMap::from([("running".to_string(), 2)]),
+            provider_distribution: BTreeMap::from([("vultr".to_string(), 2)]),
+            region_distribution: BTreeMap::new(),
+            total_deployments: 3,
+            active_deployments: 2,
+            terminated_deployments: 1,
+            average_uptime_hours: 12.5,
+            lifetime_cost: 4.2,
+            most_expensive: Vec::new(),
+            longest_running: Vec::new(),
+        };
+
+        let plain = statistics_text(&stats, false);
+        assert!(!plain.contains('\u{1b}'));
+        assert!(plain.contains("  Total xNodes: 2"));
+        assert!(plain.contains("PROVIDER DISTRIBUTION\n  vultr: 2"));
+        assert!(!plain.contains("REGION DISTRIBUTION"));
+    }
+}
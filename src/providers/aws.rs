@@ -1,6 +1,18 @@
-use super::{Provider, ProviderTemplate, Instance, DeployConfig};
+use super::{Provider, ProviderCapabilities, ProviderTemplate, Instance, DeployConfig, DeployFieldError};
 use anyhow::Result;
 
+/// Location of each region slug
+const REGION_NAMES: &[(&str, &str)] = &[
+    ("us-east-1", "N. Virginia"),
+    ("us-west-2", "Oregon"),
+    ("eu-west-1", "Ireland"),
+    ("ap-southeast-1", "Singapore"),
+    ("ap-northeast-1", "Tokyo"),
+];
+
+/// Instance page in the EC2 console
+const CONSOLE_URL: &str = "https://{region}.console.aws.amazon.com/ec2/home?region={region}#InstanceDetails:instanceId={id}";
+
 pub struct AWSProvider {
     name: String,
     api_key: Option<String>,
@@ -34,6 +46,7 @@ impl AWSProvider {
                 price_hourly: 0.0104,
                 price_monthly: 7.50,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["us-east-1".into(), "us-west-2".into(), "eu-west-1".into()],
                 features: vec!["burstable".into(), "cloud".into()],
             },
@@ -48,9 +61,25 @@ impl AWSProvider {
                 price_hourly: 0.0416,
                 price_monthly: 30.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["us-east-1".into(), "us-west-2".into(), "eu-west-1".into(), "ap-southeast-1".into()],
                 features: vec!["burstable".into(), "cloud".into()],
             },
+            ProviderTemplate {
+                id: "aws-t4g-medium".to_string(),
+                name: "t4g.medium".to_string(),
+                provider: "aws".to_string(),
+                cpu: 2,
+                memory_gb: 4,
+                storage_gb: 50,
+                bandwidth_tb: 0.5,
+                price_hourly: 0.0336,
+                price_monthly: 24.50,
+                gpu: None,
+                arch: "arm64".into(),
+                regions: vec!["us-east-1".into(), "us-west-2".into(), "eu-west-1".into(), "ap-southeast-1".into()],
+                features: vec!["burstable".into(), "cloud".into(), "graviton".into()],
+            },
             ProviderTemplate {
                 id: "aws-m5-large".to_string(),
                 name: "m5.large".to_string(),
@@ -62,6 +91,7 @@ impl AWSProvider {
                 price_hourly: 0.096,
                 price_monthly: 70.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["us-east-1".into(), "us-west-2".into(), "eu-west-1".into(), "ap-southeast-1".into()],
                 features: vec!["cloud".into(), "general-purpose".into()],
             },
@@ -76,6 +106,7 @@ impl AWSProvider {
                 price_hourly: 0.34,
                 price_monthly: 248.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["us-east-1".into(), "us-west-2".into(), "eu-west-1".into()],
                 features: vec!["cloud".into(), "compute-optimized".into()],
             },
@@ -102,10 +133,47 @@ impl Provider for AWSProvider {
         &self.templates
     }
 
+    fn templates_mut(&mut self) -> &mut [ProviderTemplate] {
+        &mut self.templates
+    }
+
     fn regions(&self) -> &[String] {
         &self.regions
     }
 
+    fn region_display_name(&self, region: &str) -> Option<String> {
+        super::lookup_region(REGION_NAMES, region)
+    }
+
+    fn console_url_template(&self) -> Option<&str> {
+        Some(CONSOLE_URL)
+    }
+
+    fn bandwidth_overage_per_tb(&self) -> f64 {
+        // Internet egress is billed from the first GB at about $0.09/GB
+        90.0
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        // Resize not implemented yet
+        ProviderCapabilities {
+            resize: false,
+            ..ProviderCapabilities::vm()
+        }
+    }
+
+    fn check_deploy_config(&self, config: &DeployConfig) -> Vec<DeployFieldError> {
+        // Instances launch into a subnet; the VPC is implied but may be pinned
+        [
+            DeployFieldError::check_prefixed(config, "subnet_id", "subnet-", true),
+            DeployFieldError::check_prefixed(config, "vpc_id", "vpc-", false),
+            DeployFieldError::check_prefixed(config, "security_group_id", "sg-", false),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
     fn deploy(&self, template_id: &str, config: &DeployConfig) -> Result<Instance> {
         let template = self.get_template(template_id)
             .ok_or_else(|| anyhow::anyhow!("Template {} not found", template_id))?;
@@ -154,4 +222,14 @@ impl Provider for AWSProvider {
         println!("Stopping AWS instance {}", instance_id);
         Ok(true)
     }
+
+    fn is_configured(&self) -> bool {
+        self.api_key.is_some()
+    }
+
+    fn snapshot_instance(&self, instance_id: &str, name: &str) -> Result<String> {
+        // TODO: Actual API implementation
+        println!("Creating AWS snapshot '{}' of instance {}", name, instance_id);
+        Ok(format!("aws-snap-{}-{}", instance_id, chrono::Utc::now().timestamp()))
+    }
 }
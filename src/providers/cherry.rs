@@ -1,6 +1,28 @@
-use super::{Provider, ProviderTemplate, Instance, DeployConfig};
+use super::images::{static_images, ImageInfo};
+use super::{Provider, ProviderCapabilities, ProviderTemplate, Instance, DeployConfig};
 use anyhow::Result;
 
+/// Location of each region slug
+const REGION_NAMES: &[(&str, &str)] = &[
+    ("eu-nord-1", "Stockholm"),
+    ("eu-west-1", "Amsterdam"),
+    ("us-east-1", "New York"),
+    ("us-west-1", "San Jose"),
+    ("ap-southeast-1", "Singapore"),
+    ("ap-east-1", "Tokyo"),
+];
+
+/// Server page in the Cherry Servers portal
+const CONSOLE_URL: &str = "https://portal.cherryservers.com/servers/{id}";
+
+/// Common Cherry Servers OS images
+const IMAGES: &[(&str, &str, &str)] = &[
+    ("ubuntu_24_04", "Ubuntu 24.04 LTS", "x86_64"),
+    ("ubuntu_22_04", "Ubuntu 22.04 LTS", "x86_64"),
+    ("debian_12_64bit", "Debian 12", "x86_64"),
+    ("rocky_9_64bit", "Rocky Linux 9", "x86_64"),
+];
+
 pub struct CherryServersProvider {
     name: String,
     api_key: Option<String>,
@@ -34,6 +56,7 @@ impl CherryServersProvider {
                 price_hourly: 0.08,
                 price_monthly: 58.0,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["eu-nord-1".into(), "eu-west-1".into(), "us-east-1".into()],
                 features: vec!["dedicated".into(), "bare-metal".into(), "ipmi".into()],
             },
@@ -48,6 +71,7 @@ impl CherryServersProvider {
                 price_hourly: 0.25,
                 price_monthly: 180.0,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["eu-nord-1".into(), "eu-west-1".into(), "us-east-1".into(), "us-west-1".into()],
                 features: vec!["dedicated".into(), "bare-metal".into(), "ipmi".into(), "raid".into()],
             },
@@ -62,6 +86,7 @@ impl CherryServersProvider {
                 price_hourly: 0.45,
                 price_monthly: 325.0,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["eu-nord-1".into(), "eu-west-1".into(), "us-east-1".into(), "us-west-1".into()],
                 features: vec!["dedicated".into(), "bare-metal".into(), "ipmi".into(), "raid".into(), "redundant-power".into()],
             },
@@ -76,6 +101,7 @@ impl CherryServersProvider {
                 price_hourly: 0.95,
                 price_monthly: 695.0,
                 gpu: Some("NVIDIA RTX A4000 (16GB)".to_string()),
+                arch: "x86_64".into(),
                 regions: vec!["eu-nord-1".into(), "eu-west-1".into(), "us-east-1".into()],
                 features: vec!["dedicated".into(), "bare-metal".into(), "gpu".into(), "ipmi".into()],
             },
@@ -90,6 +116,7 @@ impl CherryServersProvider {
                 price_hourly: 1.35,
                 price_monthly: 985.0,
                 gpu: Some("NVIDIA RTX A5000 (24GB)".to_string()),
+                arch: "x86_64".into(),
                 regions: vec!["eu-nord-1".into(), "eu-west-1".into(), "us-east-1".into(), "us-west-1".into()],
                 features: vec!["dedicated".into(), "bare-metal".into(), "gpu".into(), "ipmi".into(), "nvme".into()],
             },
@@ -98,12 +125,12 @@ impl CherryServersProvider {
 
     fn initialize_regions(&mut self) {
         self.regions = vec![
-            "eu-nord-1".into(),    // Stockholm
-            "eu-west-1".into(),    // Amsterdam
-            "us-east-1".into(),    // New York
-            "us-west-1".into(),    // San Jose
-            "ap-southeast-1".into(), // Singapore
-            "ap-east-1".into(),    // Tokyo
+            "eu-nord-1".into(),
+            "eu-west-1".into(),
+            "us-east-1".into(),
+            "us-west-1".into(),
+            "ap-southeast-1".into(),
+            "ap-east-1".into(),
         ];
     }
 }
@@ -117,10 +144,34 @@ impl Provider for CherryServersProvider {
         &self.templates
     }
 
+    fn templates_mut(&mut self) -> &mut [ProviderTemplate] {
+        &mut self.templates
+    }
+
     fn regions(&self) -> &[String] {
         &self.regions
     }
 
+    fn region_display_name(&self, region: &str) -> Option<String> {
+        super::lookup_region(REGION_NAMES, region)
+    }
+
+    fn is_configured(&self) -> bool {
+        self.api_key.is_some()
+    }
+
+    fn list_images(&self) -> Result<Vec<ImageInfo>> {
+        Ok(static_images(IMAGES))
+    }
+
+    fn console_url_template(&self) -> Option<&str> {
+        Some(CONSOLE_URL)
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities::bare_metal()
+    }
+
     fn deploy(&self, template_id: &str, config: &DeployConfig) -> Result<Instance> {
         let template = self.get_template(template_id)
             .ok_or_else(|| anyhow::anyhow!("Template {} not found", template_id))?;
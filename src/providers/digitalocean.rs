@@ -1,11 +1,86 @@
-use super::{Provider, ProviderTemplate, Instance, DeployConfig};
+use super::images::{parse_digitalocean_images, static_images, ImageInfo};
+use super::pricing::{apply_plan_prices, fetch_json, parse_digitalocean_sizes};
+use super::{Provider, ProviderCapabilities, ProviderTemplate, ProviderTimeouts, Instance, DeployConfig};
 use anyhow::Result;
 
+use crate::api::DigitalOceanClient;
+
+/// Location of each region slug
+const REGION_NAMES: &[(&str, &str)] = &[
+    ("nyc1", "New York"),
+    ("nyc3", "New York"),
+    ("sfo3", "San Francisco"),
+    ("lon1", "London"),
+    ("fra1", "Frankfurt"),
+    ("sgp1", "Singapore"),
+    ("tor1", "Toronto"),
+    ("ams3", "Amsterdam"),
+];
+
+/// Droplet page in the DigitalOcean control panel
+const CONSOLE_URL: &str = "https://cloud.digitalocean.com/droplets/{id}";
+
+/// Common images, listed when no API key is configured to fetch them
+const IMAGES: &[(&str, &str, &str)] = &[
+    ("ubuntu-24-04-x64", "Ubuntu 24.04 (LTS) x64", "x86_64"),
+    ("ubuntu-22-04-x64", "Ubuntu 22.04 (LTS) x64", "x86_64"),
+    ("debian-12-x64", "Debian 12 x64", "x86_64"),
+    ("rockylinux-9-x64", "Rocky Linux 9 x64", "x86_64"),
+];
+
 pub struct DigitalOceanProvider {
     name: String,
     api_key: Option<String>,
     templates: Vec<ProviderTemplate>,
     regions: Vec<String>,
+    timeouts: ProviderTimeouts,
+}
+
+/// Template ID to DigitalOcean plan slug, for price refreshes
+const PLAN_SLUGS: &[(&str, &str)] = &[
+    ("do-basic-1", "s-1vcpu-1gb"),
+    ("do-basic-2", "s-2vcpu-2gb"),
+    ("do-standard-4", "s-4vcpu-8gb"),
+    ("do-cpu-8", "c-8"),
+];
+
+/// Map a droplet status onto capsule's instance statuses
+fn droplet_status(status: &str) -> &str {
+    match status {
+        "new" => "deploying",
+        "active" => "running",
+        "off" => "stopped",
+        "archive" => "terminated",
+        other => other,
+    }
+}
+
+/// Parse the `droplet` object of `GET /droplets/{id}`
+fn parse_droplet(droplet: &serde_json::Value) -> Option<Instance> {
+    let size_slug = droplet.get("size_slug")?.as_str()?;
+    let template = PLAN_SLUGS
+        .iter()
+        .find(|(_, slug)| *slug == size_slug)
+        .map_or(size_slug, |(id, _)| *id);
+    let ip_address = droplet["networks"]["v4"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|net| net["type"] == "public")
+        .and_then(|net| net["ip_address"].as_str())
+        .unwrap_or_default();
+
+    Some(Instance {
+        id: droplet.get("id")?.to_string(),
+        name: droplet.get("name")?.as_str()?.to_string(),
+        provider: "digitalocean".to_string(),
+        template: template.to_string(),
+        region: droplet["region"]["slug"].as_str().unwrap_or_default().to_string(),
+        status: droplet_status(droplet.get("status")?.as_str()?).to_string(),
+        ip_address: ip_address.to_string(),
+        cost_hourly: droplet["size"]["price_hourly"].as_f64().unwrap_or_default(),
+        metadata: None,
+    })
 }
 
 impl DigitalOceanProvider {
@@ -15,6 +90,7 @@ impl DigitalOceanProvider {
             api_key,
             templates: Vec::new(),
             regions: Vec::new(),
+            timeouts: ProviderTimeouts::defaults(ProviderCapabilities::vm()),
         };
         provider.initialize_templates();
         provider.initialize_regions();
@@ -34,6 +110,7 @@ impl DigitalOceanProvider {
                 price_hourly: 0.007,
                 price_monthly: 5.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["nyc1".into(), "nyc3".into(), "sfo3".into(), "lon1".into(), "fra1".into()],
                 features: vec!["ssd".into(), "cloud".into()],
             },
@@ -48,6 +125,7 @@ impl DigitalOceanProvider {
                 price_hourly: 0.015,
                 price_monthly: 12.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["nyc1".into(), "nyc3".into(), "sfo3".into(), "lon1".into(), "fra1".into(), "sgp1".into()],
                 features: vec!["ssd".into(), "cloud".into()],
             },
@@ -62,6 +140,7 @@ impl DigitalOceanProvider {
                 price_hourly: 0.071,
                 price_monthly: 48.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["nyc1".into(), "nyc3".into(), "sfo3".into(), "lon1".into(), "fra1".into(), "sgp1".into(), "tor1".into()],
                 features: vec!["ssd".into(), "cloud".into(), "monitoring".into()],
             },
@@ -76,6 +155,7 @@ impl DigitalOceanProvider {
                 price_hourly: 0.238,
                 price_monthly: 160.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["nyc1".into(), "sfo3".into(), "lon1".into(), "fra1".into()],
                 features: vec!["ssd".into(), "cloud".into(), "cpu-optimized".into()],
             },
@@ -105,10 +185,43 @@ impl Provider for DigitalOceanProvider {
         &self.templates
     }
 
+    fn templates_mut(&mut self) -> &mut [ProviderTemplate] {
+        &mut self.templates
+    }
+
     fn regions(&self) -> &[String] {
         &self.regions
     }
 
+    fn region_display_name(&self, region: &str) -> Option<String> {
+        super::lookup_region(REGION_NAMES, region)
+    }
+
+    fn is_configured(&self) -> bool {
+        self.api_key.is_some()
+    }
+
+    fn list_images(&self) -> Result<Vec<ImageInfo>> {
+        let Some(api_key) = self.api_key.clone() else {
+            return Ok(static_images(IMAGES));
+        };
+
+        let client = DigitalOceanClient::new(api_key)?.with_timeout(self.timeouts.list);
+        let body = fetch_json(client.client(), "/images?type=distribution&per_page=200")?;
+        Ok(parse_digitalocean_images(&body))
+    }
+
+    fn console_url_template(&self) -> Option<&str> {
+        Some(CONSOLE_URL)
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            live_pricing: true,
+            ..ProviderCapabilities::vm()
+        }
+    }
+
     fn deploy(&self, template_id: &str, config: &DeployConfig) -> Result<Instance> {
         let template = self.get_template(template_id)
             .ok_or_else(|| anyhow::anyhow!("Template {} not found", template_id))?;
@@ -143,12 +256,13 @@ impl Provider for DigitalOceanProvider {
     }
 
     fn get_instance(&self, instance_id: &str) -> Result<Instance> {
-        if self.api_key.is_none() {
-            anyhow::bail!("DigitalOcean API key not configured");
-        }
+        let api_key = self.api_key.clone()
+            .ok_or_else(|| anyhow::anyhow!("DigitalOcean API key not configured"))?;
 
-        // TODO: Actual API implementation
-        anyhow::bail!("Instance {} not found", instance_id)
+        let client = DigitalOceanClient::new(api_key)?.with_timeout(self.timeouts.list);
+        let body = fetch_json(client.client(), &format!("/droplets/{}", instance_id))?;
+        parse_droplet(&body["droplet"])
+            .ok_or_else(|| anyhow::anyhow!("Unexpected response for droplet {}", instance_id))
     }
 
     fn delete_instance(&self, instance_id: &str) -> Result<bool> {
@@ -180,4 +294,87 @@ impl Provider for DigitalOceanProvider {
         println!("Stopping DigitalOcean instance {}", instance_id);
         Ok(true)
     }
+
+    fn resize(&self, instance_id: &str, new_template_id: &str) -> Result<Instance> {
+        let template = self.get_template(new_template_id)
+            .ok_or_else(|| anyhow::anyhow!("Template {} not found", new_template_id))?;
+
+        if self.api_key.is_none() {
+            anyhow::bail!("DigitalOcean API key not configured");
+        }
+
+        // TODO: Actual API implementation
+        println!("Resizing DigitalOcean instance {} to {}", instance_id, new_template_id);
+
+        Ok(Instance {
+            id: instance_id.to_string(),
+            name: instance_id.to_string(),
+            provider: "digitalocean".to_string(),
+            template: new_template_id.to_string(),
+            region: "".to_string(),
+            status: "resizing".to_string(),
+            ip_address: "".to_string(),
+            cost_hourly: template.price_hourly,
+            metadata: None,
+        })
+    }
+
+    fn snapshot_instance(&self, instance_id: &str, name: &str) -> Result<String> {
+        if self.api_key.is_none() {
+            anyhow::bail!("DigitalOcean API key not configured");
+        }
+
+        // TODO: Actual API implementation
+        println!("Creating DigitalOcean snapshot '{}' of instance {}", name, instance_id);
+        Ok(format!("digitalocean-snap-{}-{}", instance_id, chrono::Utc::now().timestamp()))
+    }
+
+    fn refresh_pricing(&mut self) -> Result<()> {
+        let api_key = self.api_key.clone()
+            .ok_or_else(|| anyhow::anyhow!("DigitalOcean API key not configured"))?;
+
+        let client = DigitalOceanClient::new(api_key)?.with_timeout(self.timeouts.list);
+        let body = fetch_json(client.client(), "/sizes?per_page=200")?;
+        apply_plan_prices(&mut self.templates, PLAN_SLUGS, &parse_digitalocean_sizes(&body));
+        Ok(())
+    }
+
+    fn set_timeouts(&mut self, timeouts: ProviderTimeouts) {
+        self.timeouts = timeouts;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_droplet() {
+        let droplet = json!({
+            "id": 3164444,
+            "name": "web",
+            "status": "active",
+            "size_slug": "s-1vcpu-1gb",
+            "size": {"price_hourly": 0.00893},
+            "region": {"slug": "nyc3"},
+            "networks": {"v4": [
+                {"ip_address": "10.128.0.2", "type": "private"},
+                {"ip_address": "104.236.32.182", "type": "public"}
+            ]}
+        });
+
+        let instance = parse_droplet(&droplet).unwrap();
+        assert_eq!(instance.id, "3164444");
+        assert_eq!(instance.template, "do-basic-1");
+        assert_eq!(instance.status, "running");
+        assert_eq!(instance.ip_address, "104.236.32.182");
+        assert_eq!(instance.region, "nyc3");
+
+        let booting = json!({"id": 1, "name": "db", "status": "new", "size_slug": "g-2vcpu-8gb"});
+        let instance = parse_droplet(&booting).unwrap();
+        assert_eq!(instance.status, "deploying");
+        assert_eq!(instance.template, "g-2vcpu-8gb");
+        assert!(parse_droplet(&json!({"message": "not found"})).is_none());
+    }
 }
@@ -1,6 +1,29 @@
-use super::{Provider, ProviderTemplate, Instance, DeployConfig};
+use super::images::{static_images, ImageInfo};
+use super::{Provider, ProviderCapabilities, ProviderTemplate, Instance, DeployConfig, DeployFieldError};
 use anyhow::Result;
 
+/// Location of each region slug
+const REGION_NAMES: &[(&str, &str)] = &[
+    ("da", "Dallas"),
+    ("sv", "Silicon Valley"),
+    ("ny", "New York"),
+    ("am", "Amsterdam"),
+    ("sg", "Singapore"),
+    ("ty", "Tokyo"),
+    ("fr", "Frankfurt"),
+];
+
+/// Device page in the Equinix Metal console
+const CONSOLE_URL: &str = "https://console.equinix.com/devices/{id}";
+
+/// Common Equinix Metal OS images
+const IMAGES: &[(&str, &str, &str)] = &[
+    ("ubuntu_24_04", "Ubuntu 24.04 LTS", "x86_64"),
+    ("ubuntu_22_04", "Ubuntu 22.04 LTS", "x86_64"),
+    ("debian_12", "Debian 12", "x86_64"),
+    ("rocky_9", "Rocky Linux 9", "x86_64"),
+];
+
 pub struct EquinixProvider {
     name: String,
     api_key: Option<String>,
@@ -34,6 +57,7 @@ impl EquinixProvider {
                 price_hourly: 0.50,
                 price_monthly: 350.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["da".into(), "sv".into(), "ny".into(), "am".into()],
                 features: vec!["bare-metal".into(), "nvme".into()],
             },
@@ -48,6 +72,7 @@ impl EquinixProvider {
                 price_hourly: 1.00,
                 price_monthly: 700.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["da".into(), "sv".into(), "ny".into(), "am".into(), "sg".into()],
                 features: vec!["bare-metal".into(), "nvme".into(), "high-memory".into()],
             },
@@ -62,6 +87,7 @@ impl EquinixProvider {
                 price_hourly: 3.00,
                 price_monthly: 2100.00,
                 gpu: Some("NVIDIA Tesla V100".to_string()),
+                arch: "x86_64".into(),
                 regions: vec!["da".into(), "sv".into(), "ny".into()],
                 features: vec!["bare-metal".into(), "gpu".into(), "nvme".into()],
             },
@@ -90,10 +116,46 @@ impl Provider for EquinixProvider {
         &self.templates
     }
 
+    fn templates_mut(&mut self) -> &mut [ProviderTemplate] {
+        &mut self.templates
+    }
+
     fn regions(&self) -> &[String] {
         &self.regions
     }
 
+    fn region_display_name(&self, region: &str) -> Option<String> {
+        super::lookup_region(REGION_NAMES, region)
+    }
+
+    fn is_configured(&self) -> bool {
+        self.api_key.is_some()
+    }
+
+    fn list_images(&self) -> Result<Vec<ImageInfo>> {
+        Ok(static_images(IMAGES))
+    }
+
+    fn console_url_template(&self) -> Option<&str> {
+        Some(CONSOLE_URL)
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities::bare_metal()
+    }
+
+    fn check_deploy_config(&self, config: &DeployConfig) -> Vec<DeployFieldError> {
+        // Equinix Metal devices always belong to a project
+        match config.extra_str("project_id") {
+            Some(id) if !id.trim().is_empty() => Vec::new(),
+            Some(_) => vec![DeployFieldError::Invalid {
+                field: "project_id".to_string(),
+                reason: "must not be empty".to_string(),
+            }],
+            None => vec![DeployFieldError::Missing("project_id".to_string())],
+        }
+    }
+
     fn deploy(&self, template_id: &str, config: &DeployConfig) -> Result<Instance> {
         let template = self.get_template(template_id)
             .ok_or_else(|| anyhow::anyhow!("Template {} not found", template_id))?;
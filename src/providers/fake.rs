@@ -0,0 +1,205 @@
+// In-memory provider for tests
+//
+// Available to unit tests and, with the `testing` feature, to integration
+// tests. Instances get deterministic IDs (`fake-1`, `fake-2`, ...) and
+// addresses, and clones share state so a test can inspect what the
+// `ProviderManager` did through its own handle.
+
+use anyhow::Result;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use super::{DeployConfig, Instance, Provider, ProviderCapabilities, ProviderTemplate};
+
+#[derive(Default)]
+struct FakeState {
+    next_id: u32,
+    instances: BTreeMap<String, Instance>,
+    /// Statuses each instance still has to pass through before `running`
+    booting: BTreeMap<String, VecDeque<String>>,
+    failed_polls: u32,
+}
+
+#[derive(Clone)]
+pub struct FakeProvider {
+    name: String,
+    templates: Vec<ProviderTemplate>,
+    regions: Vec<String>,
+    capabilities: ProviderCapabilities,
+    boot_states: Vec<String>,
+    poll_errors: u32,
+    configured: bool,
+    state: Arc<Mutex<FakeState>>,
+}
+
+impl Default for FakeProvider {
+    fn default() -> Self {
+        Self::new("fake")
+    }
+}
+
+impl FakeProvider {
+    /// A VM-style provider with `small` and `large` templates in `test-1`
+    pub fn new(name: &str) -> Self {
+        let template = |id: &str, cpu: u32, price_hourly: f64| ProviderTemplate {
+            id: id.to_string(),
+            name: format!("Fake {}", id),
+            provider: name.to_string(),
+            cpu,
+            memory_gb: cpu * 2,
+            storage_gb: cpu * 25,
+            bandwidth_tb: 1.0,
+            price_hourly,
+            price_monthly: price_hourly * 730.0,
+            gpu: None,
+            arch: "x86_64".into(),
+            regions: vec!["test-1".to_string()],
+            features: vec!["cloud".to_string()],
+        };
+
+        Self {
+            name: name.to_string(),
+            templates: vec![template("small", 1, 0.01), template("large", 4, 0.04)],
+            regions: vec!["test-1".to_string(), "test-2".to_string()],
+            capabilities: ProviderCapabilities { live_deploy: true, live_listing: true, ..ProviderCapabilities::vm() },
+            boot_states: Vec::new(),
+            poll_errors: 0,
+            configured: true,
+            state: Arc::default(),
+        }
+    }
+
+    pub fn with_capabilities(mut self, capabilities: ProviderCapabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Have new instances start in `states[0]` and move one state further
+    /// after each `get_instance`, ending up `running`
+    pub fn with_boot_states(mut self, states: &[&str]) -> Self {
+        self.boot_states = states.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    /// Fail the first `count` calls to `get_instance`, like a flaky API
+    pub fn with_poll_errors(mut self, count: u32) -> Self {
+        self.poll_errors = count;
+        self
+    }
+
+    /// Act like a provider with no API key: `is_configured` is false and
+    /// `list_instances` returns nothing, as the real stubs do
+    pub fn without_credentials(mut self) -> Self {
+        self.configured = false;
+        self
+    }
+
+    /// Instances currently held by the provider, ordered by ID
+    pub fn instances(&self) -> Vec<Instance> {
+        self.state.lock().unwrap().instances.values().cloned().collect()
+    }
+
+    fn set_status(&self, instance_id: &str, status: &str) -> Result<bool> {
+        let mut state = self.state.lock().unwrap();
+        let instance = state
+            .instances
+            .get_mut(instance_id)
+            .ok_or_else(|| anyhow::anyhow!("Instance {} not found", instance_id))?;
+        instance.status = status.to_string();
+        Ok(true)
+    }
+}
+
+impl Provider for FakeProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn templates(&self) -> &[ProviderTemplate] {
+        &self.templates
+    }
+
+    fn templates_mut(&mut self) -> &mut [ProviderTemplate] {
+        &mut self.templates
+    }
+
+    fn regions(&self) -> &[String] {
+        &self.regions
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.capabilities
+    }
+
+    fn deploy(&self, template_id: &str, config: &DeployConfig) -> Result<Instance> {
+        let template = self.get_template(template_id)
+            .ok_or_else(|| anyhow::anyhow!("Template {} not found", template_id))?;
+
+        let mut booting: VecDeque<String> = self.boot_states.iter().cloned().collect();
+        let status = booting.pop_front().unwrap_or_else(|| "running".to_string());
+
+        let mut state = self.state.lock().unwrap();
+        state.next_id += 1;
+        let instance = Instance {
+            id: format!("{}-{}", self.name, state.next_id),
+            name: config.name.clone(),
+            provider: self.name.clone(),
+            template: template_id.to_string(),
+            region: config.region.clone(),
+            status,
+            ip_address: format!("10.0.0.{}", state.next_id),
+            cost_hourly: template.price_hourly,
+            metadata: None,
+        };
+        state.instances.insert(instance.id.clone(), instance.clone());
+        if !booting.is_empty() || instance.status != "running" {
+            state.booting.insert(instance.id.clone(), booting);
+        }
+        Ok(instance)
+    }
+
+    fn is_configured(&self) -> bool {
+        self.configured
+    }
+
+    fn list_instances(&self) -> Result<Vec<Instance>> {
+        if !self.configured {
+            return Ok(Vec::new());
+        }
+        Ok(self.instances())
+    }
+
+    fn get_instance(&self, instance_id: &str) -> Result<Instance> {
+        let mut state = self.state.lock().unwrap();
+        if state.failed_polls < self.poll_errors {
+            state.failed_polls += 1;
+            anyhow::bail!("Temporary API error");
+        }
+        let FakeState { instances, booting, .. } = &mut *state;
+        let instance = instances
+            .get_mut(instance_id)
+            .ok_or_else(|| anyhow::anyhow!("Instance {} not found", instance_id))?;
+
+        // Report the current state, then move on for the next poll
+        let current = instance.clone();
+        if let Some(pending) = booting.get_mut(instance_id) {
+            instance.status = pending.pop_front().unwrap_or_else(|| "running".to_string());
+            if instance.status == "running" {
+                booting.remove(instance_id);
+            }
+        }
+        Ok(current)
+    }
+
+    fn delete_instance(&self, instance_id: &str) -> Result<bool> {
+        Ok(self.state.lock().unwrap().instances.remove(instance_id).is_some())
+    }
+
+    fn start_instance(&self, instance_id: &str) -> Result<bool> {
+        self.set_status(instance_id, "running")
+    }
+
+    fn stop_instance(&self, instance_id: &str) -> Result<bool> {
+        self.set_status(instance_id, "stopped")
+    }
+}
@@ -0,0 +1,226 @@
+// Template filter expressions for `capsule openmesh xnode templates --filter`
+//
+// An expression is one or more conditions joined with `&&`, e.g.
+// `cpu>=8 && memory>=16 && price<0.5`. Each condition compares a numeric
+// template field with a number, or checks for a GPU with `gpu` / `!gpu`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::ProviderTemplate;
+
+/// A numeric `ProviderTemplate` field that can be compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterField {
+    Cpu,
+    MemoryGb,
+    StorageGb,
+    PriceHourly,
+    PriceMonthly,
+}
+
+impl FilterField {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "cpu" => Some(Self::Cpu),
+            "memory_gb" | "memory" => Some(Self::MemoryGb),
+            "storage_gb" | "storage" => Some(Self::StorageGb),
+            "price_hourly" | "price" => Some(Self::PriceHourly),
+            "price_monthly" => Some(Self::PriceMonthly),
+            _ => None,
+        }
+    }
+
+    fn value(&self, template: &ProviderTemplate) -> f64 {
+        match self {
+            Self::Cpu => template.cpu as f64,
+            Self::MemoryGb => template.memory_gb as f64,
+            Self::StorageGb => template.storage_gb as f64,
+            Self::PriceHourly => template.price_hourly,
+            Self::PriceMonthly => template.price_monthly,
+        }
+    }
+}
+
+impl fmt::Display for FilterField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Cpu => "cpu",
+            Self::MemoryGb => "memory_gb",
+            Self::StorageGb => "storage_gb",
+            Self::PriceHourly => "price_hourly",
+            Self::PriceMonthly => "price_monthly",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Comparison operators, longest first so `>=` isn't read as `>`
+const OPERATORS: [(&str, Comparison); 7] = [
+    (">=", Comparison::Ge),
+    ("<=", Comparison::Le),
+    ("==", Comparison::Eq),
+    ("!=", Comparison::Ne),
+    (">", Comparison::Gt),
+    ("<", Comparison::Lt),
+    ("=", Comparison::Eq),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+}
+
+impl Comparison {
+    fn holds(&self, left: f64, right: f64) -> bool {
+        match self {
+            Self::Gt => left > right,
+            Self::Ge => left >= right,
+            Self::Lt => left < right,
+            Self::Le => left <= right,
+            Self::Eq => (left - right).abs() < f64::EPSILON,
+            Self::Ne => (left - right).abs() >= f64::EPSILON,
+        }
+    }
+}
+
+/// One condition of a filter expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Compare { field: FilterField, op: Comparison, value: f64 },
+    Gpu(bool),
+}
+
+impl Condition {
+    fn matches(&self, template: &ProviderTemplate) -> bool {
+        match self {
+            Self::Compare { field, op, value } => op.holds(field.value(template), *value),
+            Self::Gpu(wanted) => template.gpu.is_some() == *wanted,
+        }
+    }
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim();
+        match text {
+            "" => return Err("empty condition (check for a stray '&&')".to_string()),
+            "gpu" => return Ok(Self::Gpu(true)),
+            "!gpu" => return Ok(Self::Gpu(false)),
+            _ => {}
+        }
+
+        let (at, symbol, op) = OPERATORS
+            .iter()
+            .filter_map(|(symbol, op)| text.find(symbol).map(|at| (at, *symbol, *op)))
+            .min_by_key(|(at, symbol, _)| (*at, std::cmp::Reverse(symbol.len())))
+            .ok_or_else(|| format!("'{}' is not a comparison (e.g. cpu>=8) or gpu check", text))?;
+
+        let name = text[..at].trim();
+        let field = FilterField::parse(name).ok_or_else(|| {
+            format!(
+                "unknown field '{}' (expected cpu, memory_gb, storage_gb, price_hourly, price_monthly or gpu)",
+                name
+            )
+        })?;
+        let number = text[at + symbol.len()..].trim();
+        let value = number
+            .parse::<f64>()
+            .map_err(|_| format!("'{}' in '{}' is not a number", number, text))?;
+
+        Ok(Self::Compare { field, op, value })
+    }
+}
+
+/// Conditions that must all hold for a template to be listed
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateFilter {
+    pub conditions: Vec<Condition>,
+}
+
+impl TemplateFilter {
+    pub fn matches(&self, template: &ProviderTemplate) -> bool {
+        self.conditions.iter().all(|c| c.matches(template))
+    }
+}
+
+impl FromStr for TemplateFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.contains("||") {
+            return Err("only '&&' is supported between conditions".to_string());
+        }
+        let conditions = s
+            .split("&&")
+            .map(str::parse)
+            .collect::<Result<Vec<Condition>, String>>()?;
+        Ok(Self { conditions })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(cpu: u32, memory_gb: u32, price_hourly: f64, gpu: Option<&str>) -> ProviderTemplate {
+        ProviderTemplate {
+            id: format!("t-{}", cpu),
+            name: format!("{} vCPU", cpu),
+            provider: "fake".to_string(),
+            cpu,
+            memory_gb,
+            storage_gb: 100,
+            bandwidth_tb: 1.0,
+            price_hourly,
+            price_monthly: price_hourly * 730.0,
+            gpu: gpu.map(String::from),
+            arch: "x86_64".into(),
+            regions: Vec::new(),
+            features: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_conditions() {
+        let filter: TemplateFilter = "cpu>=8 && memory >= 16&&price<0.5 && !gpu".parse().unwrap();
+        assert_eq!(
+            filter.conditions,
+            [
+                Condition::Compare { field: FilterField::Cpu, op: Comparison::Ge, value: 8.0 },
+                Condition::Compare { field: FilterField::MemoryGb, op: Comparison::Ge, value: 16.0 },
+                Condition::Compare { field: FilterField::PriceHourly, op: Comparison::Lt, value: 0.5 },
+                Condition::Gpu(false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filter_matches_templates() {
+        let filter: TemplateFilter = "cpu>=8 && memory_gb>=16 && price_hourly<0.5".parse().unwrap();
+        assert!(filter.matches(&template(8, 16, 0.4, None)));
+        assert!(!filter.matches(&template(4, 16, 0.4, None)));
+        assert!(!filter.matches(&template(8, 32, 0.5, None)));
+
+        let gpu: TemplateFilter = "gpu && storage_gb == 100".parse().unwrap();
+        assert!(gpu.matches(&template(8, 16, 2.0, Some("A100"))));
+        assert!(!gpu.matches(&template(8, 16, 2.0, None)));
+    }
+
+    #[test]
+    fn test_invalid_expressions_are_explained() {
+        let err = |text: &str| text.parse::<TemplateFilter>().unwrap_err();
+        assert!(err("disk>10").contains("unknown field 'disk'"));
+        assert!(err("cpu>=eight").contains("not a number"));
+        assert!(err("cpu>=8 &&").contains("empty condition"));
+        assert!(err("cpu").contains("not a comparison"));
+        assert!(err("cpu>8 || gpu").contains("only '&&'"));
+    }
+}
@@ -1,6 +1,27 @@
-use super::{Provider, ProviderTemplate, Instance, DeployConfig};
+use super::images::{static_images, ImageInfo};
+use super::{Provider, ProviderCapabilities, ProviderTemplate, Instance, DeployConfig};
 use anyhow::Result;
 
+/// Location of each region slug
+const REGION_NAMES: &[(&str, &str)] = &[
+    ("atlanta", "Atlanta"),
+    ("tampa", "Tampa"),
+    ("los-angeles", "Los Angeles"),
+    ("new-york", "New York"),
+    ("miami", "Miami"),
+];
+
+/// Device page in the Hivelocity portal
+const CONSOLE_URL: &str = "https://my.hivelocity.net/device/{id}";
+
+/// Common Hivelocity OS images
+const IMAGES: &[(&str, &str, &str)] = &[
+    ("Ubuntu 24.x", "Ubuntu 24.04 LTS", "x86_64"),
+    ("Ubuntu 22.x", "Ubuntu 22.04 LTS", "x86_64"),
+    ("Debian 12.x", "Debian 12", "x86_64"),
+    ("Rocky Linux 9.x", "Rocky Linux 9", "x86_64"),
+];
+
 pub struct HivelocityProvider {
     name: String,
     api_key: Option<String>,
@@ -34,6 +55,7 @@ impl HivelocityProvider {
                 price_hourly: 0.12,
                 price_monthly: 85.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["atlanta".into(), "tampa".into(), "los-angeles".into()],
                 features: vec!["dedicated".into(), "bare-metal".into(), "ipmi".into()],
             },
@@ -48,6 +70,7 @@ impl HivelocityProvider {
                 price_hourly: 0.25,
                 price_monthly: 180.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["atlanta".into(), "tampa".into(), "los-angeles".into(), "new-york".into()],
                 features: vec!["dedicated".into(), "bare-metal".into(), "ipmi".into(), "raid".into()],
             },
@@ -62,6 +85,7 @@ impl HivelocityProvider {
                 price_hourly: 0.50,
                 price_monthly: 360.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["atlanta".into(), "tampa".into(), "los-angeles".into(), "new-york".into()],
                 features: vec!["dedicated".into(), "bare-metal".into(), "ipmi".into(), "raid".into(), "redundant-power".into()],
             },
@@ -76,6 +100,7 @@ impl HivelocityProvider {
                 price_hourly: 0.80,
                 price_monthly: 575.00,
                 gpu: Some("NVIDIA RTX 4090".to_string()),
+                arch: "x86_64".into(),
                 regions: vec!["atlanta".into(), "los-angeles".into()],
                 features: vec!["dedicated".into(), "bare-metal".into(), "gpu".into(), "ipmi".into()],
             },
@@ -102,10 +127,38 @@ impl Provider for HivelocityProvider {
         &self.templates
     }
 
+    fn templates_mut(&mut self) -> &mut [ProviderTemplate] {
+        &mut self.templates
+    }
+
     fn regions(&self) -> &[String] {
         &self.regions
     }
 
+    fn region_display_name(&self, region: &str) -> Option<String> {
+        super::lookup_region(REGION_NAMES, region)
+    }
+
+    fn is_configured(&self) -> bool {
+        self.api_key.is_some()
+    }
+
+    fn list_images(&self) -> Result<Vec<ImageInfo>> {
+        Ok(static_images(IMAGES))
+    }
+
+    fn console_url_template(&self) -> Option<&str> {
+        Some(CONSOLE_URL)
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        // Dedicated servers that can be power cycled, but not resized or imaged
+        ProviderCapabilities {
+            start_stop: true,
+            ..ProviderCapabilities::bare_metal()
+        }
+    }
+
     fn deploy(&self, template_id: &str, config: &DeployConfig) -> Result<Instance> {
         let template = self.get_template(template_id)
             .ok_or_else(|| anyhow::anyhow!("Template {} not found", template_id))?;
@@ -0,0 +1,135 @@
+// OS images
+//
+// Deploys name their OS image by the provider's own identifier, which
+// differs per provider ("ubuntu-22-04-x64" on DigitalOcean, "1743" on
+// Vultr). Providers with an API client list their images live once an API
+// key is configured; the rest ship a curated list of common images.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{parse_arch, ARCH_X86_64};
+
+/// An OS image a provider can deploy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImageInfo {
+    /// The identifier the provider's API takes
+    pub id: String,
+    pub name: String,
+    /// CPU architecture, `x86_64` or `arm64`
+    pub arch: String,
+}
+
+impl ImageInfo {
+    fn new(id: impl Into<String>, name: impl Into<String>, arch: &str) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            arch: normalize_arch(arch),
+        }
+    }
+}
+
+/// Images for providers without a list of their own
+pub const COMMON_IMAGES: &[(&str, &str, &str)] = &[
+    ("ubuntu-24.04", "Ubuntu 24.04 LTS", "x86_64"),
+    ("ubuntu-22.04", "Ubuntu 22.04 LTS", "x86_64"),
+    ("ubuntu-20.04", "Ubuntu 20.04 LTS", "x86_64"),
+    ("debian-12", "Debian 12", "x86_64"),
+    ("rocky-9", "Rocky Linux 9", "x86_64"),
+];
+
+/// A curated `(id, name, arch)` table as images
+pub fn static_images(images: &[(&str, &str, &str)]) -> Vec<ImageInfo> {
+    images.iter().map(|(id, name, arch)| ImageInfo::new(*id, *name, arch)).collect()
+}
+
+/// Canonical architecture for an API's spelling of it; Vultr says `x64`
+fn normalize_arch(arch: &str) -> String {
+    match arch {
+        "x64" => ARCH_X86_64.to_string(),
+        other => parse_arch(other).unwrap_or_else(|_| other.to_string()),
+    }
+}
+
+/// Parse DigitalOcean `GET /images?type=distribution`, skipping images
+/// without a slug (those can only be deployed by numeric ID)
+pub fn parse_digitalocean_images(body: &Value) -> Vec<ImageInfo> {
+    image_list(body, "images")
+        .filter_map(|image| {
+            let slug = image.get("slug")?.as_str()?;
+            let name = format!(
+                "{} {}",
+                image.get("distribution").and_then(Value::as_str).unwrap_or_default(),
+                image.get("name")?.as_str()?
+            );
+            Some(ImageInfo::new(slug, name.trim(), ARCH_X86_64))
+        })
+        .collect()
+}
+
+/// Parse Vultr `GET /os`
+pub fn parse_vultr_os(body: &Value) -> Vec<ImageInfo> {
+    image_list(body, "os")
+        .filter_map(|os| {
+            let id = os.get("id")?.as_i64()?;
+            let arch = os.get("arch").and_then(Value::as_str).unwrap_or(ARCH_X86_64);
+            Some(ImageInfo::new(id.to_string(), os.get("name")?.as_str()?, arch))
+        })
+        .collect()
+}
+
+/// Parse Linode `GET /images`, skipping deprecated images
+pub fn parse_linode_images(body: &Value) -> Vec<ImageInfo> {
+    image_list(body, "data")
+        .filter(|image| !image.get("deprecated").and_then(Value::as_bool).unwrap_or(false))
+        .filter_map(|image| {
+            Some(ImageInfo::new(image.get("id")?.as_str()?, image.get("label")?.as_str()?, ARCH_X86_64))
+        })
+        .collect()
+}
+
+fn image_list<'a>(body: &'a Value, key: &str) -> impl Iterator<Item = &'a Value> {
+    body.get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_image_catalogues() {
+        let images = parse_digitalocean_images(&json!({
+            "images": [
+                {"slug": "ubuntu-22-04-x64", "distribution": "Ubuntu", "name": "22.04 (LTS) x64"},
+                {"slug": null, "distribution": "Ubuntu", "name": "custom"}
+            ]
+        }));
+        assert_eq!(images, vec![ImageInfo::new("ubuntu-22-04-x64", "Ubuntu 22.04 (LTS) x64", "x86_64")]);
+
+        let images = parse_vultr_os(&json!({
+            "os": [
+                {"id": 1743, "name": "Ubuntu 22.04 LTS x64", "arch": "x64", "family": "ubuntu"},
+                {"id": 2076, "name": "Alpine Linux arm64", "arch": "arm64"}
+            ]
+        }));
+        assert_eq!(images[0].id, "1743");
+        assert_eq!(images[0].arch, "x86_64");
+        assert_eq!(images[1].arch, "arm64");
+
+        let images = parse_linode_images(&json!({
+            "data": [
+                {"id": "linode/ubuntu22.04", "label": "Ubuntu 22.04 LTS", "deprecated": false},
+                {"id": "linode/ubuntu16.04lts", "label": "Ubuntu 16.04 LTS", "deprecated": true}
+            ]
+        }));
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].id, "linode/ubuntu22.04");
+
+        assert!(parse_vultr_os(&json!({"error": "unauthorized"})).is_empty());
+    }
+}
@@ -1,13 +1,54 @@
-use super::{Provider, ProviderTemplate, Instance, DeployConfig};
+use super::images::{parse_linode_images, static_images, ImageInfo};
+use super::pricing::{apply_plan_prices, fetch_json, parse_linode_types};
+use super::{Provider, ProviderCapabilities, ProviderTemplate, ProviderTimeouts, Instance, DeployConfig};
 use anyhow::Result;
 
+use crate::api::LinodeClient;
+
+/// Location of each region slug
+const REGION_NAMES: &[(&str, &str)] = &[
+    ("us-east", "Newark"),
+    ("us-west", "Fremont"),
+    ("us-central", "Dallas"),
+    ("us-southeast", "Atlanta"),
+    ("eu-west", "London"),
+    ("eu-central", "Frankfurt"),
+    ("ap-south", "Singapore"),
+    ("ap-northeast", "Tokyo"),
+    ("ap-southeast", "Sydney"),
+    ("ca-central", "Toronto"),
+    ("au-sydney", "Sydney"),
+];
+
+/// Linode page in Cloud Manager
+const CONSOLE_URL: &str = "https://cloud.linode.com/linodes/{id}";
+
+/// Common images, listed when no API key is configured to fetch them
+const IMAGES: &[(&str, &str, &str)] = &[
+    ("linode/ubuntu24.04", "Ubuntu 24.04 LTS", "x86_64"),
+    ("linode/ubuntu22.04", "Ubuntu 22.04 LTS", "x86_64"),
+    ("linode/debian12", "Debian 12", "x86_64"),
+    ("linode/rocky9", "Rocky Linux 9", "x86_64"),
+];
+
 pub struct LinodeProvider {
     name: String,
     api_key: Option<String>,
     templates: Vec<ProviderTemplate>,
     regions: Vec<String>,
+    timeouts: ProviderTimeouts,
 }
 
+/// Template ID to Linode plan slug, for price refreshes
+const PLAN_SLUGS: &[(&str, &str)] = &[
+    ("linode-nanode-1gb", "g6-nanode-1"),
+    ("linode-2gb", "g6-standard-1"),
+    ("linode-4gb", "g6-standard-2"),
+    ("linode-dedicated-4gb", "g6-dedicated-2"),
+    ("linode-dedicated-8gb", "g6-dedicated-4"),
+    ("linode-gpu-rtx6000", "g1-gpu-rtx6000-1"),
+];
+
 impl LinodeProvider {
     pub fn new(api_key: Option<String>) -> Self {
         let mut provider = Self {
@@ -15,6 +56,7 @@ impl LinodeProvider {
             api_key,
             templates: Vec::new(),
             regions: Vec::new(),
+            timeouts: ProviderTimeouts::defaults(ProviderCapabilities::vm()),
         };
         provider.initialize_templates();
         provider.initialize_regions();
@@ -34,6 +76,7 @@ impl LinodeProvider {
                 price_hourly: 0.0075,
                 price_monthly: 5.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["us-east".into(), "us-west".into(), "eu-west".into(), "eu-central".into(), "ap-south".into()],
                 features: vec!["ssd".into(), "cloud".into()],
             },
@@ -48,6 +91,7 @@ impl LinodeProvider {
                 price_hourly: 0.015,
                 price_monthly: 10.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["us-east".into(), "us-west".into(), "us-central".into(), "eu-west".into(), "eu-central".into(), "ap-south".into(), "ap-northeast".into()],
                 features: vec!["ssd".into(), "cloud".into()],
             },
@@ -62,6 +106,7 @@ impl LinodeProvider {
                 price_hourly: 0.030,
                 price_monthly: 20.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["us-east".into(), "us-west".into(), "us-central".into(), "eu-west".into(), "eu-central".into(), "ap-south".into(), "ap-northeast".into(), "ap-southeast".into()],
                 features: vec!["ssd".into(), "cloud".into()],
             },
@@ -76,6 +121,7 @@ impl LinodeProvider {
                 price_hourly: 0.045,
                 price_monthly: 30.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["us-east".into(), "us-west".into(), "eu-west".into(), "ap-south".into()],
                 features: vec!["ssd".into(), "cloud".into(), "dedicated-cpu".into()],
             },
@@ -90,6 +136,7 @@ impl LinodeProvider {
                 price_hourly: 0.090,
                 price_monthly: 60.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["us-east".into(), "us-west".into(), "us-central".into(), "eu-west".into(), "eu-central".into(), "ap-south".into()],
                 features: vec!["ssd".into(), "cloud".into(), "dedicated-cpu".into(), "high-memory".into()],
             },
@@ -104,6 +151,7 @@ impl LinodeProvider {
                 price_hourly: 1.50,
                 price_monthly: 1000.00,
                 gpu: Some("NVIDIA RTX 6000".to_string()),
+                arch: "x86_64".into(),
                 regions: vec!["us-east".into(), "eu-west".into()],
                 features: vec!["ssd".into(), "cloud".into(), "gpu".into(), "dedicated-cpu".into()],
             },
@@ -136,10 +184,48 @@ impl Provider for LinodeProvider {
         &self.templates
     }
 
+    fn templates_mut(&mut self) -> &mut [ProviderTemplate] {
+        &mut self.templates
+    }
+
     fn regions(&self) -> &[String] {
         &self.regions
     }
 
+    fn region_display_name(&self, region: &str) -> Option<String> {
+        super::lookup_region(REGION_NAMES, region)
+    }
+
+    fn is_configured(&self) -> bool {
+        self.api_key.is_some()
+    }
+
+    fn list_images(&self) -> Result<Vec<ImageInfo>> {
+        let Some(api_key) = self.api_key.clone() else {
+            return Ok(static_images(IMAGES));
+        };
+
+        let client = LinodeClient::new(api_key)?.with_timeout(self.timeouts.list);
+        let body = fetch_json(client.client(), "/images?page_size=500")?;
+        Ok(parse_linode_images(&body))
+    }
+
+    fn console_url_template(&self) -> Option<&str> {
+        Some(CONSOLE_URL)
+    }
+
+    fn bandwidth_overage_per_tb(&self) -> f64 {
+        // $0.005/GB beyond the pooled transfer allowance
+        5.0
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            live_pricing: true,
+            ..ProviderCapabilities::vm()
+        }
+    }
+
     fn deploy(&self, template_id: &str, config: &DeployConfig) -> Result<Instance> {
         let template = self.get_template(template_id)
             .ok_or_else(|| anyhow::anyhow!("Template {} not found", template_id))?;
@@ -211,4 +297,52 @@ impl Provider for LinodeProvider {
         println!("Stopping Linode instance {}", instance_id);
         Ok(true)
     }
+
+    fn resize(&self, instance_id: &str, new_template_id: &str) -> Result<Instance> {
+        let template = self.get_template(new_template_id)
+            .ok_or_else(|| anyhow::anyhow!("Template {} not found", new_template_id))?;
+
+        if self.api_key.is_none() {
+            anyhow::bail!("Linode API key not configured");
+        }
+
+        // TODO: Actual API implementation
+        println!("Resizing Linode instance {} to {}", instance_id, new_template_id);
+
+        Ok(Instance {
+            id: instance_id.to_string(),
+            name: instance_id.to_string(),
+            provider: "linode".to_string(),
+            template: new_template_id.to_string(),
+            region: "".to_string(),
+            status: "resizing".to_string(),
+            ip_address: "".to_string(),
+            cost_hourly: template.price_hourly,
+            metadata: None,
+        })
+    }
+
+    fn snapshot_instance(&self, instance_id: &str, name: &str) -> Result<String> {
+        if self.api_key.is_none() {
+            anyhow::bail!("Linode API key not configured");
+        }
+
+        // TODO: Actual API implementation
+        println!("Creating Linode snapshot '{}' of instance {}", name, instance_id);
+        Ok(format!("linode-snap-{}-{}", instance_id, chrono::Utc::now().timestamp()))
+    }
+
+    fn refresh_pricing(&mut self) -> Result<()> {
+        let api_key = self.api_key.clone()
+            .ok_or_else(|| anyhow::anyhow!("Linode API key not configured"))?;
+
+        let client = LinodeClient::new(api_key)?.with_timeout(self.timeouts.list);
+        let body = fetch_json(client.client(), "/linode/types")?;
+        apply_plan_prices(&mut self.templates, PLAN_SLUGS, &parse_linode_types(&body));
+        Ok(())
+    }
+
+    fn set_timeouts(&mut self, timeouts: ProviderTimeouts) {
+        self.timeouts = timeouts;
+    }
 }
@@ -1,7 +1,16 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::exit::CliError;
+use images::{static_images, ImageInfo, COMMON_IMAGES};
+use pricing::PricingCache;
+
+/// Refreshed template prices, kept next to providers.yml
+const PRICING_CACHE_FILE: &str = "pricing.json";
 
 pub mod cherry;
 pub mod hivelocity;
@@ -11,6 +20,11 @@ pub mod aws;
 pub mod equinix;
 pub mod linode;
 pub mod scaleway;
+pub mod pricing;
+pub mod images;
+pub mod filter;
+#[cfg(any(test, feature = "testing"))]
+pub mod fake;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderTemplate {
@@ -25,14 +39,124 @@ pub struct ProviderTemplate {
     pub price_monthly: f64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub gpu: Option<String>,
+    /// CPU architecture, `x86_64` or `arm64`
+    #[serde(default = "default_arch")]
+    pub arch: String,
     pub regions: Vec<String>,
+    /// Free-form traits such as `bare-metal`, `nvme` or `dedicated-cpu`
     pub features: Vec<String>,
 }
 
+pub const ARCH_X86_64: &str = "x86_64";
+pub const ARCH_ARM64: &str = "arm64";
+
+fn default_arch() -> String {
+    ARCH_X86_64.to_string()
+}
+
+/// The canonical architecture name for `name`, accepting common aliases
+/// (`amd64`, `x86`, `aarch64`, `arm`)
+pub fn parse_arch(name: &str) -> Result<String, String> {
+    match name.to_lowercase().as_str() {
+        "x86_64" | "x86-64" | "x86" | "amd64" => Ok(ARCH_X86_64.to_string()),
+        "arm64" | "aarch64" | "arm" => Ok(ARCH_ARM64.to_string()),
+        other => Err(format!("unknown architecture '{}' (expected x86_64 or arm64)", other)),
+    }
+}
+
 impl ProviderTemplate {
     pub fn price_annual(&self) -> f64 {
         self.price_monthly * 12.0
     }
+
+    /// Dedicated hardware rather than a virtual machine
+    pub fn is_bare_metal(&self) -> bool {
+        self.features.iter().any(|f| f == "bare-metal")
+    }
+
+    /// The hardware this template offers, ignoring provider, name and price
+    pub fn spec(&self) -> TemplateSpec {
+        TemplateSpec {
+            cpu: self.cpu,
+            memory_gb: self.memory_gb,
+            storage_gb: self.storage_gb,
+            gpu: self.gpu.clone(),
+            arch: self.arch.clone(),
+        }
+    }
+
+    /// What each unit of hardware costs per month, for comparing templates
+    /// of different sizes
+    pub fn efficiency(&self) -> TemplateEfficiency {
+        let per = |amount: f64| (amount > 0.0).then(|| self.price_monthly / amount);
+        TemplateEfficiency {
+            per_cpu_monthly: per(self.cpu as f64),
+            per_gb_memory_monthly: per(self.memory_gb as f64),
+            per_gb_storage_monthly: per(self.storage_gb as f64),
+            per_tb_bandwidth_monthly: per(self.bandwidth_tb),
+        }
+    }
+}
+
+/// Monthly price per core, GB and TB of a template; `None` where the
+/// template includes none of that resource
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TemplateEfficiency {
+    pub per_cpu_monthly: Option<f64>,
+    pub per_gb_memory_monthly: Option<f64>,
+    pub per_gb_storage_monthly: Option<f64>,
+    pub per_tb_bandwidth_monthly: Option<f64>,
+}
+
+/// Templates with the same spec are interchangeable offerings of the same
+/// box; architecture counts, since arm64 and x86_64 images differ
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct TemplateSpec {
+    pub cpu: u32,
+    pub memory_gb: u32,
+    pub storage_gb: u32,
+    pub gpu: Option<String>,
+    pub arch: String,
+}
+
+/// Every template offering one spec, cheapest first
+#[derive(Debug, Clone)]
+pub struct SpecGroup {
+    pub spec: TemplateSpec,
+    pub templates: Vec<ProviderTemplate>,
+}
+
+impl SpecGroup {
+    pub fn cheapest(&self) -> &ProviderTemplate {
+        &self.templates[0]
+    }
+
+    /// The other templates offering this spec
+    pub fn alternatives(&self) -> &[ProviderTemplate] {
+        &self.templates[1..]
+    }
+}
+
+/// Collapse templates with the same spec, keeping groups in order of their
+/// cheapest hourly price
+pub fn group_by_spec(templates: Vec<ProviderTemplate>) -> Vec<SpecGroup> {
+    let mut groups: HashMap<TemplateSpec, Vec<ProviderTemplate>> = HashMap::new();
+    for template in templates {
+        groups.entry(template.spec()).or_default().push(template);
+    }
+
+    let by_price = |a: &ProviderTemplate, b: &ProviderTemplate| {
+        a.price_hourly.total_cmp(&b.price_hourly).then_with(|| a.provider.cmp(&b.provider))
+    };
+    let mut groups: Vec<SpecGroup> = groups
+        .into_iter()
+        .map(|(spec, mut templates)| {
+            templates.sort_by(by_price);
+            SpecGroup { spec, templates }
+        })
+        .collect();
+    groups.sort_by(|a, b| by_price(a.cheapest(), b.cheapest()).then_with(|| a.spec.cmp(&b.spec)));
+    groups
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,10 +169,24 @@ pub struct Instance {
     pub status: String,
     pub ip_address: String,
     pub cost_hourly: f64,
+    /// Provider-supplied extras; see `xnode::META_*` for the keys that
+    /// inventory and monitoring understand
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<serde_json::Value>,
 }
 
+impl Instance {
+    /// Metadata as a key/value map (non-object metadata is ignored)
+    pub fn metadata_map(&self) -> HashMap<String, serde_json::Value> {
+        match &self.metadata {
+            Some(serde_json::Value::Object(map)) => {
+                map.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+            }
+            _ => HashMap::new(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeployConfig {
     pub name: String,
@@ -61,11 +199,201 @@ pub struct DeployConfig {
     pub extra: HashMap<String, serde_json::Value>,
 }
 
+/// Lifecycle operations a provider may or may not support
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderOperation {
+    Start,
+    Stop,
+    Resize,
+    Snapshot,
+}
+
+impl std::fmt::Display for ProviderOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Start => "start",
+            Self::Stop => "stop",
+            Self::Resize => "resize",
+            Self::Snapshot => "snapshot",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// What a provider backend can actually do with its instances
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderCapabilities {
+    /// Instances can be powered off and on without being destroyed
+    pub start_stop: bool,
+    /// Instances can be moved to a different template in place
+    pub resize: bool,
+    /// Instances can be captured as a provider-side image
+    pub snapshot: bool,
+    /// Instances are dedicated physical machines
+    pub bare_metal: bool,
+    /// Template prices can be refreshed from the provider's plan catalogue
+    pub live_pricing: bool,
+    /// Deploys create a real instance that `get_instance` can poll, so
+    /// `deploy --follow` can wait for it
+    pub live_deploy: bool,
+    /// `list_instances` reports the provider's real instances. Stubbed
+    /// listings come back empty, which sync and prune must not read as
+    /// "nothing is running"
+    pub live_listing: bool,
+}
+
+impl ProviderCapabilities {
+    /// Everything a typical cloud VM supports
+    pub fn vm() -> Self {
+        Self {
+            start_stop: true,
+            resize: true,
+            snapshot: true,
+            bare_metal: false,
+            live_pricing: false,
+            live_deploy: false,
+            live_listing: false,
+        }
+    }
+
+    /// Dedicated servers: no power cycling or resizing through the API
+    pub fn bare_metal() -> Self {
+        Self {
+            start_stop: false,
+            resize: false,
+            snapshot: false,
+            bare_metal: true,
+            live_pricing: false,
+            live_deploy: false,
+            live_listing: false,
+        }
+    }
+
+    pub fn supports(&self, operation: ProviderOperation) -> bool {
+        match operation {
+            ProviderOperation::Start | ProviderOperation::Stop => self.start_stop,
+            ProviderOperation::Resize => self.resize,
+            ProviderOperation::Snapshot => self.snapshot,
+        }
+    }
+}
+
+impl Default for ProviderCapabilities {
+    fn default() -> Self {
+        Self::vm()
+    }
+}
+
+impl DeployConfig {
+    /// A string-valued `extra` field
+    pub fn extra_str(&self, key: &str) -> Option<&str> {
+        self.extra.get(key).and_then(|v| v.as_str())
+    }
+
+    /// Check this config against `provider` before any API call is made
+    pub fn validate_for(&self, provider: &dyn Provider) -> std::result::Result<(), DeployConfigError> {
+        let mut problems = Vec::new();
+
+        if self.name.trim().is_empty() {
+            problems.push(DeployFieldError::Missing("name".to_string()));
+        }
+
+        if self.region.is_empty() {
+            problems.push(DeployFieldError::Missing("region".to_string()));
+        } else if !provider.regions().contains(&self.region) {
+            problems.push(DeployFieldError::Invalid {
+                field: "region".to_string(),
+                reason: format!("not offered by {} (available: {})", provider.name(), provider.regions().join(", ")),
+            });
+        }
+
+        if let Some(keys) = &self.ssh_keys {
+            if keys.iter().any(|k| k.trim().is_empty()) {
+                problems.push(DeployFieldError::Invalid {
+                    field: "ssh_keys".to_string(),
+                    reason: "contains an empty key".to_string(),
+                });
+            }
+        }
+
+        problems.extend(provider.check_deploy_config(self));
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(DeployConfigError {
+                provider: provider.name().to_string(),
+                problems,
+            })
+        }
+    }
+}
+
+/// One problem found while validating a `DeployConfig`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeployFieldError {
+    Missing(String),
+    Invalid { field: String, reason: String },
+}
+
+impl DeployFieldError {
+    /// Require `extra[key]` to be a string starting with `prefix`
+    pub fn check_prefixed(config: &DeployConfig, key: &str, prefix: &str, required: bool) -> Option<Self> {
+        match config.extra.get(key) {
+            None if required => Some(Self::Missing(key.to_string())),
+            None => None,
+            Some(value) => match value.as_str() {
+                Some(s) if s.starts_with(prefix) && s.len() > prefix.len() => None,
+                _ => Some(Self::Invalid {
+                    field: key.to_string(),
+                    reason: format!("expected an ID starting with '{}'", prefix),
+                }),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for DeployFieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing(field) => write!(f, "missing required field '{}'", field),
+            Self::Invalid { field, reason } => write!(f, "invalid '{}': {}", field, reason),
+        }
+    }
+}
+
+/// A `DeployConfig` the provider would reject, with every problem found
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid deploy configuration for {provider}:{}", .problems.iter().map(|p| format!("\n  - {}", p)).collect::<String>())]
+pub struct DeployConfigError {
+    pub provider: String,
+    pub problems: Vec<DeployFieldError>,
+}
+
 pub trait Provider: Send + Sync {
     fn name(&self) -> &str;
     fn templates(&self) -> &[ProviderTemplate];
+
+    /// Templates whose prices a pricing refresh may update; providers that
+    /// don't expose them keep their built-in prices
+    fn templates_mut(&mut self) -> &mut [ProviderTemplate] {
+        &mut []
+    }
+
     fn regions(&self) -> &[String];
 
+    /// Human-friendly location of a region slug, e.g. "Stockholm" for
+    /// `eu-nord-1`; `None` if the provider doesn't know the region
+    fn region_display_name(&self, _region: &str) -> Option<String> {
+        None
+    }
+
+    /// OS images `DeployConfig::os` may name; a curated list unless the
+    /// provider can ask its API
+    fn list_images(&self) -> Result<Vec<ImageInfo>> {
+        Ok(static_images(COMMON_IMAGES))
+    }
+
     fn deploy(&self, template_id: &str, config: &DeployConfig) -> Result<Instance>;
     fn list_instances(&self) -> Result<Vec<Instance>>;
     fn get_instance(&self, instance_id: &str) -> Result<Instance>;
@@ -73,24 +401,377 @@ pub trait Provider: Send + Sync {
     fn start_instance(&self, instance_id: &str) -> Result<bool>;
     fn stop_instance(&self, instance_id: &str) -> Result<bool>;
 
+    /// Move an instance to a different template of the same provider
+    fn resize(&self, instance_id: &str, new_template_id: &str) -> Result<Instance> {
+        let _ = new_template_id;
+        Err(CliError::usage(format!(
+            "Provider {} does not support resizing instance {}",
+            self.name(),
+            instance_id
+        ))
+        .into())
+    }
+
+    /// Capture an instance as an image, returning the provider's snapshot ID
+    fn snapshot_instance(&self, instance_id: &str, name: &str) -> Result<String> {
+        let _ = name;
+        Err(CliError::usage(format!(
+            "Provider {} does not support snapshots of instance {}",
+            self.name(),
+            instance_id
+        ))
+        .into())
+    }
+
     fn get_template(&self, template_id: &str) -> Option<&ProviderTemplate> {
         self.templates().iter().find(|t| t.id == template_id)
     }
 
+    /// Whether the provider has credentials to reach its API. Unconfigured
+    /// providers answer `list_instances` with an empty list, which must not
+    /// be mistaken for "nothing is running".
+    fn is_configured(&self) -> bool {
+        true
+    }
+
     fn validate_credentials(&self) -> Result<bool> {
         // Default implementation - can be overridden
         Ok(true)
     }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities::default()
+    }
+
+    /// List price per TB of egress beyond a template's `bandwidth_tb`
+    fn bandwidth_overage_per_tb(&self) -> f64 {
+        DEFAULT_BANDWIDTH_OVERAGE_PER_TB
+    }
+
+    /// Web console page for an instance, with `{id}` and `{region}`
+    /// placeholders; `None` if the provider has no console
+    fn console_url_template(&self) -> Option<&str> {
+        None
+    }
+
+    /// Update template prices from the provider's API; a no-op for providers
+    /// without a plan catalogue (see `ProviderCapabilities::live_pricing`)
+    fn refresh_pricing(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Provider-specific checks on `config.extra`, run before deploy
+    fn check_deploy_config(&self, config: &DeployConfig) -> Vec<DeployFieldError> {
+        let _ = config;
+        Vec::new()
+    }
+
+    /// Use `timeouts` for API requests; a no-op for providers that make
+    /// no API calls yet
+    fn set_timeouts(&mut self, timeouts: ProviderTimeouts) {
+        let _ = timeouts;
+    }
+}
+
+/// Egress overage price for providers that don't list their own ($0.01/GB)
+pub const DEFAULT_BANDWIDTH_OVERAGE_PER_TB: f64 = 10.0;
+
+/// `providers.yml` setting overriding a provider's overage price
+const BANDWIDTH_OVERAGE_SETTING: &str = "bandwidth_overage_per_tb";
+
+/// `providers.yml` setting overriding a provider's console URL template
+const CONSOLE_URL_SETTING: &str = "console_url";
+
+/// Fill a console URL template for one instance. Fails if the template
+/// needs a region and none is known.
+pub fn console_url(template: &str, instance_id: &str, region: Option<&str>) -> Result<String> {
+    let url = template.replace("{id}", instance_id);
+    if !url.contains("{region}") {
+        return Ok(url);
+    }
+    match region {
+        Some(region) => Ok(url.replace("{region}", region)),
+        None => Err(CliError::usage(format!("The console URL for instance {} needs its region, which is unknown", instance_id)).into()),
+    }
+}
+
+/// Find `region` in a provider's slug→location table
+pub(crate) fn lookup_region(names: &[(&str, &str)], region: &str) -> Option<String> {
+    names.iter().find(|(slug, _)| *slug == region).map(|(_, name)| name.to_string())
+}
+
+/// `region` labelled with its location where known: "Stockholm (eu-nord-1)".
+/// Slugs that already spell out the location ("new-york") get the name alone.
+pub fn region_label(provider: &dyn Provider, region: &str) -> String {
+    match provider.region_display_name(region) {
+        Some(name) if name.to_lowercase().replace(' ', "-") != region => format!("{} ({})", name, region),
+        Some(name) => name,
+        None => region.to_string(),
+    }
+}
+
+/// A region a provider offers: the slug its API takes and, where known,
+/// the location it is in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Region {
+    pub slug: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl Region {
+    fn new(provider: &dyn Provider, slug: &str) -> Self {
+        Self {
+            slug: slug.to_string(),
+            name: provider.region_display_name(slug).filter(|name| name != slug),
+        }
+    }
+
+    /// Whether `input` names this region, ignoring case and treating
+    /// spaces and underscores as dashes
+    fn matches(&self, input: &str) -> bool {
+        let input = normalize_region(input);
+        normalize_region(&self.slug) == input || self.name.as_deref().is_some_and(|n| normalize_region(n) == input)
+    }
+}
+
+fn normalize_region(region: &str) -> String {
+    region.trim().to_lowercase().replace([' ', '_'], "-")
+}
+
+/// Whether a provider has an API key in `providers.yml`, and optionally
+/// whether the provider accepted it. Never includes the key itself.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CredentialStatus {
+    pub provider: String,
+    pub configured: bool,
+    /// Result of `validate_credentials`, if it was asked for and succeeded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid: Option<bool>,
+    /// Why validation could not complete
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Credentials and settings for one provider in `providers.yml`.
+///
+/// `Serialize` writes the real key and is only for saving the file; use
+/// `redacted()` for anything shown to a user. `Debug` masks secrets.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ProviderConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub api_key: Option<String>,
+    /// Request timeouts overriding the provider's defaults
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeouts: Option<TimeoutSettings>,
+    /// Tags recorded on every xNode deployed to this provider, alongside
+    /// any given with `--tag`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub default_tags: Vec<String>,
     #[serde(flatten)]
     pub extra: HashMap<String, serde_json::Value>,
 }
 
+/// Setting names containing any of these are treated as secrets
+const SECRET_FIELD_MARKERS: &[&str] = &["key", "secret", "token", "password"];
+
+/// Mask a secret for display, keeping enough to tell keys apart:
+/// `sk-...7f3a`. Short values are masked entirely.
+pub fn mask_secret(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    if chars.len() < 12 {
+        return "****".to_string();
+    }
+    let prefix: String = chars[..3].iter().collect();
+    let suffix: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}...{}", prefix, suffix)
+}
+
+impl ProviderConfig {
+    /// A copy safe to print or export: the API key and any string setting
+    /// whose name looks secret are masked
+    pub fn redacted(&self) -> ProviderConfig {
+        let extra = self
+            .extra
+            .iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    serde_json::Value::String(secret) if is_secret_field(name) => {
+                        serde_json::Value::String(mask_secret(secret))
+                    }
+                    other => other.clone(),
+                };
+                (name.clone(), value)
+            })
+            .collect();
+
+        ProviderConfig {
+            api_key: self.api_key.as_deref().map(mask_secret),
+            timeouts: self.timeouts,
+            default_tags: self.default_tags.clone(),
+            extra,
+        }
+    }
+
+    /// A copy with the API key and secret string settings left out
+    /// entirely, for backups that must not carry credentials
+    pub fn without_secrets(&self) -> ProviderConfig {
+        ProviderConfig {
+            api_key: None,
+            timeouts: self.timeouts,
+            default_tags: self.default_tags.clone(),
+            extra: self
+                .extra
+                .iter()
+                .filter(|(name, value)| !(value.is_string() && is_secret_field(name)))
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+        }
+    }
+
+    /// Fill in secrets this config lacks from `current`, so restoring a
+    /// backup made `without_secrets` keeps the keys already configured
+    pub fn keep_secrets_from(&mut self, current: &ProviderConfig) {
+        if self.api_key.is_none() {
+            self.api_key = current.api_key.clone();
+        }
+        for (name, value) in &current.extra {
+            if value.is_string() && is_secret_field(name) {
+                self.extra.entry(name.clone()).or_insert_with(|| value.clone());
+            }
+        }
+    }
+}
+
+fn is_secret_field(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    SECRET_FIELD_MARKERS.iter().any(|marker| lower.contains(marker))
+}
+
+impl fmt::Debug for ProviderConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let redacted = self.redacted();
+        f.debug_struct("ProviderConfig")
+            .field("api_key", &redacted.api_key)
+            .field("timeouts", &redacted.timeouts)
+            .field("default_tags", &redacted.default_tags)
+            .field("extra", &redacted.extra)
+            .finish()
+    }
+}
+
+/// Timeouts from `providers.yml`, in seconds:
+///
+/// ```yaml
+/// equinix:
+///   api_key: ...
+///   timeouts:
+///     list: 15
+///     deploy: 900
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeoutSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub list: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deploy: Option<u64>,
+}
+
+/// Request timeouts for one provider's API. `list` covers reads (listing,
+/// instance lookups, plan catalogues), which should fail fast on a dead
+/// endpoint; `deploy` covers calls that create or change instances, which
+/// bare-metal providers can take minutes to answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProviderTimeouts {
+    pub list: Duration,
+    pub deploy: Duration,
+}
+
+impl ProviderTimeouts {
+    /// Cloud VMs: 10s reads, 2 minutes for provisioning calls.
+    /// Bare metal: 20s reads, 10 minutes for provisioning calls.
+    pub fn defaults(capabilities: ProviderCapabilities) -> Self {
+        if capabilities.bare_metal {
+            Self { list: Duration::from_secs(20), deploy: Duration::from_secs(600) }
+        } else {
+            Self { list: Duration::from_secs(10), deploy: Duration::from_secs(120) }
+        }
+    }
+
+    /// These timeouts with any values set in `settings` replacing them
+    pub fn with_settings(self, settings: &TimeoutSettings) -> Result<Self> {
+        let seconds = |value: Option<u64>, default: Duration, operation: &str| match value {
+            Some(0) => anyhow::bail!("{} timeout must be at least 1 second", operation),
+            Some(secs) => Ok(Duration::from_secs(secs)),
+            None => Ok(default),
+        };
+        Ok(Self {
+            list: seconds(settings.list, self.list, "list")?,
+            deploy: seconds(settings.deploy, self.deploy, "deploy")?,
+        })
+    }
+}
+
+/// Instance statuses that mean provisioning will not complete
+const FAILED_STATUSES: &[&str] = &["error", "failed"];
+
+/// Failed polls in a row `wait_for_running` rides out before giving up
+const MAX_CONSECUTIVE_POLL_ERRORS: u32 = 3;
+
+/// Poll `provider.get_instance` until the instance is running, calling
+/// `on_change` with each new status and the time since polling began.
+/// Returns `None` if `timeout` passes first. A few failed polls in a row
+/// are tolerated, since APIs often hiccup while an instance is created.
+pub fn wait_for_running(
+    provider: &dyn Provider,
+    instance_id: &str,
+    timeout: Duration,
+    interval: Duration,
+    mut on_change: impl FnMut(&str, Duration),
+) -> Result<Option<Instance>> {
+    let started = Instant::now();
+    let mut last_status: Option<String> = None;
+    let mut poll_errors = 0;
+
+    loop {
+        match provider.get_instance(instance_id) {
+            Ok(instance) => {
+                poll_errors = 0;
+                if last_status.as_deref() != Some(instance.status.as_str()) {
+                    on_change(&instance.status, started.elapsed());
+                    last_status = Some(instance.status.clone());
+                }
+
+                if instance.status == "running" {
+                    return Ok(Some(instance));
+                }
+                if FAILED_STATUSES.contains(&instance.status.as_str()) {
+                    anyhow::bail!("Instance {} failed to provision ({})", instance_id, instance.status);
+                }
+            }
+            Err(e) => {
+                poll_errors += 1;
+                if poll_errors >= MAX_CONSECUTIVE_POLL_ERRORS || started.elapsed() >= timeout {
+                    return Err(e.context(format!("Failed to poll instance {}", instance_id)));
+                }
+                log::warn!(
+                    "Polling {} failed ({}/{}): {:#}",
+                    instance_id,
+                    poll_errors,
+                    MAX_CONSECUTIVE_POLL_ERRORS,
+                    e
+                );
+            }
+        }
+
+        let elapsed = started.elapsed();
+        if elapsed >= timeout {
+            return Ok(None);
+        }
+        std::thread::sleep(interval.min(timeout - elapsed));
+    }
+}
+
 pub struct ProviderManager {
     config_file: PathBuf,
     config: HashMap<String, ProviderConfig>,
@@ -99,6 +780,27 @@ pub struct ProviderManager {
 
 impl ProviderManager {
     pub fn new(config_file: Option<PathBuf>) -> Result<Self> {
+        let mut manager = Self::empty(config_file)?;
+        manager.initialize_providers()?;
+        Ok(manager)
+    }
+
+    /// A manager over exactly `providers` instead of the built-in backends,
+    /// for tests and embedding
+    pub fn with_providers(
+        config_file: Option<PathBuf>,
+        providers: Vec<Box<dyn Provider>>,
+    ) -> Result<Self> {
+        let mut manager = Self::empty(config_file)?;
+        for provider in providers {
+            manager.register_provider(provider);
+        }
+        manager.apply_timeouts()?;
+        manager.apply_cached_pricing()?;
+        Ok(manager)
+    }
+
+    fn empty(config_file: Option<PathBuf>) -> Result<Self> {
         let config_file = config_file.unwrap_or_else(|| {
             let home = home::home_dir().expect("Could not find home directory");
             home.join(".capsule").join("providers.yml")
@@ -111,14 +813,16 @@ impl ProviderManager {
             HashMap::new()
         };
 
-        let mut manager = Self {
+        Ok(Self {
             config_file,
             config,
             providers: HashMap::new(),
-        };
+        })
+    }
 
-        manager.initialize_providers()?;
-        Ok(manager)
+    /// Add or replace a provider, keyed by its name
+    pub fn register_provider(&mut self, provider: Box<dyn Provider>) {
+        self.providers.insert(provider.name().to_string(), provider);
     }
 
     fn initialize_providers(&mut self) -> Result<()> {
@@ -187,9 +891,95 @@ impl ProviderManager {
             Box::new(scaleway::ScalewayProvider::new(scaleway_api_key)),
         );
 
+        self.apply_timeouts()?;
+        self.apply_cached_pricing()?;
         Ok(())
     }
 
+    /// Request timeouts for `provider_name`: its defaults, overridden by
+    /// `timeouts` in `providers.yml`
+    pub fn timeouts(&self, provider_name: &str) -> Result<Option<ProviderTimeouts>> {
+        let Some(provider) = self.providers.get(provider_name) else {
+            return Ok(None);
+        };
+        let defaults = ProviderTimeouts::defaults(provider.capabilities());
+        match self.config.get(provider_name).and_then(|c| c.timeouts.as_ref()) {
+            Some(settings) => defaults
+                .with_settings(settings)
+                .with_context(|| format!("Invalid timeouts for {} in {}", provider_name, self.config_file.display()))
+                .map(Some),
+            None => Ok(Some(defaults)),
+        }
+    }
+
+    fn apply_timeouts(&mut self) -> Result<()> {
+        for name in self.list_providers() {
+            if let Some(timeouts) = self.timeouts(&name)? {
+                if let Some(provider) = self.providers.get_mut(&name) {
+                    provider.set_timeouts(timeouts);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn pricing_cache(&self) -> Result<PricingCache> {
+        PricingCache::load(&self.config_file.with_file_name(PRICING_CACHE_FILE))
+    }
+
+    /// Overlay previously refreshed prices on the built-in templates
+    fn apply_cached_pricing(&mut self) -> Result<()> {
+        let cache = self.pricing_cache()?;
+        for (name, pricing) in &cache.providers {
+            if let Some(provider) = self.providers.get_mut(name) {
+                for template in provider.templates_mut() {
+                    if let Some(price) = pricing.prices.get(&template.id) {
+                        pricing::set_price(template, *price);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Refresh prices for every provider with a plan catalogue, caching the
+    /// results. Returns each provider with the number of prices that changed.
+    pub fn refresh_pricing(&mut self) -> Result<Vec<(String, Result<usize>)>> {
+        let mut cache = self.pricing_cache()?;
+        let mut results = Vec::new();
+
+        for name in self.list_providers() {
+            let Some(provider) = self.providers.get_mut(&name) else {
+                continue;
+            };
+            if !provider.capabilities().live_pricing {
+                continue;
+            }
+
+            let before: Vec<(f64, f64)> = provider.templates()
+                .iter()
+                .map(|t| (t.price_hourly, t.price_monthly))
+                .collect();
+            let result = provider.refresh_pricing().map(|()| {
+                cache.record(&name, provider.templates());
+                provider.templates()
+                    .iter()
+                    .zip(&before)
+                    .filter(|(t, (hourly, monthly))| t.price_hourly != *hourly || t.price_monthly != *monthly)
+                    .count()
+            });
+            results.push((name, result));
+        }
+
+        cache.save()?;
+        Ok(results)
+    }
+
+    /// When cached prices were last refreshed (oldest provider), if ever
+    pub fn pricing_refreshed_at(&self) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+        Ok(self.pricing_cache()?.oldest_refresh())
+    }
+
     pub fn list_providers(&self) -> Vec<String> {
         let mut providers: Vec<String> = self.providers.keys().cloned().collect();
         // Sort providers, but Cherry Servers always first!
@@ -201,8 +991,51 @@ impl ProviderManager {
         providers
     }
 
-    pub fn get_provider(&self, name: &str) -> Option<&Box<dyn Provider>> {
-        self.providers.get(name)
+    pub fn get_provider(&self, name: &str) -> Option<&dyn Provider> {
+        self.providers.get(name).map(|p| p.as_ref())
+    }
+
+    /// Every region `provider_name` offers, in the provider's order
+    pub fn regions(&self, provider_name: &str) -> Result<Vec<Region>> {
+        let provider = self.get_provider(provider_name)
+            .ok_or_else(|| CliError::not_found(format!("Provider {} not found", provider_name)))?;
+        Ok(provider.regions().iter().map(|slug| Region::new(provider, slug)).collect())
+    }
+
+    /// The OS images `provider_name` can deploy
+    pub fn images(&self, provider_name: &str) -> Result<Vec<ImageInfo>> {
+        let provider = self.get_provider(provider_name)
+            .ok_or_else(|| CliError::not_found(format!("Provider {} not found", provider_name)))?;
+        provider.list_images()
+    }
+
+    /// The slug of the `provider_name` region that `input` names, by slug
+    /// or location ("EU_NORD_1" or "stockholm" for Cherry's `eu-nord-1`)
+    pub fn resolve_region(&self, provider_name: &str, input: &str) -> Result<String> {
+        let regions = self.regions(provider_name)?;
+        if let Some(region) = regions.iter().find(|r| r.slug == input) {
+            return Ok(region.slug.clone());
+        }
+
+        let matches: Vec<&Region> = regions.iter().filter(|r| r.matches(input)).collect();
+        match matches.as_slice() {
+            [region] => Ok(region.slug.clone()),
+            [] => Err(CliError::usage(format!(
+                "Unknown region '{}' for {} (available: {}); see `capsule openmesh provider regions {}`",
+                input,
+                provider_name,
+                regions.iter().map(|r| r.slug.as_str()).collect::<Vec<_>>().join(", "),
+                provider_name
+            ))
+            .into()),
+            several => Err(CliError::usage(format!(
+                "Region '{}' is ambiguous for {}; use one of: {}",
+                input,
+                provider_name,
+                several.iter().map(|r| r.slug.as_str()).collect::<Vec<_>>().join(", ")
+            ))
+            .into()),
+        }
     }
 
     pub fn get_all_templates(&self) -> Vec<ProviderTemplate> {
@@ -213,6 +1046,36 @@ impl ProviderManager {
         templates
     }
 
+    /// The template with `template_id`, searching every provider unless
+    /// `provider_name` narrows it. Fails if several providers use the ID.
+    pub fn find_template(&self, template_id: &str, provider_name: Option<&str>) -> Result<ProviderTemplate> {
+        let mut matches: Vec<&ProviderTemplate> = Vec::new();
+        for name in self.list_providers() {
+            if provider_name.is_some_and(|p| p != name) {
+                continue;
+            }
+            if let Some(template) = self.providers[&name].get_template(template_id) {
+                matches.push(template);
+            }
+        }
+
+        match matches.as_slice() {
+            [template] => Ok((*template).clone()),
+            [] => Err(CliError::not_found(match provider_name {
+                Some(provider) if self.get_provider(provider).is_none() => format!("Provider {} not found", provider),
+                Some(provider) => format!("Template '{}' not found for provider {}", template_id, provider),
+                None => format!("Template '{}' not found; see `capsule openmesh xnode templates --all`", template_id),
+            })
+            .into()),
+            several => Err(CliError::usage(format!(
+                "Template '{}' is offered by several providers ({}); pass --provider",
+                template_id,
+                several.iter().map(|t| t.provider.as_str()).collect::<Vec<_>>().join(", ")
+            ))
+            .into()),
+        }
+    }
+
     pub fn compare_templates(
         &self,
         min_cpu: u32,
@@ -252,9 +1115,30 @@ impl ProviderManager {
         let provider = self.get_provider(provider_name)
             .ok_or_else(|| anyhow::anyhow!("Provider {} not found", provider_name))?;
 
+        config.validate_for(provider)?;
         provider.deploy(template_id, config)
     }
 
+    /// Look up a provider, refusing operations its backend does not support
+    pub fn provider_supporting(
+        &self,
+        provider_name: &str,
+        operation: ProviderOperation,
+    ) -> Result<&dyn Provider> {
+        let provider = self.get_provider(provider_name)
+            .ok_or_else(|| CliError::not_found(format!("Provider {} not found", provider_name)))?;
+
+        if !provider.capabilities().supports(operation) {
+            return Err(CliError::usage(format!(
+                "Provider {} does not support {} for its instances",
+                provider_name, operation
+            ))
+            .into());
+        }
+
+        Ok(provider)
+    }
+
     pub fn configure_provider(&mut self, provider_name: String, api_key: String) -> Result<()> {
         if !self.providers.contains_key(&provider_name) {
             anyhow::bail!("Unknown provider: {}", provider_name);
@@ -264,6 +1148,8 @@ impl ProviderManager {
             .entry(provider_name.clone())
             .or_insert_with(|| ProviderConfig {
                 api_key: None,
+                timeouts: None,
+                default_tags: Vec::new(),
                 extra: HashMap::new(),
             })
             .api_key = Some(api_key);
@@ -275,6 +1161,76 @@ impl ProviderManager {
         Ok(())
     }
 
+    /// Forget a provider's API key and secret settings, keeping timeouts and
+    /// other settings. Returns false if it had no credentials stored.
+    pub fn remove_provider_credentials(&mut self, provider_name: &str) -> Result<bool> {
+        if !self.providers.contains_key(provider_name) {
+            return Err(CliError::not_found(format!("Unknown provider: {}", provider_name)).into());
+        }
+        let Some(current) = self.config.get(provider_name) else {
+            return Ok(false);
+        };
+
+        let remaining = current.without_secrets();
+        if remaining.extra.len() == current.extra.len() && current.api_key.is_none() {
+            return Ok(false);
+        }
+        if remaining.timeouts.is_none() && remaining.default_tags.is_empty() && remaining.extra.is_empty() {
+            self.config.remove(provider_name);
+        } else {
+            self.config.insert(provider_name.to_string(), remaining);
+        }
+
+        self.save_config()?;
+        self.initialize_providers()?;
+        Ok(true)
+    }
+
+    /// Price per TB of egress beyond the included allowance: the provider's
+    /// `bandwidth_overage_per_tb` setting in `providers.yml`, else its list price
+    pub fn bandwidth_overage_per_tb(&self, provider_name: &str) -> Option<f64> {
+        let provider = self.get_provider(provider_name)?;
+        let configured = self
+            .config
+            .get(provider_name)
+            .and_then(|c| c.extra.get(BANDWIDTH_OVERAGE_SETTING))
+            .and_then(|v| v.as_f64())
+            .filter(|rate| rate.is_finite() && *rate >= 0.0);
+        Some(configured.unwrap_or_else(|| provider.bandwidth_overage_per_tb()))
+    }
+
+    /// Console URL template for a provider: its `console_url` setting in
+    /// `providers.yml`, else the built-in one
+    pub fn console_url_template(&self, provider_name: &str) -> Option<String> {
+        let provider = self.get_provider(provider_name)?;
+        let configured = self
+            .config
+            .get(provider_name)
+            .and_then(|c| c.extra.get(CONSOLE_URL_SETTING))
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+        configured.or_else(|| provider.console_url_template().map(str::to_string))
+    }
+
+    /// Tags to record for a node deployed to a provider: its `default_tags`
+    /// from `providers.yml` followed by `tags`, without duplicates
+    pub fn deploy_tags(&self, provider_name: &str, tags: &[String]) -> Vec<String> {
+        let defaults = self
+            .config
+            .get(provider_name)
+            .map(|c| c.default_tags.as_slice())
+            .unwrap_or_default();
+
+        let mut merged: Vec<String> = Vec::new();
+        for tag in defaults.iter().chain(tags) {
+            let tag = tag.trim();
+            if !tag.is_empty() && !merged.iter().any(|t| t == tag) {
+                merged.push(tag.to_string());
+            }
+        }
+        merged
+    }
+
     pub fn has_credentials(&self, provider_name: &str) -> bool {
         self.config
             .get(provider_name)
@@ -282,6 +1238,33 @@ impl ProviderManager {
             .is_some()
     }
 
+    /// The contents of `providers.yml` with secrets masked, for display
+    pub fn redacted_config(&self) -> std::collections::BTreeMap<String, ProviderConfig> {
+        self.config
+            .iter()
+            .map(|(name, config)| (name.clone(), config.redacted()))
+            .collect()
+    }
+
+    /// Credential status of every provider in `list_providers` order. With
+    /// `verify`, configured keys are checked with `validate_credentials`.
+    pub fn credential_status(&self, verify: bool) -> Vec<CredentialStatus> {
+        self.list_providers()
+            .into_iter()
+            .map(|name| {
+                let configured = self.has_credentials(&name);
+                let check = (verify && configured)
+                    .then(|| self.providers[&name].validate_credentials());
+                let (valid, error) = match check {
+                    Some(Ok(valid)) => (Some(valid), None),
+                    Some(Err(e)) => (None, Some(format!("{:#}", e))),
+                    None => (None, None),
+                };
+                CredentialStatus { provider: name, configured, valid, error }
+            })
+            .collect()
+    }
+
     fn save_config(&self) -> Result<()> {
         if let Some(parent) = self.config_file.parent() {
             std::fs::create_dir_all(parent)?;
@@ -292,3 +1275,457 @@ impl ProviderManager {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_wait_for_running_reports_transitions() {
+        let fake = fake::FakeProvider::default().with_boot_states(&["new", "provisioning"]);
+        let config = DeployConfig {
+            name: "web".to_string(),
+            region: "test-1".to_string(),
+            os: None,
+            ssh_keys: None,
+            extra: HashMap::new(),
+        };
+        let instance = fake.deploy("small", &config).unwrap();
+        assert_eq!(instance.status, "new");
+
+        let mut seen = Vec::new();
+        let running = wait_for_running(&fake, &instance.id, Duration::from_secs(5), Duration::ZERO, |status, _| {
+            seen.push(status.to_string())
+        })
+        .unwrap()
+        .unwrap();
+        assert_eq!(running.status, "running");
+        assert_eq!(seen, ["new", "provisioning", "running"]);
+
+        let stuck = fake::FakeProvider::default().with_boot_states(&["new"; 1000]);
+        let instance = stuck.deploy("small", &config).unwrap();
+        let result = wait_for_running(&stuck, &instance.id, Duration::ZERO, Duration::ZERO, |_, _| {}).unwrap();
+        assert!(result.is_none());
+
+        let failing = fake::FakeProvider::default().with_boot_states(&["new", "error"]);
+        let instance = failing.deploy("small", &config).unwrap();
+        assert!(wait_for_running(&failing, &instance.id, Duration::from_secs(5), Duration::ZERO, |_, _| {}).is_err());
+
+        let flaky = fake::FakeProvider::default().with_boot_states(&["new"]).with_poll_errors(2);
+        let instance = flaky.deploy("small", &config).unwrap();
+        let running = wait_for_running(&flaky, &instance.id, Duration::from_secs(5), Duration::ZERO, |_, _| {}).unwrap();
+        assert_eq!(running.unwrap().status, "running");
+
+        let down = fake::FakeProvider::default().with_boot_states(&["new"]).with_poll_errors(3);
+        let instance = down.deploy("small", &config).unwrap();
+        assert!(wait_for_running(&down, &instance.id, Duration::from_secs(5), Duration::ZERO, |_, _| {}).is_err());
+    }
+
+    #[test]
+    fn test_provider_config_redaction() {
+        let config: ProviderConfig = serde_yaml::from_str(
+            "api_key: sk-live-0123456789abcdef\nsecret_access_key: AKIAABCDEFGHIJKL\nregion: nyc3\npin: 1234\n",
+        )
+        .unwrap();
+
+        let redacted = config.redacted();
+        assert_eq!(redacted.api_key.as_deref(), Some("sk-...cdef"));
+        assert_eq!(redacted.extra["secret_access_key"], "AKI...IJKL");
+        assert_eq!(redacted.extra["region"], "nyc3");
+        assert_eq!(mask_secret("short"), "****");
+
+        let debug = format!("{:?}", config);
+        assert!(!debug.contains("0123456789"));
+        assert!(debug.contains("sk-...cdef"));
+
+        // Saving keeps the real values
+        let saved = serde_yaml::to_string(&config).unwrap();
+        assert!(saved.contains("sk-live-0123456789abcdef"));
+
+        let mut stripped = config.without_secrets();
+        assert!(stripped.api_key.is_none());
+        assert!(!stripped.extra.contains_key("secret_access_key"));
+        assert_eq!(stripped.extra["region"], "nyc3");
+        stripped.keep_secrets_from(&config);
+        assert_eq!(stripped.api_key, config.api_key);
+        assert_eq!(stripped.extra["secret_access_key"], "AKIAABCDEFGHIJKL");
+    }
+
+    #[test]
+    fn test_credential_status_hides_keys() {
+        let dir = tempdir().unwrap();
+        let config = dir.path().join("providers.yml");
+        std::fs::write(&config, "hivelocity:\n  api_key: secret-key-123\n").unwrap();
+        let manager = ProviderManager::new(Some(config)).unwrap();
+
+        let status = manager.credential_status(false);
+        assert_eq!(status.len(), manager.list_providers().len());
+        let hivelocity = status.iter().find(|s| s.provider == "hivelocity").unwrap();
+        assert!(hivelocity.configured);
+        assert_eq!(hivelocity.valid, None);
+        assert!(!status.iter().find(|s| s.provider == "vultr").unwrap().configured);
+
+        let verified = manager.credential_status(true);
+        assert_eq!(verified.iter().find(|s| s.provider == "hivelocity").unwrap().valid, Some(true));
+        assert_eq!(verified.iter().find(|s| s.provider == "vultr").unwrap().valid, None);
+        assert!(!serde_json::to_string(&verified).unwrap().contains("secret-key-123"));
+    }
+
+    #[test]
+    fn test_remove_provider_credentials() {
+        let dir = tempdir().unwrap();
+        let config = dir.path().join("providers.yml");
+        std::fs::write(
+            &config,
+            "hivelocity:\n  api_key: secret-key-123\nvultr:\n  api_key: other-key-456\n  timeouts:\n    list: 3\n",
+        )
+        .unwrap();
+        let mut manager = ProviderManager::new(Some(config.clone())).unwrap();
+
+        assert!(manager.remove_provider_credentials("hivelocity").unwrap());
+        assert!(manager.remove_provider_credentials("vultr").unwrap());
+        assert!(!manager.has_credentials("hivelocity"));
+        assert!(!manager.remove_provider_credentials("hivelocity").unwrap());
+        assert!(manager.remove_provider_credentials("nope").is_err());
+
+        let reloaded = ProviderManager::new(Some(config)).unwrap();
+        assert!(reloaded.credential_status(false).iter().all(|s| !s.configured));
+        assert!(!reloaded.redacted_config().contains_key("hivelocity"));
+        assert_eq!(reloaded.timeouts("vultr").unwrap().unwrap().list, Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_capabilities_default_to_vm() {
+        let caps = ProviderCapabilities::default();
+        assert!(caps.supports(ProviderOperation::Start));
+        assert!(caps.supports(ProviderOperation::Resize));
+        assert!(!caps.bare_metal);
+    }
+
+    #[test]
+    fn test_bare_metal_providers_refuse_start_stop() {
+        let dir = tempdir().unwrap();
+        let manager = ProviderManager::new(Some(dir.path().join("providers.yml"))).unwrap();
+
+        for name in ["cherry", "equinix"] {
+            let err = manager
+                .provider_supporting(name, ProviderOperation::Stop)
+                .err()
+                .unwrap();
+            assert_eq!(crate::exit::exit_code_for(&err), crate::exit::USAGE);
+            assert!(err.to_string().contains("does not support stop"));
+        }
+
+        assert!(manager.provider_supporting("digitalocean", ProviderOperation::Start).is_ok());
+        let err = manager
+            .provider_supporting("nope", ProviderOperation::Start)
+            .err()
+            .unwrap();
+        assert_eq!(crate::exit::exit_code_for(&err), crate::exit::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_every_region_has_a_display_name() {
+        let dir = tempdir().unwrap();
+        let manager = ProviderManager::new(Some(dir.path().join("providers.yml"))).unwrap();
+
+        for name in manager.list_providers() {
+            let provider = manager.get_provider(&name).unwrap();
+            let template_regions = provider.templates().iter().flat_map(|t| t.regions.iter());
+            for region in provider.regions().iter().chain(template_regions) {
+                assert!(provider.region_display_name(region).is_some(), "{} has no name for {}", name, region);
+            }
+        }
+
+        let cherry = manager.get_provider("cherry").unwrap();
+        assert_eq!(region_label(cherry, "eu-nord-1"), "Stockholm (eu-nord-1)");
+        assert_eq!(region_label(cherry, "mars-1"), "mars-1");
+        let hivelocity = manager.get_provider("hivelocity").unwrap();
+        assert_eq!(region_label(hivelocity, "new-york"), "New York");
+        assert_eq!(region_label(&fake::FakeProvider::new("fake"), "lab"), "lab");
+    }
+
+    #[test]
+    fn test_every_provider_lists_images_offline() {
+        let dir = tempdir().unwrap();
+        let manager = ProviderManager::new(Some(dir.path().join("providers.yml"))).unwrap();
+        for name in manager.list_providers() {
+            let images = manager.images(&name).unwrap();
+            assert!(!images.is_empty(), "{} lists no images", name);
+            assert!(images.iter().all(|i| i.arch == ARCH_X86_64 || i.arch == ARCH_ARM64), "{}", name);
+        }
+        assert_eq!(manager.images("vultr").unwrap()[1].id, "1743");
+
+        let err = manager.images("nope").unwrap_err();
+        assert_eq!(crate::exit::exit_code_for(&err), crate::exit::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_resolve_region() {
+        let dir = tempdir().unwrap();
+        let manager = ProviderManager::new(Some(dir.path().join("providers.yml"))).unwrap();
+
+        let regions = manager.regions("cherry").unwrap();
+        assert_eq!(regions[0], Region { slug: "eu-nord-1".to_string(), name: Some("Stockholm".to_string()) });
+        assert_eq!(manager.resolve_region("cherry", "eu-nord-1").unwrap(), "eu-nord-1");
+        assert_eq!(manager.resolve_region("cherry", " EU_NORD_1 ").unwrap(), "eu-nord-1");
+        assert_eq!(manager.resolve_region("cherry", "stockholm").unwrap(), "eu-nord-1");
+        assert_eq!(manager.resolve_region("hivelocity", "New York").unwrap(), "new-york");
+
+        let err = manager.resolve_region("cherry", "eu-nrod-1").unwrap_err();
+        assert_eq!(crate::exit::exit_code_for(&err), crate::exit::USAGE);
+        assert!(err.to_string().contains("available: eu-nord-1, eu-west-1"));
+
+        let err = manager.regions("nope").unwrap_err();
+        assert_eq!(crate::exit::exit_code_for(&err), crate::exit::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_resize_gated_by_capabilities() {
+        let dir = tempdir().unwrap();
+        let manager = ProviderManager::new(Some(dir.path().join("providers.yml"))).unwrap();
+
+        assert!(manager.provider_supporting("vultr", ProviderOperation::Resize).is_err());
+        assert!(manager.provider_supporting("cherry", ProviderOperation::Resize).is_err());
+        assert!(manager.provider_supporting("cherry", ProviderOperation::Snapshot).is_err());
+        assert!(manager.provider_supporting("vultr", ProviderOperation::Snapshot).is_ok());
+
+        let provider = manager.provider_supporting("linode", ProviderOperation::Resize).unwrap();
+        let template = provider.templates()[1].clone();
+        let linode = linode::LinodeProvider::new(Some("key".to_string()));
+        let resized = linode.resize("linode-web", &template.id).unwrap();
+        assert_eq!(resized.template, template.id);
+        assert_eq!(resized.cost_hourly, template.price_hourly);
+
+        // The trait default refuses rather than pretending to succeed
+        let cherry = cherry::CherryServersProvider::new(Some("key".to_string()));
+        let err = cherry.resize("cherry-web", "cherry-e5-2630v4").unwrap_err();
+        assert_eq!(crate::exit::exit_code_for(&err), crate::exit::USAGE);
+    }
+
+    fn deploy_config(region: &str, extra: &[(&str, &str)]) -> DeployConfig {
+        DeployConfig {
+            name: "node".to_string(),
+            region: region.to_string(),
+            os: None,
+            ssh_keys: None,
+            extra: extra
+                .iter()
+                .map(|(k, v)| (k.to_string(), serde_json::Value::String(v.to_string())))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_validate_for_reports_every_problem() {
+        let aws = aws::AWSProvider::new(None);
+
+        let err = deploy_config("mars-1", &[("vpc_id", "nope")]).validate_for(&aws).unwrap_err();
+        assert_eq!(err.provider, "aws");
+        assert_eq!(err.problems.len(), 3);
+        assert!(err.problems.contains(&DeployFieldError::Missing("subnet_id".to_string())));
+        let message = err.to_string();
+        assert!(message.contains("invalid 'region'"));
+        assert!(message.contains("invalid 'vpc_id'"));
+
+        let ok = deploy_config("us-east-1", &[("subnet_id", "subnet-0abc"), ("vpc_id", "vpc-1")]);
+        assert!(ok.validate_for(&aws).is_ok());
+
+        // Providers without extra requirements only get the generic checks
+        let vultr = vultr::VultrProvider::new(None);
+        let region = vultr.regions()[0].clone();
+        assert!(deploy_config(&region, &[]).validate_for(&vultr).is_ok());
+    }
+
+    #[test]
+    fn test_deploy_config_error_exit_code() {
+        let dir = tempdir().unwrap();
+        let manager = ProviderManager::new(Some(dir.path().join("providers.yml"))).unwrap();
+        let err = manager
+            .deploy_to_provider("aws", "aws-t3-micro", &deploy_config("us-east-1", &[]))
+            .unwrap_err();
+        assert_eq!(crate::exit::exit_code_for(&err), crate::exit::USAGE);
+    }
+
+    #[test]
+    fn test_cached_pricing_applied_on_startup() {
+        let dir = tempdir().unwrap();
+        let config = dir.path().join("providers.yml");
+
+        let mut cache = PricingCache::load(&dir.path().join(PRICING_CACHE_FILE)).unwrap();
+        let mut templates = linode::LinodeProvider::new(None).templates().to_vec();
+        templates[0].price_monthly = 7.0;
+        cache.record("linode", &templates);
+        cache.save().unwrap();
+
+        let manager = ProviderManager::new(Some(config)).unwrap();
+        let linode = manager.get_provider("linode").unwrap();
+        assert_eq!(linode.templates()[0].price_monthly, 7.0);
+        assert!(manager.pricing_refreshed_at().unwrap().is_some());
+
+        // Without credentials the refresh fails per provider, not overall
+        let mut manager = manager;
+        let results = manager.refresh_pricing().unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|(_, r)| r.is_err()));
+    }
+
+    #[test]
+    fn test_template_arch() {
+        assert_eq!(parse_arch("aarch64").unwrap(), ARCH_ARM64);
+        assert_eq!(parse_arch("AMD64").unwrap(), ARCH_X86_64);
+        assert!(parse_arch("riscv").is_err());
+
+        let dir = tempdir().unwrap();
+        let manager = ProviderManager::new(Some(dir.path().join("providers.yml"))).unwrap();
+        let arm: Vec<_> = manager.get_all_templates().into_iter().filter(|t| t.arch == ARCH_ARM64).collect();
+        assert!(arm.iter().any(|t| t.id == "aws-t4g-medium"));
+        assert!(arm.iter().any(|t| t.id == "scaleway-coparm1-2c"));
+
+        let yaml = "id: t\nname: t\nprovider: p\ncpu: 1\nmemory_gb: 1\nstorage_gb: 1\nbandwidth_tb: 1.0\n\
+                    price_hourly: 0.1\nprice_monthly: 73.0\ngpu: null\nregions: []\nfeatures: []\n";
+        let template: ProviderTemplate = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(template.arch, ARCH_X86_64);
+    }
+
+    #[test]
+    fn test_timeouts_from_config() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("providers.yml");
+        std::fs::write(&path, "equinix:\n  timeouts:\n    deploy: 900\nvultr:\n  timeouts:\n    list: 3\n").unwrap();
+        let manager = ProviderManager::new(Some(path.clone())).unwrap();
+
+        let equinix = manager.timeouts("equinix").unwrap().unwrap();
+        assert_eq!(equinix.list, Duration::from_secs(20));
+        assert_eq!(equinix.deploy, Duration::from_secs(900));
+        let vultr = manager.timeouts("vultr").unwrap().unwrap();
+        assert_eq!(vultr.list, Duration::from_secs(3));
+        assert_eq!(vultr.deploy, Duration::from_secs(120));
+        assert!(manager.timeouts("nope").unwrap().is_none());
+
+        std::fs::write(&path, "linode:\n  timeouts:\n    list: 0\n").unwrap();
+        let err = ProviderManager::new(Some(path)).err().unwrap();
+        assert!(format!("{:#}", err).contains("list timeout must be at least 1 second"));
+    }
+
+    #[test]
+    fn test_group_by_spec_keeps_cheapest_per_spec() {
+        let template = |provider: &str, cpu: u32, memory_gb: u32, price_hourly: f64| ProviderTemplate {
+            id: format!("{}-{}", provider, cpu),
+            name: format!("{} vCPU", cpu),
+            provider: provider.to_string(),
+            cpu,
+            memory_gb,
+            storage_gb: 80,
+            bandwidth_tb: 1.0,
+            price_hourly,
+            price_monthly: price_hourly * 730.0,
+            gpu: None,
+            arch: ARCH_X86_64.to_string(),
+            regions: Vec::new(),
+            features: Vec::new(),
+        };
+        let arm = ProviderTemplate { arch: ARCH_ARM64.to_string(), ..template("hetzner", 2, 4, 0.005) };
+
+        let groups = group_by_spec(vec![
+            template("vultr", 2, 4, 0.030),
+            template("linode", 2, 4, 0.027),
+            template("aws", 4, 8, 0.020),
+            template("digitalocean", 2, 4, 0.036),
+            arm,
+        ]);
+
+        let cheapest: Vec<(&str, usize)> = groups
+            .iter()
+            .map(|g| (g.cheapest().provider.as_str(), g.alternatives().len()))
+            .collect();
+        assert_eq!(cheapest, vec![("hetzner", 0), ("aws", 0), ("linode", 2)]);
+        let others: Vec<&str> = groups[2].alternatives().iter().map(|t| t.provider.as_str()).collect();
+        assert_eq!(others, vec!["vultr", "digitalocean"]);
+    }
+
+    #[test]
+    fn test_console_url_from_template_or_config() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("providers.yml");
+        std::fs::write(&path, "vultr:\n  console_url: https://vultr.example/{id}\n").unwrap();
+        let manager = ProviderManager::new(Some(path)).unwrap();
+
+        let aws = manager.console_url_template("aws").unwrap();
+        assert_eq!(
+            console_url(&aws, "i-0abc", Some("eu-west-1")).unwrap(),
+            "https://eu-west-1.console.aws.amazon.com/ec2/home?region=eu-west-1#InstanceDetails:instanceId=i-0abc"
+        );
+        assert!(console_url(&aws, "i-0abc", None).is_err());
+
+        let vultr = manager.console_url_template("vultr").unwrap();
+        assert_eq!(console_url(&vultr, "42", None).unwrap(), "https://vultr.example/42");
+        assert!(manager.console_url_template("nope").is_none());
+    }
+
+    #[test]
+    fn test_find_template_across_providers() {
+        let dir = tempdir().unwrap();
+        let mut manager = ProviderManager::new(Some(dir.path().join("providers.yml"))).unwrap();
+
+        let aws = manager.get_all_templates().into_iter().find(|t| t.provider == "aws").unwrap();
+        assert_eq!(manager.find_template(&aws.id, None).unwrap().provider, "aws");
+        assert_eq!(manager.find_template(&aws.id, Some("aws")).unwrap().id, aws.id);
+
+        let err = manager.find_template(&aws.id, Some("vultr")).unwrap_err();
+        assert_eq!(crate::exit::exit_code_for(&err), crate::exit::NOT_FOUND);
+        let err = manager.find_template("no-such-template", None).unwrap_err();
+        assert_eq!(crate::exit::exit_code_for(&err), crate::exit::NOT_FOUND);
+
+        let mut twin = fake::FakeProvider::new("twin");
+        twin.templates_mut()[0].id = aws.id.clone();
+        manager.register_provider(Box::new(twin));
+        let err = manager.find_template(&aws.id, None).unwrap_err();
+        assert_eq!(crate::exit::exit_code_for(&err), crate::exit::USAGE);
+        assert!(err.to_string().contains("aws, twin"));
+        assert_eq!(manager.find_template(&aws.id, Some("twin")).unwrap().provider, "twin");
+    }
+
+    #[test]
+    fn test_template_efficiency() {
+        let template = ProviderTemplate {
+            id: "t".to_string(),
+            name: "t".to_string(),
+            provider: "fake".to_string(),
+            cpu: 4,
+            memory_gb: 8,
+            storage_gb: 0,
+            bandwidth_tb: 2.0,
+            price_hourly: 0.0548,
+            price_monthly: 40.0,
+            gpu: None,
+            arch: ARCH_X86_64.to_string(),
+            regions: Vec::new(),
+            features: Vec::new(),
+        };
+        let efficiency = template.efficiency();
+        assert_eq!(efficiency.per_cpu_monthly, Some(10.0));
+        assert_eq!(efficiency.per_gb_memory_monthly, Some(5.0));
+        assert_eq!(efficiency.per_gb_storage_monthly, None);
+        assert_eq!(efficiency.per_tb_bandwidth_monthly, Some(20.0));
+    }
+
+    #[test]
+    fn test_deploy_tags_merge_provider_defaults() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("providers.yml");
+        std::fs::write(&path, "vultr:\n  default_tags: [team:infra, managed]\n").unwrap();
+        let manager = ProviderManager::new(Some(path)).unwrap();
+
+        let flags = vec!["web".to_string(), "managed".to_string(), " web ".to_string()];
+        assert_eq!(manager.deploy_tags("vultr", &flags), ["team:infra", "managed", "web"]);
+        assert_eq!(manager.deploy_tags("aws", &flags), ["web", "managed"]);
+        assert_eq!(manager.deploy_tags("vultr", &[]), ["team:infra", "managed"]);
+
+        // Defaults are settings, not secrets, and survive a redacted copy
+        let config = &manager.config["vultr"];
+        assert_eq!(config.without_secrets().default_tags, ["team:infra", "managed"]);
+        assert!(serde_yaml::to_string(&config.redacted()).unwrap().contains("default_tags"));
+    }
+}
@@ -0,0 +1,285 @@
+// Live template pricing
+//
+// Built-in template prices drift from what providers actually charge.
+// Providers with a public plan catalogue override `Provider::refresh_pricing`
+// to pull current prices; `ProviderManager` caches the results in
+// `~/.capsule/pricing.json` and re-applies them on startup.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tokio::runtime::Runtime;
+
+use super::ProviderTemplate;
+use crate::api::ApiClient;
+
+/// Cached prices older than this are flagged in `xnode templates`
+pub const PRICING_STALE_DAYS: i64 = 30;
+
+/// Hours per month used when a plan only publishes a monthly price
+const HOURS_PER_MONTH: f64 = 730.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PlanPrice {
+    pub hourly: f64,
+    pub monthly: f64,
+}
+
+impl PlanPrice {
+    fn from_monthly(monthly: f64) -> Self {
+        Self {
+            hourly: monthly / HOURS_PER_MONTH,
+            monthly,
+        }
+    }
+}
+
+/// Update template prices from `prices` (keyed by API plan slug) using the
+/// `plans` mapping of template ID to slug. Returns how many prices changed.
+pub fn apply_plan_prices(
+    templates: &mut [ProviderTemplate],
+    plans: &[(&str, &str)],
+    prices: &HashMap<String, PlanPrice>,
+) -> usize {
+    let mut changed = 0;
+    for template in templates.iter_mut() {
+        let Some((_, slug)) = plans.iter().find(|(id, _)| *id == template.id) else {
+            continue;
+        };
+        if let Some(price) = prices.get(*slug) {
+            if set_price(template, *price) {
+                changed += 1;
+            }
+        }
+    }
+    changed
+}
+
+/// Set a template's price, returning whether it changed
+pub fn set_price(template: &mut ProviderTemplate, price: PlanPrice) -> bool {
+    let changed = template.price_hourly != price.hourly || template.price_monthly != price.monthly;
+    template.price_hourly = price.hourly;
+    template.price_monthly = price.monthly;
+    changed
+}
+
+static API_RUNTIME: OnceLock<std::io::Result<Runtime>> = OnceLock::new();
+
+/// The runtime synchronous provider code makes API calls on. It lives for
+/// the whole process so connections pooled by `api::shared_http_client`
+/// stay usable between calls, and its workers drive I/O for providers
+/// queried from several threads at once (`xnode sync --parallel`).
+pub fn api_runtime() -> Result<&'static Runtime> {
+    API_RUNTIME
+        .get_or_init(|| {
+            tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(2)
+                .enable_all()
+                .build()
+        })
+        .as_ref()
+        .map_err(|e| anyhow!("Failed to start the API runtime: {}", e))
+}
+
+/// GET `endpoint` from a provider API client on the shared API runtime
+pub fn fetch_json(client: &ApiClient, endpoint: &str) -> Result<Value> {
+    let value = api_runtime()?
+        .block_on(client.get::<Value>(endpoint, None))
+        .with_context(|| format!("Failed to fetch {}", endpoint))?;
+    Ok(value)
+}
+
+/// Parse DigitalOcean `GET /sizes`
+pub fn parse_digitalocean_sizes(body: &Value) -> HashMap<String, PlanPrice> {
+    plan_list(body, "sizes")
+        .filter_map(|size| {
+            let slug = size.get("slug")?.as_str()?;
+            let price = PlanPrice {
+                hourly: size.get("price_hourly")?.as_f64()?,
+                monthly: size.get("price_monthly")?.as_f64()?,
+            };
+            Some((slug.to_string(), price))
+        })
+        .collect()
+}
+
+/// Parse Vultr `GET /plans`, which only guarantees a monthly price
+pub fn parse_vultr_plans(body: &Value) -> HashMap<String, PlanPrice> {
+    plan_list(body, "plans")
+        .filter_map(|plan| {
+            let id = plan.get("id")?.as_str()?;
+            let monthly = plan.get("monthly_cost")?.as_f64()?;
+            let price = match plan.get("hourly_cost").and_then(Value::as_f64) {
+                Some(hourly) => PlanPrice { hourly, monthly },
+                None => PlanPrice::from_monthly(monthly),
+            };
+            Some((id.to_string(), price))
+        })
+        .collect()
+}
+
+/// Parse Linode `GET /linode/types`
+pub fn parse_linode_types(body: &Value) -> HashMap<String, PlanPrice> {
+    plan_list(body, "data")
+        .filter_map(|kind| {
+            let id = kind.get("id")?.as_str()?;
+            let price = kind.get("price")?;
+            let price = PlanPrice {
+                hourly: price.get("hourly")?.as_f64()?,
+                monthly: price.get("monthly")?.as_f64()?,
+            };
+            Some((id.to_string(), price))
+        })
+        .collect()
+}
+
+fn plan_list<'a>(body: &'a Value, key: &str) -> impl Iterator<Item = &'a Value> {
+    body.get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+}
+
+/// Refreshed prices for one provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderPricing {
+    pub refreshed_at: DateTime<Utc>,
+    /// Template ID to price
+    pub prices: HashMap<String, PlanPrice>,
+}
+
+/// Prices fetched by `providers refresh-pricing`, persisted between runs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PricingCache {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(default)]
+    pub providers: HashMap<String, ProviderPricing>,
+}
+
+impl PricingCache {
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut cache: Self = if path.exists() {
+            let contents = fs::read_to_string(path).context("Failed to read pricing cache")?;
+            serde_json::from_str(&contents).context("Failed to parse pricing cache")?
+        } else {
+            Self::default()
+        };
+        cache.path = path.to_path_buf();
+        Ok(cache)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(&self.path, json).context("Failed to write pricing cache")?;
+        Ok(())
+    }
+
+    /// Record the current template prices of a freshly refreshed provider
+    pub fn record(&mut self, provider: &str, templates: &[ProviderTemplate]) {
+        let prices = templates
+            .iter()
+            .map(|t| {
+                let price = PlanPrice {
+                    hourly: t.price_hourly,
+                    monthly: t.price_monthly,
+                };
+                (t.id.clone(), price)
+            })
+            .collect();
+        self.providers.insert(
+            provider.to_string(),
+            ProviderPricing {
+                refreshed_at: Utc::now(),
+                prices,
+            },
+        );
+    }
+
+    /// When the least recently refreshed provider was last refreshed
+    pub fn oldest_refresh(&self) -> Option<DateTime<Utc>> {
+        self.providers.values().map(|p| p.refreshed_at).min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn template(id: &str) -> ProviderTemplate {
+        ProviderTemplate {
+            id: id.to_string(),
+            name: id.to_string(),
+            provider: "test".to_string(),
+            cpu: 1,
+            memory_gb: 1,
+            storage_gb: 10,
+            bandwidth_tb: 1.0,
+            price_hourly: 0.01,
+            price_monthly: 5.0,
+            gpu: None,
+            arch: "x86_64".into(),
+            regions: Vec::new(),
+            features: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_plan_catalogues() {
+        let sizes = parse_digitalocean_sizes(&json!({
+            "sizes": [{"slug": "s-1vcpu-1gb", "price_hourly": 0.00893, "price_monthly": 6.0}]
+        }));
+        assert_eq!(sizes["s-1vcpu-1gb"].monthly, 6.0);
+
+        let plans = parse_vultr_plans(&json!({
+            "plans": [{"id": "vc2-1c-1gb", "monthly_cost": 7.3}, {"id": "bad"}]
+        }));
+        assert_eq!(plans.len(), 1);
+        assert!((plans["vc2-1c-1gb"].hourly - 0.01).abs() < 1e-9);
+
+        let types = parse_linode_types(&json!({
+            "data": [{"id": "g6-nanode-1", "price": {"hourly": 0.0075, "monthly": 5.0}}]
+        }));
+        assert_eq!(types["g6-nanode-1"].hourly, 0.0075);
+
+        assert!(parse_linode_types(&json!({"errors": []})).is_empty());
+    }
+
+    #[test]
+    fn test_apply_plan_prices() {
+        let mut templates = vec![template("a"), template("b"), template("c")];
+        let plans = [("a", "plan-a"), ("b", "plan-b")];
+        let prices = HashMap::from([
+            ("plan-a".to_string(), PlanPrice { hourly: 0.02, monthly: 10.0 }),
+            ("plan-b".to_string(), PlanPrice { hourly: 0.01, monthly: 5.0 }),
+        ]);
+
+        assert_eq!(apply_plan_prices(&mut templates, &plans, &prices), 1);
+        assert_eq!(templates[0].price_monthly, 10.0);
+        assert_eq!(templates[2].price_monthly, 5.0);
+    }
+
+    #[test]
+    fn test_cache_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pricing.json");
+
+        let mut cache = PricingCache::load(&path).unwrap();
+        assert!(cache.oldest_refresh().is_none());
+        cache.record("linode", &[template("a")]);
+        cache.save().unwrap();
+
+        let cache = PricingCache::load(&path).unwrap();
+        assert_eq!(cache.providers["linode"].prices["a"].monthly, 5.0);
+        assert!(cache.oldest_refresh().is_some());
+    }
+}
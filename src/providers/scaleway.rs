@@ -1,6 +1,28 @@
-use super::{Provider, ProviderTemplate, Instance, DeployConfig};
+use super::images::{static_images, ImageInfo};
+use super::{Provider, ProviderCapabilities, ProviderTemplate, Instance, DeployConfig};
 use anyhow::Result;
 
+/// Location of each region slug
+const REGION_NAMES: &[(&str, &str)] = &[
+    ("par1", "Paris"),
+    ("par2", "Paris"),
+    ("ams1", "Amsterdam"),
+    ("ams2", "Amsterdam"),
+    ("waw1", "Warsaw"),
+];
+
+/// Server page in the Scaleway console; regions are zones
+const CONSOLE_URL: &str = "https://console.scaleway.com/instance/servers/{region}/{id}";
+
+/// Common Scaleway OS images
+const IMAGES: &[(&str, &str, &str)] = &[
+    ("ubuntu_noble", "Ubuntu 24.04 Noble Numbat", "x86_64"),
+    ("ubuntu_jammy", "Ubuntu 22.04 Jammy Jellyfish", "x86_64"),
+    ("ubuntu_jammy", "Ubuntu 22.04 Jammy Jellyfish", "arm64"),
+    ("debian_bookworm", "Debian 12 Bookworm", "x86_64"),
+    ("rockylinux_9", "Rocky Linux 9", "x86_64"),
+];
+
 pub struct ScalewayProvider {
     name: String,
     api_key: Option<String>,
@@ -34,6 +56,7 @@ impl ScalewayProvider {
                 price_hourly: 0.0045,
                 price_monthly: 3.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["par1".into(), "ams1".into(), "waw1".into()],
                 features: vec!["ssd".into(), "cloud".into(), "x86".into()],
             },
@@ -48,9 +71,25 @@ impl ScalewayProvider {
                 price_hourly: 0.0090,
                 price_monthly: 6.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["par1".into(), "ams1".into(), "waw1".into()],
                 features: vec!["ssd".into(), "cloud".into(), "x86".into()],
             },
+            ProviderTemplate {
+                id: "scaleway-coparm1-2c".to_string(),
+                name: "COPARM1-2C-8G".to_string(),
+                provider: "scaleway".to_string(),
+                cpu: 2,
+                memory_gb: 8,
+                storage_gb: 50,
+                bandwidth_tb: 0.5,
+                price_hourly: 0.0426,
+                price_monthly: 31.10,
+                gpu: None,
+                arch: "arm64".into(),
+                regions: vec!["par1".into(), "ams1".into()],
+                features: vec!["ssd".into(), "cloud".into(), "dedicated-cpu".into()],
+            },
             ProviderTemplate {
                 id: "scaleway-gp1-xs".to_string(),
                 name: "GP1-XS".to_string(),
@@ -62,6 +101,7 @@ impl ScalewayProvider {
                 price_hourly: 0.11,
                 price_monthly: 73.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["par1".into(), "ams1".into(), "waw1".into()],
                 features: vec!["ssd".into(), "cloud".into(), "x86".into(), "high-memory".into()],
             },
@@ -76,6 +116,7 @@ impl ScalewayProvider {
                 price_hourly: 0.22,
                 price_monthly: 147.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["par1".into(), "ams1".into(), "waw1".into()],
                 features: vec!["ssd".into(), "cloud".into(), "x86".into(), "high-memory".into()],
             },
@@ -90,6 +131,7 @@ impl ScalewayProvider {
                 price_hourly: 0.44,
                 price_monthly: 294.00,
                 gpu: Some("NVIDIA T4".to_string()),
+                arch: "x86_64".into(),
                 regions: vec!["par1".into(), "ams1".into()],
                 features: vec!["nvme".into(), "cloud".into(), "gpu".into(), "x86".into()],
             },
@@ -104,6 +146,7 @@ impl ScalewayProvider {
                 price_hourly: 3.30,
                 price_monthly: 2200.00,
                 gpu: Some("NVIDIA H100 80GB".to_string()),
+                arch: "x86_64".into(),
                 regions: vec!["par1".into()],
                 features: vec!["ssd".into(), "cloud".into(), "gpu".into(), "x86".into(), "high-memory".into()],
             },
@@ -130,10 +173,38 @@ impl Provider for ScalewayProvider {
         &self.templates
     }
 
+    fn templates_mut(&mut self) -> &mut [ProviderTemplate] {
+        &mut self.templates
+    }
+
     fn regions(&self) -> &[String] {
         &self.regions
     }
 
+    fn region_display_name(&self, region: &str) -> Option<String> {
+        super::lookup_region(REGION_NAMES, region)
+    }
+
+    fn is_configured(&self) -> bool {
+        self.api_key.is_some()
+    }
+
+    fn list_images(&self) -> Result<Vec<ImageInfo>> {
+        Ok(static_images(IMAGES))
+    }
+
+    fn console_url_template(&self) -> Option<&str> {
+        Some(CONSOLE_URL)
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        // Resize not implemented yet
+        ProviderCapabilities {
+            resize: false,
+            ..ProviderCapabilities::vm()
+        }
+    }
+
     fn deploy(&self, template_id: &str, config: &DeployConfig) -> Result<Instance> {
         let template = self.get_template(template_id)
             .ok_or_else(|| anyhow::anyhow!("Template {} not found", template_id))?;
@@ -205,4 +276,14 @@ impl Provider for ScalewayProvider {
         println!("Stopping Scaleway instance {}", instance_id);
         Ok(true)
     }
+
+    fn snapshot_instance(&self, instance_id: &str, name: &str) -> Result<String> {
+        if self.api_key.is_none() {
+            anyhow::bail!("Scaleway API key not configured");
+        }
+
+        // TODO: Actual API implementation
+        println!("Creating Scaleway snapshot '{}' of instance {}", name, instance_id);
+        Ok(format!("scaleway-snap-{}-{}", instance_id, chrono::Utc::now().timestamp()))
+    }
 }
@@ -1,13 +1,48 @@
-use super::{Provider, ProviderTemplate, Instance, DeployConfig};
+use super::images::{parse_vultr_os, static_images, ImageInfo};
+use super::pricing::{apply_plan_prices, fetch_json, parse_vultr_plans};
+use super::{Provider, ProviderCapabilities, ProviderTemplate, ProviderTimeouts, Instance, DeployConfig};
 use anyhow::Result;
 
+use crate::api::VultrClient;
+
+/// Location of each region slug
+const REGION_NAMES: &[(&str, &str)] = &[
+    ("ewr", "New Jersey"),
+    ("ord", "Chicago"),
+    ("dfw", "Dallas"),
+    ("sea", "Seattle"),
+    ("lax", "Los Angeles"),
+    ("ams", "Amsterdam"),
+    ("fra", "Frankfurt"),
+    ("sgp", "Singapore"),
+    ("syd", "Sydney"),
+];
+
+/// Instance page in the Vultr customer portal
+const CONSOLE_URL: &str = "https://my.vultr.com/subs/?id={id}";
+
+/// Common images, listed when no API key is configured to fetch them
+const IMAGES: &[(&str, &str, &str)] = &[
+    ("2284", "Ubuntu 24.04 LTS x64", "x86_64"),
+    ("1743", "Ubuntu 22.04 LTS x64", "x86_64"),
+    ("2136", "Debian 12 x64 (bookworm)", "x86_64"),
+    ("1869", "Rocky Linux 9 x64", "x86_64"),
+];
+
 pub struct VultrProvider {
     name: String,
     api_key: Option<String>,
     templates: Vec<ProviderTemplate>,
     regions: Vec<String>,
+    timeouts: ProviderTimeouts,
 }
 
+/// Template ID to Vultr plan slug, for price refreshes
+const PLAN_SLUGS: &[(&str, &str)] = &[
+    ("vultr-vc2-1", "vc2-1c-1gb"),
+    ("vultr-vc2-2", "vc2-2c-4gb"),
+];
+
 impl VultrProvider {
     pub fn new(api_key: Option<String>) -> Self {
         let mut provider = Self {
@@ -15,6 +50,7 @@ impl VultrProvider {
             api_key,
             templates: Vec::new(),
             regions: Vec::new(),
+            timeouts: ProviderTimeouts::defaults(ProviderCapabilities::vm()),
         };
         provider.initialize_templates();
         provider.initialize_regions();
@@ -34,6 +70,7 @@ impl VultrProvider {
                 price_hourly: 0.004,
                 price_monthly: 2.50,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["ewr".into(), "ord".into(), "dfw".into(), "sea".into(), "lax".into()],
                 features: vec!["ssd".into(), "cloud".into()],
             },
@@ -48,6 +85,7 @@ impl VultrProvider {
                 price_hourly: 0.018,
                 price_monthly: 12.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["ewr".into(), "ord".into(), "dfw".into(), "sea".into(), "lax".into(), "ams".into()],
                 features: vec!["ssd".into(), "cloud".into()],
             },
@@ -62,6 +100,7 @@ impl VultrProvider {
                 price_hourly: 0.060,
                 price_monthly: 42.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["ewr".into(), "ord".into(), "lax".into(), "ams".into(), "sgp".into()],
                 features: vec!["nvme".into(), "cloud".into(), "high-performance".into()],
             },
@@ -76,6 +115,7 @@ impl VultrProvider {
                 price_hourly: 0.34,
                 price_monthly: 240.00,
                 gpu: None,
+                arch: "x86_64".into(),
                 regions: vec!["ewr".into(), "dfw".into()],
                 features: vec!["bare-metal".into(), "nvme".into(), "dedicated".into()],
             },
@@ -106,10 +146,45 @@ impl Provider for VultrProvider {
         &self.templates
     }
 
+    fn templates_mut(&mut self) -> &mut [ProviderTemplate] {
+        &mut self.templates
+    }
+
     fn regions(&self) -> &[String] {
         &self.regions
     }
 
+    fn region_display_name(&self, region: &str) -> Option<String> {
+        super::lookup_region(REGION_NAMES, region)
+    }
+
+    fn is_configured(&self) -> bool {
+        self.api_key.is_some()
+    }
+
+    fn list_images(&self) -> Result<Vec<ImageInfo>> {
+        let Some(api_key) = self.api_key.clone() else {
+            return Ok(static_images(IMAGES));
+        };
+
+        let client = VultrClient::new(api_key)?.with_timeout(self.timeouts.list);
+        let body = fetch_json(client.client(), "/os?per_page=500")?;
+        Ok(parse_vultr_os(&body))
+    }
+
+    fn console_url_template(&self) -> Option<&str> {
+        Some(CONSOLE_URL)
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        // Resize not implemented yet
+        ProviderCapabilities {
+            resize: false,
+            live_pricing: true,
+            ..ProviderCapabilities::vm()
+        }
+    }
+
     fn deploy(&self, template_id: &str, config: &DeployConfig) -> Result<Instance> {
         let template = self.get_template(template_id)
             .ok_or_else(|| anyhow::anyhow!("Template {} not found", template_id))?;
@@ -181,4 +256,28 @@ impl Provider for VultrProvider {
         println!("Stopping Vultr instance {}", instance_id);
         Ok(true)
     }
+
+    fn snapshot_instance(&self, instance_id: &str, name: &str) -> Result<String> {
+        if self.api_key.is_none() {
+            anyhow::bail!("Vultr API key not configured");
+        }
+
+        // TODO: Actual API implementation
+        println!("Creating Vultr snapshot '{}' of instance {}", name, instance_id);
+        Ok(format!("vultr-snap-{}-{}", instance_id, chrono::Utc::now().timestamp()))
+    }
+
+    fn refresh_pricing(&mut self) -> Result<()> {
+        let api_key = self.api_key.clone()
+            .ok_or_else(|| anyhow::anyhow!("Vultr API key not configured"))?;
+
+        let client = VultrClient::new(api_key)?.with_timeout(self.timeouts.list);
+        let body = fetch_json(client.client(), "/plans?per_page=500")?;
+        apply_plan_prices(&mut self.templates, PLAN_SLUGS, &parse_vultr_plans(&body));
+        Ok(())
+    }
+
+    fn set_timeouts(&mut self, timeouts: ProviderTimeouts) {
+        self.timeouts = timeouts;
+    }
 }
@@ -0,0 +1,179 @@
+// Self-update: download the latest release and replace the running binary
+
+use anyhow::{Context, Result};
+use colored::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use capsule::ui::*;
+use capsule::update::{
+    check_for_update, describe_check_error, download_asset, fetch_text, parse_checksum,
+    GITHUB_API_URL,
+};
+
+use crate::server::checksum::compute_file_checksum;
+
+/// Removes the downloaded file unless the update completed
+struct TempFile(PathBuf);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+pub fn handle_update_command(dry_run: bool) -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+
+    header("⬆  CAPSULE UPDATE");
+
+    let check = runtime
+        .block_on(check_for_update(GITHUB_API_URL))
+        .map_err(|e| anyhow::anyhow!(describe_check_error(&e)))?;
+
+    info_line("Installed", &check.current);
+    info_line("Latest", check.latest.version());
+    println!();
+
+    if !check.update_available {
+        success("You are running the latest version");
+        println!();
+        return Ok(());
+    }
+
+    let asset = check.latest.platform_asset().ok_or_else(|| {
+        anyhow::anyhow!(
+            "Release {} has no binary for {}-{}",
+            check.latest.tag_name,
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        )
+    })?;
+    let checksum_asset = check.latest.checksum_asset(asset).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Release {} publishes no SHA256 checksum for {}; refusing to install",
+            check.latest.tag_name,
+            asset.name
+        )
+    })?;
+
+    let exe = std::env::current_exe()
+        .and_then(|p| p.canonicalize())
+        .context("Failed to locate capsule binary")?;
+
+    info_line("Asset", &asset.name);
+    info_line("Size", &format!("{:.2} MB", asset.size as f64 / (1024.0 * 1024.0)));
+    info_line("Download", &asset.browser_download_url);
+    info_line("Checksum", &checksum_asset.name);
+    info_line("Target", &exe.display().to_string());
+    println!();
+
+    if dry_run {
+        println!("{}", "  Dry run - nothing was downloaded".yellow());
+        println!();
+        return Ok(());
+    }
+
+    let checksums = runtime
+        .block_on(fetch_text(&checksum_asset.browser_download_url))
+        .context("Failed to download checksum")?;
+    let expected = parse_checksum(&checksums, &asset.name).ok_or_else(|| {
+        anyhow::anyhow!("No checksum for {} in {}", asset.name, checksum_asset.name)
+    })?;
+
+    // Download next to the binary so the final rename stays on one filesystem
+    let dir = exe.parent().context("Binary has no parent directory")?;
+    let temp = TempFile(dir.join(format!(".capsule-update-{}", std::process::id())));
+
+    println!("{} Downloading {}...", "▸".green().bold(), asset.name.cyan());
+    runtime
+        .block_on(download_asset(&asset.browser_download_url, &temp.0))
+        .with_context(|| format!("Failed to download {}", asset.name))?;
+
+    verify_checksum(&temp.0, &expected)?;
+    println!("{} Checksum verified", "  ✓".green());
+
+    replace_binary(&temp.0, &exe)?;
+
+    println!();
+    success(&format!("Updated capsule to {}", check.latest.version()));
+    println!();
+
+    Ok(())
+}
+
+/// Fail unless `path` hashes to `expected`
+fn verify_checksum(path: &Path, expected: &str) -> Result<()> {
+    let actual = compute_file_checksum(path)?.sha256;
+    if !actual.eq_ignore_ascii_case(expected) {
+        anyhow::bail!(
+            "Checksum mismatch for downloaded binary (expected {}, got {}); existing binary left intact",
+            expected,
+            actual
+        );
+    }
+    Ok(())
+}
+
+/// Make the downloaded file executable and move it over `target`
+fn replace_binary(new: &Path, target: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(new, fs::Permissions::from_mode(0o755))
+            .context("Failed to make new binary executable")?;
+    }
+
+    #[cfg(windows)]
+    {
+        // A running executable can't be overwritten on Windows, but it can be
+        // renamed; move it aside first and put it back if the swap fails
+        let old = target.with_extension("old.exe");
+        let _ = fs::remove_file(&old);
+        fs::rename(target, &old).context("Failed to move running binary aside")?;
+        if let Err(e) = fs::rename(new, target) {
+            let _ = fs::rename(&old, target);
+            return Err(e).context("Failed to install new binary");
+        }
+        return Ok(());
+    }
+
+    #[cfg(not(windows))]
+    {
+        fs::rename(new, target).context("Failed to install new binary")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_verify_checksum() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("capsule");
+        fs::write(&file, b"hello").unwrap();
+
+        let good = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        assert!(verify_checksum(&file, good).is_ok());
+        assert!(verify_checksum(&file, &"0".repeat(64)).is_err());
+    }
+
+    #[test]
+    fn test_replace_binary() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("capsule");
+        let new = dir.path().join(".capsule-update");
+        fs::write(&target, b"old").unwrap();
+        fs::write(&new, b"new").unwrap();
+
+        replace_binary(&new, &target).unwrap();
+
+        assert_eq!(fs::read(&target).unwrap(), b"new");
+        assert!(!new.exists());
+    }
+}
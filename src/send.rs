@@ -0,0 +1,231 @@
+// Push the capsule binary to a remote server over scp/ssh
+
+use anyhow::{Context, Result};
+use colored::*;
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use capsule::ui::tip;
+
+/// Upper bound for the delay between attempts
+const MAX_BACKOFF_SECS: u64 = 30;
+
+/// stderr fragments that indicate a transient network problem
+const RETRYABLE_PATTERNS: &[&str] = &[
+    "connection timed out",
+    "connection reset",
+    "connection refused",
+    "connection closed",
+    "lost connection",
+    "broken pipe",
+    "network is unreachable",
+    "no route to host",
+    "operation timed out",
+    "temporary failure in name resolution",
+];
+
+/// stderr fragments that will not go away by trying again
+const FATAL_PATTERNS: &[&str] = &[
+    "permission denied",
+    "host key verification failed",
+    "authentication failed",
+    "too many authentication failures",
+    "could not resolve hostname",
+    "no such file or directory",
+    "sudo: a password is required",
+];
+
+/// Decide whether a failed scp/ssh invocation is worth retrying.
+///
+/// ssh exits with 255 when the connection itself failed, so that status is
+/// treated as retryable unless stderr points at an auth or permission error.
+/// Other statuses come from the remote command and are only retried when
+/// stderr looks like a dropped connection.
+pub fn is_retryable_failure(code: Option<i32>, stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+
+    if FATAL_PATTERNS.iter().any(|p| stderr.contains(p)) {
+        return false;
+    }
+    if RETRYABLE_PATTERNS.iter().any(|p| stderr.contains(p)) {
+        return true;
+    }
+
+    // Killed by a signal (e.g. the connection dropped mid-transfer)
+    code.is_none() || code == Some(255)
+}
+
+/// Delay before the given retry (1-based), doubling each time
+pub fn backoff_delay(retry: u32) -> Duration {
+    let secs = 2u64.saturating_pow(retry.saturating_sub(1)).min(MAX_BACKOFF_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Run a remote command, retrying retryable failures up to `retries` times
+pub fn run_with_retries<F>(step: &str, retries: u32, build: F) -> Result<()>
+where
+    F: Fn() -> Command,
+{
+    let attempts = retries + 1;
+
+    for attempt in 1..=attempts {
+        if attempts > 1 {
+            println!("{} Attempt {}/{}", "  ▸".cyan(), attempt, attempts);
+        }
+
+        let output: Output = build()
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::piped())
+            .output()
+            .with_context(|| format!("Failed to execute {}", step))?;
+
+        if output.status.success() {
+            return Ok(());
+        }
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr = stderr.trim();
+        if !stderr.is_empty() {
+            eprintln!("{}", stderr.bright_black());
+        }
+
+        if !is_retryable_failure(output.status.code(), stderr) {
+            anyhow::bail!("{} failed (not retryable)", step);
+        }
+
+        if attempt < attempts {
+            let delay = backoff_delay(attempt);
+            println!(
+                "{} {} failed, retrying in {}s...",
+                "  !".yellow(),
+                step,
+                delay.as_secs()
+            );
+            thread::sleep(delay);
+        }
+    }
+
+    anyhow::bail!("{} failed after {} attempt(s)", step, attempts)
+}
+
+pub fn handle_send_command(server: &str, remote_path: &str, retries: u32) -> Result<()> {
+    println!("{}", "📤 Sending capsule binary to remote server...".cyan().bold());
+    println!();
+
+    // Get the current binary path
+    let binary_path = std::env::current_exe()
+        .context("Failed to locate capsule binary")?;
+
+    println!("{} Binary location: {}",
+        "▸".green().bold(),
+        binary_path.display().to_string().cyan());
+
+    // Check binary size
+    let metadata = std::fs::metadata(&binary_path)
+        .context("Failed to read binary metadata")?;
+    let size_mb = metadata.len() as f64 / (1024.0 * 1024.0);
+
+    println!("{} Binary size: {:.2} MB",
+        "▸".green().bold(),
+        size_mb.to_string().cyan());
+    println!();
+
+    // Use SCP to transfer the binary
+    println!("{} Transferring to {}...",
+        "▸".green().bold(),
+        server.cyan());
+
+    let temp_path = format!("/tmp/capsule-{}", std::process::id());
+
+    run_with_retries("SCP transfer", retries, || {
+        let mut cmd = Command::new("scp");
+        cmd.arg(&binary_path)
+            .arg(format!("{}:{}", server, temp_path));
+        cmd
+    })?;
+
+    println!("{} Transfer complete", "  ✓".green());
+    println!();
+
+    // Install to remote path
+    println!("{} Installing to {}...",
+        "▸".green().bold(),
+        remote_path.cyan());
+
+    // Only move the upload if it is still there, so a retry after a
+    // dropped connection doesn't fail on an install that went through
+    let install_cmd = format!(
+        "if [ -e {} ]; then sudo mv {} {}; fi && sudo chmod +x {}",
+        temp_path, temp_path, remote_path, remote_path
+    );
+
+    run_with_retries("Remote installation", retries, || {
+        let mut cmd = Command::new("ssh");
+        cmd.arg(server).arg(&install_cmd);
+        cmd
+    })?;
+
+    println!("{} Installation complete", "  ✓".green());
+    println!();
+
+    // Verify installation
+    println!("{} Verifying installation...", "▸".green().bold());
+
+    let verify_cmd = format!("{} --version", remote_path);
+    let verify_status = Command::new("ssh")
+        .arg(server)
+        .arg(&verify_cmd)
+        .status()
+        .context("Failed to verify installation")?;
+
+    if !verify_status.success() {
+        println!("{} {} (binary installed but may not be in PATH)",
+            "  !".yellow(),
+            "Warning: verification failed".yellow());
+    } else {
+        println!("{} Capsule is ready on remote server", "  ✓".green());
+    }
+    println!();
+
+    println!("{} Capsule successfully deployed to {}",
+        "✅".green(),
+        server.green().bold());
+    tip(&format!("Connect: {} {}", "ssh".cyan().bold(), server.cyan()));
+    tip(&format!(
+        "Run: {} {}",
+        "ssh".cyan().bold(),
+        format!("{} 'capsule --help'", server).cyan()
+    ));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_network_failures_are_retryable() {
+        assert!(is_retryable_failure(Some(255), "ssh: connect to host example.com port 22: Connection timed out"));
+        assert!(is_retryable_failure(Some(1), "lost connection"));
+        assert!(is_retryable_failure(Some(255), ""));
+        assert!(is_retryable_failure(None, ""));
+    }
+
+    #[test]
+    fn test_auth_failures_are_not_retryable() {
+        assert!(!is_retryable_failure(Some(255), "root@example.com: Permission denied (publickey)."));
+        assert!(!is_retryable_failure(Some(255), "Host key verification failed."));
+        assert!(!is_retryable_failure(Some(1), "mv: cannot move: No such file or directory"));
+        assert!(!is_retryable_failure(Some(1), ""));
+    }
+
+    #[test]
+    fn test_backoff_delay_doubles_and_caps() {
+        assert_eq!(backoff_delay(1), Duration::from_secs(1));
+        assert_eq!(backoff_delay(2), Duration::from_secs(2));
+        assert_eq!(backoff_delay(3), Duration::from_secs(4));
+        assert_eq!(backoff_delay(10), Duration::from_secs(MAX_BACKOFF_SECS));
+    }
+}
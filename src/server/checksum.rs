@@ -3,15 +3,30 @@ use colored::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::io::{BufReader, Read};
+use std::io::{BufReader, Read, Write};
 use std::path::Path;
 
+/// Read buffer used when hashing files
+pub const DEFAULT_BUFFER_SIZE: usize = 8192;
+
+/// Files at least this large get a bigger buffer and progress output
+const LARGE_FILE_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+/// Read buffer used for large files
+const LARGE_FILE_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Name of the manifest file inside a snapshot
+pub const MANIFEST_FILE: &str = "checksums.json";
+
 /// Represents the checksum manifest for a snapshot
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ChecksumManifest {
     pub version: String,
     pub created_at: String,
     pub files: HashMap<String, FileChecksum>,
+    /// Files deliberately left out of the snapshot (e.g. secrets)
+    #[serde(default)]
+    pub excluded: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -27,55 +42,16 @@ impl ChecksumManifest {
             version: "1.0".to_string(),
             created_at: chrono::Utc::now().to_rfc3339(),
             files: HashMap::new(),
+            excluded: Vec::new(),
         }
     }
 
-    /// Generate checksums for all files in a directory
+    /// Generate checksums for every file in the snapshot directory
+    /// (except the manifest itself)
     pub fn generate(snapshot_dir: &Path) -> Result<Self> {
         let mut manifest = Self::new();
-
-        // Files to checksum
-        let files_to_check = vec![
-            "configuration.nix",
-            "packages.nix",
-            "users.nix",
-            "README.md",
-        ];
-
-        for file_name in files_to_check {
-            let file_path = snapshot_dir.join(file_name);
-            if file_path.exists() {
-                let checksum = compute_file_checksum(&file_path)?;
-                manifest.files.insert(file_name.to_string(), checksum);
-            }
-        }
-
-        // Checksum all service files
-        let services_dir = snapshot_dir.join("services");
-        if services_dir.exists() {
-            for entry in fs::read_dir(&services_dir)? {
-                let entry = entry?;
-                let path = entry.path();
-
-                if path.is_file() {
-                    let file_name = path
-                        .strip_prefix(snapshot_dir)
-                        .unwrap()
-                        .to_string_lossy()
-                        .to_string();
-
-                    let checksum = compute_file_checksum(&path)?;
-                    manifest.files.insert(file_name, checksum);
-                }
-            }
-        }
-
-        // Checksum etc-overrides if present
-        let etc_overrides = snapshot_dir.join("etc-overrides");
-        if etc_overrides.exists() {
-            Self::checksum_directory_recursive(&etc_overrides, snapshot_dir, &mut manifest)?;
-        }
-
+        Self::checksum_directory_recursive(snapshot_dir, snapshot_dir, &mut manifest)?;
+        manifest.files.remove(MANIFEST_FILE);
         Ok(manifest)
     }
 
@@ -146,7 +122,28 @@ impl ChecksumManifest {
                 continue;
             }
 
-            match compute_file_checksum(&full_path) {
+            let is_large = expected_checksum.size >= LARGE_FILE_THRESHOLD;
+            let result = if verbose && is_large {
+                let mut last_percent = None;
+                let mut report = |done: u64, total: u64| {
+                    let percent = (done * 100).checked_div(total).unwrap_or(100);
+                    if last_percent != Some(percent) {
+                        last_percent = Some(percent);
+                        print!("\r  {} {} {:>3}%", "…".cyan(), file_path, percent);
+                        let _ = std::io::stdout().flush();
+                    }
+                };
+                let result =
+                    compute_file_checksum_with(&full_path, LARGE_FILE_BUFFER_SIZE, Some(&mut report));
+                print!("\r\x1b[2K");
+                result
+            } else if is_large {
+                compute_file_checksum_with(&full_path, LARGE_FILE_BUFFER_SIZE, None)
+            } else {
+                compute_file_checksum(&full_path)
+            };
+
+            match result {
                 Ok(actual_checksum) => {
                     if actual_checksum.sha256 == expected_checksum.sha256 {
                         report.valid_files += 1;
@@ -180,7 +177,20 @@ impl ChecksumManifest {
 }
 
 /// Compute SHA256 checksum for a file
-fn compute_file_checksum(path: &Path) -> Result<FileChecksum> {
+pub fn compute_file_checksum(path: &Path) -> Result<FileChecksum> {
+    compute_file_checksum_with(path, DEFAULT_BUFFER_SIZE, None)
+}
+
+/// Progress callback invoked with `(bytes_hashed, total_bytes)`
+pub type ProgressFn<'a> = &'a mut dyn FnMut(u64, u64);
+
+/// Compute SHA256 checksum for a file, streaming it through a buffer of
+/// `buffer_size` bytes and reporting progress after each chunk
+pub fn compute_file_checksum_with(
+    path: &Path,
+    buffer_size: usize,
+    mut progress: Option<ProgressFn>,
+) -> Result<FileChecksum> {
     use sha2::{Sha256, Digest};
 
     let file = fs::File::open(path)
@@ -189,9 +199,10 @@ fn compute_file_checksum(path: &Path) -> Result<FileChecksum> {
     let metadata = file.metadata()?;
     let size = metadata.len();
 
-    let mut reader = BufReader::new(file);
+    let mut reader = BufReader::with_capacity(buffer_size.max(1), file);
     let mut hasher = Sha256::new();
-    let mut buffer = [0; 8192];
+    let mut buffer = vec![0; buffer_size.max(1)];
+    let mut hashed = 0u64;
 
     loop {
         let count = reader.read(&mut buffer)?;
@@ -199,6 +210,11 @@ fn compute_file_checksum(path: &Path) -> Result<FileChecksum> {
             break;
         }
         hasher.update(&buffer[..count]);
+        hashed += count as u64;
+
+        if let Some(report) = progress.as_mut() {
+            report(hashed, size);
+        }
     }
 
     let hash = hasher.finalize();
@@ -264,6 +280,23 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_checksum_progress_and_buffer_size() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let file_path = temp_dir.path().join("data.bin");
+        fs::write(&file_path, vec![7u8; 10_000])?;
+
+        let mut calls = Vec::new();
+        let mut report = |done: u64, total: u64| calls.push((done, total));
+        let streamed = compute_file_checksum_with(&file_path, 4096, Some(&mut report))?;
+
+        assert_eq!(calls.len(), 3);
+        assert_eq!(calls.last(), Some(&(10_000, 10_000)));
+        assert_eq!(streamed.sha256, compute_file_checksum(&file_path)?.sha256);
+
+        Ok(())
+    }
+
     #[test]
     fn test_manifest_generation() -> Result<()> {
         let temp_dir = tempfile::tempdir()?;
@@ -279,4 +312,23 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_manifest_covers_arbitrary_files() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+
+        fs::write(temp_dir.path().join("configuration.nix"), "test content")?;
+        fs::create_dir_all(temp_dir.path().join("cron/daily"))?;
+        fs::write(temp_dir.path().join("cron/daily/backup"), "#!/bin/sh")?;
+        fs::write(temp_dir.path().join(MANIFEST_FILE), "{}")?;
+
+        let manifest = ChecksumManifest::generate(temp_dir.path())?;
+
+        assert!(manifest.files.contains_key("configuration.nix"));
+        assert!(manifest.files.contains_key("cron/daily/backup"));
+        assert!(!manifest.files.contains_key(MANIFEST_FILE));
+        assert_eq!(manifest.files.len(), 2);
+
+        Ok(())
+    }
 }
@@ -1,7 +1,12 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 use std::process::Command;
 
+use capsule::exit::CliError;
+
+use super::checksum::{compute_file_checksum, ChecksumManifest, MANIFEST_FILE};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PackageInfo {
     pub name: String,
@@ -28,13 +33,126 @@ pub struct UserInfo {
     pub groups: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Firewall state captured from ufw or nftables
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FirewallInfo {
+    /// "ufw", "nftables" or "none"
+    pub backend: String,
+    pub tcp_ports: Vec<u16>,
+    pub udp_ports: Vec<u16>,
+    pub tcp_port_ranges: Vec<(u16, u16)>,
+    pub udp_port_ranges: Vec<(u16, u16)>,
+    /// ALLOW rules that aren't simply ports open to everyone inbound, such
+    /// as unknown ufw application profiles, outbound rules or rules limited
+    /// to some sources
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unmapped_rules: Vec<String>,
+    /// Raw output of the firewall tool, kept for non-Nix restores
+    pub raw_rules: Option<String>,
+}
+
+impl Default for FirewallInfo {
+    fn default() -> Self {
+        Self {
+            backend: "none".to_string(),
+            tcp_ports: Vec::new(),
+            udp_ports: Vec::new(),
+            tcp_port_ranges: Vec::new(),
+            udp_port_ranges: Vec::new(),
+            unmapped_rules: Vec::new(),
+            raw_rules: None,
+        }
+    }
+}
+
+impl FirewallInfo {
+    pub fn is_detected(&self) -> bool {
+        self.backend != "none"
+    }
+
+    fn add(&mut self, protocol: Option<&str>, from: u16, to: u16) {
+        let tcp = protocol.is_none() || protocol == Some("tcp");
+        let udp = protocol.is_none() || protocol == Some("udp");
+
+        if from == to {
+            if tcp && !self.tcp_ports.contains(&from) {
+                self.tcp_ports.push(from);
+            }
+            if udp && !self.udp_ports.contains(&from) {
+                self.udp_ports.push(from);
+            }
+        } else {
+            if tcp && !self.tcp_port_ranges.contains(&(from, to)) {
+                self.tcp_port_ranges.push((from, to));
+            }
+            if udp && !self.udp_port_ranges.contains(&(from, to)) {
+                self.udp_port_ranges.push((from, to));
+            }
+        }
+    }
+
+    fn finish(mut self) -> Self {
+        self.tcp_ports.sort_unstable();
+        self.udp_ports.sort_unstable();
+        self.tcp_port_ranges.sort_unstable();
+        self.udp_port_ranges.sort_unstable();
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemSnapshot {
     pub packages: Vec<PackageInfo>,
     pub services: Vec<ServiceInfo>,
     pub users: Vec<UserInfo>,
     pub hostname: String,
     pub os_version: String,
+    #[serde(default)]
+    pub firewall: FirewallInfo,
+}
+
+/// Raw snapshot data written alongside the generated Nix files
+pub const SNAPSHOT_FILE: &str = "snapshot.json";
+
+impl SystemSnapshot {
+    pub fn save(&self, snapshot_dir: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(snapshot_dir.join(SNAPSHOT_FILE), json)
+            .context("Failed to write snapshot.json")
+    }
+
+    /// Read back the data collected by `server pack`, so a snapshot can be
+    /// inspected or converted without scanning the live system again. When
+    /// the checksum manifest lists the file, its checksum must match.
+    pub fn load(snapshot_dir: &Path) -> Result<Self> {
+        let path = snapshot_dir.join(SNAPSHOT_FILE);
+        if !path.exists() {
+            return Err(CliError::not_found(format!(
+                "{} not found in {} (snapshots from older versions need to be re-packed)",
+                SNAPSHOT_FILE,
+                snapshot_dir.display()
+            ))
+            .into());
+        }
+
+        let manifest_path = snapshot_dir.join(MANIFEST_FILE);
+        if manifest_path.exists() {
+            let manifest = ChecksumManifest::load(&manifest_path)?;
+            if let Some(expected) = manifest.files.get(SNAPSHOT_FILE) {
+                let actual = compute_file_checksum(&path)?;
+                if actual.sha256 != expected.sha256 {
+                    anyhow::bail!(
+                        "{} does not match {}; the snapshot may have been modified",
+                        SNAPSHOT_FILE,
+                        MANIFEST_FILE
+                    );
+                }
+            }
+        }
+
+        let contents = std::fs::read_to_string(&path).context("Failed to read snapshot.json")?;
+        serde_json::from_str(&contents).context("Failed to parse snapshot.json")
+    }
 }
 
 pub fn collect_packages() -> Result<Vec<PackageInfo>> {
@@ -42,7 +160,7 @@ pub fn collect_packages() -> Result<Vec<PackageInfo>> {
 
     // Get all installed packages
     let output = Command::new("dpkg-query")
-        .args(&["-W", "-f=${Package}|${Version}|${Architecture}\\n"])
+        .args(["-W", "-f=${Package}|${Version}|${Architecture}\\n"])
         .output()
         .context("Failed to query installed packages")?;
 
@@ -81,7 +199,7 @@ pub fn collect_services() -> Result<Vec<ServiceInfo>> {
 
     // Get all services
     let output = Command::new("systemctl")
-        .args(&["list-unit-files", "--type=service", "--no-pager", "--no-legend"])
+        .args(["list-unit-files", "--type=service", "--no-pager", "--no-legend"])
         .output()
         .context("Failed to list systemd services")?;
 
@@ -95,7 +213,7 @@ pub fn collect_services() -> Result<Vec<ServiceInfo>> {
 
             // Check if service is running
             let running = Command::new("systemctl")
-                .args(&["is-active", &service_name])
+                .args(["is-active", &service_name])
                 .output()
                 .map(|o| String::from_utf8_lossy(&o.stdout).trim() == "active")
                 .unwrap_or(false);
@@ -184,3 +302,332 @@ pub fn collect_users() -> Result<Vec<UserInfo>> {
 
     Ok(users)
 }
+
+/// List /etc config files that differ from their packaged version
+pub fn collect_modified_etc_files() -> Vec<std::path::PathBuf> {
+    // `dpkg --verify` prints e.g. "??5??????  c /etc/ssh/sshd_config"
+    let Ok(output) = Command::new("dpkg").arg("--verify").output() else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<std::path::PathBuf> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let flags = parts.next()?;
+            let path = parts.last()?;
+            (flags.chars().nth(2) == Some('5') && path.starts_with("/etc/"))
+                .then(|| std::path::PathBuf::from(path))
+        })
+        .collect();
+
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// Collect the active firewall rules, preferring ufw over nftables
+pub fn collect_firewall() -> FirewallInfo {
+    if let Ok(output) = Command::new("ufw").args(["status", "numbered"]).output() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if output.status.success() && stdout.contains("Status: active") {
+            return parse_ufw_status(&stdout);
+        }
+    }
+
+    if let Ok(output) = Command::new("nft").args(["list", "ruleset"]).output() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if output.status.success() && !stdout.trim().is_empty() {
+            return parse_nft_ruleset(&stdout);
+        }
+    }
+
+    FirewallInfo::default()
+}
+
+/// Parse a port spec such as `22`, `6000:6007` or `6000-6007`
+fn parse_port_spec(spec: &str) -> Option<(u16, u16)> {
+    let spec = spec.trim();
+    match spec.split_once([':', '-']) {
+        Some((from, to)) => Some((from.trim().parse().ok()?, to.trim().parse().ok()?)),
+        None => spec.parse().ok().map(|p| (p, p)),
+    }
+}
+
+/// Ports of common ufw application profiles (`ufw app list`)
+const UFW_APP_PROFILES: &[(&str, &str)] = &[
+    ("OpenSSH", "22/tcp"),
+    ("Nginx HTTP", "80/tcp"),
+    ("Nginx HTTPS", "443/tcp"),
+    ("Nginx Full", "80,443/tcp"),
+    ("Apache", "80/tcp"),
+    ("Apache Secure", "443/tcp"),
+    ("Apache Full", "80,443/tcp"),
+    ("Postfix", "25/tcp"),
+    ("Postfix SMTPS", "465/tcp"),
+    ("Postfix Submission", "587/tcp"),
+    ("Dovecot IMAP", "143/tcp"),
+    ("Dovecot Secure IMAP", "993/tcp"),
+    ("Dovecot POP3", "110/tcp"),
+    ("Dovecot Secure POP3", "995/tcp"),
+    ("Bind9", "53"),
+    ("Samba", "137,138/udp"),
+];
+
+/// Parse `ufw status numbered` output, keeping ALLOW rules only. Inbound
+/// rules open to any source become ports, with known application profiles
+/// mapped to theirs; every other ALLOW rule is listed in `unmapped_rules`
+/// rather than opened to the world.
+pub fn parse_ufw_status(output: &str) -> FirewallInfo {
+    let mut info = FirewallInfo {
+        backend: "ufw".to_string(),
+        raw_rules: Some(output.to_string()),
+        ..Default::default()
+    };
+
+    for line in output.lines() {
+        // Strip the "[ 1]" rule number
+        let line = match line.trim().strip_prefix('[') {
+            Some(rest) => rest.split_once(']').map(|(_, r)| r).unwrap_or(rest),
+            None => continue,
+        };
+        // Drop "(v6)" so both address families of a rule read alike
+        let rule = line.split_whitespace().filter(|w| *w != "(v6)").collect::<Vec<_>>().join(" ");
+        let Some((target, action)) = rule.split_once(" ALLOW") else { continue };
+
+        // "ALLOW" alone means inbound; OUT and FWD rules open nothing here
+        let action = action.split(" #").next().unwrap_or_default().trim();
+        let from = action.strip_prefix("IN ").unwrap_or(action);
+        let ports = if from != "Anywhere" {
+            None
+        } else if target.starts_with(|c: char| c.is_ascii_digit()) {
+            // A port spec alone; "on eth0" and the like limit the rule
+            (!target.contains(' ')).then_some(target)
+        } else {
+            UFW_APP_PROFILES.iter().find(|(name, _)| *name == target).map(|(_, ports)| *ports)
+        };
+        let Some(target) = ports else {
+            if !info.unmapped_rules.contains(&rule) {
+                info.unmapped_rules.push(rule);
+            }
+            continue;
+        };
+        let (ports, protocol) = match target.split_once('/') {
+            Some((ports, proto)) => (ports, Some(proto)),
+            None => (target, None),
+        };
+
+        for spec in ports.split(',') {
+            if let Some((from, to)) = parse_port_spec(spec) {
+                info.add(protocol, from, to);
+            }
+        }
+    }
+
+    info.finish()
+}
+
+/// Parse `nft list ruleset` output for `tcp|udp dport ... accept` rules.
+/// Only rules in input chains that don't match on source or interface
+/// become ports; the others are listed in `unmapped_rules`.
+pub fn parse_nft_ruleset(output: &str) -> FirewallInfo {
+    let mut info = FirewallInfo {
+        backend: "nftables".to_string(),
+        raw_rules: Some(output.to_string()),
+        ..Default::default()
+    };
+
+    // Regular chains have no hook; they are reached by jumps from input
+    let mut inbound = true;
+    for line in output.lines() {
+        let line = line.trim();
+        if line.starts_with("chain ") {
+            inbound = true;
+        }
+        if let Some((_, hook)) = line.split_once("hook ") {
+            inbound = hook.starts_with("input");
+        }
+        if !line.ends_with("accept") || !line.contains(" dport ") {
+            continue;
+        }
+
+        let restricted = ["saddr", "iif", "iifname"]
+            .iter()
+            .any(|word| line.split_whitespace().any(|w| w == *word));
+        if !inbound || restricted {
+            if !info.unmapped_rules.iter().any(|r| r == line) {
+                info.unmapped_rules.push(line.to_string());
+            }
+            continue;
+        }
+
+        for protocol in ["tcp", "udp"] {
+            let marker = format!("{} dport ", protocol);
+            let Some(idx) = line.find(&marker) else { continue };
+            let rest = &line[idx + marker.len()..];
+
+            let ports = if let Some(set) = rest.strip_prefix('{') {
+                set.split('}').next().unwrap_or("")
+            } else {
+                rest.split_whitespace().next().unwrap_or("")
+            };
+
+            for spec in ports.split(',') {
+                if let Some((from, to)) = parse_port_spec(spec) {
+                    info.add(Some(protocol), from, to);
+                }
+            }
+        }
+    }
+
+    info.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_snapshot() -> SystemSnapshot {
+        SystemSnapshot {
+            packages: vec![PackageInfo {
+                name: "nginx".to_string(),
+                version: "1.24".to_string(),
+                architecture: "amd64".to_string(),
+                manually_installed: true,
+            }],
+            services: Vec::new(),
+            users: Vec::new(),
+            hostname: "web-1".to_string(),
+            os_version: "Ubuntu 24.04".to_string(),
+            firewall: FirewallInfo::default(),
+        }
+    }
+
+    #[test]
+    fn test_snapshot_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = SystemSnapshot::load(dir.path()).unwrap_err();
+        assert_eq!(capsule::exit::exit_code_for(&err), capsule::exit::NOT_FOUND);
+
+        sample_snapshot().save(dir.path()).unwrap();
+        ChecksumManifest::generate(dir.path())
+            .unwrap()
+            .save(&dir.path().join(MANIFEST_FILE))
+            .unwrap();
+
+        let loaded = SystemSnapshot::load(dir.path()).unwrap();
+        assert_eq!(loaded.hostname, "web-1");
+        assert_eq!(loaded.packages[0].name, "nginx");
+
+        // Edits after packing are caught by the manifest
+        let mut edited = sample_snapshot();
+        edited.hostname = "web-2".to_string();
+        edited.save(dir.path()).unwrap();
+        let err = SystemSnapshot::load(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("does not match"));
+    }
+
+    #[test]
+    fn test_parse_ufw_status() {
+        let output = "Status: active
+
+     To                         Action      From
+     --                         ------      ----
+[ 1] 22/tcp                     ALLOW IN    Anywhere
+[ 2] 80,443/tcp                 ALLOW IN    Anywhere
+[ 3] 51820/udp                  ALLOW IN    Anywhere
+[ 4] 6000:6007/tcp              ALLOW IN    Anywhere
+[ 5] 53                         ALLOW IN    Anywhere
+[ 6] 3306/tcp                   DENY IN     Anywhere
+[ 7] 22/tcp (v6)                ALLOW IN    Anywhere (v6)
+[ 8] OpenSSH                    ALLOW IN    Anywhere
+[ 9] Nginx Full                 ALLOW IN    Anywhere
+[10] Custom App                 ALLOW IN    Anywhere
+[11] Custom App (v6)            ALLOW IN    Anywhere (v6)
+";
+        let info = parse_ufw_status(output);
+        assert_eq!(info.backend, "ufw");
+        assert_eq!(info.tcp_ports, vec![22, 53, 80, 443]);
+        assert_eq!(info.unmapped_rules, vec!["Custom App ALLOW IN Anywhere"]);
+        assert_eq!(info.udp_ports, vec![53, 51820]);
+        assert_eq!(info.tcp_port_ranges, vec![(6000, 6007)]);
+        assert!(info.raw_rules.is_some());
+    }
+
+    #[test]
+    fn test_parse_ufw_status_leaves_restricted_rules_closed() {
+        let output = "Status: active
+
+     To                         Action      From
+     --                         ------      ----
+[ 1] 22/tcp                     ALLOW IN    Anywhere
+[ 2] 25/tcp                     ALLOW OUT   Anywhere                   (out)
+[ 3] 5432/tcp                   ALLOW IN    10.0.0.0/8
+[ 4] 8080/tcp on eth1           ALLOW IN    Anywhere
+[ 5] Anywhere                   ALLOW IN    192.168.1.0/24
+";
+        let info = parse_ufw_status(output);
+        assert_eq!(info.tcp_ports, vec![22]);
+        assert_eq!(
+            info.unmapped_rules,
+            vec![
+                "25/tcp ALLOW OUT Anywhere (out)",
+                "5432/tcp ALLOW IN 10.0.0.0/8",
+                "8080/tcp on eth1 ALLOW IN Anywhere",
+                "Anywhere ALLOW IN 192.168.1.0/24",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_nft_ruleset() {
+        let output = "table inet filter {
+\tchain input {
+\t\ttype filter hook input priority 0; policy drop;
+\t\ttcp dport 22 accept
+\t\ttcp dport { 80, 443 } accept
+\t\tudp dport 60000-61000 accept
+\t\ttcp dport 3306 drop
+\t}
+}
+";
+        let info = parse_nft_ruleset(output);
+        assert_eq!(info.backend, "nftables");
+        assert_eq!(info.tcp_ports, vec![22, 80, 443]);
+        assert_eq!(info.udp_port_ranges, vec![(60000, 61000)]);
+        assert!(info.unmapped_rules.is_empty());
+    }
+
+    #[test]
+    fn test_parse_nft_ruleset_leaves_restricted_rules_closed() {
+        let output = "table inet filter {
+\tchain input {
+\t\ttype filter hook input priority 0; policy drop;
+\t\ttcp dport 22 accept
+\t\tip saddr 10.0.0.0/8 tcp dport 5432 accept
+\t\tiifname \"wg0\" udp dport 53 accept
+\t}
+\tchain output {
+\t\ttype filter hook output priority 0; policy accept;
+\t\ttcp dport 25 accept
+\t}
+}
+";
+        let info = parse_nft_ruleset(output);
+        assert_eq!(info.tcp_ports, vec![22]);
+        assert!(info.udp_ports.is_empty());
+        assert_eq!(
+            info.unmapped_rules,
+            vec![
+                "ip saddr 10.0.0.0/8 tcp dport 5432 accept",
+                "iifname \"wg0\" udp dport 53 accept",
+                "tcp dport 25 accept",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_firewall_default_is_none() {
+        assert!(!FirewallInfo::default().is_detected());
+    }
+}
@@ -0,0 +1,242 @@
+// Snapshot encryption at rest
+//
+// An encrypted snapshot is the snapshot directory packed with `tar` and
+// sealed with AES-256-GCM under a key derived from the passphrase with
+// PBKDF2-HMAC-SHA256. GCM authenticates the whole archive, so a tampered or
+// truncated file fails to decrypt instead of yielding altered contents.
+//
+// File layout: MAGIC, 16-byte salt, 12-byte nonce, ciphertext with tag.
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+
+/// File extension for encrypted snapshots
+pub const ENCRYPTED_EXTENSION: &str = "enc";
+
+/// Identifies the format, so other files are rejected up front
+const MAGIC: &[u8] = b"CAPSULE-SNAPSHOT-GCM1";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// PBKDF2 iterations used to derive the key from the passphrase
+#[cfg(not(test))]
+const PBKDF2_ITERATIONS: u32 = 600_000;
+#[cfg(test)]
+const PBKDF2_ITERATIONS: u32 = 1_000;
+
+/// Where the encryption passphrase comes from
+pub enum KeySource {
+    Passphrase(String),
+    KeyFile(PathBuf),
+}
+
+impl KeySource {
+    /// Use `key_file` if given, otherwise prompt for a passphrase without echo
+    pub fn resolve(key_file: Option<PathBuf>, confirm: bool) -> Result<Self> {
+        if let Some(path) = key_file {
+            if !path.exists() {
+                anyhow::bail!("Key file not found: {}", path.display());
+            }
+            return Ok(Self::KeyFile(path));
+        }
+
+        let mut prompt = dialoguer::Password::new().with_prompt("Snapshot passphrase");
+        if confirm {
+            prompt = prompt.with_confirmation("Confirm passphrase", "Passphrases do not match");
+        }
+        let passphrase = prompt.interact().context("Failed to read passphrase")?;
+
+        if passphrase.is_empty() {
+            anyhow::bail!("Passphrase must not be empty");
+        }
+        Ok(Self::Passphrase(passphrase))
+    }
+
+    /// The passphrase; for a key file, its first line
+    fn passphrase(&self) -> Result<String> {
+        match self {
+            Self::Passphrase(passphrase) => Ok(passphrase.clone()),
+            Self::KeyFile(path) => {
+                let contents = fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read key file {}", path.display()))?;
+                let passphrase = contents.lines().next().unwrap_or_default().to_string();
+                if passphrase.is_empty() {
+                    anyhow::bail!("Key file {} is empty", path.display());
+                }
+                Ok(passphrase)
+            }
+        }
+    }
+
+    fn cipher(&self, salt: &[u8]) -> Result<Aes256Gcm> {
+        let mut key = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<sha2::Sha256>(self.passphrase()?.as_bytes(), salt, PBKDF2_ITERATIONS, &mut key);
+        Ok(Aes256Gcm::new(&key.into()))
+    }
+}
+
+/// Seal `plaintext` with a fresh salt and nonce
+fn seal(plaintext: &[u8], key: &KeySource) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = key
+        .cipher(&salt)?
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| anyhow::anyhow!("Failed to encrypt snapshot"))?;
+
+    let mut sealed = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    sealed.extend_from_slice(MAGIC);
+    sealed.extend_from_slice(&salt);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+/// Open a sealed archive, failing if it was modified or the key is wrong
+fn open(sealed: &[u8], key: &KeySource) -> Result<Vec<u8>> {
+    let body = sealed
+        .strip_prefix(MAGIC)
+        .context("Not an encrypted capsule snapshot")?;
+    if body.len() < SALT_LEN + NONCE_LEN {
+        anyhow::bail!("Encrypted snapshot is truncated");
+    }
+    let (salt, rest) = body.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    key.cipher(salt)?
+        .decrypt(&Nonce::from(<[u8; NONCE_LEN]>::try_from(nonce)?), ciphertext)
+        .map_err(|_| anyhow::anyhow!("Failed to decrypt snapshot (wrong passphrase or corrupted archive)"))
+}
+
+/// Is `path` an encrypted snapshot archive rather than a snapshot directory?
+pub fn is_encrypted_snapshot(path: &Path) -> bool {
+    path.is_file() && path.extension().and_then(|e| e.to_str()) == Some(ENCRYPTED_EXTENSION)
+}
+
+/// File name of the encrypted archive for a snapshot called `name`
+pub fn encrypted_file_name(name: &str) -> String {
+    format!("{}.tar.{}", name, ENCRYPTED_EXTENSION)
+}
+
+/// Pack `snapshot_dir` into the encrypted archive `encrypted`
+pub fn encrypt_snapshot(snapshot_dir: &Path, encrypted: &Path, key: &KeySource) -> Result<()> {
+    // The plaintext tarball never leaves a private directory
+    let staging = TempDir::new().context("Failed to create temporary directory")?;
+    let archive = staging.path().join("snapshot.tar");
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(&archive)
+        .arg("-C")
+        .arg(snapshot_dir)
+        .arg(".")
+        .status()
+        .context("Failed to execute tar")?;
+    if !status.success() {
+        anyhow::bail!("Failed to archive snapshot");
+    }
+
+    let plaintext = fs::read(&archive).context("Failed to read snapshot archive")?;
+    fs::write(encrypted, seal(&plaintext, key)?).context("Failed to write encrypted snapshot")?;
+
+    Ok(())
+}
+
+/// A decrypted snapshot extracted to a temporary directory, removed on drop
+pub struct DecryptedSnapshot {
+    dir: TempDir,
+}
+
+impl DecryptedSnapshot {
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+}
+
+/// Decrypt and extract an encrypted snapshot into a private temp directory
+pub fn decrypt_snapshot(archive: &Path, key: &KeySource) -> Result<DecryptedSnapshot> {
+    let sealed = fs::read(archive)
+        .with_context(|| format!("Failed to read {}", archive.display()))?;
+    let plaintext = open(&sealed, key)?;
+
+    let snapshot = DecryptedSnapshot {
+        dir: TempDir::new().context("Failed to create temporary directory")?,
+    };
+    let tarball = snapshot.path().join(".snapshot.tar");
+    fs::write(&tarball, plaintext).context("Failed to write decrypted archive")?;
+
+    let status = Command::new("tar")
+        .arg("-xzf")
+        .arg(&tarball)
+        .arg("-C")
+        .arg(snapshot.path())
+        .status()
+        .context("Failed to execute tar")?;
+    let _ = fs::remove_file(&tarball);
+
+    if !status.success() {
+        anyhow::bail!("Failed to extract decrypted snapshot");
+    }
+
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let dir = tempdir().unwrap();
+        let snapshot = dir.path().join("snap");
+        fs::create_dir_all(snapshot.join("services")).unwrap();
+        fs::write(snapshot.join("configuration.nix"), "{ }").unwrap();
+        fs::write(snapshot.join("services/app.service"), "[Unit]").unwrap();
+
+        let key = KeySource::Passphrase("correct horse".to_string());
+        let encrypted = dir.path().join(encrypted_file_name("snap"));
+        encrypt_snapshot(&snapshot, &encrypted, &key).unwrap();
+        assert!(is_encrypted_snapshot(&encrypted));
+
+        let decrypted = decrypt_snapshot(&encrypted, &key).unwrap();
+        assert_eq!(
+            fs::read_to_string(decrypted.path().join("configuration.nix")).unwrap(),
+            "{ }"
+        );
+        assert!(decrypted.path().join("services/app.service").exists());
+
+        let wrong = KeySource::Passphrase("wrong".to_string());
+        assert!(decrypt_snapshot(&encrypted, &wrong).is_err());
+
+        // Any modified byte fails authentication
+        let mut tampered = fs::read(&encrypted).unwrap();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 0x01;
+        fs::write(&encrypted, tampered).unwrap();
+        let err = decrypt_snapshot(&encrypted, &key).err().unwrap();
+        assert!(err.to_string().contains("corrupted"));
+    }
+
+    #[test]
+    fn test_key_file_roundtrip() {
+        let dir = tempdir().unwrap();
+        let snapshot = dir.path().join("snap");
+        fs::create_dir_all(&snapshot).unwrap();
+        fs::write(snapshot.join("packages.nix"), "{ }").unwrap();
+        let key_file = dir.path().join("key");
+        fs::write(&key_file, "secret-from-file\n").unwrap();
+
+        let key = KeySource::KeyFile(key_file);
+        let encrypted = dir.path().join(encrypted_file_name("snap"));
+        encrypt_snapshot(&snapshot, &encrypted, &key).unwrap();
+        let decrypted = decrypt_snapshot(&encrypted, &key).unwrap();
+        assert!(decrypted.path().join("packages.nix").exists());
+    }
+}
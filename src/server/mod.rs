@@ -1,26 +1,86 @@
 use anyhow::{Context, Result};
 use colored::*;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::fs;
+use tempfile::TempDir;
+
+use capsule::exit::CliError;
+use capsule::ui::tip;
 
 mod collectors;
 mod nix_generator;
 mod package_mapper;
-mod checksum;
+pub mod checksum;
+mod encryption;
 
-use collectors::SystemSnapshot;
+pub use collectors::SystemSnapshot;
+pub use nix_generator::{nixos_profile, nixos_users};
 use nix_generator::NixConfigGenerator;
 use checksum::ChecksumManifest;
+use encryption::KeySource;
+
+/// Files that never belong in a shareable snapshot
+pub const DEFAULT_EXCLUDES: &[&str] = &[
+    "/etc/shadow*",
+    "/etc/gshadow*",
+    "/etc/ssl/private/**",
+    "/etc/ssh/ssh_host_*_key",
+    "/etc/letsencrypt/**",
+    "**/*.key",
+];
+
+/// Options for `server pack`
+#[derive(Debug, Default)]
+pub struct PackOptions {
+    /// Merge the active profile's packages into packages.nix
+    pub include_profile: bool,
+    /// Extra glob patterns for files to leave out of etc-overrides
+    pub excludes: Vec<String>,
+    /// Skip the built-in exclude list
+    pub no_default_excludes: bool,
+    /// Produce an encrypted archive instead of a plain directory
+    pub encrypt: bool,
+    /// Read the encryption passphrase from this file instead of prompting
+    pub key_file: Option<PathBuf>,
+}
 
-pub fn pack(output_dir: &Path) -> Result<()> {
+impl PackOptions {
+    fn exclude_patterns(&self) -> Vec<String> {
+        let mut patterns: Vec<String> = if self.no_default_excludes {
+            Vec::new()
+        } else {
+            DEFAULT_EXCLUDES.iter().map(|s| s.to_string()).collect()
+        };
+        patterns.extend(self.excludes.iter().cloned());
+        patterns
+    }
+}
+
+pub fn pack(output_dir: &Path, options: &PackOptions) -> Result<()> {
     println!("{}", "📸 Creating server snapshot...".cyan().bold());
     println!();
 
+    // Ask for the passphrase up front rather than after a long collection
+    let key = if options.encrypt {
+        Some(KeySource::resolve(options.key_file.clone(), true)?)
+    } else {
+        None
+    };
+
     // Create output directory
     fs::create_dir_all(output_dir)
         .context("Failed to create output directory")?;
 
+    // An encrypted snapshot is assembled in a private directory, so only
+    // the sealed archive ever lands in `output_dir`
+    let staging = if key.is_some() {
+        Some(TempDir::new().context("Failed to create temporary directory")?)
+    } else {
+        None
+    };
+    let snapshot_dir = staging.as_ref().map_or(output_dir, |dir| dir.path());
+
     println!("{} Analyzing system...", "▸".green().bold());
 
     // Collect system information
@@ -29,57 +89,212 @@ pub fn pack(output_dir: &Path) -> Result<()> {
     println!("{} Found {} packages", "  ✓".green(), snapshot.packages.len());
     println!("{} Found {} services", "  ✓".green(), snapshot.services.len());
     println!("{} Found {} users", "  ✓".green(), snapshot.users.len());
+    if snapshot.firewall.is_detected() {
+        println!(
+            "{} Found {} firewall ({} TCP, {} UDP ports)",
+            "  ✓".green(),
+            snapshot.firewall.backend,
+            snapshot.firewall.tcp_ports.len() + snapshot.firewall.tcp_port_ranges.len(),
+            snapshot.firewall.udp_ports.len() + snapshot.firewall.udp_port_ranges.len()
+        );
+        for rule in &snapshot.firewall.unmapped_rules {
+            println!(
+                "{} Firewall rule '{}' was not carried over; recreate it in firewall.nix by hand",
+                "  !".yellow(),
+                rule
+            );
+        }
+    } else {
+        println!("{} Firewall: none detected", "  !".yellow());
+    }
+    println!();
+
+    snapshot.save(snapshot_dir)?;
+    println!("{} Saved {}", "  ✓".green(), collectors::SNAPSHOT_FILE);
     println!();
 
     // Generate Nix configuration
     println!("{} Generating Nix configuration...", "▸".green().bold());
-    let generator = NixConfigGenerator::new(snapshot);
-    generator.generate(output_dir)?;
+    let mut generator = NixConfigGenerator::new(snapshot);
+
+    if options.include_profile {
+        let (profile_name, config) = capsule::config::load_active_config()?;
+        let (packages, _) = capsule::config::collect_packages(&config)?;
+        println!(
+            "{} Merging {} packages from profile '{}'",
+            "  ✓".green(),
+            packages.len(),
+            profile_name
+        );
+        generator = generator.with_profile_packages(&profile_name, packages);
+    }
+
+    generator.generate(snapshot_dir)?;
 
     println!("{} Created configuration.nix", "  ✓".green());
     println!("{} Created packages.nix", "  ✓".green());
     println!("{} Created users.nix", "  ✓".green());
+    println!("{} Created firewall.nix", "  ✓".green());
     println!("{} Created services/", "  ✓".green());
     println!();
 
+    // Capture modified /etc files, skipping anything sensitive
+    println!("{} Capturing modified /etc files...", "▸".green().bold());
+    let (included, excluded) = partition_excluded(
+        collectors::collect_modified_etc_files(),
+        &options.exclude_patterns(),
+    );
+    let captured = copy_etc_overrides(&included, &snapshot_dir.join("etc-overrides"));
+    println!("{} Captured {} files in etc-overrides/", "  ✓".green(), captured);
+    for path in &excluded {
+        println!("{} Excluded {}", "  ○".white(), path.display());
+    }
+    println!();
+
+    let excluded: Vec<String> = excluded.iter().map(|p| p.display().to_string()).collect();
+
     // Save README
-    let readme = generate_readme();
-    fs::write(output_dir.join("README.md"), readme)?;
+    let readme = generate_readme(&excluded);
+    fs::write(snapshot_dir.join("README.md"), readme)?;
     println!("{} Created README.md", "  ✓".green());
     println!();
 
     // Generate checksums
     println!("{} Generating checksums...", "▸".green().bold());
-    let manifest = ChecksumManifest::generate(output_dir)?;
-    let checksum_file = output_dir.join("checksums.json");
+    let mut manifest = ChecksumManifest::generate(snapshot_dir)?;
+    manifest.excluded = excluded;
+    let checksum_file = snapshot_dir.join(checksum::MANIFEST_FILE);
     manifest.save(&checksum_file)?;
     println!("{} Created checksums.json ({} files)", "  ✓".green(), manifest.files.len());
     println!();
 
+    let snapshot_path = match key {
+        Some(key) => {
+            println!("{} Encrypting snapshot...", "▸".green().bold());
+            let name = output_dir
+                .canonicalize()
+                .context("Failed to resolve output directory")?
+                .file_name()
+                .map_or_else(|| "snapshot".to_string(), |name| name.to_string_lossy().into_owned());
+            let encrypted = output_dir.join(encryption::encrypted_file_name(&name));
+            encryption::encrypt_snapshot(snapshot_dir, &encrypted, &key)?;
+            println!("{} Created {}", "  ✓".green(), encrypted.display());
+            println!();
+            encrypted
+        }
+        None => output_dir.to_path_buf(),
+    };
+    let output_dir = snapshot_path.as_path();
+
     println!(
         "{} Snapshot created successfully at: {}",
         "✅".green(),
         output_dir.display().to_string().cyan()
     );
-    println!();
-    println!(
-        "{} To validate: {} {}",
-        "💡 Tip:".yellow(),
+    tip(&format!(
+        "To validate: {} {}",
         "capsule server validate".cyan().bold(),
         output_dir.display().to_string().cyan()
-    );
-    println!(
-        "{} To restore: {} {}",
-        "💡 Tip:".yellow(),
+    ));
+    tip(&format!(
+        "To restore: {} {}",
         "capsule server unpack".cyan().bold(),
         output_dir.display().to_string().cyan()
-    );
-    println!();
+    ));
 
     Ok(())
 }
 
-pub fn unpack(snapshot_dir: &Path, dry_run: bool) -> Result<()> {
+/// Decrypt `snapshot` first if it is an encrypted archive, then run `f`
+fn with_snapshot_dir<T, F>(snapshot: &Path, key_file: Option<PathBuf>, f: F) -> Result<T>
+where
+    F: FnOnce(&Path) -> Result<T>,
+{
+    if !encryption::is_encrypted_snapshot(snapshot) {
+        return f(snapshot);
+    }
+
+    println!("{} Decrypting {}...", "▸".green().bold(), snapshot.display());
+    let key = KeySource::resolve(key_file, false)?;
+    let decrypted = encryption::decrypt_snapshot(snapshot, &key)?;
+    println!("{} Snapshot decrypted", "  ✓".green());
+    println!();
+
+    f(decrypted.path())
+}
+
+/// Load the collected system data from a snapshot directory or archive
+pub fn load_snapshot(snapshot: &Path, key_file: Option<PathBuf>) -> Result<SystemSnapshot> {
+    if !snapshot.exists() {
+        return Err(CliError::not_found(format!("Snapshot not found: {}", snapshot.display())).into());
+    }
+    with_snapshot_dir(snapshot, key_file, SystemSnapshot::load)
+}
+
+/// A part of a snapshot that `server unpack` can apply on its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RestoreStep {
+    /// Install Nix if needed and apply configuration.nix
+    Packages,
+    /// Create accounts recorded in snapshot.json that are missing
+    Users,
+    /// Copy etc-overrides/ into /etc
+    Etc,
+    /// Install, enable and start the systemd units in services/
+    Services,
+}
+
+impl RestoreStep {
+    /// Every step, in the order `unpack` applies them
+    pub const ALL: [RestoreStep; 4] = [Self::Packages, Self::Users, Self::Etc, Self::Services];
+
+    /// The steps to run for `--only`: everything when none are given,
+    /// otherwise the selection in restore order without duplicates
+    pub fn selection(only: &[RestoreStep]) -> Vec<RestoreStep> {
+        if only.is_empty() {
+            return Self::ALL.to_vec();
+        }
+        let mut steps = only.to_vec();
+        steps.sort();
+        steps.dedup();
+        steps
+    }
+}
+
+impl std::str::FromStr for RestoreStep {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "packages" => Ok(Self::Packages),
+            "users" => Ok(Self::Users),
+            "etc" => Ok(Self::Etc),
+            "services" => Ok(Self::Services),
+            other => Err(format!(
+                "unknown component '{}' (expected packages, users, etc or services)",
+                other
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for RestoreStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Packages => "packages",
+            Self::Users => "users",
+            Self::Etc => "etc",
+            Self::Services => "services",
+        })
+    }
+}
+
+/// Apply a snapshot; `only` limits which components are restored (all when empty)
+pub fn unpack(snapshot: &Path, dry_run: bool, only: &[RestoreStep], key_file: Option<PathBuf>) -> Result<()> {
+    with_snapshot_dir(snapshot, key_file, |dir| unpack_dir(dir, dry_run, only))
+}
+
+fn unpack_dir(snapshot_dir: &Path, dry_run: bool, only: &[RestoreStep]) -> Result<()> {
     if dry_run {
         println!("{}", "🔍 Dry run - showing what would be done".cyan().bold());
     } else {
@@ -89,14 +304,45 @@ pub fn unpack(snapshot_dir: &Path, dry_run: bool) -> Result<()> {
 
     // Validate snapshot directory
     if !snapshot_dir.exists() {
-        anyhow::bail!("Snapshot directory not found: {}", snapshot_dir.display());
+        return Err(CliError::not_found(format!(
+            "Snapshot directory not found: {}",
+            snapshot_dir.display()
+        ))
+        .into());
     }
 
-    let config_file = snapshot_dir.join("configuration.nix");
-    if !config_file.exists() {
+    let steps = RestoreStep::selection(only);
+    if steps.contains(&RestoreStep::Packages) && !snapshot_dir.join("configuration.nix").exists() {
         anyhow::bail!("Invalid snapshot: configuration.nix not found");
     }
+    if steps.len() < RestoreStep::ALL.len() {
+        let names: Vec<String> = steps.iter().map(|s| s.to_string()).collect();
+        println!("{} Restoring only: {}", "ℹ".cyan(), names.join(", "));
+        println!();
+    }
+
+    for step in steps {
+        match step {
+            RestoreStep::Packages => restore_packages(snapshot_dir, dry_run)?,
+            RestoreStep::Users => restore_users(snapshot_dir, dry_run)?,
+            RestoreStep::Etc => restore_etc(snapshot_dir, dry_run)?,
+            RestoreStep::Services => restore_services(snapshot_dir, dry_run)?,
+        }
+        println!();
+    }
 
+    if dry_run {
+        println!("{} Dry run complete - no changes made", "✅".green());
+        println!();
+    } else {
+        println!("{} Server restoration complete!", "✅".green());
+        tip(&format!("Validate services with: {}", "systemctl status".cyan().bold()));
+    }
+
+    Ok(())
+}
+
+fn restore_packages(snapshot_dir: &Path, dry_run: bool) -> Result<()> {
     println!("{} Checking Nix installation...", "▸".green().bold());
 
     let nix_installed = Command::new("nix")
@@ -124,10 +370,99 @@ pub fn unpack(snapshot_dir: &Path, dry_run: bool) -> Result<()> {
         println!("{} Configuration applied", "  ✓".green());
     } else {
         println!("{} Would apply Nix configuration from {}",
-            "  →".cyan(), config_file.display());
+            "  →".cyan(), snapshot_dir.join("configuration.nix").display());
     }
-    println!();
 
+    Ok(())
+}
+
+fn restore_users(snapshot_dir: &Path, dry_run: bool) -> Result<()> {
+    println!("{} Restoring user accounts...", "▸".green().bold());
+    if !snapshot_dir.join(collectors::SNAPSHOT_FILE).exists() {
+        println!("{} No {} found", "  ○".white(), collectors::SNAPSHOT_FILE);
+        return Ok(());
+    }
+
+    let snapshot = SystemSnapshot::load(snapshot_dir)?;
+    let missing = missing_users(&snapshot.users, user_exists);
+    if missing.is_empty() {
+        println!("{} All {} users already exist", "  ✓".green(), snapshot.users.len());
+        return Ok(());
+    }
+
+    for user in missing {
+        if dry_run {
+            println!("{} Would create user {}", "  →".cyan(), user.username);
+            continue;
+        }
+        let status = Command::new("sudo")
+            .arg("useradd")
+            .args(useradd_args(user))
+            .status()
+            .context("Failed to run useradd")?;
+        if !status.success() {
+            anyhow::bail!("Failed to create user {} ({})", user.username, status);
+        }
+        println!("{} Created user {}", "  ✓".green(), user.username);
+    }
+
+    Ok(())
+}
+
+/// Users from the snapshot that `exists` does not know about; root is always present
+fn missing_users(
+    users: &[collectors::UserInfo],
+    exists: impl Fn(&str) -> bool,
+) -> Vec<&collectors::UserInfo> {
+    users
+        .iter()
+        .filter(|user| user.username != "root" && !exists(&user.username))
+        .collect()
+}
+
+fn user_exists(username: &str) -> bool {
+    Command::new("id")
+        .arg("-u")
+        .arg(username)
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// `useradd` arguments recreating `user`; groups missing on this host are skipped
+fn useradd_args(user: &collectors::UserInfo) -> Vec<String> {
+    let mut args = vec![
+        "--create-home".to_string(),
+        "--home-dir".to_string(),
+        user.home.clone(),
+        "--shell".to_string(),
+        user.shell.clone(),
+        "--uid".to_string(),
+        user.uid.to_string(),
+    ];
+    let groups: Vec<&str> = user
+        .groups
+        .iter()
+        .map(String::as_str)
+        .filter(|g| *g != user.username && group_exists(g))
+        .collect();
+    if !groups.is_empty() {
+        args.push("--groups".to_string());
+        args.push(groups.join(","));
+    }
+    args.push(user.username.clone());
+    args
+}
+
+fn group_exists(group: &str) -> bool {
+    Command::new("getent")
+        .args(["group", group])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+fn restore_etc(snapshot_dir: &Path, dry_run: bool) -> Result<()> {
     println!("{} Restoring configuration files...", "▸".green().bold());
     let etc_overrides = snapshot_dir.join("etc-overrides");
     if etc_overrides.exists() {
@@ -140,35 +475,75 @@ pub fn unpack(snapshot_dir: &Path, dry_run: bool) -> Result<()> {
     } else {
         println!("{} No etc-overrides found", "  ○".white());
     }
-    println!();
 
+    Ok(())
+}
+
+fn restore_services(snapshot_dir: &Path, dry_run: bool) -> Result<()> {
     println!("{} Enabling and starting services...", "▸".green().bold());
-    if !dry_run {
-        enable_services(snapshot_dir)?;
-        println!("{} Services started", "  ✓".green());
-    } else {
+    if dry_run {
         println!("{} Would enable and start systemd services", "  →".cyan());
+        return Ok(());
     }
-    println!();
 
-    if dry_run {
-        println!("{} Dry run complete - no changes made", "✅".green());
-    } else {
-        println!("{} Server restoration complete!", "✅".green());
-        println!();
-        println!("{} Validate services with: {}",
-            "💡 Tip:".yellow(),
-            "systemctl status".cyan().bold());
+    let report = enable_services(snapshot_dir)?;
+    println!("{} {} service(s) enabled and started", "  ✓".green(), report.started.len());
+    for failure in &report.failures {
+        println!(
+            "{} {}: failed to {} ({})",
+            "  ✗".red(),
+            failure.service,
+            failure.step,
+            failure.reason
+        );
+    }
+    if !report.failures.is_empty() {
+        let names: Vec<&str> = report.failures.iter().map(|f| f.service.as_str()).collect();
+        anyhow::bail!(
+            "Restore incomplete: {} service(s) failed: {}. Check them with `journalctl -u <service>`",
+            names.len(),
+            names.join(", ")
+        );
     }
-    println!();
 
     Ok(())
 }
 
+/// Split `files` into those to keep and those matching an exclude pattern
+fn partition_excluded(files: Vec<PathBuf>, patterns: &[String]) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    files
+        .into_iter()
+        .partition(|path| !capsule::glob::matches_any(patterns, &path.to_string_lossy()))
+}
+
+/// Copy /etc files into `dest`, preserving their path below /etc.
+/// Unreadable files are skipped with a warning.
+fn copy_etc_overrides(files: &[PathBuf], dest: &Path) -> usize {
+    let mut copied = 0;
+
+    for path in files {
+        let Ok(relative) = path.strip_prefix("/etc") else { continue };
+        let target = dest.join(relative);
+
+        let result = target
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::copy(path, &target));
+
+        match result {
+            Ok(_) => copied += 1,
+            Err(e) => println!("{} Skipped {}: {}", "  !".yellow(), path.display(), e),
+        }
+    }
+
+    copied
+}
+
 fn collect_system_snapshot() -> Result<SystemSnapshot> {
     let packages = collectors::collect_packages()?;
     let services = collectors::collect_services()?;
     let users = collectors::collect_users()?;
+    let firewall = collectors::collect_firewall();
 
     Ok(SystemSnapshot {
         packages,
@@ -176,6 +551,7 @@ fn collect_system_snapshot() -> Result<SystemSnapshot> {
         users,
         hostname: get_hostname()?,
         os_version: get_os_version()?,
+        firewall,
     })
 }
 
@@ -240,7 +616,7 @@ fn restore_etc_overrides(etc_dir: &Path) -> Result<()> {
     let status = Command::new("sudo")
         .arg("cp")
         .arg("-r")
-        .arg(etc_dir.to_str().unwrap())
+        .arg(format!("{}/.", etc_dir.display()))
         .arg("/etc/")
         .status()
         .context("Failed to restore etc files")?;
@@ -252,64 +628,140 @@ fn restore_etc_overrides(etc_dir: &Path) -> Result<()> {
     Ok(())
 }
 
-fn enable_services(snapshot_dir: &Path) -> Result<()> {
+/// Times `systemctl start` is tried before a service counts as failed
+const SERVICE_START_ATTEMPTS: u32 = 3;
+
+/// Pause between `systemctl start` attempts
+const SERVICE_START_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Which step of bringing a service up failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ServiceStep {
+    Install,
+    Enable,
+    Start,
+}
+
+impl std::fmt::Display for ServiceStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Install => "install",
+            Self::Enable => "enable",
+            Self::Start => "start",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ServiceFailure {
+    service: String,
+    step: ServiceStep,
+    reason: String,
+}
+
+/// Outcome of `enable_services`: every service is tried even after a failure
+#[derive(Debug, Default)]
+struct ServiceReport {
+    started: Vec<String>,
+    failures: Vec<ServiceFailure>,
+}
+
+fn enable_services(snapshot_dir: &Path) -> Result<ServiceReport> {
+    enable_services_with(snapshot_dir, SERVICE_START_RETRY_DELAY, |args| {
+        let status = Command::new("sudo")
+            .args(args)
+            .status()
+            .map_err(|e| format!("could not run sudo: {}", e))?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(status.to_string())
+        }
+    })
+}
+
+/// Install, enable and start each unit in `services/`, running commands
+/// through `run` (arguments to `sudo`; `Err` holds why it failed)
+fn enable_services_with(
+    snapshot_dir: &Path,
+    retry_delay: std::time::Duration,
+    mut run: impl FnMut(&[&str]) -> std::result::Result<(), String>,
+) -> Result<ServiceReport> {
+    let mut report = ServiceReport::default();
     let services_dir = snapshot_dir.join("services");
 
     if !services_dir.exists() {
-        return Ok(());
+        return Ok(report);
     }
 
-    for entry in fs::read_dir(&services_dir)? {
-        let entry = entry?;
-        let path = entry.path();
-
-        if path.extension().and_then(|s| s.to_str()) == Some("service") {
-            let service_name = path.file_name()
-                .and_then(|s| s.to_str())
-                .unwrap_or("");
-
-            // Copy service file
-            Command::new("sudo")
-                .arg("cp")
-                .arg(&path)
-                .arg(format!("/etc/systemd/system/{}", service_name))
-                .status()?;
-
-            // Enable and start service
-            Command::new("sudo")
-                .arg("systemctl")
-                .arg("enable")
-                .arg(service_name)
-                .status()?;
-
-            Command::new("sudo")
-                .arg("systemctl")
-                .arg("start")
-                .arg(service_name)
-                .status()?;
+    let mut units: Vec<PathBuf> = fs::read_dir(&services_dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<_>>()?;
+    units.retain(|path| path.extension().and_then(|s| s.to_str()) == Some("service"));
+    units.sort();
+
+    let mut installed = Vec::new();
+    for path in &units {
+        let Some(service) = path.file_name().and_then(|s| s.to_str()) else { continue };
+        let target = format!("/etc/systemd/system/{}", service);
+        match run(&["cp", &path.to_string_lossy(), &target]) {
+            Ok(()) => installed.push(service.to_string()),
+            Err(reason) => report.failures.push(ServiceFailure {
+                service: service.to_string(),
+                step: ServiceStep::Install,
+                reason,
+            }),
         }
     }
 
-    // Reload systemd
-    Command::new("sudo")
-        .arg("systemctl")
-        .arg("daemon-reload")
-        .status()?;
+    // systemd must see the new unit files before they can be enabled
+    if let Err(reason) = run(&["systemctl", "daemon-reload"]) {
+        println!("{} systemctl daemon-reload failed ({})", "  !".yellow(), reason);
+    }
 
-    Ok(())
+    for service in installed {
+        if let Err(reason) = run(&["systemctl", "enable", &service]) {
+            report.failures.push(ServiceFailure { service, step: ServiceStep::Enable, reason });
+            continue;
+        }
+
+        let mut result = run(&["systemctl", "start", &service]);
+        for _ in 1..SERVICE_START_ATTEMPTS {
+            if result.is_ok() {
+                break;
+            }
+            std::thread::sleep(retry_delay);
+            result = run(&["systemctl", "start", &service]);
+        }
+
+        match result {
+            Ok(()) => report.started.push(service),
+            Err(reason) => report.failures.push(ServiceFailure { service, step: ServiceStep::Start, reason }),
+        }
+    }
+
+    Ok(report)
 }
 
-pub fn validate(snapshot_dir: &Path, verbose: bool) -> Result<()> {
+pub fn validate(snapshot: &Path, verbose: bool, key_file: Option<PathBuf>) -> Result<()> {
+    with_snapshot_dir(snapshot, key_file, |dir| validate_dir(dir, verbose))
+}
+
+fn validate_dir(snapshot_dir: &Path, verbose: bool) -> Result<()> {
     println!("{}", "🔍 Validating snapshot integrity...".cyan().bold());
     println!();
 
     // Check if snapshot exists
     if !snapshot_dir.exists() {
-        anyhow::bail!("Snapshot directory not found: {}", snapshot_dir.display());
+        return Err(CliError::not_found(format!(
+            "Snapshot directory not found: {}",
+            snapshot_dir.display()
+        ))
+        .into());
     }
 
     // Load checksums manifest
-    let checksum_file = snapshot_dir.join("checksums.json");
+    let checksum_file = snapshot_dir.join(checksum::MANIFEST_FILE);
     if !checksum_file.exists() {
         anyhow::bail!("Checksum manifest not found. This snapshot may have been created with an older version of capsule.");
     }
@@ -323,6 +775,20 @@ pub fn validate(snapshot_dir: &Path, verbose: bool) -> Result<()> {
     println!("{} Snapshot Information:", "▸".cyan().bold());
     println!("  {} {}", "Version:".white().bold(), manifest.version.cyan());
     println!("  {} {}", "Created:".white().bold(), manifest.created_at.cyan());
+    match SystemSnapshot::load(snapshot_dir) {
+        Ok(snapshot) => {
+            println!("  {} {}", "Host:".white().bold(), snapshot.hostname.cyan());
+            println!("  {} {}", "OS:".white().bold(), snapshot.os_version.cyan());
+            println!(
+                "  {} {} packages, {} services, {} users",
+                "Contents:".white().bold(),
+                snapshot.packages.len(),
+                snapshot.services.len(),
+                snapshot.users.len()
+            );
+        }
+        Err(e) => println!("  {} {}", "Snapshot data:".white().bold(), e.to_string().yellow()),
+    }
     println!();
 
     // Validate files
@@ -419,8 +885,8 @@ pub fn validate(snapshot_dir: &Path, verbose: bool) -> Result<()> {
     Ok(())
 }
 
-fn generate_readme() -> String {
-    r#"# Capsule Server Snapshot
+fn generate_readme(excluded: &[String]) -> String {
+    let mut readme = String::from(r#"# Capsule Server Snapshot
 
 This snapshot was created by the `capsule` CLI tool.
 
@@ -429,8 +895,10 @@ This snapshot was created by the `capsule` CLI tool.
 - `configuration.nix` - Main Nix configuration
 - `packages.nix` - Package definitions
 - `users.nix` - User account definitions
+- `firewall.nix` - Open firewall ports (`firewall/` holds the raw rules)
 - `services/` - SystemD service files
 - `etc-overrides/` - Configuration files that can't be declaratively managed
+- `snapshot.json` - The raw collected system data
 - `checksums.json` - File integrity checksums
 
 ## Validation
@@ -458,6 +926,15 @@ To preview what would be done without making changes:
 capsule server unpack --dry-run ./capsule-snapshot
 ```
 
+## Migrating to NixOS
+
+To turn this snapshot into a full NixOS configuration (`configuration.nix`,
+`flake.nix`, `home.nix`):
+
+```bash
+capsule nixos generate --from-snapshot ./capsule-snapshot
+```
+
 ## Requirements
 
 - Ubuntu 20.04+ (recommended)
@@ -484,5 +961,172 @@ Some things may require manual intervention:
 ## Generated
 
 This snapshot was created with Capsule - a user-friendly server configuration tool.
-"#.to_string()
+"#);
+
+    if !excluded.is_empty() {
+        readme.push_str("\n## Excluded Files\n\n");
+        readme.push_str("These files were left out of `etc-overrides/` and must be provisioned manually:\n\n");
+        for path in excluded {
+            readme.push_str(&format!("- `{}`\n", path));
+        }
+    }
+
+    readme
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_excludes_catch_secrets() {
+        let files = vec![
+            PathBuf::from("/etc/shadow"),
+            PathBuf::from("/etc/gshadow-"),
+            PathBuf::from("/etc/ssh/ssh_host_rsa_key"),
+            PathBuf::from("/etc/ssh/ssh_host_rsa_key.pub"),
+            PathBuf::from("/etc/ssl/private/site.pem"),
+            PathBuf::from("/etc/nginx/nginx.conf"),
+        ];
+
+        let options = PackOptions::default();
+        let (included, excluded) = partition_excluded(files, &options.exclude_patterns());
+
+        assert_eq!(
+            included,
+            vec![
+                PathBuf::from("/etc/ssh/ssh_host_rsa_key.pub"),
+                PathBuf::from("/etc/nginx/nginx.conf"),
+            ]
+        );
+        assert_eq!(excluded.len(), 4);
+    }
+
+    #[test]
+    fn test_custom_excludes() {
+        let options = PackOptions {
+            excludes: vec!["/etc/nginx/**".to_string()],
+            no_default_excludes: true,
+            ..Default::default()
+        };
+        let files = vec![PathBuf::from("/etc/shadow"), PathBuf::from("/etc/nginx/nginx.conf")];
+        let (included, excluded) = partition_excluded(files, &options.exclude_patterns());
+
+        assert_eq!(included, vec![PathBuf::from("/etc/shadow")]);
+        assert_eq!(excluded, vec![PathBuf::from("/etc/nginx/nginx.conf")]);
+    }
+
+    #[test]
+    fn test_readme_lists_excluded_files() {
+        let readme = generate_readme(&["/etc/shadow".to_string()]);
+        assert!(readme.contains("## Excluded Files"));
+        assert!(readme.contains("`/etc/shadow`"));
+        assert!(!generate_readme(&[]).contains("## Excluded Files"));
+    }
+
+    #[test]
+    fn test_enable_services_continues_past_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let services = dir.path().join("services");
+        fs::create_dir_all(&services).unwrap();
+        for name in ["api.service", "db.service", "web.service", "notes.txt"] {
+            fs::write(services.join(name), "").unwrap();
+        }
+
+        let mut calls = Vec::new();
+        let mut web_starts = 0;
+        let report = enable_services_with(dir.path(), std::time::Duration::ZERO, |args| {
+            calls.push(args.join(" "));
+            match args {
+                ["systemctl", "enable", "db.service"] => Err("exit status: 1".to_string()),
+                ["systemctl", "start", "web.service"] => {
+                    web_starts += 1;
+                    if web_starts < 2 { Err("exit status: 1".to_string()) } else { Ok(()) }
+                }
+                _ => Ok(()),
+            }
+        })
+        .unwrap();
+
+        assert_eq!(report.started, vec!["api.service", "web.service"]);
+        assert_eq!(
+            report.failures,
+            vec![ServiceFailure {
+                service: "db.service".to_string(),
+                step: ServiceStep::Enable,
+                reason: "exit status: 1".to_string(),
+            }]
+        );
+        assert_eq!(web_starts, 2);
+
+        // units are reloaded before anything is enabled
+        let reload = calls.iter().position(|c| c == "systemctl daemon-reload").unwrap();
+        let first_enable = calls.iter().position(|c| c.starts_with("systemctl enable")).unwrap();
+        assert!(reload < first_enable);
+    }
+
+    #[test]
+    fn test_enable_services_gives_up_after_retries() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("services")).unwrap();
+        fs::write(dir.path().join("services/api.service"), "").unwrap();
+
+        let mut starts = 0;
+        let report = enable_services_with(dir.path(), std::time::Duration::ZERO, |args| {
+            if args[..2] == ["systemctl", "start"] {
+                starts += 1;
+                return Err("exit status: 3".to_string());
+            }
+            Ok(())
+        })
+        .unwrap();
+
+        assert!(report.started.is_empty());
+        assert_eq!(report.failures[0].step, ServiceStep::Start);
+        assert_eq!(starts, SERVICE_START_ATTEMPTS);
+    }
+
+    fn user(name: &str, groups: &[&str]) -> collectors::UserInfo {
+        collectors::UserInfo {
+            username: name.to_string(),
+            uid: 1000,
+            gid: 1000,
+            home: format!("/home/{}", name),
+            shell: "/bin/bash".to_string(),
+            groups: groups.iter().map(|g| g.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_restore_step_selection() {
+        assert_eq!(RestoreStep::selection(&[]), RestoreStep::ALL.to_vec());
+        assert_eq!(
+            RestoreStep::selection(&[RestoreStep::Services, RestoreStep::Etc, RestoreStep::Services]),
+            vec![RestoreStep::Etc, RestoreStep::Services]
+        );
+        assert_eq!("Users".parse::<RestoreStep>(), Ok(RestoreStep::Users));
+        assert!("firewall".parse::<RestoreStep>().unwrap_err().contains("expected packages"));
+    }
+
+    #[test]
+    fn test_unpack_only_skips_configuration_check() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // no configuration.nix: fine unless packages are restored
+        unpack_dir(dir.path(), true, &[RestoreStep::Etc, RestoreStep::Services]).unwrap();
+        let err = unpack_dir(dir.path(), true, &[]).unwrap_err();
+        assert!(err.to_string().contains("configuration.nix not found"));
+    }
+
+    #[test]
+    fn test_missing_users() {
+        let users = vec![user("root", &[]), user("alice", &["alice"]), user("bob", &[])];
+        let missing = missing_users(&users, |name| name == "alice");
+        let names: Vec<&str> = missing.iter().map(|u| u.username.as_str()).collect();
+        assert_eq!(names, vec!["bob"]);
+
+        let args = useradd_args(&users[1]);
+        assert_eq!(args.last().map(String::as_str), Some("alice"));
+        assert!(!args.contains(&"--groups".to_string()));
+    }
 }
@@ -2,12 +2,16 @@ use anyhow::{Context, Result};
 use std::fs;
 use std::path::Path;
 
-use super::collectors::SystemSnapshot;
+use capsule::config::Config;
+use capsule::nixos::ImportedUser;
+
+use super::collectors::{FirewallInfo, SystemSnapshot};
 use super::package_mapper::PackageMapper;
 
 pub struct NixConfigGenerator {
     snapshot: SystemSnapshot,
     mapper: PackageMapper,
+    profile_packages: Option<(String, Vec<String>)>,
 }
 
 impl NixConfigGenerator {
@@ -15,9 +19,16 @@ impl NixConfigGenerator {
         Self {
             snapshot,
             mapper: PackageMapper::new(),
+            profile_packages: None,
         }
     }
 
+    /// Merge a capsule profile's resolved packages into packages.nix
+    pub fn with_profile_packages(mut self, profile: &str, packages: Vec<String>) -> Self {
+        self.profile_packages = Some((profile.to_string(), packages));
+        self
+    }
+
     pub fn generate(&self, output_dir: &Path) -> Result<()> {
         // Create subdirectories
         let services_dir = output_dir.join("services");
@@ -32,6 +43,9 @@ impl NixConfigGenerator {
         // Generate users.nix
         self.generate_users_nix(output_dir)?;
 
+        // Generate firewall.nix (and raw rules for non-Nix restores)
+        self.generate_firewall(output_dir)?;
+
         // Generate service files
         self.generate_service_files(&services_dir)?;
 
@@ -51,6 +65,7 @@ impl NixConfigGenerator {
   imports = [
     ./packages.nix
     ./users.nix
+    ./firewall.nix
   ];
 
   # System metadata
@@ -82,29 +97,7 @@ impl NixConfigGenerator {
     }
 
     fn generate_packages_nix(&self, output_dir: &Path) -> Result<()> {
-        let mut nix_packages = Vec::new();
-        let mut unmapped = Vec::new();
-
-        // Map packages
-        for pkg in &self.snapshot.packages {
-            // Skip system packages
-            if self.mapper.is_system_package(&pkg.name) {
-                continue;
-            }
-
-            // Only include manually installed packages to avoid bloat
-            if pkg.manually_installed {
-                if let Some(nix_name) = self.mapper.map(&pkg.name) {
-                    nix_packages.push(nix_name);
-                } else {
-                    unmapped.push(pkg.name.clone());
-                }
-            }
-        }
-
-        // Remove duplicates
-        nix_packages.sort();
-        nix_packages.dedup();
+        let (nix_packages, unmapped) = map_packages(&self.mapper, &self.snapshot);
 
         let mut config = String::from(
             r#"# Package Configuration
@@ -121,6 +114,16 @@ impl NixConfigGenerator {
             config.push_str(&format!("    {}\n", pkg));
         }
 
+        if let Some((profile, packages)) = &self.profile_packages {
+            let extra = profile_only_packages(&nix_packages, packages);
+            if !extra.is_empty() {
+                config.push_str(&format!("\n    # From capsule profile '{}'\n", profile));
+                for pkg in extra {
+                    config.push_str(&format!("    {}\n", pkg));
+                }
+            }
+        }
+
         config.push_str("  ];\n");
 
         if !unmapped.is_empty() {
@@ -181,6 +184,26 @@ impl NixConfigGenerator {
             .context("Failed to write users.nix")
     }
 
+    fn generate_firewall(&self, output_dir: &Path) -> Result<()> {
+        let firewall = &self.snapshot.firewall;
+
+        fs::write(output_dir.join("firewall.nix"), firewall_to_nix(firewall))
+            .context("Failed to write firewall.nix")?;
+
+        if let Some(ref rules) = firewall.raw_rules {
+            let rules_dir = output_dir.join("firewall");
+            fs::create_dir_all(&rules_dir)?;
+            let file_name = match firewall.backend.as_str() {
+                "nftables" => "nftables.conf",
+                _ => "ufw-status.txt",
+            };
+            fs::write(rules_dir.join(file_name), rules)
+                .context("Failed to write firewall rules")?;
+        }
+
+        Ok(())
+    }
+
     fn generate_service_files(&self, services_dir: &Path) -> Result<()> {
         for service in &self.snapshot.services {
             // Only save custom service files (from /etc/systemd/system)
@@ -212,6 +235,145 @@ impl NixConfigGenerator {
     }
 }
 
+/// Nix names for the snapshot's manually installed packages, sorted and
+/// deduplicated, plus the apt names that could not be mapped
+fn map_packages(mapper: &PackageMapper, snapshot: &SystemSnapshot) -> (Vec<String>, Vec<String>) {
+    let mut nix_packages = Vec::new();
+    let mut unmapped = Vec::new();
+
+    for pkg in &snapshot.packages {
+        // Skip system packages
+        if mapper.is_system_package(&pkg.name) {
+            continue;
+        }
+
+        // Only include manually installed packages to avoid bloat
+        if pkg.manually_installed {
+            if let Some(nix_name) = mapper.map(&pkg.name) {
+                nix_packages.push(nix_name);
+            } else {
+                unmapped.push(pkg.name.clone());
+            }
+        }
+    }
+
+    nix_packages.sort();
+    nix_packages.dedup();
+    (nix_packages, unmapped)
+}
+
+/// Presets whose NixOS service settings match a captured systemd unit
+const SERVICE_PRESETS: &[(&str, &str)] = &[
+    ("docker.service", "docker"),
+    ("containerd.service", "docker"),
+    ("nginx.service", "webserver"),
+    ("apache2.service", "webserver"),
+    ("postgresql.service", "database"),
+    ("mysql.service", "database"),
+    ("mariadb.service", "database"),
+    ("prometheus.service", "monitoring"),
+    ("grafana-server.service", "monitoring"),
+];
+
+/// A capsule profile equivalent to the snapshot, for the NixOS generator:
+/// mapped packages become custom packages and enabled services pick the
+/// presets that configure them
+pub fn nixos_profile(snapshot: &SystemSnapshot) -> Config {
+    let (custom_packages, _) = map_packages(&PackageMapper::new(), snapshot);
+
+    let mut presets = vec!["base".to_string()];
+    for service in snapshot.services.iter().filter(|s| s.enabled || s.running) {
+        for (unit, preset) in SERVICE_PRESETS {
+            if service.name == *unit && !presets.iter().any(|p| p == preset) {
+                presets.push(preset.to_string());
+            }
+        }
+    }
+
+    Config {
+        description: Some(format!("Migrated from {} ({})", snapshot.hostname, snapshot.os_version)),
+        presets,
+        custom_packages,
+        editor: None,
+        extends: None,
+    }
+}
+
+/// Non-root accounts captured in the snapshot
+pub fn nixos_users(snapshot: &SystemSnapshot) -> Vec<ImportedUser> {
+    snapshot
+        .users
+        .iter()
+        .filter(|u| u.username != "root")
+        .map(|u| ImportedUser {
+            name: u.username.clone(),
+            uid: u.uid,
+            home: u.home.clone(),
+            shell: shell_to_nix(&u.shell).to_string(),
+            groups: u.groups.clone(),
+        })
+        .collect()
+}
+
+/// Render the NixOS firewall module for the captured rules
+fn firewall_to_nix(firewall: &FirewallInfo) -> String {
+    let mut config = String::from(
+        "# Firewall Configuration\n# This file defines open ports\n\n{ config, pkgs, ... }:\n\n{\n",
+    );
+
+    if !firewall.is_detected() {
+        config.push_str("  # Firewall: none detected on the source system\n}\n");
+        return config;
+    }
+
+    let ports = |ports: &[u16]| {
+        ports.iter().map(|p| format!("{} ", p)).collect::<String>()
+    };
+    let ranges = |ranges: &[(u16, u16)]| {
+        ranges
+            .iter()
+            .map(|(from, to)| format!("{{ from = {}; to = {}; }} ", from, to))
+            .collect::<String>()
+    };
+
+    config.push_str(&format!("  # Captured from {}\n", firewall.backend));
+    for rule in &firewall.unmapped_rules {
+        config.push_str(&format!("  # TODO: not carried over, recreate by hand: {}\n", rule));
+    }
+    config.push_str("  networking.firewall.enable = true;\n");
+    config.push_str(&format!(
+        "  networking.firewall.allowedTCPPorts = [ {}];\n",
+        ports(&firewall.tcp_ports)
+    ));
+    config.push_str(&format!(
+        "  networking.firewall.allowedUDPPorts = [ {}];\n",
+        ports(&firewall.udp_ports)
+    ));
+    if !firewall.tcp_port_ranges.is_empty() {
+        config.push_str(&format!(
+            "  networking.firewall.allowedTCPPortRanges = [ {}];\n",
+            ranges(&firewall.tcp_port_ranges)
+        ));
+    }
+    if !firewall.udp_port_ranges.is_empty() {
+        config.push_str(&format!(
+            "  networking.firewall.allowedUDPPortRanges = [ {}];\n",
+            ranges(&firewall.udp_port_ranges)
+        ));
+    }
+    config.push_str("}\n");
+    config
+}
+
+/// Profile packages not already present among the detected packages
+fn profile_only_packages<'a>(detected: &[String], profile: &'a [String]) -> Vec<&'a String> {
+    let mut seen: std::collections::HashSet<&str> = detected.iter().map(|s| s.as_str()).collect();
+    profile
+        .iter()
+        .filter(|pkg| seen.insert(pkg.as_str()))
+        .collect()
+}
+
 fn shell_to_nix(shell: &str) -> &str {
     match shell {
         "/bin/bash" | "/usr/bin/bash" => "bash",
@@ -233,4 +395,85 @@ mod tests {
         assert_eq!(shell_to_nix("/usr/bin/fish"), "fish");
         assert_eq!(shell_to_nix("/bin/sh"), "bash");
     }
+
+    #[test]
+    fn test_firewall_to_nix() {
+        let firewall = FirewallInfo {
+            backend: "ufw".to_string(),
+            tcp_ports: vec![22, 443],
+            udp_ports: vec![],
+            tcp_port_ranges: vec![(6000, 6007)],
+            udp_port_ranges: vec![],
+            unmapped_rules: vec!["Custom App ALLOW IN Anywhere".to_string()],
+            raw_rules: None,
+        };
+
+        let nix = firewall_to_nix(&firewall);
+        assert!(nix.contains("# TODO: not carried over, recreate by hand: Custom App ALLOW IN Anywhere"));
+        assert!(nix.contains("allowedTCPPorts = [ 22 443 ];"));
+        assert!(nix.contains("allowedUDPPorts = [ ];"));
+        assert!(nix.contains("{ from = 6000; to = 6007; }"));
+
+        let none = firewall_to_nix(&FirewallInfo::default());
+        assert!(none.contains("none detected"));
+        assert!(!none.contains("networking.firewall"));
+    }
+
+    #[test]
+    fn test_profile_packages_deduplicated() {
+        let detected = vec!["git".to_string(), "nginx".to_string()];
+        let profile = vec![
+            "git".to_string(),
+            "tmux".to_string(),
+            "jq".to_string(),
+            "tmux".to_string(),
+        ];
+
+        let extra = profile_only_packages(&detected, &profile);
+        assert_eq!(extra, vec!["tmux", "jq"]);
+    }
+
+    #[test]
+    fn test_nixos_profile_from_snapshot() {
+        use super::super::collectors::{PackageInfo, ServiceInfo, UserInfo};
+
+        let package = |name: &str, manual| PackageInfo {
+            name: name.to_string(),
+            version: "1.0".to_string(),
+            architecture: "amd64".to_string(),
+            manually_installed: manual,
+        };
+        let service = |name: &str, enabled| ServiceInfo {
+            name: name.to_string(),
+            enabled,
+            running: false,
+            unit_file: None,
+        };
+        let user = |name: &str, uid| UserInfo {
+            username: name.to_string(),
+            uid,
+            gid: uid,
+            home: format!("/home/{}", name),
+            shell: "/usr/bin/zsh".to_string(),
+            groups: vec!["sudo".to_string()],
+        };
+        let snapshot = SystemSnapshot {
+            packages: vec![package("tmux", true), package("jq", true), package("libfoo1", false), package("bash", true)],
+            services: vec![service("nginx.service", true), service("docker.service", true), service("mysql.service", false)],
+            users: vec![user("root", 0), user("deploy", 1000)],
+            hostname: "web-1".to_string(),
+            os_version: "Ubuntu 22.04".to_string(),
+            firewall: FirewallInfo::default(),
+        };
+
+        let profile = nixos_profile(&snapshot);
+        assert_eq!(profile.presets, ["base", "webserver", "docker"]);
+        assert_eq!(profile.custom_packages, ["jq", "tmux"]);
+        assert!(profile.description.unwrap().contains("web-1"));
+
+        let users = nixos_users(&snapshot);
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].name, "deploy");
+        assert_eq!(users[0].shell, "zsh");
+    }
 }
@@ -0,0 +1,116 @@
+// Graceful shutdown on SIGINT/SIGTERM
+//
+// Long-running async loops select on `shutdown_signal()` and persist their
+// state before returning. Synchronous loops that mutate inventory install an
+// `Interrupt`, which turns the first Ctrl-C into a request to stop after the
+// current step; a second Ctrl-C exits immediately.
+
+use anyhow::{Context, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+
+use crate::exit::INTERRUPTED;
+
+/// SIGINT and SIGTERM listeners; must be created inside a Tokio runtime
+struct Signals {
+    #[cfg(unix)]
+    interrupt: tokio::signal::unix::Signal,
+    #[cfg(unix)]
+    terminate: tokio::signal::unix::Signal,
+}
+
+impl Signals {
+    fn new() -> std::io::Result<Self> {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            Ok(Self {
+                interrupt: signal(SignalKind::interrupt())?,
+                terminate: signal(SignalKind::terminate())?,
+            })
+        }
+
+        #[cfg(not(unix))]
+        Ok(Self {})
+    }
+
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        tokio::select! {
+            _ = self.interrupt.recv() => {}
+            _ = self.terminate.recv() => {}
+        }
+
+        #[cfg(not(unix))]
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// Resolve when the process receives SIGINT or SIGTERM
+pub async fn shutdown_signal() {
+    match Signals::new() {
+        Ok(mut signals) => signals.recv().await,
+        Err(_) => {
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
+/// A flag raised by the first SIGINT/SIGTERM, for synchronous loops
+#[derive(Clone)]
+pub struct Interrupt {
+    flag: Arc<AtomicBool>,
+}
+
+impl Interrupt {
+    /// Start listening for signals; returns once the handler is in place
+    pub fn install() -> Result<Self> {
+        let flag = Arc::new(AtomicBool::new(false));
+        let raised = flag.clone();
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        std::thread::Builder::new()
+            .name("capsule-interrupt".to_string())
+            .spawn(move || {
+                let runtime = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(runtime) => runtime,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                        return;
+                    }
+                };
+
+                runtime.block_on(async {
+                    let mut signals = match Signals::new() {
+                        Ok(signals) => signals,
+                        Err(e) => {
+                            let _ = ready_tx.send(Err(e));
+                            return;
+                        }
+                    };
+                    let _ = ready_tx.send(Ok(()));
+
+                    signals.recv().await;
+                    raised.store(true, Ordering::SeqCst);
+                    eprintln!("\nInterrupted: finishing the current step (Ctrl-C again to abort)");
+
+                    signals.recv().await;
+                    std::process::exit(INTERRUPTED);
+                });
+            })
+            .context("Failed to start signal handler")?;
+
+        ready_rx
+            .recv()
+            .context("Signal handler exited during startup")?
+            .context("Failed to install signal handler")?;
+        Ok(Self { flag })
+    }
+
+    pub fn is_raised(&self) -> bool {
+        self.flag.load(Ordering::SeqCst)
+    }
+}
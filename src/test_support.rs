@@ -0,0 +1,30 @@
+// Shared helpers for unit tests
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+/// Serve a single canned HTTP response. Returns the base URL and a handle
+/// resolving to the raw request the server received.
+pub async fn serve_once(status: &'static str, body: &'static str) -> (String, JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let handle = tokio::spawn(async move {
+        let Ok((mut socket, _)) = listener.accept().await else {
+            return String::new();
+        };
+        let mut buf = [0u8; 4096];
+        let n = socket.read(&mut buf).await.unwrap_or(0);
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        let _ = socket.write_all(response.as_bytes()).await;
+        String::from_utf8_lossy(&buf[..n]).into_owned()
+    });
+
+    (format!("http://{}", addr), handle)
+}
@@ -1,9 +1,177 @@
 // Terminal UI utilities for Capsule
 
 use colored::Colorize;
+use prettytable::{format, Cell, Row, Table};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+/// Set from `--quiet`; suppresses banners, headers, dividers and tips
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Turn decorative output off (or back on) for the rest of the process
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Whether decorative output should be skipped
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// How `FitTable`s are laid out, from `--wide`/`--compact`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TableLayout {
+    /// Drop low-priority columns, then fall back to `Compact`, when the
+    /// table is wider than the terminal
+    #[default]
+    Auto,
+    /// Every column, however wide
+    Wide,
+    /// One block of `Column: value` lines per row
+    Compact,
+}
+
+static TABLE_LAYOUT: AtomicU8 = AtomicU8::new(0);
+
+/// Choose the table layout for the rest of the process
+pub fn set_table_layout(layout: TableLayout) {
+    TABLE_LAYOUT.store(layout as u8, Ordering::Relaxed);
+}
+
+pub fn table_layout() -> TableLayout {
+    match TABLE_LAYOUT.load(Ordering::Relaxed) {
+        1 => TableLayout::Wide,
+        2 => TableLayout::Compact,
+        _ => TableLayout::Auto,
+    }
+}
+
+/// Width of the terminal stdout is attached to, else `$COLUMNS`; `None`
+/// when output is piped and no width is set
+pub fn terminal_width() -> Option<usize> {
+    terminal_size::terminal_size()
+        .map(|(terminal_size::Width(width), _)| width as usize)
+        .or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+        .filter(|width| *width > 0)
+}
+
+/// A table that fits itself to the terminal. Each column has a priority:
+/// 0 is always shown, and when the table is too wide the highest-numbered
+/// columns are dropped first (rightmost first among equals). If even the
+/// priority-0 columns don't fit, rows are printed as `Column: value` blocks.
+pub struct FitTable {
+    titles: Vec<String>,
+    priorities: Vec<u8>,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl FitTable {
+    /// Columns as `(title, priority)`
+    pub fn new(columns: &[(&str, u8)]) -> Self {
+        Self {
+            titles: columns.iter().map(|(title, _)| title.to_string()).collect(),
+            priorities: columns.iter().map(|(_, priority)| *priority).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn add_row(&mut self, cells: Vec<Cell>) {
+        self.rows.push(cells);
+    }
+
+    /// Display width of each column, including its title
+    fn column_widths(&self) -> Vec<usize> {
+        self.titles
+            .iter()
+            .enumerate()
+            .map(|(i, title)| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| row.get(i))
+                    .map(|cell| console::measure_text_width(&cell.get_content()))
+                    .fold(console::measure_text_width(title), usize::max)
+            })
+            .collect()
+    }
+
+    pub fn printstd(&self) {
+        let columns = match (table_layout(), terminal_width()) {
+            (TableLayout::Compact, _) => None,
+            (TableLayout::Auto, Some(width)) => fit_columns(&self.column_widths(), &self.priorities, width),
+            _ => Some((0..self.titles.len()).collect()),
+        };
+
+        match columns {
+            Some(columns) => {
+                self.print_columns(&columns);
+                let hidden = self.titles.len() - columns.len();
+                if hidden > 0 && !is_quiet() {
+                    println!(
+                        "  {}",
+                        format!("{} column(s) hidden to fit the terminal; --wide shows all, --compact every field", hidden).dimmed()
+                    );
+                }
+            }
+            None => self.print_compact(),
+        }
+    }
+
+    fn print_columns(&self, columns: &[usize]) {
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+        table.set_titles(Row::new(
+            columns.iter().map(|&i| Cell::new(&self.titles[i]).style_spec("Fb")).collect(),
+        ));
+        for row in &self.rows {
+            table.add_row(Row::new(
+                columns.iter().map(|&i| row.get(i).cloned().unwrap_or_else(|| Cell::new(""))).collect(),
+            ));
+        }
+        table.printstd();
+    }
+
+    fn print_compact(&self) {
+        let label_width = self.titles.iter().map(|t| t.chars().count()).max().unwrap_or(0) + 1;
+        for row in &self.rows {
+            let mut cells = row.iter().zip(&self.titles);
+            if let Some((first, _)) = cells.next() {
+                println!("{}", first.get_content().cyan().bold());
+            }
+            for (cell, title) in cells {
+                let value = cell.get_content();
+                if !title.is_empty() && !value.is_empty() {
+                    let label = format!("{:<width$}", format!("{}:", title), width = label_width);
+                    println!("  {} {}", label.bright_black(), value);
+                }
+            }
+            println!();
+        }
+    }
+}
+
+/// Indices of the columns to keep so a bordered table of `widths` fits in
+/// `available` terminal columns, or `None` if the priority-0 ones don't
+fn fit_columns(widths: &[usize], priorities: &[u8], available: usize) -> Option<Vec<usize>> {
+    // "| a | b |": one space of padding either side and a border per column
+    let table_width = |columns: &[usize]| columns.iter().map(|&i| widths[i] + 3).sum::<usize>() + 1;
+
+    let mut columns: Vec<usize> = (0..widths.len()).collect();
+    while table_width(&columns) > available {
+        let drop = columns
+            .iter()
+            .enumerate()
+            .filter(|(_, &i)| priorities[i] > 0)
+            .max_by_key(|(position, &i)| (priorities[i], *position))
+            .map(|(position, _)| position)?;
+        columns.remove(drop);
+    }
+    Some(columns)
+}
 
 /// Print a header banner
 pub fn header(text: &str) {
+    if is_quiet() {
+        return;
+    }
     println!();
     println!("{}", "═".repeat(70).bright_blue());
     println!("  {}", text.bold().bright_cyan());
@@ -13,6 +181,9 @@ pub fn header(text: &str) {
 
 /// Print a section header
 pub fn section_header(text: &str) {
+    if is_quiet() {
+        return;
+    }
     println!();
     println!("  {}", text.bold().bright_white());
     println!("  {}", "─".repeat(text.len()).bright_black());
@@ -20,9 +191,22 @@ pub fn section_header(text: &str) {
 
 /// Print a divider
 pub fn divider() {
+    if is_quiet() {
+        return;
+    }
     println!("{}", "─".repeat(70).bright_black());
 }
 
+/// Print a hint pointing at a related command
+pub fn tip(text: &str) {
+    if is_quiet() {
+        return;
+    }
+    println!();
+    println!("  {} {}", "💡 Tip:".cyan(), text);
+    println!();
+}
+
 /// Print a success message
 pub fn success(text: &str) {
     println!("  {} {}", "✓".green().bold(), text.green());
@@ -45,6 +229,9 @@ pub fn info_line(label: &str, value: &str) {
 
 /// Print a banner with ASCII art
 pub fn banner(text: &str) {
+    if is_quiet() {
+        return;
+    }
     println!();
     println!("{}", "╔═══════════════════════════════════════════════════════════╗".bright_blue());
     println!("{}  {:<57}  {}", "║".bright_blue(), text.bright_cyan().bold(), "║".bright_blue());
@@ -54,6 +241,9 @@ pub fn banner(text: &str) {
 
 /// Print the Capsule logo
 pub fn print_logo() {
+    if is_quiet() {
+        return;
+    }
     let logo = r#"
     ╔═══════════════════════════════════════════════════════════╗
     ║                                                           ║
@@ -92,3 +282,21 @@ pub fn package_item(name: &str) {
     let name_colored = name.magenta().bold();
     println!("  {} {}", icon, name_colored);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_columns_drops_lowest_priority_first() {
+        let widths = [10, 6, 12, 8];
+        let priorities = [0, 2, 1, 2];
+        // Full width: 36 + 4 * 3 + 1
+        assert_eq!(fit_columns(&widths, &priorities, 49), Some(vec![0, 1, 2, 3]));
+        // Equal priorities go rightmost first
+        assert_eq!(fit_columns(&widths, &priorities, 48), Some(vec![0, 1, 2]));
+        assert_eq!(fit_columns(&widths, &priorities, 30), Some(vec![0, 2]));
+        assert_eq!(fit_columns(&widths, &priorities, 14), Some(vec![0]));
+        assert_eq!(fit_columns(&widths, &priorities, 13), None);
+    }
+}
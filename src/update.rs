@@ -0,0 +1,372 @@
+// Release checks against the GitHub releases API
+
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::time::Duration;
+
+use crate::api::{ApiClient, ApiError, ApiResult};
+
+/// Version compiled into this binary
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// GitHub repository that publishes capsule releases
+pub const RELEASE_REPO: &str = "Geijutsu/capsule";
+
+/// GitHub API base URL
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+
+/// A published release asset
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+    #[serde(default)]
+    pub size: u64,
+}
+
+/// Latest release as returned by `GET /repos/{owner}/{repo}/releases/latest`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseInfo {
+    pub tag_name: String,
+    pub html_url: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+/// File name tokens that identify a platform in asset names
+fn platform_tokens<'a>(os: &'a str, arch: &'a str) -> (Vec<&'a str>, Vec<&'a str>) {
+    let os = match os {
+        "macos" => vec!["macos", "darwin", "apple"],
+        "windows" => vec!["windows", "win"],
+        other => vec![other],
+    };
+    let arch = match arch {
+        "x86_64" => vec!["x86_64", "amd64", "x64"],
+        "aarch64" => vec!["aarch64", "arm64"],
+        other => vec![other],
+    };
+    (os, arch)
+}
+
+fn name_segments(name: &str) -> Vec<&str> {
+    name.split(['-', '_', '.']).filter(|s| !s.is_empty()).collect()
+}
+
+/// Whether `token` appears as whole segments of the asset name, so "win"
+/// matches `capsule-win-x64.exe` but not `capsule-darwin-x64`
+fn has_token(segments: &[&str], token: &str) -> bool {
+    let token = name_segments(token);
+    segments.windows(token.len()).any(|w| w == token.as_slice())
+}
+
+fn is_checksum_file(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.ends_with(".sha256") || name.contains("sha256sums") || name.contains("checksums")
+}
+
+impl ReleaseInfo {
+    /// Release version with any leading `v` removed
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    /// Binary asset built for the platform this binary is running on
+    pub fn platform_asset(&self) -> Option<&ReleaseAsset> {
+        self.asset_for(std::env::consts::OS, std::env::consts::ARCH)
+    }
+
+    /// Binary asset built for `os`/`arch`, spelled as in `std::env::consts`
+    fn asset_for(&self, os: &str, arch: &str) -> Option<&ReleaseAsset> {
+        let (os, arch) = platform_tokens(os, arch);
+        self.assets.iter().find(|asset| {
+            let name = asset.name.to_lowercase();
+            let segments = name_segments(&name);
+            name.starts_with("capsule")
+                && !is_checksum_file(&name)
+                && os.iter().any(|t| has_token(&segments, t))
+                && arch.iter().any(|t| has_token(&segments, t))
+        })
+    }
+
+    /// Checksum asset covering `asset`: either `<asset>.sha256` or a combined
+    /// `SHA256SUMS`/`checksums.txt` file
+    pub fn checksum_asset(&self, asset: &ReleaseAsset) -> Option<&ReleaseAsset> {
+        let per_file = format!("{}.sha256", asset.name);
+        self.assets
+            .iter()
+            .find(|a| a.name == per_file)
+            .or_else(|| {
+                self.assets
+                    .iter()
+                    .find(|a| a.name != asset.name && is_checksum_file(&a.name))
+            })
+    }
+}
+
+/// Find the SHA256 for `file_name` in a checksum file.
+///
+/// Accepts the `sha256sum` format (`<hex>  <name>`, optionally with a `*`
+/// binary marker) as well as a bare hex digest for single-file checksums.
+pub fn parse_checksum(contents: &str, file_name: &str) -> Option<String> {
+    let is_hex = |s: &str| s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit());
+
+    for line in contents.lines() {
+        let mut parts = line.split_whitespace();
+        let Some(hash) = parts.next() else { continue };
+        if !is_hex(hash) {
+            continue;
+        }
+        match parts.next() {
+            Some(name) if name.trim_start_matches('*') == file_name => {
+                return Some(hash.to_lowercase())
+            }
+            None if contents.lines().filter(|l| !l.trim().is_empty()).count() == 1 => {
+                return Some(hash.to_lowercase())
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Plain HTTP client for release downloads (assets are not JSON)
+fn download_client() -> ApiResult<reqwest::Client> {
+    reqwest::Client::builder()
+        .user_agent(format!("capsule/{}", CURRENT_VERSION))
+        .build()
+        .map_err(|e| ApiError::RequestBuild(e.to_string()))
+}
+
+/// Download `url` to `dest`, returning the number of bytes written
+pub async fn download_asset(url: &str, dest: &std::path::Path) -> ApiResult<u64> {
+    use tokio::io::AsyncWriteExt;
+
+    let client = download_client()?;
+    let mut response = client.get(url).send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(ApiError::general(
+            format!("Download of {} failed", url),
+            status.as_u16(),
+        ));
+    }
+
+    let mut file = tokio::fs::File::create(dest)
+        .await
+        .map_err(|e| ApiError::Network(format!("Failed to create {}: {}", dest.display(), e)))?;
+
+    let mut written = 0u64;
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| ApiError::Network(format!("Failed to write {}: {}", dest.display(), e)))?;
+        written += chunk.len() as u64;
+    }
+    file.flush()
+        .await
+        .map_err(|e| ApiError::Network(format!("Failed to write {}: {}", dest.display(), e)))?;
+
+    Ok(written)
+}
+
+/// Download a small text asset such as a checksum file
+pub async fn fetch_text(url: &str) -> ApiResult<String> {
+    let client = download_client()?;
+    let response = client.get(url).send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(ApiError::general(
+            format!("Download of {} failed", url),
+            status.as_u16(),
+        ));
+    }
+    Ok(response.text().await?)
+}
+
+/// Result of comparing the running binary against the latest release
+#[derive(Debug, Clone)]
+pub struct UpdateCheck {
+    pub current: String,
+    pub latest: ReleaseInfo,
+    pub update_available: bool,
+}
+
+/// Parse the numeric `major.minor.patch` part of a version string
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let core = version
+        .trim()
+        .trim_start_matches('v')
+        .split(['-', '+'])
+        .next()?;
+
+    core.split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// Compare two version strings numerically (`v` prefixes and pre-release
+/// suffixes are ignored). Returns `None` if either side is not a version.
+pub fn compare_versions(a: &str, b: &str) -> Option<Ordering> {
+    let mut a = parse_version(a)?;
+    let mut b = parse_version(b)?;
+
+    let len = a.len().max(b.len());
+    a.resize(len, 0);
+    b.resize(len, 0);
+    Some(a.cmp(&b))
+}
+
+/// Build a client for the GitHub API rooted at `base_url`
+pub fn release_client(base_url: &str) -> ApiResult<ApiClient> {
+    ApiClient::builder(base_url)
+        .header("Accept", "application/vnd.github+json")
+        .header("User-Agent", format!("capsule/{}", CURRENT_VERSION))
+        .timeout(Duration::from_secs(10))
+        .max_retries(1)
+        .build()
+}
+
+/// Fetch the latest published release for `repo`
+pub async fn fetch_latest_release(client: &ApiClient, repo: &str) -> ApiResult<ReleaseInfo> {
+    client
+        .get(&format!("/repos/{}/releases/latest", repo), None)
+        .await
+}
+
+/// Check whether a newer release than the running binary is available
+pub async fn check_for_update(base_url: &str) -> ApiResult<UpdateCheck> {
+    let client = release_client(base_url)?;
+    let latest = fetch_latest_release(&client, RELEASE_REPO).await?;
+
+    let update_available = matches!(
+        compare_versions(latest.version(), CURRENT_VERSION),
+        Some(Ordering::Greater)
+    );
+
+    Ok(UpdateCheck {
+        current: CURRENT_VERSION.to_string(),
+        latest,
+        update_available,
+    })
+}
+
+/// Human-readable explanation for a failed release lookup
+pub fn describe_check_error(err: &ApiError) -> String {
+    match err {
+        ApiError::Connection(_) | ApiError::Timeout(_) | ApiError::Network(_) => {
+            "Could not reach GitHub (are you offline?)".to_string()
+        }
+        ApiError::RateLimit { .. } => {
+            "GitHub API rate limit reached, try again later".to_string()
+        }
+        // GitHub reports unauthenticated rate limiting as 403
+        ApiError::Authentication { message, .. } if message.to_lowercase().contains("rate limit") => {
+            "GitHub API rate limit reached, try again later".to_string()
+        }
+        ApiError::ResourceNotFound { .. } => "No published releases found".to_string(),
+        other => format!("Release check failed: {}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::serve_once;
+
+    fn asset(name: &str) -> ReleaseAsset {
+        ReleaseAsset {
+            name: name.to_string(),
+            browser_download_url: format!("https://example.com/{}", name),
+            size: 1,
+        }
+    }
+
+    #[test]
+    fn test_platform_asset_selection() {
+        let os = std::env::consts::OS;
+        let arch = std::env::consts::ARCH;
+        let binary = format!("capsule-{}-{}", os, arch);
+
+        let release = ReleaseInfo {
+            tag_name: "v1.0.0".to_string(),
+            html_url: String::new(),
+            name: None,
+            assets: vec![
+                asset(&format!("{}.sha256", binary)),
+                asset("capsule-plan9-mips"),
+                asset(&binary),
+            ],
+        };
+
+        let selected = release.platform_asset().unwrap();
+        assert_eq!(selected.name, binary);
+        assert_eq!(
+            release.checksum_asset(selected).unwrap().name,
+            format!("{}.sha256", binary)
+        );
+    }
+
+    #[test]
+    fn test_windows_token_does_not_match_darwin() {
+        let release = ReleaseInfo {
+            tag_name: "v1.0.0".to_string(),
+            html_url: String::new(),
+            name: None,
+            assets: vec![
+                asset("capsule-darwin-x86_64"),
+                asset("capsule-win-x86_64.exe"),
+            ],
+        };
+
+        assert_eq!(release.asset_for("windows", "x86_64").unwrap().name, "capsule-win-x86_64.exe");
+        assert_eq!(release.asset_for("macos", "x86_64").unwrap().name, "capsule-darwin-x86_64");
+    }
+
+    #[test]
+    fn test_parse_checksum() {
+        let hash = "a".repeat(64);
+        let sums = format!("{}  capsule-linux-x86_64\n{}  *capsule-macos-aarch64\n", hash, "b".repeat(64));
+        assert_eq!(parse_checksum(&sums, "capsule-linux-x86_64"), Some(hash.clone()));
+        assert_eq!(parse_checksum(&sums, "capsule-macos-aarch64"), Some("b".repeat(64)));
+        assert_eq!(parse_checksum(&sums, "capsule-windows-x86_64.exe"), None);
+
+        // Single-file checksum with no name
+        assert_eq!(parse_checksum(&format!("{}\n", hash), "anything"), Some(hash));
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("v0.2.0", "0.1.0"), Some(Ordering::Greater));
+        assert_eq!(compare_versions("0.1.0", "0.1"), Some(Ordering::Equal));
+        assert_eq!(compare_versions("0.1.0-rc1", "0.1.0"), Some(Ordering::Equal));
+        assert_eq!(compare_versions("0.9.9", "0.10.0"), Some(Ordering::Less));
+        assert_eq!(compare_versions("nightly", "0.1.0"), None);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_latest_release() {
+        let (base, _) = serve_once(
+            "200 OK",
+            r#"{"tag_name":"v9.0.0","html_url":"https://example.com/r/v9.0.0","assets":[]}"#,
+        )
+        .await;
+
+        let check = check_for_update(&base).await.unwrap();
+        assert_eq!(check.latest.version(), "9.0.0");
+        assert_eq!(check.latest.html_url, "https://example.com/r/v9.0.0");
+        assert!(check.update_available);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_check_is_described() {
+        let (base, _) = serve_once(
+            "403 Forbidden",
+            r#"{"message":"API rate limit exceeded for 127.0.0.1."}"#,
+        )
+        .await;
+
+        let err = check_for_update(&base).await.unwrap_err();
+        assert!(describe_check_error(&err).contains("rate limit"));
+    }
+}
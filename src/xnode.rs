@@ -2,6 +2,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::providers::Instance;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct XNode {
     pub id: String,
@@ -22,6 +24,88 @@ fn default_ssh_port() -> u16 {
     22
 }
 
+/// Metadata key: `true` if the node serves HTTP, enabling the HTTP health check
+pub const META_HAS_WEBSERVER: &str = "has_webserver";
+
+/// Metadata key: user for SSH-based checks and metrics (defaults to `root`)
+pub const META_SSH_USER: &str = "ssh_user";
+
+/// Metadata key: private key used for SSH (defaults to `~/.ssh/id_rsa`)
+pub const META_SSH_KEY_PATH: &str = "ssh_key_path";
+
+/// Metadata key: extra TCP ports to probe in health checks, as a list of
+/// numbers (`[5432, 8080]`) or a comma-separated string
+pub const META_TCP_PORTS: &str = "tcp_ports";
+
+/// Metadata key: provider snapshots taken with `xnode snapshot`, oldest first
+pub const META_SNAPSHOTS: &str = "snapshots";
+
+/// Metadata key: expected outbound transfer per month in TB, used to
+/// estimate bandwidth cost (see `cost::DEFAULT_EGRESS_TB_MONTHLY`)
+pub const META_EGRESS_TB_MONTHLY: &str = "egress_tb_monthly";
+
+/// Connection settings read from the conventional metadata keys
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConnectionSettings {
+    pub has_webserver: bool,
+    pub ssh_user: Option<String>,
+    pub ssh_key_path: Option<String>,
+    pub tcp_ports: Vec<u16>,
+}
+
+impl ConnectionSettings {
+    pub fn from_metadata(metadata: &HashMap<String, serde_json::Value>) -> Self {
+        let string = |key: &str| {
+            metadata
+                .get(key)
+                .and_then(|v| v.as_str())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+        };
+
+        Self {
+            has_webserver: metadata
+                .get(META_HAS_WEBSERVER)
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            ssh_user: string(META_SSH_USER),
+            ssh_key_path: string(META_SSH_KEY_PATH),
+            tcp_ports: match metadata.get(META_TCP_PORTS) {
+                Some(serde_json::Value::Array(ports)) => ports
+                    .iter()
+                    .filter_map(|p| p.as_u64().and_then(|p| u16::try_from(p).ok()))
+                    .collect(),
+                Some(serde_json::Value::String(ports)) => ports
+                    .split(',')
+                    .filter_map(|p| p.trim().parse::<u16>().ok())
+                    .collect(),
+                _ => Vec::new(),
+            },
+        }
+    }
+
+    pub fn ssh_user(&self) -> &str {
+        self.ssh_user.as_deref().unwrap_or("root")
+    }
+
+    /// An `ssh` invocation for `user@ip` on `port` using the configured key;
+    /// append the remote command with `.arg`. Exits 255 if the node can't be
+    /// reached.
+    pub fn ssh_command(&self, ip: &str, port: u16) -> std::process::Command {
+        let mut cmd = std::process::Command::new("ssh");
+        cmd.args(["-o", "ConnectTimeout=10", "-p", &port.to_string()]);
+        if let Some(key) = &self.ssh_key_path {
+            let key = match (key.strip_prefix("~/"), dirs::home_dir()) {
+                (Some(rest), Some(home)) => home.join(rest).display().to_string(),
+                _ => key.clone(),
+            };
+            cmd.args(["-i", &key]);
+        }
+        cmd.arg(format!("{}@{}", self.ssh_user(), ip));
+        cmd
+    }
+}
+
 impl XNode {
     pub fn new(
         id: String,
@@ -42,6 +126,25 @@ impl XNode {
         }
     }
 
+    /// Build an xNode from a freshly deployed provider instance, keeping the
+    /// provider-supplied metadata
+    pub fn from_instance(instance: &Instance) -> Self {
+        let mut xnode = Self::new(
+            instance.id.clone(),
+            instance.name.clone(),
+            instance.status.clone(),
+            instance.ip_address.clone(),
+        );
+        xnode.region = Some(instance.region.clone());
+        xnode.metadata = instance.metadata_map();
+        xnode
+    }
+
+    /// Connection settings derived from this node's metadata
+    pub fn connection_settings(&self) -> ConnectionSettings {
+        ConnectionSettings::from_metadata(&self.metadata)
+    }
+
     pub fn is_running(&self) -> bool {
         self.status == "running"
     }
@@ -54,3 +157,70 @@ impl XNode {
         self.status == "deploying"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_instance_keeps_metadata() {
+        let instance = Instance {
+            id: "vultr-1".to_string(),
+            name: "web".to_string(),
+            provider: "vultr".to_string(),
+            template: "vultr-vc2-1c-1gb".to_string(),
+            region: "ewr".to_string(),
+            status: "running".to_string(),
+            ip_address: "203.0.113.10".to_string(),
+            cost_hourly: 0.007,
+            metadata: Some(json!({
+                "has_webserver": true,
+                "ssh_user": "ubuntu",
+                "ssh_key_path": "~/.ssh/deploy",
+            })),
+        };
+
+        let xnode = XNode::from_instance(&instance);
+        assert_eq!(xnode.region.as_deref(), Some("ewr"));
+
+        let settings = xnode.connection_settings();
+        assert!(settings.has_webserver);
+        assert_eq!(settings.ssh_user(), "ubuntu");
+        assert_eq!(settings.ssh_key_path.as_deref(), Some("~/.ssh/deploy"));
+    }
+
+    #[test]
+    fn test_ssh_command_uses_settings() {
+        let settings = ConnectionSettings {
+            ssh_user: Some("ubuntu".to_string()),
+            ssh_key_path: Some("/keys/deploy".to_string()),
+            ..Default::default()
+        };
+        let cmd = settings.ssh_command("203.0.113.10", 2222);
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_string_lossy().into_owned()).collect();
+        assert_eq!(
+            args,
+            ["-o", "ConnectTimeout=10", "-p", "2222", "-i", "/keys/deploy", "ubuntu@203.0.113.10"]
+        );
+    }
+
+    #[test]
+    fn test_connection_settings_defaults() {
+        let settings = ConnectionSettings::from_metadata(&HashMap::new());
+        assert!(!settings.has_webserver);
+        assert_eq!(settings.ssh_user(), "root");
+        assert!(settings.ssh_key_path.is_none());
+        assert!(settings.tcp_ports.is_empty());
+    }
+
+    #[test]
+    fn test_tcp_ports_from_metadata() {
+        let mut metadata = HashMap::new();
+        metadata.insert(META_TCP_PORTS.to_string(), serde_json::json!([5432, 8080, 70000]));
+        assert_eq!(ConnectionSettings::from_metadata(&metadata).tcp_ports, [5432, 8080]);
+
+        metadata.insert(META_TCP_PORTS.to_string(), serde_json::json!("5432, 6379"));
+        assert_eq!(ConnectionSettings::from_metadata(&metadata).tcp_ports, [5432, 6379]);
+    }
+}
@@ -0,0 +1,35 @@
+// Running the capsule binary from integration tests
+#![allow(dead_code)] // each test crate uses only some of these
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+use tempfile::TempDir;
+
+/// The capsule binary with `home` as HOME and colours off
+pub fn capsule_command(home: &TempDir, args: &[&str]) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_capsule"));
+    cmd.args(args).env("HOME", home.path()).env("NO_COLOR", "1");
+    cmd
+}
+
+/// Run capsule to completion with empty stdin
+pub fn capsule(home: &TempDir, args: &[&str]) -> Output {
+    capsule_with_stdin(home, args, None)
+}
+
+/// Run capsule to completion, feeding it `input` on stdin
+pub fn capsule_with_stdin(home: &TempDir, args: &[&str], input: Option<&[u8]>) -> Output {
+    let mut child = capsule_command(home, args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run capsule");
+
+    let mut stdin = child.stdin.take().unwrap();
+    if let Some(input) = input {
+        stdin.write_all(input).unwrap();
+    }
+    drop(stdin);
+    child.wait_with_output().unwrap()
+}
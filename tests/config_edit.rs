@@ -0,0 +1,50 @@
+// `capsule config edit` with a scripted editor standing in for vim
+#![cfg(unix)]
+
+mod common;
+
+use common::capsule;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+fn write_profile(home: &TempDir, name: &str, editor: &str) -> PathBuf {
+    let dir = home.path().join(".capsule/configs");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("{}.yml", name));
+    fs::write(
+        &path,
+        format!("description: Work\npresets:\n- base\ncustom_packages:\n- htop\neditor: {}\n", editor),
+    )
+    .unwrap();
+    path
+}
+
+#[test]
+fn test_edit_saves_valid_profile() {
+    let home = TempDir::new().unwrap();
+    let path = write_profile(&home, "work", "sed -i s/htop/btop/");
+
+    let output = capsule(&home, &["config", "edit", "work"]);
+    assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(fs::read_to_string(path).unwrap().contains("btop"));
+}
+
+#[test]
+fn test_edit_restores_broken_profile() {
+    let home = TempDir::new().unwrap();
+    let path = write_profile(&home, "work", "sed -i s/presets:/presets:[/");
+    let original = fs::read_to_string(&path).unwrap();
+
+    let output = capsule(&home, &["config", "edit", "work"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("left unchanged"));
+    assert_eq!(fs::read_to_string(path).unwrap(), original);
+}
+
+#[test]
+fn test_edit_unknown_profile_exits_not_found() {
+    let home = TempDir::new().unwrap();
+    let output = capsule(&home, &["config", "edit", "nope"]);
+    assert_eq!(output.status.code(), Some(3));
+}
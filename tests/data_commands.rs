@@ -0,0 +1,71 @@
+// `capsule data` commands end to end
+
+mod common;
+
+use common::{capsule, capsule_with_stdin};
+use tempfile::TempDir;
+
+#[test]
+fn test_set_from_stdin_stores_bytes() {
+    let home = TempDir::new().unwrap();
+    let input = b"{\"token\": \"s3cret\"}\n\xff\x00binary";
+    let output = capsule_with_stdin(&home, &["data", "set", "blob", "--stdin"], Some(input));
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&format!("({} bytes)", input.len())));
+
+    let out = home.path().join("blob.out");
+    let output = capsule(&home, &["data", "get-file", "blob", "-o", out.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(std::fs::read(out).unwrap(), input);
+}
+
+#[test]
+fn test_set_from_empty_stdin_is_refused() {
+    let home = TempDir::new().unwrap();
+    let output = capsule_with_stdin(&home, &["data", "set", "blob", "--stdin"], Some(b""));
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = capsule(&home, &["data", "get", "blob"]);
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn test_export_filters_keys_by_glob() {
+    let home = TempDir::new().unwrap();
+    for key in ["deploy/web", "deploy/db/primary", "notes"] {
+        let output = capsule(&home, &["data", "set", key, "value"]);
+        assert_eq!(output.status.code(), Some(0));
+    }
+
+    let out = home.path().join("export");
+    let output = capsule(&home, &["data", "export", out.to_str().unwrap(), "--key", "deploy/*"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Exported 1 keys matching 'deploy/*'"));
+    assert!(out.join("deploy_web").exists());
+    assert!(!out.join("deploy_db_primary").exists());
+    assert!(!out.join("notes").exists());
+
+    let output = capsule(&home, &["data", "export", out.to_str().unwrap(), "--key", "deploy/**"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Exported 2 keys"));
+}
+
+#[test]
+fn test_stats_top_keys_as_json() {
+    let home = TempDir::new().unwrap();
+    capsule(&home, &["data", "set", "tiny", "x"]);
+    capsule(&home, &["data", "set", "medium", &"m".repeat(100)]);
+    capsule(&home, &["data", "set", "large", &"abcdefghij".repeat(80)]);
+
+    let output = capsule(&home, &["data", "stats", "--top", "2", "--json"]);
+    assert_eq!(output.status.code(), Some(0));
+    let stats: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(stats["keys"], 3);
+    let largest = stats["largest_keys"].as_array().unwrap();
+    assert_eq!(largest.len(), 2);
+    assert_eq!(largest[0]["key"], "large");
+    assert_eq!(largest[1]["key"], "medium");
+
+    let output = capsule(&home, &["data", "stats", "--json"]);
+    let stats: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(stats["largest_keys"].is_null());
+}
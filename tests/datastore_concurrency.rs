@@ -0,0 +1,31 @@
+// Several `capsule data set` processes writing to one store at once
+
+mod common;
+
+use common::{capsule, capsule_command};
+use std::process::Stdio;
+use tempfile::TempDir;
+
+#[test]
+fn test_concurrent_processes_keep_every_key() {
+    let home = TempDir::new().unwrap();
+    let writers: Vec<_> = (0..6)
+        .map(|i| {
+            capsule_command(&home, &["data", "set", &format!("writer-{}", i), &format!("value-{}", i)])
+                .stdout(Stdio::null())
+                .stderr(Stdio::piped())
+                .spawn()
+                .expect("failed to run capsule")
+        })
+        .collect();
+
+    for writer in writers {
+        let output = writer.wait_with_output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    for i in 0..6 {
+        let output = capsule(&home, &["data", "get", &format!("writer-{}", i)]);
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), format!("value-{}", i));
+    }
+}
@@ -0,0 +1,54 @@
+// Exit status checks for the capsule binary
+
+mod common;
+
+use common::capsule;
+use tempfile::TempDir;
+
+#[test]
+fn test_missing_key_exits_not_found() {
+    let home = TempDir::new().unwrap();
+    let output = capsule(&home, &["data", "get", "missing-key"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("missing-key"));
+}
+
+#[test]
+fn test_unknown_profile_exits_not_found() {
+    let home = TempDir::new().unwrap();
+    let output = capsule(&home, &["profile", "use", "no-such-profile"]);
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn test_clear_without_confirm_exits_usage() {
+    let home = TempDir::new().unwrap();
+    let output = capsule(&home, &["data", "clear"]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_successful_commands_exit_zero() {
+    let home = TempDir::new().unwrap();
+    let output = capsule(&home, &["data", "set", "greeting", "hello"]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = capsule(&home, &["data", "get", "greeting"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "hello");
+
+    // Informational empty results are not failures
+    let output = capsule(&home, &["data", "keys"]);
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn test_follow_on_stub_provider_exits_usage() {
+    let home = TempDir::new().unwrap();
+    let output = capsule(
+        &home,
+        &["openmesh", "xnode", "deploy", "--provider", "digitalocean", "--template", "do-basic-1", "--follow", "--yes"],
+    );
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--follow is not supported for digitalocean"));
+}
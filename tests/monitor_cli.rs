@@ -0,0 +1,38 @@
+// `capsule monitor check-all` against a temporary fleet
+
+mod common;
+
+use capsule::inventory::XNodeInventory;
+use capsule::xnode::XNode;
+use common::capsule;
+use tempfile::TempDir;
+
+#[test]
+fn test_check_all_with_no_running_xnodes_succeeds() {
+    let home = TempDir::new().unwrap();
+    let output = capsule(&home, &["monitor", "check-all", "--json"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "[]");
+}
+
+#[test]
+fn test_check_all_fails_and_alerts_on_unreachable_xnode() {
+    let home = TempDir::new().unwrap();
+    let mut inventory = XNodeInventory::new(Some(home.path().join(".capsule/inventory.json"))).unwrap();
+    // 192.0.2.0/24 is reserved for documentation and never answers
+    let xnode = XNode::new("node-1".to_string(), "web".to_string(), "running".to_string(), "192.0.2.1".to_string());
+    inventory
+        .add_xnode(&xnode, "vultr".to_string(), "vc2-1c-1gb".to_string(), 0.01, vec![])
+        .unwrap();
+
+    let output = capsule(&home, &["monitor", "check-all", "--json"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("1 of 1 xNode(s) unhealthy: node-1"));
+
+    let results: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(results[0]["health"]["status"], "unhealthy");
+    assert!(results[0]["active_alerts"].as_u64().unwrap() > 0);
+
+    let alerts = std::fs::read_to_string(home.path().join(".capsule/monitoring_data/active_alerts.json")).unwrap();
+    assert!(alerts.contains("node-1"));
+}
@@ -0,0 +1,70 @@
+// `capsule openmesh` provider, template and inventory commands end to end
+
+mod common;
+
+use capsule::inventory::XNodeInventory;
+use capsule::xnode::XNode;
+use common::capsule;
+use tempfile::TempDir;
+
+#[test]
+fn test_describe_template() {
+    let home = TempDir::new().unwrap();
+    let output = capsule(&home, &["openmesh", "xnode", "describe-template", "no-such-template"]);
+    assert_eq!(output.status.code(), Some(3));
+
+    let output = capsule(&home, &["openmesh", "xnode", "describe-template", "cherry-e3-1240v5", "--json"]);
+    assert_eq!(output.status.code(), Some(0));
+    let details: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(details["provider"], "cherry");
+    assert_eq!(details["region_details"][0]["name"], "Stockholm");
+    assert!(details["efficiency"]["per_cpu_monthly"].as_f64().unwrap() > 0.0);
+}
+
+#[test]
+fn test_provider_images() {
+    let home = TempDir::new().unwrap();
+    let output = capsule(&home, &["openmesh", "provider", "images", "nope"]);
+    assert_eq!(output.status.code(), Some(3));
+
+    let output = capsule(&home, &["openmesh", "provider", "images", "linode", "--json"]);
+    assert_eq!(output.status.code(), Some(0));
+    let images: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(images[0]["id"], "linode/ubuntu24.04");
+    assert_eq!(images[0]["arch"], "x86_64");
+}
+
+#[test]
+fn test_provider_show_config_masks_keys() {
+    let home = TempDir::new().unwrap();
+    let output = capsule(&home, &["openmesh", "provider", "configure", "vultr", "--api-key", "vultr-secret-123456"]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = capsule(&home, &["openmesh", "provider", "show-config", "--json"]);
+    assert_eq!(output.status.code(), Some(0));
+    let config: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(config["vultr"]["api_key"], "vul...3456");
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("vultr-secret"));
+}
+
+#[test]
+fn test_prune_keeps_xnodes_of_providers_that_cannot_list() {
+    let home = TempDir::new().unwrap();
+    // Vultr's instance listing is still a stub that comes back empty
+    std::fs::create_dir_all(home.path().join(".capsule")).unwrap();
+    std::fs::write(home.path().join(".capsule/providers.yml"), "vultr:\n  api_key: test-key-123\n").unwrap();
+    let mut inventory = XNodeInventory::new(Some(home.path().join(".capsule/inventory.json"))).unwrap();
+    let xnode = XNode::new("vultr-web".to_string(), "web".to_string(), "running".to_string(), "192.0.2.1".to_string());
+    inventory
+        .add_xnode(&xnode, "vultr".to_string(), "vc2-1c-1gb".to_string(), 0.01, vec![])
+        .unwrap();
+
+    let output = capsule(&home, &["openmesh", "xnode", "prune", "--yes"]);
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("vultr: listing instances is not supported yet"));
+    assert!(stdout.contains("Nothing to prune"));
+
+    let inventory = XNodeInventory::new(Some(home.path().join(".capsule/inventory.json"))).unwrap();
+    assert!(inventory.get_xnode("vultr-web").is_some());
+}
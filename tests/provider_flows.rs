@@ -0,0 +1,278 @@
+// End-to-end xNode flows against the in-memory FakeProvider
+//
+// Requires the `testing` feature, which the crate's own dev-dependency
+// enables for `cargo test`.
+#![cfg(feature = "testing")]
+
+use std::collections::HashMap;
+
+use capsule::inventory::{HistoryWindow, XNodeInventory};
+use capsule::openmesh::{adopt_instance, deploy_and_record, destroy_xnode, find_ghosts, plan_move, sync_inventory, MoveOptions};
+use capsule::providers::fake::FakeProvider;
+use capsule::providers::{DeployConfig, Provider, ProviderCapabilities, ProviderManager, ProviderOperation};
+use tempfile::TempDir;
+
+fn manager(dir: &TempDir, fake: &FakeProvider) -> ProviderManager {
+    ProviderManager::with_providers(
+        Some(dir.path().join("providers.yml")),
+        vec![Box::new(fake.clone())],
+    )
+    .unwrap()
+}
+
+fn config(name: &str) -> DeployConfig {
+    DeployConfig {
+        name: name.to_string(),
+        region: "test-1".to_string(),
+        os: None,
+        ssh_keys: Some(vec!["ssh-ed25519 AAAA test".to_string()]),
+        extra: HashMap::new(),
+    }
+}
+
+#[test]
+fn test_deploy_records_provider_default_tags() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("providers.yml"), "fake:\n  default_tags: [env:test, fleet]\n").unwrap();
+    let fake = FakeProvider::default();
+    let manager = manager(&dir, &fake);
+
+    let mut inventory = XNodeInventory::new(Some(dir.path().join("inventory.json"))).unwrap();
+    let tags = ["fleet".to_string(), "role:web".to_string()];
+    let web = deploy_and_record(&manager, &mut inventory, "fake", "small", &config("web"), &tags).unwrap();
+    assert_eq!(inventory.get_xnode(&web.id).unwrap().tags, ["env:test", "fleet", "role:web"]);
+}
+
+#[test]
+fn test_deploy_list_destroy() {
+    let dir = TempDir::new().unwrap();
+    let inventory_file = dir.path().join("inventory.json");
+    let fake = FakeProvider::default();
+    let manager = manager(&dir, &fake);
+
+    let mut inventory = XNodeInventory::new(Some(inventory_file.clone())).unwrap();
+    let web = deploy_and_record(&manager, &mut inventory, "fake", "small", &config("web"), &[]).unwrap();
+    let db = deploy_and_record(&manager, &mut inventory, "fake", "large", &config("db"), &["team:data".to_string()]).unwrap();
+    assert_eq!(web.id, "fake-1");
+    assert_eq!(db.ip_address, "10.0.0.2");
+
+    // The inventory is persisted and lists both nodes
+    let mut inventory = XNodeInventory::new(Some(inventory_file.clone())).unwrap();
+    let mut ids: Vec<_> = inventory.list_by_provider("fake").iter().map(|e| e.id.clone()).collect();
+    ids.sort();
+    assert_eq!(ids, ["fake-1", "fake-2"]);
+    assert_eq!(inventory.get_xnode("fake-2").unwrap().template, "large");
+    assert_eq!(inventory.get_xnode("fake-2").unwrap().tags, ["team:data"]);
+
+    destroy_xnode(&manager, &mut inventory, "fake-1", Some("cost cleanup")).unwrap();
+    assert_eq!(fake.instances().len(), 1);
+
+    let inventory = XNodeInventory::new(Some(inventory_file)).unwrap();
+    assert!(inventory.get_xnode("fake-1").is_none());
+    let history = inventory.get_deployment_history(Some("fake-1"), None, &HistoryWindow::default(), None);
+    assert_eq!(history.len(), 1);
+    assert!(history[0].terminated_at.is_some());
+    assert_eq!(history[0].termination_reason.as_deref(), Some("cost cleanup"));
+}
+
+#[test]
+fn test_destroy_unknown_xnode_is_not_found() {
+    let dir = TempDir::new().unwrap();
+    let fake = FakeProvider::default();
+    let manager = manager(&dir, &fake);
+    let mut inventory = XNodeInventory::new(Some(dir.path().join("inventory.json"))).unwrap();
+
+    let err = destroy_xnode(&manager, &mut inventory, "fake-9", None).unwrap_err();
+    assert_eq!(capsule::exit::exit_code_for(&err), capsule::exit::NOT_FOUND);
+}
+
+#[test]
+fn test_invalid_deploy_is_rejected_before_provider() {
+    let dir = TempDir::new().unwrap();
+    let fake = FakeProvider::default();
+    let manager = manager(&dir, &fake);
+    let mut inventory = XNodeInventory::new(Some(dir.path().join("inventory.json"))).unwrap();
+
+    let mut bad = config("web");
+    bad.region = "nowhere".to_string();
+    let err = deploy_and_record(&manager, &mut inventory, "fake", "small", &bad, &[]).unwrap_err();
+    assert_eq!(capsule::exit::exit_code_for(&err), capsule::exit::USAGE);
+    assert!(fake.instances().is_empty());
+    assert!(inventory.list_all().is_empty());
+}
+
+#[test]
+fn test_capabilities_gate_operations() {
+    let dir = TempDir::new().unwrap();
+    let fake = FakeProvider::new("metal").with_capabilities(ProviderCapabilities::bare_metal());
+    let manager = manager(&dir, &fake);
+
+    assert!(manager.provider_supporting("metal", ProviderOperation::Start).is_err());
+    assert!(manager.provider_supporting("fake", ProviderOperation::Start).is_err());
+}
+
+#[test]
+fn test_adopt_external_instance() {
+    let dir = TempDir::new().unwrap();
+    let fake = FakeProvider::default();
+    let manager = manager(&dir, &fake);
+    let mut inventory = XNodeInventory::new(Some(dir.path().join("inventory.json"))).unwrap();
+
+    // Created at the provider without going through capsule
+    let external = fake.deploy("large", &config("console")).unwrap();
+
+    let entry = adopt_instance(&manager, &mut inventory, "fake", &external.id, None).unwrap();
+    assert_eq!(entry.name, "console");
+    assert_eq!(entry.template, "large");
+    assert_eq!(entry.cost_hourly, 0.04);
+    assert_eq!(inventory.get_deployment_history(Some(&external.id), None, &HistoryWindow::default(), None).len(), 1);
+
+    let err = adopt_instance(&manager, &mut inventory, "fake", &external.id, None).unwrap_err();
+    assert_eq!(capsule::exit::exit_code_for(&err), capsule::exit::USAGE);
+
+    assert!(adopt_instance(&manager, &mut inventory, "fake", "fake-404", None).is_err());
+    let err = adopt_instance(&manager, &mut inventory, "nope", "x", None).unwrap_err();
+    assert_eq!(capsule::exit::exit_code_for(&err), capsule::exit::NOT_FOUND);
+}
+
+#[test]
+fn test_sync_reconciles_inventory_and_collects_errors() {
+    let dir = TempDir::new().unwrap();
+    let fake = FakeProvider::default();
+    let manager = manager(&dir, &fake);
+    let mut inventory = XNodeInventory::new(Some(dir.path().join("inventory.json"))).unwrap();
+
+    deploy_and_record(&manager, &mut inventory, "fake", "small", &config("web"), &[]).unwrap();
+    deploy_and_record(&manager, &mut inventory, "fake", "small", &config("db"), &[]).unwrap();
+    fake.stop_instance("fake-2").unwrap();
+    fake.delete_instance("fake-1").unwrap();
+    fake.deploy("small", &config("stray")).unwrap();
+
+    let providers = ["fake".to_string(), "nope".to_string()];
+    let report = sync_inventory(&manager, &mut inventory, &providers, 2).unwrap();
+
+    assert_eq!(report.synced, ["fake"]);
+    assert_eq!(report.updated.len(), 1);
+    assert_eq!(report.updated[0].0, "fake-2");
+    assert_eq!(inventory.get_xnode("fake-2").unwrap().status, "stopped");
+    assert!(inventory.get_xnode("fake-2").unwrap().last_seen.is_some());
+    assert_eq!(report.missing, ["fake-1"]);
+    assert!(inventory.get_xnode("fake-1").unwrap().last_seen.is_none());
+    let untracked: Vec<_> = report.untracked.iter().map(|i| i.id.as_str()).collect();
+    assert_eq!(untracked, ["fake-3"]);
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].0, "nope");
+    assert_eq!(capsule::exit::exit_code_for(&report.errors[0].1), capsule::exit::NOT_FOUND);
+
+    let err = sync_inventory(&manager, &mut inventory, &providers, 0).unwrap_err();
+    assert_eq!(capsule::exit::exit_code_for(&err), capsule::exit::USAGE);
+}
+
+#[test]
+fn test_find_ghosts_skips_providers_that_fail() {
+    let dir = TempDir::new().unwrap();
+    let fake = FakeProvider::default();
+    let manager = manager(&dir, &fake);
+    let mut inventory = XNodeInventory::new(Some(dir.path().join("inventory.json"))).unwrap();
+
+    deploy_and_record(&manager, &mut inventory, "fake", "small", &config("web"), &[]).unwrap();
+    deploy_and_record(&manager, &mut inventory, "fake", "small", &config("db"), &[]).unwrap();
+    fake.delete_instance("fake-1").unwrap();
+
+    let report = find_ghosts(&manager, &inventory, &["fake".to_string()], 1).unwrap();
+    let ids: Vec<_> = report.ghosts.iter().map(|e| e.id.as_str()).collect();
+    assert_eq!(ids, ["fake-1"]);
+    assert!(report.errors.is_empty());
+
+    let report = find_ghosts(&manager, &inventory, &["nope".to_string()], 1).unwrap();
+    assert!(report.ghosts.is_empty());
+    assert_eq!(report.errors.len(), 1);
+}
+
+#[test]
+fn test_stub_listing_provider_is_not_checked() {
+    let dir = TempDir::new().unwrap();
+    let fake = FakeProvider::default();
+    let manager = manager(&dir, &fake);
+    let mut inventory = XNodeInventory::new(Some(dir.path().join("inventory.json"))).unwrap();
+    deploy_and_record(&manager, &mut inventory, "fake", "small", &config("web"), &[]).unwrap();
+
+    // Configured, but listing is a stub that always comes back empty
+    let stub = fake.clone().with_capabilities(ProviderCapabilities::vm());
+    fake.delete_instance("fake-1").unwrap();
+    let manager = ProviderManager::with_providers(Some(dir.path().join("providers.yml")), vec![Box::new(stub)]).unwrap();
+    let providers = ["fake".to_string()];
+
+    let report = find_ghosts(&manager, &inventory, &providers, 1).unwrap();
+    assert!(report.ghosts.is_empty());
+    assert!(report.errors.is_empty());
+    assert_eq!(report.unchecked, ["fake"]);
+
+    let report = sync_inventory(&manager, &mut inventory, &providers, 1).unwrap();
+    assert!(report.missing.is_empty());
+    assert!(report.synced.is_empty());
+    assert!(report.errors.is_empty());
+    assert_eq!(report.unlisted, ["fake"]);
+    assert!(inventory.get_xnode("fake-1").is_some());
+}
+
+#[test]
+fn test_unconfigured_provider_reports_no_ghosts() {
+    let dir = TempDir::new().unwrap();
+    let fake = FakeProvider::default();
+    let manager = manager(&dir, &fake);
+    let mut inventory = XNodeInventory::new(Some(dir.path().join("inventory.json"))).unwrap();
+    deploy_and_record(&manager, &mut inventory, "fake", "small", &config("web"), &[]).unwrap();
+
+    // The same provider once its API key is gone lists nothing at all
+    let manager = ProviderManager::with_providers(
+        Some(dir.path().join("providers.yml")),
+        vec![Box::new(fake.clone().without_credentials())],
+    )
+    .unwrap();
+    let providers = ["fake".to_string()];
+
+    let report = find_ghosts(&manager, &inventory, &providers, 1).unwrap();
+    assert!(report.ghosts.is_empty());
+    assert_eq!(report.errors.len(), 1);
+    assert!(report.errors[0].1.to_string().contains("No API key configured for fake"));
+
+    let report = sync_inventory(&manager, &mut inventory, &providers, 1).unwrap();
+    assert!(report.missing.is_empty());
+    assert!(report.synced.is_empty());
+    assert_eq!(report.errors.len(), 1);
+    assert!(inventory.get_xnode("fake-1").is_some());
+}
+
+#[test]
+fn test_plan_move_prices_from_the_catalog() {
+    let dir = TempDir::new().unwrap();
+    let fake = FakeProvider::default();
+    let manager = manager(&dir, &fake);
+    let mut inventory = XNodeInventory::new(Some(dir.path().join("inventory.json"))).unwrap();
+    deploy_and_record(&manager, &mut inventory, "fake", "small", &config("web"), &[]).unwrap();
+    let entry = inventory.get_xnode("fake-1").unwrap().clone();
+
+    let options = |template: Option<&str>| MoveOptions {
+        xnode_id: entry.id.clone(),
+        template: template.map(String::from),
+        ..Default::default()
+    };
+
+    let target = plan_move(&manager, &entry, &options(Some("large"))).unwrap();
+    assert_eq!(target.template, "large");
+    assert_eq!(target.cost_hourly, 0.04);
+    assert_eq!(target.region, entry.region);
+
+    let err = plan_move(&manager, &entry, &options(None)).unwrap_err();
+    assert_eq!(capsule::exit::exit_code_for(&err), capsule::exit::USAGE);
+
+    let err = plan_move(&manager, &entry, &options(Some("huge"))).unwrap_err();
+    assert_eq!(capsule::exit::exit_code_for(&err), capsule::exit::NOT_FOUND);
+
+    let elsewhere = MoveOptions { provider: Some("nope".to_string()), ..options(Some("large")) };
+    assert!(plan_move(&manager, &entry, &elsewhere).is_err());
+
+    inventory.move_xnode(&entry.id, target).unwrap();
+    assert_eq!(inventory.get_xnode("fake-1").unwrap().cost_hourly, 0.04);
+}
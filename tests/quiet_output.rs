@@ -0,0 +1,36 @@
+// `--quiet` drops banners and tips but keeps results
+
+mod common;
+
+use common::capsule;
+use tempfile::TempDir;
+
+fn capsule_stdout(home: &TempDir, args: &[&str]) -> String {
+    let output = capsule(home, args);
+    assert!(output.status.success(), "capsule {:?} failed", args);
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn test_quiet_suppresses_banners_and_tips() {
+    let home = TempDir::new().unwrap();
+
+    let loud = capsule_stdout(&home, &["stacks"]);
+    assert!(loud.contains('═'));
+    assert!(loud.contains("Tip:"));
+
+    let quiet = capsule_stdout(&home, &["stacks", "--quiet"]);
+    assert!(!quiet.contains('═'));
+    assert!(!quiet.contains('─'));
+    assert!(!quiet.contains("Tip:"));
+    assert!(quiet.contains("ollama"));
+
+    let overview = capsule_stdout(&home, &["-q", "openmesh"]);
+    assert!(!overview.contains('╔'));
+    assert!(!overview.contains("PRO TIPS"));
+    assert!(overview.contains("QUICK START"));
+
+    let init = capsule_stdout(&home, &["init", "--non-interactive", "--quiet"]);
+    assert!(init.contains("Created profile 'default'"));
+    assert!(!init.contains("Tip:"));
+}
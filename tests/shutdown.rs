@@ -0,0 +1,37 @@
+// Interrupting long-running commands persists their state
+#![cfg(unix)]
+
+mod common;
+
+use common::capsule_command;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+#[test]
+fn test_sigint_stops_watch_and_saves_history() {
+    let home = TempDir::new().unwrap();
+    let mut child = capsule_command(&home, &["monitor", "watch"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("failed to run capsule");
+
+    // Wait for the first dashboard render before interrupting
+    let history = home.path().join(".capsule/monitoring_data");
+    let started = Instant::now();
+    while !history.exists() && started.elapsed() < Duration::from_secs(10) {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    std::thread::sleep(Duration::from_millis(500));
+
+    let status = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.code(), Some(0));
+    assert!(history.join("metrics_history.json").exists());
+}
@@ -0,0 +1,40 @@
+// Wide tables drop columns, then go vertical, on narrow terminals
+
+mod common;
+
+use common::capsule_command;
+use tempfile::TempDir;
+
+fn providers_table(home: &TempDir, columns: &str, flags: &[&str]) -> String {
+    let output = capsule_command(home, &["openmesh", "providers"])
+        .args(flags)
+        .env("COLUMNS", columns)
+        .output()
+        .expect("failed to run capsule");
+    assert!(output.status.success(), "capsule openmesh providers {:?} failed", flags);
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn test_tables_fit_the_terminal() {
+    let home = TempDir::new().unwrap();
+
+    let wide = providers_table(&home, "200", &[]);
+    assert!(wide.contains("| Type "));
+    assert!(!wide.contains("hidden to fit"));
+
+    // Lower-priority columns go first; provider and price stay
+    let narrow = providers_table(&home, "60", &[]);
+    assert!(!narrow.contains("| Type "));
+    assert!(narrow.contains("| Price/hr "));
+    assert!(narrow.contains("column(s) hidden to fit the terminal"));
+    assert!(narrow.lines().filter(|l| l.starts_with('+')).all(|l| l.chars().count() <= 60));
+
+    // Too narrow for any table: one block per row
+    let tiny = providers_table(&home, "30", &[]);
+    assert!(!tiny.contains("| Provider"));
+    assert!(tiny.contains("  Price/hr:  $"));
+
+    assert!(providers_table(&home, "60", &["--wide"]).contains("| Type "));
+    assert!(providers_table(&home, "200", &["--compact"]).contains("  Type:      Cloud"));
+}